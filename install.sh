@@ -25,7 +25,7 @@ else
 fi
 
 echo "=== Создание структуры каталогов ==="
-sudo mkdir -p /opt/arb-bot/{src,tests,keys,logs}
+sudo mkdir -p /opt/arb-bot/{src,tests,keys,logs,data}
 sudo mkdir -p /var/log/arb-bot
 sudo chown -R $USER:$USER /opt/arb-bot
 sudo chown -R $USER:$USER /var/log/arb-bot
@@ -0,0 +1,180 @@
+use anyhow::Context;
+use solana_client::client_error::Result as ClientResult;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::dex::get_accounts_chunked;
+use crate::rpc::{AccountRead, MAX_MULTIPLE_ACCOUNTS};
+
+/// Согласованное чтение аккаунтов за прогон поиска
+///
+/// Цены пары на разных DEX читаются друг за другом, и без снимка каждая нога
+/// видит своё состояние сети: часть спреда — разница во времени чтения. В начале
+/// прогона аккаунты, которые адаптеры читали в предыдущем прогоне, читаются
+/// пачкой getMultipleAccounts не раньше слота первого запроса, и до конца
+/// прогона RPC клиенты отвечают на их чтение из снимка (`SnapshotSender`).
+/// Аккаунты не из снимка читаются через RPC и попадают в снимок следующего прогона.
+#[derive(Debug, Default)]
+pub struct AccountSnapshot {
+    state: Mutex<SnapshotState>,
+}
+
+#[derive(Debug, Default)]
+struct SnapshotState {
+    /// Открытые прогоны (снимок действует, пока открыт хотя бы один)
+    readers: usize,
+    /// Аккаунты снимка (None — не существует) и слот чтения
+    accounts: HashMap<Pubkey, (Option<Account>, u64)>,
+    /// Аккаунты, прочитанные за текущий прогон
+    used: HashSet<Pubkey>,
+    /// Аккаунты снимка следующего прогона
+    known: Vec<Pubkey>,
+}
+
+impl AccountSnapshot {
+    /// Снимок для RPC endpoint (общий для всех клиентов процесса с этим URL)
+    pub fn for_url(rpc_url: &str) -> Arc<Self> {
+        static SNAPSHOTS: OnceLock<Mutex<HashMap<String, Arc<AccountSnapshot>>>> = OnceLock::new();
+        SNAPSHOTS.get_or_init(Default::default)
+            .lock()
+            .expect("мьютекс снимков аккаунтов отравлен")
+            .entry(rpc_url.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Открытие снимка на прогон поиска
+    ///
+    /// Первый открывший прогон читает аккаунты снимка через `rpc_client`; если
+    /// снимок уже открыт другим прогоном, используется он. Ошибка чтения не
+    /// прерывает прогон: аккаунты читаются через RPC по отдельности.
+    pub async fn open(self: &Arc<Self>, rpc_client: Arc<RpcClient>) -> SnapshotGuard {
+        let addresses = {
+            let mut state = self.lock();
+            state.readers += 1;
+            if state.readers > 1 {
+                None
+            } else {
+                state.accounts.clear();
+                state.used.clear();
+                Some(state.known.clone())
+            }
+        };
+        let guard = SnapshotGuard { snapshot: self.clone() };
+        let Some(addresses) = addresses.filter(|addresses| !addresses.is_empty()) else {
+            return guard;
+        };
+
+        let fetch = addresses.clone();
+        let fetched = tokio::task::spawn_blocking(move || get_accounts_chunked(&rpc_client, &fetch))
+            .await
+            .context("Задача чтения снимка аккаунтов завершилась с ошибкой")
+            .and_then(|result| result);
+        match fetched {
+            Ok(chunks) => {
+                let mut state = self.lock();
+                for (addresses, (accounts, slot)) in addresses.chunks(MAX_MULTIPLE_ACCOUNTS).zip(chunks) {
+                    for (address, account) in addresses.iter().zip(accounts) {
+                        state.accounts.insert(*address, (account, slot));
+                    }
+                }
+            }
+            Err(e) => log::warn!("Снимок аккаунтов не прочитан, аккаунты читаются по отдельности: {:#}", e),
+        }
+        guard
+    }
+
+    /// Слоты чтения снимка: наименьший и наибольший (None — снимок пуст)
+    pub fn slots(&self) -> Option<(u64, u64)> {
+        let state = self.lock();
+        let slots = state.accounts.values().map(|(_, slot)| *slot);
+        Some((slots.clone().min()?, slots.max()?))
+    }
+
+    /// Аккаунты запроса из снимка (None — снимок закрыт или в нём нет хотя бы одного)
+    ///
+    /// Пока снимок открыт, адреса запоминаются для снимка следующего прогона.
+    fn get(&self, addresses: &[Pubkey]) -> Option<(Vec<Option<Account>>, u64)> {
+        let mut state = self.lock();
+        if state.readers == 0 {
+            return None;
+        }
+        state.used.extend(addresses.iter().copied());
+        let mut slot = 0;
+        let accounts = addresses.iter()
+            .map(|address| {
+                let (account, read_at) = state.accounts.get(address)?;
+                slot = slot.max(*read_at);
+                Some(account.clone())
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some((accounts, slot))
+    }
+
+    fn close(&self) {
+        let mut state = self.lock();
+        state.readers -= 1;
+        if state.readers == 0 {
+            state.known = state.used.drain().collect();
+            state.accounts.clear();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SnapshotState> {
+        self.state.lock().expect("мьютекс снимка аккаунтов отравлен")
+    }
+}
+
+/// Открытый снимок прогона (закрывается при удалении)
+pub struct SnapshotGuard {
+    snapshot: Arc<AccountSnapshot>,
+}
+
+impl SnapshotGuard {
+    /// Слоты чтения снимка: наименьший и наибольший (None — снимок пуст)
+    pub fn slots(&self) -> Option<(u64, u64)> {
+        self.snapshot.slots()
+    }
+}
+
+impl Drop for SnapshotGuard {
+    fn drop(&mut self) {
+        self.snapshot.close();
+    }
+}
+
+/// Транспорт RPC, отвечающий на чтение аккаунтов из открытого снимка прогона
+pub struct SnapshotSender<S> {
+    inner: S,
+    snapshot: Arc<AccountSnapshot>,
+}
+
+impl<S> SnapshotSender<S> {
+    pub fn new(inner: S, snapshot: Arc<AccountSnapshot>) -> Self {
+        Self { inner, snapshot }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for SnapshotSender<S> {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        let Some(read) = AccountRead::parse(request, &params).filter(|read| read.binary) else {
+            return self.inner.send(request, params).await;
+        };
+        let Some((accounts, slot)) = self.snapshot.get(&read.addresses) else {
+            return self.inner.send(request, params).await;
+        };
+        Ok(read.response(&accounts, slot))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
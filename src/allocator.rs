@@ -0,0 +1,156 @@
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::config::AllocationConfig;
+use crate::ranking::expected_profit;
+
+/// Максимальное количество раундов перераспределения остатка капитала
+const MAX_ROUNDS: usize = 16;
+
+/// Занятый капитал по парам и DEX
+#[derive(Debug, Default)]
+struct Exposure {
+    pairs: HashMap<String, Decimal>,
+    dexes: HashMap<String, Decimal>,
+}
+
+impl Exposure {
+    fn pair(&self, opportunity: &ArbitrageOpportunity) -> Decimal {
+        self.pairs.get(&pair_key(opportunity)).copied().unwrap_or(Decimal::ZERO)
+    }
+
+    fn dex(&self, dex: &str) -> Decimal {
+        self.dexes.get(dex).copied().unwrap_or(Decimal::ZERO)
+    }
+
+    fn add(&mut self, opportunity: &ArbitrageOpportunity, amount: Decimal) {
+        *self.pairs.entry(pair_key(opportunity)).or_default() += amount;
+        *self.dexes.entry(opportunity.from_dex.clone()).or_default() += amount;
+        if opportunity.to_dex != opportunity.from_dex {
+            *self.dexes.entry(opportunity.to_dex.clone()).or_default() += amount;
+        }
+    }
+}
+
+fn pair_key(opportunity: &ArbitrageOpportunity) -> String {
+    format!("{}/{}", opportunity.base_token, opportunity.quote_token)
+}
+
+/// Распределение капитала цикла между одновременными возможностями
+///
+/// Капитал делится пропорционально ожидаемой прибыли каждой возможности
+/// (при её расчётном объёме). Доля ограничена расчётным объёмом, лимитом
+/// на пару и лимитом на каждый из двух DEX; то, что не поместилось, в
+/// следующем раунде делится между остальными. Возможности с долей меньше
+/// `min_amount` отбрасываются. Порядок входного списка (ранжирование) сохраняется.
+pub fn allocate_capital<F>(
+    opportunities: Vec<ArbitrageOpportunity>,
+    config: &AllocationConfig,
+    min_amount: F,
+) -> Vec<ArbitrageOpportunity>
+where
+    F: Fn(&ArbitrageOpportunity) -> Decimal,
+{
+    let weights: Vec<Decimal> = opportunities.iter()
+        .map(|o| expected_profit(o).max(Decimal::ZERO))
+        .collect();
+    let pair_cap = config.pair_cap();
+    let dex_cap = config.dex_cap();
+
+    let mut allocated = vec![Decimal::ZERO; opportunities.len()];
+    let mut active: Vec<bool> = weights.iter().map(|w| *w > Decimal::ZERO).collect();
+    let mut exposure = Exposure::default();
+    let mut remaining = config.capital();
+
+    for _ in 0..MAX_ROUNDS {
+        let total_weight: Decimal = weights.iter()
+            .zip(&active)
+            .filter(|(_, &is_active)| is_active)
+            .map(|(w, _)| *w)
+            .sum();
+        if remaining <= Decimal::ZERO || total_weight <= Decimal::ZERO {
+            break;
+        }
+
+        let budget = remaining;
+        let mut granted_in_round = Decimal::ZERO;
+        for (i, opportunity) in opportunities.iter().enumerate() {
+            if !active[i] {
+                continue;
+            }
+            let headroom = (opportunity.trade_amount - allocated[i])
+                .min(pair_cap - exposure.pair(opportunity))
+                .min(dex_cap - exposure.dex(&opportunity.from_dex))
+                .min(dex_cap - exposure.dex(&opportunity.to_dex))
+                .min(remaining);
+            let share = budget * weights[i] / total_weight;
+            let grant = share.min(headroom).max(Decimal::ZERO);
+
+            allocated[i] += grant;
+            exposure.add(opportunity, grant);
+            remaining -= grant;
+            granted_in_round += grant;
+            // Упёрлась в лимит — в следующих раундах не участвует
+            if grant < share {
+                active[i] = false;
+            }
+        }
+
+        if granted_in_round <= Decimal::ZERO {
+            break;
+        }
+    }
+
+    opportunities.into_iter()
+        .zip(allocated)
+        .filter_map(|(mut opportunity, amount)| {
+            if amount <= Decimal::ZERO || amount < min_amount(&opportunity) {
+                log::debug!(
+                    "{} -> {} {}: доля капитала {} меньше минимального объёма — пропущена",
+                    opportunity.from_dex, opportunity.to_dex, pair_key(&opportunity), amount
+                );
+                return None;
+            }
+            if amount < opportunity.trade_amount {
+                opportunity.estimated_fees = opportunity.estimated_fees * amount / opportunity.trade_amount;
+                opportunity.trade_amount = amount;
+            }
+            Some(opportunity)
+        })
+        .collect()
+}
+
+/// Общие токены или DEX у двух возможностей
+fn overlaps(a: &ArbitrageOpportunity, b: &ArbitrageOpportunity) -> bool {
+    let tokens = [&a.base_token, &a.quote_token];
+    let venues = [&a.from_dex, &a.to_dex];
+    tokens.contains(&&b.base_token)
+        || tokens.contains(&&b.quote_token)
+        || venues.contains(&&b.from_dex)
+        || venues.contains(&&b.to_dex)
+}
+
+/// Разбиение возможностей на пачки для одновременного исполнения
+///
+/// Возможности берутся в порядке ранжирования: следующая присоединяется к
+/// текущей пачке, только если не делит с её возможностями ни токены, ни DEX
+/// (иначе сделки расходуют один баланс или двигают цену одного пула).
+/// Пачки исполняются по очереди, возможности внутри пачки — одновременно.
+pub fn execution_batches(
+    opportunities: Vec<ArbitrageOpportunity>,
+    max_batch_size: usize,
+) -> Vec<Vec<ArbitrageOpportunity>> {
+    let mut batches: Vec<Vec<ArbitrageOpportunity>> = Vec::new();
+    for opportunity in opportunities {
+        match batches.last_mut() {
+            Some(batch)
+                if batch.len() < max_batch_size.max(1)
+                    && batch.iter().all(|other| !overlaps(other, &opportunity)) =>
+            {
+                batch.push(opportunity);
+            }
+            _ => batches.push(vec![opportunity]),
+        }
+    }
+    batches
+}
@@ -0,0 +1,267 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::{Mutex, OnceCell};
+use uuid::Uuid;
+use crate::store::Store;
+
+/// Коллекция выпусков и отзывов токенов API в хранилище
+pub const TOKENS_COLLECTION: &str = "api_tokens";
+
+/// Префикс токена (отличает его от пароля Basic в логах и менеджерах секретов)
+pub const TOKEN_PREFIX: &str = "arb_";
+
+/// Срок действия токена по умолчанию
+pub const DEFAULT_TOKEN_TTL_SEC: u64 = 30 * 24 * 3600;
+
+/// Максимальный срок действия токена
+pub const MAX_TOKEN_TTL_SEC: u64 = 365 * 24 * 3600;
+
+/// Максимальная длина названия токена
+pub const MAX_TOKEN_NAME_LEN: usize = 100;
+
+/// Право доступа токена API
+///
+/// Учётные данные администратора (Basic) разрешают всё; токены — только
+/// перечисленное. Выпуск и отзыв токенов, замена ключа кошелька и прочие
+/// изменения без отдельного права доступны только администратору.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Scope {
+    /// GET /api/metrics, /api/metrics/prometheus (Grafana, Prometheus)
+    #[serde(rename = "read:metrics")]
+    ReadMetrics,
+    /// GET /api/status
+    #[serde(rename = "read:status")]
+    ReadStatus,
+    /// GET /api/history, /api/history/cleanups
+    #[serde(rename = "read:history")]
+    ReadHistory,
+    /// Любой GET запрос к /api (кроме токенов)
+    #[serde(rename = "read")]
+    Read,
+    /// Управление циклом, расписанием и включением DEX
+    #[serde(rename = "control")]
+    Control,
+}
+
+impl Scope {
+    /// Право, достаточное для запроса (None — только администратор)
+    pub fn required(method: &str, path: &str) -> Option<Scope> {
+        if path.starts_with("/api/auth/") || path == "/api/auth" {
+            return None;
+        }
+        match method {
+            "GET" => Some(match path {
+                "/api/metrics" | "/api/metrics/prometheus" => Scope::ReadMetrics,
+                "/api/status" => Scope::ReadStatus,
+                "/api/history" | "/api/history/cleanups" => Scope::ReadHistory,
+                _ => Scope::Read,
+            }),
+            "POST" if path.starts_with("/api/control/")
+                || path == "/api/schedule/override"
+                || (path.starts_with("/api/dexes/") && (path.ends_with("/enable") || path.ends_with("/disable"))) => {
+                Some(Scope::Control)
+            }
+            _ => None,
+        }
+    }
+
+    /// Покрывает ли право `self` требуемое право `required`
+    pub fn covers(self, required: Scope) -> bool {
+        self == required
+            || (self == Scope::Read && matches!(required, Scope::ReadMetrics | Scope::ReadStatus | Scope::ReadHistory))
+    }
+}
+
+/// Выпущенный токен (в хранилище — только хеш самого токена)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// SHA-256 токена (hex)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token_hash: String,
+}
+
+impl ApiToken {
+    /// Действует ли токен на момент `now`
+    pub fn active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && now < self.expires_at
+    }
+
+    /// Разрешает ли токен запрос с требуемым правом
+    pub fn allows(&self, required: Scope) -> bool {
+        self.scopes.iter().any(|scope| scope.covers(required))
+    }
+
+    /// Описание токена для API (без хеша)
+    pub fn public(&self) -> ApiToken {
+        ApiToken { token_hash: String::new(), ..self.clone() }
+    }
+}
+
+/// Запись журнала токенов: выпуск или отзыв
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum TokenEvent {
+    Issued(ApiToken),
+    Revoked { id: Uuid, revoked_at: DateTime<Utc> },
+}
+
+/// Параметры выпуска токена
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenRequest {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// Срок действия в секундах (по умолчанию 30 дней, не больше года)
+    #[serde(default)]
+    pub expires_in_sec: Option<u64>,
+}
+
+impl TokenRequest {
+    /// Проверка и нормализация: обрезка названия, удаление повторяющихся прав
+    pub fn normalized(mut self) -> Result<Self> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() || self.name.chars().count() > MAX_TOKEN_NAME_LEN {
+            anyhow::bail!("Название токена должно быть непустым и не длиннее {} символов", MAX_TOKEN_NAME_LEN);
+        }
+        let mut scopes: Vec<Scope> = Vec::with_capacity(self.scopes.len());
+        for scope in self.scopes {
+            if !scopes.contains(&scope) {
+                scopes.push(scope);
+            }
+        }
+        if scopes.is_empty() {
+            anyhow::bail!("Токену нужно хотя бы одно право (scopes)");
+        }
+        self.scopes = scopes;
+        if let Some(ttl) = self.expires_in_sec {
+            if ttl == 0 || ttl > MAX_TOKEN_TTL_SEC {
+                anyhow::bail!("expires_in_sec должен быть от 1 до {}", MAX_TOKEN_TTL_SEC);
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// SHA-256 токена в hex
+fn hash_token(token: &str) -> String {
+    solana_sdk::hash::hashv(&[token.as_bytes()])
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Новый секрет токена: префикс и 32 случайных байта в base58
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(Uuid::new_v4().as_bytes());
+    bytes[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+    format!("{}{}", TOKEN_PREFIX, bs58::encode(bytes).into_string())
+}
+
+/// Токены API с ограниченными правами и сроком действия
+///
+/// Выпуски и отзывы дописываются в журнал хранилища, секрет токена
+/// показывается один раз при выпуске и хранится только как SHA-256.
+/// Журнал читается при первом обращении.
+#[derive(Debug, Clone)]
+pub struct ApiTokens {
+    store: Store,
+    tokens: std::sync::Arc<OnceCell<Mutex<HashMap<Uuid, ApiToken>>>>,
+}
+
+impl ApiTokens {
+    pub fn new(store: Store) -> Self {
+        Self {
+            store,
+            tokens: std::sync::Arc::new(OnceCell::new()),
+        }
+    }
+
+    async fn loaded(&self) -> Result<&Mutex<HashMap<Uuid, ApiToken>>> {
+        self.tokens.get_or_try_init(|| async {
+            let events: Vec<TokenEvent> = self.store.read_all(TOKENS_COLLECTION).await?;
+            let mut tokens = HashMap::new();
+            for event in events {
+                match event {
+                    TokenEvent::Issued(token) => {
+                        tokens.insert(token.id, token);
+                    }
+                    TokenEvent::Revoked { id, revoked_at } => {
+                        if let Some(token) = tokens.get_mut(&id) {
+                            token.revoked_at = Some(revoked_at);
+                        }
+                    }
+                }
+            }
+            Ok(Mutex::new(tokens))
+        }).await
+    }
+
+    /// Выпуск токена по проверенным параметрам (`TokenRequest::normalized`)
+    ///
+    /// Возвращает описание и секрет токена — секрет больше нигде не сохраняется.
+    pub async fn issue(&self, request: TokenRequest, now: DateTime<Utc>) -> Result<(ApiToken, String)> {
+        let ttl = request.expires_in_sec.unwrap_or(DEFAULT_TOKEN_TTL_SEC).min(MAX_TOKEN_TTL_SEC);
+        let secret = generate_token();
+        let token = ApiToken {
+            id: Uuid::new_v4(),
+            name: request.name,
+            scopes: request.scopes,
+            created_at: now,
+            expires_at: now + Duration::seconds(ttl as i64),
+            revoked_at: None,
+            token_hash: hash_token(&secret),
+        };
+
+        let tokens = self.loaded().await?;
+        let mut tokens = tokens.lock().await;
+        self.store.append(TOKENS_COLLECTION, &TokenEvent::Issued(token.clone())).await
+            .context("Не удалось сохранить токен")?;
+        tokens.insert(token.id, token.clone());
+        Ok((token.public(), secret))
+    }
+
+    /// Отзыв токена; false, если токен не найден
+    pub async fn revoke(&self, id: Uuid, now: DateTime<Utc>) -> Result<bool> {
+        let tokens = self.loaded().await?;
+        let mut tokens = tokens.lock().await;
+        let Some(token) = tokens.get_mut(&id) else {
+            return Ok(false);
+        };
+        if token.revoked_at.is_none() {
+            self.store.append(TOKENS_COLLECTION, &TokenEvent::Revoked { id, revoked_at: now }).await
+                .context("Не удалось сохранить отзыв токена")?;
+            token.revoked_at = Some(now);
+        }
+        Ok(true)
+    }
+
+    /// Все выпущенные токены (без хешей), новые первыми
+    pub async fn list(&self) -> Result<Vec<ApiToken>> {
+        let tokens = self.loaded().await?.lock().await;
+        let mut list: Vec<ApiToken> = tokens.values().map(ApiToken::public).collect();
+        list.sort_by_key(|token| std::cmp::Reverse(token.created_at));
+        Ok(list)
+    }
+
+    /// Действующий токен по секрету
+    pub async fn authenticate(&self, secret: &str, now: DateTime<Utc>) -> Result<Option<ApiToken>> {
+        if !secret.starts_with(TOKEN_PREFIX) {
+            return Ok(None);
+        }
+        let hash = hash_token(secret);
+        let tokens = self.loaded().await?.lock().await;
+        Ok(tokens.values()
+            .find(|token| token.token_hash == hash && token.active(now))
+            .map(ApiToken::public))
+    }
+}
@@ -1,16 +1,201 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
 use rust_decimal::Decimal;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::timeout;
-use crate::config::Config;
-use crate::wallet::Wallet;
-use crate::dex::{DexManager, DexInterface};
+use crate::allocator::{allocate_capital, execution_batches};
+use crate::balance_guard::BalanceGuard;
+use crate::clock::{system_clock, SharedClock};
+use crate::config::{Config, DataSource, FlashLoanReserveConfig, LegGate};
+use crate::wallet::{HotWallet, Wallet};
+use crate::event_log::SafetyEvent;
+use crate::failure::{classify_failure, CircuitBreaker, FailureKind};
+use crate::flash_loan::FlashLoan;
+use crate::dex::{default_fee_percent, fetch_token_balance, venue_type, DexManager, DexInterface};
+use crate::history::{TradeRecord, TradeStatus};
 use crate::monitor::Monitor;
+use crate::price_check::PriceCheck;
+use crate::price_snapshot::{PriceSnapshot, PriceStamp, VenuePrice};
+use crate::profile::{self, Stage};
+use crate::profit::{capital_sol, net_profit, network_fee_sol, profit_breakdown, ProfitBreakdown};
+use crate::receipt::{self, spawn_confirmation_watch, wait_for_confirmation, ExecutionStage, ExecutionTracker, TradeLeg};
+use crate::ranking::{rank_opportunities, FillHistory};
+use crate::reserve_guard::ReserveGuard;
+use crate::routing::{find_routes, PoolQuote, Route, RouteHop};
+use crate::rpc::create_rpc_client;
+use crate::account_snapshot::{AccountSnapshot, SnapshotGuard};
+use crate::scan_budget::ScanBudget;
+use crate::scan_stats::SkipReason;
+use crate::tx_error::failure_reason;
+use crate::trade_size::{self, SwapCurve};
+use crate::units::{self, Rounding, RoundingPolicy};
+use crate::venue_limiter::VenueLimiter;
+use crate::venue_score::{prefer_venues, VenueHistory, VenueScorer};
+use crate::wsol::is_native_sol;
 use std::sync::Arc;
 
-/// Арбитражная возможность
+/// Направления арбитража для списка цен: пары индексов (покупка, продажа)
+///
+/// Каждая неупорядоченная пара площадок проверяется один раз, покупка — там, где цена ниже.
+/// Пары с одинаковой ценой пропускаются.
+pub fn directed_spreads<T>(prices: &[(T, Decimal)]) -> Vec<(usize, usize)> {
+    let mut directions = Vec::new();
+    for i in 0..prices.len() {
+        for j in (i + 1)..prices.len() {
+            match prices[i].1.cmp(&prices[j].1) {
+                std::cmp::Ordering::Less => directions.push((i, j)),
+                std::cmp::Ordering::Greater => directions.push((j, i)),
+                std::cmp::Ordering::Equal => {}
+            }
+        }
+    }
+    directions
+}
+
+/// Допустимое проскальзывание по ногам сделки, в процентах
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegSlippage {
+    pub buy: Decimal,
+    pub sell: Decimal,
+}
+
+/// Минимальный выход каждой ноги сделки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegMinOutput {
+    /// Минимум base токена, получаемого при покупке
+    pub buy: Decimal,
+    /// Минимум quote токена, получаемого при продаже
+    pub sell: Decimal,
+}
+
+/// Ожидаемый выход ног для точного объёма сделки по состоянию пулов
+/// (после комиссии пула и влияния объёма на цену)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegQuote {
+    /// base токен, получаемый при покупке
+    pub buy: Decimal,
+    /// quote токен, получаемый при продаже
+    pub sell: Decimal,
+}
+
+impl LegQuote {
+    /// Минимальный выход ног: котировка за вычетом допустимого проскальзывания
+    ///
+    /// Проскальзывание покрывает только движение цены между котировкой и
+    /// исполнением: комиссия и влияние объёма на цену уже учтены в котировке.
+    pub fn min_outputs(&self, slippage: LegSlippage) -> LegMinOutput {
+        let hundred = Decimal::from(100);
+        LegMinOutput {
+            buy: self.buy * (Decimal::ONE - slippage.buy / hundred),
+            sell: self.sell * (Decimal::ONE - slippage.sell / hundred),
+        }
+    }
+}
+
+/// Вход ноги покупки: quote токен на покупку trade_amount base по цене покупки
+pub fn buy_leg_input(opportunity: &ArbitrageOpportunity) -> Decimal {
+    opportunity.trade_amount * opportunity.buy_price
+}
+
+/// Минимальный выход ног по ценам возможности и допустимому проскальзыванию
+///
+/// Покупка должна дать trade_amount base токена, продажа — trade_amount × sell_price
+/// quote токена; каждая нога допускает отклонение на своё проскальзывание.
+/// Без минимума на покупке пул, цену которого сдвинули, исполнил бы её по любому курсу.
+/// Запасной вариант, если котировку пулов (`LegQuote`) получить не удалось.
+pub fn leg_min_outputs(opportunity: &ArbitrageOpportunity, slippage: LegSlippage) -> LegMinOutput {
+    let hundred = Decimal::from(100);
+    LegMinOutput {
+        buy: opportunity.trade_amount * (Decimal::ONE - slippage.buy / hundred),
+        sell: opportunity.trade_amount * opportunity.sell_price * (Decimal::ONE - slippage.sell / hundred),
+    }
+}
+
+/// Объём и минимальный выход продажи по фактически полученному при покупке
+///
+/// Если покупка исполнилась частично, продаётся только полученное, а минимальный
+/// выход уменьшается пропорционально. Ошибка, если покупка не дала base токена.
+pub fn size_sell_leg(
+    opportunity: &ArbitrageOpportunity,
+    sell_min_output: Decimal,
+    received: Decimal,
+) -> Result<(Decimal, Decimal)> {
+    if received <= Decimal::ZERO {
+        anyhow::bail!("Покупка не увеличила баланс {}: продавать нечего", opportunity.base_token);
+    }
+    if opportunity.trade_amount <= Decimal::ZERO {
+        return Ok((received, sell_min_output));
+    }
+    if received < opportunity.trade_amount {
+        log::warn!(
+            "Покупка исполнена частично: получено {} {} из {}, продажа уменьшена",
+            received, opportunity.base_token, opportunity.trade_amount
+        );
+    }
+    Ok((received, sell_min_output * received / opportunity.trade_amount))
+}
+
+/// Объём и минимальный выход продажи без проверки полученного покупкой
+///
+/// Покупка гарантирует только `min_output.buy` base токена (меньше trade_amount
+/// после комиссии и влияния на цену): продаётся столько, а минимальный выход
+/// продажи уменьшается в той же пропорции. Продажа trade_amount откатила бы
+/// транзакцию без запаса base токена или молча продала бы запас кошелька.
+/// Так размеряется продажа в одной транзакции с покупкой и в два этапа, если
+/// баланс после покупки не читается (оптимистичный переход, неизвестный mint).
+pub fn atomic_sell_leg(opportunity: &ArbitrageOpportunity, min_output: LegMinOutput) -> (Decimal, Decimal) {
+    if opportunity.trade_amount <= Decimal::ZERO {
+        return (min_output.buy, min_output.sell);
+    }
+    (min_output.buy, min_output.sell * min_output.buy / opportunity.trade_amount)
+}
+
+/// Сделка в одной транзакции, собранная для отправки или симуляции
 #[derive(Debug, Clone)]
+pub struct AtomicTrade {
+    /// Количество инструкций покупки и продажи
+    pub leg_instructions: (usize, usize),
+    /// Группы инструкций свопов (на флеш-займе — между заёмом и возвратом)
+    pub swaps: Vec<Vec<Instruction>>,
+    /// Проверка прироста баланса quote токена
+    pub guard: BalanceGuard,
+}
+
+/// Возможность по замкнутому маршруту
+///
+/// base_token — токен, получаемый первым обменом из стартового (quote) токена;
+/// цена покупки — его цена в стартовом токене на первом шаге, цена продажи —
+/// цена, которую за него даёт весь маршрут до комиссий. Так прибыль и объём
+/// считаются так же, как у покупки и продажи одной пары.
+pub fn route_opportunity(route: Route, trade_amount: Decimal) -> ArbitrageOpportunity {
+    let first = &route.hops[0];
+    let last = &route.hops[route.hops.len() - 1];
+    let buy_price = Decimal::ONE / first.rate;
+    ArbitrageOpportunity {
+        from_dex: first.dex.clone(),
+        to_dex: last.dex.clone(),
+        base_token: first.to_token.clone(),
+        quote_token: route.start_token().to_string(),
+        buy_price,
+        sell_price: buy_price * route.gross_rate(),
+        profit_percent: route.profit_percent(),
+        profit_percent_after_fees: route.profit_percent_after_fees(),
+        trade_amount,
+        estimated_fees: trade_amount * (route.total_fee_percent() / Decimal::from(100)),
+        route: route.hops,
+        price_impact: PriceImpact::default(),
+        price_stamps: LegStamps::default(),
+    }
+}
+
+/// Арбитражная возможность
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArbitrageOpportunity {
     pub from_dex: String,
     pub to_dex: String,
@@ -22,15 +207,146 @@ pub struct ArbitrageOpportunity {
     pub profit_percent_after_fees: Decimal, // Прибыль с учётом комиссий
     pub trade_amount: Decimal,
     pub estimated_fees: Decimal, // Оценка комиссий
+    /// Шаги маршрута через несколько пар (пусто для покупки и продажи одной пары)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub route: Vec<RouteHop>,
+    /// Влияние объёма сделки на цену по ногам (по резервам пулов или глубине книги)
+    #[serde(default)]
+    pub price_impact: PriceImpact,
+    /// Время получения и слот цен ног (пусто для маршрутов)
+    #[serde(default)]
+    pub price_stamps: LegStamps,
+}
+
+/// Влияние сделки на цену по ногам, в процентах от цены DEX
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PriceImpact {
+    /// Насколько средняя цена покупки выше цены DEX покупки
+    pub buy_percent: Decimal,
+    /// Насколько средняя цена продажи ниже цены DEX продажи
+    pub sell_percent: Decimal,
+}
+
+impl PriceImpact {
+    /// Суммарное влияние обеих ног (сравнивается со спредом)
+    pub fn total(&self) -> Decimal {
+        self.buy_percent + self.sell_percent
+    }
+}
+
+/// Время получения и слот цен ног, по которым найдена возможность
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegStamps {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buy: Option<PriceStamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sell: Option<PriceStamp>,
+    /// Разница слотов цен ног (None — слот хотя бы одной ноги неизвестен)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot_skew: Option<u64>,
+}
+
+impl LegStamps {
+    /// Отметки цен ног и разница их слотов
+    pub fn new(buy: PriceStamp, sell: PriceStamp) -> Self {
+        let slot_skew = buy.slot.zip(sell.slot).map(|(buy, sell)| buy.abs_diff(sell));
+        Self { buy: Some(buy), sell: Some(sell), slot_skew }
+    }
+
+    /// Возраст самой старой цены ног на момент `now` (None — время цен неизвестно)
+    pub fn max_age(&self, now: DateTime<Utc>) -> Option<Duration> {
+        [self.buy, self.sell].into_iter().flatten().map(|stamp| stamp.age(now)).max()
+    }
+}
+
+impl ArbitrageOpportunity {
+    /// Возможность по маршруту через несколько пар (routing)
+    pub fn is_route(&self) -> bool {
+        !self.route.is_empty()
+    }
+}
+
+/// Сделка для оценки в песочнице: пара, площадки и объём задаются вручную
+#[derive(Debug, Clone)]
+pub struct SandboxTrade {
+    pub base_token: String,
+    pub quote_token: String,
+    pub buy_dex: String,
+    pub sell_dex: String,
+    /// Объём в базовом токене
+    pub amount: Decimal,
+}
+
+/// Результат одной проверки перед исполнением
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl RiskCheck {
+    fn new(name: &'static str, passed: bool, detail: String) -> Self {
+        Self { name, passed, detail }
+    }
+}
+
+/// Полная оценка сделки — то, что движок посчитал бы перед исполнением
+#[derive(Debug, Clone)]
+pub struct TradeEvaluation {
+    pub opportunity: ArbitrageOpportunity,
+    pub buy_fee_percent: Decimal,
+    pub sell_fee_percent: Decimal,
+    /// Порог прибыли после комиссий (с надбавкой за перегрузку сети)
+    pub min_profit_percent: Decimal,
+    pub slippage: LegSlippage,
+    /// Котировка ног по состоянию пулов (None — не удалось получить)
+    pub quote: Option<LegQuote>,
+    pub min_output: LegMinOutput,
+    pub profit: ProfitBreakdown,
+    pub checks: Vec<RiskCheck>,
+}
+
+impl TradeEvaluation {
+    /// Прошла бы сделка все проверки
+    pub fn would_execute(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+}
+
+/// Кривая обмена ноги сделки
+struct LegCurve {
+    curve: SwapCurve,
+    /// Ликвидность DEX в base токене (только для пула без известных резервов)
+    liquidity: Option<Decimal>,
+}
+
+/// Влияние сделки объёмом amount (в base) на цену по ногам
+fn leg_price_impact(buy: &LegCurve, sell: &LegCurve, amount: Decimal, buy_price: Decimal) -> PriceImpact {
+    PriceImpact {
+        buy_percent: buy.curve.price_impact_percent(amount * buy_price),
+        sell_percent: sell.curve.price_impact_percent(amount),
+    }
 }
 
 /// Движок арбитража
 pub struct ArbitrageEngine {
     config: Config,
-    wallet: Arc<Wallet>,
-    dex_manager: DexManager,
+    wallet: HotWallet,
+    dex_manager: Arc<DexManager>,
     monitor: Monitor,
     consecutive_failures: u32,
+    circuit_breaker: CircuitBreaker,
+    halt_reason: Option<String>,
+    fill_history: FillHistory,
+    venue_history: VenueHistory,
+    venue_limiter: Arc<VenueLimiter>,
+    price_check: PriceCheck,
+    scan_budget: ScanBudget,
+    reserve_guard: ReserveGuard,
+    /// Снимок аккаунтов на прогон поиска и клиент для его чтения (monitoring.consistent_snapshot)
+    account_snapshot: Option<(Arc<AccountSnapshot>, Arc<RpcClient>)>,
+    clock: SharedClock,
 }
 
 impl ArbitrageEngine {
@@ -38,30 +354,124 @@ impl ArbitrageEngine {
     pub fn new(
         config: Config,
         wallet: Arc<Wallet>,
-        dex_manager: DexManager,
+        dex_manager: impl Into<Arc<DexManager>>,
         monitor: Monitor,
     ) -> Self {
+        let venue_limiter = Arc::new(VenueLimiter::new(&config.dex));
+        let circuit_breaker = CircuitBreaker::new(
+            config.safety.circuit_breaker_threshold,
+            Duration::from_secs(config.safety.circuit_breaker_cooldown_sec),
+        );
+        let wallet = HotWallet::new(wallet, config.wallet.keypair_path.clone());
+        let dex_manager = dex_manager.into();
+        let price_check = PriceCheck::new(&config.price_check, dex_manager.tokens(), &config.network.proxy);
+        let scan_budget = ScanBudget::new(&config.scan_budget);
+        let reserve_guard = ReserveGuard::new(
+            config.safety.reserve_change_limit(),
+            Duration::from_millis(config.monitoring.check_interval_ms),
+        );
+        // С подпиской Yellowstone аккаунты и так отвечаются из кеша на последнем слоте узла
+        let account_snapshot = if config.monitoring.consistent_snapshot && config.network.data_source == DataSource::Rpc {
+            create_rpc_client(&config.network)
+                .inspect_err(|e| log::warn!("Снимок аккаунтов на прогон отключён: {:#}", e))
+                .ok()
+                .map(|client| (AccountSnapshot::for_url(&config.network.rpc_url), Arc::new(client)))
+        } else {
+            None
+        };
         Self {
             config,
             wallet,
             dex_manager,
             monitor,
             consecutive_failures: 0,
+            circuit_breaker,
+            halt_reason: None,
+            fill_history: FillHistory::default(),
+            venue_history: VenueHistory::default(),
+            venue_limiter,
+            price_check,
+            scan_budget,
+            reserve_guard,
+            account_snapshot,
+            clock: system_clock(),
         }
     }
 
+    /// Замена источника времени (для тестов и режима воспроизведения)
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Кошелёк с заменой ключа (общий с веб-сервером)
+    pub fn wallet(&self) -> HotWallet {
+        self.wallet.clone()
+    }
+
+    /// Ограничитель одновременных транзакций по DEX (для метрик очередей)
+    pub fn venue_limiter(&self) -> Arc<VenueLimiter> {
+        self.venue_limiter.clone()
+    }
+
+    /// Менеджер DEX (общий с веб-сервером: цены и отключение DEX без блокировки движка)
+    pub fn dex_manager(&self) -> Arc<DexManager> {
+        self.dex_manager.clone()
+    }
+
     /// Поиск арбитражных возможностей
     pub async fn find_opportunities(&self) -> Result<Vec<ArbitrageOpportunity>> {
+        self.search().await.map(|(opportunities, _)| opportunities)
+    }
+
+    /// Прогон поиска торгового цикла: поиск возможностей с учётом в статистике прогонов
+    ///
+    /// Запросы возможностей через API в статистику не попадают.
+    pub async fn scan(&self) -> Result<Vec<ArbitrageOpportunity>> {
+        let started = Instant::now();
+        let result = self.search().await;
+        let (prices_fetched, found) = match &result {
+            Ok((opportunities, prices_fetched)) => (*prices_fetched, Some(opportunities.len())),
+            Err(_) => (0, None),
+        };
+        self.monitor.scan_stats().record_cycle(self.clock.now(), started.elapsed(), prices_fetched, found);
+        result.map(|(opportunities, _)| opportunities)
+    }
+
+    /// Поиск возможностей и количество полученных цен
+    ///
+    /// Цены и найденные возможности публикуются снимком в `Monitor::prices`.
+    async fn search(&self) -> Result<(Vec<ArbitrageOpportunity>, usize)> {
         let mut opportunities = Vec::new();
+        let mut prices_fetched = 0;
+        let mut snapshot_pairs = BTreeMap::new();
 
-        // Получение всех DEX
-        let dexes = self.dex_manager.get_dexes();
+        // Получение включённых DEX
+        let dexes = self.dex_manager.active_dexes();
         if dexes.len() < 2 {
-            return Ok(opportunities); // Нужно минимум 2 DEX для арбитража
+            self.publish_snapshot(snapshot_pairs, &opportunities, None);
+            return Ok((opportunities, prices_fetched)); // Нужно минимум 2 DEX для арбитража
         }
 
+        // Цены всех DEX считаются по аккаунтам, прочитанным одним снимком
+        let accounts = self.open_account_snapshot().await;
+
+        // Пары, которые помещаются в бюджет запросов RPC на прогон
+        let venues: Vec<&str> = dexes.iter().map(|dex| dex.name()).collect();
+        let plan = self.scan_budget.plan(&self.dex_manager.trading_pairs(), &venues, &self.config.dex);
+        if !plan.skipped.is_empty() {
+            log::debug!(
+                "Бюджет запросов {}: проверяется {} пар ({} запросов), пропущены: {}",
+                self.config.scan_budget.max_requests, plan.pairs.len(), plan.requests, plan.skipped.join(", ")
+            );
+        }
+
+        // Котировки пулов всех пар для поиска маршрутов через несколько пар
+        let routing = self.config.routing.enabled;
+        let mut quotes = Vec::new();
+
         // Проверка каждой торговой пары
-        for pair in &self.config.dex.trading_pairs {
+        for pair in &plan.pairs {
             let parts: Vec<&str> = pair.split('/').collect();
             if parts.len() != 2 {
                 log::warn!("Некорректный формат торговой пары: {}", pair);
@@ -71,12 +481,23 @@ impl ArbitrageEngine {
             let base_token = parts[0];
             let quote_token = parts[1];
 
-            // Получение цен со всех DEX
+            // Получение цен со всех DEX, на которых торгуется пара
             let mut prices = Vec::new();
-            for dex in dexes {
-                match dex.get_price(base_token, quote_token).await {
+            let mut snapshot_prices = Vec::new();
+            for dex in &dexes {
+                if !self.config.dex.lists_pair(dex.name(), pair) {
+                    continue;
+                }
+                match profile::measure(Stage::PriceFetch, Some(dex.name()), dex.get_price(base_token, quote_token)).await {
                     Ok(price) => {
+                        self.price_check.spawn_check(dex.name(), base_token, quote_token, price, &self.monitor);
                         prices.push((dex.name(), price));
+                        snapshot_prices.push(VenuePrice {
+                            dex: dex.name().to_string(),
+                            price,
+                            fetched_at: self.clock.now(),
+                            slot: dex.price_slot(base_token, quote_token),
+                        });
                     }
                     Err(e) => {
                         log::debug!("Ошибка получения цены с {}: {}", dex.name(), e);
@@ -84,77 +505,608 @@ impl ArbitrageEngine {
                 }
             }
 
+            prices_fetched += prices.len();
+            let stamps: Vec<PriceStamp> = snapshot_prices.iter().map(VenuePrice::stamp).collect();
+            snapshot_pairs.insert(pair.clone(), snapshot_prices);
+
+            if self.reserves_jumped(pair, base_token, quote_token, &prices) {
+                continue;
+            }
+
+            if routing {
+                for (dex_name, price) in &prices {
+                    quotes.push(PoolQuote {
+                        dex: dex_name.to_string(),
+                        base_token: base_token.to_string(),
+                        quote_token: quote_token.to_string(),
+                        price: *price,
+                        fee_percent: self.get_dex_fee(dex_name, base_token, quote_token).await,
+                    });
+                }
+            }
+
             if prices.len() < 2 {
                 continue; // Нужно минимум 2 цены для сравнения
             }
+            let detection_started = Instant::now();
+
+            // Каждая пара DEX рассматривается один раз: направление (где купить, где продать)
+            // определяется знаком спреда
+            for (i, j) in directed_spreads(&prices) {
+                let (buy_dex, buy_price) = &prices[i];
+                let (sell_dex, sell_price) = &prices[j];
+                if !self.config.dex.direction_allowed(pair, buy_dex, sell_dex) {
+                    log::debug!("{}: направление {} -> {} запрещено dex.directions", pair, buy_dex, sell_dex);
+                    continue;
+                }
+
+                // Цены на далёких слотах дают фантомный спред: пул одной площадки
+                // уже изменился, а другой ещё нет
+                let price_stamps = LegStamps::new(stamps[i], stamps[j]);
+                if self.slot_skew_exceeded(&price_stamps) {
+                    log::debug!(
+                        "{} -> {} {}: цены на слотах {:?} и {:?}, разница больше arbitrage.max_slot_skew {}",
+                        buy_dex, sell_dex, pair, stamps[i].slot, stamps[j].slot, self.config.arbitrage.max_slot_skew
+                    );
+                    continue;
+                }
+
+                let profit_percent = ((sell_price - buy_price) / buy_price) * Decimal::from(100);
+
+                // Расчёт оптимального объёма сделки (до учёта комиссий)
+                let Some((trade_amount, price_impact)) = self.calculate_optimal_trade_amount(
+                    *buy_price,
+                    *sell_price,
+                    base_token,
+                    quote_token,
+                    buy_dex,
+                    sell_dex,
+                ).await? else {
+                    continue;
+                };
+
+                // Спред по ценам DEX съедается влиянием объёма на цену
+                if price_impact.total() >= profit_percent {
+                    log::debug!(
+                        "{} -> {} {}: влияние на цену {}% (покупка {}%, продажа {}%) не меньше спреда {}%",
+                        buy_dex, sell_dex, pair, price_impact.total().round_dp(4),
+                        price_impact.buy_percent.round_dp(4), price_impact.sell_percent.round_dp(4),
+                        profit_percent.round_dp(4)
+                    );
+                    continue;
+                }
+
+                // Получение комиссий DEX (и флеш-займа, если сделка пойдёт на заёмные средства)
+                let buy_fee_percent = self.get_dex_fee(buy_dex, base_token, quote_token).await;
+                let sell_fee_percent = self.get_dex_fee(sell_dex, base_token, quote_token).await;
+                let total_fee_percent = buy_fee_percent
+                    + sell_fee_percent
+                    + self.flash_loan_fee_percent(buy_dex, sell_dex, quote_token);
+
+                // Расчёт прибыли с учётом комиссий
+                let profit_after_fees = profit_percent - total_fee_percent;
+                
+                // Оценка комиссий в SOL
+                let estimated_fees = trade_amount * (total_fee_percent / Decimal::from(100));
+
+                let min_profit = self.min_profit_percent();
 
-            // Поиск максимальной разницы в ценах
-            for i in 0..prices.len() {
-                for j in 0..prices.len() {
-                    if i == j {
+                // Проверка минимальной прибыли с учётом комиссий
+                if profit_after_fees < min_profit {
+                    continue;
+                }
+                let opportunity = ArbitrageOpportunity {
+                    from_dex: buy_dex.to_string(),
+                    to_dex: sell_dex.to_string(),
+                    base_token: base_token.to_string(),
+                    quote_token: quote_token.to_string(),
+                    buy_price: *buy_price,
+                    sell_price: *sell_price,
+                    profit_percent,
+                    profit_percent_after_fees: profit_after_fees,
+                    trade_amount,
+                    estimated_fees,
+                    route: Vec::new(),
+                    price_impact,
+                    price_stamps,
+                };
+
+                // Абсолютный порог прибыли после сетевой комиссии
+                if let (Some(buy), Some(sell)) = (self.dex_manager.get_dex(buy_dex), self.dex_manager.get_dex(sell_dex)) {
+                    let check = self.min_profit_absolute_check(&opportunity, buy, sell).await;
+                    if !check.passed {
+                        log::debug!("{} -> {} {}: {}", buy_dex, sell_dex, pair, check.detail);
                         continue;
                     }
+                }
+                opportunities.push(opportunity);
+            }
+            profile::record(Stage::Detection, None, detection_started.elapsed());
+        }
 
-                    let (buy_dex, buy_price) = &prices[i];
-                    let (sell_dex, sell_price) = &prices[j];
-
-                    // Проверка возможности арбитража (покупка дешевле, продажа дороже)
-                    if sell_price > buy_price {
-                        let profit_percent = ((sell_price - buy_price) / buy_price) * Decimal::from(100);
-
-                        // Расчёт оптимального объёма сделки (до учёта комиссий)
-                        let trade_amount = self.calculate_optimal_trade_amount(
-                            *buy_price,
-                            *sell_price,
-                            base_token,
-                            quote_token,
-                            buy_dex,
-                            sell_dex,
-                        ).await?;
-
-                        // Получение комиссий DEX
-                        let buy_fee_percent = self.get_dex_fee(buy_dex).await.unwrap_or(Decimal::from_str("0.25")?); // 0.25% по умолчанию
-                        let sell_fee_percent = self.get_dex_fee(sell_dex).await.unwrap_or(Decimal::from_str("0.25")?); // 0.25% по умолчанию
-                        let total_fee_percent = buy_fee_percent + sell_fee_percent;
-
-                        // Расчёт прибыли с учётом комиссий
-                        let profit_after_fees = profit_percent - total_fee_percent;
-                        
-                        // Оценка комиссий в SOL
-                        let estimated_fees = trade_amount * (total_fee_percent / Decimal::from(100));
-
-                        let min_profit = Decimal::from_str(&format!("{:.10}", self.config.arbitrage.min_profit_percent))
-                            .unwrap_or(Decimal::ZERO);
-                        
-                        // Проверка минимальной прибыли с учётом комиссий
-                        if profit_after_fees >= min_profit {
-                            opportunities.push(ArbitrageOpportunity {
-                                from_dex: buy_dex.to_string(),
-                                to_dex: sell_dex.to_string(),
-                                base_token: base_token.to_string(),
-                                quote_token: quote_token.to_string(),
-                                buy_price: *buy_price,
-                                sell_price: *sell_price,
-                                profit_percent,
-                                profit_percent_after_fees: profit_after_fees,
-                                trade_amount,
-                                estimated_fees,
-                            });
-                        }
+        // Частота возможностей по парам определяет приоритет при нехватке бюджета
+        for pair in &plan.pairs {
+            let found = opportunities.iter()
+                .any(|o| pair.split_once('/') == Some((o.base_token.as_str(), o.quote_token.as_str())));
+            self.scan_budget.record(pair, found);
+        }
+
+        if routing {
+            let detection_started = Instant::now();
+            opportunities.extend(self.route_opportunities(&quotes));
+            profile::record(Stage::Detection, None, detection_started.elapsed());
+        }
+
+        // Сортировка согласно выбранной стратегии ранжирования
+        rank_opportunities(&mut opportunities, self.config.arbitrage.ranking, &self.fill_history);
+        if self.config.venue_scoring.enabled {
+            let mut fees = HashMap::new();
+            for opportunity in opportunities.iter().filter(|o| !o.is_route()) {
+                for venue in [&opportunity.from_dex, &opportunity.to_dex] {
+                    let key = (venue.clone(), opportunity.base_token.clone(), opportunity.quote_token.clone());
+                    if let std::collections::hash_map::Entry::Vacant(entry) = fees.entry(key) {
+                        entry.insert(self.get_dex_fee(venue, &opportunity.base_token, &opportunity.quote_token).await);
                     }
                 }
             }
+            let scorer = VenueScorer::new(&self.config.venue_scoring);
+            prefer_venues(&mut opportunities, &scorer, &self.venue_history, |venue, o| {
+                fees.get(&(venue.to_string(), o.base_token.clone(), o.quote_token.clone()))
+                    .copied()
+                    .unwrap_or(Decimal::ZERO)
+            });
+        }
+
+        let accounts_slot = accounts.and_then(|accounts| accounts.slots()).map(|(first, _)| first);
+        self.publish_snapshot(snapshot_pairs, &opportunities, accounts_slot);
+        Ok((opportunities, prices_fetched))
+    }
+
+    /// Снимок аккаунтов пулов на прогон (None — monitoring.consistent_snapshot выключен)
+    async fn open_account_snapshot(&self) -> Option<SnapshotGuard> {
+        let (snapshot, rpc_client) = self.account_snapshot.as_ref()?;
+        let guard = snapshot.open(rpc_client.clone()).await;
+        if let Some((first, last)) = guard.slots() {
+            log::debug!("Снимок аккаунтов прогона: слоты {}..={}", first, last);
+        }
+        Some(guard)
+    }
+
+    fn publish_snapshot(
+        &self,
+        pairs: BTreeMap<String, Vec<VenuePrice>>,
+        opportunities: &[ArbitrageOpportunity],
+        accounts_slot: Option<u64>,
+    ) {
+        self.monitor.prices().publish(PriceSnapshot {
+            seq: 0,
+            taken_at: self.clock.now(),
+            accounts_slot,
+            pairs,
+            opportunities: opportunities.to_vec(),
+        });
+    }
+
+    /// Возможности по замкнутым маршрутам через несколько пар (routing)
+    ///
+    /// Маршруты начинаются и заканчиваются в quote токенах торговых пар.
+    fn route_opportunities(&self, quotes: &[PoolQuote]) -> Vec<ArbitrageOpportunity> {
+        let trading_pairs = self.dex_manager.trading_pairs();
+        let mut start_tokens: Vec<&str> = Vec::new();
+        for pair in &trading_pairs {
+            if let Some((_, quote_token)) = pair.split_once('/') {
+                if !start_tokens.contains(&quote_token) {
+                    start_tokens.push(quote_token);
+                }
+            }
+        }
+        let trade_amount = Decimal::from_str(&format!("{:.10}", self.config.arbitrage.max_trade_amount_sol))
+            .unwrap_or(Decimal::ZERO);
+        find_routes(quotes, &start_tokens, self.config.routing.max_hops, self.min_profit_percent())
+            .into_iter()
+            .map(|route| route_opportunity(route, trade_amount))
+            .collect()
+    }
+
+    /// Резко ли изменились резервы пулов пары с предыдущего прогона
+    ///
+    /// Наблюдения обновляются на всех DEX, даже если изменение найдено на первом,
+    /// чтобы следующий прогон сравнивал уже с новыми резервами.
+    fn reserves_jumped(&self, pair: &str, base_token: &str, quote_token: &str, prices: &[(&str, Decimal)]) -> bool {
+        if !self.reserve_guard.enabled() {
+            return false;
+        }
+        let now = self.clock.now();
+        let mut jumped = false;
+        for (dex_name, _) in prices {
+            let Some(reserves) = self.dex_manager.get_dex(dex_name)
+                .and_then(|dex| dex.pool_reserves(base_token, quote_token)) else {
+                continue;
+            };
+            if let Some(change) = self.reserve_guard.observe(dex_name, pair, reserves, now) {
+                log::warn!(
+                    "Резервы пула {} на {} изменились на {:.2}% с предыдущего прогона: пара отложена",
+                    pair, dex_name, change
+                );
+                jumped = true;
+            }
+        }
+        jumped
+    }
+
+    /// Оценка произвольной сделки без исполнения (песочница)
+    ///
+    /// Котировки, комиссии, прибыль и минимальные выходы считаются так же, как
+    /// при поиске и исполнении возможностей; проверки, которые отсеяли бы сделку,
+    /// возвращаются списком вместо пропуска. Ничего не ставится в очередь.
+    pub async fn evaluate_trade(&self, trade: &SandboxTrade) -> Result<TradeEvaluation> {
+        let buy_dex = self.dex_manager.get_dex(&trade.buy_dex)
+            .ok_or_else(|| anyhow::anyhow!("DEX не найден: {}", trade.buy_dex))?;
+        let sell_dex = self.dex_manager.get_dex(&trade.sell_dex)
+            .ok_or_else(|| anyhow::anyhow!("DEX не найден: {}", trade.sell_dex))?;
+        let (base_token, quote_token) = (trade.base_token.as_str(), trade.quote_token.as_str());
+
+        let buy_price = buy_dex.get_price(base_token, quote_token).await
+            .with_context(|| format!("Не удалось получить цену на {}", trade.buy_dex))?;
+        let sell_price = sell_dex.get_price(base_token, quote_token).await
+            .with_context(|| format!("Не удалось получить цену на {}", trade.sell_dex))?;
+        let fetched_at = self.clock.now();
+        let price_stamps = LegStamps::new(
+            PriceStamp { fetched_at, slot: buy_dex.price_slot(base_token, quote_token) },
+            PriceStamp { fetched_at, slot: sell_dex.price_slot(base_token, quote_token) },
+        );
+        if buy_price.is_zero() {
+            anyhow::bail!("Нулевая цена на {}", trade.buy_dex);
+        }
+
+        let profit_percent = ((sell_price - buy_price) / buy_price) * Decimal::from(100);
+        let buy_fee_percent = self.get_dex_fee(&trade.buy_dex, base_token, quote_token).await;
+        let sell_fee_percent = self.get_dex_fee(&trade.sell_dex, base_token, quote_token).await;
+        let total_fee_percent = buy_fee_percent
+            + sell_fee_percent
+            + self.flash_loan_fee_percent(&trade.buy_dex, &trade.sell_dex, quote_token);
+        let profit_after_fees = profit_percent - total_fee_percent;
+        let min_profit = self.min_profit_percent();
+        let buy_leg = self.leg_curve(&trade.buy_dex, base_token, quote_token, buy_price, true).await;
+        let sell_leg = self.leg_curve(&trade.sell_dex, base_token, quote_token, sell_price, false).await;
+        let price_impact = leg_price_impact(&buy_leg, &sell_leg, trade.amount, buy_price);
+
+        let opportunity = ArbitrageOpportunity {
+            from_dex: trade.buy_dex.clone(),
+            to_dex: trade.sell_dex.clone(),
+            base_token: trade.base_token.clone(),
+            quote_token: trade.quote_token.clone(),
+            buy_price,
+            sell_price,
+            profit_percent,
+            profit_percent_after_fees: profit_after_fees,
+            trade_amount: trade.amount,
+            estimated_fees: trade.amount * (total_fee_percent / Decimal::from(100)),
+            route: Vec::new(),
+            price_impact,
+            price_stamps,
+        };
+        let slippage = self.configured_slippage(buy_dex, sell_dex);
+        let quote = self.quote_legs(&opportunity).await
+            .inspect_err(|e| log::debug!("Песочница: котировка ног не получена: {:#}", e))
+            .ok();
+        let min_output = match quote {
+            Some(quote) => quote.min_outputs(slippage),
+            None => leg_min_outputs(&opportunity, slippage),
+        };
+        let profit = self.profit_breakdown(&opportunity).await;
+
+        let pair = format!("{}/{}", base_token, quote_token);
+        let dex_config = &self.config.dex;
+        let min_amount = dex_config.min_trade_amount(&trade.buy_dex).max(dex_config.min_trade_amount(&trade.sell_dex));
+        let max_amount = self.max_trade_amount(&trade.buy_dex, &trade.sell_dex, quote_token, buy_price);
+        let unlisted: Vec<&str> = [trade.buy_dex.as_str(), trade.sell_dex.as_str()].into_iter()
+            .filter(|dex| !dex_config.lists_pair(dex, &pair))
+            .collect();
+        let checks = vec![
+            RiskCheck::new(
+                "trading_pair",
+                self.dex_manager.trading_pairs().contains(&pair),
+                format!("{} в dex.trading_pairs или среди пар из токенов кошелька", pair),
+            ),
+            RiskCheck::new(
+                "pair_listed",
+                unlisted.is_empty(),
+                if unlisted.is_empty() {
+                    format!("{} торгуется на обеих площадках", pair)
+                } else {
+                    format!("{} не указана в dex.pairs для {}", pair, unlisted.join(", "))
+                },
+            ),
+            RiskCheck::new(
+                "direction",
+                buy_price < sell_price,
+                format!("покупка {} на {}, продажа {} на {}", buy_price, trade.buy_dex, sell_price, trade.sell_dex),
+            ),
+            RiskCheck::new(
+                "pair_direction",
+                dex_config.direction_allowed(&pair, &trade.buy_dex, &trade.sell_dex),
+                format!("{}: {} -> {} в dex.directions", pair, trade.buy_dex, trade.sell_dex),
+            ),
+            RiskCheck::new(
+                "min_profit",
+                profit_after_fees >= min_profit,
+                format!("прибыль после комиссий {}%, порог {}%", profit_after_fees, min_profit),
+            ),
+            RiskCheck::new(
+                "price_impact",
+                price_impact.total() < profit_percent,
+                format!(
+                    "влияние на цену {}% (покупка {}%, продажа {}%), спред {}%",
+                    price_impact.total().round_dp(4), price_impact.buy_percent.round_dp(4),
+                    price_impact.sell_percent.round_dp(4), profit_percent.round_dp(4)
+                ),
+            ),
+            RiskCheck::new(
+                "slot_skew",
+                !self.slot_skew_exceeded(&price_stamps),
+                match price_stamps.slot_skew {
+                    Some(skew) => format!(
+                        "разница слотов цен {}, допустимо {} (arbitrage.max_slot_skew)",
+                        skew, self.config.arbitrage.max_slot_skew
+                    ),
+                    None => "слот цены неизвестен".to_string(),
+                },
+            ),
+            self.min_profit_absolute_check(&opportunity, buy_dex, sell_dex).await,
+            RiskCheck::new(
+                "min_trade_amount",
+                trade.amount >= min_amount,
+                format!("объём {}, минимум {}", trade.amount, min_amount),
+            ),
+            RiskCheck::new(
+                "max_trade_amount",
+                trade.amount <= max_amount,
+                format!("объём {}, максимум {}", trade.amount, max_amount),
+            ),
+            match self.ensure_tokens_allowed(&opportunity) {
+                Ok(()) => RiskCheck::new("allowed_tokens", true, format!("{} в safety.allowed_tokens", pair)),
+                Err(e) => RiskCheck::new("allowed_tokens", false, format!("{:#}", e)),
+            },
+            match self.ensure_can_execute() {
+                Ok(()) => RiskCheck::new("execution_allowed", true, "исполнение разрешено".to_string()),
+                Err(e) => RiskCheck::new("execution_allowed", false, format!("{:#}", e)),
+            },
+        ];
+
+        Ok(TradeEvaluation {
+            opportunity,
+            buy_fee_percent,
+            sell_fee_percent,
+            min_profit_percent: min_profit,
+            slippage,
+            quote,
+            min_output,
+            profit,
+            checks,
+        })
+    }
+
+    /// Порог прибыли после комиссий с надбавкой за перегрузку сети, в процентах
+    fn min_profit_percent(&self) -> Decimal {
+        Decimal::from_str(&format!("{:.10}", self.config.arbitrage.min_profit_percent))
+            .unwrap_or(Decimal::ZERO)
+            + self.monitor.congestion().profit_margin()
+    }
+
+    /// Причина остановки исполнения (None, если исполнение разрешено)
+    pub fn halt_reason(&self) -> Option<&str> {
+        self.halt_reason.as_deref()
+    }
+
+    /// Снятие остановки исполнения при перезапуске торговой задачи
+    pub fn resume(&mut self) {
+        if let Some(reason) = self.halt_reason.take() {
+            log::info!("Остановка исполнения снята (была: {})", reason);
+            self.monitor.publish_safety(SafetyEvent::KillSwitch {
+                engaged: false,
+                reason: format!("перезапуск торгового цикла, была остановка: {}", reason),
+            });
         }
+        self.consecutive_failures = 0;
+        self.circuit_breaker.reset();
+    }
+
+    /// Остановка исполнения до перезапуска торговой задачи
+    fn halt(&mut self, reason: String) {
+        self.monitor.log_critical(&format!("Исполнение остановлено: {}", reason));
+        self.monitor.publish_safety(SafetyEvent::KillSwitch { engaged: true, reason: reason.clone() });
+        self.halt_reason = Some(reason);
+    }
 
-        // Сортировка по прибыльности с учётом комиссий
-        opportunities.sort_by(|a, b| b.profit_percent_after_fees.cmp(&a.profit_percent_after_fees));
+    /// Распределение капитала между найденными возможностями перед исполнением
+    ///
+    /// При выключенном распределении список возвращается без изменений.
+    pub fn allocate(&self, opportunities: Vec<ArbitrageOpportunity>) -> Vec<ArbitrageOpportunity> {
+        if !self.config.allocation.enabled || opportunities.is_empty() {
+            return opportunities;
+        }
+        let dex_config = &self.config.dex;
+        let found = opportunities.len();
+        let allocated = allocate_capital(opportunities, &self.config.allocation, |o| {
+            dex_config.min_trade_amount(&o.from_dex).max(dex_config.min_trade_amount(&o.to_dex))
+        });
+        self.monitor.scan_stats().record_skipped(SkipReason::Allocation, found - allocated.len());
+        allocated
+    }
 
-        Ok(opportunities)
+    /// Разбиение возможностей на пачки для исполнения
+    ///
+    /// Без `allocation.parallel_execution` каждая возможность — отдельная пачка
+    /// (строго последовательное исполнение).
+    pub fn batches(&self, opportunities: Vec<ArbitrageOpportunity>) -> Vec<Vec<ArbitrageOpportunity>> {
+        let allocation = &self.config.allocation;
+        if allocation.enabled && allocation.parallel_execution {
+            execution_batches(opportunities, allocation.max_parallel_executions)
+        } else {
+            opportunities.into_iter().map(|opportunity| vec![opportunity]).collect()
+        }
+    }
+
+    /// Возможности, которые разрешено исполнять (токены из safety.allowed_tokens,
+    /// направление из dex.directions, цены ног не старше arbitrage.max_price_age_ms)
+    ///
+    /// Остальные возможности только публикуются как сигналы.
+    pub fn executable(&self, opportunities: Vec<ArbitrageOpportunity>) -> Vec<ArbitrageOpportunity> {
+        opportunities.into_iter()
+            .filter(|opportunity| {
+                let check = self.ensure_single_pair(opportunity)
+                    .map_err(|e| (SkipReason::MultiHop, e))
+                    .and_then(|()| self.ensure_tokens_allowed(opportunity)
+                        .map_err(|e| (SkipReason::TokenNotAllowed, e)))
+                    .and_then(|()| self.ensure_direction_allowed(opportunity)
+                        .map_err(|e| (SkipReason::DirectionNotAllowed, e)))
+                    .and_then(|()| self.ensure_prices_fresh(opportunity)
+                        .map_err(|e| (SkipReason::StalePrice, e)));
+                match check {
+                    Ok(()) => true,
+                    Err((reason, e)) => {
+                        log::debug!("{} -> {}: {:#}", opportunity.from_dex, opportunity.to_dex, e);
+                        self.monitor.scan_stats().record_skipped(reason, 1);
+                        false
+                    }
+                }
+            })
+            .collect()
     }
 
     /// Выполнение арбитража
     pub async fn execute_arbitrage(&mut self, opportunity: ArbitrageOpportunity) -> Result<()> {
+        self.ensure_can_execute()?;
+        self.ensure_single_pair(&opportunity)?;
+        self.ensure_tokens_allowed(&opportunity)?;
+        self.ensure_direction_allowed(&opportunity)?;
+        self.ensure_prices_fresh(&opportunity)?;
+        self.monitor.scan_stats().record_executed(1);
+        let result = self.run_execution(&opportunity).await;
+        self.settle(&opportunity, result)
+    }
+
+    /// Одновременное выполнение пачки возможностей без общих токенов и DEX
+    ///
+    /// Результаты учитываются (счётчик неудач, остановка исполнения) после
+    /// завершения всей пачки, в порядке ранжирования.
+    pub async fn execute_batch(&mut self, batch: Vec<ArbitrageOpportunity>) -> Vec<Result<()>> {
+        if let Err(e) = self.ensure_can_execute() {
+            if let Some(reason) = self.execution_block() {
+                self.monitor.scan_stats().record_skipped(reason, batch.len());
+            }
+            return vec![Err(e)];
+        }
+        let batch = self.executable(batch);
+        if batch.len() > 1 {
+            log::info!("Одновременное выполнение {} возможностей", batch.len());
+        }
+        self.monitor.scan_stats().record_executed(batch.len());
+        let results = join_all(batch.iter().map(|opportunity| self.run_execution(opportunity))).await;
+        batch.iter()
+            .zip(results)
+            .map(|(opportunity, result)| self.settle(opportunity, result))
+            .collect()
+    }
+
+    /// Проверка, что исполнение сейчас разрешено
+    fn ensure_can_execute(&self) -> Result<()> {
+        match self.execution_block() {
+            None => Ok(()),
+            Some(SkipReason::Halted) => anyhow::bail!(
+                "Исполнение остановлено: {}", self.halt_reason.as_deref().unwrap_or_default()
+            ),
+            Some(SkipReason::CircuitBreaker) => anyhow::bail!("Исполнение приостановлено после сбоев RPC"),
+            Some(_) => anyhow::bail!("Исполнение отключено: бот работает в режиме {:?}", self.config.safety.mode),
+        }
+    }
+
+    /// Причина, по которой исполнение сейчас запрещено
+    fn execution_block(&self) -> Option<SkipReason> {
+        if !self.config.safety.mode.executes() {
+            Some(SkipReason::NoExecution)
+        } else if self.halt_reason.is_some() {
+            Some(SkipReason::Halted)
+        } else if self.circuit_breaker.is_open(self.clock.now()) {
+            Some(SkipReason::CircuitBreaker)
+        } else {
+            None
+        }
+    }
+
+    /// Проверка, что возможность исполняется покупкой и продажей одной пары
+    ///
+    /// Маршруты через несколько пар пока только публикуются как сигналы.
+    fn ensure_single_pair(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        if opportunity.is_route() {
+            let hops: Vec<String> = opportunity.route.iter().map(|hop| hop.to_string()).collect();
+            anyhow::bail!("Исполнение маршрутов через несколько пар не поддерживается: {}", hops.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Проверка, что оба токена пары входят в safety.allowed_tokens
+    fn ensure_tokens_allowed(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        for token in [&opportunity.base_token, &opportunity.quote_token] {
+            if !self.config.safety.token_allowed(token) {
+                anyhow::bail!(
+                    "Токен {} не входит в safety.allowed_tokens: пара {}/{} только отслеживается",
+                    token, opportunity.base_token, opportunity.quote_token
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Проверка, что направление сделки разрешено dex.directions
+    ///
+    /// Возможность могла прийти из шины или API в обход фильтра поиска.
+    fn ensure_direction_allowed(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let pair = format!("{}/{}", opportunity.base_token, opportunity.quote_token);
+        if !self.config.dex.direction_allowed(&pair, &opportunity.from_dex, &opportunity.to_dex) {
+            anyhow::bail!(
+                "Направление {} -> {} для {} запрещено dex.directions",
+                opportunity.from_dex, opportunity.to_dex, pair
+            );
+        }
+        Ok(())
+    }
+
+    /// Разница слотов цен ног больше arbitrage.max_slot_skew
+    ///
+    /// Ноги с неизвестным слотом (DEX без чтения аккаунтов) сравниваются как раньше.
+    fn slot_skew_exceeded(&self, stamps: &LegStamps) -> bool {
+        let max_skew = self.config.arbitrage.max_slot_skew;
+        max_skew > 0 && stamps.slot_skew.is_some_and(|skew| skew > max_skew)
+    }
+
+    /// Проверка, что цены обеих ног не старше arbitrage.max_price_age_ms
+    ///
+    /// После задержек RPC или в очереди на исполнение цены успевают устареть:
+    /// сделка по ним исполнилась бы по другому курсу. Возможности без времени
+    /// цен (маршруты, записи старого формата из шины) не проверяются.
+    fn ensure_prices_fresh(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let max_age_ms = self.config.arbitrage.max_price_age_ms;
+        if max_age_ms == 0 {
+            return Ok(());
+        }
+        let Some(age) = opportunity.price_stamps.max_age(self.clock.now()) else {
+            return Ok(());
+        };
+        if age > Duration::from_millis(max_age_ms) {
+            anyhow::bail!(
+                "Цена {}/{} устарела: получена {} мс назад, допустимо {} мс (arbitrage.max_price_age_ms)",
+                opportunity.base_token, opportunity.quote_token, age.as_millis(), max_age_ms
+            );
+        }
+        Ok(())
+    }
+
+    /// Исполнение сделки и запись её в журнал (без учёта результата в счётчиках)
+    async fn run_execution(&self, opportunity: &ArbitrageOpportunity) -> Result<(String, String)> {
         let simulation_mode = self.config.safety.simulation_mode;
+        let quote_started = Instant::now();
 
         log::info!(
             "Выполнение арбитража: {} -> {} (прибыль: {:.2}%, после комиссий: {:.2}%)",
@@ -170,47 +1122,113 @@ impl ArbitrageEngine {
         let sell_dex = self.dex_manager.get_dex(&opportunity.to_dex)
             .ok_or_else(|| anyhow::anyhow!("DEX не найден: {}", opportunity.to_dex))?;
 
-        // Получение актуального slippage из пулов
-        let actual_slippage = self.get_actual_slippage(
-            buy_dex,
-            sell_dex,
-            &opportunity.base_token,
-            &opportunity.quote_token,
-            opportunity.trade_amount,
-        ).await.unwrap_or_else(|e| {
-            log::warn!("Не удалось получить актуальный slippage, используем значение из конфига: {}", e);
-            Decimal::from_str(&format!("{:.10}", self.config.arbitrage.slippage_tolerance))
-                .unwrap_or(Decimal::from_str("1.0").unwrap()) // 1% по умолчанию
-        });
+        // Допустимое проскальзывание для каждой ноги
+        let slippage = self.configured_slippage(buy_dex, sell_dex);
+        log::debug!("Проскальзывание: покупка {}%, продажа {}%", slippage.buy, slippage.sell);
 
-        // Расчёт минимального выхода с учётом актуального slippage
-        let slippage_multiplier = Decimal::from(1) - (actual_slippage / Decimal::from(100));
-        let min_output = opportunity.trade_amount * opportunity.sell_price * slippage_multiplier;
+        // Минимальный выход каждой ноги: котировка пулов для точного объёма за вычетом проскальзывания
+        let min_output = match self.quote_legs(opportunity).await {
+            Ok(quote) => {
+                log::debug!(
+                    "Котировка: покупка {} {}, продажа {} {}",
+                    quote.buy, opportunity.base_token, quote.sell, opportunity.quote_token
+                );
+                quote.min_outputs(slippage)
+            }
+            Err(e) => {
+                log::warn!("Не удалось получить котировку ног, минимальный выход по ценам возможности: {:#}", e);
+                leg_min_outputs(opportunity, slippage)
+            }
+        };
+        log::debug!(
+            "Минимальный выход: покупка {} {}, продажа {} {}",
+            min_output.buy, opportunity.base_token, min_output.sell, opportunity.quote_token
+        );
 
         // Таймаут для транзакций
         let tx_timeout = Duration::from_secs(self.config.arbitrage.transaction_timeout_sec);
+        profile::record(Stage::Quote, None, quote_started.elapsed());
+
+        // Ход исполнения публикуется в ленту WebSocket (quoted → built → sent → confirmed/failed)
+        let tracker = ExecutionTracker::new(self.monitor.receipts(), opportunity, simulation_mode);
+        tracker.emit(ExecutionStage::Quoted, None, None, None);
+
+        // Условие перехода к продаже действует только для двухэтапного исполнения
+        let flash_loan = self.flash_loan_reserve(buy_dex, sell_dex, &opportunity.quote_token);
+        let atomic = self.can_execute_atomically(buy_dex, sell_dex, opportunity);
+        let mut leg_gate = None;
+        let mut hold = None;
+
+        let result = receipt::scope(tracker.clone(), async {
+            // На флеш-займе объём может превышать баланс: исполнение только одной транзакцией
+            if let Some(reserve) = flash_loan {
+                return self.execute_flash_loan_arbitrage(
+                    buy_dex,
+                    sell_dex,
+                    opportunity,
+                    reserve,
+                    min_output,
+                    simulation_mode,
+                    tx_timeout,
+                ).await;
+            }
+
+            // Атомарное выполнение, если оба DEX собирают инструкции свопа
+            if atomic {
+                if let Some(signatures) = self.execute_atomic_arbitrage(
+                    buy_dex,
+                    sell_dex,
+                    opportunity,
+                    min_output,
+                    simulation_mode,
+                    tx_timeout,
+                ).await? {
+                    return Ok(signatures);
+                }
+            }
 
-        // Попытка атомарного выполнения (если возможно)
-        let result = if self.can_execute_atomically(buy_dex, sell_dex) {
-            self.execute_atomic_arbitrage(
-                buy_dex,
-                sell_dex,
-                &opportunity,
-                min_output,
-                simulation_mode,
-                tx_timeout,
-            ).await
-        } else {
             // Выполнение в два этапа
-            self.execute_two_step_arbitrage(
+            leg_gate = Some(self.config.arbitrage.leg_gate);
+            let (buy_signature, sell_signature, held) = self.execute_two_step_arbitrage(
                 buy_dex,
                 sell_dex,
-                &opportunity,
+                opportunity,
                 min_output,
                 simulation_mode,
                 tx_timeout,
-            ).await
+            ).await?;
+            hold = Some(held);
+            Ok((buy_signature, sell_signature))
+        }).await;
+        if let Err(ref e) = result {
+            tracker.emit(ExecutionStage::Failed, None, None, Some(failure_reason(e)));
+        }
+
+        let profit = self.profit_breakdown(opportunity).await;
+        let simulation_logs = tracker.simulation_logs();
+        let mut record = match &result {
+            Ok((_, sell_sig)) => {
+                let status = if simulation_mode { TradeStatus::Simulated } else { TradeStatus::Success };
+                trade_record(opportunity, profit, status, leg_gate, Some(sell_sig.clone()), None, simulation_logs)
+            }
+            Err(e) => trade_record(opportunity, profit, TradeStatus::Failed, leg_gate, None, Some(failure_reason(e)), simulation_logs),
         };
+        record.capital_sol = self.capital_sol(opportunity).await;
+        record.hold_ms = hold.map(|held: Duration| held.as_millis() as u64);
+        self.monitor.record_trade(record).await;
+        result
+    }
+
+    /// Учёт результата сделки: статистика маршрута, счётчик неудач, остановка исполнения
+    fn settle(&mut self, opportunity: &ArbitrageOpportunity, result: Result<(String, String)>) -> Result<()> {
+        let simulation_mode = self.config.safety.simulation_mode;
+        self.fill_history.record(&opportunity.from_dex, &opportunity.to_dex, result.is_ok());
+        // Отменённая до отправки сделка ничего не говорит об исполнении на DEX
+        if !result.as_ref().is_err_and(|e| classify_failure(e) == FailureKind::Aborted) {
+            for venue in [&opportunity.from_dex, &opportunity.to_dex] {
+                self.venue_history.record_leg(venue, result.is_ok());
+            }
+        }
 
         match result {
             Ok((buy_sig, sell_sig)) => {
@@ -219,6 +1237,7 @@ impl ArbitrageEngine {
                 
                 // Обновление статистики при успехе
                 self.consecutive_failures = 0;
+                self.circuit_breaker.reset();
                 
                 if simulation_mode {
                     log::info!("✅ Арбитраж выполнен (симуляция): прибыль {:.2}% (после комиссий: {:.2}%)", 
@@ -239,13 +1258,50 @@ impl ArbitrageEngine {
                 Ok(())
             }
             Err(e) => {
-                // Увеличение счётчика неудач
+                let kind = classify_failure(&e);
+                match kind {
+                    FailureKind::Fatal => {
+                        // Нехватка баланса или ошибка конфигурации: повтор не поможет
+                        self.halt(failure_reason(&e));
+                        return Err(e.context("Критическая ошибка, остановка выполнения"));
+                    }
+                    FailureKind::Transient => {
+                        // Сбой RPC или таймаут не говорит о качестве стратегии — к лимиту не относится
+                        log::warn!("Временный сбой при выполнении арбитража: {}", failure_reason(&e));
+                        if self.circuit_breaker.record_failure(self.clock.now()) {
+                            self.monitor.log_warning(&format!(
+                                "Сбои RPC подряд: исполнение приостановлено на {} с",
+                                self.config.safety.circuit_breaker_cooldown_sec
+                            ));
+                            if let Some(until) = self.circuit_breaker.open_until() {
+                                self.monitor.publish_safety(SafetyEvent::CircuitBreakerTripped {
+                                    until,
+                                    reason: failure_reason(&e),
+                                });
+                            }
+                        }
+                        return Err(e);
+                    }
+                    FailureKind::Aborted => {
+                        // Транзакция не отправлялась: счётчики неудач и автомат не меняются
+                        log::warn!("Сделка отменена до отправки: {}", failure_reason(&e));
+                        return Err(e);
+                    }
+                    FailureKind::Execution => {}
+                }
+                self.circuit_breaker.reset();
+
+                // Увеличение счётчика неудач исполнения
                 self.consecutive_failures += 1;
                 log::error!("Ошибка выполнения арбитража (неудач подряд: {}): {}", 
-                    self.consecutive_failures, e);
+                    self.consecutive_failures, failure_reason(&e));
                 
                 // Проверка лимита неудач
                 if self.consecutive_failures >= self.config.safety.max_consecutive_failures {
+                    self.halt(format!(
+                        "достигнут лимит последовательных неудач ({})",
+                        self.config.safety.max_consecutive_failures
+                    ));
                     anyhow::bail!(
                         "Достигнут лимит последовательных неудач ({}), остановка выполнения",
                         self.config.safety.max_consecutive_failures
@@ -258,131 +1314,575 @@ impl ArbitrageEngine {
     }
 
     /// Расчёт оптимального объёма сделки с учётом ликвидности и комиссий
+    ///
+    /// Объём максимизирует прибыль после комиссий пулов (и флеш-займа) и влияния
+    /// на цену по кривым обоих пулов (см. `trade_size::optimal_input`). Пул без
+    /// известных резервов моделируется по ликвидности DEX (см. `leg_curve`),
+    /// а объём на нём ограничивается этой ликвидностью.
+    ///
+    /// Возвращает объём и влияние на цену по ногам; None, если сделка неприбыльна
+    /// при любом объёме или объём меньше минимального для одного из DEX (ордер
+    /// будет отклонён или своп даст пыль).
     async fn calculate_optimal_trade_amount(
         &self,
-        _buy_price: Decimal,
-        _sell_price: Decimal,
+        buy_price: Decimal,
+        sell_price: Decimal,
         base_token: &str,
         quote_token: &str,
         buy_dex: &str,
         sell_dex: &str,
-    ) -> Result<Decimal> {
-        let max_amount = Decimal::from_str(&format!("{:.10}", self.config.arbitrage.max_trade_amount_sol))
-            .unwrap_or(Decimal::ZERO);
+    ) -> Result<Option<(Decimal, PriceImpact)>> {
+        let mut max_amount = self.max_trade_amount(buy_dex, sell_dex, quote_token, buy_price);
 
-        // Получение доступной ликвидности на DEX
-        let buy_liquidity = self.get_dex_liquidity(buy_dex, base_token, quote_token).await
-            .unwrap_or(max_amount * Decimal::from(10)); // Если не удалось получить, предполагаем достаточную ликвидность
-        let sell_liquidity = self.get_dex_liquidity(sell_dex, base_token, quote_token).await
-            .unwrap_or(max_amount * Decimal::from(10));
+        let buy_leg = self.leg_curve(buy_dex, base_token, quote_token, buy_price, true).await;
+        let sell_leg = self.leg_curve(sell_dex, base_token, quote_token, sell_price, false).await;
+        for liquidity in [buy_leg.liquidity, sell_leg.liquidity].into_iter().flatten() {
+            max_amount = max_amount.min(liquidity);
+        }
+        if max_amount <= Decimal::ZERO || buy_price <= Decimal::ZERO {
+            log::debug!("{} -> {}: недостаточная ликвидность для арбитража", buy_dex, sell_dex);
+            return Ok(None);
+        }
 
-        // Используем минимальную ликвидность из двух DEX
-        let available_liquidity = buy_liquidity.min(sell_liquidity);
+        let cost_multiplier = Decimal::ONE
+            + self.flash_loan_fee_percent(buy_dex, sell_dex, quote_token) / Decimal::from(100);
+        let optimal_input = trade_size::optimal_input(&buy_leg.curve, &sell_leg.curve, max_amount * buy_price, cost_multiplier);
+        let optimal_amount = optimal_input / buy_price;
 
-        // Расчёт оптимального объёма: используем 10% от доступной ликвидности или max_amount, что меньше
-        // Это помогает избежать большого price impact
-        let optimal_from_liquidity = available_liquidity * Decimal::from_str("0.1")?;
-        
-        // Выбираем минимум из: max_amount, optimal_from_liquidity, и доступной ликвидности
-        let optimal_amount = max_amount.min(optimal_from_liquidity).min(available_liquidity);
+        log::debug!(
+            "Расчёт объёма: max={}, buy={:?}, sell={:?}, optimal={}",
+            max_amount, buy_leg.curve, sell_leg.curve, optimal_amount
+        );
 
-        // Проверка, что объём больше нуля
         if optimal_amount <= Decimal::ZERO {
-            anyhow::bail!("Недостаточная ликвидность для выполнения арбитража");
+            log::debug!("{} -> {}: сделка неприбыльна при любом объёме — возможность пропущена", buy_dex, sell_dex);
+            return Ok(None);
         }
 
-        log::debug!(
-            "Расчёт объёма: max={}, buy_liq={}, sell_liq={}, optimal={}",
-            max_amount, buy_liquidity, sell_liquidity, optimal_amount
-        );
+        let min_amount = self.config.dex.min_trade_amount(buy_dex)
+            .max(self.config.dex.min_trade_amount(sell_dex));
+        if optimal_amount < min_amount {
+            log::debug!(
+                "{} -> {}: объём {} меньше минимального {} — возможность пропущена",
+                buy_dex, sell_dex, optimal_amount, min_amount
+            );
+            return Ok(None);
+        }
+
+        Ok(Some((optimal_amount, leg_price_impact(&buy_leg, &sell_leg, optimal_amount, buy_price))))
+    }
 
-        Ok(optimal_amount)
-    }
-
-    /// Получение комиссии DEX в процентах
-    async fn get_dex_fee(&self, dex_name: &str) -> Result<Decimal> {
-        // В реальной реализации нужно получать комиссию из DEX API или конфигурации
-        // Для разных DEX комиссии разные:
-        // Raydium: обычно 0.25%
-        // Orca: обычно 0.3%
-        // Serum: обычно 0.04%
-        let fee = match dex_name {
-            "raydium" => Decimal::from_str("0.25")?,
-            "orca" => Decimal::from_str("0.3")?,
-            "serum" => Decimal::from_str("0.04")?,
-            _ => Decimal::from_str("0.25")?, // По умолчанию
+    /// Кривая обмена ноги и, для пула без известных резервов, ликвидность DEX
+    ///
+    /// DEX без кривой пула (книга ордеров, CLMM) с известной ликвидностью
+    /// считается пулом x*y=k с резервами по этой ликвидности и цене DEX: цены
+    /// уровней книги неизвестны, а так объём, съедающий заметную долю глубины,
+    /// даёт влияние на цену.
+    async fn leg_curve(&self, dex_name: &str, base_token: &str, quote_token: &str, price: Decimal, buy: bool) -> LegCurve {
+        let curve = self.pool_curve(dex_name, base_token, quote_token, price, buy).await;
+        let SwapCurve::Fixed { fee_percent, .. } = curve else {
+            return LegCurve { curve, liquidity: None };
         };
-        Ok(fee)
+        match self.get_dex_liquidity(dex_name, base_token, quote_token, price, buy).await {
+            Ok(liquidity) if liquidity > Decimal::ZERO => {
+                let (reserve_in, reserve_out) = if buy { (liquidity * price, liquidity) } else { (liquidity, liquidity * price) };
+                LegCurve {
+                    curve: SwapCurve::ConstantProduct { reserve_in, reserve_out, fee_percent },
+                    liquidity: Some(liquidity),
+                }
+            }
+            Ok(liquidity) => LegCurve { curve, liquidity: Some(liquidity) },
+            // Если ликвидность не удалось получить, предполагаем достаточную
+            Err(e) => {
+                log::debug!("{}: ликвидность {}/{} неизвестна: {:#}", dex_name, base_token, quote_token, e);
+                LegCurve { curve, liquidity: None }
+            }
+        }
+    }
+
+    /// Кривая обмена пула пары на DEX в направлении сделки
+    ///
+    /// Покупка (buy = true) меняет quote на base, продажа — base на quote.
+    /// Пул x*y=k — если DEX сообщает резервы и decimals обоих токенов известны,
+    /// иначе постоянный курс по цене DEX.
+    async fn pool_curve(&self, dex_name: &str, base_token: &str, quote_token: &str, price: Decimal, buy: bool) -> SwapCurve {
+        let fee_percent = self.get_dex_fee(dex_name, base_token, quote_token).await;
+        let tokens = self.dex_manager.tokens();
+        let decimals = |symbol: &str| tokens.get(symbol).and_then(|token| token.decimals);
+        let reserves = self.dex_manager.get_dex(dex_name)
+            .and_then(|dex| dex.pool_reserves(base_token, quote_token))
+            .zip(decimals(base_token).zip(decimals(quote_token)))
+            .and_then(|((base, quote), (base_decimals, quote_decimals))| {
+                let base = units::from_base_units(base, base_decimals).ok()?;
+                let quote = units::from_base_units(quote, quote_decimals).ok()?;
+                (base > Decimal::ZERO && quote > Decimal::ZERO).then_some((base, quote))
+            });
+        match (reserves, buy) {
+            (Some((base, quote)), true) => SwapCurve::ConstantProduct { reserve_in: quote, reserve_out: base, fee_percent },
+            (Some((base, quote)), false) => SwapCurve::ConstantProduct { reserve_in: base, reserve_out: quote, fee_percent },
+            (None, true) => SwapCurve::Fixed { rate: Decimal::ONE / price, fee_percent },
+            (None, false) => SwapCurve::Fixed { rate: price, fee_percent },
+        }
+    }
+
+    /// Максимальный объём сделки в base токене
+    ///
+    /// Сделка на флеш-займе ограничена не балансом кошелька (max_trade_amount_sol),
+    /// а максимальным заёмом резерва, если он больше.
+    fn max_trade_amount(&self, buy_dex: &str, sell_dex: &str, quote_token: &str, buy_price: Decimal) -> Decimal {
+        let max_amount = Decimal::from_str(&format!("{:.10}", self.config.arbitrage.max_trade_amount_sol))
+            .unwrap_or(Decimal::ZERO);
+        match self.flash_loan_reserve_for(buy_dex, sell_dex, quote_token) {
+            Some(reserve) if buy_price > Decimal::ZERO => max_amount.max(reserve.max_borrow() / buy_price),
+            _ => max_amount,
+        }
     }
 
-    /// Получение доступной ликвидности на DEX
-    async fn get_dex_liquidity(&self, dex_name: &str, base_token: &str, quote_token: &str) -> Result<Decimal> {
-        // В реальной реализации нужно получать ликвидность из пула
-        // Для упрощения возвращаем большое значение
-        // TODO: Реализовать получение реальной ликвидности из пулов
+    /// Резерв флеш-займа для сделки: флеш-займы включены, для quote токена
+    /// настроен резерв, и оба DEX собирают инструкции свопа для одной транзакции
+    fn flash_loan_reserve(
+        &self,
+        buy_dex: &dyn DexInterface,
+        sell_dex: &dyn DexInterface,
+        quote_token: &str,
+    ) -> Option<&FlashLoanReserveConfig> {
+        if !buy_dex.supports_atomic_swap() || !sell_dex.supports_atomic_swap() {
+            return None;
+        }
+        self.config.flash_loan.reserve(quote_token)
+    }
+
+    /// Резерв флеш-займа для сделки по именам DEX
+    fn flash_loan_reserve_for(&self, buy_dex: &str, sell_dex: &str, quote_token: &str) -> Option<&FlashLoanReserveConfig> {
+        let buy = self.dex_manager.get_dex(buy_dex)?;
+        let sell = self.dex_manager.get_dex(sell_dex)?;
+        self.flash_loan_reserve(buy, sell, quote_token)
+    }
+
+    /// Комиссия флеш-займа в процентах (0, если сделка исполняется на свои средства)
+    fn flash_loan_fee_percent(&self, buy_dex: &str, sell_dex: &str, quote_token: &str) -> Decimal {
+        self.flash_loan_reserve_for(buy_dex, sell_dex, quote_token)
+            .map(FlashLoanReserveConfig::fee_percent)
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Комиссия пула пары на DEX в процентах (типичная для DEX, если DEX её не сообщает)
+    async fn get_dex_fee(&self, dex_name: &str, base_token: &str, quote_token: &str) -> Decimal {
+        let pool_fee = match self.dex_manager.get_dex(dex_name) {
+            Some(dex) => dex.pool_fee_percent(base_token, quote_token).await,
+            None => None,
+        };
+        pool_fee.unwrap_or_else(|| default_fee_percent(dex_name))
+    }
+
+    /// Доступная ликвидность на DEX в base токене для ноги сделки
+    ///
+    /// Покупка (buy = true) ограничена base в пуле или asks книги, продажа —
+    /// quote в пуле или bids, пересчитанным в base по цене DEX.
+    async fn get_dex_liquidity(&self, dex_name: &str, base_token: &str, quote_token: &str, price: Decimal, buy: bool) -> Result<Decimal> {
         let dex = self.dex_manager.get_dex(dex_name)
             .ok_or_else(|| anyhow::anyhow!("DEX не найден: {}", dex_name))?;
-        
-        // Пытаемся получить цену, чтобы проверить доступность пула
-        match dex.get_price(base_token, quote_token).await {
-            Ok(_) => {
-                // Если цена получена, предполагаем достаточную ликвидность
-                Ok(Decimal::from_str("1000")?) // 1000 SOL по умолчанию
-            }
-            Err(_) => {
-                // Если не удалось получить цену, ликвидность нулевая
-                Ok(Decimal::ZERO)
-            }
+        let (base, quote) = dex.get_liquidity(base_token, quote_token).await?;
+
+        let tokens = self.dex_manager.tokens();
+        let decimals = |symbol: &str| tokens.get(symbol)
+            .and_then(|token| token.decimals)
+            .with_context(|| format!("decimals токена {} неизвестны", symbol));
+        if buy {
+            return units::from_base_units(base, decimals(base_token)?);
+        }
+        if price <= Decimal::ZERO {
+            return Ok(Decimal::ZERO);
         }
+        Ok(units::from_base_units(quote, decimals(quote_token)?)? / price)
+    }
+
+    /// Котировка ног для точного объёма возможности по кривым пулов
+    ///
+    /// Покупка тратит `buy_leg_input`, продажа — trade_amount; выход считается
+    /// по резервам пула (или ликвидности DEX) с комиссией, а у DEX без
+    /// известных резервов — по цене возможности с комиссией.
+    async fn quote_legs(&self, opportunity: &ArbitrageOpportunity) -> Result<LegQuote> {
+        let (base_token, quote_token) = (opportunity.base_token.as_str(), opportunity.quote_token.as_str());
+        let buy = self.leg_curve(&opportunity.from_dex, base_token, quote_token, opportunity.buy_price, true).await;
+        let sell = self.leg_curve(&opportunity.to_dex, base_token, quote_token, opportunity.sell_price, false).await;
+        let quote = LegQuote {
+            buy: buy.curve.output(buy_leg_input(opportunity)),
+            sell: sell.curve.output(opportunity.trade_amount),
+        };
+        if quote.buy <= Decimal::ZERO || quote.sell <= Decimal::ZERO {
+            anyhow::bail!(
+                "{} -> {}: нулевой выход котировки (покупка {} {}, продажа {} {})",
+                opportunity.from_dex, opportunity.to_dex, quote.buy, base_token, quote.sell, quote_token
+            );
+        }
+        Ok(quote)
+    }
+
+    /// Проскальзывание ног из конфигурации (с учётом типа DEX)
+    fn configured_slippage(&self, buy_dex: &dyn DexInterface, sell_dex: &dyn DexInterface) -> LegSlippage {
+        LegSlippage {
+            buy: self.config.arbitrage.buy_slippage(venue_type(buy_dex.name())),
+            sell: self.config.arbitrage.sell_slippage(venue_type(sell_dex.name())),
+        }
+    }
+
+    /// Прибыль возможности в котируемом токене, SOL и USD по текущим курсам
+    async fn profit_breakdown(&self, opportunity: &ArbitrageOpportunity) -> ProfitBreakdown {
+        let fiat = self.monitor.fiat();
+        let quote_usd = fiat.usd_rate(&opportunity.quote_token).await.map(|rate| rate.price);
+        let sol_usd = fiat.usd_rate("SOL").await.map(|rate| rate.price);
+        profit_breakdown(opportunity, quote_usd, sol_usd, &self.rounding())
+    }
+
+    /// Капитал сделки в SOL по курсам из кеша
+    async fn capital_sol(&self, opportunity: &ArbitrageOpportunity) -> Option<Decimal> {
+        let fiat = self.monitor.fiat();
+        let quote_usd = fiat.usd_rate(&opportunity.quote_token).await.map(|rate| rate.price);
+        let sol_usd = fiat.usd_rate("SOL").await.map(|rate| rate.price);
+        capital_sol(opportunity, quote_usd, sol_usd, &self.rounding())
+    }
+
+    /// Политика округления расчётных сумм из конфигурации
+    fn rounding(&self) -> RoundingPolicy {
+        RoundingPolicy::new(&self.config.arbitrage.rounding)
     }
 
-    /// Получение актуального slippage из пулов
-    async fn get_actual_slippage(
+    /// Проверка абсолютного порога прибыли (arbitrage.min_profit_absolute)
+    ///
+    /// Прибыль после комиссий DEX уменьшается на сетевую комиссию: одна
+    /// транзакция при атомарном исполнении, две — при исполнении по ногам.
+    async fn min_profit_absolute_check(
         &self,
-        _buy_dex: &dyn DexInterface,
-        _sell_dex: &dyn DexInterface,
-        _base_token: &str,
-        _quote_token: &str,
-        _trade_amount: Decimal,
-    ) -> Result<Decimal> {
-        // В реальной реализации нужно симулировать своп и получить реальный slippage
-        // Для упрощения используем значение из конфигурации
-        // TODO: Реализовать симуляцию свопа для получения актуального slippage
-        Ok(Decimal::from_str(&format!("{:.10}", self.config.arbitrage.slippage_tolerance))
-            .unwrap_or(Decimal::from_str("1.0")?))
+        opportunity: &ArbitrageOpportunity,
+        buy_dex: &dyn DexInterface,
+        sell_dex: &dyn DexInterface,
+    ) -> RiskCheck {
+        let threshold = &self.config.arbitrage.min_profit_absolute;
+        let rounding = self.rounding();
+        let thresholds = [
+            (opportunity.quote_token.as_str(), threshold.quote, rounding.quote_decimal_places),
+            ("USD", threshold.usd, rounding.usd_decimal_places),
+        ];
+        if thresholds.iter().all(|(_, min, _)| min.is_none()) {
+            return RiskCheck::new("min_profit_absolute", true, "порог не задан".to_string());
+        }
+
+        let single_transaction = self.can_execute_atomically(buy_dex, sell_dex, opportunity)
+            || self.flash_loan_reserve(buy_dex, sell_dex, &opportunity.quote_token).is_some();
+        let transactions = if single_transaction { 1 } else { 2 };
+        let fee = network_fee_sol(transactions);
+        let fiat = self.monitor.fiat();
+        let quote_usd = fiat.usd_rate(&opportunity.quote_token).await.map(|rate| rate.price);
+        let sol_usd = fiat.usd_rate("SOL").await.map(|rate| rate.price);
+        let net = net_profit(opportunity, fee, quote_usd, sol_usd);
+
+        let mut passed = true;
+        let mut details = Vec::new();
+        for ((unit, min, places), value) in thresholds.into_iter().zip([net.quote, net.usd]) {
+            let Some(min) = min.and_then(|min| Decimal::from_str(&format!("{:.10}", min)).ok()) else {
+                continue;
+            };
+            match value {
+                Some(value) => {
+                    passed &= value >= min;
+                    details.push(format!("{} {} (порог {})", rounding.round_output(value, places), unit, min));
+                }
+                None => {
+                    passed = false;
+                    details.push(format!("нет курса для пересчёта в {}", unit));
+                }
+            }
+        }
+        RiskCheck::new(
+            "min_profit_absolute",
+            passed,
+            format!("прибыль за вычетом сетевой комиссии {} SOL: {}", fee, details.join(", ")),
+        )
     }
 
     /// Проверка возможности атомарного выполнения
-    fn can_execute_atomically(&self, _buy_dex: &dyn DexInterface, _sell_dex: &dyn DexInterface) -> bool {
-        // Атомарное выполнение возможно только если оба свопа можно объединить в одну транзакцию
-        // На Solana это возможно, если оба DEX поддерживают это
-        // Для упрощения возвращаем false, так как требуется дополнительная реализация
-        // TODO: Реализовать проверку поддержки атомарных транзакций
-        false
+    ///
+    /// Оба DEX должны собирать инструкции свопа, а quote токен — быть SPL токеном
+    /// на ATA: проверка баланса не работает с нативным SOL, временный ATA WSOL
+    /// которого закрывается после свопа.
+    fn can_execute_atomically(
+        &self,
+        buy_dex: &dyn DexInterface,
+        sell_dex: &dyn DexInterface,
+        opportunity: &ArbitrageOpportunity,
+    ) -> bool {
+        self.config.arbitrage.atomic_execution
+            && buy_dex.supports_atomic_swap()
+            && sell_dex.supports_atomic_swap()
+            && !is_native_sol(&opportunity.quote_token)
+    }
+
+    /// DEX возможности (None — один из них не подключён)
+    fn opportunity_dexes(&self, opportunity: &ArbitrageOpportunity) -> Option<(&dyn DexInterface, &dyn DexInterface)> {
+        Some((self.dex_manager.get_dex(&opportunity.from_dex)?, self.dex_manager.get_dex(&opportunity.to_dex)?))
+    }
+
+    /// Исполнялась бы сделка одной транзакцией (на флеш-займе или атомарно)
+    pub fn executes_atomically(&self, opportunity: &ArbitrageOpportunity) -> bool {
+        self.opportunity_dexes(opportunity).is_some_and(|(buy_dex, sell_dex)| {
+            self.flash_loan_reserve(buy_dex, sell_dex, &opportunity.quote_token).is_some()
+                || self.can_execute_atomically(buy_dex, sell_dex, opportunity)
+        })
+    }
+
+    /// Транзакция сделки так, как её собрало бы исполнение (для `run --once`)
+    ///
+    /// `balance_before` — баланс ATA quote токена, от которого считается проверка.
+    pub async fn build_atomic_transaction(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        min_output: LegMinOutput,
+        balance_before: u64,
+        wallet: &Wallet,
+    ) -> Result<AtomicTrade> {
+        if !self.executes_atomically(opportunity) {
+            anyhow::bail!("сделка {} -> {} исполняется в два этапа", opportunity.from_dex, opportunity.to_dex);
+        }
+        let (buy_dex, sell_dex) = self.opportunity_dexes(opportunity).context("DEX возможности не найден")?;
+        let flash_loan = self.flash_loan_reserve(buy_dex, sell_dex, &opportunity.quote_token);
+        self.build_atomic_trade(buy_dex, sell_dex, opportunity, min_output, flash_loan, balance_before, wallet).await
     }
 
     /// Выполнение атомарного арбитража (покупка и продажа в одной транзакции)
+    ///
+    /// Продаётся гарантированный выход покупки (`atomic_sell_leg`), так что запас
+    /// base токена кошелька не расходуется. Последней инструкцией идёт проверка
+    /// баланса quote токена: если сделка дала меньше минимального выхода продажи
+    /// за вычетом входа покупки (но не меньше нуля), транзакция откатывается
+    /// целиком и риска одной исполненной ноги нет.
+    /// Возвращает None, если транзакция не помещается в пакет — ничего не
+    /// отправлено, сделка исполняется в два этапа.
     async fn execute_atomic_arbitrage(
         &self,
         buy_dex: &dyn DexInterface,
         sell_dex: &dyn DexInterface,
         opportunity: &ArbitrageOpportunity,
-        min_output: Decimal,
+        min_output: LegMinOutput,
+        simulation_mode: bool,
+        tx_timeout: Duration,
+    ) -> Result<Option<(String, String)>> {
+        log::info!("Атомарное выполнение: {} -> {} одной транзакцией", buy_dex.name(), sell_dex.name());
+        if simulation_mode {
+            log::info!("Атомарный арбитраж: симуляция (реальная транзакция не отправляется)");
+            let signature = "simulated_signature_atomic".to_string();
+            self.track_leg(None, &signature, simulation_mode);
+            return Ok(Some((signature.clone(), signature)));
+        }
+
+        // Ключ не меняется до отправки транзакции
+        let wallet = self.wallet.lease().await;
+        let balance_before = self.quote_balance(opportunity, &wallet).await?;
+        let trade = self.build_atomic_trade(buy_dex, sell_dex, opportunity, min_output, None, balance_before, &wallet).await?;
+
+        // Очереди обоих DEX занимаются в одном порядке: встречные сделки не ждут друг друга
+        let mut venues = [buy_dex.name(), sell_dex.name()];
+        venues.sort_unstable();
+        let mut permits = Vec::with_capacity(venues.len());
+        for venue in venues {
+            permits.push(
+                timeout(tx_timeout, self.venue_limiter.acquire(venue))
+                    .await
+                    .context("Таймаут ожидания очереди исполнения")??,
+            );
+        }
+
+        let signature = timeout(tx_timeout, self.dex_manager.execute_atomic(trade.swaps, Some(&trade.guard), &wallet))
+            .await
+            .context("Таймаут при выполнении атомарной транзакции")?
+            .context("Ошибка выполнения атомарной транзакции")?;
+        let Some(signature) = signature else {
+            return Ok(None);
+        };
+        log::info!("Атомарная транзакция отправлена: {}", signature);
+        self.track_leg(None, &signature, simulation_mode);
+        Ok(Some((signature.clone(), signature)))
+    }
+
+    /// Выполнение арбитража на флеш-займе quote токена
+    ///
+    /// В одной транзакции: заём входа покупки, покупка, продажа гарантированного
+    /// выхода покупки (`atomic_sell_leg`), возврат займа с комиссией и проверка
+    /// прироста баланса quote токена. Если продажа не
+    /// покрыла возврат, транзакция откатывается целиком — своих средств на
+    /// покупку не нужно. Транзакция, не помещающаяся в пакет, не отправляется:
+    /// исполнить такой объём по ногам на свои средства нельзя.
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_flash_loan_arbitrage(
+        &self,
+        buy_dex: &dyn DexInterface,
+        sell_dex: &dyn DexInterface,
+        opportunity: &ArbitrageOpportunity,
+        reserve: &FlashLoanReserveConfig,
+        min_output: LegMinOutput,
         simulation_mode: bool,
         tx_timeout: Duration,
     ) -> Result<(String, String)> {
-        // Атомарное выполнение требует объединения инструкций от обоих DEX в одну транзакцию
-        // Это сложная реализация, требующая доступа к внутренним методам DEX
-        // Для упрощения выполняем как две отдельные транзакции, но с таймаутом
-        log::info!("Атомарное выполнение не реализовано, используем двухэтапное");
-        self.execute_two_step_arbitrage(
-            buy_dex,
-            sell_dex,
-            opportunity,
-            min_output,
-            simulation_mode,
-            tx_timeout,
-        ).await
+        let buy_input = buy_leg_input(opportunity);
+        log::info!(
+            "Флеш-займ {} {}: {} -> {} одной транзакцией",
+            buy_input, opportunity.quote_token, buy_dex.name(), sell_dex.name()
+        );
+        if simulation_mode {
+            log::info!("Арбитраж на флеш-займе: симуляция (реальная транзакция не отправляется)");
+            let signature = "simulated_signature_flash_loan".to_string();
+            self.track_leg(None, &signature, simulation_mode);
+            return Ok((signature.clone(), signature));
+        }
+
+        // Ключ не меняется до отправки транзакции
+        let wallet = self.wallet.lease().await;
+        let balance_before = self.quote_balance(opportunity, &wallet).await?;
+        let trade = self.build_atomic_trade(buy_dex, sell_dex, opportunity, min_output, Some(reserve), balance_before, &wallet).await?;
+
+        // Очереди обоих DEX занимаются в одном порядке: встречные сделки не ждут друг друга
+        let mut venues = [buy_dex.name(), sell_dex.name()];
+        venues.sort_unstable();
+        let mut permits = Vec::with_capacity(venues.len());
+        for venue in venues {
+            permits.push(
+                timeout(tx_timeout, self.venue_limiter.acquire(venue))
+                    .await
+                    .context("Таймаут ожидания очереди исполнения")??,
+            );
+        }
+
+        let signature = timeout(tx_timeout, self.dex_manager.execute_atomic(trade.swaps, Some(&trade.guard), &wallet))
+            .await
+            .context("Таймаут при выполнении транзакции с флеш-займом")?
+            .context("Ошибка выполнения транзакции с флеш-займом")?;
+        let Some(signature) = signature else {
+            return Err(anyhow::Error::new(FailureKind::Aborted)
+                .context("Транзакция с флеш-займом не помещается в пакет, сделка отменена"));
+        };
+        log::info!("Транзакция с флеш-займом отправлена: {}", signature);
+        self.track_leg(None, &signature, simulation_mode);
+        Ok((signature.clone(), signature))
+    }
+
+    /// Баланс quote токена кошелька перед сделкой в одной транзакции (от него считается проверка)
+    async fn quote_balance(&self, opportunity: &ArbitrageOpportunity, wallet: &Wallet) -> Result<u64> {
+        let quote_mint = self.dex_manager.tokens().mint(&opportunity.quote_token)?;
+        let rpc_client = Arc::new(create_rpc_client(&self.config.network)?);
+        fetch_token_balance(rpc_client, wallet.associated_token_address(&quote_mint))
+            .await
+            .context("Не удалось получить баланс quote токена перед сделкой")
+    }
+
+    /// Сборка сделки в одной транзакции: свопы, флеш-займ (если есть) и проверка баланса
+    ///
+    /// Продаётся гарантированный выход покупки (`atomic_sell_leg`). Без займа
+    /// проверка требует прироста не меньше минимального выхода продажи за вычетом
+    /// входа покупки (но не меньше нуля), на займе — остатка после возврата.
+    /// Общая для исполнения и `run --once`, так что симулируется та же транзакция,
+    /// что была бы отправлена.
+    #[allow(clippy::too_many_arguments)]
+    async fn build_atomic_trade(
+        &self,
+        buy_dex: &dyn DexInterface,
+        sell_dex: &dyn DexInterface,
+        opportunity: &ArbitrageOpportunity,
+        min_output: LegMinOutput,
+        flash_loan: Option<&FlashLoanReserveConfig>,
+        balance_before: u64,
+        wallet: &Wallet,
+    ) -> Result<AtomicTrade> {
+        let buy_input = buy_leg_input(opportunity);
+        let (sell_amount, sell_min_output) = atomic_sell_leg(opportunity, min_output);
+        let buy = buy_dex.build_swap_instructions(
+            &opportunity.quote_token,
+            &opportunity.base_token,
+            buy_input,
+            min_output.buy,
+            wallet,
+        ).await.context("Не удалось собрать инструкции покупки")?;
+        let sell = sell_dex.build_swap_instructions(
+            &opportunity.base_token,
+            &opportunity.quote_token,
+            sell_amount,
+            sell_min_output,
+            wallet,
+        ).await.context("Не удалось собрать инструкции продажи")?;
+        let leg_instructions = (buy.len(), sell.len());
+
+        let tokens = self.dex_manager.tokens();
+        let quote_mint = tokens.mint(&opportunity.quote_token)?;
+        let rpc_client = create_rpc_client(&self.config.network)?;
+        let (swaps, min_increase) = match flash_loan {
+            Some(reserve) => {
+                // Заём округляется вверх: вход покупки округляется вниз и его покрывает
+                let flash_loan = FlashLoan::new(reserve, quote_mint)?;
+                let borrow = tokens.to_base_units(&rpc_client, &quote_mint, buy_input, Rounding::Up)?;
+                let repay = flash_loan.repay_amount(borrow)?;
+                // После возврата займа на кошельке должна остаться прибыль
+                let min_sell = tokens.to_base_units(&rpc_client, &quote_mint, sell_min_output, Rounding::MIN_OUTPUT)?;
+                (flash_loan.wrap(wallet.pubkey(), borrow, vec![buy, sell]), min_sell.saturating_sub(repay))
+            }
+            None => {
+                // Проверка прироста quote токена: сделка не может быть убыточной
+                let min_increase = tokens.to_base_units(
+                    &rpc_client,
+                    &quote_mint,
+                    (sell_min_output - buy_input).max(Decimal::ZERO),
+                    Rounding::MIN_OUTPUT,
+                )?;
+                (vec![buy, sell], min_increase)
+            }
+        };
+        Ok(AtomicTrade {
+            leg_instructions,
+            swaps,
+            guard: BalanceGuard::new(wallet.pubkey(), &quote_mint, balance_before, min_increase),
+        })
+    }
+
+    /// Подтверждение отправленной ноги для ленты исполнения
+    ///
+    /// В режиме симуляции транзакции нет — этапы публикуются сразу.
+    fn track_leg(&self, leg: Option<TradeLeg>, signature: &str, simulation_mode: bool) {
+        let Some(tracker) = receipt::current() else { return };
+        if simulation_mode {
+            for stage in [ExecutionStage::Built, ExecutionStage::Sent, ExecutionStage::Confirmed] {
+                tracker.emit(stage, leg, Some(signature), None);
+            }
+        } else {
+            let confirm_timeout = Duration::from_secs(self.config.arbitrage.transaction_timeout_sec);
+            spawn_confirmation_watch(
+                tracker,
+                leg,
+                signature.to_string(),
+                &self.config.network,
+                confirm_timeout,
+                self.monitor.fees().clone(),
+            );
+        }
+    }
+
+    /// Ожидание подтверждения ноги перед следующим шагом
+    async fn confirm_leg(&self, leg: TradeLeg, signature: &str) -> Result<()> {
+        let tracker = receipt::current();
+        let confirm_timeout = Duration::from_secs(self.config.arbitrage.transaction_timeout_sec);
+        let started = Instant::now();
+        let commitment = self.config.arbitrage.leg_gate_commitment.commitment_config();
+        let result = wait_for_confirmation(&self.config.network, signature, Some(commitment), confirm_timeout).await;
+        profile::record(Stage::Confirm, tracker.as_ref().map(|t| t.venue(leg)), started.elapsed());
+        if let (Ok(()), Some(tracker)) = (&result, &tracker) {
+            self.venue_history.record_latency(tracker.venue(leg), started.elapsed());
+        }
+        self.monitor.fees().spawn_record(&self.config.network, signature.to_string());
+
+        if let Some(tracker) = tracker {
+            match result {
+                Ok(()) => tracker.emit(ExecutionStage::Confirmed, Some(leg), Some(signature), None),
+                Err(ref e) => tracker.emit(ExecutionStage::Failed, Some(leg), Some(signature), Some(format!("{:#}", e))),
+            }
+        }
+        result
     }
 
     /// Выполнение двухэтапного арбитража (покупка, затем продажа)
@@ -391,48 +1891,139 @@ impl ArbitrageEngine {
         buy_dex: &dyn DexInterface,
         sell_dex: &dyn DexInterface,
         opportunity: &ArbitrageOpportunity,
-        min_output: Decimal,
+        min_output: LegMinOutput,
         simulation_mode: bool,
         tx_timeout: Duration,
-    ) -> Result<(String, String)> {
+    ) -> Result<(String, String, Duration)> {
+        // Ключ не меняется, пока обе ноги не завершены
+        let wallet = self.wallet.lease().await;
+
+        // Баланс base токена до покупки: после подтверждения продажа размеряется по фактически полученному
+        let gate = self.config.arbitrage.leg_gate;
+        let base_mint = match gate {
+            LegGate::Confirmed if !simulation_mode => self.dex_manager.tokens().mint(&opportunity.base_token).ok(),
+            _ => None,
+        };
+        let balance_before = match base_mint {
+            Some(ref mint) => Some(
+                wallet.token_balance(&self.config.network, mint).await
+                    .context("Не удалось получить баланс перед покупкой")?,
+            ),
+            None => {
+                if gate == LegGate::Confirmed && !simulation_mode {
+                    log::warn!("Mint {} не известен: продажа без проверки полученного", opportunity.base_token);
+                }
+                None
+            }
+        };
+
         // Шаг 1: Покупка на первом DEX с таймаутом
+        let buy_permit = timeout(tx_timeout, self.venue_limiter.acquire(buy_dex.name()))
+            .await
+            .context("Таймаут ожидания очереди исполнения для покупки")??;
         let buy_future = buy_dex.execute_swap(
             simulation_mode,
             &opportunity.quote_token,
             &opportunity.base_token,
-            opportunity.trade_amount,
-            Decimal::ZERO, // Минимальный выход для покупки
-            &self.wallet,
+            buy_leg_input(opportunity),
+            min_output.buy,
+            &wallet,
         );
 
-        let buy_signature = timeout(tx_timeout, buy_future)
+        let buy_signature = receipt::leg_scope(TradeLeg::Buy, timeout(tx_timeout, buy_future))
             .await
             .context("Таймаут при выполнении покупки")?
             .context("Ошибка выполнения покупки")?;
-
+        drop(buy_permit);
         log::info!("Покупка выполнена: {}", buy_signature);
+        // С этого момента капитал удерживается в base токене до продажи
+        let bought_at = self.clock.now();
 
-        // Небольшая задержка между транзакциями для подтверждения
-        if !simulation_mode {
-            tokio::time::sleep(Duration::from_millis(500)).await;
-        }
+        let (sell_amount, sell_min_output) = match (gate, base_mint, balance_before) {
+            _ if simulation_mode => {
+                self.track_leg(Some(TradeLeg::Buy), &buy_signature, simulation_mode);
+                atomic_sell_leg(opportunity, min_output)
+            }
+            (LegGate::Confirmed, Some(mint), Some(before)) => {
+                // Полученное известно только после подтверждения покупки
+                self.confirm_leg(TradeLeg::Buy, &buy_signature).await
+                    .context("Покупка не подтверждена")?;
+                let after = wallet.token_balance(&self.config.network, &mint).await
+                    .context("Не удалось получить баланс после покупки")?;
+                size_sell_leg(opportunity, min_output.sell, after - before)?
+            }
+            (LegGate::Confirmed, _, _) => {
+                self.confirm_leg(TradeLeg::Buy, &buy_signature).await
+                    .context("Покупка не подтверждена")?;
+                // Полученное не проверить: продаётся гарантированный выход покупки
+                atomic_sell_leg(opportunity, min_output)
+            }
+            (LegGate::Optimistic, _, _) => {
+                // Подтверждение покупки отслеживается в фоне, продажа — на гарантированный выход покупки
+                self.track_leg(Some(TradeLeg::Buy), &buy_signature, simulation_mode);
+                let delay = self.config.arbitrage.inter_leg_delay_ms;
+                if delay > 0 {
+                    self.clock.sleep(Duration::from_millis(delay)).await;
+                }
+                atomic_sell_leg(opportunity, min_output)
+            }
+        };
 
         // Шаг 2: Продажа на втором DEX с таймаутом
+        let _sell_permit = timeout(tx_timeout, self.venue_limiter.acquire(sell_dex.name()))
+            .await
+            .context("Таймаут ожидания очереди исполнения для продажи")??;
         let sell_future = sell_dex.execute_swap(
             simulation_mode,
             &opportunity.base_token,
             &opportunity.quote_token,
-            opportunity.trade_amount,
-            min_output,
-            &self.wallet,
+            sell_amount,
+            sell_min_output,
+            &wallet,
         );
 
-        let sell_signature = timeout(tx_timeout, sell_future)
+        let sell_signature = receipt::leg_scope(TradeLeg::Sell, timeout(tx_timeout, sell_future))
             .await
             .context("Таймаут при выполнении продажи")?
             .context("Ошибка выполнения продажи")?;
+        self.track_leg(Some(TradeLeg::Sell), &sell_signature, simulation_mode);
 
-        Ok((buy_signature, sell_signature))
+        let held = (self.clock.now() - bought_at).to_std().unwrap_or_default();
+        Ok((buy_signature, sell_signature, held))
     }
 }
 
+/// Запись о сделке по арбитражной возможности
+fn trade_record(
+    opportunity: &ArbitrageOpportunity,
+    profit: ProfitBreakdown,
+    status: TradeStatus,
+    leg_gate: Option<LegGate>,
+    tx_signature: Option<String>,
+    failure_reason: Option<String>,
+    simulation_logs: Vec<String>,
+) -> TradeRecord {
+    TradeRecord {
+        id: uuid::Uuid::new_v4(),
+        timestamp: chrono::Utc::now(),
+        from_dex: opportunity.from_dex.clone(),
+        to_dex: opportunity.to_dex.clone(),
+        base_token: opportunity.base_token.clone(),
+        quote_token: opportunity.quote_token.clone(),
+        amount: opportunity.trade_amount,
+        profit_percent: opportunity.profit_percent_after_fees,
+        profit_quote: profit.quote,
+        profit_sol: profit.sol,
+        profit_usd: profit.usd,
+        status,
+        tx_signature,
+        failure_reason,
+        trace_id: crate::trace::current().map(|id| id.to_string()),
+        notes: None,
+        tags: Vec::new(),
+        leg_gate,
+        simulation_logs,
+        capital_sol: None,
+        hold_ms: None,
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use crate::config::Config;
+use crate::dex::DexManager;
+use crate::fees::{fetch_transaction_costs, FeeBreakdown, FeeLedger, LAMPORTS_PER_SIGNATURE};
+use crate::store::Store;
+use crate::units::lamports_to_sol;
+use crate::wallet::{HotWallet, TokenAccountBalance};
+
+/// Коллекция хранилища с выполненными очистками токен-аккаунтов
+pub const ATA_CLEANUPS_COLLECTION: &str = "ata_cleanups";
+
+/// Закрытий в одной транзакции (инструкция close_account — 3 аккаунта, транзакция остаётся в пакете)
+pub const MAX_CLOSE_PER_TRANSACTION: usize = 20;
+
+/// Закрытый токен-аккаунт
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClosedAccount {
+    pub address: String,
+    pub mint: String,
+    /// Баланс аккаунта перед закрытием (рента), lamports
+    pub lamports: u64,
+}
+
+/// Выполненная очистка: одна транзакция закрытия токен-аккаунтов
+///
+/// Изменение баланса SOL кошелька транзакцией — `rent_reclaimed - fee`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AtaCleanup {
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+    pub accounts: Vec<ClosedAccount>,
+    /// Возвращённая рента по подтверждённой транзакции, lamports
+    pub rent_reclaimed: u64,
+    /// Комиссия транзакции (базовая и приоритетная), lamports
+    pub fee: u64,
+}
+
+/// Пустые токен-аккаунты кошелька, которые можно закрыть
+///
+/// Аккаунты mint из `keep` (токены торговых пар) не закрываются: первый же
+/// своп создал бы их снова, заплатив ренту.
+pub fn cleanup_candidates(accounts: &[TokenAccountBalance], keep: &HashSet<Pubkey>) -> Vec<TokenAccountBalance> {
+    accounts.iter()
+        .filter(|account| account.amount == 0 && !keep.contains(&account.mint))
+        .cloned()
+        .collect()
+}
+
+/// Mint токенов торговых пар (неизвестные реестру токены пропускаются)
+fn trading_mints(dex_manager: &DexManager) -> HashSet<Pubkey> {
+    let tokens = dex_manager.tokens();
+    dex_manager.trading_pairs()
+        .iter()
+        .filter_map(|pair| pair.split_once('/'))
+        .flat_map(|(base, quote)| [base, quote])
+        .filter_map(|symbol| tokens.mint(symbol).ok())
+        .collect()
+}
+
+/// Выполненные очистки за период (границы включительно)
+pub async fn load_cleanups(
+    store: &Store,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<AtaCleanup>> {
+    let mut cleanups: Vec<AtaCleanup> = store.read_all(ATA_CLEANUPS_COLLECTION).await?;
+    cleanups.retain(|c| from.is_none_or(|from| c.timestamp >= from) && to.is_none_or(|to| c.timestamp <= to));
+    Ok(cleanups)
+}
+
+/// Учёт выполненной очистки: возвращённая рента и комиссия в учёте расходов, запись в историю
+///
+/// Если транзакцию прочитать не удалось, рента берётся по балансам закрытых
+/// аккаунтов, а комиссия — базовая за одну подпись.
+pub async fn record_cleanup(
+    fees: &FeeLedger,
+    store: &Store,
+    signature: &str,
+    accounts: Vec<ClosedAccount>,
+    costs: Option<FeeBreakdown>,
+    now: DateTime<Utc>,
+) -> Result<AtaCleanup> {
+    let costs = costs.unwrap_or_else(|| FeeBreakdown {
+        base_fee: LAMPORTS_PER_SIGNATURE,
+        rent_reclaimed: accounts.iter().map(|account| account.lamports).sum(),
+        ..FeeBreakdown::default()
+    });
+    fees.record(signature, costs).await;
+    let cleanup = AtaCleanup {
+        timestamp: now,
+        signature: signature.to_string(),
+        accounts,
+        rent_reclaimed: costs.rent_reclaimed,
+        fee: costs.base_fee + costs.priority_fee,
+    };
+    store.append(ATA_CLEANUPS_COLLECTION, &cleanup).await
+        .context("Не удалось сохранить очистку токен-аккаунтов")?;
+    Ok(cleanup)
+}
+
+/// Закрытие пустых токен-аккаунтов кошелька (задача `ata_cleanup` планировщика)
+///
+/// В режиме симуляции аккаунты только перечисляются. Аккаунты закрываются
+/// транзакциями по MAX_CLOSE_PER_TRANSACTION; каждая подтверждённая транзакция
+/// учитывается сразу, так что ошибка следующей не теряет уже возвращённую ренту.
+pub async fn run_cleanup(
+    config: &Config,
+    wallet: &HotWallet,
+    dex_manager: &DexManager,
+    fees: &FeeLedger,
+    store: &Store,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    // Ключ не меняется, пока аккаунты не закрыты
+    let wallet = wallet.lease().await;
+    let accounts = wallet.get_token_accounts(&config.network).await
+        .context("Не удалось получить токен-аккаунты кошелька")?;
+    let candidates = cleanup_candidates(&accounts, &trading_mints(dex_manager));
+    if candidates.is_empty() {
+        return Ok("пустых токен-аккаунтов нет".to_string());
+    }
+    if config.safety.simulation_mode {
+        return Ok(format!("симуляция: пустых токен-аккаунтов к закрытию {}", candidates.len()));
+    }
+
+    let (mut closed, mut reclaimed) = (0, 0);
+    for chunk in candidates.chunks(MAX_CLOSE_PER_TRANSACTION) {
+        let addresses: Vec<Pubkey> = chunk.iter().map(|account| account.address).collect();
+        let signature = dex_manager.close_token_accounts(&addresses, &wallet).await?;
+        let costs = match fetch_transaction_costs(&config.network, &signature).await {
+            Ok(costs) => costs,
+            Err(e) => {
+                log::warn!("Не удалось прочитать транзакцию очистки {}: {:#}", signature, e);
+                None
+            }
+        };
+        let accounts = chunk.iter()
+            .map(|account| ClosedAccount {
+                address: account.address.to_string(),
+                mint: account.mint.to_string(),
+                lamports: account.lamports,
+            })
+            .collect();
+        let cleanup = record_cleanup(fees, store, &signature, accounts, costs, now).await?;
+        log::info!(
+            "Закрыто токен-аккаунтов: {}, возвращено ренты {} SOL, транзакция {}",
+            cleanup.accounts.len(), lamports_to_sol(cleanup.rent_reclaimed), signature
+        );
+        closed += cleanup.accounts.len();
+        reclaimed += cleanup.rent_reclaimed;
+    }
+    Ok(format!("закрыто токен-аккаунтов: {}, возвращено ренты {} SOL", closed, lamports_to_sol(reclaimed)))
+}
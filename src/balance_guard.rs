@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address;
+use crate::failure::FailureKind;
+use crate::tx_error::DecodedTxError;
+
+/// Код ошибки SPL Token InsufficientFunds
+const SPL_TOKEN_INSUFFICIENT_FUNDS: u32 = 1;
+
+/// Проверка прироста баланса внутри атомарной транзакции
+///
+/// Последней инструкцией транзакции добавляется перевод SPL токена с ATA кошелька
+/// на него же на сумму `баланс_до + минимальный_прирост`. Программа SPL Token проверяет
+/// достаточность средств до обработки перевода самому себе, поэтому если после свопов
+/// баланс quote токена вырос меньше ожидаемого, инструкция падает с InsufficientFunds
+/// и вся транзакция откатывается. Своя on-chain программа для этого не нужна.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceGuard {
+    /// Владелец кошелька (подписант транзакции)
+    pub owner: Pubkey,
+    /// ATA quote токена кошелька
+    pub token_account: Pubkey,
+    /// Баланс ATA до транзакции (в минимальных единицах токена)
+    pub balance_before: u64,
+    /// Минимальный прирост баланса (в минимальных единицах токена)
+    pub min_increase: u64,
+}
+
+impl BalanceGuard {
+    /// Создание проверки для ATA кошелька с указанным mint
+    pub fn new(owner: &Pubkey, quote_mint: &Pubkey, balance_before: u64, min_increase: u64) -> Self {
+        Self {
+            owner: *owner,
+            token_account: get_associated_token_address(owner, quote_mint),
+            balance_before,
+            min_increase,
+        }
+    }
+
+    /// Баланс, который должен быть на ATA к моменту выполнения проверки
+    pub fn required_balance(&self) -> Result<u64> {
+        self.balance_before
+            .checked_add(self.min_increase)
+            .context("Переполнение при расчёте требуемого баланса")
+    }
+
+    /// Инструкция проверки (перевод самому себе на требуемую сумму)
+    pub fn instruction(&self) -> Result<Instruction> {
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            &self.token_account,
+            &self.token_account,
+            &self.owner,
+            &[],
+            self.required_balance()?,
+        )
+        .context("Не удалось построить инструкцию проверки баланса")
+    }
+
+    /// Добавление проверки последней инструкцией транзакции
+    ///
+    /// Возвращает индекс инструкции проверки для разбора ошибки.
+    pub fn append_to(&self, instructions: &mut Vec<Instruction>) -> Result<u8> {
+        let index = u8::try_from(instructions.len())
+            .context("Слишком много инструкций в транзакции")?;
+        instructions.push(self.instruction()?);
+        Ok(index)
+    }
+
+    /// Расшифрованная ошибка отправки, если это срабатывание проверки с индексом guard_index
+    ///
+    /// Программа SPL Token сообщает о нехватке средств, что означало бы остановку
+    /// бота (FailureKind::Fatal); здесь это недостаточная прибыль сделки.
+    /// Категория — неудача исполнения; вызывающий переопределяет её, если
+    /// транзакцию отклонила симуляция перед отправкой.
+    pub fn guard_failure(error: &DecodedTxError, guard_index: u8) -> Option<DecodedTxError> {
+        if error.instruction_index != Some(guard_index) || error.code != Some(SPL_TOKEN_INSUFFICIENT_FUNDS) {
+            return None;
+        }
+        Some(DecodedTxError {
+            program: None,
+            code: None,
+            reason: "сделка не дала минимального прироста баланса quote токена, транзакция откачена".to_string(),
+            kind: FailureKind::Execution,
+            ..error.clone()
+        })
+    }
+}
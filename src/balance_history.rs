@@ -0,0 +1,98 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::config::Config;
+use crate::rpc::simulation_network;
+use crate::store::Store;
+use crate::units::lamports_to_sol;
+use crate::wallet::Wallet;
+
+/// Коллекция хранилища со снимками баланса
+pub const BALANCE_COLLECTION: &str = "balance_history";
+
+/// Снимок баланса кошелька
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceSnapshot {
+    pub timestamp: DateTime<Utc>,
+    /// Баланс SOL
+    pub sol: Decimal,
+    /// Балансы SPL токенов: mint -> количество
+    #[serde(default)]
+    pub tokens: BTreeMap<String, Decimal>,
+}
+
+/// Снятие и сохранение снимка баланса (через `simulation_rpc_url`, если он задан)
+pub async fn record_snapshot(config: &Config, wallet: &Wallet, store: &Store) -> Result<BalanceSnapshot> {
+    let network = simulation_network(&config.network);
+    let lamports = wallet.get_balance(&network).await?;
+    let tokens = match wallet.get_token_balances(&network).await {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            // Баланс SOL важнее полноты снимка — сохраняем его без токенов
+            log::warn!("Не удалось получить балансы токенов: {}", e);
+            BTreeMap::new()
+        }
+    };
+
+    let snapshot = BalanceSnapshot {
+        timestamp: Utc::now(),
+        sol: lamports_to_sol(lamports),
+        tokens,
+    };
+    store.append(BALANCE_COLLECTION, &snapshot).await?;
+    Ok(snapshot)
+}
+
+/// Загрузка истории баланса за период
+pub async fn load_history(
+    store: &Store,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<BalanceSnapshot>> {
+    let mut snapshots: Vec<BalanceSnapshot> = store.read_all(BALANCE_COLLECTION).await?;
+    snapshots.retain(|s| from.is_none_or(|from| s.timestamp >= from) && to.is_none_or(|to| s.timestamp <= to));
+    snapshots.sort_by_key(|s| s.timestamp);
+    Ok(snapshots)
+}
+
+/// Средний баланс SOL по снимкам (None — снимков нет)
+pub fn average_sol(snapshots: &[BalanceSnapshot]) -> Option<Decimal> {
+    if snapshots.is_empty() {
+        return None;
+    }
+    Some(snapshots.iter().map(|snapshot| snapshot.sol).sum::<Decimal>() / Decimal::from(snapshots.len()))
+}
+
+/// Прореживание ряда до max_points точек
+///
+/// Период делится на равные интервалы, из каждого берётся последний снимок:
+/// для кривой капитала важно значение на конец интервала, а не среднее.
+pub fn downsample(snapshots: &[BalanceSnapshot], max_points: usize) -> Vec<BalanceSnapshot> {
+    if max_points == 0 || snapshots.len() <= max_points {
+        return snapshots.to_vec();
+    }
+
+    let (first, last) = match (snapshots.first(), snapshots.last()) {
+        (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+        _ => return Vec::new(),
+    };
+    let span_ms = (last - first).num_milliseconds().max(1);
+
+    let mut result: Vec<BalanceSnapshot> = Vec::with_capacity(max_points);
+    let mut current_bucket = None;
+    for snapshot in snapshots {
+        let offset_ms = (snapshot.timestamp - first).num_milliseconds();
+        let bucket = ((offset_ms as i128 * max_points as i128) / (span_ms as i128 + 1)) as usize;
+        if current_bucket == Some(bucket) {
+            if let Some(previous) = result.last_mut() {
+                *previous = snapshot.clone();
+            }
+        } else {
+            result.push(snapshot.clone());
+            current_bucket = Some(bucket);
+        }
+    }
+    result
+}
@@ -0,0 +1,309 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::config::BusConfig;
+
+/// Таймаут подключения и обычных команд Redis
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+/// Сколько возможностей забирается из очереди за один прогон исполнения
+const MAX_BATCH: usize = 32;
+
+/// Возможность в очереди между процессами поиска и исполнения
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedOpportunity {
+    /// Когда возможность найдена (по часам процесса поиска)
+    pub found_at: DateTime<Utc>,
+    /// Идентификатор прогона поиска — для сквозного поиска по логам обоих процессов
+    pub trace_id: Option<String>,
+    pub opportunity: ArbitrageOpportunity,
+}
+
+impl QueuedOpportunity {
+    /// Возраст возможности на момент `now` (отрицательный при расхождении часов — ноль)
+    pub fn age(&self, now: DateTime<Utc>) -> Duration {
+        (now - self.found_at).to_std().unwrap_or_default()
+    }
+}
+
+/// Адрес Redis из `bus.url`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisAddress {
+    /// host:port
+    pub host: String,
+    /// Номер базы (SELECT), None — по умолчанию
+    pub db: Option<u32>,
+}
+
+impl RedisAddress {
+    /// Разбор `redis://host[:port][/db]`
+    ///
+    /// Пароль в адресе не принимается: он передаётся через переменную окружения
+    /// `bus.password_env`, чтобы не попадать в config.toml.
+    pub fn parse(url: &str) -> Result<Self> {
+        if url.starts_with("nats://") {
+            anyhow::bail!("NATS не поддерживается этой сборкой, используйте Redis (redis://host:port)");
+        }
+        let rest = url.strip_prefix("redis://")
+            .with_context(|| format!("Адрес очереди должен начинаться с redis://: {}", url))?;
+        let (authority, db) = match rest.split_once('/') {
+            Some((authority, "")) => (authority, None),
+            Some((authority, db)) => (authority, Some(db.parse::<u32>()
+                .with_context(|| format!("Некорректный номер базы Redis: {}", db))?)),
+            None => (rest, None),
+        };
+        if authority.contains('@') {
+            anyhow::bail!("Пароль Redis задаётся через bus.password_env, а не в адресе");
+        }
+        if authority.is_empty() {
+            anyhow::bail!("Не указан хост Redis: {}", url);
+        }
+        let host = if authority.contains(':') {
+            authority.to_string()
+        } else {
+            format!("{}:6379", authority)
+        };
+        Ok(Self { host, db })
+    }
+}
+
+/// Ответ Redis (вложенные массивы не нужны используемым командам)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Status(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+/// Команда в формате RESP
+pub fn encode_command(args: &[&[u8]]) -> Vec<u8> {
+    let mut buf = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        buf.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        buf.extend_from_slice(arg);
+        buf.extend_from_slice(b"\r\n");
+    }
+    buf
+}
+
+/// Разбор ответа RESP; ошибка Redis (`-ERR ...`) возвращается как Err
+pub async fn read_reply<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<Reply> {
+    let line = read_line(reader).await?;
+    match line.split_at(1) {
+        ("*", count) => {
+            let Some(count) = parse_length(count)? else { return Ok(Reply::Array(None)) };
+            let mut items = Vec::with_capacity(count);
+            for _ in 0..count {
+                let line = read_line(reader).await?;
+                let (kind, rest) = line.split_at(1);
+                if kind == "*" {
+                    anyhow::bail!("Вложенные массивы в ответе Redis не поддерживаются");
+                }
+                items.push(read_scalar(reader, kind, rest).await?);
+            }
+            Ok(Reply::Array(Some(items)))
+        }
+        (kind, rest) => read_scalar(reader, kind, rest).await,
+    }
+}
+
+async fn read_scalar<R: AsyncBufReadExt + Unpin>(reader: &mut R, kind: &str, rest: &str) -> Result<Reply> {
+    match kind {
+        "+" => Ok(Reply::Status(rest.to_string())),
+        "-" => anyhow::bail!("Ошибка Redis: {}", rest),
+        ":" => Ok(Reply::Integer(rest.parse().with_context(|| format!("Некорректное число в ответе Redis: {}", rest))?)),
+        "$" => {
+            let Some(len) = parse_length(rest)? else { return Ok(Reply::Bulk(None)) };
+            let mut data = vec![0u8; len + 2];
+            reader.read_exact(&mut data).await.context("Соединение с Redis закрыто")?;
+            data.truncate(len);
+            Ok(Reply::Bulk(Some(data)))
+        }
+        _ => anyhow::bail!("Неожиданный ответ Redis: {}{}", kind, rest),
+    }
+}
+
+async fn read_line<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.context("Не удалось прочитать ответ Redis")? == 0 {
+        anyhow::bail!("Соединение с Redis закрыто");
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() {
+        anyhow::bail!("Пустая строка в ответе Redis");
+    }
+    Ok(line.to_string())
+}
+
+/// Длина массива или строки (-1 — отсутствует)
+fn parse_length(value: &str) -> Result<Option<usize>> {
+    let len: i64 = value.parse().with_context(|| format!("Некорректная длина в ответе Redis: {}", value))?;
+    Ok(usize::try_from(len).ok())
+}
+
+struct RedisConnection {
+    stream: BufReader<TcpStream>,
+}
+
+impl RedisConnection {
+    async fn connect(address: &RedisAddress, password: Option<&str>) -> Result<Self> {
+        let stream = tokio::time::timeout(COMMAND_TIMEOUT, TcpStream::connect(&address.host))
+            .await
+            .with_context(|| format!("Таймаут подключения к Redis {}", address.host))?
+            .with_context(|| format!("Не удалось подключиться к Redis {}", address.host))?;
+        let mut connection = Self { stream: BufReader::new(stream) };
+        if let Some(password) = password {
+            connection.command(&[b"AUTH", password.as_bytes()], COMMAND_TIMEOUT).await
+                .context("Redis отклонил пароль")?;
+        }
+        if let Some(db) = address.db {
+            connection.command(&[b"SELECT", db.to_string().as_bytes()], COMMAND_TIMEOUT).await
+                .context("Не удалось выбрать базу Redis")?;
+        }
+        Ok(connection)
+    }
+
+    async fn command(&mut self, args: &[&[u8]], timeout: Duration) -> Result<Reply> {
+        let exchange = async {
+            self.stream.get_mut().write_all(&encode_command(args)).await
+                .context("Не удалось отправить команду Redis")?;
+            read_reply(&mut self.stream).await
+        };
+        tokio::time::timeout(timeout, exchange)
+            .await
+            .context("Таймаут ответа Redis")?
+    }
+}
+
+/// Очередь возможностей в Redis между процессами поиска и исполнения
+///
+/// Процесс поиска (`mode = "scan"`) добавляет возможности в список, процесс
+/// исполнения (`mode = "execute_only"`) забирает их в порядке поступления:
+/// каждая возможность исполняется не больше одного раза. Длина списка
+/// ограничена `bus.max_queue_len`, пока исполнитель не запущен. После ошибки
+/// соединение открывается заново при следующей команде.
+pub struct OpportunityBus {
+    config: BusConfig,
+    address: RedisAddress,
+    password: Option<String>,
+    connection: Mutex<Option<RedisConnection>>,
+}
+
+impl OpportunityBus {
+    pub fn new(config: &BusConfig) -> Result<Self> {
+        let url = config.url.as_deref().context("Не задан bus.url")?;
+        let password = match config.password_env {
+            Some(ref name) => Some(std::env::var(name)
+                .with_context(|| format!("Переменная окружения {} (bus.password_env) не задана", name))?),
+            None => None,
+        };
+        Ok(Self {
+            config: config.clone(),
+            address: RedisAddress::parse(url)?,
+            password,
+            connection: Mutex::new(None),
+        })
+    }
+
+    /// Команда через общее соединение (с переподключением)
+    async fn command(&self, args: &[&[u8]], timeout: Duration) -> Result<Reply> {
+        let mut slot = self.connection.lock().await;
+        let mut connection = match slot.take() {
+            Some(connection) => connection,
+            None => RedisConnection::connect(&self.address, self.password.as_deref()).await?,
+        };
+        let result = connection.command(args, timeout).await;
+        // После сбоя состояние протокола неизвестно: соединение не возвращается
+        if result.is_ok() {
+            *slot = Some(connection);
+        }
+        result
+    }
+
+    /// Публикация найденных возможностей
+    pub async fn publish(&self, opportunities: &[ArbitrageOpportunity], found_at: DateTime<Utc>) -> Result<()> {
+        if opportunities.is_empty() {
+            return Ok(());
+        }
+        let trace_id = crate::trace::current().map(|id| id.to_string());
+        let payloads = opportunities.iter()
+            .map(|opportunity| serde_json::to_vec(&QueuedOpportunity {
+                found_at,
+                trace_id: trace_id.clone(),
+                opportunity: opportunity.clone(),
+            }))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Не удалось сериализовать возможность")?;
+
+        let queue = self.config.queue.as_bytes();
+        let mut args: Vec<&[u8]> = vec![b"LPUSH", queue];
+        args.extend(payloads.iter().map(Vec::as_slice));
+        self.command(&args, COMMAND_TIMEOUT).await.context("Не удалось добавить возможности в очередь")?;
+        // Новые возможности в начале списка: при переполнении вытесняются самые старые
+        let last = (self.config.max_queue_len.saturating_sub(1)).to_string();
+        self.command(&[b"LTRIM", queue, b"0", last.as_bytes()], COMMAND_TIMEOUT).await
+            .context("Не удалось ограничить длину очереди")?;
+        Ok(())
+    }
+
+    /// Ожидание возможностей до `wait` и выборка накопившихся (старые первыми)
+    ///
+    /// Пустой результат — за время ожидания возможностей не было. Записи,
+    /// которые не удалось разобрать, пропускаются с предупреждением.
+    pub async fn receive(&self, wait: Duration) -> Result<Vec<QueuedOpportunity>> {
+        let queue = self.config.queue.as_bytes();
+        let wait_sec = wait.as_secs().max(1).to_string();
+        let first = self.command(&[b"BRPOP", queue, wait_sec.as_bytes()], wait + COMMAND_TIMEOUT).await
+            .context("Не удалось получить возможности из очереди")?;
+        let mut payloads = match first {
+            Reply::Array(Some(items)) => match items.into_iter().nth(1) {
+                Some(Reply::Bulk(Some(payload))) => vec![payload],
+                _ => anyhow::bail!("Неожиданный ответ Redis на BRPOP"),
+            },
+            _ => return Ok(Vec::new()),
+        };
+        while payloads.len() < MAX_BATCH {
+            match self.command(&[b"RPOP", queue], COMMAND_TIMEOUT).await? {
+                Reply::Bulk(Some(payload)) => payloads.push(payload),
+                _ => break,
+            }
+        }
+
+        Ok(payloads.into_iter()
+            .filter_map(|payload| match serde_json::from_slice::<QueuedOpportunity>(&payload) {
+                Ok(queued) => Some(queued),
+                Err(e) => {
+                    log::warn!("Пропущена некорректная запись очереди возможностей: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Возможности, найденные не раньше `bus.max_age_ms` до `now`
+    ///
+    /// Цены устаревших возможностей уже могли измениться — они отбрасываются
+    /// без исполнения.
+    pub fn fresh(&self, queued: Vec<QueuedOpportunity>, now: DateTime<Utc>) -> Vec<QueuedOpportunity> {
+        let max_age = Duration::from_millis(self.config.max_age_ms);
+        queued.into_iter()
+            .filter(|queued| {
+                let age = queued.age(now);
+                if age > max_age {
+                    log::info!(
+                        "Возможность {} -> {} {}/{} устарела ({} мс), пропускаем",
+                        queued.opportunity.from_dex, queued.opportunity.to_dex,
+                        queued.opportunity.base_token, queued.opportunity.quote_token,
+                        age.as_millis()
+                    );
+                }
+                age <= max_age
+            })
+            .collect()
+    }
+}
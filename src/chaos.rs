@@ -0,0 +1,179 @@
+//! Внедрение сбоев в транспорт RPC для стресс-тестов (feature `chaos`)
+//!
+//! Сбои включаются для отдельного адреса RPC через [`install`], поэтому тесты
+//! с разными фиктивными узлами не мешают друг другу. В сборке без feature
+//! модуля нет, и транспорт RPC не меняется.
+
+use base64::Engine;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::rpc_request::RpcRequest;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// Вероятности и параметры сбоев (0.0 — сбой не внедряется)
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    /// Случайная задержка каждого запроса, от нуля до этого значения
+    pub max_latency: Duration,
+    /// sendTransaction отвечает подписью, но транзакция до узла не доходит
+    pub drop_send: f64,
+    /// Транзакция доходит до узла, но ответ теряется (ошибка транспорта)
+    pub lost_ack: f64,
+    /// Транзакция доставляется узлу дважды (двойное подтверждение)
+    pub duplicate_send: f64,
+    /// Ошибка транспорта вместо ответа на остальные запросы
+    pub rpc_error: f64,
+    /// Начальное значение генератора: одинаковый seed — одинаковая последовательность сбоев
+    pub seed: u64,
+}
+
+/// Счётчики внедрённых сбоев
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub requests: u64,
+    pub delayed: u64,
+    pub rpc_errors: u64,
+    /// Подписи транзакций, потерянных при отправке
+    pub dropped_sends: Vec<String>,
+    /// Подписи транзакций, ответ на отправку которых потерян
+    pub lost_acks: Vec<String>,
+    /// Подписи транзакций, доставленных дважды
+    pub duplicated_sends: Vec<String>,
+}
+
+/// Сбои одного адреса RPC
+#[derive(Debug)]
+pub struct Chaos {
+    config: ChaosConfig,
+    rng: Mutex<u64>,
+    stats: Mutex<ChaosStats>,
+}
+
+impl Chaos {
+    fn new(config: ChaosConfig) -> Self {
+        // xorshift не выходит из нулевого состояния
+        let seed = config.seed.max(1);
+        Self { config, rng: Mutex::new(seed), stats: Mutex::new(ChaosStats::default()) }
+    }
+
+    /// Внедрённые сбои на текущий момент
+    pub fn stats(&self) -> ChaosStats {
+        self.stats.lock().expect("блокировка счётчиков сбоев отравлена").clone()
+    }
+
+    /// Следующее число генератора xorshift64* в [0, 1)
+    fn next(&self) -> f64 {
+        let mut state = self.rng.lock().expect("блокировка генератора сбоев отравлена");
+        *state ^= *state >> 12;
+        *state ^= *state << 25;
+        *state ^= *state >> 27;
+        (state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn roll(&self, probability: f64) -> bool {
+        probability > 0.0 && self.next() < probability
+    }
+
+    fn record(&self, update: impl FnOnce(&mut ChaosStats)) {
+        update(&mut self.stats.lock().expect("блокировка счётчиков сбоев отравлена"));
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, Arc<Chaos>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<Chaos>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Включение сбоев для RPC клиентов, создаваемых для `rpc_url`
+///
+/// Клиенты, созданные раньше, продолжают работать без сбоев.
+pub fn install(rpc_url: &str, config: ChaosConfig) -> Arc<Chaos> {
+    let chaos = Arc::new(Chaos::new(config));
+    registry().lock().expect("реестр сбоев отравлен").insert(rpc_url.to_string(), chaos.clone());
+    chaos
+}
+
+/// Отключение сбоев для `rpc_url`
+pub fn uninstall(rpc_url: &str) {
+    registry().lock().expect("реестр сбоев отравлен").remove(rpc_url);
+}
+
+/// Транспорт RPC со сбоями из реестра (без записи для адреса — без сбоев)
+pub struct ChaosSender<S> {
+    inner: S,
+    chaos: Option<Arc<Chaos>>,
+}
+
+impl<S> ChaosSender<S> {
+    pub fn for_url(inner: S, rpc_url: &str) -> Self {
+        let chaos = registry().lock().expect("реестр сбоев отравлен").get(rpc_url).cloned();
+        Self { inner, chaos }
+    }
+}
+
+/// Ошибка транспорта, как при обрыве соединения
+fn transport_error(request: RpcRequest, message: &str) -> ClientError {
+    ClientError::new_with_request(
+        ClientErrorKind::Io(std::io::Error::new(std::io::ErrorKind::ConnectionReset, message.to_string())),
+        request,
+    )
+}
+
+/// Подпись транзакции из параметров sendTransaction (base64, первая подпись)
+fn sent_signature(params: &serde_json::Value) -> Option<String> {
+    let encoded = params.get(0)?.as_str()?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).ok()?;
+    // compact-u16 число подписей занимает 1 байт, пока подписей меньше 128
+    let signature = bytes.get(1..65)?;
+    Some(bs58::encode(signature).into_string())
+}
+
+#[async_trait::async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for ChaosSender<S> {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        let Some(ref chaos) = self.chaos else {
+            return self.inner.send(request, params).await;
+        };
+        chaos.record(|stats| stats.requests += 1);
+
+        if !chaos.config.max_latency.is_zero() {
+            let latency = chaos.config.max_latency.mul_f64(chaos.next());
+            chaos.record(|stats| stats.delayed += 1);
+            tokio::time::sleep(latency).await;
+        }
+
+        if request != RpcRequest::SendTransaction {
+            if chaos.roll(chaos.config.rpc_error) {
+                chaos.record(|stats| stats.rpc_errors += 1);
+                return Err(transport_error(request, "chaos: ошибка транспорта RPC"));
+            }
+            return self.inner.send(request, params).await;
+        }
+
+        let signature = sent_signature(&params).unwrap_or_default();
+        if chaos.roll(chaos.config.drop_send) {
+            chaos.record(|stats| stats.dropped_sends.push(signature.clone()));
+            return Ok(serde_json::Value::String(signature));
+        }
+        let result = self.inner.send(request, params.clone()).await;
+        if chaos.roll(chaos.config.duplicate_send) {
+            chaos.record(|stats| stats.duplicated_sends.push(signature.clone()));
+            let _ = self.inner.send(request, params).await;
+        }
+        if result.is_ok() && chaos.roll(chaos.config.lost_ack) {
+            chaos.record(|stats| stats.lost_acks.push(signature));
+            return Err(transport_error(request, "chaos: ответ на отправку транзакции потерян"));
+        }
+        result
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+/// Команда, определяемая аргументами командной строки
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Обычный запуск бота
+    Run {
+        /// Запись времени этапов конвейера в отчёт прогона
+        profile: bool,
+    },
+    /// Проверки перед запуском, один прогон поиска с симуляцией транзакций
+    /// и отчёт в JSON вместо торгового цикла
+    RunOnce,
+    /// Расширенная проверка конфигурации без запуска бота
+    CheckConfig,
+    /// Проверка миграций хранилища без их применения
+    CheckMigrations,
+    /// Проверка здоровья работающего бота через /health (код выхода для Docker)
+    Healthcheck,
+    /// Airdrop SOL на devnet и создание ATA для указанных mint адресов
+    DevnetAirdrop {
+        /// Баланс SOL, до которого пополняется кошелёк
+        sol: Decimal,
+        /// Mint адреса, для которых нужно создать ATA
+        mints: Vec<String>,
+    },
+    /// Создание тестового пула на devnet из двух новых токенов
+    DevnetSeedPool {
+        /// Резерв каждого токена пула в целых токенах
+        amount: u64,
+    },
+}
+
+/// Количество SOL для airdrop по умолчанию (лимит devnet faucet — 2 SOL за запрос)
+const DEFAULT_AIRDROP_SOL: &str = "1";
+/// Резерв тестового пула по умолчанию (целых токенов каждой стороны)
+const DEFAULT_SEED_POOL_AMOUNT: u64 = 1_000;
+
+/// Разбор аргументов командной строки (без имени программы)
+///
+/// Поддерживаемые формы:
+/// - без аргументов — запуск бота
+/// - `--profile` — запуск бота с профилированием этапов
+/// - `run [--profile | --once]` — то же, `--once` — один прогон без исполнения и выход
+/// - `check-config`
+/// - `--check-migrations` — список миграций хранилища, которые будут применены
+/// - `--healthcheck` — код выхода 0, если работающий бот здоров
+/// - `devnet airdrop [--sol N] [--mint MINT]...`
+/// - `devnet seed-pool [--amount N]`
+pub fn parse_args<I>(args: I) -> Result<Command>
+where
+    I: IntoIterator<Item = String>,
+{
+    let args: Vec<String> = args.into_iter().collect();
+
+    match args.first().map(String::as_str) {
+        None => Ok(Command::Run { profile: false }),
+        Some("--profile") => match args.get(1) {
+            None => Ok(Command::Run { profile: true }),
+            Some(extra) => anyhow::bail!("Неизвестный параметр: {}", extra),
+        },
+        Some("run") => {
+            let command = match args.get(1).map(String::as_str) {
+                None => Command::Run { profile: false },
+                Some("--profile") => Command::Run { profile: true },
+                Some("--once") => Command::RunOnce,
+                Some(other) => anyhow::bail!("Неизвестный параметр run: {}", other),
+            };
+            match args.get(2) {
+                None => Ok(command),
+                Some(extra) => anyhow::bail!("Неизвестный параметр run: {}", extra),
+            }
+        }
+        Some("--check-migrations") => match args.get(1) {
+            None => Ok(Command::CheckMigrations),
+            Some(extra) => anyhow::bail!("Неизвестный параметр: {}", extra),
+        },
+        Some("--healthcheck") => match args.get(1) {
+            None => Ok(Command::Healthcheck),
+            Some(extra) => anyhow::bail!("Неизвестный параметр: {}", extra),
+        },
+        Some("check-config") => match args.get(1) {
+            None => Ok(Command::CheckConfig),
+            Some(extra) => anyhow::bail!("Неизвестный параметр check-config: {}", extra),
+        },
+        Some("devnet") => parse_devnet(&args[1..]),
+        Some(other) => anyhow::bail!("Неизвестная команда: {}", other),
+    }
+}
+
+/// Разбор подкоманд `devnet`
+fn parse_devnet(args: &[String]) -> Result<Command> {
+    match args.first().map(String::as_str) {
+        Some("airdrop") => {}
+        Some("seed-pool") => return parse_seed_pool(&args[1..]),
+        Some(other) => anyhow::bail!("Неизвестная подкоманда devnet: {}", other),
+        None => anyhow::bail!("Использование: arb-bot devnet airdrop [--sol N] [--mint MINT]... | devnet seed-pool [--amount N]"),
+    }
+
+    let mut sol = Decimal::from_str(DEFAULT_AIRDROP_SOL)?;
+    let mut mints = Vec::new();
+
+    let mut iter = args[1..].iter();
+    while let Some(flag) = iter.next() {
+        let value = iter.next()
+            .with_context(|| format!("Не указано значение для {}", flag))?;
+        match flag.as_str() {
+            "--sol" => {
+                sol = Decimal::from_str(value)
+                    .with_context(|| format!("Некорректное количество SOL: {}", value))?;
+                if sol <= Decimal::ZERO {
+                    anyhow::bail!("Количество SOL должно быть больше 0");
+                }
+            }
+            "--mint" => mints.push(value.clone()),
+            _ => anyhow::bail!("Неизвестный параметр: {}", flag),
+        }
+    }
+
+    Ok(Command::DevnetAirdrop { sol, mints })
+}
+
+/// Разбор параметров `devnet seed-pool`
+fn parse_seed_pool(args: &[String]) -> Result<Command> {
+    let mut amount = DEFAULT_SEED_POOL_AMOUNT;
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter.next()
+            .with_context(|| format!("Не указано значение для {}", flag))?;
+        match flag.as_str() {
+            "--amount" => {
+                amount = value.parse()
+                    .with_context(|| format!("Некорректный резерв пула: {}", value))?;
+                if amount == 0 {
+                    anyhow::bail!("Резерв пула должен быть больше 0");
+                }
+            }
+            _ => anyhow::bail!("Неизвестный параметр: {}", flag),
+        }
+    }
+
+    Ok(Command::DevnetSeedPool { amount })
+}
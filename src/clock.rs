@@ -0,0 +1,85 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Источник времени для движка
+///
+/// Цикл сканирования, паузы, TTL и очистка истории берут время отсюда,
+/// чтобы тесты и режим воспроизведения могли управлять временем детерминированно.
+#[async_trait::async_trait]
+pub trait Clock: Send + Sync {
+    /// Текущее время
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Ожидание указанного интервала
+    async fn sleep(&self, duration: Duration);
+}
+
+/// Разделяемый источник времени
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Системные часы (tokio::time)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[async_trait::async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Системные часы как разделяемый источник времени
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Управляемые вручную часы для тестов и воспроизведения
+///
+/// `sleep` не ждёт реального времени, а сдвигает часы вперёд.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl ManualClock {
+    /// Создание часов, показывающих указанное время
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    /// Сдвиг времени вперёд
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().expect("мьютекс ManualClock отравлен");
+        *now += to_chrono(duration);
+    }
+
+    /// Установка времени
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.now.lock().expect("мьютекс ManualClock отравлен") = time;
+    }
+}
+
+#[async_trait::async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("мьютекс ManualClock отравлен")
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+        // Отдаём управление, чтобы другие задачи успели увидеть новое время
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Перевод std::time::Duration в chrono::Duration (с насыщением)
+pub fn to_chrono(duration: Duration) -> ChronoDuration {
+    ChronoDuration::from_std(duration).unwrap_or(ChronoDuration::MAX)
+}
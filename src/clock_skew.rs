@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::config::{Config, NetworkConfig};
+use crate::rpc::create_rpc_client;
+use crate::supervisor::Supervisor;
+
+/// Расхождение локальных часов с временем кластера
+///
+/// Положительное значение означает, что локальные часы спешат.
+/// Все проверки устаревания данных должны использовать `cluster_now()`,
+/// иначе на хосте с неверным NTP они молча перестают работать.
+#[derive(Debug, Clone, Default)]
+pub struct ClockSkew {
+    skew_ms: Arc<AtomicI64>,
+}
+
+impl ClockSkew {
+    /// Создание трекера с нулевым расхождением
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Последнее измеренное расхождение в миллисекундах
+    pub fn skew_ms(&self) -> i64 {
+        self.skew_ms.load(Ordering::Relaxed)
+    }
+
+    /// Сохранение нового измерения
+    pub fn record(&self, skew_ms: i64) {
+        self.skew_ms.store(skew_ms, Ordering::Relaxed);
+    }
+
+    /// Текущее время по часам кластера (локальное время с поправкой)
+    pub fn cluster_now(&self) -> DateTime<Utc> {
+        Utc::now() - ChronoDuration::milliseconds(self.skew_ms())
+    }
+}
+
+/// Измерение расхождения: локальное время минус block time последнего слота
+pub async fn measure_skew(network: &NetworkConfig) -> Result<i64> {
+    let network = network.clone();
+    let block_time = tokio::task::spawn_blocking(move || -> Result<i64> {
+        let client = create_rpc_client(&network)?;
+        let slot = client.get_slot()
+            .context("Не удалось получить текущий слот")?;
+        client.get_block_time(slot)
+            .with_context(|| format!("Не удалось получить время блока для слота {}", slot))
+    })
+    .await
+    .context("Задача измерения времени кластера завершилась с ошибкой")??;
+
+    Ok(Utc::now().timestamp_millis() - block_time * 1000)
+}
+
+/// Запуск фоновой проверки расхождения часов под надзором
+pub fn spawn_clock_skew_monitor(supervisor: &Supervisor, config: &Config, skew: ClockSkew) {
+    let config = config.clone();
+    supervisor.spawn("clock_skew", move || run_clock_skew_monitor(config.clone(), skew.clone()));
+}
+
+async fn run_clock_skew_monitor(config: Config, skew: ClockSkew) -> Result<()> {
+    let network = config.network;
+    let check_interval = Duration::from_secs(config.monitoring.clock_skew_check_interval_sec);
+    let max_skew_ms = config.monitoring.max_clock_skew_ms as i64;
+
+    let mut timer = tokio::time::interval(check_interval);
+    loop {
+        timer.tick().await;
+        match measure_skew(&network).await {
+            Ok(skew_ms) => {
+                skew.record(skew_ms);
+                if skew_ms.abs() > max_skew_ms {
+                    log::warn!(
+                        "⚠️  Расхождение локальных часов с кластером {} мс (порог {} мс), проверьте NTP",
+                        skew_ms, max_skew_ms
+                    );
+                } else {
+                    log::debug!("Расхождение часов с кластером: {} мс", skew_ms);
+                }
+            }
+            Err(e) => {
+                log::warn!("Не удалось измерить расхождение часов с кластером: {}", e);
+            }
+        }
+    }
+}
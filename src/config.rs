@@ -1,5 +1,8 @@
 use anyhow::{Context, Result};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -10,10 +13,53 @@ pub struct Config {
     pub wallet: WalletConfig,
     pub arbitrage: ArbitrageConfig,
     pub dex: DexConfig,
+    /// Токены торговых пар сверх встроенных (символ → mint и decimals)
+    #[serde(default)]
+    pub tokens: HashMap<String, TokenConfig>,
     pub monitoring: MonitoringConfig,
     pub safety: SafetyConfig,
     #[serde(default)]
     pub web: WebConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub congestion: CongestionConfig,
+    #[serde(default)]
+    pub allocation: AllocationConfig,
+    #[serde(default)]
+    pub templates: TemplatesConfig,
+    #[serde(default)]
+    pub fiat: FiatConfig,
+    #[serde(default)]
+    pub supervisor: SupervisorConfig,
+    #[serde(default)]
+    pub rpc_cache: RpcCacheConfig,
+    #[serde(default)]
+    pub price_check: PriceCheckConfig,
+    #[serde(default)]
+    pub raydium_api: RaydiumApiConfig,
+    #[serde(default)]
+    pub orca_api: OrcaApiConfig,
+    #[serde(default)]
+    pub scan_budget: ScanBudgetConfig,
+    #[serde(default)]
+    pub bus: BusConfig,
+    #[serde(default)]
+    pub self_test: SelfTestConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    #[serde(default)]
+    pub flash_loan: FlashLoanConfig,
+    #[serde(default)]
+    pub venue_scoring: VenueScoringConfig,
+    #[serde(default)]
+    pub pair_discovery: PairDiscoveryConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
 }
 
 /// Настройки сети
@@ -21,10 +67,90 @@ pub struct Config {
 pub struct NetworkConfig {
     /// RPC endpoint Solana
     pub rpc_url: String,
-    /// WebSocket URL для подписок
+    /// WebSocket URL для подписок (data_source = "websocket")
     pub ws_url: Option<String>,
     /// Уровень подтверждения транзакций
     pub commitment: String,
+    /// Имя переменной окружения с API ключом RPC провайдера (Helius, Triton, QuickNode)
+    /// Ключ не хранится в конфиге и не попадает в логи: URL в ошибках RPC не выводится
+    #[serde(default)]
+    pub rpc_api_key_env: Option<String>,
+    /// Имя query параметра для API ключа (например, "api-key" для Helius)
+    #[serde(default = "default_rpc_api_key_param")]
+    pub rpc_api_key_param: String,
+    /// Имя HTTP заголовка для API ключа (например, "x-token" для Triton); если задан,
+    /// ключ передаётся заголовком RPC запросов и WebSocket подключения, а не в URL
+    #[serde(default)]
+    pub rpc_api_key_header: Option<String>,
+    /// Дополнительные HTTP заголовки для RPC запросов
+    /// Значения вида "env:NAME" берутся из переменных окружения (.env)
+    #[serde(default)]
+    pub rpc_headers: HashMap<String, String>,
+    /// Заголовки для авторизации WebSocket подключения (формат как у rpc_headers)
+    #[serde(default)]
+    pub ws_headers: HashMap<String, String>,
+    /// Отдельный RPC endpoint для симуляций, самопроверки, `run --once` и аналитики
+    /// (снимки баланса, расходы транзакций); чтения для сделок и отправка идут через rpc_url.
+    /// API ключ rpc_api_key_env на этот endpoint не передаётся
+    #[serde(default)]
+    pub simulation_rpc_url: Option<String>,
+    /// Заголовки для simulation_rpc_url (формат как у rpc_headers)
+    #[serde(default)]
+    pub simulation_rpc_headers: HashMap<String, String>,
+    /// Интервал TCP keep-alive для RPC соединений в секундах (0 — отключить)
+    #[serde(default = "default_keep_alive_sec")]
+    pub keep_alive_sec: u64,
+    /// Источник обновлений пулов
+    #[serde(default)]
+    pub data_source: DataSource,
+    /// Endpoint Yellowstone gRPC (для data_source = "geyser")
+    #[serde(default)]
+    pub geyser_url: Option<String>,
+    /// Имя переменной окружения с x-token для Yellowstone gRPC
+    #[serde(default)]
+    pub geyser_token_env: Option<String>,
+    /// Прокси для исходящих соединений
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+}
+
+/// Прокси для исходящих соединений (`[network.proxy]`)
+///
+/// Значение — URL прокси `http://`, `https://`, `socks5://` (адрес цели
+/// разрешается локально) или `socks5h://` (разрешает прокси), учётные данные
+/// в URL; "env:NAME" — URL из переменной окружения; "direct" — без прокси.
+/// Endpoint без своего значения использует `url`; без настройки HTTP клиенты
+/// следуют переменным окружения HTTP_PROXY/HTTPS_PROXY.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// Прокси по умолчанию для всех endpoint
+    pub url: Option<String>,
+    /// RPC (rpc_url)
+    pub rpc: Option<String>,
+    /// RPC симуляций и аналитики (simulation_rpc_url)
+    pub simulation_rpc: Option<String>,
+    /// Подписка Yellowstone gRPC (geyser_url): только http:// и socks5
+    pub geyser: Option<String>,
+    /// Подписка WebSocket (ws_url): только http:// и socks5
+    pub ws: Option<String>,
+    /// HTTP API пулов DEX, сверки цен и курсов
+    pub http: Option<String>,
+    /// Каналы уведомлений (Telegram, вебхуки)
+    pub notifications: Option<String>,
+}
+
+/// Источник обновлений состояния пулов
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    /// Опрос RPC каждые check_interval_ms
+    #[default]
+    Rpc,
+    /// Подписка accountSubscribe через WebSocket
+    Websocket,
+    /// Подписка через Yellowstone gRPC (Geyser плагин)
+    Geyser,
 }
 
 /// Настройки кошелька
@@ -41,10 +167,186 @@ pub struct ArbitrageConfig {
     pub min_profit_percent: f64,
     /// Максимальный объём сделки в SOL
     pub max_trade_amount_sol: f64,
-    /// Допустимое проскальзывание в процентах
+    /// Допустимое проскальзывание в процентах (для ног и типов DEX без своего значения)
     pub slippage_tolerance: f64,
+    /// Проскальзывание для ноги покупки, в процентах
+    #[serde(default)]
+    pub buy_slippage_tolerance: Option<f64>,
+    /// Проскальзывание для ноги продажи, в процентах
+    #[serde(default)]
+    pub sell_slippage_tolerance: Option<f64>,
+    /// Проскальзывание по типу DEX, в процентах (orderbook — уже, CLMM — шире)
+    #[serde(default)]
+    pub slippage_by_venue_type: HashMap<VenueType, f64>,
     /// Таймаут транзакции в секундах
     pub transaction_timeout_sec: u64,
+    /// Наибольший возраст цены любой ноги перед исполнением, мс (0 — не проверять)
+    #[serde(default = "default_max_price_age_ms")]
+    pub max_price_age_ms: u64,
+    /// Наибольшая разница слотов цен ног, при которой они сравниваются (0 — не проверять)
+    #[serde(default = "default_max_slot_skew")]
+    pub max_slot_skew: u64,
+    /// Стратегия ранжирования найденных возможностей
+    #[serde(default)]
+    pub ranking: RankingStrategy,
+    /// Условие перехода к продаже в двухэтапном арбитраже
+    #[serde(default)]
+    pub leg_gate: LegGate,
+    /// Уровень подтверждения покупки для `leg_gate = "confirmed"`
+    #[serde(default)]
+    pub leg_gate_commitment: GateCommitment,
+    /// Пауза между отправкой покупки и продажей для `leg_gate = "optimistic"`, мс
+    #[serde(default)]
+    pub inter_leg_delay_ms: u64,
+    /// Покупка и продажа одной транзакцией, если оба DEX это поддерживают
+    #[serde(default = "default_true")]
+    pub atomic_execution: bool,
+    /// Минимальная прибыль сделки в абсолютных суммах (в дополнение к min_profit_percent)
+    #[serde(default)]
+    pub min_profit_absolute: MinProfitAbsolute,
+    /// Точность и правило округления расчётных сумм
+    #[serde(default)]
+    pub rounding: RoundingConfig,
+}
+
+/// Порог прибыли в абсолютных суммах (`[arbitrage.min_profit_absolute]`)
+///
+/// Сравнивается с прибылью после комиссий DEX и сетевой комиссии. Если для
+/// пересчёта в нужную единицу нет курса, возможность пропускается.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MinProfitAbsolute {
+    /// В котируемом токене пары
+    #[serde(default)]
+    pub quote: Option<f64>,
+    /// В USD
+    #[serde(default)]
+    pub usd: Option<f64>,
+}
+
+/// Округление расчётных сумм (`[arbitrage.rounding]`)
+///
+/// Правило `outputs` применяется к ожидаемым выходам и прибыли. Вход свопа и
+/// минимальный выход округляются вниз всегда, независимо от настройки.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundingConfig {
+    #[serde(default)]
+    pub outputs: OutputRounding,
+    /// Знаков после запятой для сумм в котируемом токене
+    #[serde(default = "default_quote_decimal_places")]
+    pub quote_decimal_places: u32,
+    /// Знаков после запятой для сумм в USD
+    #[serde(default = "default_usd_decimal_places")]
+    pub usd_decimal_places: u32,
+}
+
+impl Default for RoundingConfig {
+    fn default() -> Self {
+        Self {
+            outputs: OutputRounding::default(),
+            quote_decimal_places: default_quote_decimal_places(),
+            usd_decimal_places: default_usd_decimal_places(),
+        }
+    }
+}
+
+/// Правило округления ожидаемых выходов и прибыли
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputRounding {
+    /// Половина — к ближайшему чётному: ошибки округления не копятся в одну сторону
+    #[default]
+    Bankers,
+    /// Вниз: расчётная сумма никогда не завышается
+    Floor,
+}
+
+impl ArbitrageConfig {
+    /// Проскальзывание для ноги покупки на DEX указанного типа
+    pub fn buy_slippage(&self, venue: VenueType) -> Decimal {
+        self.leg_slippage(self.buy_slippage_tolerance, venue)
+    }
+
+    /// Проскальзывание для ноги продажи на DEX указанного типа
+    pub fn sell_slippage(&self, venue: VenueType) -> Decimal {
+        self.leg_slippage(self.sell_slippage_tolerance, venue)
+    }
+
+    /// Приоритет: значение ноги, затем значение типа DEX, затем общее slippage_tolerance
+    fn leg_slippage(&self, leg: Option<f64>, venue: VenueType) -> Decimal {
+        leg.or_else(|| self.slippage_by_venue_type.get(&venue).copied())
+            .and_then(Decimal::from_f64)
+            .or_else(|| Decimal::from_f64(self.slippage_tolerance))
+            .unwrap_or(Decimal::ONE)
+    }
+}
+
+/// Тип площадки (определяет допустимое проскальзывание по умолчанию)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VenueType {
+    /// Пул AMM: constant product (Raydium AMM v4), с ценой от оракула (Lifinity) или stable swap (Saber)
+    Amm,
+    /// Пул с концентрированной ликвидностью (Orca Whirlpool)
+    Clmm,
+    /// Книга ордеров (Serum/OpenBook)
+    Orderbook,
+}
+
+impl VenueType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VenueType::Amm => "amm",
+            VenueType::Clmm => "clmm",
+            VenueType::Orderbook => "orderbook",
+        }
+    }
+}
+
+/// Условие перехода от покупки к продаже
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegGate {
+    /// Продажа после подтверждения покупки (объём — по фактически полученному)
+    #[default]
+    Confirmed,
+    /// Продажа сразу после отправки покупки (и паузы `inter_leg_delay_ms`)
+    Optimistic,
+}
+
+/// Уровень подтверждения транзакции
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GateCommitment {
+    Processed,
+    #[default]
+    Confirmed,
+    Finalized,
+}
+
+impl GateCommitment {
+    pub fn commitment_config(&self) -> solana_sdk::commitment_config::CommitmentConfig {
+        use solana_sdk::commitment_config::CommitmentConfig;
+        match self {
+            GateCommitment::Processed => CommitmentConfig::processed(),
+            GateCommitment::Confirmed => CommitmentConfig::confirmed(),
+            GateCommitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+/// Стратегия ранжирования арбитражных возможностей
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingStrategy {
+    /// Процент прибыли после комиссий
+    #[default]
+    ProfitPercent,
+    /// Абсолютная ожидаемая прибыль в quote токене
+    ExpectedProfit,
+    /// Ожидаемая прибыль, умноженная на историческую вероятность исполнения маршрута
+    FillProbability,
+    /// Наименьший риск исполнения (доля неудачных сделок по маршруту)
+    LowestRisk,
 }
 
 /// Настройки DEX
@@ -54,6 +356,103 @@ pub struct DexConfig {
     pub enabled_dexes: Vec<String>,
     /// Торговые пары для мониторинга
     pub trading_pairs: Vec<String>,
+    /// Максимум одновременных транзакций на один DEX
+    #[serde(default = "default_max_in_flight_per_dex")]
+    pub max_in_flight_per_dex: usize,
+    /// Переопределение лимита для отдельных DEX
+    #[serde(default)]
+    pub max_in_flight: HashMap<String, usize>,
+    /// Пары, торгуемые на отдельных DEX (DEX без записи торгует всеми trading_pairs)
+    #[serde(default)]
+    pub pairs: HashMap<String, Vec<String>>,
+    /// Разрешённые направления сделок по парам: маршруты `"<DEX покупки>-><DEX продажи>"`
+    /// (пара без записи торгуется в обе стороны между всеми DEX)
+    #[serde(default)]
+    pub directions: HashMap<String, Vec<String>>,
+    /// Минимальный объём сделки на DEX в base токене (переопределяет значения по умолчанию)
+    #[serde(default)]
+    pub min_trade_amount: HashMap<String, f64>,
+    /// Пользовательские AMM x*y=k (`[dex.custom.<имя>]`); включаются по имени в enabled_dexes
+    #[serde(default)]
+    pub custom: HashMap<String, CustomAmmConfig>,
+}
+
+/// Токен торговых пар (`[tokens.<символ>]`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenConfig {
+    /// Адрес mint
+    pub mint: String,
+    /// Decimals mint (None — читаются из аккаунта mint)
+    #[serde(default)]
+    pub decimals: Option<u8>,
+}
+
+/// Описание AMM x*y=k, для которого нет отдельного адаптера
+///
+/// Смещения полей считаются от начала данных аккаунта пула (вместе с discriminator Anchor).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomAmmConfig {
+    /// Program ID AMM
+    pub program_id: String,
+    /// Размер аккаунта пула в байтах (None — пулы ищутся без фильтра по размеру)
+    #[serde(default)]
+    pub account_size: Option<u64>,
+    pub token_a_mint_offset: usize,
+    pub token_b_mint_offset: usize,
+    /// Смещения адресов хранилищ (SPL токен-аккаунтов) резервов пула
+    pub token_a_vault_offset: usize,
+    pub token_b_vault_offset: usize,
+    /// Смещения комиссии пула (u64 numerator и denominator); задаются вместе
+    #[serde(default)]
+    pub fee_numerator_offset: Option<usize>,
+    #[serde(default)]
+    pub fee_denominator_offset: Option<usize>,
+    /// Комиссия в процентах, если пул её не хранит (None — типичная 0.25%)
+    #[serde(default)]
+    pub fee_percent: Option<f64>,
+    /// Данные инструкции swap перед amount_in и min_amount_out (тег или discriminator)
+    #[serde(default)]
+    pub swap_data_prefix: Vec<u8>,
+    /// Аккаунты инструкции swap по порядку (пусто — DEX только для цен, без исполнения)
+    ///
+    /// Роли: pool, authority (PDA от адреса пула), user, user_source, user_destination,
+    /// pool_source, pool_destination, token_program; адрес — аккаунт только для чтения,
+    /// `mut:<адрес>` — изменяемый.
+    #[serde(default)]
+    pub swap_accounts: Vec<String>,
+}
+
+impl DexConfig {
+    /// Торгуется ли пара на DEX
+    pub fn lists_pair(&self, dex: &str, pair: &str) -> bool {
+        self.pairs
+            .get(dex)
+            .is_none_or(|pairs| pairs.iter().any(|listed| listed == pair))
+    }
+
+    /// Разрешено ли покупать пару на `buy_dex` и продавать на `sell_dex`
+    pub fn direction_allowed(&self, pair: &str, buy_dex: &str, sell_dex: &str) -> bool {
+        self.directions.get(pair).is_none_or(|routes| {
+            routes.iter()
+                .filter_map(|route| parse_direction(route))
+                .any(|(buy, sell)| buy == buy_dex && sell == sell_dex)
+        })
+    }
+
+    /// Минимальный объём сделки на DEX
+    pub fn min_trade_amount(&self, dex: &str) -> Decimal {
+        self.min_trade_amount
+            .get(dex)
+            .and_then(|&amount| Decimal::from_f64(amount))
+            .unwrap_or_else(|| crate::dex::default_min_trade_amount(dex))
+    }
+}
+
+/// Маршрут `"<DEX покупки>-><DEX продажи>"` из dex.directions
+pub fn parse_direction(route: &str) -> Option<(&str, &str)> {
+    let (buy, sell) = route.split_once("->")?;
+    let (buy, sell) = (buy.trim(), sell.trim());
+    (!buy.is_empty() && !sell.is_empty() && buy != sell).then_some((buy, sell))
 }
 
 /// Настройки мониторинга
@@ -65,17 +464,102 @@ pub struct MonitoringConfig {
     pub log_level: String,
     /// Путь к файлу логов
     pub log_file: PathBuf,
+    /// Интервал проверки расхождения часов с кластером в секундах
+    #[serde(default = "default_clock_skew_check_interval_sec")]
+    pub clock_skew_check_interval_sec: u64,
+    /// Допустимое расхождение локальных часов с кластером в миллисекундах
+    #[serde(default = "default_max_clock_skew_ms")]
+    pub max_clock_skew_ms: u64,
+    /// Интервал снимков баланса кошелька в секундах (0 — отключить)
+    #[serde(default = "default_balance_snapshot_interval_sec")]
+    pub balance_snapshot_interval_sec: u64,
+    /// Время без завершённого прогона цикла, после которого он считается зависшим
+    /// (секунды): /health отвечает 503, пинги watchdog systemd прекращаются
+    #[serde(default = "default_scan_stall_timeout_sec")]
+    pub scan_stall_timeout_sec: u64,
+    /// Интервал проверки доступности RPC и минимального баланса для
+    /// /api/metrics/prometheus в секундах (0 — отключить)
+    #[serde(default = "default_state_probe_interval_sec")]
+    pub state_probe_interval_sec: u64,
+    /// Чтение аккаунтов пулов одним снимком на прогон поиска (при data_source = "rpc")
+    #[serde(default = "default_true")]
+    pub consistent_snapshot: bool,
+}
+
+impl MonitoringConfig {
+    /// Порог зависания цикла поиска
+    pub fn scan_stall_timeout(&self) -> chrono::Duration {
+        chrono::Duration::from_std(std::time::Duration::from_secs(self.scan_stall_timeout_sec))
+            .unwrap_or(chrono::Duration::MAX)
+    }
 }
 
 /// Настройки безопасности
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SafetyConfig {
+    /// Режим работы: исполнение сделок или только публикация сигналов
+    #[serde(default)]
+    pub mode: ExecutionMode,
     /// Режим симуляции (не выполняет реальные транзакции)
     pub simulation_mode: bool,
-    /// Максимальное количество последовательных ошибок
+    /// Максимальное количество последовательных неудач исполнения
+    /// (проскальзывание, пустой пул); ошибки баланса и конфигурации останавливают сразу
     pub max_consecutive_failures: u32,
     /// Минимальный баланс SOL для продолжения работы
     pub min_balance_sol: f64,
+    /// Количество временных сбоев RPC подряд, после которого исполнение приостанавливается (0 — отключить)
+    #[serde(default = "default_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32,
+    /// Пауза исполнения после срабатывания автомата, в секундах
+    #[serde(default = "default_circuit_breaker_cooldown_sec")]
+    pub circuit_breaker_cooldown_sec: u64,
+    /// Токены, с которыми разрешено исполнение (пусто — без ограничения);
+    /// пары с другими токенами только отслеживаются
+    #[serde(default)]
+    pub allowed_tokens: Vec<String>,
+    /// Изменение резервов пула с предыдущего прогона поиска, при котором пара
+    /// откладывается на прогон, в процентах (0 — не проверять)
+    #[serde(default)]
+    pub max_reserve_change_percent: f64,
+}
+
+impl SafetyConfig {
+    /// Разрешено ли исполнение сделок с токеном
+    pub fn token_allowed(&self, token: &str) -> bool {
+        self.allowed_tokens.is_empty() || self.allowed_tokens.iter().any(|allowed| allowed == token)
+    }
+
+    /// Порог изменения резервов пула в процентах (0 — не проверять)
+    pub fn reserve_change_limit(&self) -> Decimal {
+        Decimal::from_f64(self.max_reserve_change_percent).unwrap_or(Decimal::ZERO)
+    }
+}
+
+/// Режим работы бота
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// Поиск и исполнение сделок (реально или в симуляции — см. simulation_mode)
+    #[default]
+    Execute,
+    /// Только поиск и публикация возможностей (API, WebSocket, уведомления), без исполнения
+    Signal,
+    /// Только поиск: возможности уходят в очередь [bus] процессу исполнения, ключ не загружается
+    Scan,
+    /// Только исполнение возможностей из очереди [bus], без собственного поиска
+    ExecuteOnly,
+}
+
+impl ExecutionMode {
+    /// Исполняет ли процесс сделки
+    pub fn executes(&self) -> bool {
+        matches!(self, Self::Execute | Self::ExecuteOnly)
+    }
+
+    /// Нужна ли очередь [bus] между процессами
+    pub fn uses_bus(&self) -> bool {
+        matches!(self, Self::Scan | Self::ExecuteOnly)
+    }
 }
 
 /// Настройки веб-сервера
@@ -93,79 +577,1451 @@ pub struct WebConfig {
     /// Путь к статическим файлам
     #[serde(default = "default_static_dir")]
     pub static_dir: PathBuf,
+    /// Открыть /public/status без авторизации (без балансов и данных кошелька)
+    #[serde(default)]
+    pub public_status: bool,
+    /// Сколько запрос ждёт движок, занятый торговым циклом, прежде чем вернуть engine_busy (мс)
+    #[serde(default = "default_engine_busy_timeout_ms")]
+    pub engine_busy_timeout_ms: u64,
 }
 
-fn default_true() -> bool {
-    true
+impl Default for WebConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            port: default_web_port(),
+            bind_address: default_bind_address(),
+            static_dir: default_static_dir(),
+            public_status: false,
+            engine_busy_timeout_ms: default_engine_busy_timeout_ms(),
+        }
+    }
 }
 
-fn default_web_port() -> u16 {
-    8080
+/// Настройки уведомлений
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Включить отправку уведомлений
+    #[serde(default)]
+    pub enabled: bool,
+    /// Каналы доставки уведомлений
+    #[serde(default)]
+    pub sinks: Vec<NotificationSinkConfig>,
 }
 
-fn default_bind_address() -> String {
-    "127.0.0.1".to_string()
+/// Настройки канала уведомлений
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSinkConfig {
+    /// Тип канала
+    pub kind: NotificationSinkKind,
+    /// Режим доставки: каждое событие сразу или сводкой
+    #[serde(default)]
+    pub mode: NotificationMode,
+    /// Интервал отправки сводки в секундах (для mode = "digest")
+    #[serde(default = "default_digest_interval_sec")]
+    pub digest_interval_sec: u64,
+    /// Шаблон сводки; если не задан, используется шаблон по умолчанию
+    #[serde(default)]
+    pub digest_template: Option<String>,
+    /// Имя переменной окружения с токеном Telegram бота
+    #[serde(default = "default_telegram_token_env")]
+    pub bot_token_env: String,
+    /// Имя переменной окружения с chat id Telegram
+    #[serde(default = "default_telegram_chat_id_env")]
+    pub chat_id_env: String,
+    /// Имя переменной окружения с URL вебхука
+    #[serde(default = "default_webhook_url_env")]
+    pub url_env: String,
 }
 
-fn default_static_dir() -> PathBuf {
-    if cfg!(windows) {
-        PathBuf::from("static")
-    } else {
-        PathBuf::from("/opt/arb-bot/static")
+/// Тип канала уведомлений
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationSinkKind {
+    /// Сообщения в лог
+    Log,
+    /// Сообщения в Telegram через Bot API
+    Telegram,
+    /// JSON POST на произвольный URL
+    Webhook,
+}
+
+/// Режим доставки уведомлений
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationMode {
+    /// Каждое событие отправляется сразу
+    #[default]
+    Immediate,
+    /// События собираются в сводку; критичные алерты отправляются сразу
+    Digest,
+}
+
+/// Настройки хранилища истории
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Каталог с файлами истории
+    #[serde(default = "default_data_dir")]
+    pub data_dir: PathBuf,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: default_data_dir(),
+        }
     }
 }
 
-impl Config {
-    /// Загрузка конфигурации из файла
-    pub fn load() -> Result<Self> {
-        // Путь к config.toml согласно правилам проекта
-        let config_path = if cfg!(windows) {
-            // На Windows используем текущую директорию для разработки
-            PathBuf::from("config.toml")
-        } else {
-            PathBuf::from("/opt/arb-bot/config.toml")
-        };
+/// Заготовки транзакций свопа для приоритетных пар
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplatesConfig {
+    /// Собирать шаблоны при запуске
+    #[serde(default)]
+    pub enabled: bool,
+    /// Пары, для которых собираются шаблоны (в обоих направлениях)
+    #[serde(default)]
+    pub pairs: Vec<String>,
+    /// Лимит compute units (0 — не задавать)
+    #[serde(default = "default_compute_unit_limit")]
+    pub compute_unit_limit: u32,
+    /// Priority fee в micro-lamports за CU (0 — без priority fee)
+    #[serde(default)]
+    pub compute_unit_price_micro_lamports: u64,
+    /// Адреса address lookup tables для сжатия списка аккаунтов
+    #[serde(default)]
+    pub lookup_tables: Vec<String>,
+}
 
-        // Загрузка переменных окружения из .env
-        dotenv::dotenv().ok();
+impl Default for TemplatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pairs: Vec::new(),
+            compute_unit_limit: default_compute_unit_limit(),
+            compute_unit_price_micro_lamports: 0,
+            lookup_tables: Vec::new(),
+        }
+    }
+}
 
-        // Чтение и парсинг config.toml
-        let content = fs::read_to_string(&config_path)
-            .with_context(|| format!("Не удалось прочитать конфигурацию: {:?}", config_path))?;
+/// Распределение капитала между одновременными возможностями
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationConfig {
+    /// Делить капитал по ожидаемой прибыли (иначе — исполнение по очереди полным объёмом)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Капитал на один цикл сканирования, в SOL
+    #[serde(default = "default_allocation_capital_sol")]
+    pub capital_sol: f64,
+    /// Максимальная доля капитала на одну торговую пару, в процентах
+    #[serde(default = "default_max_exposure_percent")]
+    pub max_pair_percent: f64,
+    /// Максимальная доля капитала на один DEX (обе ноги), в процентах
+    #[serde(default = "default_max_exposure_percent")]
+    pub max_dex_percent: f64,
+    /// Исполнять одновременно возможности без общих токенов и DEX
+    #[serde(default)]
+    pub parallel_execution: bool,
+    /// Максимальное количество одновременно исполняемых возможностей
+    #[serde(default = "default_max_parallel_executions")]
+    pub max_parallel_executions: usize,
+}
 
-        let mut config: Config = toml::from_str(&content)
-            .context("Ошибка парсинга config.toml")?;
+impl Default for AllocationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capital_sol: default_allocation_capital_sol(),
+            max_pair_percent: default_max_exposure_percent(),
+            max_dex_percent: default_max_exposure_percent(),
+            parallel_execution: false,
+            max_parallel_executions: default_max_parallel_executions(),
+        }
+    }
+}
 
-        // Валидация конфигурации
-        config.validate()?;
+impl AllocationConfig {
+    /// Капитал на цикл
+    pub fn capital(&self) -> Decimal {
+        Decimal::from_f64(self.capital_sol).unwrap_or(Decimal::ZERO)
+    }
 
-        Ok(config)
+    /// Лимит на одну торговую пару
+    pub fn pair_cap(&self) -> Decimal {
+        self.capital() * Decimal::from_f64(self.max_pair_percent).unwrap_or(Decimal::ZERO) / Decimal::from(100)
     }
 
-    /// Валидация конфигурации
-    fn validate(&self) -> Result<()> {
-        // Проверка обязательных полей
-        if self.network.rpc_url.is_empty() {
-            anyhow::bail!("rpc_url не может быть пустым");
+    /// Лимит на один DEX
+    pub fn dex_cap(&self) -> Decimal {
+        self.capital() * Decimal::from_f64(self.max_dex_percent).unwrap_or(Decimal::ZERO) / Decimal::from(100)
+    }
+}
+
+/// Определение перегрузки сети
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CongestionConfig {
+    /// Повышать порог прибыли во время перегрузки
+    #[serde(default)]
+    pub enabled: bool,
+    /// Интервал проверки загрузки сети в секундах
+    #[serde(default = "default_congestion_check_interval_sec")]
+    pub check_interval_sec: u64,
+    /// Медиана priority fee (micro-lamports за CU), выше которой сеть считается перегруженной
+    #[serde(default = "default_priority_fee_threshold")]
+    pub priority_fee_threshold: u64,
+    /// Транзакций (без голосований) на слот, выше которого сеть считается перегруженной
+    #[serde(default = "default_tx_per_slot_threshold")]
+    pub tx_per_slot_threshold: u64,
+    /// Надбавка к min_profit_percent во время перегрузки, в процентах
+    #[serde(default = "default_congestion_profit_margin")]
+    pub min_profit_margin_percent: f64,
+}
+
+impl Default for CongestionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            check_interval_sec: default_congestion_check_interval_sec(),
+            priority_fee_threshold: default_priority_fee_threshold(),
+            tx_per_slot_threshold: default_tx_per_slot_threshold(),
+            min_profit_margin_percent: default_congestion_profit_margin(),
         }
+    }
+}
 
-        if self.arbitrage.min_profit_percent <= 0.0 {
-            anyhow::bail!("min_profit_percent должен быть больше 0");
+/// Перезапуск упавших фоновых задач
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    /// Пауза перед первым перезапуском в миллисекундах (удваивается с каждым падением подряд)
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Максимальная пауза перед перезапуском в секундах
+    #[serde(default = "default_max_backoff_sec")]
+    pub max_backoff_sec: u64,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_sec: default_max_backoff_sec(),
         }
+    }
+}
 
-        if self.arbitrage.max_trade_amount_sol <= 0.0 {
-            anyhow::bail!("max_trade_amount_sol должен быть больше 0");
+/// Кеш RPC для неизменяемых данных (decimals mint, статические поля рынков)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcCacheConfig {
+    /// Кешировать неизменяемые данные
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Сохранять кеш в каталог данных между перезапусками
+    #[serde(default = "default_true")]
+    pub persist: bool,
+    /// Время жизни decimals mint в секундах
+    #[serde(default = "default_mint_ttl_sec")]
+    pub mint_ttl_sec: u64,
+    /// Время жизни статических полей пулов и рынков в секундах
+    #[serde(default = "default_market_ttl_sec")]
+    pub market_ttl_sec: u64,
+    /// Время жизни списков аккаунтов программ в секундах
+    #[serde(default = "default_program_accounts_ttl_sec")]
+    pub program_accounts_ttl_sec: u64,
+    /// Время жизни пулов торговых пар из реестра в секундах
+    /// (после истечения пул ищется заново: ликвидность переходит между пулами)
+    #[serde(default = "default_pool_ttl_sec")]
+    pub pool_ttl_sec: u64,
+}
+
+impl Default for RpcCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            persist: true,
+            mint_ttl_sec: default_mint_ttl_sec(),
+            market_ttl_sec: default_market_ttl_sec(),
+            program_accounts_ttl_sec: default_program_accounts_ttl_sec(),
+            pool_ttl_sec: default_pool_ttl_sec(),
         }
+    }
+}
 
-        if self.monitoring.check_interval_ms == 0 {
-            anyhow::bail!("check_interval_ms должен быть больше 0");
+/// Raydium API v3: поиск пулов, комиссия и ликвидность пулов
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaydiumApiConfig {
+    /// Искать пулы через API (при недоступности API — поиск on-chain)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Базовый URL Raydium API v3
+    #[serde(default = "default_raydium_api_url")]
+    pub base_url: String,
+    /// Время жизни ответов API в кеше, в секундах
+    #[serde(default = "default_raydium_api_cache_ttl_sec")]
+    pub cache_ttl_sec: u64,
+}
+
+impl Default for RaydiumApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            base_url: default_raydium_api_url(),
+            cache_ttl_sec: default_raydium_api_cache_ttl_sec(),
         }
+    }
+}
 
-        if self.safety.simulation_mode {
-            log::warn!("⚠️  Режим симуляции активен - реальные транзакции не выполняются");
+/// Orca API: список whirlpools для поиска пулов и fee tier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrcaApiConfig {
+    /// Искать whirlpools через API (при недоступности API — поиск on-chain)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Базовый URL Orca API со списком whirlpools
+    #[serde(default = "default_orca_whirlpool_api_url")]
+    pub base_url: String,
+    /// Интервал обновления списка whirlpools и найденных пулов, в секундах
+    #[serde(default = "default_orca_api_refresh_interval_sec")]
+    pub refresh_interval_sec: u64,
+}
+
+impl Default for OrcaApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            base_url: default_orca_whirlpool_api_url(),
+            refresh_interval_sec: default_orca_api_refresh_interval_sec(),
         }
+    }
+}
 
-        Ok(())
+/// Сверка on-chain цен DEX с HTTP API котировок
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceCheckConfig {
+    /// Сверять цены (HTTP запрос не чаще interval_sec на DEX и пару)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Минимальный интервал между сверками одной пары на одном DEX, в секундах
+    #[serde(default = "default_price_check_interval_sec")]
+    pub interval_sec: u64,
+    /// Расхождение on-chain и HTTP цены, при котором отправляется алерт, в процентах
+    #[serde(default = "default_price_check_max_divergence_percent")]
+    pub max_divergence_percent: f64,
+    /// HTTP источник котировок для каждого DEX: "raydium_api", "orca_api", "jupiter"
+    #[serde(default = "default_price_check_sources")]
+    pub sources: HashMap<String, String>,
+    /// Базовый URL Raydium API v3
+    #[serde(default = "default_raydium_api_url")]
+    pub raydium_api_url: String,
+    /// Базовый URL Orca API
+    #[serde(default = "default_orca_api_url")]
+    pub orca_api_url: String,
+    /// Базовый URL Jupiter Price API
+    #[serde(default = "default_jupiter_api_url")]
+    pub jupiter_api_url: String,
+}
+
+impl Default for PriceCheckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_sec: default_price_check_interval_sec(),
+            max_divergence_percent: default_price_check_max_divergence_percent(),
+            sources: default_price_check_sources(),
+            raydium_api_url: default_raydium_api_url(),
+            orca_api_url: default_orca_api_url(),
+            jupiter_api_url: default_jupiter_api_url(),
+        }
+    }
+}
+
+/// Бюджет запросов RPC на один интервал проверки
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanBudgetConfig {
+    /// Максимум запросов за прогон поиска (0 — без ограничения); при нехватке
+    /// в первую очередь проверяются пары, где недавно находились возможности
+    #[serde(default)]
+    pub max_requests: u32,
+    /// Стоимость котировки пары на DEX в запросах RPC (по умолчанию 1)
+    #[serde(default)]
+    pub request_cost: HashMap<String, u32>,
+}
+
+impl ScanBudgetConfig {
+    /// Стоимость котировки пары на DEX
+    pub fn request_cost(&self, dex: &str) -> u32 {
+        self.request_cost.get(dex).copied().unwrap_or(1)
     }
 }
 
+/// Очередь возможностей между процессом поиска (mode = "scan") и исполнения (mode = "execute_only")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusConfig {
+    /// Адрес Redis: redis://host:port[/db]
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Переменная окружения с паролем Redis
+    #[serde(default)]
+    pub password_env: Option<String>,
+    /// Ключ списка Redis с возможностями
+    #[serde(default = "default_bus_queue")]
+    pub queue: String,
+    /// Возможности старше этого возраста не исполняются, в миллисекундах
+    #[serde(default = "default_bus_max_age_ms")]
+    pub max_age_ms: u64,
+    /// Максимальная длина очереди (при переполнении вытесняются самые старые)
+    #[serde(default = "default_bus_max_queue_len")]
+    pub max_queue_len: usize,
+}
+
+fn default_bus_queue() -> String {
+    "arb-bot:opportunities".to_string()
+}
+
+fn default_bus_max_age_ms() -> u64 {
+    2000
+}
+
+fn default_bus_max_queue_len() -> usize {
+    100
+}
+
+impl Default for BusConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            password_env: None,
+            queue: default_bus_queue(),
+            max_age_ms: default_bus_max_age_ms(),
+            max_queue_len: default_bus_max_queue_len(),
+        }
+    }
+}
+
+/// Самопроверка адаптеров DEX при запуске (только devnet или simulation_mode)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SelfTestConfig {
+    /// Прогнать цену, котировку, сборку и симуляцию свопа на каждом DEX перед торговлей
+    #[serde(default)]
+    pub enabled: bool,
+    /// Пара для проверки (по умолчанию — первая пара из trading_pairs, торгуемая на DEX)
+    #[serde(default)]
+    pub pair: Option<String>,
+    /// Объём свопа в base токене (по умолчанию — минимальный объём сделки на DEX)
+    #[serde(default)]
+    pub amount: Option<f64>,
+}
+
+/// Поиск замкнутых маршрутов через несколько пар (например USDC -> SOL -> BONK -> USDC)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Искать маршруты по графу токенов из цен всех пар на всех DEX
+    #[serde(default)]
+    pub enabled: bool,
+    /// Максимальное количество обменов в маршруте (от 3 до 5)
+    #[serde(default = "default_routing_max_hops")]
+    pub max_hops: usize,
+}
+
+fn default_routing_max_hops() -> usize {
+    3
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_hops: default_routing_max_hops(),
+        }
+    }
+}
+
+/// Оценка DEX для ноги сделки, когда ногу может исполнить несколько DEX
+///
+/// Оценка DEX: `landing_weight · доля исполненных ног − slippage_weight ·
+/// среднее проскальзывание (%) − fee_weight · комиссия пула (%) −
+/// latency_weight · среднее время подтверждения (с)`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueScoringConfig {
+    /// Упорядочивать возможности одной пары по оценке DEX обеих ног
+    #[serde(default)]
+    pub enabled: bool,
+    /// Вес доли исполненных ног
+    #[serde(default = "default_venue_weight")]
+    pub landing_weight: f64,
+    /// Вес среднего фактического проскальзывания
+    #[serde(default = "default_venue_weight")]
+    pub slippage_weight: f64,
+    /// Вес комиссии пула
+    #[serde(default = "default_venue_weight")]
+    pub fee_weight: f64,
+    /// Вес среднего времени подтверждения
+    #[serde(default = "default_venue_weight")]
+    pub latency_weight: f64,
+}
+
+fn default_venue_weight() -> f64 {
+    1.0
+}
+
+impl Default for VenueScoringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            landing_weight: default_venue_weight(),
+            slippage_weight: default_venue_weight(),
+            fee_weight: default_venue_weight(),
+            latency_weight: default_venue_weight(),
+        }
+    }
+}
+
+impl VenueScoringConfig {
+    /// Вес в Decimal (некорректное значение отсеивает validate)
+    pub fn weight(value: f64) -> Decimal {
+        Decimal::from_f64(value).unwrap_or(Decimal::ZERO)
+    }
+}
+
+/// Торговые пары из токенов кошелька: токен с балансом выше порога
+/// сканируется в паре с каждым из quote_tokens сверх dex.trading_pairs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairDiscoveryConfig {
+    /// Добавлять пары из токенов кошелька
+    #[serde(default)]
+    pub enabled: bool,
+    /// Quote токены найденных пар
+    #[serde(default = "default_discovery_quote_tokens")]
+    pub quote_tokens: Vec<String>,
+    /// Баланс токена (в целых токенах), выше которого токен становится base токеном пары
+    #[serde(default)]
+    pub min_balance: f64,
+    /// Интервал перечитывания балансов кошелька в секундах
+    #[serde(default = "default_discovery_interval_sec")]
+    pub interval_sec: u64,
+}
+
+fn default_discovery_quote_tokens() -> Vec<String> {
+    vec!["USDC".to_string()]
+}
+
+fn default_discovery_interval_sec() -> u64 {
+    300
+}
+
+impl Default for PairDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            quote_tokens: default_discovery_quote_tokens(),
+            min_balance: 0.0,
+            interval_sec: default_discovery_interval_sec(),
+        }
+    }
+}
+
+/// Регулярные задачи обслуживания (планировщик `jobs`)
+///
+/// Расписание — cron из 5 полей по UTC (`30 3 * * *`), `@every 10m`,
+/// `@hourly`, `@daily`, `@weekly` или `off`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// Сводка сделок за сутки в уведомления
+    #[serde(default = "default_daily_report_schedule")]
+    pub daily_report: String,
+    /// Сжатие журналов хранилища
+    #[serde(default = "default_history_compaction_schedule")]
+    pub history_compaction: String,
+    /// Снимки баланса кошелька (по умолчанию — каждые monitoring.balance_snapshot_interval_sec)
+    #[serde(default)]
+    pub balance_snapshot: Option<String>,
+    /// Пары из токенов кошелька (по умолчанию — каждые pair_discovery.interval_sec)
+    #[serde(default)]
+    pub pair_discovery: Option<String>,
+    /// Поиск пулов торговых пар через реестры DEX вне цикла поиска
+    #[serde(default = "default_pool_registry_refresh_schedule")]
+    pub pool_registry_refresh: String,
+    /// Перечитывание ALT (templates.lookup_tables) и пересборка шаблонов транзакций
+    #[serde(default = "default_lookup_tables_refresh_schedule")]
+    pub lookup_tables_refresh: String,
+    /// Закрытие пустых токен-аккаунтов кошелька с возвратом ренты (по умолчанию выключено)
+    #[serde(default = "default_ata_cleanup_schedule")]
+    pub ata_cleanup: String,
+    /// Срок хранения снимков баланса и найденных возможностей в днях (0 — без ограничения)
+    #[serde(default)]
+    pub retention_days: u32,
+}
+
+fn default_daily_report_schedule() -> String {
+    "0 0 * * *".to_string()
+}
+
+fn default_history_compaction_schedule() -> String {
+    "30 3 * * *".to_string()
+}
+
+fn default_pool_registry_refresh_schedule() -> String {
+    "@every 10m".to_string()
+}
+
+fn default_lookup_tables_refresh_schedule() -> String {
+    "@every 1h".to_string()
+}
+
+fn default_ata_cleanup_schedule() -> String {
+    "off".to_string()
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            daily_report: default_daily_report_schedule(),
+            history_compaction: default_history_compaction_schedule(),
+            balance_snapshot: None,
+            pair_discovery: None,
+            pool_registry_refresh: default_pool_registry_refresh_schedule(),
+            lookup_tables_refresh: default_lookup_tables_refresh_schedule(),
+            ata_cleanup: default_ata_cleanup_schedule(),
+            retention_days: 0,
+        }
+    }
+}
+
+/// Флеш-займы quote токена: заём, обе ноги и возврат одной транзакцией
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlashLoanConfig {
+    /// Исполнять сделки на заёмные средства, если для quote токена настроен резерв
+    #[serde(default)]
+    pub enabled: bool,
+    /// Протокол флеш-займов
+    #[serde(default)]
+    pub provider: FlashLoanProvider,
+    /// Резервы протокола по символу quote токена
+    #[serde(default)]
+    pub reserves: HashMap<String, FlashLoanReserveConfig>,
+}
+
+impl FlashLoanConfig {
+    /// Резерв для займа токена (None, если флеш-займы выключены или резерв не настроен)
+    pub fn reserve(&self, token: &str) -> Option<&FlashLoanReserveConfig> {
+        if !self.enabled {
+            return None;
+        }
+        self.reserves.get(token)
+    }
+}
+
+/// Протокол флеш-займов
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FlashLoanProvider {
+    /// Kamino Lending (klend)
+    #[default]
+    Kamino,
+}
+
+/// Резерв лендингового рынка, из которого занимается quote токен
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashLoanReserveConfig {
+    /// Адрес лендингового рынка
+    pub lending_market: String,
+    /// Адрес резерва токена
+    pub reserve: String,
+    /// Хранилище ликвидности резерва (supply vault)
+    pub liquidity_supply: String,
+    /// Получатель комиссии флеш-займа
+    pub fee_receiver: String,
+    /// Комиссия флеш-займа в процентах (из настроек резерва)
+    #[serde(default)]
+    pub fee_percent: f64,
+    /// Максимальный заём в quote токене за сделку
+    pub max_borrow: f64,
+}
+
+impl FlashLoanReserveConfig {
+    /// Комиссия флеш-займа в процентах
+    pub fn fee_percent(&self) -> Decimal {
+        Decimal::from_f64(self.fee_percent).unwrap_or(Decimal::ZERO)
+    }
+
+    /// Максимальный заём в quote токене
+    pub fn max_borrow(&self) -> Decimal {
+        Decimal::from_f64(self.max_borrow).unwrap_or(Decimal::ZERO)
+    }
+}
+
+/// Курсы токенов в USD для отображения прибыли
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiatConfig {
+    /// Запрашивать курсы у внешних API (без этого в USD считаются только сделки в стейблкоинах)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Источники курсов в порядке приоритета: "pyth", "coingecko", "binance"
+    #[serde(default = "default_fiat_providers")]
+    pub providers: Vec<String>,
+    /// Максимальное расхождение цен источников, при котором цена считается подтверждённой, в процентах
+    #[serde(default = "default_fiat_max_deviation_percent")]
+    pub max_deviation_percent: f64,
+    /// Максимальный возраст цены источника в секундах (более старые отбрасываются)
+    #[serde(default = "default_fiat_max_age_sec")]
+    pub max_age_sec: u64,
+    /// Время жизни курса в кеше, в секундах
+    #[serde(default = "default_fiat_cache_ttl_sec")]
+    pub cache_ttl_sec: u64,
+    /// Базовый URL Pyth Hermes API
+    #[serde(default = "default_pyth_url")]
+    pub pyth_url: String,
+    /// Идентификаторы price feed Pyth (к USD) по символу токена
+    #[serde(default = "default_pyth_feeds")]
+    pub pyth_feeds: HashMap<String, String>,
+    /// Базовый URL CoinGecko API
+    #[serde(default = "default_coingecko_url")]
+    pub coingecko_url: String,
+    /// Базовый URL Binance API
+    #[serde(default = "default_binance_url")]
+    pub binance_url: String,
+}
+
+impl Default for FiatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            providers: default_fiat_providers(),
+            max_deviation_percent: default_fiat_max_deviation_percent(),
+            max_age_sec: default_fiat_max_age_sec(),
+            cache_ttl_sec: default_fiat_cache_ttl_sec(),
+            pyth_url: default_pyth_url(),
+            pyth_feeds: default_pyth_feeds(),
+            coingecko_url: default_coingecko_url(),
+            binance_url: default_binance_url(),
+        }
+    }
+}
+
+/// Расписание приостановки исполнения сделок
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Окна обслуживания, во время которых сделки не исполняются
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindowConfig>,
+}
+
+/// Окно обслуживания: еженедельное (days/start/end) или разовое (from/until)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindowConfig {
+    /// Название окна для логов и API
+    pub name: String,
+    /// Дни недели ("mon", "tue", ...); пусто — каждый день
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// Начало еженедельного окна, "HH:MM" UTC
+    #[serde(default)]
+    pub start: Option<String>,
+    /// Конец еженедельного окна, "HH:MM" UTC (меньше start — окно через полночь)
+    #[serde(default)]
+    pub end: Option<String>,
+    /// Начало разового окна, RFC3339
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Конец разового окна, RFC3339
+    #[serde(default)]
+    pub until: Option<String>,
+}
+
+fn default_rpc_api_key_param() -> String {
+    "api-key".to_string()
+}
+
+fn default_keep_alive_sec() -> u64 {
+    60
+}
+
+fn default_max_price_age_ms() -> u64 {
+    5000
+}
+
+fn default_max_slot_skew() -> u64 {
+    5
+}
+
+fn default_quote_decimal_places() -> u32 {
+    6
+}
+
+fn default_usd_decimal_places() -> u32 {
+    2
+}
+
+fn default_circuit_breaker_threshold() -> u32 {
+    3
+}
+
+fn default_circuit_breaker_cooldown_sec() -> u64 {
+    60
+}
+
+fn default_compute_unit_limit() -> u32 {
+    200_000
+}
+
+fn default_allocation_capital_sol() -> f64 {
+    1.0
+}
+
+fn default_max_exposure_percent() -> f64 {
+    100.0
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_max_backoff_sec() -> u64 {
+    60
+}
+
+fn default_max_parallel_executions() -> usize {
+    4
+}
+
+fn default_mint_ttl_sec() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_market_ttl_sec() -> u64 {
+    24 * 3600
+}
+
+fn default_program_accounts_ttl_sec() -> u64 {
+    3600
+}
+
+fn default_pool_ttl_sec() -> u64 {
+    6 * 3600
+}
+
+fn default_price_check_interval_sec() -> u64 {
+    60
+}
+
+fn default_price_check_max_divergence_percent() -> f64 {
+    1.0
+}
+
+fn default_price_check_sources() -> HashMap<String, String> {
+    HashMap::from([
+        ("raydium".to_string(), "raydium_api".to_string()),
+        ("orca".to_string(), "orca_api".to_string()),
+        ("serum".to_string(), "jupiter".to_string()),
+        ("openbook_v2".to_string(), "jupiter".to_string()),
+        ("lifinity".to_string(), "jupiter".to_string()),
+        ("saber".to_string(), "jupiter".to_string()),
+    ])
+}
+
+fn default_raydium_api_url() -> String {
+    "https://api-v3.raydium.io".to_string()
+}
+
+fn default_raydium_api_cache_ttl_sec() -> u64 {
+    300
+}
+
+fn default_orca_api_url() -> String {
+    "https://api.orca.so".to_string()
+}
+
+fn default_orca_whirlpool_api_url() -> String {
+    "https://api.mainnet.orca.so".to_string()
+}
+
+fn default_orca_api_refresh_interval_sec() -> u64 {
+    600
+}
+
+fn default_jupiter_api_url() -> String {
+    "https://api.jup.ag".to_string()
+}
+
+fn default_fiat_providers() -> Vec<String> {
+    vec!["pyth".to_string(), "coingecko".to_string(), "binance".to_string()]
+}
+
+fn default_fiat_max_deviation_percent() -> f64 {
+    1.0
+}
+
+fn default_fiat_max_age_sec() -> u64 {
+    300
+}
+
+fn default_pyth_url() -> String {
+    "https://hermes.pyth.network".to_string()
+}
+
+fn default_pyth_feeds() -> HashMap<String, String> {
+    HashMap::from([(
+        "SOL".to_string(),
+        "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d".to_string(),
+    )])
+}
+
+fn default_binance_url() -> String {
+    "https://api.binance.com".to_string()
+}
+
+fn default_coingecko_url() -> String {
+    "https://api.coingecko.com/api/v3".to_string()
+}
+
+fn default_fiat_cache_ttl_sec() -> u64 {
+    60
+}
+
+fn default_congestion_check_interval_sec() -> u64 {
+    30
+}
+
+fn default_priority_fee_threshold() -> u64 {
+    100_000
+}
+
+fn default_tx_per_slot_threshold() -> u64 {
+    1_500
+}
+
+fn default_congestion_profit_margin() -> f64 {
+    0.2
+}
+
+fn default_clock_skew_check_interval_sec() -> u64 {
+    60
+}
+
+fn default_max_clock_skew_ms() -> u64 {
+    2000
+}
+
+fn default_scan_stall_timeout_sec() -> u64 {
+    120
+}
+
+fn default_max_in_flight_per_dex() -> usize {
+    1
+}
+
+fn default_balance_snapshot_interval_sec() -> u64 {
+    300
+}
+
+fn default_state_probe_interval_sec() -> u64 {
+    30
+}
+
+fn default_data_dir() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from("data")
+    } else {
+        PathBuf::from("/opt/arb-bot/data")
+    }
+}
+
+fn default_digest_interval_sec() -> u64 {
+    300
+}
+
+fn default_telegram_token_env() -> String {
+    "TELEGRAM_BOT_TOKEN".to_string()
+}
+
+fn default_telegram_chat_id_env() -> String {
+    "TELEGRAM_CHAT_ID".to_string()
+}
+
+fn default_webhook_url_env() -> String {
+    "NOTIFY_WEBHOOK_URL".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_web_port() -> u16 {
+    8080
+}
+
+fn default_engine_busy_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_static_dir() -> PathBuf {
+    if cfg!(windows) {
+        PathBuf::from("static")
+    } else {
+        PathBuf::from("/opt/arb-bot/static")
+    }
+}
+
+impl Config {
+    /// Загрузка конфигурации из файла
+    pub fn load() -> Result<Self> {
+        let config = Self::read()?;
+
+        // Валидация конфигурации
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Чтение и парсинг конфигурации без валидации
+    ///
+    /// Используется командой check-config, чтобы вывести все замечания,
+    /// а не только первую ошибку валидации.
+    pub fn read() -> Result<Self> {
+        let config_path = Self::path();
+
+        // Загрузка переменных окружения из .env
+        dotenv::dotenv().ok();
+
+        // Чтение и парсинг config.toml
+        let content = fs::read_to_string(&config_path)
+            .with_context(|| format!("Не удалось прочитать конфигурацию: {:?}", config_path))?;
+
+        toml::from_str(&content)
+            .context("Ошибка парсинга config.toml")
+    }
+
+    /// Секции верхнего уровня, которые отличаются от `other` (например, "safety", "dex")
+    pub fn changed_sections(&self, other: &Config) -> Vec<String> {
+        let (Ok(serde_json::Value::Object(current)), Ok(serde_json::Value::Object(other))) =
+            (serde_json::to_value(self), serde_json::to_value(other))
+        else {
+            return Vec::new();
+        };
+        let mut changed: Vec<String> = current.keys()
+            .chain(other.keys())
+            .filter(|key| current.get(*key) != other.get(*key))
+            .cloned()
+            .collect();
+        changed.sort();
+        changed.dedup();
+        changed
+    }
+
+    /// Путь к config.toml согласно правилам проекта
+    pub fn path() -> PathBuf {
+        if cfg!(windows) {
+            // На Windows используем текущую директорию для разработки
+            PathBuf::from("config.toml")
+        } else {
+            PathBuf::from("/opt/arb-bot/config.toml")
+        }
+    }
+
+    /// Валидация конфигурации
+    pub fn validate(&self) -> Result<()> {
+        // Проверка обязательных полей
+        if self.network.rpc_url.is_empty() {
+            anyhow::bail!("rpc_url не может быть пустым");
+        }
+
+        // Все секреты, на которые ссылается конфигурация, должны быть доступны
+        if let Some(ref key_env) = self.network.rpc_api_key_env {
+            std::env::var(key_env)
+                .with_context(|| format!("Переменная окружения {} (rpc_api_key_env) не задана", key_env))?;
+        }
+        for (name, value) in self.network.rpc_headers.iter()
+            .chain(self.network.ws_headers.iter())
+            .chain(self.network.simulation_rpc_headers.iter())
+        {
+            resolve_env_value(value)
+                .with_context(|| format!("Не удалось получить значение заголовка {}", name))?;
+        }
+        crate::proxy::validate(&self.network.proxy)?;
+        if let Some(ref simulation_rpc_url) = self.network.simulation_rpc_url {
+            if !simulation_rpc_url.starts_with("http://") && !simulation_rpc_url.starts_with("https://") {
+                anyhow::bail!(
+                    "simulation_rpc_url должен начинаться с http:// или https://: {}",
+                    crate::rpc::redact_url(simulation_rpc_url)
+                );
+            }
+        }
+
+        match self.network.data_source {
+            DataSource::Rpc => {}
+            DataSource::Websocket => {
+                let Some(ref ws_url) = self.network.ws_url else {
+                    anyhow::bail!("data_source = \"websocket\" требует ws_url");
+                };
+                if !ws_url.starts_with("ws://") && !ws_url.starts_with("wss://") {
+                    anyhow::bail!("ws_url должен начинаться с ws:// или wss://: {}", crate::rpc::redact_url(ws_url));
+                }
+            }
+            DataSource::Geyser => {
+                let Some(ref geyser_url) = self.network.geyser_url else {
+                    anyhow::bail!("data_source = \"geyser\" требует geyser_url");
+                };
+                if !geyser_url.starts_with("http://") && !geyser_url.starts_with("https://") {
+                    anyhow::bail!("geyser_url должен начинаться с http:// или https://: {}", crate::rpc::redact_url(geyser_url));
+                }
+                if let Some(ref token_env) = self.network.geyser_token_env {
+                    std::env::var(token_env)
+                        .with_context(|| format!("Переменная окружения {} (geyser_token_env) не задана", token_env))?;
+                }
+            }
+        }
+
+        if self.arbitrage.min_profit_percent <= 0.0 {
+            anyhow::bail!("min_profit_percent должен быть больше 0");
+        }
+        let absolute = &self.arbitrage.min_profit_absolute;
+        if [absolute.quote, absolute.usd].into_iter().flatten().any(|value| !value.is_finite() || value <= 0.0) {
+            anyhow::bail!("arbitrage.min_profit_absolute: пороги должны быть больше 0");
+        }
+        let rounding = &self.arbitrage.rounding;
+        if rounding.quote_decimal_places.max(rounding.usd_decimal_places) > u32::from(crate::units::MAX_DECIMALS) {
+            anyhow::bail!(
+                "arbitrage.rounding: точность не больше {} знаков после запятой",
+                crate::units::MAX_DECIMALS
+            );
+        }
+
+        let leg_tolerances = [self.arbitrage.buy_slippage_tolerance, self.arbitrage.sell_slippage_tolerance];
+        let tolerances = leg_tolerances.into_iter().flatten()
+            .chain(self.arbitrage.slippage_by_venue_type.values().copied());
+        for value in tolerances {
+            if !(value > 0.0 && value < 100.0) {
+                anyhow::bail!("Проскальзывание должно быть в диапазоне (0, 100), получено {}", value);
+            }
+        }
+
+        if self.safety.allowed_tokens.iter().any(|token| token.trim().is_empty() || token.trim() != token) {
+            anyhow::bail!("safety.allowed_tokens: пустой токен или пробелы в названии");
+        }
+
+        if !(self.safety.max_reserve_change_percent >= 0.0 && self.safety.max_reserve_change_percent.is_finite()) {
+            anyhow::bail!("safety.max_reserve_change_percent должен быть неотрицательным числом");
+        }
+
+        if self.arbitrage.max_trade_amount_sol <= 0.0 {
+            anyhow::bail!("max_trade_amount_sol должен быть больше 0");
+        }
+
+        if self.dex.min_trade_amount.values().any(|&amount| !(amount >= 0.0 && amount.is_finite())) {
+            anyhow::bail!("min_trade_amount должен быть неотрицательным числом");
+        }
+
+        crate::tokens::TokenRegistry::new(&self.tokens)?;
+
+        for (name, custom) in &self.dex.custom {
+            if crate::dex::SUPPORTED_DEXES.contains(&name.as_str()) {
+                anyhow::bail!("dex.custom.{}: имя совпадает со встроенным DEX", name);
+            }
+            crate::custom_amm::PoolLayout::from_config(custom)
+                .with_context(|| format!("Некорректное описание dex.custom.{}", name))?;
+        }
+
+        if self.dex.max_in_flight_per_dex == 0 || self.dex.max_in_flight.values().any(|&limit| limit == 0) {
+            anyhow::bail!("Лимит одновременных транзакций на DEX должен быть больше 0");
+        }
+
+        if self.safety.mode.uses_bus() {
+            let url = self.bus.url.as_deref()
+                .context("Режимы scan и execute_only требуют bus.url")?;
+            crate::bus::RedisAddress::parse(url).context("Некорректный bus.url")?;
+            if let Some(ref password_env) = self.bus.password_env {
+                std::env::var(password_env)
+                    .with_context(|| format!("Переменная окружения {} (bus.password_env) не задана", password_env))?;
+            }
+            if self.bus.queue.trim().is_empty() {
+                anyhow::bail!("bus.queue не может быть пустым");
+            }
+            if self.bus.max_age_ms == 0 || self.bus.max_queue_len == 0 {
+                anyhow::bail!("bus.max_age_ms и bus.max_queue_len должны быть больше 0");
+            }
+        }
+
+        for (pair, routes) in &self.dex.directions {
+            if !self.dex.trading_pairs.contains(pair) {
+                anyhow::bail!("dex.directions: пара {} отсутствует в dex.trading_pairs", pair);
+            }
+            if routes.is_empty() {
+                anyhow::bail!("dex.directions: пустой список направлений для {} (уберите запись, чтобы торговать в обе стороны)", pair);
+            }
+            for route in routes {
+                let (buy, sell) = parse_direction(route).with_context(|| {
+                    format!("dex.directions: некорректное направление {} для {} (ожидается \"raydium->orca\")", route, pair)
+                })?;
+                for dex in [buy, sell] {
+                    if !self.dex.enabled_dexes.iter().any(|name| name == dex) {
+                        anyhow::bail!("dex.directions: DEX {} в направлении {} для {} не входит в enabled_dexes", dex, route, pair);
+                    }
+                }
+            }
+        }
+
+        if self.scan_budget.request_cost.values().any(|&cost| cost == 0) {
+            anyhow::bail!("scan_budget.request_cost: стоимость котировки должна быть больше 0");
+        }
+
+        if self.flash_loan.enabled {
+            if self.flash_loan.reserves.is_empty() {
+                anyhow::bail!("flash_loan.enabled требует хотя бы один резерв в [flash_loan.reserves.<токен>]");
+            }
+            for (token, reserve) in &self.flash_loan.reserves {
+                if crate::wsol::is_native_sol(token) {
+                    anyhow::bail!("flash_loan.reserves.{}: нативный SOL не занимается, используйте WSOL", token);
+                }
+                crate::flash_loan::FlashLoan::new(reserve, solana_sdk::pubkey::Pubkey::default())
+                    .with_context(|| format!("Некорректный резерв flash_loan.reserves.{}", token))?;
+                if reserve.max_borrow <= 0.0 {
+                    anyhow::bail!("flash_loan.reserves.{}.max_borrow должен быть больше 0", token);
+                }
+                if !(0.0..100.0).contains(&reserve.fee_percent) {
+                    anyhow::bail!("flash_loan.reserves.{}.fee_percent должен быть от 0 до 100", token);
+                }
+            }
+        }
+
+        use crate::routing::{MAX_ROUTE_HOPS, MIN_ROUTE_HOPS};
+        if self.routing.enabled && !(MIN_ROUTE_HOPS..=MAX_ROUTE_HOPS).contains(&self.routing.max_hops) {
+            anyhow::bail!("routing.max_hops должен быть от {} до {}", MIN_ROUTE_HOPS, MAX_ROUTE_HOPS);
+        }
+
+        for (name, value) in [
+            ("landing_weight", self.venue_scoring.landing_weight),
+            ("slippage_weight", self.venue_scoring.slippage_weight),
+            ("fee_weight", self.venue_scoring.fee_weight),
+            ("latency_weight", self.venue_scoring.latency_weight),
+        ] {
+            if !(value >= 0.0 && value.is_finite()) {
+                anyhow::bail!("venue_scoring.{} должен быть неотрицательным числом", name);
+            }
+        }
+
+        if self.pair_discovery.enabled {
+            if self.pair_discovery.quote_tokens.is_empty() {
+                anyhow::bail!("pair_discovery.quote_tokens не может быть пустым");
+            }
+            if let Some(token) = self.pair_discovery.quote_tokens.iter()
+                .find(|token| token.is_empty() || token.contains('/'))
+            {
+                anyhow::bail!("pair_discovery.quote_tokens: некорректный токен \"{}\"", token);
+            }
+            if !(self.pair_discovery.min_balance >= 0.0 && self.pair_discovery.min_balance.is_finite()) {
+                anyhow::bail!("pair_discovery.min_balance должен быть неотрицательным числом");
+            }
+            if self.pair_discovery.interval_sec == 0 {
+                anyhow::bail!("pair_discovery.interval_sec должен быть больше 0");
+            }
+        }
+
+        for (name, spec) in [
+            ("daily_report", Some(&self.jobs.daily_report)),
+            ("history_compaction", Some(&self.jobs.history_compaction)),
+            ("balance_snapshot", self.jobs.balance_snapshot.as_ref()),
+            ("pair_discovery", self.jobs.pair_discovery.as_ref()),
+            ("pool_registry_refresh", Some(&self.jobs.pool_registry_refresh)),
+            ("lookup_tables_refresh", Some(&self.jobs.lookup_tables_refresh)),
+            ("ata_cleanup", Some(&self.jobs.ata_cleanup)),
+        ] {
+            if let Some(spec) = spec {
+                crate::jobs::JobSchedule::parse(spec).with_context(|| format!("jobs.{}", name))?;
+            }
+        }
+
+        if self.monitoring.check_interval_ms == 0 {
+            anyhow::bail!("check_interval_ms должен быть больше 0");
+        }
+
+        if self.monitoring.scan_stall_timeout_sec.saturating_mul(1000) <= self.monitoring.check_interval_ms {
+            anyhow::bail!("monitoring.scan_stall_timeout_sec должен быть больше check_interval_ms");
+        }
+
+        if self.templates.enabled {
+            for pair in &self.templates.pairs {
+                if !self.dex.trading_pairs.contains(pair) {
+                    anyhow::bail!("templates.pairs: пара {} отсутствует в dex.trading_pairs", pair);
+                }
+            }
+            for address in &self.templates.lookup_tables {
+                address.parse::<solana_sdk::pubkey::Pubkey>()
+                    .with_context(|| format!("templates.lookup_tables: некорректный адрес {}", address))?;
+            }
+        }
+
+        if self.self_test.enabled {
+            if let Some(ref pair) = self.self_test.pair {
+                if !self.dex.trading_pairs.contains(pair) {
+                    anyhow::bail!("self_test.pair: пара {} отсутствует в dex.trading_pairs", pair);
+                }
+            }
+            if self.self_test.amount.is_some_and(|amount| !amount.is_finite() || amount <= 0.0) {
+                anyhow::bail!("self_test.amount должен быть больше 0");
+            }
+        }
+
+        if self.allocation.enabled {
+            if self.allocation.capital_sol <= 0.0 {
+                anyhow::bail!("allocation.capital_sol должен быть больше 0");
+            }
+            for (name, value) in [
+                ("max_pair_percent", self.allocation.max_pair_percent),
+                ("max_dex_percent", self.allocation.max_dex_percent),
+            ] {
+                if value <= 0.0 || value > 100.0 {
+                    anyhow::bail!("allocation.{} должен быть в диапазоне (0, 100]", name);
+                }
+            }
+            if self.allocation.parallel_execution && self.allocation.max_parallel_executions == 0 {
+                anyhow::bail!("allocation.max_parallel_executions должен быть больше 0");
+            }
+        } else if self.allocation.parallel_execution {
+            // Без распределения каждая возможность рассчитана на весь баланс кошелька
+            anyhow::bail!("allocation.parallel_execution требует allocation.enabled = true");
+        }
+
+        if self.congestion.enabled {
+            if self.congestion.check_interval_sec == 0 {
+                anyhow::bail!("congestion.check_interval_sec должен быть больше 0");
+            }
+            if self.congestion.min_profit_margin_percent < 0.0 {
+                anyhow::bail!("congestion.min_profit_margin_percent не может быть отрицательным");
+            }
+        }
+
+        if self.supervisor.initial_backoff_ms == 0 {
+            anyhow::bail!("supervisor.initial_backoff_ms должен быть больше 0");
+        }
+        if self.supervisor.max_backoff_sec * 1000 < self.supervisor.initial_backoff_ms {
+            anyhow::bail!("supervisor.max_backoff_sec не может быть меньше initial_backoff_ms");
+        }
+
+        if self.rpc_cache.enabled
+            && (self.rpc_cache.mint_ttl_sec == 0
+                || self.rpc_cache.market_ttl_sec == 0
+                || self.rpc_cache.program_accounts_ttl_sec == 0
+                || self.rpc_cache.pool_ttl_sec == 0)
+        {
+            anyhow::bail!("Время жизни записей rpc_cache должно быть больше 0");
+        }
+
+        if self.fiat.enabled {
+            if self.fiat.providers.is_empty() {
+                anyhow::bail!("fiat.providers не может быть пустым");
+            }
+            for provider in &self.fiat.providers {
+                if !crate::fiat::FIAT_PROVIDERS.contains(&provider.as_str()) {
+                    anyhow::bail!(
+                        "Неизвестный источник курсов fiat.providers: {} (доступны: {})",
+                        provider, crate::fiat::FIAT_PROVIDERS.join(", ")
+                    );
+                }
+            }
+            if self.fiat.max_deviation_percent <= 0.0 {
+                anyhow::bail!("fiat.max_deviation_percent должен быть больше 0");
+            }
+            if self.fiat.max_age_sec == 0 {
+                anyhow::bail!("fiat.max_age_sec должен быть больше 0");
+            }
+            for (name, url) in [("pyth_url", &self.fiat.pyth_url), ("coingecko_url", &self.fiat.coingecko_url), ("binance_url", &self.fiat.binance_url)] {
+                if !url.starts_with("http") {
+                    anyhow::bail!("fiat.{} должен быть HTTP(S) URL", name);
+                }
+            }
+        }
+
+        if self.raydium_api.enabled && !self.raydium_api.base_url.starts_with("http") {
+            anyhow::bail!("raydium_api.base_url должен быть HTTP(S) URL");
+        }
+        if self.orca_api.enabled {
+            if !self.orca_api.base_url.starts_with("http") {
+                anyhow::bail!("orca_api.base_url должен быть HTTP(S) URL");
+            }
+            if self.orca_api.refresh_interval_sec == 0 {
+                anyhow::bail!("orca_api.refresh_interval_sec должен быть больше 0");
+            }
+        }
+
+        if self.price_check.enabled {
+            if self.price_check.interval_sec == 0 {
+                anyhow::bail!("price_check.interval_sec должен быть больше 0");
+            }
+            if self.price_check.max_divergence_percent <= 0.0 {
+                anyhow::bail!("price_check.max_divergence_percent должен быть больше 0");
+            }
+            for (dex, source) in &self.price_check.sources {
+                if !crate::price_check::QUOTE_SOURCES.contains(&source.as_str()) {
+                    anyhow::bail!(
+                        "Неизвестный источник котировок price_check.sources.{}: {} (доступны: {})",
+                        dex, source, crate::price_check::QUOTE_SOURCES.join(", ")
+                    );
+                }
+            }
+            for (name, url) in [
+                ("raydium_api_url", &self.price_check.raydium_api_url),
+                ("orca_api_url", &self.price_check.orca_api_url),
+                ("jupiter_api_url", &self.price_check.jupiter_api_url),
+            ] {
+                if !url.starts_with("http") {
+                    anyhow::bail!("price_check.{} должен быть HTTP(S) URL", name);
+                }
+            }
+        }
+
+        if self.monitoring.clock_skew_check_interval_sec == 0 {
+            anyhow::bail!("clock_skew_check_interval_sec должен быть больше 0");
+        }
+
+        if self.notifications.enabled {
+            for sink in &self.notifications.sinks {
+                if sink.mode == NotificationMode::Digest && sink.digest_interval_sec == 0 {
+                    anyhow::bail!("digest_interval_sec должен быть больше 0");
+                }
+                if sink.kind == NotificationSinkKind::Telegram {
+                    for env_name in [&sink.bot_token_env, &sink.chat_id_env] {
+                        std::env::var(env_name)
+                            .with_context(|| format!("Переменная окружения {} (Telegram) не задана", env_name))?;
+                    }
+                }
+                if sink.kind == NotificationSinkKind::Webhook {
+                    std::env::var(&sink.url_env)
+                        .with_context(|| format!("Переменная окружения {} (webhook) не задана", sink.url_env))?;
+                }
+            }
+        }
+
+        crate::schedule::Schedule::from_config(&self.schedule)
+            .context("Некорректное расписание [schedule]")?;
+
+        if self.web.engine_busy_timeout_ms == 0 {
+            anyhow::bail!("web.engine_busy_timeout_ms должен быть больше 0");
+        }
+
+        if self.safety.mode == ExecutionMode::Signal {
+            log::warn!("📡 Режим сигналов - возможности публикуются, сделки не исполняются");
+        } else if self.safety.mode == ExecutionMode::Scan {
+            log::warn!("📡 Режим поиска - возможности передаются процессу исполнения через очередь");
+        } else if self.safety.simulation_mode {
+            log::warn!("⚠️  Режим симуляции активен - реальные транзакции не выполняются");
+        }
+
+        Ok(())
+    }
+}
+
+/// Получение значения параметра конфигурации с поддержкой ссылок на .env
+///
+/// Значение вида "env:NAME" заменяется содержимым переменной окружения NAME,
+/// остальные значения возвращаются как есть.
+pub fn resolve_env_value(value: &str) -> Result<String> {
+    match value.strip_prefix("env:") {
+        Some(name) => std::env::var(name)
+            .with_context(|| format!("Переменная окружения {} не задана", name)),
+        None => Ok(value.to_string()),
+    }
+}
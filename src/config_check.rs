@@ -0,0 +1,358 @@
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt;
+use crate::config::Config;
+use crate::dex::{default_fee_percent, SUPPORTED_DEXES};
+use crate::tokens::TokenRegistry;
+
+/// Порог slippage_tolerance, выше которого сделки теряют смысл (в процентах)
+const MAX_REASONABLE_SLIPPAGE_PERCENT: f64 = 5.0;
+/// Минимальный интервал опроса для публичных RPC (лимиты запросов)
+const MIN_PUBLIC_RPC_INTERVAL_MS: u64 = 1000;
+/// Публичные RPC Solana Foundation с жёсткими лимитами запросов
+const PUBLIC_RPC_HOSTS: &[&str] = &[
+    "api.mainnet-beta.solana.com",
+    "api.devnet.solana.com",
+    "api.testnet.solana.com",
+];
+/// Допустимые значения commitment
+const COMMITMENT_LEVELS: &[&str] = &["processed", "confirmed", "finalized"];
+
+/// Уровень замечания
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Конфигурация не может использоваться
+    Error,
+    /// Конфигурация рабочая, но вероятно ошибочная или опасная
+    Warning,
+}
+
+/// Замечание к конфигурации
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    /// Параметр конфигурации, к которому относится замечание
+    pub field: String,
+    pub message: String,
+    /// Рекомендация по исправлению
+    pub hint: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "ОШИБКА",
+            Severity::Warning => "ПРЕДУПРЕЖДЕНИЕ",
+        };
+        write!(f, "[{}] {}: {}\n    → {}", level, self.field, self.message, self.hint)
+    }
+}
+
+/// Результат проверки конфигурации
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ConfigReport {
+    fn error(&mut self, field: &str, message: impl Into<String>, hint: impl Into<String>) {
+        self.push(Severity::Error, field, message.into(), hint.into());
+    }
+
+    fn warning(&mut self, field: &str, message: impl Into<String>, hint: impl Into<String>) {
+        self.push(Severity::Warning, field, message.into(), hint.into());
+    }
+
+    fn push(&mut self, severity: Severity, field: &str, message: String, hint: String) {
+        self.findings.push(Finding {
+            severity,
+            field: field.to_string(),
+            message,
+            hint,
+        });
+    }
+
+    /// Есть ли замечания уровня Error
+    pub fn has_errors(&self) -> bool {
+        self.findings.iter().any(|f| f.severity == Severity::Error)
+    }
+
+    /// Замечания указанного уровня
+    pub fn by_severity(&self, severity: Severity) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(move |f| f.severity == severity)
+    }
+}
+
+/// Расширенная проверка конфигурации для команды `arb-bot check-config`
+///
+/// Включает обычную валидацию (ошибки) и эвристики (предупреждения),
+/// которые не мешают запуску, но обычно указывают на ошибку настройки.
+pub fn check_config(config: &Config) -> ConfigReport {
+    let mut report = ConfigReport::default();
+
+    if let Err(e) = config.validate() {
+        report.error("config", format!("{:#}", e), "Исправьте параметр согласно config.example.toml");
+    }
+
+    check_network(config, &mut report);
+    check_wallet(config, &mut report);
+    check_arbitrage(config, &mut report);
+    check_dex(config, &mut report);
+    check_dex_pairs(config, &mut report);
+    check_min_trade_amount(config, &mut report);
+    check_safety(config, &mut report);
+
+    report
+}
+
+fn check_network(config: &Config, report: &mut ConfigReport) {
+    let network = &config.network;
+
+    if !COMMITMENT_LEVELS.contains(&network.commitment.as_str()) {
+        report.error(
+            "network.commitment",
+            format!("Неизвестный уровень подтверждения: {}", network.commitment),
+            format!("Допустимые значения: {}", COMMITMENT_LEVELS.join(", ")),
+        );
+    }
+
+    let is_public_rpc = PUBLIC_RPC_HOSTS.iter().any(|host| network.rpc_url.contains(host));
+    if is_public_rpc && config.monitoring.check_interval_ms < MIN_PUBLIC_RPC_INTERVAL_MS {
+        report.warning(
+            "monitoring.check_interval_ms",
+            format!(
+                "Публичный RPC с интервалом опроса {} мс — запросы будут упираться в rate limit",
+                config.monitoring.check_interval_ms
+            ),
+            format!(
+                "Используйте приватный RPC провайдер или увеличьте интервал до {} мс",
+                MIN_PUBLIC_RPC_INTERVAL_MS
+            ),
+        );
+    }
+}
+
+fn check_wallet(config: &Config, report: &mut ConfigReport) {
+    let keypair_path = &config.wallet.keypair_path;
+    if !keypair_path.exists() {
+        report.error(
+            "wallet.keypair_path",
+            format!("Файл ключа не найден: {:?}", keypair_path),
+            "Создайте ключ: solana-keygen new -o <путь> && chmod 400 <путь>",
+        );
+    }
+}
+
+fn check_arbitrage(config: &Config, report: &mut ConfigReport) {
+    let arbitrage = &config.arbitrage;
+
+    let mut tolerances = vec![("arbitrage.slippage_tolerance".to_string(), arbitrage.slippage_tolerance)];
+    tolerances.extend(arbitrage.buy_slippage_tolerance.map(|v| ("arbitrage.buy_slippage_tolerance".to_string(), v)));
+    tolerances.extend(arbitrage.sell_slippage_tolerance.map(|v| ("arbitrage.sell_slippage_tolerance".to_string(), v)));
+    for (venue, value) in &arbitrage.slippage_by_venue_type {
+        tolerances.push((format!("arbitrage.slippage_by_venue_type.{}", venue.as_str()), *value));
+    }
+
+    for (field, value) in tolerances {
+        if value <= 0.0 || value >= 100.0 {
+            report.error(
+                &field,
+                format!("Некорректное значение: {}", value),
+                "Задайте проскальзывание в процентах в диапазоне (0, 100)",
+            );
+        } else if value > MAX_REASONABLE_SLIPPAGE_PERCENT {
+            report.warning(
+                &field,
+                format!(
+                    "Проскальзывание {}% больше {}% — оно перекрывает любую арбитражную прибыль",
+                    value, MAX_REASONABLE_SLIPPAGE_PERCENT
+                ),
+                "Обычно достаточно 0.5–1%",
+            );
+        }
+    }
+
+    // Порог считается после комиссий DEX, но должен покрывать ещё проскальзывание
+    // и комиссию сети; значение меньше комиссии одного свопа — почти всегда ошибка
+    if let Some(swap_fee) = min_swap_fee_percent(&config.dex.enabled_dexes) {
+        let min_profit = Decimal::from_f64(arbitrage.min_profit_percent).unwrap_or(Decimal::ZERO);
+        if min_profit > Decimal::ZERO && min_profit < swap_fee {
+            report.warning(
+                "arbitrage.min_profit_percent",
+                format!(
+                    "Минимальная прибыль {}% меньше типичной комиссии свопа ({}%)",
+                    arbitrage.min_profit_percent, swap_fee
+                ),
+                "Проскальзывание и комиссия сети съедят такую прибыль; увеличьте порог",
+            );
+        }
+    }
+
+    if arbitrage.transaction_timeout_sec == 0 {
+        report.error(
+            "arbitrage.transaction_timeout_sec",
+            "Таймаут транзакции равен 0",
+            "Задайте таймаут в секундах, например 30",
+        );
+    }
+}
+
+fn check_dex(config: &Config, report: &mut ConfigReport) {
+    let dex = &config.dex;
+
+    for name in &dex.enabled_dexes {
+        if !SUPPORTED_DEXES.contains(&name.as_str()) && !dex.custom.contains_key(name) {
+            report.error(
+                "dex.enabled_dexes",
+                format!("Неизвестный DEX: {}", name),
+                format!("Поддерживаются: {}, а также DEX из [dex.custom.<имя>]", SUPPORTED_DEXES.join(", ")),
+            );
+        }
+    }
+
+    for name in dex.custom.keys() {
+        if !dex.enabled_dexes.contains(name) {
+            report.warning(
+                "dex.custom",
+                format!("Пользовательский AMM {} описан, но не включён", name),
+                "Добавьте имя в enabled_dexes или уберите секцию",
+            );
+        }
+    }
+
+    if dex.enabled_dexes.len() < 2 {
+        report.warning(
+            "dex.enabled_dexes",
+            "Для арбитража нужно минимум 2 DEX — бот не найдёт ни одной возможности",
+            "Добавьте ещё один DEX в enabled_dexes",
+        );
+    }
+
+    if dex.trading_pairs.is_empty() {
+        report.warning(
+            "dex.trading_pairs",
+            "Список торговых пар пуст",
+            "Добавьте пары в формате \"BASE/QUOTE\", например \"SOL/USDC\"",
+        );
+    }
+
+    for pair in &dex.trading_pairs {
+        let valid = matches!(
+            pair.split('/').collect::<Vec<_>>().as_slice(),
+            [base, quote] if !base.is_empty() && !quote.is_empty()
+        );
+        if !valid {
+            report.error(
+                "dex.trading_pairs",
+                format!("Некорректный формат торговой пары: {}", pair),
+                "Используйте формат \"BASE/QUOTE\", например \"SOL/USDC\"",
+            );
+        }
+    }
+
+    // Ошибка в [tokens] уже отмечена валидацией
+    if let Ok(tokens) = TokenRegistry::new(&config.tokens) {
+        let mut unknown: Vec<&str> = dex.trading_pairs.iter()
+            .filter_map(|pair| pair.split_once('/'))
+            .filter(|(base, quote)| !base.is_empty() && !quote.is_empty() && !quote.contains('/'))
+            .flat_map(|(base, quote)| [base, quote])
+            .filter(|symbol| tokens.get(symbol).is_none())
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        for symbol in unknown {
+            report.error(
+                "dex.trading_pairs",
+                format!("Mint токена {} не известен", symbol),
+                format!("Добавьте секцию [tokens.{}] с mint токена", symbol),
+            );
+        }
+    }
+}
+
+/// Проверка минимальных объёмов сделки по DEX
+fn check_min_trade_amount(config: &Config, report: &mut ConfigReport) {
+    let max_amount = Decimal::from_f64(config.arbitrage.max_trade_amount_sol).unwrap_or(Decimal::ZERO);
+    for name in &config.dex.enabled_dexes {
+        let min_amount = config.dex.min_trade_amount(name);
+        if min_amount > max_amount {
+            report.warning(
+                "dex.min_trade_amount",
+                format!("Минимальный объём сделки на {} ({}) больше max_trade_amount_sol ({}) — DEX не будет использоваться", name, min_amount, max_amount),
+                "Увеличьте max_trade_amount_sol или уменьшите min_trade_amount для DEX",
+            );
+        }
+    }
+}
+
+/// Проверка списков пар по отдельным DEX
+fn check_dex_pairs(config: &Config, report: &mut ConfigReport) {
+    let dex = &config.dex;
+
+    for (name, pairs) in &dex.pairs {
+        if !dex.enabled_dexes.contains(name) {
+            report.warning(
+                "dex.pairs",
+                format!("Список пар задан для неактивного DEX: {}", name),
+                "Уберите запись или добавьте DEX в enabled_dexes",
+            );
+        }
+        for pair in pairs {
+            if !dex.trading_pairs.contains(pair) {
+                report.warning(
+                    "dex.pairs",
+                    format!("Пара {} для {} отсутствует в trading_pairs и не сканируется", pair, name),
+                    "Добавьте пару в trading_pairs или уберите её из списка DEX",
+                );
+            }
+        }
+    }
+
+    // Нехватка DEX в целом уже отмечена в check_dex
+    if dex.enabled_dexes.len() < 2 {
+        return;
+    }
+    for pair in &dex.trading_pairs {
+        let venues = dex.enabled_dexes.iter()
+            .filter(|name| dex.lists_pair(name, pair))
+            .count();
+        if venues < 2 {
+            report.warning(
+                "dex.pairs",
+                format!("Пара {} торгуется менее чем на 2 DEX ({}) — арбитраж по ней невозможен", pair, venues),
+                "Добавьте пару в списки других DEX или уберите её из trading_pairs",
+            );
+        }
+    }
+}
+
+fn check_safety(config: &Config, report: &mut ConfigReport) {
+    let safety = &config.safety;
+
+    if safety.min_balance_sol < 0.0 {
+        report.error(
+            "safety.min_balance_sol",
+            format!("Отрицательный минимальный баланс: {}", safety.min_balance_sol),
+            "Задайте резерв SOL на комиссии, например 0.1",
+        );
+    }
+
+    if !safety.simulation_mode && safety.mode.executes() {
+        // Дневной лимит убытков не реализован — единственный автоматический стоп
+        // в продакшн режиме это max_consecutive_failures
+        report.warning(
+            "safety.simulation_mode",
+            "Продакшн режим без дневного лимита убытков",
+            "Проверьте стратегию в режиме симуляции и держите max_consecutive_failures небольшим",
+        );
+    }
+}
+
+/// Минимальная типичная комиссия свопа среди включённых DEX
+fn min_swap_fee_percent(enabled_dexes: &[String]) -> Option<Decimal> {
+    enabled_dexes.iter()
+        .map(|name| default_fee_percent(name))
+        .min()
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use crate::config::Config;
+use crate::rpc::redact_url;
+use crate::store::Store;
+
+/// Коллекция хранилища с журналом изменений конфигурации
+pub const CONFIG_HISTORY_COLLECTION: &str = "config_history";
+
+/// Замена значения, которое может содержать секрет
+const REDACTED: &str = "***";
+
+/// Источник изменения конфигурации
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeSource {
+    /// Конфигурация перечитана с диска (POST /api/config/reload)
+    Reload,
+    /// DEX включён или отключён через API
+    Dex,
+    /// Фильтр логов изменён через API
+    Logging,
+    /// Переопределение расписания через API
+    Schedule,
+}
+
+/// Изменение одного поля: путь через точку и значения до и после
+/// (None — поля не было или его не стало)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl FieldChange {
+    pub fn new(path: impl Into<String>, old: impl Serialize, new: impl Serialize) -> Self {
+        Self {
+            path: path.into(),
+            old: serde_json::to_value(old).ok(),
+            new: serde_json::to_value(new).ok(),
+        }
+    }
+}
+
+/// Запись журнала: кто, когда и что изменил
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub timestamp: DateTime<Utc>,
+    /// Пользователь панели или `token:<название>` для токена API
+    pub actor: String,
+    pub source: ChangeSource,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Изменившиеся поля конфигурации (по алфавиту путей)
+///
+/// Секции сравниваются до отдельных полей, массивы — целиком. URL и литеральные
+/// значения заголовков RPC маскируются: в них бывают ключи провайдера.
+pub fn diff(old: &Config, new: &Config) -> Vec<FieldChange> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut old_fields = BTreeMap::new();
+    let mut new_fields = BTreeMap::new();
+    flatten(String::new(), old, &mut old_fields);
+    flatten(String::new(), new, &mut new_fields);
+
+    let mut paths: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths.into_iter()
+        .filter(|path| old_fields.get(*path) != new_fields.get(*path))
+        .map(|path| FieldChange {
+            path: path.clone(),
+            old: old_fields.get(path).map(|value| redact(path, value)),
+            new: new_fields.get(path).map(|value| redact(path, value)),
+        })
+        .collect()
+}
+
+/// Поля значения по путям через точку (пустые таблицы полей не дают)
+fn flatten(prefix: String, value: Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                flatten(path, value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix, value);
+        }
+    }
+}
+
+fn redact(path: &str, value: &Value) -> Value {
+    let Value::String(text) = value else {
+        return value.clone();
+    };
+    let key = path.rsplit('.').next().unwrap_or(path);
+    // URL прокси может содержать учётные данные
+    if path.starts_with("network.proxy.") {
+        if text.starts_with("env:") || text == crate::proxy::DIRECT {
+            return value.clone();
+        }
+        return Value::String(redact_url(text));
+    }
+    if key.ends_with("url") {
+        return Value::String(redact_url(text));
+    }
+    let header = ["network.rpc_headers.", "network.ws_headers.", "network.simulation_rpc_headers."]
+        .iter()
+        .any(|prefix| path.starts_with(prefix));
+    if header && !text.starts_with("env:") {
+        return Value::String(REDACTED.to_string());
+    }
+    value.clone()
+}
+
+/// Журнал изменений конфигурации в хранилище
+///
+/// Перечитанная конфигурация сравнивается с прочитанной в прошлый раз
+/// (при старте — с загруженной), поэтому повторное чтение того же файла
+/// в журнал не попадает.
+#[derive(Debug, Clone)]
+pub struct ConfigHistory {
+    store: Store,
+    last_read: Arc<Mutex<Config>>,
+}
+
+impl ConfigHistory {
+    pub fn new(store: Store, config: &Config) -> Self {
+        Self {
+            store,
+            last_read: Arc::new(Mutex::new(config.clone())),
+        }
+    }
+
+    /// Запись перечитанной конфигурации; None — поля не изменились
+    pub async fn record_reload(&self, config: &Config, actor: &str, now: DateTime<Utc>) -> Result<Option<ConfigChange>> {
+        let changes = {
+            let mut last_read = self.last_read.lock().expect("мьютекс журнала конфигурации отравлен");
+            let changes = diff(&last_read, config);
+            *last_read = config.clone();
+            changes
+        };
+        if changes.is_empty() {
+            return Ok(None);
+        }
+        self.record(ChangeSource::Reload, actor, changes, now).await.map(Some)
+    }
+
+    /// Запись изменения настроек во время работы
+    pub async fn record(&self, source: ChangeSource, actor: &str, changes: Vec<FieldChange>, now: DateTime<Utc>) -> Result<ConfigChange> {
+        let change = ConfigChange {
+            timestamp: now,
+            actor: actor.to_string(),
+            source,
+            changes,
+        };
+        self.store.append(CONFIG_HISTORY_COLLECTION, &change).await?;
+        Ok(change)
+    }
+
+    /// Изменения за период (от старых к новым)
+    pub async fn load(&self, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<Vec<ConfigChange>> {
+        let mut changes: Vec<ConfigChange> = self.store.read_all(CONFIG_HISTORY_COLLECTION).await?;
+        changes.retain(|c| from.is_none_or(|from| c.timestamp >= from) && to.is_none_or(|to| c.timestamp <= to));
+        changes.sort_by_key(|c| c.timestamp);
+        Ok(changes)
+    }
+}
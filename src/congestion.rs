@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::config::{CongestionConfig, NetworkConfig};
+use crate::rpc::create_rpc_client;
+use crate::supervisor::Supervisor;
+
+/// Количество сэмплов производительности для оценки загрузки блоков (по ~60 с каждый)
+const PERFORMANCE_SAMPLES: usize = 5;
+/// Доля порога, ниже которой перегрузка считается завершённой (гистерезис)
+const EXIT_THRESHOLD_RATIO: f64 = 0.8;
+
+/// Измерение загрузки кластера
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CongestionSample {
+    /// Медиана priority fee за последние слоты, micro-lamports за CU
+    pub median_priority_fee: u64,
+    /// Среднее количество транзакций (без голосований) на слот
+    pub tx_per_slot: f64,
+}
+
+#[derive(Debug, Default)]
+struct CongestionState {
+    congested: bool,
+    since: Option<DateTime<Utc>>,
+    last_sample: Option<CongestionSample>,
+}
+
+/// Состояние перегрузки сети
+///
+/// Во время перегрузки растёт стоимость включения транзакции в блок и доля
+/// проигранных гонок, поэтому порог прибыли повышается на `min_profit_margin_percent`.
+#[derive(Debug, Clone)]
+pub struct Congestion {
+    config: CongestionConfig,
+    state: Arc<Mutex<CongestionState>>,
+}
+
+impl Congestion {
+    pub fn new(config: &CongestionConfig) -> Self {
+        Self {
+            config: config.clone(),
+            state: Arc::new(Mutex::new(CongestionState::default())),
+        }
+    }
+
+    /// Идёт ли сейчас перегрузка
+    pub fn is_congested(&self) -> bool {
+        self.lock().congested
+    }
+
+    /// Начало текущей перегрузки
+    pub fn congested_since(&self) -> Option<DateTime<Utc>> {
+        self.lock().since
+    }
+
+    /// Последнее измерение
+    pub fn last_sample(&self) -> Option<CongestionSample> {
+        self.lock().last_sample
+    }
+
+    /// Надбавка к min_profit_percent (0, если перегрузки нет)
+    pub fn profit_margin(&self) -> Decimal {
+        if self.is_congested() {
+            Decimal::from_f64(self.config.min_profit_margin_percent).unwrap_or(Decimal::ZERO)
+        } else {
+            Decimal::ZERO
+        }
+    }
+
+    /// Учёт нового измерения; возвращает новое состояние, если оно изменилось
+    ///
+    /// Перегрузка начинается, когда любая метрика выше порога, и заканчивается,
+    /// когда обе опустились ниже 80% порога — чтобы порог прибыли не «дребезжал».
+    pub fn record(&self, sample: CongestionSample, now: DateTime<Utc>) -> Option<bool> {
+        let fee_threshold = self.config.priority_fee_threshold as f64;
+        let tx_threshold = self.config.tx_per_slot_threshold as f64;
+        let fee = sample.median_priority_fee as f64;
+
+        let mut state = self.lock();
+        state.last_sample = Some(sample);
+
+        let congested = if state.congested {
+            fee >= fee_threshold * EXIT_THRESHOLD_RATIO || sample.tx_per_slot >= tx_threshold * EXIT_THRESHOLD_RATIO
+        } else {
+            fee > fee_threshold || sample.tx_per_slot > tx_threshold
+        };
+        if congested == state.congested {
+            return None;
+        }
+
+        state.congested = congested;
+        state.since = congested.then_some(now);
+        Some(congested)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CongestionState> {
+        self.state.lock().expect("мьютекс состояния перегрузки отравлен")
+    }
+}
+
+/// Измерение загрузки кластера через RPC
+pub async fn measure_congestion(network: &NetworkConfig) -> Result<CongestionSample> {
+    let network = network.clone();
+    tokio::task::spawn_blocking(move || -> Result<CongestionSample> {
+        let client = create_rpc_client(&network)?;
+
+        let mut fees: Vec<u64> = client.get_recent_prioritization_fees(&[])
+            .context("Не удалось получить priority fee последних слотов")?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+        fees.sort_unstable();
+        let median_priority_fee = fees.get(fees.len() / 2).copied().unwrap_or(0);
+
+        let samples = client.get_recent_performance_samples(Some(PERFORMANCE_SAMPLES))
+            .context("Не удалось получить сэмплы производительности")?;
+        let (transactions, slots) = samples.iter().fold((0u64, 0u64), |(tx, slots), s| {
+            (tx + s.num_non_vote_transactions.unwrap_or(s.num_transactions), slots + s.num_slots)
+        });
+        let tx_per_slot = if slots > 0 { transactions as f64 / slots as f64 } else { 0.0 };
+
+        Ok(CongestionSample { median_priority_fee, tx_per_slot })
+    })
+    .await
+    .context("Задача измерения загрузки кластера завершилась с ошибкой")?
+}
+
+/// Запуск фоновой проверки перегрузки сети под надзором
+pub fn spawn_congestion_monitor(supervisor: &Supervisor, network: &NetworkConfig, congestion: Congestion) {
+    if !congestion.config.enabled {
+        return;
+    }
+    let network = network.clone();
+    supervisor.spawn("congestion", move || run_congestion_monitor(network.clone(), congestion.clone()));
+}
+
+async fn run_congestion_monitor(network: NetworkConfig, congestion: Congestion) -> Result<()> {
+    let check_interval = Duration::from_secs(congestion.config.check_interval_sec);
+    let mut timer = tokio::time::interval(check_interval);
+    loop {
+        timer.tick().await;
+        let sample = match measure_congestion(&network).await {
+            Ok(sample) => sample,
+            Err(e) => {
+                log::warn!("Не удалось оценить загрузку сети: {}", e);
+                continue;
+            }
+        };
+        log::debug!(
+            "Загрузка сети: priority fee {} µlamports/CU, {:.0} tx/слот",
+            sample.median_priority_fee, sample.tx_per_slot
+        );
+        match congestion.record(sample, Utc::now()) {
+            Some(true) => log::warn!(
+                "🚦 Перегрузка сети (priority fee {} µlamports/CU, {:.0} tx/слот): min_profit_percent повышен на {}%",
+                sample.median_priority_fee, sample.tx_per_slot, congestion.config.min_profit_margin_percent
+            ),
+            Some(false) => log::info!("🚦 Перегрузка сети завершилась, min_profit_percent возвращён"),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::str::FromStr;
+use crate::config::CustomAmmConfig;
+use crate::math::Fee;
+
+/// Знаменатель комиссии, заданной в процентах (fee_percent)
+const FIXED_FEE_DENOMINATOR: u64 = 1_000_000;
+
+/// Префикс изменяемого аккаунта с фиксированным адресом в swap_accounts
+const WRITABLE_PREFIX: &str = "mut:";
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .with_context(|| format!("Данные короче смещения {}", offset + N))?
+        .try_into()
+        .context("Некорректная длина поля")
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+/// Откуда берётся комиссия пула
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeeSource {
+    /// u64 numerator и denominator по смещениям в аккаунте пула
+    Account { numerator_offset: usize, denominator_offset: usize },
+    /// Одинаковая для всех пулов (из fee_percent)
+    Fixed(Fee),
+}
+
+/// Аккаунт инструкции swap
+#[derive(Debug, Clone, PartialEq)]
+pub enum SwapAccount {
+    /// Аккаунт пула (изменяемый)
+    Pool,
+    /// Authority пула: PDA программы от адреса пула
+    Authority,
+    /// Кошелёк (подписант)
+    User,
+    /// ATA кошелька входного токена
+    UserSource,
+    /// ATA кошелька выходного токена
+    UserDestination,
+    /// Хранилище пула входного токена
+    PoolSource,
+    /// Хранилище пула выходного токена
+    PoolDestination,
+    TokenProgram,
+    /// Фиксированный адрес
+    Fixed { address: Pubkey, writable: bool },
+}
+
+impl FromStr for SwapAccount {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let account = match value {
+            "pool" => SwapAccount::Pool,
+            "authority" => SwapAccount::Authority,
+            "user" => SwapAccount::User,
+            "user_source" => SwapAccount::UserSource,
+            "user_destination" => SwapAccount::UserDestination,
+            "pool_source" => SwapAccount::PoolSource,
+            "pool_destination" => SwapAccount::PoolDestination,
+            "token_program" => SwapAccount::TokenProgram,
+            _ => {
+                let (address, writable) = match value.strip_prefix(WRITABLE_PREFIX) {
+                    Some(address) => (address, true),
+                    None => (value, false),
+                };
+                let address = Pubkey::from_str(address)
+                    .with_context(|| format!("Неизвестная роль или некорректный адрес аккаунта: {}", value))?;
+                SwapAccount::Fixed { address, writable }
+            }
+        };
+        Ok(account)
+    }
+}
+
+/// Разметка аккаунта пула и формат инструкции swap пользовательского AMM
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolLayout {
+    pub program_id: Pubkey,
+    pub account_size: Option<u64>,
+    pub token_a_mint_offset: usize,
+    pub token_b_mint_offset: usize,
+    pub token_a_vault_offset: usize,
+    pub token_b_vault_offset: usize,
+    pub fee: FeeSource,
+    pub swap_data_prefix: Vec<u8>,
+    pub swap_accounts: Vec<SwapAccount>,
+}
+
+impl PoolLayout {
+    /// Разметка из секции `[dex.custom.<имя>]`
+    pub fn from_config(config: &CustomAmmConfig) -> Result<Self> {
+        let program_id = Pubkey::from_str(&config.program_id).context("Некорректный program_id")?;
+
+        let fee = match (config.fee_numerator_offset, config.fee_denominator_offset, config.fee_percent) {
+            (Some(numerator_offset), Some(denominator_offset), None) => {
+                FeeSource::Account { numerator_offset, denominator_offset }
+            }
+            (None, None, percent) => {
+                let percent = percent.unwrap_or(0.25);
+                let numerator = Decimal::from_f64(percent)
+                    .filter(|percent| !percent.is_sign_negative())
+                    .and_then(|percent| (percent * Decimal::from(FIXED_FEE_DENOMINATOR / 100)).round().to_u64())
+                    .with_context(|| format!("Некорректная комиссия fee_percent: {}", percent))?;
+                FeeSource::Fixed(Fee::new(numerator, FIXED_FEE_DENOMINATOR).context("Некорректная комиссия fee_percent")?)
+            }
+            (_, _, Some(_)) => anyhow::bail!("fee_percent нельзя задавать вместе со смещениями комиссии"),
+            _ => anyhow::bail!("fee_numerator_offset и fee_denominator_offset задаются вместе"),
+        };
+
+        let swap_accounts = config.swap_accounts.iter()
+            .map(|account| account.parse())
+            .collect::<Result<Vec<SwapAccount>>>()?;
+        if !swap_accounts.is_empty() && !swap_accounts.contains(&SwapAccount::User) {
+            anyhow::bail!("swap_accounts: нет подписанта user");
+        }
+
+        let layout = Self {
+            program_id,
+            account_size: config.account_size,
+            token_a_mint_offset: config.token_a_mint_offset,
+            token_b_mint_offset: config.token_b_mint_offset,
+            token_a_vault_offset: config.token_a_vault_offset,
+            token_b_vault_offset: config.token_b_vault_offset,
+            fee,
+            swap_data_prefix: config.swap_data_prefix.clone(),
+            swap_accounts,
+        };
+        if let Some(size) = layout.account_size {
+            let end = layout.fields_end();
+            if end as u64 > size {
+                anyhow::bail!("Поле на смещении до {} выходит за размер аккаунта {} байт", end, size);
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Конец последнего поля разметки в данных аккаунта
+    fn fields_end(&self) -> usize {
+        let pubkeys = [
+            self.token_a_mint_offset,
+            self.token_b_mint_offset,
+            self.token_a_vault_offset,
+            self.token_b_vault_offset,
+        ].into_iter().map(|offset| offset + 32);
+        let fee = match self.fee {
+            FeeSource::Account { numerator_offset, denominator_offset } => {
+                vec![numerator_offset + 8, denominator_offset + 8]
+            }
+            FeeSource::Fixed(_) => Vec::new(),
+        };
+        pubkeys.chain(fee).max().unwrap_or(0)
+    }
+
+    /// Инструкция swap описана в настройках (иначе DEX используется только для цен)
+    pub fn can_execute(&self) -> bool {
+        !self.swap_accounts.is_empty()
+    }
+
+    /// Разбор данных аккаунта пула
+    pub fn parse_pool(&self, data: &[u8]) -> Result<Pool> {
+        if let Some(size) = self.account_size {
+            if data.len() as u64 != size {
+                anyhow::bail!("Размер аккаунта {} байт не совпадает с пулом ({} байт)", data.len(), size);
+            }
+        }
+        let fee = match self.fee {
+            FeeSource::Account { numerator_offset, denominator_offset } => {
+                Fee::new(read_u64(data, numerator_offset)?, read_u64(data, denominator_offset)?)
+                    .context("Некорректная комиссия пула")?
+            }
+            FeeSource::Fixed(fee) => fee,
+        };
+        Ok(Pool {
+            token_a_mint: read_pubkey(data, self.token_a_mint_offset)?,
+            token_b_mint: read_pubkey(data, self.token_b_mint_offset)?,
+            token_a_vault: read_pubkey(data, self.token_a_vault_offset)?,
+            token_b_vault: read_pubkey(data, self.token_b_vault_offset)?,
+            fee,
+        })
+    }
+}
+
+/// Поля пула пользовательского AMM
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pool {
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    /// Хранилища (SPL токен-аккаунты) резервов пула
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub fee: Fee,
+}
+
+impl Pool {
+    /// Хранилище пула для токена и хранилище противоположного токена
+    pub fn vaults_for(&self, from_mint: &Pubkey) -> Result<(Pubkey, Pubkey)> {
+        if *from_mint == self.token_a_mint {
+            Ok((self.token_a_vault, self.token_b_vault))
+        } else if *from_mint == self.token_b_mint {
+            Ok((self.token_b_vault, self.token_a_vault))
+        } else {
+            anyhow::bail!("Токен {} не торгуется в пуле", from_mint)
+        }
+    }
+}
+
+/// Инструкция swap: amount_in токена `from_mint` на не меньше min_amount_out
+///
+/// Данные: swap_data_prefix, затем amount_in и min_amount_out (u64 LE).
+/// Токены списываются с ATA кошелька и зачисляются на ATA кошелька.
+pub fn swap_instruction(
+    layout: &PoolLayout,
+    pool_address: &Pubkey,
+    pool: &Pool,
+    user_wallet: &Pubkey,
+    from_mint: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<Instruction> {
+    if !layout.can_execute() {
+        anyhow::bail!("Аккаунты инструкции swap не заданы (swap_accounts)");
+    }
+    let (pool_source, pool_destination) = pool.vaults_for(from_mint)?;
+    let to_mint = if *from_mint == pool.token_a_mint { pool.token_b_mint } else { pool.token_a_mint };
+    let accounts = layout.swap_accounts.iter()
+        .map(|account| match account {
+            SwapAccount::Pool => AccountMeta::new(*pool_address, false),
+            SwapAccount::Authority => AccountMeta::new_readonly(
+                Pubkey::find_program_address(&[pool_address.as_ref()], &layout.program_id).0,
+                false,
+            ),
+            SwapAccount::User => AccountMeta::new_readonly(*user_wallet, true),
+            SwapAccount::UserSource => AccountMeta::new(get_associated_token_address(user_wallet, from_mint), false),
+            SwapAccount::UserDestination => AccountMeta::new(get_associated_token_address(user_wallet, &to_mint), false),
+            SwapAccount::PoolSource => AccountMeta::new(pool_source, false),
+            SwapAccount::PoolDestination => AccountMeta::new(pool_destination, false),
+            SwapAccount::TokenProgram => AccountMeta::new_readonly(spl_token::id(), false),
+            SwapAccount::Fixed { address, writable: true } => AccountMeta::new(*address, false),
+            SwapAccount::Fixed { address, writable: false } => AccountMeta::new_readonly(*address, false),
+        })
+        .collect();
+
+    let mut data = Vec::with_capacity(layout.swap_data_prefix.len() + 16);
+    data.extend_from_slice(&layout.swap_data_prefix);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Ok(Instruction { program_id: layout.program_id, accounts, data })
+}
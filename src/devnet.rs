@@ -0,0 +1,327 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::config::NetworkConfig;
+use crate::rpc::create_rpc_client;
+use crate::units::{sol_to_lamports, Rounding};
+use crate::wallet::Wallet;
+
+/// Максимальное время ожидания подтверждения airdrop
+const AIRDROP_CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+/// Интервал опроса статуса airdrop
+const AIRDROP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Genesis hash кластера devnet
+pub const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+
+/// Program ID SPL Token Swap (пул x*y=k для тестовых пулов)
+pub const TOKEN_SWAP_PROGRAM_ID: &str = "SwapsVeCiPHMUAtzQWZw7RjsKjgCjhwU55QGu4U1Szw";
+/// Владелец аккаунта комиссий, которого требует сборка SPL Token Swap для публичных кластеров
+const TOKEN_SWAP_FEE_OWNER: &str = "HfoTxFR1Tm6kGmWgYWD6J7YHVy1UwqSULUGVLXkJqaKN";
+/// Размер аккаунта пула SPL Token Swap (версия + SwapV1)
+const TOKEN_SWAP_ACCOUNT_LEN: usize = 324;
+/// Decimals тестовых токенов
+pub const TEST_TOKEN_DECIMALS: u8 = 6;
+
+/// Проверка, что операции выполняются только на devnet
+///
+/// Faucet существует только на devnet/testnet, но защищаемся явно,
+/// чтобы вспомогательные функции никогда не отправляли транзакции в mainnet.
+/// Кластер определяется по genesis hash, а не по адресу RPC: адрес
+/// провайдера может не содержать имени кластера или вводить в заблуждение.
+pub async fn ensure_devnet(network: &NetworkConfig) -> Result<()> {
+    let network = network.clone();
+    let genesis_hash = tokio::task::spawn_blocking(move || -> Result<String> {
+        let client = create_rpc_client(&network)?;
+        let hash = client.get_genesis_hash()
+            .context("Не удалось получить genesis hash кластера")?;
+        Ok(hash.to_string())
+    })
+    .await
+    .context("Задача проверки кластера завершилась с ошибкой")??;
+
+    if genesis_hash != DEVNET_GENESIS_HASH {
+        anyhow::bail!("Операция доступна только на devnet (genesis hash кластера {})", genesis_hash);
+    }
+    Ok(())
+}
+
+/// Запрос airdrop и ожидание его подтверждения
+pub async fn request_airdrop(network: &NetworkConfig, pubkey: &Pubkey, sol: Decimal) -> Result<Signature> {
+    ensure_devnet(network).await?;
+
+    let lamports = sol_to_lamports(sol, Rounding::Down)
+        .context("Некорректное количество SOL для airdrop")?;
+
+    let network = network.clone();
+    let pubkey = *pubkey;
+    tokio::task::spawn_blocking(move || -> Result<Signature> {
+        let client = create_rpc_client(&network)?;
+        let signature = client.request_airdrop(&pubkey, lamports)
+            .context("Не удалось запросить airdrop (возможно, превышен лимит faucet)")?;
+
+        log::info!("Airdrop {} SOL запрошен для {}: {}", sol, pubkey, signature);
+
+        let started = std::time::Instant::now();
+        while started.elapsed() < AIRDROP_CONFIRM_TIMEOUT {
+            if client.confirm_transaction(&signature).unwrap_or(false) {
+                log::info!("Airdrop подтверждён: {}", signature);
+                return Ok(signature);
+            }
+            std::thread::sleep(AIRDROP_POLL_INTERVAL);
+        }
+
+        anyhow::bail!("Airdrop не подтверждён за {:?}: {}", AIRDROP_CONFIRM_TIMEOUT, signature)
+    })
+    .await
+    .context("Задача airdrop завершилась с ошибкой")?
+}
+
+/// Создание ATA кошелька для указанных mint адресов (идемпотентно)
+///
+/// Возвращает адреса ATA в порядке переданных mint адресов.
+pub async fn create_token_accounts(network: &NetworkConfig, wallet: &Wallet, mints: &[String]) -> Result<Vec<Pubkey>> {
+    ensure_devnet(network).await?;
+
+    if mints.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut instructions = Vec::with_capacity(mints.len());
+    let mut addresses = Vec::with_capacity(mints.len());
+    for mint in mints {
+        let mint = Pubkey::from_str(mint)
+            .with_context(|| format!("Некорректный mint адрес: {}", mint))?;
+        addresses.push(get_associated_token_address(wallet.pubkey(), &mint));
+        instructions.push(
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                wallet.pubkey(),
+                wallet.pubkey(),
+                &mint,
+                &spl_token::id(),
+            ),
+        );
+    }
+
+    let signature = send_and_confirm(network, &instructions, wallet, &[]).await
+        .context("Не удалось создать ATA")?;
+
+    log::info!("Создано/проверено {} ATA: {}", addresses.len(), signature);
+    for address in &addresses {
+        log::info!("ATA: {}", address);
+    }
+
+    Ok(addresses)
+}
+
+/// Пополнение кошелька до минимального баланса (утилита для devnet тестов)
+///
+/// Airdrop запрашивается только если баланс ниже `min_sol`.
+pub async fn ensure_funded(network: &NetworkConfig, wallet: &Wallet, min_sol: Decimal) -> Result<u64> {
+    ensure_devnet(network).await?;
+
+    let min_lamports = sol_to_lamports(min_sol, Rounding::Down)
+        .context("Некорректный минимальный баланс")?;
+
+    let balance = wallet.get_balance(network).await?;
+    if balance >= min_lamports {
+        return Ok(balance);
+    }
+
+    request_airdrop(network, wallet.pubkey(), min_sol).await?;
+    wallet.get_balance(network).await
+}
+
+/// Тестовый пул SPL Token Swap (x*y=k) из двух новых токенов
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestPool {
+    /// Аккаунт пула
+    pub swap: Pubkey,
+    /// PDA пула, владелец резервов и mint токенов LP
+    pub authority: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// Резерв токена A
+    pub token_a: Pubkey,
+    /// Резерв токена B
+    pub token_b: Pubkey,
+    /// Mint токенов LP
+    pub pool_mint: Pubkey,
+}
+
+/// Создание тестового пула на devnet: два новых токена (mint authority — кошелёк)
+/// и пул SPL Token Swap с резервами `amount` каждого токена (в минимальных единицах)
+///
+/// Остаток выпуска токенов остаётся на ATA кошелька для тестовых свопов.
+pub async fn seed_test_pool(network: &NetworkConfig, wallet: &Wallet, amount: u64) -> Result<TestPool> {
+    ensure_devnet(network).await?;
+    if amount == 0 {
+        anyhow::bail!("Резерв тестового пула должен быть больше 0");
+    }
+
+    let program_id = Pubkey::from_str(TOKEN_SWAP_PROGRAM_ID)?;
+    let fee_owner = Pubkey::from_str(TOKEN_SWAP_FEE_OWNER)?;
+    let (mint_a, mint_b, swap, pool_mint) = (Keypair::new(), Keypair::new(), Keypair::new(), Keypair::new());
+    let (authority, _) = Pubkey::find_program_address(&[swap.pubkey().as_ref()], &program_id);
+    let supply = amount.checked_mul(2).context("Слишком большой резерв тестового пула")?;
+
+    let rent_network = network.clone();
+    let (mint_rent, swap_rent) = tokio::task::spawn_blocking(move || -> Result<(u64, u64)> {
+        let client = create_rpc_client(&rent_network)?;
+        Ok((
+            client.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?,
+            client.get_minimum_balance_for_rent_exemption(TOKEN_SWAP_ACCOUNT_LEN)?,
+        ))
+    })
+    .await
+    .context("Задача чтения rent завершилась с ошибкой")?
+    .context("Не удалось получить минимальный баланс для rent")?;
+
+    // Токены пула: выпуск на ATA кошелька
+    let mut instructions = Vec::new();
+    for mint in [&mint_a, &mint_b] {
+        instructions.extend(create_mint(wallet.pubkey(), &mint.pubkey(), wallet.pubkey(), mint_rent)?);
+        instructions.push(spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            wallet.pubkey(), wallet.pubkey(), &mint.pubkey(), &spl_token::id(),
+        ));
+        instructions.push(spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &get_associated_token_address(wallet.pubkey(), &mint.pubkey()),
+            wallet.pubkey(),
+            &[],
+            supply,
+        )?);
+    }
+    send_and_confirm(network, &instructions, wallet, &[&mint_a, &mint_b]).await
+        .context("Не удалось создать тестовые токены")?;
+
+    // Аккаунт пула, mint LP и резервы, принадлежащие PDA пула
+    let token_a = get_associated_token_address(&authority, &mint_a.pubkey());
+    let token_b = get_associated_token_address(&authority, &mint_b.pubkey());
+    let mut instructions = vec![system_instruction::create_account(
+        wallet.pubkey(), &swap.pubkey(), swap_rent, TOKEN_SWAP_ACCOUNT_LEN as u64, &program_id,
+    )];
+    instructions.extend(create_mint(wallet.pubkey(), &pool_mint.pubkey(), &authority, mint_rent)?);
+    for (mint, reserve) in [(&mint_a, &token_a), (&mint_b, &token_b)] {
+        instructions.push(spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            wallet.pubkey(), &authority, &mint.pubkey(), &spl_token::id(),
+        ));
+        instructions.push(spl_token::instruction::transfer(
+            &spl_token::id(),
+            &get_associated_token_address(wallet.pubkey(), &mint.pubkey()),
+            reserve,
+            wallet.pubkey(),
+            &[],
+            amount,
+        )?);
+    }
+    for owner in [&fee_owner, wallet.pubkey()] {
+        instructions.push(spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            wallet.pubkey(), owner, &pool_mint.pubkey(), &spl_token::id(),
+        ));
+    }
+    send_and_confirm(network, &instructions, wallet, &[&swap, &pool_mint]).await
+        .context("Не удалось создать аккаунты тестового пула")?;
+
+    let pool = TestPool {
+        swap: swap.pubkey(),
+        authority,
+        mint_a: mint_a.pubkey(),
+        mint_b: mint_b.pubkey(),
+        token_a,
+        token_b,
+        pool_mint: pool_mint.pubkey(),
+    };
+    let initialize = token_swap_initialize(
+        &pool,
+        &get_associated_token_address(&fee_owner, &pool.pool_mint),
+        &get_associated_token_address(wallet.pubkey(), &pool.pool_mint),
+    )?;
+    let signature = send_and_confirm(network, &[initialize], wallet, &[&swap]).await
+        .context("Не удалось инициализировать тестовый пул")?;
+
+    log::info!("Тестовый пул {} создан: {}", pool.swap, signature);
+    Ok(pool)
+}
+
+/// Инструкция Initialize SPL Token Swap: кривая x*y=k и комиссии, которые
+/// допускает сборка программы для публичных кластеров
+pub fn token_swap_initialize(pool: &TestPool, fee_account: &Pubkey, destination: &Pubkey) -> Result<Instruction> {
+    // Fees: trade 25/10000, owner trade 5/10000, owner withdraw 0/0, host 20/100
+    const FEES: [u64; 8] = [25, 10_000, 5, 10_000, 0, 0, 20, 100];
+    // SwapCurve: ConstantProduct без параметров
+    const CONSTANT_PRODUCT: u8 = 0;
+
+    let mut data = vec![0u8];
+    for value in FEES {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(CONSTANT_PRODUCT);
+    data.extend_from_slice(&[0u8; 32]);
+
+    Ok(Instruction {
+        program_id: Pubkey::from_str(TOKEN_SWAP_PROGRAM_ID)?,
+        accounts: vec![
+            AccountMeta::new(pool.swap, true),
+            AccountMeta::new_readonly(pool.authority, false),
+            AccountMeta::new_readonly(pool.token_a, false),
+            AccountMeta::new_readonly(pool.token_b, false),
+            AccountMeta::new(pool.pool_mint, false),
+            AccountMeta::new_readonly(*fee_account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data,
+    })
+}
+
+/// Создание и инициализация mint без freeze authority
+fn create_mint(payer: &Pubkey, mint: &Pubkey, authority: &Pubkey, rent: u64) -> Result<Vec<Instruction>> {
+    Ok(vec![
+        system_instruction::create_account(payer, mint, rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), mint, authority, None, TEST_TOKEN_DECIMALS)?,
+    ])
+}
+
+/// Подпись кошельком (и дополнительными ключами новых аккаунтов), отправка и подтверждение
+async fn send_and_confirm(
+    network: &NetworkConfig,
+    instructions: &[Instruction],
+    wallet: &Wallet,
+    signers: &[&Keypair],
+) -> Result<Signature> {
+    let client = create_rpc_client(network)?;
+    let (client, recent_blockhash) = tokio::task::spawn_blocking(move || {
+        let blockhash = client.get_latest_blockhash();
+        (client, blockhash)
+    })
+    .await
+    .context("Задача получения blockhash завершилась с ошибкой")?;
+    let recent_blockhash = recent_blockhash.context("Не удалось получить blockhash")?;
+
+    let mut keypairs = vec![wallet.keypair()];
+    keypairs.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(wallet.pubkey()),
+        &keypairs,
+        recent_blockhash,
+    );
+
+    tokio::task::spawn_blocking(move || {
+        client.send_and_confirm_transaction(&transaction)
+            .context("Транзакция не подтверждена")
+    })
+    .await
+    .context("Задача отправки транзакции завершилась с ошибкой")?
+}
@@ -1,16 +1,55 @@
 use anyhow::{Context, Result};
 use rust_decimal::Decimal;
+use std::collections::HashSet;
 use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
 use solana_sdk::{
+    account::Account,
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    hash::Hash,
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
+    signature::Signature,
     instruction::{Instruction, AccountMeta},
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError, VersionedTransaction},
     system_program,
 };
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
-use crate::config::Config;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::client_error::ClientError;
+use solana_client::rpc_client::{RpcClient, SerializableTransaction};
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use spl_associated_token_account::get_associated_token_address;
+use crate::balance_guard::BalanceGuard;
+use crate::clock_skew::ClockSkew;
+use crate::config::{Config, TemplatesConfig, VenueType};
+use crate::math::{self, Fee};
+use crate::raydium_amm::{self, AmmInfo};
+use crate::profile::{self, Stage};
+use crate::receipt::{self, ExecutionStage};
+use crate::reserve_guard::{LastReserves, Reserves};
+use crate::custom_amm;
+use crate::failure::FailureKind;
+use crate::lifinity;
+use crate::openbook_v2;
+use crate::presend::{self, ExpectedOutput, Rejected};
+use crate::price_snapshot::PriceSlots;
+use crate::pool_registry::{
+    CustomAmmPoolRegistry, LifinityPoolRegistry, OpenBookMarketRegistry, PoolInfo, RaydiumPoolRegistry, SaberPoolRegistry, WhirlpoolRegistry,
+};
+use crate::rpc::{create_rpc_client, MAX_MULTIPLE_ACCOUNTS};
+use crate::rpc_cache::RpcCache;
+use crate::saber;
+use crate::tokens::TokenRegistry;
+use crate::trade_memo::TradeMemo;
+use crate::tx_error::{decode_client_error, decode_versioned_client_error, DecodedTxError};
+use crate::units::{self, Rounding};
+use crate::tx_template::{
+    AmountSlot, TemplateCache, TxTemplate, SWAP_AMOUNT_IN_OFFSET, SWAP_MIN_AMOUNT_OUT_OFFSET,
+};
 use crate::wallet::Wallet;
+use crate::whirlpool::{self, TickArray, Whirlpool};
+use crate::wsol::{is_native_sol, WsolWrap};
 
 /// Унифицированный интерфейс для работы с DEX
 #[async_trait::async_trait]
@@ -34,43 +73,192 @@ pub trait DexInterface: Send + Sync {
         min_output: Decimal,
         wallet: &Wallet,
     ) -> Result<String>; // Возвращает signature транзакции
+
+    /// Комиссия пула пары в процентах (None — неизвестна, используется типичная для DEX)
+    async fn pool_fee_percent(&self, _base_token: &str, _quote_token: &str) -> Option<Decimal> {
+        None
+    }
+
+    /// Резервы пула пары (base, quote) в минимальных единицах из последнего запроса цены
+    ///
+    /// None — резервы неизвестны или DEX их не сообщает (книги ордеров, CLMM, оракульные пулы).
+    fn pool_reserves(&self, _base_token: &str, _quote_token: &str) -> Option<Reserves> {
+        None
+    }
+
+    /// Слот, на котором прочитаны аккаунты последнего запроса цены пары
+    ///
+    /// None — слот неизвестен или DEX его не сообщает.
+    fn price_slot(&self, _base_token: &str, _quote_token: &str) -> Option<u64> {
+        None
+    }
+
+    /// Доступная ликвидность пары (base, quote) в минимальных единицах
+    ///
+    /// Для пулов — балансы хранилищ пула, для книг ордеров — объём заявок:
+    /// base в asks (можно купить) и quote в bids (можно получить за продажу base).
+    async fn get_liquidity(&self, _base_token: &str, _quote_token: &str) -> Result<Reserves> {
+        anyhow::bail!("{}: получение ликвидности не поддерживается", self.name())
+    }
+
+    /// Поиск пула пары через реестр пулов DEX (устаревшая запись находится заново)
+    ///
+    /// Возвращает false, если у DEX нет реестра пулов.
+    async fn refresh_pool(&self, _base_token: &str, _quote_token: &str) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Сборка шаблонов транзакций свопа для пар из настроек (в обоих направлениях)
+    ///
+    /// Возвращает количество собранных шаблонов; DEX без поддержки шаблонов возвращают 0.
+    async fn prepare_swap_templates(&self, _config: &TemplatesConfig, _payer: &Pubkey) -> Result<usize> {
+        Ok(0)
+    }
+
+    /// Может ли своп DEX войти в атомарную транзакцию вместе со свопом другого DEX
+    ///
+    /// Нужно, чтобы полученные токены поступали на ATA кошелька той же инструкцией
+    /// (у Serum v3 средства рассчитываются отдельно через settle_funds).
+    fn supports_atomic_swap(&self) -> bool {
+        false
+    }
+
+    /// Инструкции свопа для атомарной транзакции (без memo, подписи и отправки)
+    ///
+    /// Включают создание недостающих ATA и обёртку SOL вокруг свопа, поэтому
+    /// инструкции нескольких свопов можно ставить в транзакцию подряд.
+    async fn build_swap_instructions(
+        &self,
+        _from_token: &str,
+        _to_token: &str,
+        _amount: Decimal,
+        _min_output: Decimal,
+        _wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        anyhow::bail!("{}: сборка инструкций свопа для атомарной транзакции не поддерживается", self.name())
+    }
+}
+
+/// DEX, поддерживаемые в enabled_dexes
+pub const SUPPORTED_DEXES: &[&str] = &["raydium", "orca", "serum", "openbook_v2", "lifinity", "saber"];
+
+/// Типичная комиссия пула DEX в процентах
+///
+/// Raydium: 0.25%, Orca: 0.3%, Serum, OpenBook v2 и Saber: 0.04%, Lifinity: 0.1%; для неизвестных DEX — 0.25%.
+pub fn default_fee_percent(dex_name: &str) -> Decimal {
+    match dex_name {
+        "raydium" => Decimal::new(25, 2),
+        "lifinity" => Decimal::new(1, 1),
+        "orca" => Decimal::new(3, 1),
+        "serum" | "openbook_v2" | "saber" => Decimal::new(4, 2),
+        _ => Decimal::new(25, 2),
+    }
+}
+
+/// Тип площадки DEX
+pub fn venue_type(dex_name: &str) -> VenueType {
+    match dex_name {
+        "orca" => VenueType::Clmm,
+        "serum" | "openbook_v2" => VenueType::Orderbook,
+        _ => VenueType::Amm,
+    }
+}
+
+/// Минимальный объём сделки на DEX (в base токене) по умолчанию
+///
+/// Orderbook (Serum, OpenBook v2) не принимает ордера меньше размера лота рынка (0.1 SOL для SOL/USDC),
+/// у AMM слишком маленький своп даёт пыль на выходе после округления и комиссий.
+pub fn default_min_trade_amount(dex_name: &str) -> Decimal {
+    match dex_name {
+        "serum" | "openbook_v2" => Decimal::new(1, 1),
+        _ => Decimal::new(1, 3),
+    }
 }
 
 /// Менеджер DEX
+///
+/// Общий для движка арбитража и веб-сервера (`Arc<DexManager>`): адаптеры не
+/// меняются после создания, отключение DEX во время работы хранится внутри.
 pub struct DexManager {
     dexes: Vec<Box<dyn DexInterface>>,
+    /// DEX, отключённые во время работы (не участвуют в поиске возможностей)
+    disabled: RwLock<HashSet<String>>,
+    /// Пары из токенов кошелька (pair_discovery) сверх dex.trading_pairs
+    discovered_pairs: RwLock<Vec<String>>,
+    tokens: Arc<TokenRegistry>,
     config: Config,
 }
 
 impl DexManager {
     /// Создание нового менеджера DEX
     pub fn new(config: &Config) -> Result<Self> {
+        Self::new_with_clock_skew(config, ClockSkew::new())
+    }
+
+    /// Создание менеджера DEX с общим трекером расхождения часов
+    ///
+    /// Адаптеры, проверяющие время из аккаунтов (оракулы, изменение A, срок
+    /// ордеров), сравнивают его со временем кластера, а не с локальными часами.
+    pub fn new_with_clock_skew(config: &Config, clock_skew: ClockSkew) -> Result<Self> {
         let mut dexes: Vec<Box<dyn DexInterface>> = Vec::new();
+        let rpc_cache = Arc::new(RpcCache::open(&config.rpc_cache, &config.storage.data_dir));
+        let tokens = Arc::new(TokenRegistry::new(&config.tokens)?.with_rpc_cache(rpc_cache.clone()));
 
         // Регистрация DEX согласно конфигурации
         for dex_name in &config.dex.enabled_dexes {
             match dex_name.as_str() {
                 "raydium" => {
-                    dexes.push(Box::new(RaydiumDex::new(config)?));
+                    dexes.push(Box::new(RaydiumDex::new(config, rpc_cache.clone(), tokens.clone())?));
                 }
                 "orca" => {
-                    dexes.push(Box::new(OrcaDex::new(config)?));
+                    dexes.push(Box::new(OrcaDex::new(config, tokens.clone())?));
                 }
                 "serum" => {
-                    dexes.push(Box::new(SerumDex::new(config)?));
+                    dexes.push(Box::new(SerumDex::new(config, rpc_cache.clone(), tokens.clone())?));
+                }
+                "openbook_v2" => {
+                    dexes.push(Box::new(OpenBookV2Dex::new(config, rpc_cache.clone(), tokens.clone(), clock_skew.clone())?));
+                }
+                "lifinity" => {
+                    dexes.push(Box::new(LifinityDex::new(config, rpc_cache.clone(), tokens.clone(), clock_skew.clone())?));
                 }
-                _ => {
-                    log::warn!("Неизвестный DEX: {}, пропускаем", dex_name);
+                "saber" => {
+                    dexes.push(Box::new(SaberDex::new(config, rpc_cache.clone(), tokens.clone(), clock_skew.clone())?));
                 }
+                name => match config.dex.custom.get(name) {
+                    Some(custom) => {
+                        dexes.push(Box::new(ConfigurableAmmDex::new(config, name, custom, rpc_cache.clone(), tokens.clone())?));
+                    }
+                    None => log::warn!("Неизвестный DEX: {}, пропускаем", dex_name),
+                },
             }
         }
 
         log::info!("Зарегистрировано {} DEX", dexes.len());
 
-        Ok(Self {
+        Ok(Self::with_tokens(config, dexes, tokens))
+    }
+
+    /// Менеджер с готовыми адаптерами DEX вместо создаваемых по enabled_dexes (моки в тестах)
+    #[allow(dead_code)]
+    pub fn from_dexes(config: &Config, dexes: Vec<Box<dyn DexInterface>>) -> Self {
+        let tokens = TokenRegistry::new(&config.tokens).expect("секция [tokens] проверяется в Config::validate");
+        Self::with_tokens(config, dexes, Arc::new(tokens))
+    }
+
+    fn with_tokens(config: &Config, dexes: Vec<Box<dyn DexInterface>>, tokens: Arc<TokenRegistry>) -> Self {
+        Self {
             dexes,
+            disabled: RwLock::new(HashSet::new()),
+            discovered_pairs: RwLock::new(Vec::new()),
+            tokens,
             config: config.clone(),
-        })
+        }
+    }
+
+    /// Реестр токенов, общий для адаптеров DEX
+    pub fn tokens(&self) -> Arc<TokenRegistry> {
+        self.tokens.clone()
     }
 
     /// Получение всех зарегистрированных DEX
@@ -78,12 +266,606 @@ impl DexManager {
         &self.dexes
     }
 
+    /// Включённые DEX (участвуют в поиске возможностей)
+    pub fn active_dexes(&self) -> Vec<&dyn DexInterface> {
+        let disabled = self.disabled.read().expect("блокировка списка отключённых DEX отравлена");
+        self.dexes.iter()
+            .filter(|dex| !disabled.contains(dex.name()))
+            .map(|dex| dex.as_ref())
+            .collect()
+    }
+
+    /// Включён ли DEX
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.read().expect("блокировка списка отключённых DEX отравлена").contains(name)
+    }
+
+    /// Включение или отключение зарегистрированного DEX во время работы
+    ///
+    /// Отключённый DEX не участвует в поиске возможностей; уже найденные
+    /// сделки исполняются до конца. Ошибка, если DEX не зарегистрирован.
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        if self.get_dex(name).is_none() {
+            anyhow::bail!("DEX {} не зарегистрирован", name);
+        }
+        let mut disabled = self.disabled.write().expect("блокировка списка отключённых DEX отравлена");
+        if enabled {
+            disabled.remove(name);
+        } else {
+            disabled.insert(name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Торговые пары поиска: dex.trading_pairs и пары из токенов кошелька
+    pub fn trading_pairs(&self) -> Vec<String> {
+        let mut pairs = self.config.dex.trading_pairs.clone();
+        pairs.extend(self.discovered_pairs());
+        pairs
+    }
+
+    /// Пары из токенов кошелька, не входящие в dex.trading_pairs
+    pub fn discovered_pairs(&self) -> Vec<String> {
+        self.discovered_pairs.read().expect("блокировка найденных пар отравлена").clone()
+    }
+
+    /// Замена пар из токенов кошелька (пары из dex.trading_pairs и повторы отбрасываются)
+    pub fn set_discovered_pairs(&self, pairs: Vec<String>) {
+        let mut discovered: Vec<String> = Vec::with_capacity(pairs.len());
+        for pair in pairs {
+            if !self.config.dex.trading_pairs.contains(&pair) && !discovered.contains(&pair) {
+                discovered.push(pair);
+            }
+        }
+        *self.discovered_pairs.write().expect("блокировка найденных пар отравлена") = discovered;
+    }
+
     /// Получение DEX по имени
     pub fn get_dex(&self, name: &str) -> Option<&dyn DexInterface> {
         self.dexes.iter()
             .find(|dex| dex.name() == name)
             .map(|dex| dex.as_ref())
     }
+
+    /// Поиск пулов торговых пар через реестры включённых DEX
+    ///
+    /// Возвращает количество найденных пулов; ошибки пар пишутся в лог.
+    pub async fn refresh_pools(&self) -> usize {
+        let mut refreshed = 0;
+        for pair in self.trading_pairs() {
+            let Some((base_token, quote_token)) = pair.split_once('/') else { continue };
+            for dex in self.active_dexes() {
+                if !self.config.dex.lists_pair(dex.name(), &pair) {
+                    continue;
+                }
+                match dex.refresh_pool(base_token, quote_token).await {
+                    Ok(true) => refreshed += 1,
+                    Ok(false) => {}
+                    Err(e) => log::warn!("{}: не удалось найти пул {}: {:#}", dex.name(), pair, e),
+                }
+            }
+        }
+        refreshed
+    }
+
+    /// Сборка шаблонов транзакций для приоритетных пар на всех DEX
+    ///
+    /// Ошибка одного DEX не мешает остальным: без шаблона своп собирается как обычно.
+    pub async fn prepare_templates(&self, config: &TemplatesConfig, payer: &Pubkey) -> usize {
+        if !config.enabled || config.pairs.is_empty() {
+            return 0;
+        }
+        let mut total = 0;
+        for dex in &self.dexes {
+            match dex.prepare_swap_templates(config, payer).await {
+                Ok(0) => {}
+                Ok(count) => {
+                    log::info!("{}: подготовлено шаблонов транзакций: {}", dex.name(), count);
+                    total += count;
+                }
+                Err(e) => log::warn!("{}: не удалось подготовить шаблоны транзакций: {:#}", dex.name(), e),
+            }
+        }
+        total
+    }
+
+    /// Закрытие токен-аккаунтов кошелька: рента возвращается на кошелёк
+    ///
+    /// Закрываются только пустые аккаунты (иначе программа SPL Token отклонит
+    /// транзакцию). Возвращает подпись после подтверждения транзакции: по ней
+    /// читаются комиссия и возвращённая рента.
+    pub async fn close_token_accounts(&self, accounts: &[Pubkey], wallet: &Wallet) -> Result<String> {
+        let owner = wallet.pubkey();
+        let instructions = accounts.iter()
+            .map(|account| spl_token::instruction::close_account(&spl_token::id(), account, owner, owner, &[]))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Не удалось построить инструкции закрытия токен-аккаунтов")?;
+
+        let rpc_client = create_rpc_client(&self.config.network)?;
+        let recent_blockhash = rpc_client
+            .get_latest_blockhash()
+            .context("Не удалось получить blockhash")?;
+        let transaction = Transaction::new_signed_with_payer(&instructions, Some(owner), &[wallet.keypair()], recent_blockhash);
+        let signature = send_transaction_with_retry(&rpc_client, &transaction, wallet, 3, None).await?;
+        for account in accounts {
+            wallet.forget_token_account(account);
+        }
+
+        rpc_client
+            .poll_for_signature_with_commitment(transaction.get_signature(), rpc_client.commitment())
+            .with_context(|| format!("Транзакция закрытия токен-аккаунтов {} не подтверждена", signature))?;
+        Ok(signature)
+    }
+
+    /// Атомарная сделка: инструкции свопов, memo сделки и проверка баланса в одной транзакции
+    ///
+    /// Транзакция исполняется или откатывается целиком — исполненной одной ноги
+    /// не бывает. Срабатывание проверки баланса — неудача исполнения (сделка
+    /// не дала прибыли), а не нехватка средств. Возвращает None, если транзакция
+    /// не помещается в пакет: ничего не отправлено, сделку можно исполнить по ногам.
+    pub async fn execute_atomic(
+        &self,
+        swaps: Vec<Vec<Instruction>>,
+        guard: Option<&BalanceGuard>,
+        wallet: &Wallet,
+    ) -> Result<Option<String>> {
+        let build_started = Instant::now();
+        let (instructions, guard_index) = atomic_instructions(swaps, guard)?;
+
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        let size = transaction_size(&transaction);
+        if size > PACKET_DATA_SIZE {
+            log::info!(
+                "Атомарная транзакция {} байт больше лимита {} ({} инструкций), исполнение по ногам",
+                size, PACKET_DATA_SIZE, instructions.len()
+            );
+            return Ok(None);
+        }
+
+        let rpc_client = create_rpc_client(&self.config.network)?;
+        let recent_blockhash = rpc_client
+            .get_latest_blockhash()
+            .context("Не удалось получить blockhash")?;
+        profile::record(Stage::Build, None, build_started.elapsed());
+        let sign_started = Instant::now();
+        transaction.try_sign(&[wallet.keypair()], recent_blockhash)
+            .context("Не удалось подписать атомарную транзакцию")?;
+        profile::record(Stage::Sign, None, sign_started.elapsed());
+
+        // Выход проверяет сама инструкция проверки баланса, отдельный ожидаемый выход не нужен
+        match profile::measure(Stage::Send, None, send_transaction_with_retry(&rpc_client, &transaction, wallet, 3, None)).await {
+            Ok(signature) => Ok(Some(signature)),
+            Err(e) => {
+                let guard_failure = guard_index.and_then(|index| {
+                    e.chain()
+                        .find_map(|cause| cause.downcast_ref::<DecodedTxError>())
+                        .and_then(|decoded| BalanceGuard::guard_failure(decoded, index))
+                });
+                match guard_failure {
+                    // Проверка сработала в симуляции перед отправкой: сделка отменена, а не провалена
+                    Some(decoded) if e.is::<Rejected>() => {
+                        let decoded = DecodedTxError { kind: FailureKind::Aborted, ..decoded };
+                        Err(anyhow::Error::new(decoded).context(Rejected))
+                    }
+                    Some(decoded) => Err(anyhow::Error::new(decoded)),
+                    None => Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// Инструкции атомарной транзакции: свопы, memo сделки и последней — проверка баланса
+///
+/// Возвращает индекс инструкции проверки для разбора ошибки. Та же сборка
+/// симулируется в `run --once`.
+pub fn atomic_instructions(swaps: Vec<Vec<Instruction>>, guard: Option<&BalanceGuard>) -> Result<(Vec<Instruction>, Option<u8>)> {
+    let mut instructions: Vec<Instruction> = swaps.into_iter().flatten().collect();
+    instructions.push(TradeMemo::current().instruction());
+    let guard_index = guard.map(|guard| guard.append_to(&mut instructions)).transpose()?;
+    Ok((instructions, guard_index))
+}
+
+/// Размер подписанной транзакции в байтах (лимит пакета — PACKET_DATA_SIZE)
+fn transaction_size(transaction: &Transaction) -> usize {
+    let signatures = transaction.message.header.num_required_signatures as usize;
+    // compact-u16 длина массива подписей: 1 байт до 127 подписей
+    1 + signatures * 64 + transaction.message_data().len()
+}
+
+/// Транзакция, ошибку симуляции которой можно расшифровать
+trait DecodableTransaction: SerializableTransaction + Clone {
+    fn decode_error(&self, error: &ClientError) -> Option<DecodedTxError>;
+
+    /// Та же транзакция с новым blockhash, заново подписанная ключом кошелька
+    fn resign(&self, recent_blockhash: Hash, wallet: &Wallet) -> Result<Self>;
+}
+
+impl DecodableTransaction for Transaction {
+    fn decode_error(&self, error: &ClientError) -> Option<DecodedTxError> {
+        decode_client_error(error, self)
+    }
+
+    fn resign(&self, recent_blockhash: Hash, wallet: &Wallet) -> Result<Self> {
+        let mut transaction = self.clone();
+        transaction.try_sign(&[wallet.keypair()], recent_blockhash)
+            .context("Не удалось переподписать транзакцию")?;
+        Ok(transaction)
+    }
+}
+
+impl DecodableTransaction for VersionedTransaction {
+    fn decode_error(&self, error: &ClientError) -> Option<DecodedTxError> {
+        decode_versioned_client_error(error, self)
+    }
+
+    fn resign(&self, recent_blockhash: Hash, wallet: &Wallet) -> Result<Self> {
+        let mut message = self.message.clone();
+        message.set_recent_blockhash(recent_blockhash);
+        VersionedTransaction::try_new(message, &[wallet.keypair()])
+            .context("Не удалось переподписать транзакцию")
+    }
+}
+
+/// Симуляция транзакции перед первой отправкой
+///
+/// Ошибка исполнения в симуляции и выход меньше `expected` отменяют сделку:
+/// транзакция не отправляется, ошибка помечена `Rejected`. Логи симуляции
+/// сохраняются в записи о сделке.
+fn simulate_before_send<T: DecodableTransaction>(
+    rpc_client: &RpcClient,
+    transaction: &T,
+    expected: Option<&ExpectedOutput>,
+) -> Result<()> {
+    let simulation = presend::simulate(rpc_client, transaction, expected)?;
+    receipt::record_simulation_logs(&simulation.logs);
+    let rejection = match simulation.error {
+        Some(ref error) => transaction.decode_error(&ClientError::from(error.clone()))
+            .map(|decoded| presend::rejection(decoded.with_logs(simulation.logs.clone()))),
+        None => simulation.shortfall(expected),
+    };
+    match rejection {
+        Some(decoded) => {
+            log::warn!("Симуляция перед отправкой не прошла, сделка отменена: {}", decoded);
+            for line in &decoded.logs {
+                log::debug!("  {}", line);
+            }
+            Err(anyhow::Error::new(decoded).context(Rejected))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Исполненная подпись среди отправленных и ошибка её исполнения, если была
+fn landed_signature(rpc_client: &RpcClient, signatures: &[Signature]) -> Result<Option<(Signature, Option<TransactionError>)>> {
+    let statuses = rpc_client.get_signature_statuses(signatures)?.value;
+    Ok(signatures.iter()
+        .zip(statuses)
+        .find_map(|(signature, status)| status.map(|status| (*signature, status.err))))
+}
+
+/// Отправка транзакции с повторами при сетевых ошибках
+///
+/// Перед первой отправкой транзакция симулируется (`simulate_before_send`):
+/// сделка, которая упадёт или даст выход меньше `expected`, не отправляется.
+/// Ошибки исполнения (неудачная preflight симуляция) детерминированы, поэтому
+/// не повторяются: они расшифровываются и возвращаются как `DecodedTxError`.
+///
+/// Ошибка отправки (например, таймаут ответа) не значит, что транзакция не попала
+/// в кластер. Перед каждым повтором проверяются статусы всех уже отправленных
+/// подписей: исполненная подпись возвращается, и сделка второй раз не уходит.
+/// Пока blockhash действителен, повтор отправляет те же байты — дубликат
+/// кластер отбросит. Истёкший blockhash заменяется новым с переподписью (memo
+/// сделки в транзакции сохраняется), но только если статусы проверены: без них
+/// повтор снова отправляет прежнюю транзакцию.
+async fn send_transaction_with_retry<T: DecodableTransaction>(
+    rpc_client: &RpcClient,
+    transaction: &T,
+    wallet: &Wallet,
+    max_retries: u32,
+    expected: Option<&ExpectedOutput>,
+) -> Result<String> {
+    let mut last_error = None;
+    receipt::emit(ExecutionStage::Built, None);
+    simulate_before_send(rpc_client, transaction, expected)?;
+
+    let mut transaction = transaction.clone();
+    let mut sent = Vec::new();
+    for attempt in 0..max_retries {
+        if attempt > 0 {
+            // Срок blockhash проверяется до статусов: с истёкшим blockhash прежние
+            // транзакции уже не исполнятся, и отсутствие статусов окончательно
+            let expired = !rpc_client
+                .is_blockhash_valid(transaction.get_recent_blockhash(), rpc_client.commitment())
+                .unwrap_or(true);
+            match landed_signature(rpc_client, &sent) {
+                Ok(Some((signature, error))) => return landed(signature, error),
+                Ok(None) if expired => {
+                    let recent_blockhash = rpc_client
+                        .get_latest_blockhash()
+                        .context("Не удалось получить blockhash для повтора")?;
+                    transaction = transaction.resign(recent_blockhash, wallet)?;
+                    log::info!("blockhash истёк, транзакция переподписана: {}", transaction.get_signature());
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("Не удалось проверить статусы отправленных подписей, повтор без переподписи: {:#}", e),
+            }
+        }
+
+        if !sent.contains(transaction.get_signature()) {
+            sent.push(*transaction.get_signature());
+        }
+        match rpc_client.send_transaction(&transaction) {
+            Ok(signature) => {
+                log::info!("Транзакция отправлена успешно: {}", signature);
+                let signature = signature.to_string();
+                receipt::emit(ExecutionStage::Sent, Some(&signature));
+                return Ok(signature);
+            }
+            Err(e) => {
+                if let Some(decoded) = transaction.decode_error(&e) {
+                    log::error!("Симуляция транзакции не прошла: {}", decoded);
+                    for line in &decoded.logs {
+                        log::debug!("  {}", line);
+                    }
+                    return Err(decoded.into());
+                }
+
+                if attempt + 1 < max_retries {
+                    let delay = std::time::Duration::from_millis(100 * (attempt + 1) as u64);
+                    log::warn!("Попытка {} не удалась, повтор через {:?}: {}", attempt + 1, delay, e);
+                    tokio::time::sleep(delay).await;
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if let Ok(Some((signature, error))) = landed_signature(rpc_client, &sent) {
+        return landed(signature, error);
+    }
+
+    // Исходная ошибка RPC остаётся в цепочке, чтобы сбой классифицировался как временный
+    let message = format!("Не удалось отправить транзакцию после {} попыток", max_retries);
+    Err(match last_error {
+        Some(e) => anyhow::Error::new(e).context(message),
+        None => anyhow::anyhow!(message),
+    })
+}
+
+/// Итог подписи, исполненной несмотря на ошибку её отправки
+fn landed(signature: Signature, error: Option<TransactionError>) -> Result<String> {
+    let signature = signature.to_string();
+    match error {
+        None => {
+            log::warn!("Транзакция {} исполнена, хотя отправка завершилась ошибкой; повтор не нужен", signature);
+            receipt::emit(ExecutionStage::Sent, Some(&signature));
+            Ok(signature)
+        }
+        Some(e) => anyhow::bail!("Транзакция {} исполнена с ошибкой: {}", signature, e),
+    }
+}
+
+/// Поля сумм в инструкции swap (amount_in, min_amount_out)
+fn swap_amount_slots() -> [AmountSlot; 2] {
+    [
+        AmountSlot { instruction: 0, offset: SWAP_AMOUNT_IN_OFFSET },
+        AmountSlot { instruction: 0, offset: SWAP_MIN_AMOUNT_OUT_OFFSET },
+    ]
+}
+
+/// Направления свопа для пар из настроек шаблонов, которые торгуются на DEX
+fn template_directions<'a>(config: &'a TemplatesConfig, dex_config: &Config, dex_name: &str) -> Vec<(&'a str, &'a str)> {
+    config.pairs.iter()
+        .filter(|pair| dex_config.dex.lists_pair(dex_name, pair))
+        .filter_map(|pair| pair.split_once('/'))
+        .flat_map(|(base, quote)| [(base, quote), (quote, base)])
+        .collect()
+}
+
+/// Суммы свопа для инструкции в минимальных единицах токенов
+///
+/// Движок передаёт amount в целых токенах from_token, min_output — в целых
+/// токенах to_token; decimals берутся из реестра токенов. Обе суммы
+/// округляются вниз (`Rounding::INPUT` и `Rounding::MIN_OUTPUT`) при любой
+/// политике округления. Сумма меньше минимальной единицы токена — ошибка, а
+/// не пустой своп.
+fn swap_amounts(
+    tokens: &TokenRegistry,
+    client: &RpcClient,
+    from_token: &str,
+    to_token: &str,
+    amount: Decimal,
+    min_output: Decimal,
+) -> Result<(u64, u64)> {
+    let amount_in = tokens.to_base_units(client, &tokens.mint(from_token)?, amount, Rounding::INPUT)
+        .with_context(|| format!("Не удалось перевести amount {} {} в минимальные единицы", amount, from_token))?;
+    if amount_in == 0 {
+        anyhow::bail!("Сумма свопа {} {} меньше минимальной единицы токена", amount, from_token);
+    }
+    let min_amount_out = tokens.to_base_units(client, &tokens.mint(to_token)?, min_output, Rounding::MIN_OUTPUT)
+        .with_context(|| format!("Не удалось перевести min_output {} {} в минимальные единицы", min_output, to_token))?;
+    Ok((amount_in, min_amount_out))
+}
+
+/// Ожидаемый выход свопа для симуляции перед отправкой
+///
+/// Для нативного SOL выход не проверяется: временный ATA WSOL закрывается
+/// той же транзакцией, и симуляция проверяет только отсутствие ошибки.
+fn expected_output(tokens: &TokenRegistry, wallet: &Wallet, to_token: &str, min_amount_out: u64) -> Result<Option<ExpectedOutput>> {
+    if is_native_sol(to_token) {
+        return Ok(None);
+    }
+    Ok(Some(ExpectedOutput::new(wallet.pubkey(), &tokens.mint(to_token)?, min_amount_out)))
+}
+
+/// Инструкции вокруг свопа: недостающие ATA кошелька и обёртка SOL
+struct SwapAccounts {
+    setup: Vec<Instruction>,
+    cleanup: Vec<Instruction>,
+}
+
+impl SwapAccounts {
+    /// Своп без дополнительных инструкций (можно отправить по шаблону)
+    fn is_empty(&self) -> bool {
+        self.setup.is_empty() && self.cleanup.is_empty()
+    }
+
+    /// Инструкции транзакции: подготовка аккаунтов, своп, закрытие WSOL
+    fn around(self, swap: impl IntoIterator<Item = Instruction>) -> Vec<Instruction> {
+        let mut instructions = self.setup;
+        instructions.extend(swap);
+        instructions.extend(self.cleanup);
+        instructions
+    }
+}
+
+/// Подготовка токен-аккаунтов для свопа
+///
+/// Недостающие ATA создаются перед свопом: своп не падает на отсутствующем
+/// токен-аккаунте получаемого (или впервые используемого) токена. Нативный SOL
+/// оборачивается во временный ATA WSOL, который закрывается после свопа.
+fn swap_accounts(
+    tokens: &TokenRegistry,
+    client: &RpcClient,
+    wallet: &Wallet,
+    from_token: &str,
+    to_token: &str,
+    amount_in: u64,
+) -> Result<SwapAccounts> {
+    let wsol = WsolWrap::for_swap(wallet, from_token, to_token, amount_in)?;
+    let mut mints = Vec::with_capacity(2);
+    for token in [from_token, to_token].into_iter().filter(|token| !is_native_sol(token)) {
+        mints.push(tokens.mint(token)?);
+    }
+    let mut setup = wallet.missing_token_account_instructions(client, &mints)
+        .context("Не удалось проверить ATA кошелька для свопа")?;
+    setup.extend(wsol.wrap);
+    Ok(SwapAccounts { setup, cleanup: wsol.unwrap })
+}
+
+/// Исполнение свопа по шаблону: подстановка сумм, memo сделки и blockhash, подпись и отправка
+///
+/// Возвращает None, если шаблон собран для прежнего ключа кошелька —
+/// тогда транзакция собирается обычным путём.
+async fn send_from_template(
+    venue: &str,
+    rpc_client: &RpcClient,
+    template: &TxTemplate,
+    amount_in: u64,
+    min_amount_out: u64,
+    wallet: &Wallet,
+    expected: Option<&ExpectedOutput>,
+) -> Result<Option<String>> {
+    if template.payer() != wallet.pubkey() {
+        log::warn!("Шаблон транзакции собран для прежнего ключа кошелька, собираем транзакцию заново");
+        return Ok(None);
+    }
+
+    let recent_blockhash = rpc_client
+        .get_latest_blockhash()
+        .context("Не удалось получить blockhash")?;
+
+    // Сборка заранее выполнена: подстановка сумм и подпись учитываются как подпись
+    let sign_started = Instant::now();
+    let transaction = template.instantiate(&[amount_in, min_amount_out], &TradeMemo::current(), recent_blockhash, wallet)?;
+    profile::record(Stage::Sign, Some(venue), sign_started.elapsed());
+    profile::measure(Stage::Send, Some(venue), send_transaction_with_retry(rpc_client, &transaction, wallet, 3, expected))
+        .await
+        .map(Some)
+}
+
+/// Чтение аккаунтов одним запросом вместе со слотом, на котором они прочитаны
+fn get_accounts_with_slot(rpc_client: &RpcClient, addresses: &[Pubkey]) -> Result<(Vec<Option<Account>>, u64)> {
+    let response = rpc_client.get_multiple_accounts_with_commitment(addresses, rpc_client.commitment())?;
+    Ok((response.value, response.context.slot))
+}
+
+/// Текущий баланс ATA (0, если аккаунт ещё не создан)
+pub async fn fetch_token_balance(rpc_client: Arc<RpcClient>, token_account: Pubkey) -> Result<u64> {
+    tokio::task::spawn_blocking(move || token_balance(&rpc_client, &token_account))
+        .await
+        .context("Задача получения баланса токена завершилась с ошибкой")?
+}
+
+/// Текущий баланс ATA, блокирующий запрос (0, если аккаунт ещё не создан)
+pub fn token_balance(rpc_client: &RpcClient, token_account: &Pubkey) -> Result<u64> {
+    match rpc_client.get_token_account_balance(token_account) {
+        Ok(balance) => balance.amount
+            .parse::<u64>()
+            .context("Некорректный баланс токен аккаунта"),
+        // Нулевой баланс — только если узел подтвердил отсутствие ATA: ошибка
+        // транспорта не должна ослаблять проверку баланса
+        Err(e) => match rpc_client.get_account_with_commitment(token_account, rpc_client.commitment()) {
+            Ok(response) if response.value.is_none() => {
+                log::debug!("ATA {} не найден, баланс считается нулевым: {}", token_account, e);
+                Ok(0)
+            }
+            _ => Err(e).context("Не удалось получить баланс токен аккаунта"),
+        },
+    }
+}
+
+/// Загрузка address lookup tables из конфигурации
+fn fetch_lookup_tables(rpc_client: &RpcClient, config: &TemplatesConfig) -> Result<Vec<AddressLookupTableAccount>> {
+    config.lookup_tables.iter()
+        .map(|address| {
+            let key: Pubkey = address.parse()
+                .with_context(|| format!("Некорректный адрес lookup table: {}", address))?;
+            let data = rpc_client.get_account_data(&key)
+                .with_context(|| format!("Не удалось получить lookup table {}", key))?;
+            let table = AddressLookupTable::deserialize(&data)
+                .with_context(|| format!("Не удалось разобрать lookup table {}", key))?;
+            Ok(AddressLookupTableAccount {
+                key,
+                addresses: table.addresses.to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// Чтение аккаунтов пачками getMultipleAccounts не раньше слота первой пачки
+pub fn get_accounts_chunked(rpc_client: &RpcClient, addresses: &[Pubkey]) -> Result<Vec<(Vec<Option<Account>>, u64)>> {
+    let mut chunks = Vec::new();
+    let mut min_context_slot = None;
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64Zstd),
+            commitment: Some(rpc_client.commitment()),
+            min_context_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+        let response = rpc_client.get_multiple_accounts_with_config(chunk, config)
+            .context("Не удалось прочитать аккаунты снимка")?;
+        min_context_slot = min_context_slot.or(Some(response.context.slot));
+        chunks.push((response.value, response.context.slot));
+    }
+    Ok(chunks)
+}
+
+/// Ликвидность пула (base, quote) по балансам хранилищ токенов A и B
+///
+/// Хранилища читаются одним запросом; `(mint, vault)` — токен пула и его хранилище.
+fn vault_liquidity(
+    rpc_client: &RpcClient,
+    token_a: (&Pubkey, &Pubkey),
+    token_b: (&Pubkey, &Pubkey),
+    base_mint: &Pubkey,
+) -> Result<Reserves> {
+    let accounts = rpc_client
+        .get_multiple_accounts(&[*token_a.1, *token_b.1])
+        .context("Не удалось получить хранилища пула")?;
+    let [vault_a, vault_b]: [Option<Account>; 2] = accounts.try_into()
+        .map_err(|_| anyhow::anyhow!("RPC вернул неполный список хранилищ пула"))?;
+    let amount_a = raydium_amm::token_account_amount(&vault_a.context("Хранилище A пула не найдено")?.data)?;
+    let amount_b = raydium_amm::token_account_amount(&vault_b.context("Хранилище B пула не найдено")?.data)?;
+    if base_mint == token_a.0 {
+        Ok((amount_a, amount_b))
+    } else if base_mint == token_b.0 {
+        Ok((amount_b, amount_a))
+    } else {
+        anyhow::bail!("Токен {} не торгуется в пуле", base_mint)
+    }
 }
 
 /// Raydium AMM Program ID (mainnet)
@@ -101,118 +883,132 @@ struct RaydiumPool {
     pub token_b_vault: Pubkey,
     pub token_a_reserve: u64,
     pub token_b_reserve: u64,
+    pub token_a_decimals: u8,
+    pub token_b_decimals: u8,
+    pub swap_fee: Fee,
+    /// Слот, на котором прочитаны пул и vault
+    pub slot: u64,
+}
+
+impl RaydiumPool {
+    /// Резервы (вход, выход) для свопа из токена `from_mint`
+    fn reserves_for(&self, from_mint: &Pubkey) -> Result<(u64, u64)> {
+        if *from_mint == self.token_a_mint {
+            Ok((self.token_a_reserve, self.token_b_reserve))
+        } else if *from_mint == self.token_b_mint {
+            Ok((self.token_b_reserve, self.token_a_reserve))
+        } else {
+            anyhow::bail!("Токен {} не торгуется в пуле Raydium", from_mint)
+        }
+    }
 }
 
 /// Реализация для Raydium
 struct RaydiumDex {
     config: Config,
     rpc_client: RpcClient,
+    rpc_cache: Arc<RpcCache>,
+    tokens: Arc<TokenRegistry>,
+    registry: RaydiumPoolRegistry,
+    templates: TemplateCache,
+    last_reserves: LastReserves,
+    price_slots: PriceSlots,
 }
 
 impl RaydiumDex {
-    fn new(config: &Config) -> Result<Self> {
-        let rpc_client = RpcClient::new_with_commitment(
-            config.network.rpc_url.clone(),
-            CommitmentConfig::confirmed(),
-        );
+    fn new(config: &Config, rpc_cache: Arc<RpcCache>, tokens: Arc<TokenRegistry>) -> Result<Self> {
+        let rpc_client = create_rpc_client(&config.network)?;
+        let registry = RaydiumPoolRegistry::new(config, Self::program_id(config)?, rpc_cache.clone());
 
         Ok(Self {
             config: config.clone(),
             rpc_client,
+            rpc_cache,
+            tokens,
+            registry,
+            templates: TemplateCache::default(),
+            last_reserves: LastReserves::default(),
+            price_slots: PriceSlots::default(),
         })
     }
 
-    /// Получение адреса пула для торговой пары
-    /// В реальной реализации можно использовать API Raydium или on-chain данные
-    fn get_pool_address(&self, _token_a: &str, _token_b: &str) -> Result<Pubkey> {
-        // Для devnet используем известные адреса пулов
-        // В продакшене нужно получать через API или on-chain поиск
-        let is_devnet = self.config.network.rpc_url.contains("devnet");
-        
-        // Пример: SOL/USDC пул на devnet
-        // В реальной реализации нужно получать через Raydium API или поиск по mint адресам
-        if is_devnet {
-            // Заглушка: в реальности нужно получать через API
-            log::warn!("Получение адреса пула через API не реализовано, используем заглушку");
-            // Возвращаем случайный адрес для примера (в реальности нужно получать реальный)
-            Pubkey::from_str("11111111111111111111111111111111")
-                .context("Ошибка создания адреса пула")
+    /// Program ID Raydium AMM v4 для текущей сети
+    fn program_id(config: &Config) -> Result<Pubkey> {
+        let address = if config.network.rpc_url.contains("devnet") {
+            RAYDIUM_AMM_PROGRAM_ID_DEVNET
         } else {
-            // Mainnet: можно использовать известные адреса или API
-            log::warn!("Получение адреса пула через API не реализовано, используем заглушку");
-            Pubkey::from_str("11111111111111111111111111111111")
-                .context("Ошибка создания адреса пула")
-        }
+            RAYDIUM_AMM_PROGRAM_ID
+        };
+        Pubkey::from_str(address).context("Некорректный Program ID Raydium")
+    }
+
+    /// Пул торговой пары из реестра (Raydium API или поиск on-chain)
+    async fn get_pool(&self, token_a: &str, token_b: &str) -> Result<PoolInfo> {
+        let mint_a = self.tokens.mint(token_a)?;
+        let mint_b = self.tokens.mint(token_b)?;
+        self.registry.resolve(&mint_a, &mint_b).await
     }
 
     /// Чтение данных пула из аккаунта
+    ///
+    /// Адреса vault и OpenOrders берутся из ключей пула Raydium API или из
+    /// кешированного аккаунта пула, затем пул (статус и прибыль к выводу),
+    /// vault и OpenOrders читаются одним запросом.
     async fn get_pool_data(&self, pool_address: &Pubkey) -> Result<RaydiumPool> {
-        // Получение данных аккаунта пула
-        let _account_data = self.rpc_client
-            .get_account_data(pool_address)
-            .context("Не удалось получить данные аккаунта пула")?;
-
-        // Парсинг структуры пула Raydium
-        // Структура может отличаться в зависимости от версии программы
-        // Обычно: version (u8), status (u8), nonce (u8), orderNum (u8), 
-        //         depth (u8), coinDecimals (u8), pcDecimals (u8), state (u8),
-        //         resetFlag (u8), minSize (u64), volMaxCutRatio (u64),
-        //         amountWaveRatio (u64), coinLotSize (u64), pcLotSize (u64),
-        //         minPriceMultiplier (u64), maxPriceMultiplier (u64),
-        //         systemDecimalsValue (u64), ammTargetOrders (u32),
-        //         poolCoinTokenAccount (Pubkey), poolPcTokenAccount (Pubkey),
-        //         coinMintAddress (Pubkey), pcMintAddress (Pubkey),
-        //         lpMintAddress (Pubkey), ammOpenOrders (Pubkey),
-        //         serumMarket (Pubkey), serumProgramId (Pubkey),
-        //         ammTargetOrders (Pubkey), poolWithdrawQueue (Pubkey),
-        //         poolTempLpTokenAccount (Pubkey), ammOwner (Pubkey),
-        //         poolCoinTokenAccountPayer (Pubkey)
-        
-        // Упрощённая версия: читаем резервы из vault аккаунтов
-        // В реальной реализации нужно парсить полную структуру
-        
-        // Получаем mint адреса токенов (упрощённо, в реальности из структуры пула)
-        let token_a_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")?; // SOL
-        let token_b_mint = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")?; // USDC
-        
-        // Получаем vault адреса (упрощённо)
-        let token_a_vault = Pubkey::from_str("11111111111111111111111111111111")?;
-        let token_b_vault = Pubkey::from_str("11111111111111111111111111111111")?;
-        
-        // Читаем балансы vault аккаунтов
-        // В реальной реализации нужно парсить структуру пула и получать реальные vault адреса
-        // Для упрощения используем заглушки
-        let token_a_reserve = match self.rpc_client.get_account(&token_a_vault) {
-            Ok(_account) => {
-                // Парсим баланс из данных аккаунта
-                // В реальности нужно использовать правильный парсинг SPL Token аккаунта
-                0 // Заглушка
-            }
-            Err(_) => {
-                log::warn!("Не удалось получить данные vault аккаунта, используем заглушку");
-                0
+        // Статические поля (vault, OpenOrders) не меняются — берём из ключей API или кеша
+        let (coin_vault_address, pc_vault_address, open_orders_address) = match self.registry.pool_keys(pool_address) {
+            Some(keys) => (keys.vault_a, keys.vault_b, keys.open_orders),
+            None => {
+                let static_data = self.rpc_cache
+                    .static_account_data(&self.rpc_client, pool_address)
+                    .context("Не удалось получить данные аккаунта пула")?;
+                let layout = AmmInfo::parse(&static_data)
+                    .context("Не удалось разобрать аккаунт пула Raydium AMM v4")?;
+                (layout.coin_vault, layout.pc_vault, layout.open_orders)
             }
         };
-        
-        let token_b_reserve = match self.rpc_client.get_account(&token_b_vault) {
-            Ok(_account) => {
-                // Парсим баланс из данных аккаунта
-                0 // Заглушка
-            }
-            Err(_) => {
-                log::warn!("Не удалось получить данные vault аккаунта, используем заглушку");
-                0
+
+        let (accounts, slot) = get_accounts_with_slot(
+            &self.rpc_client,
+            &[*pool_address, coin_vault_address, pc_vault_address, open_orders_address],
+        )
+        .context("Не удалось получить аккаунты пула")?;
+        let [pool_account, coin_vault, pc_vault, open_orders]: [Option<Account>; 4] = accounts.try_into()
+            .map_err(|_| anyhow::anyhow!("RPC вернул неполный список аккаунтов пула"))?;
+
+        let amm = AmmInfo::parse(&pool_account.context("Аккаунт пула не найден")?.data)
+            .context("Не удалось разобрать аккаунт пула Raydium AMM v4")?;
+        if !amm.status.swap_enabled() {
+            anyhow::bail!("Свопы в пуле {} недоступны (статус {:?})", pool_address, amm.status);
+        }
+        if (amm.coin_vault, amm.pc_vault, amm.open_orders) != (coin_vault_address, pc_vault_address, open_orders_address) {
+            anyhow::bail!("Адреса vault пула {} не совпадают с аккаунтом пула", pool_address);
+        }
+        let coin_amount = raydium_amm::token_account_amount(&coin_vault.context("Vault coin пула не найден")?.data)?;
+        let pc_amount = raydium_amm::token_account_amount(&pc_vault.context("Vault pc пула не найден")?.data)?;
+        // Пулы без ордеров в OpenBook держат всё в vault
+        let in_orders = match open_orders.map(|account| raydium_amm::open_orders_totals(&account.data)) {
+            Some(Ok(totals)) => totals,
+            Some(Err(e)) => {
+                log::debug!("Raydium: OpenOrders пула {} не учтены: {:#}", pool_address, e);
+                (0, 0)
             }
+            None => (0, 0),
         };
+        let (token_a_reserve, token_b_reserve) = amm.reserves(coin_amount, pc_amount, in_orders)?;
 
         Ok(RaydiumPool {
             pool_address: *pool_address,
-            token_a_mint,
-            token_b_mint,
-            token_a_vault,
-            token_b_vault,
+            token_a_mint: amm.coin_mint,
+            token_b_mint: amm.pc_mint,
+            token_a_vault: amm.coin_vault,
+            token_b_vault: amm.pc_vault,
             token_a_reserve,
             token_b_reserve,
+            token_a_decimals: amm.coin_decimals,
+            token_b_decimals: amm.pc_decimals,
+            swap_fee: amm.swap_fee,
+            slot,
         })
     }
 
@@ -220,19 +1016,20 @@ impl RaydiumDex {
     /// Возвращает цену: сколько quote_token за 1 base_token
     fn calculate_price(&self, pool: &RaydiumPool, base_token: &str, _quote_token: &str) -> Result<Decimal> {
         // Определяем, какой токен является base, а какой quote
-        let (base_reserve, quote_reserve) = if base_token == "SOL" {
-            (pool.token_a_reserve, pool.token_b_reserve)
+        let base_mint = self.tokens.mint(base_token)?;
+        let (base_reserve, base_decimals, quote_reserve, quote_decimals) = if base_mint == pool.token_a_mint {
+            (pool.token_a_reserve, pool.token_a_decimals, pool.token_b_reserve, pool.token_b_decimals)
         } else {
-            (pool.token_b_reserve, pool.token_a_reserve)
+            (pool.token_b_reserve, pool.token_b_decimals, pool.token_a_reserve, pool.token_a_decimals)
         };
 
-        if base_reserve == 0 {
+        // Цена = quote_reserve / base_reserve, в целых токенах
+        let base = units::from_base_units(base_reserve, base_decimals)?;
+        let quote = units::from_base_units(quote_reserve, quote_decimals)?;
+        if base.is_zero() {
             anyhow::bail!("Резерв base токена равен нулю");
         }
-
-        // Цена = quote_reserve / base_reserve
-        let price = Decimal::from(quote_reserve) / Decimal::from(base_reserve);
-        Ok(price)
+        quote.checked_div(base).context("Переполнение при расчёте цены")
     }
 
     /// Расчёт выходного количества токенов при свопе по формуле x*y=k
@@ -249,15 +1046,8 @@ impl RaydiumDex {
             (pool.token_b_reserve, pool.token_a_reserve)
         };
 
-        if reserve_in == 0 || reserve_out == 0 {
-            anyhow::bail!("Резерв равен нулю");
-        }
-
-        // Формула: amount_out = (amount_in * reserve_out) / (reserve_in + amount_in)
-        // Упрощённая версия без учёта комиссий
-        let amount_out = (amount_in as u128 * reserve_out as u128) / (reserve_in as u128 + amount_in as u128);
-        
-        Ok(amount_out as u64)
+        // Формула: amount_out = (amount_in * reserve_out) / (reserve_in + amount_in), комиссия пула со входа
+        math::constant_product_output(amount_in, reserve_in, reserve_out, pool.swap_fee)
     }
 
     /// Построение инструкции swap для Raydium
@@ -269,11 +1059,7 @@ impl RaydiumDex {
         min_amount_out: u64,
         is_token_a_to_b: bool,
     ) -> Result<Instruction> {
-        let program_id = if self.config.network.rpc_url.contains("devnet") {
-            Pubkey::from_str(RAYDIUM_AMM_PROGRAM_ID_DEVNET)?
-        } else {
-            Pubkey::from_str(RAYDIUM_AMM_PROGRAM_ID)?
-        };
+        let program_id = Self::program_id(&self.config)?;
 
         // Построение инструкции swap
         // В реальной реализации нужно использовать правильные аккаунты и данные
@@ -303,36 +1089,46 @@ impl RaydiumDex {
         })
     }
 
+    /// Инструкция свопа по актуальным данным пула
+    async fn swap_instruction(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+        min_amount_out: u64,
+        payer: &Pubkey,
+    ) -> Result<Instruction> {
+        // Получение адреса пула
+        let pool_address = self.get_pool(from_token, to_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+
+        // Чтение актуальных данных пула
+        let pool = self.get_pool_data(&pool_address).await
+            .context("Не удалось получить данные пула")?;
+
+        // Определение направления свопа
+        let is_token_a_to_b = self.tokens.mint(from_token)? == pool.token_a_mint;
+
+        // Построение инструкции swap
+        self.build_swap_instruction(
+            &pool,
+            payer,
+            amount_in,
+            min_amount_out,
+            is_token_a_to_b,
+        ).context("Не удалось построить инструкцию swap")
+    }
+
     /// Отправка транзакции с retry-логикой
     async fn send_transaction_with_retry(
         &self,
         transaction: &Transaction,
+        wallet: &Wallet,
         max_retries: u32,
+        expected: Option<&ExpectedOutput>,
     ) -> Result<String> {
-        let mut last_error = None;
-        
-        for attempt in 0..max_retries {
-            match self.rpc_client.send_transaction(transaction) {
-                Ok(signature) => {
-                    log::info!("Транзакция отправлена успешно: {}", signature);
-                    return Ok(signature.to_string());
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries - 1 {
-                        let delay = std::time::Duration::from_millis(100 * (attempt + 1) as u64);
-                        log::warn!("Попытка {} не удалась, повтор через {:?}: {}", attempt + 1, delay, last_error.as_ref().unwrap());
-                        tokio::time::sleep(delay).await;
-                    }
-                }
-            }
-        }
-
-        Err(anyhow::anyhow!(
-            "Не удалось отправить транзакцию после {} попыток: {:?}",
-            max_retries,
-            last_error
-        ))
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
     }
 }
 
@@ -342,12 +1138,18 @@ impl DexInterface for RaydiumDex {
         "raydium"
     }
 
+    async fn refresh_pool(&self, base_token: &str, quote_token: &str) -> Result<bool> {
+        self.get_pool(base_token, quote_token).await?;
+        Ok(true)
+    }
+
     async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
         log::debug!("Raydium: получение цены {}/{}", base_token, quote_token);
         
         // Получение адреса пула
-        let pool_address = self.get_pool_address(base_token, quote_token)
-            .context("Не удалось получить адрес пула")?;
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
         
         // Чтение данных пула
         let pool = self.get_pool_data(&pool_address).await
@@ -356,6 +1158,8 @@ impl DexInterface for RaydiumDex {
         // Расчёт цены
         let price = self.calculate_price(&pool, base_token, quote_token)
             .context("Не удалось рассчитать цену")?;
+        self.last_reserves.record(base_token, quote_token, pool.reserves_for(&self.tokens.mint(base_token)?)?);
+        self.price_slots.record(base_token, quote_token, pool.slot);
         
         log::debug!("Raydium: цена {}/{} = {}", base_token, quote_token, price);
         Ok(price)
@@ -378,33 +1182,401 @@ impl DexInterface for RaydiumDex {
             return Ok("simulated_signature_raydium".to_string());
         }
 
-        // Получение адреса пула
-        let pool_address = self.get_pool_address(from_token, to_token)
-            .context("Не удалось получить адрес пула")?;
+        // Конвертация amount в lamports/token units
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+
+        // Заготовка транзакции: подставляются только суммы и blockhash (ATA уже есть)
+        if let Some(template) = self.templates.get(from_token, to_token).filter(|_| accounts.is_empty()) {
+            if let Some(signature) = send_from_template(self.name(), &self.rpc_client, &template, amount_in, min_amount_out, wallet, expected.as_ref()).await
+                .context("Не удалось отправить транзакцию из шаблона")?
+            {
+                log::info!("Raydium: своп выполнен по шаблону, signature: {}", signature);
+                return Ok(signature);
+            }
+        }
+
+        let build_started = Instant::now();
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
         
-        // Чтение актуальных данных пула
-        let pool = self.get_pool_data(&pool_address).await
-            .context("Не удалось получить данные пула")?;
+        // Получение последнего blockhash
+        let recent_blockhash = self.rpc_client
+            .get_latest_blockhash()
+            .context("Не удалось получить blockhash")?;
         
-        // Конвертация amount в lamports/token units
-        // Упрощённо: предполагаем, что amount уже в правильных единицах
-        let amount_in = amount.to_string().parse::<u64>()
-            .context("Не удалось конвертировать amount в u64")?;
+        // Создание транзакции
+        let instructions = accounts.around([swap_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
+        transaction.sign(&[wallet.keypair()], recent_blockhash);
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
         
-        let min_amount_out = min_output.to_string().parse::<u64>()
-            .context("Не удалось конвертировать min_output в u64")?;
+        // Отправка транзакции с retry
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
+            .context("Не удалось отправить транзакцию")?;
         
-        // Определение направления свопа
-        let is_token_a_to_b = from_token == "SOL"; // Упрощённо
+        log::info!("Raydium: своп выполнен, signature: {}", signature);
+        Ok(signature)
+    }
+
+    fn pool_reserves(&self, base_token: &str, quote_token: &str) -> Option<Reserves> {
+        self.last_reserves.get(base_token, quote_token)
+    }
+
+    fn price_slot(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.price_slots.get(base_token, quote_token)
+    }
+
+    async fn pool_fee_percent(&self, base_token: &str, quote_token: &str) -> Option<Decimal> {
+        match self.get_pool(base_token, quote_token).await {
+            Ok(pool) => pool.fee_percent,
+            Err(e) => {
+                log::debug!("Raydium: комиссия пула {}/{} неизвестна: {:#}", base_token, quote_token, e);
+                None
+            }
+        }
+    }
+
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address).await
+            .context("Не удалось получить данные пула")?;
+        pool.reserves_for(&self.tokens.mint(base_token)?)
+    }
+
+    async fn prepare_swap_templates(&self, config: &TemplatesConfig, payer: &Pubkey) -> Result<usize> {
+        let lookup_tables = fetch_lookup_tables(&self.rpc_client, config)?;
+        for (from_token, to_token) in template_directions(config, &self.config, self.name()) {
+            let pool_address = self.get_pool(from_token, to_token).await
+                .context("Не удалось получить адрес пула")?
+                .address;
+            let pool = self.get_pool_data(&pool_address).await
+                .context("Не удалось получить данные пула")?;
+            // Суммы перезаписываются при исполнении
+            let instruction = self.build_swap_instruction(&pool, payer, 0, 0, self.tokens.mint(from_token)? == pool.token_a_mint)
+                .context("Не удалось построить инструкцию swap")?;
+            let template = TxTemplate::build(payer, config, &[instruction], &swap_amount_slots(), &lookup_tables)?;
+            self.templates.insert(from_token, to_token, template);
+        }
+        Ok(self.templates.len())
+    }
+
+    fn supports_atomic_swap(&self) -> bool {
+        true
+    }
+
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+        Ok(accounts.around([swap_instruction]))
+    }
+}
+
+/// Orca Whirlpools Program ID (mainnet)
+const ORCA_WHIRLPOOLS_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+/// Orca Whirlpools Program ID (devnet)
+const ORCA_WHIRLPOOLS_PROGRAM_ID_DEVNET: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+/// Структура данных пула Orca Whirlpools
+#[derive(Debug, Clone)]
+struct OrcaWhirlpool {
+    pub whirlpool_address: Pubkey,
+    pub state: Whirlpool,
+    pub token_a_decimals: u8,
+    pub token_b_decimals: u8,
+    /// Слот, на котором прочитан аккаунт Whirlpool
+    pub slot: u64,
+}
+
+/// Реализация для Orca Whirlpools
+struct OrcaDex {
+    config: Config,
+    rpc_client: RpcClient,
+    tokens: Arc<TokenRegistry>,
+    registry: WhirlpoolRegistry,
+    templates: TemplateCache,
+    price_slots: PriceSlots,
+}
+
+impl OrcaDex {
+    fn new(config: &Config, tokens: Arc<TokenRegistry>) -> Result<Self> {
+        let rpc_client = create_rpc_client(&config.network)?;
+        let registry = WhirlpoolRegistry::new(config, Self::program_id(config)?);
+
+        Ok(Self {
+            config: config.clone(),
+            rpc_client,
+            tokens,
+            registry,
+            templates: TemplateCache::default(),
+            price_slots: PriceSlots::default(),
+        })
+    }
+
+    /// Program ID Orca Whirlpools для текущей сети
+    fn program_id(config: &Config) -> Result<Pubkey> {
+        let address = if config.network.rpc_url.contains("devnet") {
+            ORCA_WHIRLPOOLS_PROGRAM_ID_DEVNET
+        } else {
+            ORCA_WHIRLPOOLS_PROGRAM_ID
+        };
+        Pubkey::from_str(address).context("Некорректный Program ID Orca Whirlpools")
+    }
+
+    /// Whirlpool торговой пары из реестра (Orca API или поиск on-chain)
+    async fn get_pool(&self, token_a: &str, token_b: &str) -> Result<PoolInfo> {
+        let mint_a = self.tokens.mint(token_a)?;
+        let mint_b = self.tokens.mint(token_b)?;
+        self.registry.resolve(&mint_a, &mint_b).await
+    }
+
+    /// Чтение данных Whirlpool из аккаунта
+    ///
+    /// sqrt_price, ликвидность и текущий тик меняются с каждым свопом,
+    /// поэтому аккаунт читается напрямую; decimals токенов — из кеша.
+    async fn get_whirlpool_data(&self, whirlpool_address: &Pubkey) -> Result<OrcaWhirlpool> {
+        let response = self.rpc_client
+            .get_account_with_commitment(whirlpool_address, self.rpc_client.commitment())
+            .context("Не удалось получить данные аккаунта Whirlpool")?;
+        let slot = response.context.slot;
+        let account = response.value.with_context(|| format!("Аккаунт Whirlpool {} не найден", whirlpool_address))?;
+        let state = Whirlpool::parse(&account.data)
+            .context("Не удалось разобрать аккаунт Whirlpool")?;
+        let token_a_decimals = self.tokens.decimals(&self.rpc_client, &state.token_mint_a)
+            .context("Не удалось получить decimals токена A")?;
+        let token_b_decimals = self.tokens.decimals(&self.rpc_client, &state.token_mint_b)
+            .context("Не удалось получить decimals токена B")?;
+
+        Ok(OrcaWhirlpool {
+            whirlpool_address: *whirlpool_address,
+            state,
+            token_a_decimals,
+            token_b_decimals,
+            slot,
+        })
+    }
+
+    /// Загрузка TickArray в направлении свопа
+    ///
+    /// Загрузка останавливается на первом неинициализированном массиве:
+    /// за ним ликвидность неизвестна, и котировка не должна её угадывать.
+    #[allow(dead_code)]
+    async fn get_tick_arrays(&self, pool: &OrcaWhirlpool, is_token_a_to_b: bool) -> Result<Vec<TickArray>> {
+        let program_id = Self::program_id(&self.config)?;
+        let addresses: Vec<Pubkey> = pool.state.tick_array_starts(is_token_a_to_b).into_iter()
+            .map(|start| whirlpool::tick_array_address(&program_id, &pool.whirlpool_address, start))
+            .collect();
+        let accounts = self.rpc_client
+            .get_multiple_accounts(&addresses)
+            .context("Не удалось получить TickArray пула")?;
+
+        let mut tick_arrays = Vec::new();
+        for account in accounts {
+            let Some(account) = account else { break };
+            tick_arrays.push(TickArray::parse(&account.data, pool.state.tick_spacing)
+                .context("Не удалось разобрать TickArray")?);
+        }
+        if tick_arrays.is_empty() {
+            anyhow::bail!("TickArray с текущим тиком пула {} не инициализирован", pool.whirlpool_address);
+        }
+        Ok(tick_arrays)
+    }
+
+    /// Расчёт цены из sqrt_price Whirlpool
+    /// Возвращает цену: сколько quote_token за 1 base_token
+    fn calculate_price(&self, pool: &OrcaWhirlpool, base_token: &str, _quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        // sqrt_price задаёт цену B за A
+        let price = math::price_from_sqrt_price(pool.state.sqrt_price, pool.token_a_decimals, pool.token_b_decimals)?;
+        if base_mint == pool.state.token_mint_a {
+            return Ok(price);
+        }
+        if price.is_zero() {
+            anyhow::bail!("Нулевая цена пула {}", pool.whirlpool_address);
+        }
+        Decimal::ONE.checked_div(price).context("Переполнение при расчёте цены")
+    }
+
+    /// Расчёт выходного количества токенов при свопе
+    /// Своп проходит по тикам концентрированной ликвидности из загруженных TickArray
+    #[allow(dead_code)]
+    async fn calculate_swap_output(
+        &self,
+        pool: &OrcaWhirlpool,
+        amount_in: u64,
+        is_token_a_to_b: bool,
+    ) -> Result<u64> {
+        let tick_arrays = self.get_tick_arrays(pool, is_token_a_to_b).await?;
+        Ok(whirlpool::quote_exact_in(&pool.state, &tick_arrays, amount_in, is_token_a_to_b)?.amount_out)
+    }
+
+    /// Построение инструкции swap для Orca Whirlpools
+    fn build_swap_instruction(
+        &self,
+        pool: &OrcaWhirlpool,
+        user_wallet: &Pubkey,
+        amount_in: u64,
+        min_amount_out: u64,
+        _is_token_a_to_b: bool,
+    ) -> Result<Instruction> {
+        let program_id = Self::program_id(&self.config)?;
+
+        // Построение инструкции swap для Whirlpools
+        // В реальной реализации нужно использовать правильные аккаунты и данные
+        // Это упрощённая версия
         
+        let accounts = vec![
+            AccountMeta::new(*user_wallet, true), // user wallet (signer)
+            AccountMeta::new(pool.whirlpool_address, false), // whirlpool
+            AccountMeta::new(pool.state.token_vault_a, false), // token_a vault
+            AccountMeta::new(pool.state.token_vault_b, false), // token_b vault
+            AccountMeta::new(pool.state.token_mint_a, false), // token_a mint
+            AccountMeta::new(pool.state.token_mint_b, false), // token_b mint
+            AccountMeta::new_readonly(system_program::id(), false), // system program
+        ];
+
+        // Данные инструкции: discriminator (8 bytes) + amount_in (8 bytes) + min_amount_out (8 bytes)
+        let mut data = vec![0u8; 24];
+        // В реальной реализации нужно использовать правильный discriminator для swap Whirlpools
+        // Discriminator для swap обычно: 0x02 (или другой в зависимости от версии)
+        data[0..8].copy_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]); // Пример discriminator
+        data[8..16].copy_from_slice(&amount_in.to_le_bytes());
+        data[16..24].copy_from_slice(&min_amount_out.to_le_bytes());
+
+        Ok(Instruction {
+            program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Инструкция свопа по актуальным данным пула
+    async fn swap_instruction(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+        min_amount_out: u64,
+        payer: &Pubkey,
+    ) -> Result<Instruction> {
+        // Получение адреса Whirlpool
+        let whirlpool_address = self.get_pool(from_token, to_token).await
+            .context("Не удалось получить адрес Whirlpool")?
+            .address;
+
+        // Чтение актуальных данных Whirlpool
+        let pool = self.get_whirlpool_data(&whirlpool_address).await
+            .context("Не удалось получить данные Whirlpool")?;
+
+        // Определение направления свопа
+        let is_token_a_to_b = self.tokens.mint(from_token)? == pool.state.token_mint_a;
+
         // Построение инструкции swap
-        let swap_instruction = self.build_swap_instruction(
+        self.build_swap_instruction(
             &pool,
-            wallet.pubkey(),
+            payer,
             amount_in,
             min_amount_out,
             is_token_a_to_b,
-        ).context("Не удалось построить инструкцию swap")?;
+        ).context("Не удалось построить инструкцию swap")
+    }
+
+    /// Отправка транзакции с retry-логикой
+    async fn send_transaction_with_retry(
+        &self,
+        transaction: &Transaction,
+        wallet: &Wallet,
+        max_retries: u32,
+        expected: Option<&ExpectedOutput>,
+    ) -> Result<String> {
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl DexInterface for OrcaDex {
+    fn name(&self) -> &str {
+        "orca"
+    }
+
+    async fn refresh_pool(&self, base_token: &str, quote_token: &str) -> Result<bool> {
+        self.get_pool(base_token, quote_token).await?;
+        Ok(true)
+    }
+
+    fn price_slot(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.price_slots.get(base_token, quote_token)
+    }
+
+    async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        log::debug!("Orca: получение цены {}/{}", base_token, quote_token);
+        
+        // Получение адреса Whirlpool
+        let whirlpool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес Whirlpool")?
+            .address;
+        
+        // Чтение данных Whirlpool
+        let pool = self.get_whirlpool_data(&whirlpool_address).await
+            .context("Не удалось получить данные Whirlpool")?;
+        
+        // Расчёт цены
+        let price = self.calculate_price(&pool, base_token, quote_token)
+            .context("Не удалось рассчитать цену")?;
+        self.price_slots.record(base_token, quote_token, pool.slot);
+        
+        log::debug!("Orca: цена {}/{} = {}", base_token, quote_token, price);
+        Ok(price)
+    }
+
+    async fn execute_swap(
+        &self,
+        simulation_mode: bool,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<String> {
+        log::info!("Orca: выполнение свопа {} -> {} ({}), min_output: {}", 
+            from_token, to_token, amount, min_output);
+        
+        if simulation_mode {
+            log::info!("Orca: симуляция свопа (реальная транзакция не отправляется)");
+            return Ok("simulated_signature_orca".to_string());
+        }
+
+        // Конвертация amount в lamports/token units
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+
+        // Заготовка транзакции: подставляются только суммы и blockhash (ATA уже есть)
+        if let Some(template) = self.templates.get(from_token, to_token).filter(|_| accounts.is_empty()) {
+            if let Some(signature) = send_from_template(self.name(), &self.rpc_client, &template, amount_in, min_amount_out, wallet, expected.as_ref()).await
+                .context("Не удалось отправить транзакцию из шаблона")?
+            {
+                log::info!("Orca: своп выполнен по шаблону, signature: {}", signature);
+                return Ok(signature);
+            }
+        }
+
+        let build_started = Instant::now();
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
         
         // Получение последнего blockhash
         let recent_blockhash = self.rpc_client
@@ -412,318 +1584,1091 @@ impl DexInterface for RaydiumDex {
             .context("Не удалось получить blockhash")?;
         
         // Создание транзакции
-        let mut transaction = Transaction::new_with_payer(
-            &[swap_instruction],
-            Some(wallet.pubkey()),
-        );
+        let instructions = accounts.around([swap_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
+        transaction.sign(&[wallet.keypair()], recent_blockhash);
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
+        
+        // Отправка транзакции с retry
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
+            .context("Не удалось отправить транзакцию")?;
+        
+        log::info!("Orca: своп выполнен, signature: {}", signature);
+        Ok(signature)
+    }
+
+    async fn pool_fee_percent(&self, base_token: &str, quote_token: &str) -> Option<Decimal> {
+        match self.get_pool(base_token, quote_token).await {
+            Ok(pool) => pool.fee_percent,
+            Err(e) => {
+                log::debug!("Orca: комиссия пула {}/{} неизвестна: {:#}", base_token, quote_token, e);
+                None
+            }
+        }
+    }
+
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        let whirlpool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес Whirlpool")?
+            .address;
+        let pool = self.get_whirlpool_data(&whirlpool_address).await
+            .context("Не удалось получить данные Whirlpool")?;
+        let state = &pool.state;
+        vault_liquidity(
+            &self.rpc_client,
+            (&state.token_mint_a, &state.token_vault_a),
+            (&state.token_mint_b, &state.token_vault_b),
+            &self.tokens.mint(base_token)?,
+        )
+    }
+
+    async fn prepare_swap_templates(&self, config: &TemplatesConfig, payer: &Pubkey) -> Result<usize> {
+        let lookup_tables = fetch_lookup_tables(&self.rpc_client, config)?;
+        for (from_token, to_token) in template_directions(config, &self.config, self.name()) {
+            let whirlpool_address = self.get_pool(from_token, to_token).await
+                .context("Не удалось получить адрес пула")?
+                .address;
+            let pool = self.get_whirlpool_data(&whirlpool_address).await
+                .context("Не удалось получить данные пула")?;
+            // Суммы перезаписываются при исполнении
+            let instruction = self.build_swap_instruction(&pool, payer, 0, 0, self.tokens.mint(from_token)? == pool.state.token_mint_a)
+                .context("Не удалось построить инструкцию swap")?;
+            let template = TxTemplate::build(payer, config, &[instruction], &swap_amount_slots(), &lookup_tables)?;
+            self.templates.insert(from_token, to_token, template);
+        }
+        Ok(self.templates.len())
+    }
+
+    fn supports_atomic_swap(&self) -> bool {
+        true
+    }
+
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+        Ok(accounts.around([swap_instruction]))
+    }
+}
+
+/// Структура данных рынка OpenBook v2
+#[derive(Debug, Clone)]
+struct OpenBookV2Market {
+    pub market_address: Pubkey,
+    pub market: openbook_v2::Market,
+    /// Ордера на покупку, от лучшей цены
+    pub bids: Vec<openbook_v2::Order>,
+    /// Ордера на продажу, от лучшей цены
+    pub asks: Vec<openbook_v2::Order>,
+    /// Слот, на котором прочитана книга ордеров
+    pub slot: u64,
+}
+
+/// Реализация для OpenBook v2 (свопы через place_take_order)
+struct OpenBookV2Dex {
+    rpc_client: RpcClient,
+    rpc_cache: Arc<RpcCache>,
+    tokens: Arc<TokenRegistry>,
+    registry: OpenBookMarketRegistry,
+    /// Время кластера для проверки времени из аккаунтов
+    clock_skew: ClockSkew,
+    price_slots: PriceSlots,
+}
+
+impl OpenBookV2Dex {
+    fn new(config: &Config, rpc_cache: Arc<RpcCache>, tokens: Arc<TokenRegistry>, clock_skew: ClockSkew) -> Result<Self> {
+        let rpc_client = create_rpc_client(&config.network)?;
+        let registry = OpenBookMarketRegistry::new(config, Self::program_id()?, rpc_cache.clone());
+
+        Ok(Self {
+            rpc_client,
+            rpc_cache,
+            tokens,
+            registry,
+            clock_skew,
+            price_slots: PriceSlots::default(),
+        })
+    }
+
+    /// Program ID OpenBook v2 (одинаковый в mainnet и devnet)
+    fn program_id() -> Result<Pubkey> {
+        Pubkey::from_str(openbook_v2::OPENBOOK_V2_PROGRAM_ID).context("Некорректный Program ID OpenBook v2")
+    }
+
+    /// Рынок торговой пары из реестра (поиск on-chain)
+    async fn get_market(&self, token_a: &str, token_b: &str) -> Result<PoolInfo> {
+        let mint_a = self.tokens.mint(token_a)?;
+        let mint_b = self.tokens.mint(token_b)?;
+        self.registry.resolve(&mint_a, &mint_b).await
+    }
+
+    /// Чтение рынка и книги ордеров
+    ///
+    /// Адреса и параметры рынка не меняются — аккаунт рынка берётся из кеша,
+    /// bids и asks читаются одним запросом.
+    async fn get_market_data(&self, market_address: &Pubkey) -> Result<OpenBookV2Market> {
+        let data = self.rpc_cache
+            .static_account_data(&self.rpc_client, market_address)
+            .context("Не удалось получить данные аккаунта рынка")?;
+        let market = openbook_v2::Market::parse(&data)
+            .context("Не удалось разобрать аккаунт рынка OpenBook v2")?;
+
+        let (accounts, slot) = get_accounts_with_slot(&self.rpc_client, &[market.bids, market.asks])
+            .context("Не удалось получить книгу ордеров")?;
+        let now = u64::try_from(self.clock_skew.cluster_now().timestamp()).unwrap_or_default();
+        let book_side = |account: Option<&Account>, side: openbook_v2::Side| -> Result<Vec<openbook_v2::Order>> {
+            let account = account.with_context(|| format!("Аккаунт {:?} книги ордеров не найден", side))?;
+            openbook_v2::parse_book_side(&account.data, side, now)
+        };
+        let bids = book_side(accounts.first().and_then(Option::as_ref), openbook_v2::Side::Bid)?;
+        let asks = book_side(accounts.get(1).and_then(Option::as_ref), openbook_v2::Side::Ask)?;
+
+        Ok(OpenBookV2Market {
+            market_address: *market_address,
+            market,
+            bids,
+            asks,
+            slot,
+        })
+    }
+
+    /// Mid price из лучших bid и ask
+    /// Возвращает цену: сколько quote_token за 1 base_token
+    fn calculate_price(&self, book: &OpenBookV2Market, base_token: &str, _quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        let price_lots = match (book.bids.first(), book.asks.first()) {
+            (Some(bid), Some(ask)) => Decimal::from(bid.price_lots + ask.price_lots) / Decimal::TWO,
+            (Some(best), None) | (None, Some(best)) => Decimal::from(best.price_lots),
+            (None, None) => anyhow::bail!("Книга ордеров рынка {} пуста", book.market_address),
+        };
+        // Цена в лотах задаёт quote рынка за base рынка
+        let price = book.market.price_from_lots(1)?
+            .checked_mul(price_lots)
+            .context("Переполнение при расчёте цены")?;
+        if base_mint == book.market.base_mint {
+            return Ok(price);
+        }
+        if price.is_zero() {
+            anyhow::bail!("Нулевая цена рынка {}", book.market_address);
+        }
+        Decimal::ONE.checked_div(price).context("Переполнение при расчёте цены")
+    }
+
+    /// Построение инструкции place_take_order для свопа amount_in -> не меньше min_amount_out
+    fn build_take_order_instruction(
+        &self,
+        book: &OpenBookV2Market,
+        user_wallet: &Pubkey,
+        from_mint: &Pubkey,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<Instruction> {
+        let market = &book.market;
+        let side = if *from_mint == market.base_mint {
+            openbook_v2::Side::Ask
+        } else if *from_mint == market.quote_mint {
+            openbook_v2::Side::Bid
+        } else {
+            anyhow::bail!("Токен {} не торгуется на рынке {}", from_mint, book.market_address);
+        };
+        let quote = openbook_v2::quote_take(market, if side == openbook_v2::Side::Bid { &book.asks } else { &book.bids }, side, amount_in)?;
+        if quote.amount_out < min_amount_out {
+            anyhow::bail!(
+                "Книга ордеров даёт {} вместо минимума {} (исполнится {} из {})",
+                quote.amount_out, min_amount_out, quote.amount_in_used, amount_in
+            );
+        }
+
+        let args = openbook_v2::TakeOrderArgs::exact_in(market, side, amount_in, min_amount_out);
+        Ok(openbook_v2::place_take_order_instruction(
+            &Self::program_id()?,
+            &book.market_address,
+            market,
+            user_wallet,
+            &get_associated_token_address(user_wallet, &market.base_mint),
+            &get_associated_token_address(user_wallet, &market.quote_mint),
+            &args,
+        ))
+    }
+
+    /// Инструкция свопа (take-ордер) по актуальной книге ордеров
+    async fn swap_instruction(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+        min_amount_out: u64,
+        payer: &Pubkey,
+    ) -> Result<Instruction> {
+        let market_address = self.get_market(from_token, to_token).await
+            .context("Не удалось получить адрес рынка")?
+            .address;
+        // Книга ордеров читается заново: котировка и предельная цена по актуальным ордерам
+        let book = self.get_market_data(&market_address).await
+            .context("Не удалось получить данные рынка")?;
+
+        let from_mint = self.tokens.mint(from_token)?;
+        self.build_take_order_instruction(
+            &book,
+            payer,
+            &from_mint,
+            amount_in,
+            min_amount_out,
+        ).context("Не удалось построить инструкцию place_take_order")
+    }
+
+    /// Отправка транзакции с retry-логикой
+    async fn send_transaction_with_retry(
+        &self,
+        transaction: &Transaction,
+        wallet: &Wallet,
+        max_retries: u32,
+        expected: Option<&ExpectedOutput>,
+    ) -> Result<String> {
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl DexInterface for OpenBookV2Dex {
+    fn name(&self) -> &str {
+        "openbook_v2"
+    }
+
+    async fn refresh_pool(&self, base_token: &str, quote_token: &str) -> Result<bool> {
+        self.get_market(base_token, quote_token).await?;
+        Ok(true)
+    }
+
+    fn price_slot(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.price_slots.get(base_token, quote_token)
+    }
+
+    async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        log::debug!("OpenBook v2: получение цены {}/{}", base_token, quote_token);
+
+        let market_address = self.get_market(base_token, quote_token).await
+            .context("Не удалось получить адрес рынка")?
+            .address;
+        let book = self.get_market_data(&market_address).await
+            .context("Не удалось получить данные рынка")?;
+        let price = self.calculate_price(&book, base_token, quote_token)
+            .context("Не удалось рассчитать цену")?;
+        self.price_slots.record(base_token, quote_token, book.slot);
+
+        log::debug!("OpenBook v2: цена {}/{} = {}", base_token, quote_token, price);
+        Ok(price)
+    }
+
+    async fn execute_swap(
+        &self,
+        simulation_mode: bool,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<String> {
+        log::info!("OpenBook v2: выполнение свопа {} -> {} ({}), min_output: {}",
+            from_token, to_token, amount, min_output);
+
+        if simulation_mode {
+            log::info!("OpenBook v2: симуляция свопа (реальная транзакция не отправляется)");
+            return Ok("simulated_signature_openbook_v2".to_string());
+        }
+
+        let build_started = Instant::now();
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let order_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+
+        let recent_blockhash = self.rpc_client
+            .get_latest_blockhash()
+            .context("Не удалось получить blockhash")?;
+
+        let instructions = accounts.around([order_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
+        transaction.sign(&[wallet.keypair()], recent_blockhash);
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
+
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
+            .context("Не удалось отправить транзакцию")?;
+
+        log::info!("OpenBook v2: своп выполнен, signature: {}", signature);
+        Ok(signature)
+    }
+
+    async fn pool_fee_percent(&self, base_token: &str, quote_token: &str) -> Option<Decimal> {
+        match self.get_market(base_token, quote_token).await {
+            Ok(market) => market.fee_percent,
+            Err(e) => {
+                log::debug!("OpenBook v2: комиссия рынка {}/{} неизвестна: {:#}", base_token, quote_token, e);
+                None
+            }
+        }
+    }
+
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        let market_address = self.get_market(base_token, quote_token).await
+            .context("Не удалось получить адрес рынка")?
+            .address;
+        let book = self.get_market_data(&market_address).await
+            .context("Не удалось получить данные рынка")?;
+        let asks = openbook_v2::book_depth(&book.market, &book.asks, openbook_v2::Side::Ask);
+        let bids = openbook_v2::book_depth(&book.market, &book.bids, openbook_v2::Side::Bid);
+        // Если base пары — quote рынка, покупка base идёт по bids, продажа — по asks
+        if self.tokens.mint(base_token)? == book.market.base_mint {
+            Ok((asks, bids))
+        } else {
+            Ok((bids, asks))
+        }
+    }
+
+    fn supports_atomic_swap(&self) -> bool {
+        true
+    }
+
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+        Ok(accounts.around([swap_instruction]))
+    }
+}
+
+/// Реализация для Lifinity v2 (AMM с ценой от оракула)
+struct LifinityDex {
+    rpc_client: RpcClient,
+    rpc_cache: Arc<RpcCache>,
+    tokens: Arc<TokenRegistry>,
+    registry: LifinityPoolRegistry,
+    /// Время кластера для проверки времени из аккаунтов
+    clock_skew: ClockSkew,
+}
+
+impl LifinityDex {
+    fn new(config: &Config, rpc_cache: Arc<RpcCache>, tokens: Arc<TokenRegistry>, clock_skew: ClockSkew) -> Result<Self> {
+        let rpc_client = create_rpc_client(&config.network)?;
+        let registry = LifinityPoolRegistry::new(config, Self::program_id()?, rpc_cache.clone());
+
+        Ok(Self {
+            rpc_client,
+            rpc_cache,
+            tokens,
+            registry,
+            clock_skew,
+        })
+    }
+
+    /// Program ID Lifinity v2
+    fn program_id() -> Result<Pubkey> {
+        Pubkey::from_str(lifinity::LIFINITY_V2_PROGRAM_ID).context("Некорректный Program ID Lifinity")
+    }
+
+    /// Пул торговой пары из реестра (поиск on-chain)
+    async fn get_pool(&self, token_a: &str, token_b: &str) -> Result<PoolInfo> {
+        let mint_a = self.tokens.mint(token_a)?;
+        let mint_b = self.tokens.mint(token_b)?;
+        self.registry.resolve(&mint_a, &mint_b).await
+    }
+
+    /// Чтение аккаунта пула
+    ///
+    /// Используемые поля (хранилища, оракулы, комиссия) не меняются — аккаунт берётся из кеша.
+    fn get_pool_data(&self, pool_address: &Pubkey) -> Result<lifinity::Amm> {
+        let data = self.rpc_cache
+            .static_account_data(&self.rpc_client, pool_address)
+            .context("Не удалось получить данные аккаунта пула")?;
+        let amm = lifinity::Amm::parse(&data).context("Не удалось разобрать аккаунт пула Lifinity")?;
+        if amm.freeze_trade {
+            anyhow::bail!("Свопы в пуле Lifinity {} остановлены", pool_address);
+        }
+        Ok(amm)
+    }
+
+    /// Цена токена A пула в токенах B по оракулам (одним запросом)
+    ///
+    /// Пул котирует вокруг цены оракула: это цена, по которой он готов торговать
+    /// малый объём; минимальный выход крупного свопа проверяет программа.
+    fn oracle_price(&self, amm: &lifinity::Amm) -> Result<Decimal> {
+        let mut oracles = vec![amm.oracle_main_account];
+        if amm.has_quote_oracle() {
+            oracles.push(amm.oracle_pc_account);
+        }
+        let accounts = self.rpc_client
+            .get_multiple_accounts(&oracles)
+            .context("Не удалось получить аккаунты оракулов")?;
+        let now = self.clock_skew.cluster_now().timestamp();
+        let prices = oracles.iter()
+            .zip(accounts)
+            .map(|(address, account)| {
+                let account = account.with_context(|| format!("Аккаунт оракула {} не найден", address))?;
+                let price = lifinity::parse_pyth_price(&account.data)
+                    .with_context(|| format!("Не удалось разобрать оракул {}", address))?;
+                price.ensure_fresh(now).with_context(|| format!("Оракул {}", address))?;
+                Ok(price)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        lifinity::pool_price(&prices[0], prices.get(1))
+    }
+
+    /// Цена пула: сколько quote_token за 1 base_token
+    fn calculate_price(&self, amm: &lifinity::Amm, base_token: &str, _quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        let price = self.oracle_price(amm)?;
+        if base_mint == amm.token_a_mint {
+            return Ok(price);
+        }
+        Decimal::ONE.checked_div(price).context("Переполнение при расчёте цены")
+    }
+
+    /// Инструкция свопа по актуальным данным пула
+    async fn swap_instruction(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+        min_amount_out: u64,
+        payer: &Pubkey,
+    ) -> Result<Instruction> {
+        let pool_address = self.get_pool(from_token, to_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let amm = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+
+        let from_mint = self.tokens.mint(from_token)?;
+        lifinity::swap_instruction(
+            &Self::program_id()?,
+            &pool_address,
+            &amm,
+            payer,
+            &from_mint,
+            amount_in,
+            min_amount_out,
+        ).context("Не удалось построить инструкцию swap")
+    }
+
+    /// Отправка транзакции с retry-логикой
+    async fn send_transaction_with_retry(
+        &self,
+        transaction: &Transaction,
+        wallet: &Wallet,
+        max_retries: u32,
+        expected: Option<&ExpectedOutput>,
+    ) -> Result<String> {
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl DexInterface for LifinityDex {
+    fn name(&self) -> &str {
+        "lifinity"
+    }
+
+    async fn refresh_pool(&self, base_token: &str, quote_token: &str) -> Result<bool> {
+        self.get_pool(base_token, quote_token).await?;
+        Ok(true)
+    }
+
+    async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        log::debug!("Lifinity: получение цены {}/{}", base_token, quote_token);
+
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let amm = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+        let price = self.calculate_price(&amm, base_token, quote_token)
+            .context("Не удалось рассчитать цену")?;
+
+        log::debug!("Lifinity: цена {}/{} = {}", base_token, quote_token, price);
+        Ok(price)
+    }
+
+    async fn execute_swap(
+        &self,
+        simulation_mode: bool,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<String> {
+        log::info!("Lifinity: выполнение свопа {} -> {} ({}), min_output: {}",
+            from_token, to_token, amount, min_output);
+
+        if simulation_mode {
+            log::info!("Lifinity: симуляция свопа (реальная транзакция не отправляется)");
+            return Ok("simulated_signature_lifinity".to_string());
+        }
+
+        let build_started = Instant::now();
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+
+        let recent_blockhash = self.rpc_client
+            .get_latest_blockhash()
+            .context("Не удалось получить blockhash")?;
+
+        let instructions = accounts.around([swap_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
+        transaction.sign(&[wallet.keypair()], recent_blockhash);
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
+
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
+            .context("Не удалось отправить транзакцию")?;
+
+        log::info!("Lifinity: своп выполнен, signature: {}", signature);
+        Ok(signature)
+    }
+
+    async fn pool_fee_percent(&self, base_token: &str, quote_token: &str) -> Option<Decimal> {
+        match self.get_pool(base_token, quote_token).await {
+            Ok(pool) => pool.fee_percent,
+            Err(e) => {
+                log::debug!("Lifinity: комиссия пула {}/{} неизвестна: {:#}", base_token, quote_token, e);
+                None
+            }
+        }
+    }
+
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let amm = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+        vault_liquidity(
+            &self.rpc_client,
+            (&amm.token_a_mint, &amm.token_a_account),
+            (&amm.token_b_mint, &amm.token_b_account),
+            &self.tokens.mint(base_token)?,
+        )
+    }
+
+    fn supports_atomic_swap(&self) -> bool {
+        true
+    }
+
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+        Ok(accounts.around([swap_instruction]))
+    }
+}
+
+/// Состояние пула Saber: SwapInfo, резервы и decimals токенов
+struct SaberPool {
+    info: saber::SwapInfo,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    token_a_decimals: u8,
+    token_b_decimals: u8,
+    /// Время кластера на момент чтения (для линейного изменения A)
+    now: i64,
+    /// Слот, на котором прочитаны пул и резервы
+    slot: u64,
+}
+
+impl SaberPool {
+    /// Резервы (вход, выход) для свопа из токена `from_mint`
+    fn reserves_for(&self, from_mint: &Pubkey) -> Result<(u64, u64)> {
+        if *from_mint == self.info.token_a_mint {
+            Ok((self.token_a_amount, self.token_b_amount))
+        } else if *from_mint == self.info.token_b_mint {
+            Ok((self.token_b_amount, self.token_a_amount))
+        } else {
+            anyhow::bail!("Токен {} не торгуется в пуле Saber", from_mint)
+        }
+    }
+}
+
+/// Реализация для Saber (stable swap для пар стейблкоинов)
+struct SaberDex {
+    rpc_client: RpcClient,
+    rpc_cache: Arc<RpcCache>,
+    tokens: Arc<TokenRegistry>,
+    registry: SaberPoolRegistry,
+    last_reserves: LastReserves,
+    price_slots: PriceSlots,
+    /// Время кластера для проверки времени из аккаунтов
+    clock_skew: ClockSkew,
+}
+
+impl SaberDex {
+    fn new(config: &Config, rpc_cache: Arc<RpcCache>, tokens: Arc<TokenRegistry>, clock_skew: ClockSkew) -> Result<Self> {
+        let rpc_client = create_rpc_client(&config.network)?;
+        let registry = SaberPoolRegistry::new(config, Self::program_id()?, rpc_cache.clone());
+
+        Ok(Self {
+            rpc_client,
+            rpc_cache,
+            tokens,
+            registry,
+            last_reserves: LastReserves::default(),
+            price_slots: PriceSlots::default(),
+            clock_skew,
+        })
+    }
+
+    /// Program ID Saber StableSwap
+    fn program_id() -> Result<Pubkey> {
+        Pubkey::from_str(saber::SABER_PROGRAM_ID).context("Некорректный Program ID Saber")
+    }
+
+    /// Пул торговой пары из реестра (поиск on-chain)
+    async fn get_pool(&self, token_a: &str, token_b: &str) -> Result<PoolInfo> {
+        let mint_a = self.tokens.mint(token_a)?;
+        let mint_b = self.tokens.mint(token_b)?;
+        self.registry.resolve(&mint_a, &mint_b).await
+    }
+
+    /// Чтение пула: SwapInfo и резервы одним запросом
+    ///
+    /// Адреса резервов не меняются — берутся из кеша; SwapInfo читается заново
+    /// вместе с резервами, так как A и пауза могут измениться.
+    fn get_pool_data(&self, pool_address: &Pubkey) -> Result<SaberPool> {
+        let static_data = self.rpc_cache
+            .static_account_data(&self.rpc_client, pool_address)
+            .context("Не удалось получить данные аккаунта пула")?;
+        let layout = saber::SwapInfo::parse(&static_data).context("Не удалось разобрать аккаунт пула Saber")?;
+
+        let (accounts, slot) = get_accounts_with_slot(
+            &self.rpc_client,
+            &[*pool_address, layout.token_a_reserve, layout.token_b_reserve],
+        )
+        .context("Не удалось получить аккаунты пула")?;
+        let [pool_account, reserve_a, reserve_b]: [Option<Account>; 3] = accounts.try_into()
+            .map_err(|_| anyhow::anyhow!("RPC вернул неполный список аккаунтов пула"))?;
+
+        let info = saber::SwapInfo::parse(&pool_account.context("Аккаунт пула не найден")?.data)
+            .context("Не удалось разобрать аккаунт пула Saber")?;
+        if !info.is_tradable() {
+            anyhow::bail!("Свопы в пуле Saber {} недоступны", pool_address);
+        }
+        if (info.token_a_reserve, info.token_b_reserve) != (layout.token_a_reserve, layout.token_b_reserve) {
+            anyhow::bail!("Адреса резервов пула {} не совпадают с аккаунтом пула", pool_address);
+        }
+        let token_a_amount = raydium_amm::token_account_amount(&reserve_a.context("Резерв A пула не найден")?.data)?;
+        let token_b_amount = raydium_amm::token_account_amount(&reserve_b.context("Резерв B пула не найден")?.data)?;
+        let token_a_decimals = self.tokens.decimals(&self.rpc_client, &info.token_a_mint)
+            .context("Не удалось получить decimals токена A")?;
+        let token_b_decimals = self.tokens.decimals(&self.rpc_client, &info.token_b_mint)
+            .context("Не удалось получить decimals токена B")?;
+
+        let now = self.clock_skew.cluster_now().timestamp();
+
+        Ok(SaberPool { info, token_a_amount, token_b_amount, token_a_decimals, token_b_decimals, now, slot })
+    }
+
+    /// Цена пула по инварианту stable swap: сколько quote_token за 1 base_token
+    fn calculate_price(&self, pool: &SaberPool, base_token: &str, _quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        let (reserve_in, reserve_out) = pool.reserves_for(&base_mint)?;
+        let (base_decimals, quote_decimals) = if base_mint == pool.info.token_a_mint {
+            (pool.token_a_decimals, pool.token_b_decimals)
+        } else {
+            (pool.token_b_decimals, pool.token_a_decimals)
+        };
+        let rate = saber::spot_rate(&pool.info, pool.now, reserve_in, reserve_out)?;
+
+        // Курс в минимальных единицах -> в целых токенах
+        let base_unit = units::from_base_units(1, base_decimals)?;
+        let quote_unit = units::from_base_units(1, quote_decimals)?;
+        rate.checked_mul(quote_unit)
+            .and_then(|price| price.checked_div(base_unit))
+            .context("Переполнение при расчёте цены")
+    }
+
+    /// Инструкция свопа по актуальным данным пула
+    async fn swap_instruction(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount_in: u64,
+        min_amount_out: u64,
+        payer: &Pubkey,
+    ) -> Result<Instruction> {
+        let pool_address = self.get_pool(from_token, to_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+
+        let from_mint = self.tokens.mint(from_token)?;
+
+        // Выход по инварианту на весь объём: транзакция, которую программа отклонит, не отправляется
+        let (reserve_in, reserve_out) = pool.reserves_for(&from_mint)?;
+        let expected_out = saber::quote_swap(&pool.info, pool.now, amount_in, reserve_in, reserve_out)
+            .context("Не удалось рассчитать выход свопа")?;
+        if expected_out < min_amount_out {
+            anyhow::bail!("Пул Saber даёт {} вместо минимума {}", expected_out, min_amount_out);
+        }
+
+        saber::swap_instruction(
+            &Self::program_id()?,
+            &pool_address,
+            &pool.info,
+            payer,
+            &from_mint,
+            amount_in,
+            min_amount_out,
+        ).context("Не удалось построить инструкцию swap")
+    }
+
+    /// Отправка транзакции с retry-логикой
+    async fn send_transaction_with_retry(
+        &self,
+        transaction: &Transaction,
+        wallet: &Wallet,
+        max_retries: u32,
+        expected: Option<&ExpectedOutput>,
+    ) -> Result<String> {
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
+    }
+}
+
+#[async_trait::async_trait]
+impl DexInterface for SaberDex {
+    fn name(&self) -> &str {
+        "saber"
+    }
+
+    async fn refresh_pool(&self, base_token: &str, quote_token: &str) -> Result<bool> {
+        self.get_pool(base_token, quote_token).await?;
+        Ok(true)
+    }
+
+    async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        log::debug!("Saber: получение цены {}/{}", base_token, quote_token);
+
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+        let price = self.calculate_price(&pool, base_token, quote_token)
+            .context("Не удалось рассчитать цену")?;
+        self.last_reserves.record(base_token, quote_token, pool.reserves_for(&self.tokens.mint(base_token)?)?);
+        self.price_slots.record(base_token, quote_token, pool.slot);
+
+        log::debug!("Saber: цена {}/{} = {}", base_token, quote_token, price);
+        Ok(price)
+    }
+
+    async fn execute_swap(
+        &self,
+        simulation_mode: bool,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<String> {
+        log::info!("Saber: выполнение свопа {} -> {} ({}), min_output: {}",
+            from_token, to_token, amount, min_output);
+
+        if simulation_mode {
+            log::info!("Saber: симуляция свопа (реальная транзакция не отправляется)");
+            return Ok("simulated_signature_saber".to_string());
+        }
+
+        let build_started = Instant::now();
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+
+        let recent_blockhash = self.rpc_client
+            .get_latest_blockhash()
+            .context("Не удалось получить blockhash")?;
+
+        let instructions = accounts.around([swap_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
         transaction.sign(&[wallet.keypair()], recent_blockhash);
-        
-        // Отправка транзакции с retry
-        let signature = self.send_transaction_with_retry(&transaction, 3).await
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
+
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
             .context("Не удалось отправить транзакцию")?;
-        
-        log::info!("Raydium: своп выполнен, signature: {}", signature);
+
+        log::info!("Saber: своп выполнен, signature: {}", signature);
         Ok(signature)
     }
-}
 
-/// Orca Whirlpools Program ID (mainnet)
-const ORCA_WHIRLPOOLS_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
-/// Orca Whirlpools Program ID (devnet)
-const ORCA_WHIRLPOOLS_PROGRAM_ID_DEVNET: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+    fn pool_reserves(&self, base_token: &str, quote_token: &str) -> Option<Reserves> {
+        self.last_reserves.get(base_token, quote_token)
+    }
 
-/// Структура данных пула Orca Whirlpools
-#[derive(Debug, Clone)]
-struct OrcaWhirlpool {
-    pub whirlpool_address: Pubkey,
-    pub token_a_mint: Pubkey,
-    pub token_b_mint: Pubkey,
-    pub token_vault_a: Pubkey,
-    pub token_vault_b: Pubkey,
-    pub token_a_reserve: u64,
-    pub token_b_reserve: u64,
-    pub sqrt_price: u128, // sqrt price для концентрированной ликвидности
-}
+    fn price_slot(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.price_slots.get(base_token, quote_token)
+    }
 
-/// Реализация для Orca Whirlpools
-struct OrcaDex {
-    config: Config,
-    rpc_client: RpcClient,
-}
+    async fn pool_fee_percent(&self, base_token: &str, quote_token: &str) -> Option<Decimal> {
+        match self.get_pool(base_token, quote_token).await {
+            Ok(pool) => pool.fee_percent,
+            Err(e) => {
+                log::debug!("Saber: комиссия пула {}/{} неизвестна: {:#}", base_token, quote_token, e);
+                None
+            }
+        }
+    }
 
-impl OrcaDex {
-    fn new(config: &Config) -> Result<Self> {
-        let rpc_client = RpcClient::new_with_commitment(
-            config.network.rpc_url.clone(),
-            CommitmentConfig::confirmed(),
-        );
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+        pool.reserves_for(&self.tokens.mint(base_token)?)
+    }
 
-        Ok(Self {
-            config: config.clone(),
-            rpc_client,
-        })
+    fn supports_atomic_swap(&self) -> bool {
+        true
     }
 
-    /// Получение адреса Whirlpool для торговой пары
-    /// В реальной реализации можно использовать Orca API или on-chain данные
-    fn get_whirlpool_address(&self, _token_a: &str, _token_b: &str) -> Result<Pubkey> {
-        // Для devnet используем известные адреса пулов
-        // В продакшене нужно получать через API или on-chain поиск
-        let is_devnet = self.config.network.rpc_url.contains("devnet");
-        
-        // Пример: SOL/USDC пул на devnet
-        // В реальной реализации нужно получать через Orca API или поиск по mint адресам
-        if is_devnet {
-            // Заглушка: в реальности нужно получать через API
-            log::warn!("Получение адреса Whirlpool через API не реализовано, используем заглушку");
-            // Возвращаем случайный адрес для примера (в реальности нужно получать реальный)
-            Pubkey::from_str("11111111111111111111111111111111")
-                .context("Ошибка создания адреса Whirlpool")
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+        Ok(accounts.around([swap_instruction]))
+    }
+}
+
+/// Состояние пула пользовательского AMM: поля пула, резервы и decimals токенов
+struct CustomAmmPool {
+    pool: custom_amm::Pool,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    token_a_decimals: u8,
+    token_b_decimals: u8,
+    /// Слот, на котором прочитаны пул и хранилища
+    slot: u64,
+}
+
+impl CustomAmmPool {
+    /// Резервы (вход, выход) для свопа из токена `from_mint`
+    fn reserves_for(&self, from_mint: &Pubkey) -> Result<(u64, u64)> {
+        if *from_mint == self.pool.token_a_mint {
+            Ok((self.token_a_amount, self.token_b_amount))
+        } else if *from_mint == self.pool.token_b_mint {
+            Ok((self.token_b_amount, self.token_a_amount))
         } else {
-            // Mainnet: можно использовать известные адреса или API
-            log::warn!("Получение адреса Whirlpool через API не реализовано, используем заглушку");
-            Pubkey::from_str("11111111111111111111111111111111")
-                .context("Ошибка создания адреса Whirlpool")
+            anyhow::bail!("Токен {} не торгуется в пуле", from_mint)
         }
     }
+}
 
-    /// Чтение данных Whirlpool из аккаунта
-    async fn get_whirlpool_data(&self, whirlpool_address: &Pubkey) -> Result<OrcaWhirlpool> {
-        // Получение данных аккаунта Whirlpool
-        let _account_data = self.rpc_client
-            .get_account_data(whirlpool_address)
-            .context("Не удалось получить данные аккаунта Whirlpool")?;
-
-        // Парсинг структуры Whirlpool
-        // Структура Whirlpool (упрощённо):
-        // - whirlpools_config (Pubkey)
-        // - token_mint_a (Pubkey)
-        // - token_vault_a (Pubkey)
-        // - fee_rate (u16)
-        // - tick_spacing (u16)
-        // - tick_spacing_seed (u16)
-        // - tick_spacing_seed2 (u16)
-        // - token_mint_b (Pubkey)
-        // - token_vault_b (Pubkey)
-        // - fee_rate (u16)
-        // - protocol_fee_rate (u16)
-        // - liquidity (u128)
-        // - sqrt_price (u128)
-        // - tick_current_index (i32)
-        // - protocol_fee_owed_a (u64)
-        // - protocol_fee_owed_b (u64)
-        // - tick_array_bitmap (u64[8])
-        // - reward_last_updated_timestamp (u64)
-        
-        // Упрощённая версия: читаем резервы из vault аккаунтов
-        // В реальной реализации нужно парсить полную структуру
-        
-        // Получаем mint адреса токенов (упрощённо, в реальности из структуры Whirlpool)
-        let token_a_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")?; // SOL
-        let token_b_mint = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")?; // USDC
-        
-        // Получаем vault адреса (упрощённо)
-        let token_vault_a = Pubkey::from_str("11111111111111111111111111111111")?;
-        let token_vault_b = Pubkey::from_str("11111111111111111111111111111111")?;
-        
-        // Читаем балансы vault аккаунтов
-        // В реальной реализации нужно парсить структуру Whirlpool и получать реальные vault адреса
-        // Для упрощения используем заглушки
-        let token_a_reserve = match self.rpc_client.get_account(&token_vault_a) {
-            Ok(_account) => {
-                // Парсим баланс из данных аккаунта
-                // В реальности нужно использовать правильный парсинг SPL Token аккаунта
-                0 // Заглушка
-            }
-            Err(_) => {
-                log::warn!("Не удалось получить данные vault аккаунта, используем заглушку");
-                0
-            }
-        };
-        
-        let token_b_reserve = match self.rpc_client.get_account(&token_vault_b) {
-            Ok(_account) => {
-                // Парсим баланс из данных аккаунта
-                0 // Заглушка
-            }
-            Err(_) => {
-                log::warn!("Не удалось получить данные vault аккаунта, используем заглушку");
-                0
-            }
-        };
+/// AMM x*y=k, описанный в настройках (`[dex.custom.<имя>]`)
+///
+/// Разметка аккаунта пула и формат инструкции swap берутся из конфигурации,
+/// поэтому новый constant-product DEX подключается без изменения кода.
+struct ConfigurableAmmDex {
+    name: String,
+    layout: Arc<custom_amm::PoolLayout>,
+    rpc_client: RpcClient,
+    rpc_cache: Arc<RpcCache>,
+    tokens: Arc<TokenRegistry>,
+    registry: CustomAmmPoolRegistry,
+    last_reserves: LastReserves,
+    price_slots: PriceSlots,
+}
 
-        // Для Whirlpools используем sqrt_price для расчёта цены
-        // Упрощённо: если резервы есть, рассчитываем sqrt_price из них
-        let sqrt_price = if token_a_reserve > 0 && token_b_reserve > 0 {
-            // sqrt_price = sqrt(token_b_reserve / token_a_reserve) * 2^64
-            // Упрощённая версия: используем простой расчёт без integer_sqrt
-            // В реальной реализации нужно использовать правильный расчёт sqrt_price
-            let price_ratio = (token_b_reserve as u128 * 1_000_000_000_000_000_000) / token_a_reserve as u128;
-            // Приблизительный расчёт sqrt (используем простую формулу)
-            (price_ratio * (1u128 << 32)) / 1_000_000_000_000_000_000
-        } else {
-            0u128
-        };
+impl ConfigurableAmmDex {
+    fn new(config: &Config, name: &str, custom: &crate::config::CustomAmmConfig, rpc_cache: Arc<RpcCache>, tokens: Arc<TokenRegistry>) -> Result<Self> {
+        let layout = Arc::new(custom_amm::PoolLayout::from_config(custom)
+            .with_context(|| format!("Некорректное описание dex.custom.{}", name))?);
+        let rpc_client = create_rpc_client(&config.network)?;
+        let registry = CustomAmmPoolRegistry::new(config, name, layout.clone(), rpc_cache.clone());
+        if !layout.can_execute() {
+            log::info!("{}: swap_accounts не заданы, DEX используется только для цен", name);
+        }
 
-        Ok(OrcaWhirlpool {
-            whirlpool_address: *whirlpool_address,
-            token_a_mint,
-            token_b_mint,
-            token_vault_a,
-            token_vault_b,
-            token_a_reserve,
-            token_b_reserve,
-            sqrt_price,
+        Ok(Self {
+            name: name.to_string(),
+            layout,
+            rpc_client,
+            rpc_cache,
+            tokens,
+            registry,
+            last_reserves: LastReserves::default(),
+            price_slots: PriceSlots::default(),
         })
     }
 
-    /// Расчёт цены из Whirlpool
-    /// Возвращает цену: сколько quote_token за 1 base_token
-    fn calculate_price(&self, pool: &OrcaWhirlpool, base_token: &str, _quote_token: &str) -> Result<Decimal> {
-        // Определяем, какой токен является base, а какой quote
-        let (base_reserve, quote_reserve) = if base_token == "SOL" {
-            (pool.token_a_reserve, pool.token_b_reserve)
-        } else {
-            (pool.token_b_reserve, pool.token_a_reserve)
-        };
+    /// Пул торговой пары из реестра (поиск on-chain)
+    async fn get_pool(&self, token_a: &str, token_b: &str) -> Result<PoolInfo> {
+        let mint_a = self.tokens.mint(token_a)?;
+        let mint_b = self.tokens.mint(token_b)?;
+        self.registry.resolve(&mint_a, &mint_b).await
+    }
 
-        if base_reserve == 0 {
-            anyhow::bail!("Резерв base токена равен нулю");
+    /// Чтение пула и резервов одним запросом
+    ///
+    /// Адреса хранилищ не меняются — берутся из кеша; аккаунт пула читается
+    /// заново вместе с резервами, так как комиссия может измениться.
+    fn get_pool_data(&self, pool_address: &Pubkey) -> Result<CustomAmmPool> {
+        let static_data = self.rpc_cache
+            .static_account_data(&self.rpc_client, pool_address)
+            .context("Не удалось получить данные аккаунта пула")?;
+        let static_pool = self.layout.parse_pool(&static_data).context("Не удалось разобрать аккаунт пула")?;
+
+        let (accounts, slot) = get_accounts_with_slot(
+            &self.rpc_client,
+            &[*pool_address, static_pool.token_a_vault, static_pool.token_b_vault],
+        )
+        .context("Не удалось получить аккаунты пула")?;
+        let [pool_account, vault_a, vault_b]: [Option<Account>; 3] = accounts.try_into()
+            .map_err(|_| anyhow::anyhow!("RPC вернул неполный список аккаунтов пула"))?;
+
+        let pool = self.layout.parse_pool(&pool_account.context("Аккаунт пула не найден")?.data)
+            .context("Не удалось разобрать аккаунт пула")?;
+        if (pool.token_a_vault, pool.token_b_vault) != (static_pool.token_a_vault, static_pool.token_b_vault) {
+            anyhow::bail!("Адреса хранилищ пула {} не совпадают с аккаунтом пула", pool_address);
         }
-
-        // Для Whirlpools можно использовать sqrt_price для более точного расчёта
-        // Но для упрощения используем формулу из резервов
-        // Цена = quote_reserve / base_reserve
-        let price = Decimal::from(quote_reserve) / Decimal::from(base_reserve);
-        Ok(price)
+        let token_a_amount = raydium_amm::token_account_amount(&vault_a.context("Хранилище A пула не найдено")?.data)?;
+        let token_b_amount = raydium_amm::token_account_amount(&vault_b.context("Хранилище B пула не найдено")?.data)?;
+        let token_a_decimals = self.tokens.decimals(&self.rpc_client, &pool.token_a_mint)
+            .context("Не удалось получить decimals токена A")?;
+        let token_b_decimals = self.tokens.decimals(&self.rpc_client, &pool.token_b_mint)
+            .context("Не удалось получить decimals токена B")?;
+
+        Ok(CustomAmmPool { pool, token_a_amount, token_b_amount, token_a_decimals, token_b_decimals, slot })
     }
 
-    /// Расчёт выходного количества токенов при свопе
-    /// Для Whirlpools используется более сложная формула с учётом концентрированной ликвидности
-    /// Упрощённая версия использует формулу x*y=k
-    #[allow(dead_code)]
-    fn calculate_swap_output(
-        &self,
-        pool: &OrcaWhirlpool,
-        amount_in: u64,
-        is_token_a_to_b: bool,
-    ) -> Result<u64> {
-        let (reserve_in, reserve_out) = if is_token_a_to_b {
-            (pool.token_a_reserve, pool.token_b_reserve)
+    /// Расчёт цены по формуле x*y=k: сколько quote_token за 1 base_token
+    fn calculate_price(&self, pool: &CustomAmmPool, base_token: &str, _quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        let (base_reserve, quote_reserve) = pool.reserves_for(&base_mint)?;
+        let (base_decimals, quote_decimals) = if base_mint == pool.pool.token_a_mint {
+            (pool.token_a_decimals, pool.token_b_decimals)
         } else {
-            (pool.token_b_reserve, pool.token_a_reserve)
+            (pool.token_b_decimals, pool.token_a_decimals)
         };
 
-        if reserve_in == 0 || reserve_out == 0 {
-            anyhow::bail!("Резерв равен нулю");
+        // Цена = quote_reserve / base_reserve, в целых токенах
+        let base = units::from_base_units(base_reserve, base_decimals)?;
+        let quote = units::from_base_units(quote_reserve, quote_decimals)?;
+        if base.is_zero() {
+            anyhow::bail!("Резерв base токена равен нулю");
         }
-
-        // Упрощённая формула: amount_out = (amount_in * reserve_out) / (reserve_in + amount_in)
-        // В реальной реализации Whirlpools использует более сложную логику с тиками и концентрированной ликвидностью
-        let amount_out = (amount_in as u128 * reserve_out as u128) / (reserve_in as u128 + amount_in as u128);
-        
-        Ok(amount_out as u64)
+        quote.checked_div(base).context("Переполнение при расчёте цены")
     }
 
-    /// Построение инструкции swap для Orca Whirlpools
-    fn build_swap_instruction(
+    /// Инструкция свопа по актуальным данным пула
+    async fn swap_instruction(
         &self,
-        pool: &OrcaWhirlpool,
-        user_wallet: &Pubkey,
+        from_token: &str,
+        to_token: &str,
         amount_in: u64,
         min_amount_out: u64,
-        _is_token_a_to_b: bool,
+        payer: &Pubkey,
     ) -> Result<Instruction> {
-        let program_id = if self.config.network.rpc_url.contains("devnet") {
-            Pubkey::from_str(ORCA_WHIRLPOOLS_PROGRAM_ID_DEVNET)?
-        } else {
-            Pubkey::from_str(ORCA_WHIRLPOOLS_PROGRAM_ID)?
-        };
+        let pool_address = self.get_pool(from_token, to_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
 
-        // Построение инструкции swap для Whirlpools
-        // В реальной реализации нужно использовать правильные аккаунты и данные
-        // Это упрощённая версия
-        
-        let accounts = vec![
-            AccountMeta::new(*user_wallet, true), // user wallet (signer)
-            AccountMeta::new(pool.whirlpool_address, false), // whirlpool
-            AccountMeta::new(pool.token_vault_a, false), // token_a vault
-            AccountMeta::new(pool.token_vault_b, false), // token_b vault
-            AccountMeta::new(pool.token_a_mint, false), // token_a mint
-            AccountMeta::new(pool.token_b_mint, false), // token_b mint
-            AccountMeta::new_readonly(system_program::id(), false), // system program
-        ];
+        let from_mint = self.tokens.mint(from_token)?;
 
-        // Данные инструкции: discriminator (8 bytes) + amount_in (8 bytes) + min_amount_out (8 bytes)
-        let mut data = vec![0u8; 24];
-        // В реальной реализации нужно использовать правильный discriminator для swap Whirlpools
-        // Discriminator для swap обычно: 0x02 (или другой в зависимости от версии)
-        data[0..8].copy_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]); // Пример discriminator
-        data[8..16].copy_from_slice(&amount_in.to_le_bytes());
-        data[16..24].copy_from_slice(&min_amount_out.to_le_bytes());
+        // Выход по x*y=k на весь объём: транзакция, которую программа отклонит, не отправляется
+        let (reserve_in, reserve_out) = pool.reserves_for(&from_mint)?;
+        let expected_out = math::constant_product_output(amount_in, reserve_in, reserve_out, pool.pool.fee)
+            .context("Не удалось рассчитать выход свопа")?;
+        if expected_out < min_amount_out {
+            anyhow::bail!("Пул {} даёт {} вместо минимума {}", self.name, expected_out, min_amount_out);
+        }
 
-        Ok(Instruction {
-            program_id,
-            accounts,
-            data,
-        })
+        custom_amm::swap_instruction(
+            &self.layout,
+            &pool_address,
+            &pool.pool,
+            payer,
+            &from_mint,
+            amount_in,
+            min_amount_out,
+        ).context("Не удалось построить инструкцию swap")
     }
 
     /// Отправка транзакции с retry-логикой
     async fn send_transaction_with_retry(
         &self,
         transaction: &Transaction,
+        wallet: &Wallet,
         max_retries: u32,
+        expected: Option<&ExpectedOutput>,
     ) -> Result<String> {
-        let mut last_error = None;
-        
-        for attempt in 0..max_retries {
-            match self.rpc_client.send_transaction(transaction) {
-                Ok(signature) => {
-                    log::info!("Транзакция отправлена успешно: {}", signature);
-                    return Ok(signature.to_string());
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries - 1 {
-                        let delay = std::time::Duration::from_millis(100 * (attempt + 1) as u64);
-                        log::warn!("Попытка {} не удалась, повтор через {:?}: {}", attempt + 1, delay, last_error.as_ref().unwrap());
-                        tokio::time::sleep(delay).await;
-                    }
-                }
-            }
-        }
-
-        Err(anyhow::anyhow!(
-            "Не удалось отправить транзакцию после {} попыток: {:?}",
-            max_retries,
-            last_error
-        ))
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
     }
 }
 
 #[async_trait::async_trait]
-impl DexInterface for OrcaDex {
+impl DexInterface for ConfigurableAmmDex {
     fn name(&self) -> &str {
-        "orca"
+        &self.name
+    }
+
+    async fn refresh_pool(&self, base_token: &str, quote_token: &str) -> Result<bool> {
+        self.get_pool(base_token, quote_token).await?;
+        Ok(true)
     }
 
     async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
-        log::debug!("Orca: получение цены {}/{}", base_token, quote_token);
-        
-        // Получение адреса Whirlpool
-        let whirlpool_address = self.get_whirlpool_address(base_token, quote_token)
-            .context("Не удалось получить адрес Whirlpool")?;
-        
-        // Чтение данных Whirlpool
-        let pool = self.get_whirlpool_data(&whirlpool_address).await
-            .context("Не удалось получить данные Whirlpool")?;
-        
-        // Расчёт цены
+        log::debug!("{}: получение цены {}/{}", self.name, base_token, quote_token);
+
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
         let price = self.calculate_price(&pool, base_token, quote_token)
             .context("Не удалось рассчитать цену")?;
-        
-        log::debug!("Orca: цена {}/{} = {}", base_token, quote_token, price);
+        self.last_reserves.record(base_token, quote_token, pool.reserves_for(&self.tokens.mint(base_token)?)?);
+        self.price_slots.record(base_token, quote_token, pool.slot);
+
+        log::debug!("{}: цена {}/{} = {}", self.name, base_token, quote_token, price);
         Ok(price)
     }
 
@@ -736,68 +2681,93 @@ impl DexInterface for OrcaDex {
         min_output: Decimal,
         wallet: &Wallet,
     ) -> Result<String> {
-        log::info!("Orca: выполнение свопа {} -> {} ({}), min_output: {}", 
-            from_token, to_token, amount, min_output);
-        
+        log::info!("{}: выполнение свопа {} -> {} ({}), min_output: {}",
+            self.name, from_token, to_token, amount, min_output);
+
         if simulation_mode {
-            log::info!("Orca: симуляция свопа (реальная транзакция не отправляется)");
-            return Ok("simulated_signature_orca".to_string());
+            log::info!("{}: симуляция свопа (реальная транзакция не отправляется)", self.name);
+            return Ok(format!("simulated_signature_{}", self.name));
+        }
+        if !self.layout.can_execute() {
+            anyhow::bail!("{}: исполнение недоступно — в dex.custom.{} не заданы swap_accounts", self.name, self.name);
         }
 
-        // Получение адреса Whirlpool
-        let whirlpool_address = self.get_whirlpool_address(from_token, to_token)
-            .context("Не удалось получить адрес Whirlpool")?;
-        
-        // Чтение актуальных данных Whirlpool
-        let pool = self.get_whirlpool_data(&whirlpool_address).await
-            .context("Не удалось получить данные Whirlpool")?;
-        
-        // Конвертация amount в lamports/token units
-        let amount_in = amount.to_string().parse::<u64>()
-            .context("Не удалось конвертировать amount в u64")?;
-        
-        let min_amount_out = min_output.to_string().parse::<u64>()
-            .context("Не удалось конвертировать min_output в u64")?;
-        
-        // Определение направления свопа
-        let is_token_a_to_b = from_token == "SOL"; // Упрощённо
-        
-        // Построение инструкции swap
-        let swap_instruction = self.build_swap_instruction(
-            &pool,
-            wallet.pubkey(),
-            amount_in,
-            min_amount_out,
-            is_token_a_to_b,
-        ).context("Не удалось построить инструкцию swap")?;
-        
-        // Получение последнего blockhash
+        let build_started = Instant::now();
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+
         let recent_blockhash = self.rpc_client
             .get_latest_blockhash()
             .context("Не удалось получить blockhash")?;
-        
-        // Создание транзакции
-        let mut transaction = Transaction::new_with_payer(
-            &[swap_instruction],
-            Some(wallet.pubkey()),
-        );
+
+        let instructions = accounts.around([swap_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
         transaction.sign(&[wallet.keypair()], recent_blockhash);
-        
-        // Отправка транзакции с retry
-        let signature = self.send_transaction_with_retry(&transaction, 3).await
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
+
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
             .context("Не удалось отправить транзакцию")?;
-        
-        log::info!("Orca: своп выполнен, signature: {}", signature);
+
+        log::info!("{}: своп выполнен, signature: {}", self.name, signature);
         Ok(signature)
     }
+
+    fn pool_reserves(&self, base_token: &str, quote_token: &str) -> Option<Reserves> {
+        self.last_reserves.get(base_token, quote_token)
+    }
+
+    fn price_slot(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.price_slots.get(base_token, quote_token)
+    }
+
+    async fn pool_fee_percent(&self, base_token: &str, quote_token: &str) -> Option<Decimal> {
+        match self.get_pool(base_token, quote_token).await {
+            Ok(pool) => pool.fee_percent,
+            Err(e) => {
+                log::debug!("{}: комиссия пула {}/{} неизвестна: {:#}", self.name, base_token, quote_token, e);
+                None
+            }
+        }
+    }
+
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        let pool_address = self.get_pool(base_token, quote_token).await
+            .context("Не удалось получить адрес пула")?
+            .address;
+        let pool = self.get_pool_data(&pool_address)
+            .context("Не удалось получить данные пула")?;
+        pool.reserves_for(&self.tokens.mint(base_token)?)
+    }
+
+    fn supports_atomic_swap(&self) -> bool {
+        self.layout.can_execute()
+    }
+
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
+        let swap_instruction = self.swap_instruction(from_token, to_token, amount_in, min_amount_out, wallet.pubkey()).await?;
+        Ok(accounts.around([swap_instruction]))
+    }
 }
 
-/// Serum/OpenBook Program ID (mainnet)
-/// Примечание: Serum был переименован в OpenBook, но старый Program ID всё ещё используется
-const SERUM_PROGRAM_ID: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
-/// OpenBook Program ID (mainnet, новая версия)
-const OPENBOOK_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
-/// Serum/OpenBook Program ID (devnet)
+/// OpenBook v1 Program ID (mainnet) — форк Serum v3 с тем же форматом рынков
+///
+/// Исходная программа Serum v3 (9xQeWvG8…) заброшена после потери ключа
+/// обновления; рынки OpenBook v2 обслуживает отдельный адаптер `openbook_v2`.
+const OPENBOOK_V1_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+/// Serum v3 Program ID (devnet)
 const SERUM_PROGRAM_ID_DEVNET: &str = "DESVgJVGajEgKGXhb6XmqDHGz3VjdgP7rEVESBgxmroY";
 
 /// Структура данных рынка Serum/OpenBook
@@ -814,22 +2784,23 @@ struct SerumMarket {
     pub best_ask_price: u64, // Лучшая цена продажи
 }
 
-/// Реализация для Serum/OpenBook
+/// Реализация для Serum v3 / OpenBook v1 (legacy формат рынков)
 struct SerumDex {
     config: Config,
     rpc_client: RpcClient,
+    rpc_cache: Arc<RpcCache>,
+    tokens: Arc<TokenRegistry>,
 }
 
 impl SerumDex {
-    fn new(config: &Config) -> Result<Self> {
-        let rpc_client = RpcClient::new_with_commitment(
-            config.network.rpc_url.clone(),
-            CommitmentConfig::confirmed(),
-        );
+    fn new(config: &Config, rpc_cache: Arc<RpcCache>, tokens: Arc<TokenRegistry>) -> Result<Self> {
+        let rpc_client = create_rpc_client(&config.network)?;
 
         Ok(Self {
             config: config.clone(),
             rpc_client,
+            rpc_cache,
+            tokens,
         })
     }
 
@@ -857,11 +2828,22 @@ impl SerumDex {
         }
     }
 
+    /// Base и quote токены рынка для свопа: порядок из trading_pairs, иначе (from, to)
+    fn market_tokens<'a>(&self, from_token: &'a str, to_token: &'a str) -> (&'a str, &'a str) {
+        let reversed = format!("{}/{}", to_token, from_token);
+        if self.config.dex.trading_pairs.contains(&reversed) {
+            (to_token, from_token)
+        } else {
+            (from_token, to_token)
+        }
+    }
+
     /// Чтение данных рынка из аккаунта
-    async fn get_market_data(&self, market_address: &Pubkey) -> Result<SerumMarket> {
+    async fn get_market_data(&self, market_address: &Pubkey, base_token: &str, quote_token: &str) -> Result<SerumMarket> {
         // Получение данных аккаунта рынка
-        let _account_data = self.rpc_client
-            .get_account_data(market_address)
+        // Статические поля (mint, vault) не меняются — берём из кеша
+        let _account_data = self.rpc_cache
+            .static_account_data(&self.rpc_client, market_address)
             .context("Не удалось получить данные аккаунта рынка")?;
 
         // Парсинг структуры рынка Serum/OpenBook
@@ -884,8 +2866,8 @@ impl SerumDex {
         // В реальной реализации нужно парсить полную структуру
         
         // Получаем mint адреса токенов (упрощённо, в реальности из структуры рынка)
-        let base_mint = Pubkey::from_str("So11111111111111111111111111111111111111112")?; // SOL
-        let quote_mint = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v")?; // USDC
+        let base_mint = self.tokens.mint(base_token)?;
+        let quote_mint = self.tokens.mint(quote_token)?;
         
         // Получаем vault адреса (упрощённо)
         let base_vault = Pubkey::from_str("11111111111111111111111111111111")?;
@@ -992,14 +2974,12 @@ impl SerumDex {
         amount: u64,
         price: u64,
     ) -> Result<Instruction> {
-        let program_id = if self.config.network.rpc_url.contains("devnet") {
-            Pubkey::from_str(SERUM_PROGRAM_ID_DEVNET)?
+        let address = if self.config.network.rpc_url.contains("devnet") {
+            SERUM_PROGRAM_ID_DEVNET
         } else {
-            // Пробуем использовать OpenBook (новый) или Serum (старый)
-            Pubkey::from_str(OPENBOOK_PROGRAM_ID)
-                .or_else(|_| Pubkey::from_str(SERUM_PROGRAM_ID))
-                .context("Не удалось определить Program ID")?
+            OPENBOOK_V1_PROGRAM_ID
         };
+        let program_id = Pubkey::from_str(address).context("Некорректный Program ID Serum")?;
 
         // Построение инструкции place order для Serum/OpenBook
         // В реальной реализации нужно использовать правильные аккаунты и данные
@@ -1035,32 +3015,11 @@ impl SerumDex {
     async fn send_transaction_with_retry(
         &self,
         transaction: &Transaction,
+        wallet: &Wallet,
         max_retries: u32,
+        expected: Option<&ExpectedOutput>,
     ) -> Result<String> {
-        let mut last_error = None;
-        
-        for attempt in 0..max_retries {
-            match self.rpc_client.send_transaction(transaction) {
-                Ok(signature) => {
-                    log::info!("Транзакция отправлена успешно: {}", signature);
-                    return Ok(signature.to_string());
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries - 1 {
-                        let delay = std::time::Duration::from_millis(100 * (attempt + 1) as u64);
-                        log::warn!("Попытка {} не удалась, повтор через {:?}: {}", attempt + 1, delay, last_error.as_ref().unwrap());
-                        tokio::time::sleep(delay).await;
-                    }
-                }
-            }
-        }
-
-        Err(anyhow::anyhow!(
-            "Не удалось отправить транзакцию после {} попыток: {:?}",
-            max_retries,
-            last_error
-        ))
+        profile::measure(Stage::Send, Some(self.name()), send_transaction_with_retry(&self.rpc_client, transaction, wallet, max_retries, expected)).await
     }
 }
 
@@ -1078,7 +3037,7 @@ impl DexInterface for SerumDex {
             .context("Не удалось получить адрес рынка")?;
         
         // Чтение данных рынка и order book
-        let market = self.get_market_data(&market_address).await
+        let market = self.get_market_data(&market_address, base_token, quote_token).await
             .context("Не удалось получить данные рынка")?;
         
         // Расчёт цены из order book
@@ -1106,24 +3065,24 @@ impl DexInterface for SerumDex {
             return Ok("simulated_signature_serum".to_string());
         }
 
+        let build_started = Instant::now();
         // Получение адреса рынка
-        let market_address = self.get_market_address(from_token, to_token)
+        let (base_token, quote_token) = self.market_tokens(from_token, to_token);
+        let market_address = self.get_market_address(base_token, quote_token)
             .context("Не удалось получить адрес рынка")?;
         
         // Чтение актуальных данных рынка и order book
-        let market = self.get_market_data(&market_address).await
+        let market = self.get_market_data(&market_address, base_token, quote_token).await
             .context("Не удалось получить данные рынка")?;
         
         // Конвертация amount в lamports/token units
-        let amount_in = amount.to_string().parse::<u64>()
-            .context("Не удалось конвертировать amount в u64")?;
-        
-        let min_amount_out = min_output.to_string().parse::<u64>()
-            .context("Не удалось конвертировать min_output в u64")?;
+        let (amount_in, min_amount_out) = swap_amounts(&self.tokens, &self.rpc_client, from_token, to_token, amount, min_output)?;
+        let expected = expected_output(&self.tokens, wallet, to_token, min_amount_out)?;
+        let accounts = swap_accounts(&self.tokens, &self.rpc_client, wallet, from_token, to_token, amount_in)?;
         
         // Определение направления свопа
-        // Если from_token == SOL, то мы продаём SOL (sell), иначе покупаем (buy)
-        let side = from_token != "SOL"; // true = buy base_token, false = sell base_token
+        // Если from_token — base токен рынка, то мы продаём его (sell), иначе покупаем (buy)
+        let side = self.tokens.mint(from_token)? != market.base_mint; // true = buy base_token, false = sell base_token
         
         // Получение цены из order book для создания ордера
         // Используем best ask для покупки, best bid для продажи
@@ -1163,14 +3122,15 @@ impl DexInterface for SerumDex {
             .context("Не удалось получить blockhash")?;
         
         // Создание транзакции
-        let mut transaction = Transaction::new_with_payer(
-            &[order_instruction],
-            Some(wallet.pubkey()),
-        );
+        let instructions = accounts.around([order_instruction, TradeMemo::current().instruction()]);
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(wallet.pubkey()));
+        profile::record(Stage::Build, Some(self.name()), build_started.elapsed());
+        let sign_started = Instant::now();
         transaction.sign(&[wallet.keypair()], recent_blockhash);
+        profile::record(Stage::Sign, Some(self.name()), sign_started.elapsed());
         
         // Отправка транзакции с retry
-        let signature = self.send_transaction_with_retry(&transaction, 3).await
+        let signature = self.send_transaction_with_retry(&transaction, wallet, 3, expected.as_ref()).await
             .context("Не удалось отправить транзакцию")?;
         
         log::info!("Serum: своп выполнен, signature: {}", signature);
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use crate::arbitrage::{atomic_sell_leg, buy_leg_input, ArbitrageEngine, ArbitrageOpportunity, SandboxTrade};
+use crate::config_check::{ConfigReport, Finding};
+use crate::dex::atomic_instructions;
+use crate::receipt::TradeLeg;
+use crate::self_test::{CheckStatus, SelfTestReport, Simulator};
+
+/// Отчёт `arb-bot run --once`: проверки перед запуском, найденные возможности
+/// и транзакции, которые были бы отправлены (только симуляция)
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Нет ошибок конфигурации и самопроверки, поиск выполнен, ни одна
+    /// транзакция не провалила сборку или симуляцию
+    pub passed: bool,
+    pub preflight: Preflight,
+    /// Ошибка поиска возможностей (прогон не состоялся)
+    pub scan_error: Option<String>,
+    pub opportunities: Vec<DryRunOpportunity>,
+}
+
+/// Проверки перед прогоном
+#[derive(Debug, Clone, Serialize)]
+pub struct Preflight {
+    /// Замечания `check-config`
+    pub config: Vec<Finding>,
+    /// Самопроверка адаптеров (если включена и разрешена)
+    pub self_test: Option<SelfTestReport>,
+}
+
+/// Возможность прогона и её транзакция
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunOpportunity {
+    pub opportunity: ArbitrageOpportunity,
+    /// Прошла бы возможность проверки перед исполнением
+    pub would_execute: bool,
+    /// Непройденные проверки перед исполнением (как в POST /api/sandbox/trade)
+    pub failed_checks: Vec<String>,
+    /// Ноги транзакции (продажа — гарантированный выход покупки)
+    pub legs: Vec<DryRunLeg>,
+    pub simulation: DryRunSimulation,
+}
+
+/// Нога транзакции: своп from_token → to_token
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunLeg {
+    pub leg: TradeLeg,
+    pub dex: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in: Decimal,
+    pub min_output: Decimal,
+    /// Количество собранных инструкций (None — не собраны)
+    pub instructions: Option<usize>,
+}
+
+/// Результат симуляции транзакции
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunSimulation {
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl DryRunSimulation {
+    fn skipped(detail: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Skipped, detail: detail.into() }
+    }
+
+    fn failed(detail: impl Into<String>) -> Self {
+        Self { status: CheckStatus::Failed, detail: detail.into() }
+    }
+}
+
+/// Один полный прогон поиска без исполнения
+///
+/// Для каждой возможности собирается та же транзакция, что отправило бы
+/// исполнение (свопы, флеш-займ, memo сделки и проверка баланса), и
+/// симулируется целиком; ничего не отправляется. Маршруты через несколько пар
+/// и сделки, исполняемые в два этапа, в отчёте пропущены.
+pub async fn run(
+    engine: &ArbitrageEngine,
+    config_report: ConfigReport,
+    self_test: Option<SelfTestReport>,
+    simulator: &dyn Simulator,
+) -> DryRunReport {
+    let started_at = Utc::now();
+    let (opportunities, scan_error) = match engine.scan().await {
+        Ok(opportunities) => (opportunities, None),
+        Err(e) => (Vec::new(), Some(format!("{:#}", e))),
+    };
+
+    let mut results = Vec::with_capacity(opportunities.len());
+    for opportunity in opportunities {
+        results.push(dry_run_opportunity(engine, opportunity, simulator).await);
+    }
+
+    let passed = !config_report.has_errors()
+        && self_test.as_ref().is_none_or(|report| report.passed)
+        && scan_error.is_none()
+        && results.iter().all(|result| result.simulation.status != CheckStatus::Failed);
+    DryRunReport {
+        started_at,
+        finished_at: Utc::now(),
+        passed,
+        preflight: Preflight { config: config_report.findings, self_test },
+        scan_error,
+        opportunities: results,
+    }
+}
+
+async fn dry_run_opportunity(
+    engine: &ArbitrageEngine,
+    opportunity: ArbitrageOpportunity,
+    simulator: &dyn Simulator,
+) -> DryRunOpportunity {
+    let mut result = DryRunOpportunity {
+        opportunity,
+        would_execute: false,
+        failed_checks: Vec::new(),
+        legs: Vec::new(),
+        simulation: DryRunSimulation::skipped("маршруты через несколько пар не исполняются"),
+    };
+    if result.opportunity.is_route() {
+        return result;
+    }
+
+    let opportunity = &result.opportunity;
+    let trade = SandboxTrade {
+        base_token: opportunity.base_token.clone(),
+        quote_token: opportunity.quote_token.clone(),
+        buy_dex: opportunity.from_dex.clone(),
+        sell_dex: opportunity.to_dex.clone(),
+        amount: opportunity.trade_amount,
+    };
+    let evaluation = match engine.evaluate_trade(&trade).await {
+        Ok(evaluation) => evaluation,
+        Err(e) => {
+            result.simulation = DryRunSimulation::failed(format!("не удалось оценить сделку: {:#}", e));
+            return result;
+        }
+    };
+    result.would_execute = evaluation.would_execute();
+    result.failed_checks = evaluation.checks.iter()
+        .filter(|check| !check.passed)
+        .map(|check| format!("{}: {}", check.name, check.detail))
+        .collect();
+
+    let dex_manager = engine.dex_manager();
+    if dex_manager.get_dex(&opportunity.from_dex).is_none() || dex_manager.get_dex(&opportunity.to_dex).is_none() {
+        result.simulation = DryRunSimulation::failed("DEX возможности не найден");
+        return result;
+    }
+    // Продаётся гарантированный выход покупки, как при исполнении
+    let (sell_amount, sell_min_output) = atomic_sell_leg(opportunity, evaluation.min_output);
+    let legs = [
+        (TradeLeg::Buy, &opportunity.from_dex, &opportunity.quote_token, &opportunity.base_token, buy_leg_input(opportunity), evaluation.min_output.buy),
+        (TradeLeg::Sell, &opportunity.to_dex, &opportunity.base_token, &opportunity.quote_token, sell_amount, sell_min_output),
+    ];
+    result.legs = legs.into_iter()
+        .map(|(leg, dex, from_token, to_token, amount_in, min_output)| DryRunLeg {
+            leg,
+            dex: dex.clone(),
+            from_token: from_token.clone(),
+            to_token: to_token.clone(),
+            amount_in,
+            min_output,
+            instructions: None,
+        })
+        .collect();
+    if !engine.executes_atomically(opportunity) {
+        result.simulation = DryRunSimulation::skipped("сделка исполняется в два этапа, одной транзакции нет");
+        return result;
+    }
+
+    // Проверка баланса считается от баланса quote токена на узле симуляции
+    let wallet = engine.wallet().current().await;
+    let balance_before = match dex_manager.tokens().mint(&opportunity.quote_token) {
+        Ok(quote_mint) => simulator.token_balance(wallet.associated_token_address(&quote_mint)).await,
+        Err(e) => Err(e),
+    };
+    let balance_before = match balance_before {
+        Ok(balance) => balance,
+        Err(e) => {
+            result.simulation = DryRunSimulation::failed(format!("баланс quote токена: {:#}", e));
+            return result;
+        }
+    };
+    let trade = match engine.build_atomic_transaction(opportunity, evaluation.min_output, balance_before, &wallet).await {
+        Ok(trade) => trade,
+        Err(e) => {
+            result.simulation = DryRunSimulation::failed(format!("сборка транзакции: {:#}", e));
+            return result;
+        }
+    };
+    result.legs[0].instructions = Some(trade.leg_instructions.0);
+    result.legs[1].instructions = Some(trade.leg_instructions.1);
+    let instructions = match atomic_instructions(trade.swaps, Some(&trade.guard)) {
+        Ok((instructions, _)) => instructions,
+        Err(e) => {
+            result.simulation = DryRunSimulation::failed(format!("сборка транзакции: {:#}", e));
+            return result;
+        }
+    };
+
+    result.simulation = match simulator.simulate(&instructions, &wallet).await {
+        Ok(Some(units)) => DryRunSimulation { status: CheckStatus::Passed, detail: format!("{} compute units", units) },
+        Ok(None) => DryRunSimulation { status: CheckStatus::Passed, detail: "без ошибок".to_string() },
+        Err(e) => DryRunSimulation::failed(format!("{:#}", e)),
+    };
+    result
+}
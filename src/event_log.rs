@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use crate::history::TradeRecord;
+use crate::receipt::ExecutionReceipt;
+use crate::signal::Signal;
+
+/// Сколько последних событий хранится для догрузки после переподключения
+pub const EVENT_LOG_CAPACITY: usize = 1000;
+/// Ёмкость канала подписчиков (медленные подписчики пропускают старые события)
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Событие бота для клиентов WebSocket
+#[derive(Debug, Clone)]
+pub enum BotEvent {
+    /// Найденная возможность
+    Opportunity(Signal),
+    /// Этап исполнения сделки
+    Execution(ExecutionReceipt),
+    /// Записанная сделка
+    Trade(TradeRecord),
+    /// Смена конфигурации, режима работы или состояния защитных механизмов
+    Safety(SafetyEvent),
+}
+
+/// Переход режима работы бота: по этим событиям панели показывают,
+/// торгует ли бот на самом деле, а не только сделки и метрики
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SafetyEvent {
+    /// Конфигурация перечитана с диска
+    ConfigReloaded {
+        /// Изменившиеся секции конфигурации
+        changed: Vec<String>,
+        /// Секции, изменения которых уже применены (остальные — после перезапуска)
+        applied: Vec<String>,
+    },
+    /// DEX отключён (benched = true) или снова включён
+    VenueBenched {
+        venue: String,
+        benched: bool,
+        reason: String,
+    },
+    /// Автомат сбоев RPC разомкнут: исполнение приостановлено до `until`
+    CircuitBreakerTripped {
+        until: DateTime<Utc>,
+        reason: String,
+    },
+    /// Исполнение остановлено (engaged = true) или остановка снята
+    KillSwitch {
+        engaged: bool,
+        reason: String,
+    },
+    /// Режим симуляции или продакшн; effective = false — применится после перезапуска
+    ModeChanged {
+        simulation_mode: bool,
+        effective: bool,
+    },
+}
+
+/// Событие с порядковым номером
+#[derive(Debug, Clone)]
+pub struct SequencedEvent {
+    /// Номер события: растёт на 1 с каждым событием, начиная с 1
+    pub seq: u64,
+    /// Время публикации по часам сервера
+    pub server_time: DateTime<Utc>,
+    pub event: BotEvent,
+}
+
+struct EventLogInner {
+    last_seq: u64,
+    recent: VecDeque<SequencedEvent>,
+}
+
+/// Журнал событий: сквозная нумерация, последние события для догрузки
+/// и рассылка подписчикам WebSocket в порядке номеров
+#[derive(Clone)]
+pub struct EventLog {
+    tx: broadcast::Sender<SequencedEvent>,
+    inner: Arc<Mutex<EventLogInner>>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            inner: Arc::new(Mutex::new(EventLogInner {
+                last_seq: 0,
+                recent: VecDeque::with_capacity(EVENT_LOG_CAPACITY),
+            })),
+        }
+    }
+
+    /// Публикация события, возвращает присвоенный номер
+    pub fn publish(&self, event: BotEvent) -> u64 {
+        let mut inner = self.inner.lock().expect("мьютекс журнала событий отравлен");
+        inner.last_seq += 1;
+        let sequenced = SequencedEvent { seq: inner.last_seq, server_time: Utc::now(), event };
+        if inner.recent.len() == EVENT_LOG_CAPACITY {
+            inner.recent.pop_front();
+        }
+        inner.recent.push_back(sequenced.clone());
+        // Отправка под блокировкой сохраняет порядок номеров в канале;
+        // ошибка означает только отсутствие подписчиков
+        let _ = self.tx.send(sequenced);
+        inner.last_seq
+    }
+
+    /// Подписка на новые события
+    pub fn subscribe(&self) -> broadcast::Receiver<SequencedEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Номер последнего события (0 — событий ещё не было)
+    pub fn last_seq(&self) -> u64 {
+        self.inner.lock().expect("мьютекс журнала событий отравлен").last_seq
+    }
+
+    /// События с номером больше `since_seq`, старые первыми (не больше `limit`)
+    pub fn since(&self, since_seq: u64, limit: usize) -> Replay {
+        let inner = self.inner.lock().expect("мьютекс журнала событий отравлен");
+        let oldest_seq = inner.recent.front().map(|event| event.seq);
+        let events: Vec<SequencedEvent> = inner.recent.iter()
+            .filter(|event| event.seq > since_seq)
+            .take(limit)
+            .cloned()
+            .collect();
+        let has_more = events.last().is_some_and(|event| event.seq < inner.last_seq);
+        Replay {
+            // Часть событий после since_seq уже вытеснена из журнала
+            gap: oldest_seq.is_some_and(|oldest| oldest > since_seq + 1),
+            has_more,
+            last_seq: inner.last_seq,
+            events,
+        }
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Результат догрузки событий
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub events: Vec<SequencedEvent>,
+    /// Номер последнего опубликованного события
+    pub last_seq: u64,
+    /// Часть пропущенных событий уже недоступна — клиенту нужно обновить состояние целиком
+    pub gap: bool,
+    /// Есть ещё события после последнего возвращённого (догрузка частями)
+    pub has_more: bool,
+}
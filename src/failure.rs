@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use solana_client::client_error::ClientError;
+use std::fmt;
+use std::time::Duration;
+use crate::clock::to_chrono;
+use crate::tx_error::DecodedTxError;
+
+/// Категория неудачной сделки
+///
+/// От категории зависит реакция движка: к лимиту max_consecutive_failures
+/// относятся только неудачи исполнения.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// Качество исполнения: проскальзывание, пустой пул, проигранная гонка
+    Execution,
+    /// Баланс или конфигурация: повтор не поможет, исполнение останавливается сразу
+    Fatal,
+    /// Временный сбой RPC или таймаут: размыкает автомат, но не останавливает бота
+    Transient,
+    /// Сделка отменена до отправки (симуляция не прошла): не учитывается ни в лимите неудач, ни в автомате
+    Aborted,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Execution => "execution",
+            FailureKind::Fatal => "fatal",
+            FailureKind::Transient => "transient",
+            FailureKind::Aborted => "aborted",
+        }
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Позволяет явно пометить ошибку: `.context(FailureKind::Fatal)`
+impl std::error::Error for FailureKind {}
+
+/// Категория ошибки исполнения сделки
+///
+/// Порядок: явная пометка `FailureKind` в цепочке, категория расшифрованной
+/// ошибки транзакции, сетевые ошибки RPC и таймауты. Остальные ошибки считаются
+/// неудачами исполнения, как и раньше.
+pub fn classify_failure(error: &anyhow::Error) -> FailureKind {
+    if let Some(kind) = error.downcast_ref::<FailureKind>() {
+        return *kind;
+    }
+    if let Some(decoded) = error.chain().find_map(|cause| cause.downcast_ref::<DecodedTxError>()) {
+        return decoded.kind;
+    }
+    let transient = error.chain().any(|cause| {
+        cause.is::<ClientError>()
+            || cause.is::<tokio::time::error::Elapsed>()
+            || cause.is::<reqwest::Error>()
+            || cause.is::<std::io::Error>()
+    });
+    if transient {
+        FailureKind::Transient
+    } else {
+        FailureKind::Execution
+    }
+}
+
+/// Автомат для временных сбоев RPC
+///
+/// После `threshold` временных сбоев подряд исполнение приостанавливается на
+/// `cooldown`. Любой другой результат сделки сбрасывает счётчик.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    consecutive: u32,
+    open_until: Option<DateTime<Utc>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            consecutive: 0,
+            open_until: None,
+        }
+    }
+
+    /// Разомкнут ли автомат (исполнение приостановлено)
+    pub fn is_open(&self, now: DateTime<Utc>) -> bool {
+        self.open_until.is_some_and(|until| now < until)
+    }
+
+    /// Время, до которого приостановлено исполнение
+    pub fn open_until(&self) -> Option<DateTime<Utc>> {
+        self.open_until
+    }
+
+    /// Учёт временного сбоя; возвращает true, если автомат только что разомкнулся
+    pub fn record_failure(&mut self, now: DateTime<Utc>) -> bool {
+        self.consecutive += 1;
+        if self.threshold == 0 || self.consecutive < self.threshold {
+            return false;
+        }
+        self.consecutive = 0;
+        self.open_until = Some(now + to_chrono(self.cooldown));
+        true
+    }
+
+    /// Сброс после сделки без временного сбоя
+    pub fn reset(&mut self) {
+        self.consecutive = 0;
+    }
+}
@@ -0,0 +1,298 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{
+    UiLoadedAddresses, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use crate::config::NetworkConfig;
+use crate::history::TradeRecord;
+use crate::rpc::{create_rpc_client, simulation_network};
+use crate::store::Store;
+use crate::units::lamports_to_sol;
+
+/// Коллекция хранилища с расходами по транзакциям
+pub const FEES_COLLECTION: &str = "fees";
+
+/// Базовая комиссия за одну подпись, в lamports
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Аккаунты для чаевых Jito (mainnet)
+const JITO_TIP_ACCOUNTS: &[&str] = &[
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// Изменение баланса аккаунта транзакцией
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountDelta {
+    pub address: Pubkey,
+    /// Баланс до транзакции, lamports
+    pub pre: u64,
+    /// Баланс после транзакции, lamports
+    pub post: u64,
+    /// Токен аккаунт (есть в post_token_balances)
+    pub token_account: bool,
+    /// Обёрнутый SOL на токен аккаунте после транзакции (у закрытого — до неё; не рента), lamports
+    pub wrapped_lamports: u64,
+    /// Токен аккаунт закрыт транзакцией (был в pre_token_balances, баланс после — 0)
+    pub closed: bool,
+}
+
+/// Расходы кошелька на одну транзакцию, в lamports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBreakdown {
+    /// Базовая комиссия за подписи
+    pub base_fee: u64,
+    /// Приоритетная комиссия (compute unit price)
+    pub priority_fee: u64,
+    /// Рента за созданные токен аккаунты (ATA)
+    pub ata_rent: u64,
+    /// Чаевые Jito
+    pub jito_tip: u64,
+    /// Рента, возвращённая закрытием токен аккаунтов (доход, не расход)
+    #[serde(default)]
+    pub rent_reclaimed: u64,
+}
+
+/// Расходы транзакции по комиссии из meta и изменениям балансов аккаунтов
+///
+/// Комиссия делится на базовую (по числу подписей) и приоритетную (остаток).
+/// Рента — баланс токен аккаунтов, которых до транзакции не было (без обёрнутого SOL),
+/// чаевые — прирост балансов аккаунтов Jito. Возвращённая рента — баланс до
+/// транзакции закрытых ею токен аккаунтов (без обёрнутого SOL); временный ATA
+/// WSOL, созданный и закрытый одной транзакцией, ни рентой, ни возвратом не считается.
+pub fn transaction_costs(fee: u64, signatures: u64, accounts: &[AccountDelta]) -> FeeBreakdown {
+    let base_fee = fee.min(signatures * LAMPORTS_PER_SIGNATURE);
+    let tip_accounts: Vec<Pubkey> = JITO_TIP_ACCOUNTS.iter()
+        .filter_map(|address| Pubkey::from_str(address).ok())
+        .collect();
+
+    let mut costs = FeeBreakdown {
+        base_fee,
+        priority_fee: fee - base_fee,
+        ..FeeBreakdown::default()
+    };
+    for account in accounts {
+        if account.token_account && account.pre == 0 {
+            costs.ata_rent += account.post.saturating_sub(account.wrapped_lamports);
+        }
+        if account.closed {
+            costs.rent_reclaimed += account.pre.saturating_sub(account.wrapped_lamports);
+        }
+        if tip_accounts.contains(&account.address) {
+            costs.jito_tip += account.post.saturating_sub(account.pre);
+        }
+    }
+    costs
+}
+
+/// Расходы по одной транзакции (запись журнала)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeRecord {
+    pub timestamp: DateTime<Utc>,
+    pub signature: String,
+    #[serde(flatten)]
+    pub costs: FeeBreakdown,
+}
+
+/// Накопленные расходы кошелька, в lamports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct FeeTotals {
+    /// Учтённые транзакции
+    pub transactions: u64,
+    pub base_fee: u64,
+    pub priority_fee: u64,
+    pub ata_rent: u64,
+    pub jito_tip: u64,
+    /// Рента, возвращённая закрытием токен аккаунтов (в расходы не входит)
+    pub rent_reclaimed: u64,
+}
+
+impl FeeTotals {
+    pub fn add(&mut self, costs: &FeeBreakdown) {
+        self.transactions += 1;
+        self.base_fee += costs.base_fee;
+        self.priority_fee += costs.priority_fee;
+        self.ata_rent += costs.ata_rent;
+        self.jito_tip += costs.jito_tip;
+        self.rent_reclaimed += costs.rent_reclaimed;
+    }
+
+    /// Все расходы, в lamports
+    pub fn total_lamports(&self) -> u64 {
+        self.base_fee + self.priority_fee + self.ata_rent + self.jito_tip
+    }
+
+    /// Все расходы, в SOL
+    pub fn total_sol(&self) -> Decimal {
+        lamports_to_sol(self.total_lamports())
+    }
+
+    /// Возвращённая рента, в SOL
+    pub fn rent_reclaimed_sol(&self) -> Decimal {
+        lamports_to_sol(self.rent_reclaimed)
+    }
+}
+
+/// Чистая прибыль в SOL: прибыль успешных сделок за вычетом расходов кошелька
+/// и с возвращённой рентой закрытых токен аккаунтов
+///
+/// Сделки без пересчёта прибыли в SOL не учитываются.
+pub fn net_profit_sol(trades: &[TradeRecord], fees: &FeeTotals) -> Decimal {
+    let gross: Decimal = trades.iter()
+        .filter(|trade| trade.status == crate::history::TradeStatus::Success)
+        .filter_map(|trade| trade.profit_sol)
+        .sum();
+    gross - fees.total_sol() + fees.rent_reclaimed_sol()
+}
+
+/// Учёт расходов кошелька на транзакции
+///
+/// Расходы берутся из подтверждённой транзакции (getTransaction), поэтому
+/// учитываются и транзакции, исполненные с ошибкой: комиссия за них списана.
+#[derive(Clone, Default)]
+pub struct FeeLedger {
+    totals: Arc<Mutex<FeeTotals>>,
+    store: Option<Store>,
+}
+
+impl FeeLedger {
+    /// Подключение хранилища: расходы сохраняются на диск
+    pub fn with_store(mut self, store: Store) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// Восстановление накопленных расходов из хранилища
+    pub async fn restore(&self) -> Result<usize> {
+        let Some(ref store) = self.store else { return Ok(0) };
+        let records: Vec<FeeRecord> = store.read_all(FEES_COLLECTION).await?;
+        let mut totals = FeeTotals::default();
+        for record in &records {
+            totals.add(&record.costs);
+        }
+        *self.totals.lock().expect("мьютекс учёта комиссий отравлен") = totals;
+        Ok(records.len())
+    }
+
+    /// Накопленные расходы
+    pub fn totals(&self) -> FeeTotals {
+        *self.totals.lock().expect("мьютекс учёта комиссий отравлен")
+    }
+
+    /// Учёт расходов транзакции
+    pub async fn record(&self, signature: &str, costs: FeeBreakdown) {
+        self.totals.lock().expect("мьютекс учёта комиссий отравлен").add(&costs);
+        if let Some(ref store) = self.store {
+            let record = FeeRecord { timestamp: Utc::now(), signature: signature.to_string(), costs };
+            if let Err(e) = store.append(FEES_COLLECTION, &record).await {
+                log::warn!("Не удалось сохранить расходы транзакции {}: {}", signature, e);
+            }
+        }
+    }
+
+    /// Получение расходов подтверждённой транзакции и их учёт
+    pub async fn record_transaction(&self, network: &NetworkConfig, signature: &str) {
+        match fetch_transaction_costs(network, signature).await {
+            Ok(Some(costs)) => {
+                log::debug!("Расходы транзакции {}: {:?}", signature, costs);
+                self.record(signature, costs).await;
+            }
+            Ok(None) => log::warn!("Транзакция {} не найдена, расходы не учтены", signature),
+            Err(e) => log::warn!("Не удалось учесть расходы транзакции {}: {:#}", signature, e),
+        }
+    }
+
+    /// Учёт расходов подтверждённой транзакции в фоне
+    pub fn spawn_record(&self, network: &NetworkConfig, signature: String) {
+        let ledger = self.clone();
+        let network = network.clone();
+        tokio::spawn(async move {
+            ledger.record_transaction(&network, &signature).await;
+        });
+    }
+}
+
+/// Расходы транзакции по данным кластера (None — транзакция не найдена)
+pub async fn fetch_transaction_costs(network: &NetworkConfig, signature: &str) -> Result<Option<FeeBreakdown>> {
+    let signature = Signature::from_str(signature).context("Некорректная подпись транзакции")?;
+    // Учёт после подтверждения — аналитика, а не чтение для сделки
+    let network = simulation_network(network);
+    tokio::task::spawn_blocking(move || -> Result<_> {
+        let client = create_rpc_client(&network)?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let confirmed = match client.get_transaction_with_config(&signature, config) {
+            Ok(confirmed) => confirmed,
+            Err(e) if e.to_string().contains("invalid type: null") => return Ok(None),
+            Err(e) => return Err(e).context("Не удалось получить транзакцию"),
+        };
+        let transaction = confirmed.transaction.transaction.decode()
+            .context("Не удалось декодировать транзакцию")?;
+        let Some(meta) = confirmed.transaction.meta else { return Ok(None) };
+
+        let fee = meta.fee;
+        let accounts = account_deltas(transaction.message.static_account_keys(), meta)?;
+        Ok(Some(transaction_costs(fee, transaction.signatures.len() as u64, &accounts)))
+    })
+    .await
+    .context("Задача получения транзакции завершилась с ошибкой")?
+}
+
+/// Балансы аккаунтов транзакции: статические ключи, затем загруженные из ALT
+fn account_deltas(static_keys: &[Pubkey], meta: UiTransactionStatusMeta) -> Result<Vec<AccountDelta>> {
+    let mut keys = static_keys.to_vec();
+    if let Some(loaded) = Option::<UiLoadedAddresses>::from(meta.loaded_addresses) {
+        for address in loaded.writable.iter().chain(&loaded.readonly) {
+            keys.push(Pubkey::from_str(address).context("Некорректный адрес из lookup table")?);
+        }
+    }
+
+    let token_balances: Vec<UiTransactionTokenBalance> = Option::from(meta.post_token_balances).unwrap_or_default();
+    let pre_token_balances: Vec<UiTransactionTokenBalance> = Option::from(meta.pre_token_balances).unwrap_or_default();
+    let native_mint = spl_token::native_mint::id().to_string();
+    let wrapped = |balance: &UiTransactionTokenBalance| {
+        if balance.mint == native_mint {
+            balance.ui_token_amount.amount.parse().unwrap_or(0)
+        } else {
+            0
+        }
+    };
+    let deltas = keys.iter()
+        .zip(meta.pre_balances.iter().zip(&meta.post_balances))
+        .enumerate()
+        .map(|(index, (address, (&pre, &post)))| {
+            let token = token_balances.iter()
+                .find(|balance| balance.account_index as usize == index);
+            // Закрытый аккаунт есть только в pre_token_balances, и его баланс обнулён
+            let closed = token.is_none() && post == 0 && pre > 0;
+            let pre_token = pre_token_balances.iter()
+                .find(|balance| closed && balance.account_index as usize == index);
+            AccountDelta {
+                address: *address,
+                pre,
+                post,
+                token_account: token.is_some(),
+                wrapped_lamports: token.or(pre_token).map(wrapped).unwrap_or(0),
+                closed: pre_token.is_some(),
+            }
+        })
+        .collect();
+    Ok(deltas)
+}
@@ -0,0 +1,421 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use futures_util::future::join_all;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use crate::clock::to_chrono;
+use crate::clock_skew::ClockSkew;
+use crate::config::{FiatConfig, ProxyConfig};
+use crate::proxy::{self, ProxyEndpoint};
+
+/// Таймаут запросов к API курсов
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Стейблкоины, курс которых принимается равным 1 USD без запроса к API
+pub const USD_STABLECOINS: &[&str] = &["USDC", "USDT"];
+
+/// Известные источники курсов (значения `fiat.providers`)
+pub const FIAT_PROVIDERS: &[&str] = &["pyth", "coingecko", "binance"];
+
+/// Цена от одного источника
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProviderQuote {
+    /// Цена одного токена в USD
+    pub price: Decimal,
+    /// Время, на которое источник рассчитал цену
+    pub published_at: DateTime<Utc>,
+}
+
+/// Курс токена в USD
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsdRate {
+    /// Цена одного токена в USD
+    pub price: Decimal,
+    /// Источник курса
+    pub source: String,
+    /// Время, на которое источник рассчитал цену
+    pub published_at: DateTime<Utc>,
+    /// Время получения курса
+    pub fetched_at: DateTime<Utc>,
+    /// Источники, цена которых совпала с выбранной в пределах max_deviation_percent
+    pub confirmed_by: Vec<String>,
+}
+
+impl UsdRate {
+    /// Возраст курса в секундах
+    pub fn age_sec(&self, now: DateTime<Utc>) -> i64 {
+        (now - self.published_at).num_seconds().max(0)
+    }
+}
+
+/// Источник курсов токенов в USD
+#[async_trait::async_trait]
+pub trait FiatProvider: Send + Sync {
+    /// Название источника для логов и ответов API
+    fn name(&self) -> &str;
+
+    /// Цена токена в USD
+    async fn usd_price(&self, symbol: &str) -> Result<ProviderQuote>;
+}
+
+/// HTTP клиент внешнего API (прокси `network.proxy.http`)
+pub(crate) fn http_client(provider: &str, proxy: &ProxyConfig) -> Result<reqwest::Client> {
+    let builder = reqwest::Client::builder().timeout(HTTP_TIMEOUT);
+    proxy::configure(builder, proxy, ProxyEndpoint::Http)?
+        .build()
+        .with_context(|| format!("Не удалось создать HTTP клиент для {}", provider))
+}
+
+pub(crate) async fn get_json(client: &reqwest::Client, url: &str, provider: &str) -> Result<serde_json::Value> {
+    client.get(url)
+        .send()
+        .await
+        .with_context(|| format!("Ошибка запроса к {}", provider))?
+        .error_for_status()
+        .with_context(|| format!("{} вернул ошибку", provider))?
+        .json()
+        .await
+        .with_context(|| format!("Некорректный ответ {}", provider))
+}
+
+/// Разбор цены из JSON (число или строка) без промежуточного f64
+pub(crate) fn parse_price(value: &serde_json::Value) -> Result<Decimal> {
+    let text = match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Number(number) => number.to_string(),
+        _ => anyhow::bail!("Цена отсутствует в ответе"),
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .with_context(|| format!("Некорректная цена: {}", text))
+}
+
+fn from_unix(seconds: i64) -> Result<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .with_context(|| format!("Некорректное время публикации: {}", seconds))
+}
+
+/// Символ токена без обёртки (WSOL торгуется как SOL)
+fn unwrapped(symbol: &str) -> &str {
+    match symbol {
+        "WSOL" => "SOL",
+        other => other,
+    }
+}
+
+/// CoinGecko simple/price API
+pub struct CoinGeckoProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl CoinGeckoProvider {
+    pub fn new(base_url: &str, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self { client: http_client("CoinGecko", proxy)?, base_url: base_url.trim_end_matches('/').to_string() })
+    }
+}
+
+/// Идентификатор токена в CoinGecko
+fn coingecko_id(symbol: &str) -> Option<&'static str> {
+    Some(match unwrapped(symbol) {
+        "SOL" => "solana",
+        "USDC" => "usd-coin",
+        "USDT" => "tether",
+        "RAY" => "raydium",
+        "ORCA" => "orca",
+        "SRM" => "serum",
+        "BONK" => "bonk",
+        "JUP" => "jupiter-exchange-solana",
+        "MSOL" | "mSOL" => "msol",
+        _ => return None,
+    })
+}
+
+#[async_trait::async_trait]
+impl FiatProvider for CoinGeckoProvider {
+    fn name(&self) -> &str {
+        "coingecko"
+    }
+
+    async fn usd_price(&self, symbol: &str) -> Result<ProviderQuote> {
+        let id = coingecko_id(symbol)
+            .with_context(|| format!("Токен {} неизвестен CoinGecko", symbol))?;
+        let url = format!(
+            "{}/simple/price?ids={}&vs_currencies=usd&include_last_updated_at=true",
+            self.base_url, id
+        );
+        let body = get_json(&self.client, &url, "CoinGecko").await?;
+        let price = parse_price(&body[id]["usd"])
+            .with_context(|| format!("CoinGecko: цена {}", id))?;
+        let published_at = match body[id]["last_updated_at"].as_i64() {
+            Some(seconds) => from_unix(seconds)?,
+            None => Utc::now(),
+        };
+        Ok(ProviderQuote { price, published_at })
+    }
+}
+
+/// Binance spot ticker API (курс к USDT, USDT принимается равным 1 USD)
+pub struct BinanceProvider {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl BinanceProvider {
+    pub fn new(base_url: &str, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self { client: http_client("Binance", proxy)?, base_url: base_url.trim_end_matches('/').to_string() })
+    }
+}
+
+#[async_trait::async_trait]
+impl FiatProvider for BinanceProvider {
+    fn name(&self) -> &str {
+        "binance"
+    }
+
+    async fn usd_price(&self, symbol: &str) -> Result<ProviderQuote> {
+        let market = format!("{}USDT", unwrapped(symbol).to_uppercase());
+        let url = format!("{}/api/v3/ticker/price?symbol={}", self.base_url, market);
+        let body = get_json(&self.client, &url, "Binance").await?;
+        let price = parse_price(&body["price"])
+            .with_context(|| format!("Binance: цена {}", market))?;
+        // Тикер отдаёт цену последней сделки без времени — считаем её текущей
+        Ok(ProviderQuote { price, published_at: Utc::now() })
+    }
+}
+
+/// Pyth Hermes API (цены оракула с временем публикации)
+pub struct PythProvider {
+    client: reqwest::Client,
+    base_url: String,
+    /// Идентификаторы price feed по символу токена
+    feeds: HashMap<String, String>,
+}
+
+impl PythProvider {
+    pub fn new(base_url: &str, feeds: &HashMap<String, String>, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client("Pyth", proxy)?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            feeds: feeds.clone(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl FiatProvider for PythProvider {
+    fn name(&self) -> &str {
+        "pyth"
+    }
+
+    async fn usd_price(&self, symbol: &str) -> Result<ProviderQuote> {
+        let feed = self.feeds.get(unwrapped(symbol))
+            .with_context(|| format!("Для {} не задан price feed Pyth (fiat.pyth_feeds)", symbol))?;
+        let url = format!(
+            "{}/v2/updates/price/latest?ids[]={}&parsed=true",
+            self.base_url, feed.trim_start_matches("0x")
+        );
+        let body = get_json(&self.client, &url, "Pyth").await?;
+        let price = &body["parsed"][0]["price"];
+
+        let mantissa: i64 = price["price"].as_str()
+            .and_then(|text| text.parse().ok())
+            .with_context(|| format!("Pyth: нет цены в feed {}", feed))?;
+        let expo = price["expo"].as_i64()
+            .with_context(|| format!("Pyth: нет экспоненты в feed {}", feed))?;
+        let value = if expo <= 0 {
+            Decimal::try_from_i128_with_scale(mantissa as i128, (-expo) as u32)
+                .with_context(|| format!("Pyth: некорректная цена {}e{}", mantissa, expo))?
+        } else {
+            Decimal::from(mantissa) * Decimal::from(10i64.pow(expo as u32))
+        };
+        let published_at = from_unix(
+            price["publish_time"].as_i64()
+                .with_context(|| format!("Pyth: нет времени публикации в feed {}", feed))?,
+        )?;
+        Ok(ProviderQuote { price: value.normalize(), published_at })
+    }
+}
+
+/// Создание источника по имени из конфигурации
+fn build_provider(name: &str, config: &FiatConfig, proxy: &ProxyConfig) -> Result<Arc<dyn FiatProvider>> {
+    Ok(match name {
+        "pyth" => Arc::new(PythProvider::new(&config.pyth_url, &config.pyth_feeds, proxy)?),
+        "coingecko" => Arc::new(CoinGeckoProvider::new(&config.coingecko_url, proxy)?),
+        "binance" => Arc::new(BinanceProvider::new(&config.binance_url, proxy)?),
+        other => anyhow::bail!("Неизвестный источник курсов: {}", other),
+    })
+}
+
+/// Курсы токенов в USD из нескольких источников с кешированием
+///
+/// Цена запрашивается у всех источников сразу. Выбирается первая по порядку
+/// `fiat.providers` цена, которую подтвердил хотя бы один другой источник
+/// (расхождение не больше `max_deviation_percent`). Если ответил только один
+/// источник, его цена используется без подтверждения; если источники
+/// расходятся, курс считается неизвестным. Цены старше `max_age_sec`
+/// отбрасываются. Стейблкоины из `USD_STABLECOINS` считаются равными 1 USD.
+#[derive(Clone)]
+pub struct FiatRates {
+    providers: Vec<Arc<dyn FiatProvider>>,
+    cache: Arc<Mutex<HashMap<String, UsdRate>>>,
+    ttl: Duration,
+    max_age: Duration,
+    max_deviation_percent: Decimal,
+    /// Время кластера для проверки возраста цен
+    clock_skew: ClockSkew,
+}
+
+impl FiatRates {
+    /// Создание по конфигурации (без источников, если курсы отключены)
+    pub fn new(config: &FiatConfig, proxy: &ProxyConfig) -> Self {
+        let providers = if config.enabled {
+            config.providers.iter()
+                .filter_map(|name| match build_provider(name, config, proxy) {
+                    Ok(provider) => Some(provider),
+                    Err(e) => {
+                        log::warn!("Источник курсов {} недоступен: {:#}", name, e);
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self::with_providers(providers, config)
+    }
+
+    /// Создание с указанными источниками (порядок — приоритет)
+    pub fn with_providers(providers: Vec<Arc<dyn FiatProvider>>, config: &FiatConfig) -> Self {
+        Self {
+            providers,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            ttl: Duration::from_secs(config.cache_ttl_sec),
+            max_age: Duration::from_secs(config.max_age_sec),
+            max_deviation_percent: Decimal::from_f64_retain(config.max_deviation_percent)
+                .unwrap_or(Decimal::ONE),
+            clock_skew: ClockSkew::new(),
+        }
+    }
+
+    /// Проверка возраста цен по времени кластера с учётом расхождения часов
+    pub fn with_clock_skew(mut self, clock_skew: ClockSkew) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// Курс токена в USD (None, если неизвестен)
+    pub async fn usd_rate(&self, symbol: &str) -> Option<UsdRate> {
+        let now = Utc::now();
+        if USD_STABLECOINS.contains(&symbol) {
+            return Some(UsdRate {
+                price: Decimal::ONE,
+                source: "peg".to_string(),
+                published_at: now,
+                fetched_at: now,
+                confirmed_by: Vec::new(),
+            });
+        }
+        if self.providers.is_empty() {
+            return None;
+        }
+
+        let mut cache = self.cache.lock().await;
+        if let Some(rate) = cache.get(symbol) {
+            if now - rate.fetched_at < to_chrono(self.ttl) {
+                return Some(rate.clone());
+            }
+        }
+
+        let quotes = join_all(self.providers.iter().map(|provider| provider.usd_price(symbol))).await;
+        // Время публикации задаёт источник, поэтому возраст считается по часам кластера
+        let cluster_now = self.clock_skew.cluster_now();
+        let fresh: Vec<(&str, ProviderQuote)> = self.providers.iter()
+            .zip(quotes)
+            .filter_map(|(provider, quote)| match quote {
+                Ok(quote) if cluster_now - quote.published_at <= to_chrono(self.max_age) => Some((provider.name(), quote)),
+                Ok(quote) => {
+                    log::debug!(
+                        "Курс {}/USD от {} устарел (опубликован {})",
+                        symbol, provider.name(), quote.published_at
+                    );
+                    None
+                }
+                Err(e) => {
+                    log::debug!("Курс {}/USD от {} недоступен: {:#}", symbol, provider.name(), e);
+                    None
+                }
+            })
+            .collect();
+
+        let rate = self.select(symbol, &fresh, now)?;
+        cache.insert(symbol.to_string(), rate.clone());
+        Some(rate)
+    }
+
+    /// Курсы в кеше (с источником и временем публикации)
+    pub async fn cached_rates(&self) -> Vec<(String, UsdRate)> {
+        let mut rates: Vec<(String, UsdRate)> = self.cache.lock().await
+            .iter()
+            .map(|(symbol, rate)| (symbol.clone(), rate.clone()))
+            .collect();
+        rates.sort_by(|a, b| a.0.cmp(&b.0));
+        rates
+    }
+
+    /// Выбор цены с перекрёстной проверкой источников
+    fn select(&self, symbol: &str, quotes: &[(&str, ProviderQuote)], now: DateTime<Utc>) -> Option<UsdRate> {
+        let rate = |(source, quote): &(&str, ProviderQuote), confirmed_by: Vec<String>| UsdRate {
+            price: quote.price,
+            source: source.to_string(),
+            published_at: quote.published_at,
+            fetched_at: now,
+            confirmed_by,
+        };
+
+        match quotes {
+            [] => {
+                log::warn!("Не удалось получить курс {}/USD ни от одного источника", symbol);
+                None
+            }
+            [single] => {
+                if self.providers.len() > 1 {
+                    log::warn!("Курс {}/USD получен только от {} — без перекрёстной проверки", symbol, single.0);
+                }
+                Some(rate(single, Vec::new()))
+            }
+            _ => {
+                for candidate in quotes {
+                    let confirmed_by: Vec<String> = quotes.iter()
+                        .filter(|other| other.0 != candidate.0 && self.agrees(candidate.1.price, other.1.price))
+                        .map(|other| other.0.to_string())
+                        .collect();
+                    if !confirmed_by.is_empty() {
+                        return Some(rate(candidate, confirmed_by));
+                    }
+                }
+                let prices: Vec<String> = quotes.iter()
+                    .map(|(source, quote)| format!("{}={}", source, quote.price))
+                    .collect();
+                log::warn!(
+                    "Источники курса {}/USD расходятся больше чем на {}% ({}) — курс не используется",
+                    symbol, self.max_deviation_percent, prices.join(", ")
+                );
+                None
+            }
+        }
+    }
+
+    fn agrees(&self, a: Decimal, b: Decimal) -> bool {
+        if a.is_zero() {
+            return b.is_zero();
+        }
+        ((a - b).abs() / a * Decimal::from(100)) <= self.max_deviation_percent
+    }
+}
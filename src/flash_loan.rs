@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use solana_sdk::{
+    hash::hashv,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::str::FromStr;
+use crate::config::FlashLoanReserveConfig;
+use crate::units::{to_base_units, Rounding};
+
+/// Kamino Lending (klend) Program ID
+pub const KAMINO_LENDING_PROGRAM_ID: &str = "KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD";
+
+/// Seed PDA полномочий лендингового рынка Kamino
+const LENDING_MARKET_AUTHORITY_SEED: &[u8] = b"lma";
+
+/// Discriminator инструкции Anchor: первые 8 байт sha256("global:<имя>")
+fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let hash = hashv(&[format!("global:{}", name).as_bytes()]);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+fn parse_pubkey(value: &str, field: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).with_context(|| format!("Некорректный адрес {}: {}", field, value))
+}
+
+/// Флеш-займ quote токена в резерве Kamino Lending
+///
+/// Заём берётся и возвращается в одной транзакции: программа проверяет, что
+/// после инструкции займа в транзакции есть возврат той же суммы с комиссией.
+/// Если свопы не вернули достаточно quote токена, возврат падает и откатывается
+/// вся транзакция — объём сделки не ограничен балансом кошелька.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashLoan {
+    pub program_id: Pubkey,
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    /// Mint занимаемого токена
+    pub liquidity_mint: Pubkey,
+    /// Хранилище ликвидности резерва
+    pub liquidity_supply: Pubkey,
+    /// Получатель комиссии флеш-займа
+    pub fee_receiver: Pubkey,
+    /// Комиссия флеш-займа в процентах от суммы
+    pub fee_percent: Decimal,
+}
+
+impl FlashLoan {
+    /// Флеш-займ по настройкам резерва для токена с mint `liquidity_mint`
+    pub fn new(config: &FlashLoanReserveConfig, liquidity_mint: Pubkey) -> Result<Self> {
+        Ok(Self {
+            program_id: parse_pubkey(KAMINO_LENDING_PROGRAM_ID, "программы Kamino")?,
+            lending_market: parse_pubkey(&config.lending_market, "lending_market")?,
+            reserve: parse_pubkey(&config.reserve, "reserve")?,
+            liquidity_mint,
+            liquidity_supply: parse_pubkey(&config.liquidity_supply, "liquidity_supply")?,
+            fee_receiver: parse_pubkey(&config.fee_receiver, "fee_receiver")?,
+            fee_percent: config.fee_percent(),
+        })
+    }
+
+    /// PDA полномочий лендингового рынка
+    pub fn lending_market_authority(&self) -> Pubkey {
+        Pubkey::find_program_address(
+            &[LENDING_MARKET_AUTHORITY_SEED, self.lending_market.as_ref()],
+            &self.program_id,
+        ).0
+    }
+
+    /// Сумма возврата: заём и комиссия, округлённая вверх до минимальной единицы
+    pub fn repay_amount(&self, amount: u64) -> Result<u64> {
+        let fee = to_base_units(
+            Decimal::from(amount) * self.fee_percent / Decimal::from(100),
+            0,
+            Rounding::Up,
+        )?;
+        amount.checked_add(fee).context("Переполнение при расчёте суммы возврата флеш-займа")
+    }
+
+    /// Аккаунты инструкций займа и возврата (у Kamino порядок совпадает)
+    fn accounts(&self, owner: &Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(self.lending_market_authority(), false),
+            AccountMeta::new_readonly(self.lending_market, false),
+            AccountMeta::new(self.reserve, false),
+            AccountMeta::new_readonly(self.liquidity_mint, false),
+            AccountMeta::new(self.liquidity_supply, false),
+            AccountMeta::new(get_associated_token_address(owner, &self.liquidity_mint), false),
+            AccountMeta::new(self.fee_receiver, false),
+            // Реферер не используется: необязательные аккаунты Anchor передаются адресом программы
+            AccountMeta::new_readonly(self.program_id, false),
+            AccountMeta::new_readonly(self.program_id, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]
+    }
+
+    /// Инструкция займа `amount` минимальных единиц на ATA кошелька
+    pub fn borrow_instruction(&self, owner: &Pubkey, amount: u64) -> Instruction {
+        let mut data = anchor_discriminator("flash_borrow_reserve_liquidity").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        Instruction {
+            program_id: self.program_id,
+            accounts: self.accounts(owner),
+            data,
+        }
+    }
+
+    /// Инструкция возврата займа `amount` (без комиссии — её добавляет программа)
+    ///
+    /// `borrow_index` — индекс инструкции займа в транзакции.
+    pub fn repay_instruction(&self, owner: &Pubkey, amount: u64, borrow_index: u8) -> Instruction {
+        let mut data = anchor_discriminator("flash_repay_reserve_liquidity").to_vec();
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(borrow_index);
+        Instruction {
+            program_id: self.program_id,
+            accounts: self.accounts(owner),
+            data,
+        }
+    }
+
+    /// Свопы между займом и возвратом
+    ///
+    /// Первой идёт идемпотентное создание ATA занимаемого токена, затем заём,
+    /// свопы и возврат. Результат должен стоять в начале транзакции: возврат
+    /// ссылается на заём по индексу инструкции.
+    pub fn wrap(&self, owner: &Pubkey, amount: u64, swaps: Vec<Vec<Instruction>>) -> Vec<Vec<Instruction>> {
+        const BORROW_INDEX: u8 = 1;
+        let open = vec![
+            create_associated_token_account_idempotent(owner, owner, &self.liquidity_mint, &spl_token::id()),
+            self.borrow_instruction(owner, amount),
+        ];
+        let mut wrapped = Vec::with_capacity(swaps.len() + 2);
+        wrapped.push(open);
+        wrapped.extend(swaps);
+        wrapped.push(vec![self.repay_instruction(owner, amount, BORROW_INDEX)]);
+        wrapped
+    }
+}
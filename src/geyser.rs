@@ -0,0 +1,672 @@
+use anyhow::{Context, Result};
+use bytes::{Buf, Bytes, BytesMut};
+use h2::client::SendRequest;
+use h2::{RecvStream, SendStream};
+use http::{HeaderMap, HeaderValue, Request, Uri};
+use solana_client::client_error::Result as ClientResult;
+use solana_client::rpc_request::RpcRequest;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_rustls::{rustls, TlsConnector};
+use crate::config::{resolve_env_value, NetworkConfig};
+use crate::proxy::{self, proxy_url, ProxyEndpoint};
+use crate::rpc::{create_rpc_client, redact_url, AccountRead, MAX_MULTIPLE_ACCOUNTS};
+
+/// Метод подписки Yellowstone gRPC (двунаправленный поток)
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+/// Имя фильтра аккаунтов пулов в SubscribeRequest
+const ACCOUNTS_FILTER: &str = "pools";
+/// Имя фильтра слотов в SubscribeRequest
+const SLOTS_FILTER: &str = "slots";
+/// Идентификатор ping, которым клиент отвечает на ping узла
+const PING_ID: u64 = 1;
+/// Размер заголовка сообщения gRPC: флаг сжатия и длина
+const FRAME_HEADER_LEN: usize = 5;
+/// Предел размера одного сообщения (аккаунты пулов не больше 10 МБ)
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+/// Как часто проверять аккаунты, впервые прочитанные через RPC, для подписки
+const SUBSCRIBE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Соединение считается зависшим, если дольше нет сообщений (слоты приходят каждые ~400 мс)
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Пауза перед переподключением после обрыва
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Сообщение Yellowstone gRPC (SubscribeUpdate), значимое для подписок
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeyserEvent {
+    /// Новое состояние аккаунта (None — аккаунт удалён)
+    Account { address: Pubkey, slot: u64, account: Option<Account> },
+    /// Новый слот узла
+    Slot(u64),
+    /// Проверка соединения: узел ждёт ping в ответ
+    Ping,
+    /// Прочие сообщения
+    Other,
+}
+
+/// Запрос подписки (SubscribeRequest) на аккаунты `accounts` и слоты уровня `commitment`
+///
+/// Каждый запрос в потоке заменяет фильтры целиком. Пустой фильтр аккаунтов
+/// узел понял бы как подписку на все аккаунты, поэтому без адресов он не передаётся.
+pub fn encode_subscribe_request(accounts: &[Pubkey], commitment: &str) -> Result<Vec<u8>> {
+    let level = CommitmentConfig::from_str(commitment)
+        .map_err(|_| anyhow::anyhow!("Некорректный network.commitment: {}", commitment))?
+        .commitment;
+    let level = match level {
+        CommitmentLevel::Processed => 0,
+        CommitmentLevel::Confirmed => 1,
+        CommitmentLevel::Finalized => 2,
+        other => anyhow::bail!("Уровень {:?} не поддерживается Yellowstone gRPC", other),
+    };
+
+    let mut request = Vec::new();
+    if !accounts.is_empty() {
+        // map<string, SubscribeRequestFilterAccounts> accounts = 1; account = 2
+        let mut filter = Vec::new();
+        for address in accounts {
+            put_bytes(&mut filter, 2, address.to_string().as_bytes());
+        }
+        put_bytes(&mut request, 1, &map_entry(ACCOUNTS_FILTER, &filter));
+    }
+    // map<string, SubscribeRequestFilterSlots> slots = 2; filter_by_commitment = 1
+    let mut slots = Vec::new();
+    put_varint_field(&mut slots, 1, 1);
+    put_bytes(&mut request, 2, &map_entry(SLOTS_FILTER, &slots));
+    // CommitmentLevel commitment = 6
+    put_varint_field(&mut request, 6, level);
+    Ok(request)
+}
+
+/// Ответ на ping узла (SubscribeRequest с SubscribeRequestPing, фильтры не меняются)
+pub fn encode_ping() -> Vec<u8> {
+    let mut ping = Vec::new();
+    put_varint_field(&mut ping, 1, PING_ID);
+    let mut request = Vec::new();
+    put_bytes(&mut request, 9, &ping);
+    request
+}
+
+/// Разбор сообщения SubscribeUpdate
+pub fn parse_update(message: &[u8]) -> Result<GeyserEvent> {
+    let mut event = GeyserEvent::Other;
+    for field in Fields::new(message) {
+        match field? {
+            (2, Value::Bytes(account)) => event = parse_account_update(account)?,
+            (3, Value::Bytes(slot)) => {
+                let mut number = 0;
+                for field in Fields::new(slot) {
+                    if let (1, Value::Varint(value)) = field? {
+                        number = value;
+                    }
+                }
+                event = GeyserEvent::Slot(number);
+            }
+            (6, Value::Bytes(_)) => event = GeyserEvent::Ping,
+            _ => {}
+        }
+    }
+    Ok(event)
+}
+
+/// SubscribeUpdateAccount: аккаунт (SubscribeUpdateAccountInfo) и слот изменения
+fn parse_account_update(message: &[u8]) -> Result<GeyserEvent> {
+    let mut info = None;
+    let mut slot = None;
+    for field in Fields::new(message) {
+        match field? {
+            (1, Value::Bytes(bytes)) => info = Some(bytes),
+            (2, Value::Varint(value)) => slot = Some(value),
+            _ => {}
+        }
+    }
+    let info = info.context("В обновлении аккаунта нет данных аккаунта")?;
+    let slot = slot.context("В обновлении аккаунта нет слота")?;
+
+    let mut address = None;
+    let mut owner = None;
+    let mut account = Account::default();
+    for field in Fields::new(info) {
+        match field? {
+            (1, Value::Bytes(bytes)) => address = Some(pubkey(bytes).context("Некорректный адрес аккаунта")?),
+            (2, Value::Varint(value)) => account.lamports = value,
+            (3, Value::Bytes(bytes)) => owner = Some(pubkey(bytes).context("Некорректный владелец аккаунта")?),
+            (4, Value::Varint(value)) => account.executable = value != 0,
+            (5, Value::Varint(value)) => account.rent_epoch = value,
+            (6, Value::Bytes(bytes)) => account.data = bytes.to_vec(),
+            _ => {}
+        }
+    }
+    let address = address.context("В обновлении аккаунта нет адреса")?;
+    account.owner = owner.unwrap_or_default();
+
+    // Удалённый аккаунт приходит с нулевым балансом; getMultipleAccounts вернул бы null
+    let account = (account.lamports > 0).then_some(account);
+    Ok(GeyserEvent::Account { address, slot, account })
+}
+
+fn pubkey(bytes: &[u8]) -> Result<Pubkey> {
+    Pubkey::try_from(bytes).map_err(|_| anyhow::anyhow!("ожидалось 32 байта, получено {}", bytes.len()))
+}
+
+/// Сообщение gRPC без сжатия: флаг, длина (big endian) и тело
+pub fn frame(message: &[u8]) -> Result<Bytes> {
+    let len = u32::try_from(message.len()).context("Сообщение gRPC слишком большое")?;
+    let mut framed = BytesMut::with_capacity(FRAME_HEADER_LEN + message.len());
+    framed.extend_from_slice(&[0]);
+    framed.extend_from_slice(&len.to_be_bytes());
+    framed.extend_from_slice(message);
+    Ok(framed.freeze())
+}
+
+/// Сборка сообщений gRPC из фрагментов потока HTTP/2
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buffer: BytesMut,
+}
+
+impl FrameDecoder {
+    /// Добавление фрагмента тела ответа
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Следующее полностью полученное сообщение (None — нужно больше данных)
+    pub fn next_message(&mut self) -> Result<Option<Bytes>> {
+        if self.buffer.len() < FRAME_HEADER_LEN {
+            return Ok(None);
+        }
+        if self.buffer[0] != 0 {
+            anyhow::bail!("Узел прислал сжатое сообщение gRPC, сжатие не запрашивалось");
+        }
+        let len = u32::from_be_bytes([self.buffer[1], self.buffer[2], self.buffer[3], self.buffer[4]]) as usize;
+        if len > MAX_MESSAGE_LEN {
+            anyhow::bail!("Сообщение gRPC {} байт больше предела {}", len, MAX_MESSAGE_LEN);
+        }
+        if self.buffer.len() < FRAME_HEADER_LEN + len {
+            return Ok(None);
+        }
+        self.buffer.advance(FRAME_HEADER_LEN);
+        Ok(Some(self.buffer.split_to(len).freeze()))
+    }
+}
+
+/// Поток подписки Yellowstone gRPC (вызов Subscribe поверх HTTP/2)
+pub struct GeyserStream {
+    requests: SendStream<Bytes>,
+    responses: RecvStream,
+    decoder: FrameDecoder,
+    /// Задача соединения HTTP/2: останавливается вместе с потоком
+    connection: JoinHandle<()>,
+}
+
+impl Drop for GeyserStream {
+    fn drop(&mut self) {
+        self.connection.abort();
+    }
+}
+
+impl GeyserStream {
+    /// Подключение к network.geyser_url (x-token из geyser_token_env) и начальная подписка
+    pub async fn connect(network: &NetworkConfig, accounts: &[Pubkey]) -> Result<Self> {
+        let raw_url = network.geyser_url.as_deref().context("network.geyser_url не задан")?;
+        let uri: Uri = format!("{}{}", raw_url.trim_end_matches('/'), SUBSCRIBE_PATH).parse()
+            .with_context(|| format!("Некорректный network.geyser_url: {}", redact_url(raw_url)))?;
+        let host = uri.host().with_context(|| format!("В network.geyser_url нет хоста: {}", redact_url(raw_url)))?;
+        let tls = match uri.scheme_str() {
+            Some("https") => true,
+            Some("http") => false,
+            _ => anyhow::bail!("network.geyser_url: ожидается http:// или https://, получено {}", redact_url(raw_url)),
+        };
+        let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+
+        let proxy = proxy_url(&network.proxy, ProxyEndpoint::Geyser)?;
+        let tcp = proxy::connect(proxy.as_ref(), host, port).await
+            .with_context(|| format!("Не удалось подключиться к {}", redact_url(raw_url)))?;
+        let (client, connection) = if tls {
+            handshake(tls_connect(tcp, host, b"h2").await?).await
+        } else {
+            handshake(tcp).await
+        }
+        .with_context(|| format!("Не удалось установить HTTP/2 соединение с {}", redact_url(raw_url)))?;
+
+        let mut request = Request::post(uri)
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(())
+            .context("Некорректный запрос Subscribe")?;
+        if let Some(token) = geyser_token(network)? {
+            request.headers_mut().insert("x-token", token);
+        }
+        let (mut requests, responses) = open_stream(client, request).await
+            .with_context(|| format!("Не удалось открыть подписку {}", redact_url(raw_url)))?;
+        requests.send_data(frame(&encode_subscribe_request(accounts, &network.commitment)?)?, false)
+            .context("Не удалось отправить запрос подписки")?;
+
+        let response = responses.await.context("Узел не ответил на запрос подписки")?;
+        ensure_grpc_ok(response.status().as_u16(), response.headers())?;
+        Ok(Self { requests, responses: response.into_body(), decoder: FrameDecoder::default(), connection })
+    }
+
+    /// Замена набора аккаунтов подписки
+    pub fn subscribe(&mut self, accounts: &[Pubkey], commitment: &str) -> Result<()> {
+        self.requests.send_data(frame(&encode_subscribe_request(accounts, commitment)?)?, false)
+            .context("Не удалось отправить запрос подписки")
+    }
+
+    /// Ответ на ping узла
+    pub fn pong(&mut self) -> Result<()> {
+        self.requests.send_data(frame(&encode_ping())?, false)
+            .context("Не удалось отправить ping")
+    }
+
+    /// Следующее сообщение узла (ошибка — поток закрыт)
+    pub async fn next_event(&mut self) -> Result<GeyserEvent> {
+        loop {
+            if let Some(message) = self.decoder.next_message()? {
+                return parse_update(&message);
+            }
+            match self.responses.data().await {
+                Some(chunk) => {
+                    let chunk = chunk.context("Ошибка потока Yellowstone gRPC")?;
+                    self.decoder.push(&chunk);
+                    let _ = self.responses.flow_control().release_capacity(chunk.len());
+                }
+                None => {
+                    let trailers = self.responses.trailers().await
+                        .context("Ошибка завершения потока Yellowstone gRPC")?
+                        .unwrap_or_default();
+                    ensure_grpc_ok(200, &trailers)?;
+                    anyhow::bail!("Узел закрыл поток Yellowstone gRPC");
+                }
+            }
+        }
+    }
+}
+
+/// HTTP/2 поверх установленного соединения; задача соединения работает в фоне
+async fn handshake<T>(stream: T) -> Result<(SendRequest<Bytes>, JoinHandle<()>)>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (client, connection) = h2::client::handshake(stream).await?;
+    let connection = tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            log::debug!("Yellowstone gRPC: соединение HTTP/2 закрыто: {}", e);
+        }
+    });
+    Ok((client, connection))
+}
+
+/// TLS с протоколом ALPN `alpn` (корневые сертификаты webpki)
+pub(crate) async fn tls_connect(stream: TcpStream, host: &str, alpn: &[u8]) -> Result<tokio_rustls::client::TlsStream<TcpStream>> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    let mut tls_config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tls_config.alpn_protocols = vec![alpn.to_vec()];
+    let server_name = rustls::ServerName::try_from(host)
+        .with_context(|| format!("Некорректное имя хоста {}", host))?;
+    TlsConnector::from(Arc::new(tls_config))
+        .connect(server_name, stream)
+        .await
+        .with_context(|| format!("Не удалось установить TLS соединение с {}", host))
+}
+
+async fn open_stream(
+    client: SendRequest<Bytes>,
+    request: Request<()>,
+) -> Result<(SendStream<Bytes>, h2::client::ResponseFuture)> {
+    let mut client = client.ready().await.context("Соединение HTTP/2 не готово")?;
+    let (response, requests) = client.send_request(request, false).context("Не удалось отправить запрос")?;
+    Ok((requests, response))
+}
+
+/// x-token из переменной окружения geyser_token_env (не выводится в логи)
+fn geyser_token(network: &NetworkConfig) -> Result<Option<HeaderValue>> {
+    let Some(ref token_env) = network.geyser_token_env else { return Ok(None) };
+    let token = resolve_env_value(&format!("env:{}", token_env))
+        .with_context(|| format!("Переменная окружения {} (geyser_token_env) не задана", token_env))?;
+    let mut value = HeaderValue::from_str(&token).context("Некорректное значение geyser_token_env")?;
+    value.set_sensitive(true);
+    Ok(Some(value))
+}
+
+/// Проверка статуса ответа HTTP и gRPC (grpc-status в заголовках или трейлерах)
+fn ensure_grpc_ok(http_status: u16, headers: &HeaderMap) -> Result<()> {
+    if http_status != 200 {
+        anyhow::bail!("Узел Yellowstone gRPC ответил HTTP {}", http_status);
+    }
+    let status = headers.get("grpc-status").and_then(|value| value.to_str().ok());
+    if let Some(status) = status.filter(|status| *status != "0") {
+        let message = headers.get("grpc-message").and_then(|value| value.to_str().ok()).unwrap_or("");
+        anyhow::bail!("Узел Yellowstone gRPC вернул статус {}: {}", status, message);
+    }
+    Ok(())
+}
+
+/// Состояния аккаунтов из подписки Yellowstone gRPC
+///
+/// Аккаунт, впервые прочитанный через RPC, запоминается как запрошенный, и
+/// подписка добавляет его в фильтр и один раз читает начальное состояние.
+/// Дальше RPC клиент отвечает на чтение аккаунта из кеша (`CachedSender`).
+/// Аккаунт без обновлений не менялся, поэтому его состояние считается
+/// актуальным на последнем слоте узла. При обрыве подписки кеш очищается.
+/// Кеш наполняют подписки Yellowstone gRPC и WebSocket (`pubsub`).
+#[derive(Debug, Default)]
+pub struct AccountCache {
+    state: RwLock<CacheState>,
+    /// Счётчик изменений аккаунтов: торговый цикл просыпается по обновлению
+    changes: watch::Sender<u64>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// Аккаунт (None — не существует) и слот его последнего изменения
+    accounts: HashMap<Pubkey, (Option<Account>, u64)>,
+    /// Аккаунты, которые читались через RPC (кандидаты в подписку)
+    requested: HashSet<Pubkey>,
+    /// Последний слот узла
+    slot: u64,
+}
+
+impl AccountCache {
+    /// Новое состояние аккаунта (более раннее, чем сохранённое, игнорируется)
+    pub fn update(&self, address: Pubkey, account: Option<Account>, slot: u64) {
+        let mut state = self.state.write().expect("блокировка кеша аккаунтов отравлена");
+        state.slot = state.slot.max(slot);
+        if state.accounts.get(&address).is_some_and(|(_, stored)| *stored > slot) {
+            return;
+        }
+        state.accounts.insert(address, (account, slot));
+        self.changes.send_modify(|changes| *changes += 1);
+    }
+
+    /// Подписка на изменения аккаунтов кеша
+    pub fn changes(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// Новый слот узла
+    pub fn advance_slot(&self, slot: u64) {
+        let mut state = self.state.write().expect("блокировка кеша аккаунтов отравлена");
+        state.slot = state.slot.max(slot);
+    }
+
+    /// Сброс при потере соединения: без обновлений состояние может устареть
+    pub fn clear(&self) {
+        let mut state = self.state.write().expect("блокировка кеша аккаунтов отравлена");
+        state.accounts.clear();
+        state.slot = 0;
+    }
+
+    /// Аккаунты, которые читались через RPC
+    pub fn requested(&self) -> Vec<Pubkey> {
+        self.state.read().expect("блокировка кеша аккаунтов отравлена").requested.iter().copied().collect()
+    }
+
+    fn request(&self, addresses: &[Pubkey]) {
+        let mut state = self.state.write().expect("блокировка кеша аккаунтов отравлена");
+        state.requested.extend(addresses.iter().copied());
+    }
+
+    /// Аккаунты на последнем слоте узла (None — хотя бы одного нет в кеше)
+    pub fn get(&self, addresses: &[Pubkey]) -> Option<(Vec<Option<Account>>, u64)> {
+        let state = self.state.read().expect("блокировка кеша аккаунтов отравлена");
+        let accounts = addresses.iter()
+            .map(|address| state.accounts.get(address).map(|(account, _)| account.clone()))
+            .collect::<Option<Vec<_>>>()?;
+        Some((accounts, state.slot))
+    }
+}
+
+/// Кеш подписки процесса (общий для всех RPC клиентов)
+pub fn cache() -> &'static AccountCache {
+    static CACHE: OnceLock<AccountCache> = OnceLock::new();
+    CACHE.get_or_init(AccountCache::default)
+}
+
+/// Транспорт RPC, отвечающий на чтение аккаунтов из кеша подписки
+///
+/// getAccountInfo и getMultipleAccounts в бинарной кодировке отвечаются из
+/// кеша, если там есть все запрошенные аккаунты; остальные запросы и промахи
+/// уходят в RPC, а прочитанные адреса становятся кандидатами в подписку.
+pub struct CachedSender<S> {
+    inner: S,
+    cache: &'static AccountCache,
+}
+
+impl<S> CachedSender<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner, cache: cache() }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for CachedSender<S> {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        let Some(read) = AccountRead::parse(request, &params) else { return self.inner.send(request, params).await };
+
+        self.cache.request(&read.addresses);
+        let cached = read.binary
+            .then(|| self.cache.get(&read.addresses))
+            .flatten();
+        let Some((accounts, slot)) = cached else { return self.inner.send(request, params).await };
+        Ok(read.response(&accounts, slot))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+/// Запуск подписки Yellowstone gRPC с переподключением (data_source = "geyser")
+///
+/// Пока подписка не подключена, кеш пуст, и аккаунты читаются через RPC.
+pub fn spawn(network: NetworkConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = run_subscription(&network, cache()).await {
+                log::warn!("Yellowstone gRPC: подписка прервана, аккаунты читаются через RPC: {:#}", e);
+            }
+            cache().clear();
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+/// Подписка на аккаунты, прочитанные через RPC
+///
+/// Новые аккаунты добавляются повторным запросом в тот же поток — узел
+/// заменяет фильтры целиком.
+async fn run_subscription(network: &NetworkConfig, cache: &AccountCache) -> Result<()> {
+    let mut stream = GeyserStream::connect(network, &[]).await?;
+    log::info!("Yellowstone gRPC: подключено к {}", redact_url(network.geyser_url.as_deref().unwrap_or_default()));
+
+    let mut subscribed: HashSet<Pubkey> = HashSet::new();
+    let mut check = tokio::time::interval(SUBSCRIBE_CHECK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = check.tick() => {
+                let new: Vec<Pubkey> = cache.requested()
+                    .into_iter()
+                    .filter(|address| !subscribed.contains(address))
+                    .collect();
+                if new.is_empty() {
+                    continue;
+                }
+                subscribed.extend(new.iter().copied());
+                let accounts: Vec<Pubkey> = subscribed.iter().copied().collect();
+                stream.subscribe(&accounts, &network.commitment)?;
+                log::info!("Yellowstone gRPC: {} новых аккаунтов (всего {})", new.len(), subscribed.len());
+                seed(network, cache, &new).await?;
+            }
+            event = tokio::time::timeout(IDLE_TIMEOUT, stream.next_event()) => {
+                let event = event
+                    .map_err(|_| anyhow::anyhow!("Нет сообщений Yellowstone gRPC дольше {} с", IDLE_TIMEOUT.as_secs()))??;
+                match event {
+                    GeyserEvent::Account { address, slot, account } => {
+                        if subscribed.contains(&address) {
+                            cache.update(address, account, slot);
+                        }
+                    }
+                    GeyserEvent::Slot(slot) => cache.advance_slot(slot),
+                    GeyserEvent::Ping => stream.pong()?,
+                    GeyserEvent::Other => {}
+                }
+            }
+        }
+    }
+}
+
+/// Начальное состояние новых аккаунтов подписки через RPC
+///
+/// Обновления приходят только при изменении, поэтому без чтения аккаунт,
+/// который не меняется, так и остался бы неизвестным кешу. Чтение идёт после
+/// запроса подписки: изменение между ними придёт обновлением с более поздним слотом.
+pub(crate) async fn seed(network: &NetworkConfig, cache: &AccountCache, addresses: &[Pubkey]) -> Result<()> {
+    let network = network.clone();
+    let fetch = addresses.to_vec();
+    let initial = tokio::task::spawn_blocking(move || -> Result<Vec<(Vec<Option<Account>>, u64)>> {
+        let client = create_rpc_client(&network)?;
+        fetch.chunks(MAX_MULTIPLE_ACCOUNTS)
+            .map(|chunk| {
+                let response = client.get_multiple_accounts_with_commitment(chunk, client.commitment())?;
+                Ok((response.value, response.context.slot))
+            })
+            .collect()
+    })
+    .await
+    .context("Задача чтения аккаунтов подписки завершилась с ошибкой")?;
+    match initial {
+        Ok(chunks) => {
+            for (addresses, (accounts, slot)) in addresses.chunks(MAX_MULTIPLE_ACCOUNTS).zip(chunks) {
+                for (address, account) in addresses.iter().zip(accounts) {
+                    cache.update(*address, account, slot);
+                }
+            }
+        }
+        Err(e) => log::warn!("Начальное состояние аккаунтов подписки не прочитано: {:#}", e),
+    }
+    Ok(())
+}
+
+/// Значение поля protobuf
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Поля сообщения protobuf: номер и значение
+struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.data.split_first().context("Обрезанное сообщение protobuf")?;
+            self.data = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("Слишком длинное число в сообщении protobuf")
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            anyhow::bail!("Обрезанное сообщение protobuf");
+        }
+        let (value, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(value)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>)> {
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed
+            }
+            2 => {
+                let len = usize::try_from(self.varint()?).context("Некорректная длина поля protobuf")?;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                Value::Fixed
+            }
+            wire_type => anyhow::bail!("Неподдерживаемый тип поля protobuf {}", wire_type),
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
+
+fn put_bytes(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, (field << 3) | 2);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Запись map<string, message>: ключ (поле 1) и значение (поле 2)
+fn map_entry(key: &str, value: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::new();
+    put_bytes(&mut entry, 1, key.as_bytes());
+    put_bytes(&mut entry, 2, value);
+    entry
+}
@@ -0,0 +1,90 @@
+use anyhow::Result;
+use chrono::{DateTime, Timelike, Utc};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use crate::signal::Signal;
+use crate::store::Store;
+
+/// Коллекция хранилища с найденными возможностями
+pub const OPPORTUNITIES_COLLECTION: &str = "opportunities";
+
+/// Ячейка тепловой карты: час суток, пара и связка DEX
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapCell {
+    /// Час суток по UTC (0–23)
+    pub hour: u32,
+    pub base_token: String,
+    pub quote_token: String,
+    /// DEX покупки
+    pub from_dex: String,
+    /// DEX продажи
+    pub to_dex: String,
+    /// Число возможностей
+    pub count: u64,
+    /// Средняя прибыль после комиссий, в процентах
+    pub avg_profit_percent_after_fees: Decimal,
+    /// Средняя ожидаемая чистая прибыль, в quote токене
+    pub avg_net_profit: Decimal,
+    /// Наибольшая прибыль после комиссий, в процентах
+    pub max_profit_percent_after_fees: Decimal,
+}
+
+#[derive(Default)]
+struct CellTotals {
+    count: u64,
+    profit_percent: Decimal,
+    net_profit: Decimal,
+    max_profit_percent: Decimal,
+}
+
+/// Тепловая карта возможностей: час суток × пара × связка DEX
+///
+/// Маршруты через несколько пар не учитываются — у них нет одной пары и
+/// связки DEX. Ячейки упорядочены по часу, затем по паре и DEX.
+pub fn build_heatmap(signals: &[Signal]) -> Vec<HeatmapCell> {
+    let mut cells: BTreeMap<(u32, &str, &str, &str, &str), CellTotals> = BTreeMap::new();
+    for signal in signals.iter().filter(|signal| signal.route.is_empty()) {
+        let key = (
+            signal.timestamp.hour(),
+            signal.base_token.as_str(),
+            signal.quote_token.as_str(),
+            signal.from_dex.as_str(),
+            signal.to_dex.as_str(),
+        );
+        let totals = cells.entry(key).or_default();
+        if totals.count == 0 || signal.profit_percent_after_fees > totals.max_profit_percent {
+            totals.max_profit_percent = signal.profit_percent_after_fees;
+        }
+        totals.count += 1;
+        totals.profit_percent += signal.profit_percent_after_fees;
+        totals.net_profit += signal.trade_amount * signal.profit_percent_after_fees / Decimal::from(100);
+    }
+
+    cells.into_iter()
+        .map(|((hour, base_token, quote_token, from_dex, to_dex), totals)| {
+            let count = Decimal::from(totals.count);
+            HeatmapCell {
+                hour,
+                base_token: base_token.to_string(),
+                quote_token: quote_token.to_string(),
+                from_dex: from_dex.to_string(),
+                to_dex: to_dex.to_string(),
+                count: totals.count,
+                avg_profit_percent_after_fees: totals.profit_percent / count,
+                avg_net_profit: totals.net_profit / count,
+                max_profit_percent_after_fees: totals.max_profit_percent,
+            }
+        })
+        .collect()
+}
+
+/// Загрузка сохранённых возможностей за период
+pub async fn load_opportunities(
+    store: &Store,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<Signal>> {
+    let mut signals: Vec<Signal> = store.read_all(OPPORTUNITIES_COLLECTION).await?;
+    signals.retain(|s| from.is_none_or(|from| s.timestamp >= from) && to.is_none_or(|to| s.timestamp <= to));
+    Ok(signals)
+}
@@ -0,0 +1,252 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use crate::config::LegGate;
+use crate::store::Store;
+
+/// Максимальное количество сделок, хранимых в памяти
+pub const MAX_TRADE_HISTORY: usize = 10_000;
+/// Коллекция хранилища с записями о сделках
+pub const TRADES_COLLECTION: &str = "trades";
+/// Коллекция хранилища с заметками и тегами сделок
+pub const ANNOTATIONS_COLLECTION: &str = "trade_annotations";
+
+/// Максимальная длина заметки к сделке
+pub const MAX_NOTES_LEN: usize = 2000;
+/// Максимальное количество тегов у сделки
+pub const MAX_TAGS: usize = 20;
+/// Максимальная длина тега
+pub const MAX_TAG_LEN: usize = 64;
+
+/// Запись о сделке
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub from_dex: String,
+    pub to_dex: String,
+    pub base_token: String,
+    pub quote_token: String,
+    pub amount: Decimal,
+    pub profit_percent: Decimal,
+    /// Прибыль в котируемом токене (6 знаков)
+    #[serde(default)]
+    pub profit_quote: Decimal,
+    /// Прибыль в SOL (9 знаков); None, если нет курса для пересчёта
+    pub profit_sol: Option<Decimal>,
+    /// Прибыль в USD (2 знака); None, если курс USD неизвестен
+    #[serde(default)]
+    pub profit_usd: Option<Decimal>,
+    pub status: TradeStatus,
+    pub tx_signature: Option<String>,
+    /// Причина неудачи (расшифрованная ошибка программы, если есть)
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    /// Идентификатор прогона, в котором совершена сделка
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    /// Заметка оператора
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Теги оператора
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Условие перехода к продаже, с которым исполнялась сделка
+    #[serde(default)]
+    pub leg_gate: Option<LegGate>,
+    /// Логи симуляции транзакций перед отправкой (для разбора отменённых и неудачных сделок)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub simulation_logs: Vec<String>,
+    /// Капитал сделки (вход ноги покупки) в SOL; None, если нет курса для пересчёта
+    #[serde(default)]
+    pub capital_sol: Option<Decimal>,
+    /// Время от завершения покупки до завершения продажи, мс (None — обе ноги одной транзакцией)
+    #[serde(default)]
+    pub hold_ms: Option<u64>,
+}
+
+/// Статус сделки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeStatus {
+    Success,
+    Failed,
+    Simulated,
+}
+
+/// История сделок, разделяемая движком и веб-сервером
+pub type TradeHistory = Arc<Mutex<Vec<TradeRecord>>>;
+
+/// Добавление сделки с ограничением размера истории
+pub async fn push_trade(history: &TradeHistory, record: TradeRecord) {
+    let mut trades = history.lock().await;
+    trades.push(record);
+    if trades.len() > MAX_TRADE_HISTORY {
+        let excess = trades.len() - MAX_TRADE_HISTORY;
+        trades.drain(..excess);
+    }
+}
+
+/// Сводка активности для публичной страницы статуса
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeActivity {
+    /// Сделок с начала текущих суток (UTC)
+    pub trades_today: usize,
+    /// Время последней сделки
+    pub last_trade_at: Option<DateTime<Utc>>,
+}
+
+/// Сделки за текущие сутки (UTC) и время последней сделки на момент `now`
+pub fn trade_activity(trades: &[TradeRecord], now: DateTime<Utc>) -> TradeActivity {
+    let day_start = now.date_naive().and_hms_opt(0, 0, 0)
+        .expect("полночь всегда корректна")
+        .and_utc();
+    TradeActivity {
+        trades_today: trades.iter()
+            .filter(|trade| trade.timestamp >= day_start && trade.timestamp <= now)
+            .count(),
+        last_trade_at: trades.iter().map(|trade| trade.timestamp).max(),
+    }
+}
+
+/// Использование капитала успешными сделками
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapitalUsage {
+    /// Капитал сделок за последний час, SOL
+    pub deployed_hour_sol: Decimal,
+    /// Капитал сделок за последние сутки, SOL
+    pub deployed_day_sol: Decimal,
+    /// Среднее время между ногами двухэтапных сделок за сутки, мс (None — таких сделок не было)
+    pub average_hold_ms: Option<u64>,
+    /// Оборачиваемость за сутки: капитал сделок к среднему балансу SOL кошелька
+    /// (None — баланс неизвестен или нулевой)
+    pub turnover: Option<Decimal>,
+}
+
+/// Капитал, время удержания между ногами и оборачиваемость за час и сутки до `now`
+///
+/// Учитываются успешные сделки; сделки без капитала в SOL (нет курса) в суммы
+/// не входят. `balance_sol` — средний баланс SOL кошелька за сутки.
+pub fn capital_usage(trades: &[TradeRecord], balance_sol: Option<Decimal>, now: DateTime<Utc>) -> CapitalUsage {
+    let since = |duration: chrono::Duration| {
+        let start = now - duration;
+        trades.iter().filter(move |trade| {
+            trade.status == TradeStatus::Success && trade.timestamp > start && trade.timestamp <= now
+        })
+    };
+    let deployed = |duration| since(duration).filter_map(|trade| trade.capital_sol).sum::<Decimal>();
+    let deployed_day_sol = deployed(chrono::Duration::days(1));
+    let holds: Vec<u64> = since(chrono::Duration::days(1)).filter_map(|trade| trade.hold_ms).collect();
+    CapitalUsage {
+        deployed_hour_sol: deployed(chrono::Duration::hours(1)),
+        deployed_day_sol,
+        average_hold_ms: (!holds.is_empty()).then(|| holds.iter().sum::<u64>() / holds.len() as u64),
+        turnover: balance_sol.filter(|balance| !balance.is_zero()).map(|balance| deployed_day_sol / balance),
+    }
+}
+
+/// Изменение заметки и тегов сделки (поля None не меняются)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradeAnnotation {
+    pub trade_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    /// Новая заметка; пустая строка удаляет заметку
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Новый набор тегов (заменяет прежний)
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+}
+
+impl TradeAnnotation {
+    /// Проверка и нормализация: обрезка пробелов, удаление пустых и повторяющихся тегов
+    pub fn normalized(mut self) -> Result<Self> {
+        if let Some(notes) = self.notes.take() {
+            let notes = notes.trim().to_string();
+            if notes.chars().count() > MAX_NOTES_LEN {
+                anyhow::bail!("Заметка длиннее {} символов", MAX_NOTES_LEN);
+            }
+            self.notes = Some(notes);
+        }
+
+        if let Some(tags) = self.tags.take() {
+            let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+            for tag in tags {
+                let tag = tag.trim().to_string();
+                if tag.is_empty() || normalized.contains(&tag) {
+                    continue;
+                }
+                if tag.chars().count() > MAX_TAG_LEN {
+                    anyhow::bail!("Тег длиннее {} символов: {}", MAX_TAG_LEN, tag);
+                }
+                normalized.push(tag);
+            }
+            if normalized.len() > MAX_TAGS {
+                anyhow::bail!("Больше {} тегов у сделки", MAX_TAGS);
+            }
+            self.tags = Some(normalized);
+        }
+
+        Ok(self)
+    }
+
+    /// Применение к записи о сделке
+    pub fn apply(&self, record: &mut TradeRecord) {
+        if let Some(ref notes) = self.notes {
+            record.notes = if notes.is_empty() { None } else { Some(notes.clone()) };
+        }
+        if let Some(ref tags) = self.tags {
+            record.tags = tags.clone();
+        }
+    }
+}
+
+/// Загрузка истории сделок из хранилища с применением заметок и тегов
+pub async fn load_trades(store: &Store) -> Result<Vec<TradeRecord>> {
+    let mut trades: Vec<TradeRecord> = store.read_all(TRADES_COLLECTION).await?;
+    let annotations: Vec<TradeAnnotation> = store.read_all(ANNOTATIONS_COLLECTION).await?;
+
+    let index: HashMap<Uuid, usize> = trades.iter()
+        .enumerate()
+        .map(|(i, trade)| (trade.id, i))
+        .collect();
+    // Аннотации записываются по порядку, поэтому последняя правка побеждает
+    for annotation in &annotations {
+        if let Some(&i) = index.get(&annotation.trade_id) {
+            annotation.apply(&mut trades[i]);
+        }
+    }
+
+    if trades.len() > MAX_TRADE_HISTORY {
+        let excess = trades.len() - MAX_TRADE_HISTORY;
+        trades.drain(..excess);
+    }
+    Ok(trades)
+}
+
+/// Сжатие журнала сделок до MAX_TRADE_HISTORY последних записей
+///
+/// Более старые сделки при запуске всё равно не загружаются; вместе с ними
+/// удаляются их заметки. Возвращает количество удалённых сделок и заметок.
+pub async fn compact_trades(store: &Store) -> Result<(usize, usize)> {
+    let mut removed = HashSet::new();
+    store.rewrite(TRADES_COLLECTION, |mut trades: Vec<TradeRecord>| {
+        if trades.len() > MAX_TRADE_HISTORY {
+            let excess = trades.len() - MAX_TRADE_HISTORY;
+            removed = trades.drain(..excess).map(|trade| trade.id).collect();
+        }
+        trades
+    }).await?;
+    if removed.is_empty() {
+        return Ok((0, 0));
+    }
+    let (before, after) = store.rewrite(ANNOTATIONS_COLLECTION, |mut annotations: Vec<TradeAnnotation>| {
+        annotations.retain(|annotation| !removed.contains(&annotation.trade_id));
+        annotations
+    }).await?;
+    Ok((removed.len(), before - after))
+}
@@ -0,0 +1,553 @@
+//! Регулярные задачи обслуживания
+//!
+//! Ежедневный отчёт, сжатие журналов, снимки баланса, пары из токенов
+//! кошелька, очистку пустых токен-аккаунтов, обновление реестров пулов и ALT
+//! запускает один планировщик по расписанию из секции `[jobs]`, а не отдельные
+//! фоновые задачи со своими интервалами. Состояние задач отдаётся в `/api/status` (`jobs`).
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Days, NaiveDate, Timelike, Utc};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use crate::ata_cleanup::run_cleanup;
+use crate::balance_history::{average_sol, load_history, record_snapshot, BalanceSnapshot, BALANCE_COLLECTION};
+use crate::clock::{to_chrono, SharedClock};
+use crate::config::{Config, ExecutionMode};
+use crate::dex::DexManager;
+use crate::heatmap::OPPORTUNITIES_COLLECTION;
+use crate::history::{capital_usage, compact_trades, TradeRecord, TradeStatus};
+use crate::monitor::Monitor;
+use crate::pair_discovery::refresh_discovered_pairs;
+use crate::signal::Signal;
+use crate::store::Store;
+use crate::supervisor::Supervisor;
+use crate::wallet::HotWallet;
+
+/// Значение расписания: задача отключена
+pub const OFF: &str = "off";
+
+/// Период проверки расписания
+const SCHEDULER_TICK: Duration = Duration::from_secs(1);
+
+/// Предел шагов поиска следующего срабатывания cron (несколько лет вперёд)
+const MAX_CRON_STEPS: usize = 100_000;
+
+/// Расписание задачи
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobSchedule {
+    /// Через равные интервалы; первый запуск — сразу после старта
+    Every(Duration),
+    /// По cron (UTC)
+    Cron(CronSchedule),
+}
+
+impl JobSchedule {
+    /// Разбор расписания: cron из 5 полей, `@every 30s|10m|2h|1d`,
+    /// `@hourly`, `@daily`, `@weekly` (None — `off`)
+    pub fn parse(spec: &str) -> Result<Option<Self>> {
+        let spec = spec.trim();
+        let cron = match spec {
+            OFF => return Ok(None),
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            _ => match spec.strip_prefix("@every ") {
+                Some(interval) => return Ok(Some(JobSchedule::Every(parse_interval(interval.trim())?))),
+                None => spec,
+            },
+        };
+        Ok(Some(JobSchedule::Cron(CronSchedule::parse(cron)?)))
+    }
+
+    /// Первый запуск после старта в `now`
+    pub fn first_run(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            JobSchedule::Every(_) => now,
+            JobSchedule::Cron(cron) => cron.next_after(now),
+        }
+    }
+
+    /// Следующий запуск после запуска в `now`
+    pub fn next_after(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            JobSchedule::Every(interval) => now + to_chrono(*interval),
+            JobSchedule::Cron(cron) => cron.next_after(now),
+        }
+    }
+}
+
+/// Интервал вида `30s`, `10m`, `2h`, `1d`
+fn parse_interval(value: &str) -> Result<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse()
+        .with_context(|| format!("Некорректный интервал \"{}\": ожидается число с единицей s, m, h или d", value))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86_400,
+        _ => anyhow::bail!("Некорректный интервал \"{}\": единица должна быть s, m, h или d", value),
+    };
+    if number == 0 {
+        anyhow::bail!("Интервал \"{}\" должен быть больше 0", value);
+    }
+    Ok(Duration::from_secs(number.saturating_mul(seconds)))
+}
+
+/// Расписание cron: минута, час, день месяца, месяц, день недели (UTC)
+///
+/// Поля — числа, `*`, диапазоны `a-b`, шаг `/n` и списки через запятую;
+/// день недели от 0 (воскресенье) до 7 (тоже воскресенье). Если ограничены и
+/// день месяца, и день недели, достаточно совпадения любого из них, как в cron.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Оба поля дней ограничены (не начинаются с `*`)
+    days_either: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            anyhow::bail!("Расписание \"{}\": ожидается 5 полей cron (минута час день месяц день_недели)", spec);
+        };
+        let mut weekday_mask = parse_field(weekdays, "день недели", 0, 7)?;
+        // 7 — воскресенье, как и 0
+        if weekday_mask & (1 << 7) != 0 {
+            weekday_mask = (weekday_mask & !(1 << 7)) | 1;
+        }
+        let schedule = Self {
+            minutes: parse_field(minutes, "минута", 0, 59)?,
+            hours: parse_field(hours, "час", 0, 23)?,
+            days: parse_field(days, "день месяца", 1, 31)?,
+            months: parse_field(months, "месяц", 1, 12)?,
+            weekdays: weekday_mask,
+            days_either: !days.starts_with('*') && !weekdays.starts_with('*'),
+        };
+        if schedule.find_after(Utc::now()).is_none() {
+            anyhow::bail!("Расписание \"{}\" никогда не срабатывает", spec);
+        }
+        Ok(schedule)
+    }
+
+    /// Ближайшее срабатывание строго позже `time`
+    pub fn next_after(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        self.find_after(time).unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    fn find_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let minute_start = time.with_second(0)?.with_nanosecond(0)?;
+        let mut time = minute_start + chrono::Duration::minutes(1);
+        for _ in 0..MAX_CRON_STEPS {
+            if !has(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?.and_utc();
+                continue;
+            }
+            if !self.day_matches(time) {
+                time = time.date_naive().checked_add_days(Days::new(1))?.and_hms_opt(0, 0, 0)?.and_utc();
+                continue;
+            }
+            if !has(self.hours, time.hour()) {
+                time = time.with_minute(0)? + chrono::Duration::hours(1);
+                continue;
+            }
+            if !has(self.minutes, time.minute()) {
+                time += chrono::Duration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+        if self.days_either {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Поле cron как битовая маска допустимых значений
+fn parse_field(field: &str, name: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let invalid = || format!("Поле \"{}\" ({}): некорректное значение \"{}\"", field, name, item);
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().ok().filter(|step| *step > 0).with_context(invalid)?)),
+            None => (item, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse::<u32>().with_context(invalid)?, end.parse::<u32>().with_context(invalid)?)
+        } else {
+            let value = range.parse::<u32>().with_context(invalid)?;
+            // `5/15` — с 5 до конца диапазона с шагом 15
+            (value, if step.is_some() { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            anyhow::bail!("Поле \"{}\" ({}): значения должны быть от {} до {}", field, name, min, max);
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// Состояние регулярной задачи
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub name: String,
+    /// Расписание из настроек
+    pub schedule: String,
+    /// Задача выполняется сейчас
+    pub running: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    /// Итог последнего успешного запуска
+    pub last_result: Option<String>,
+    /// Ошибка последнего запуска (None — запуск успешен)
+    pub last_error: Option<String>,
+    pub runs: u64,
+    pub failures: u64,
+}
+
+/// Состояние регулярных задач (общее с веб-сервером)
+#[derive(Debug, Clone, Default)]
+pub struct JobStates {
+    jobs: Arc<Mutex<BTreeMap<String, JobStatus>>>,
+}
+
+impl JobStates {
+    /// Состояние всех задач (по имени)
+    pub fn list(&self) -> Vec<JobStatus> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Состояние задачи
+    #[allow(dead_code)]
+    pub fn get(&self, name: &str) -> Option<JobStatus> {
+        self.lock().get(name).cloned()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, JobStatus>> {
+        self.jobs.lock().expect("мьютекс состояния задач планировщика отравлен")
+    }
+}
+
+type JobRun = Arc<dyn Fn() -> BoxFuture<'static, Result<String>> + Send + Sync>;
+
+struct Job {
+    name: String,
+    spec: String,
+    schedule: JobSchedule,
+    run: JobRun,
+}
+
+/// Планировщик регулярных задач
+///
+/// Раз в секунду запускает задачи, время которых пришло. Каждый запуск
+/// выполняется отдельно, поэтому долгая задача не задерживает остальные;
+/// срабатывание, к которому задача не закончила прошлый запуск, пропускается.
+pub struct Scheduler {
+    jobs: Vec<Job>,
+    states: JobStates,
+    clock: SharedClock,
+}
+
+impl Scheduler {
+    pub fn new(states: JobStates, clock: SharedClock) -> Self {
+        Self { jobs: Vec::new(), states, clock }
+    }
+
+    /// Добавление задачи; с расписанием `off` задача не добавляется
+    ///
+    /// Задача возвращает краткий итог запуска для `/api/status`.
+    pub fn add<F, Fut>(&mut self, name: &str, spec: &str, run: F) -> Result<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let Some(schedule) = JobSchedule::parse(spec).with_context(|| format!("jobs.{}", name))? else {
+            log::info!("Задача {} отключена", name);
+            return Ok(());
+        };
+        self.jobs.push(Job {
+            name: name.to_string(),
+            spec: spec.trim().to_string(),
+            schedule,
+            run: Arc::new(move || run().boxed()),
+        });
+        Ok(())
+    }
+
+    /// Запуск задач, время которых пришло (возвращает их запуски)
+    pub fn run_due(&self) -> Vec<JoinHandle<()>> {
+        let now = self.clock.now();
+        let mut started = Vec::new();
+        for job in &self.jobs {
+            {
+                let mut states = self.states.lock();
+                let state = states.entry(job.name.clone()).or_insert_with(|| JobStatus {
+                    name: job.name.clone(),
+                    schedule: job.spec.clone(),
+                    running: false,
+                    next_run_at: job.schedule.first_run(now),
+                    last_run_at: None,
+                    last_duration_ms: None,
+                    last_result: None,
+                    last_error: None,
+                    runs: 0,
+                    failures: 0,
+                });
+                if state.next_run_at > now {
+                    continue;
+                }
+                state.next_run_at = job.schedule.next_after(now);
+                if state.running {
+                    log::warn!("Задача {} ещё выполняется, запуск пропущен", job.name);
+                    continue;
+                }
+                state.running = true;
+            }
+            started.push(self.start(job, now));
+        }
+        started
+    }
+
+    fn start(&self, job: &Job, now: DateTime<Utc>) -> JoinHandle<()> {
+        let (name, run, states) = (job.name.clone(), job.run.clone(), self.states.clone());
+        tokio::spawn(async move {
+            let started = Instant::now();
+            // Отдельная задача, чтобы паника не оставила задачу в состоянии running
+            let result = match tokio::spawn(run()).await {
+                Ok(result) => result.map_err(|e| format!("{:#}", e)),
+                Err(e) => Err(format!("паника: {}", e)),
+            };
+            match result {
+                Ok(ref summary) => log::debug!("Задача {} выполнена: {}", name, summary),
+                Err(ref error) => log::warn!("Задача {} завершилась с ошибкой: {}", name, error),
+            }
+            let mut states = states.lock();
+            if let Some(state) = states.get_mut(&name) {
+                state.running = false;
+                state.last_run_at = Some(now);
+                state.last_duration_ms = Some(started.elapsed().as_millis() as u64);
+                state.runs += 1;
+                match result {
+                    Ok(summary) => {
+                        state.last_result = Some(summary);
+                        state.last_error = None;
+                    }
+                    Err(error) => {
+                        state.last_error = Some(error);
+                        state.failures += 1;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Запуск планировщика под надзором (без задач не запускается)
+    pub fn spawn(self, supervisor: &Supervisor) {
+        if self.jobs.is_empty() {
+            return;
+        }
+        let scheduler = Arc::new(self);
+        supervisor.spawn("scheduler", move || run_scheduler(scheduler.clone()));
+    }
+}
+
+async fn run_scheduler(scheduler: Arc<Scheduler>) -> Result<()> {
+    let mut timer = tokio::time::interval(SCHEDULER_TICK);
+    loop {
+        timer.tick().await;
+        scheduler.run_due();
+    }
+}
+
+/// Расписание по интервалу в секундах (0 — `off`)
+fn every_sec(interval_sec: u64) -> String {
+    if interval_sec == 0 {
+        OFF.to_string()
+    } else {
+        format!("@every {}s", interval_sec)
+    }
+}
+
+/// Регистрация задач обслуживания из `[jobs]` и запуск планировщика
+pub fn spawn_maintenance(
+    supervisor: &Supervisor,
+    config: &Config,
+    monitor: &Monitor,
+    wallet: HotWallet,
+    store: Store,
+    dex_manager: Arc<DexManager>,
+    clock: SharedClock,
+) -> Result<()> {
+    let jobs = &config.jobs;
+    let mut scheduler = Scheduler::new(monitor.jobs().clone(), clock.clone());
+
+    let (report_monitor, report_store, report_clock) = (monitor.clone(), store.clone(), clock.clone());
+    scheduler.add("daily_report", &jobs.daily_report, move || {
+        let (monitor, store, clock) = (report_monitor.clone(), report_store.clone(), report_clock.clone());
+        async move {
+            let now = clock.now();
+            // Без истории баланса сводка выходит без оборачиваемости
+            let balance = match load_history(&store, Some(now - chrono::Duration::days(1)), Some(now)).await {
+                Ok(snapshots) => average_sol(&snapshots),
+                Err(e) => {
+                    log::warn!("Ошибка чтения истории баланса для сводки: {}", e);
+                    None
+                }
+            };
+            let history = monitor.trade_history();
+            let report = daily_report(&history.lock().await, balance, now);
+            monitor.report(&report);
+            Ok(report)
+        }
+    })?;
+
+    let (compaction_store, compaction_clock, retention_days) = (store.clone(), clock.clone(), jobs.retention_days);
+    scheduler.add("history_compaction", &jobs.history_compaction, move || {
+        let (store, clock) = (compaction_store.clone(), compaction_clock.clone());
+        async move { compact_history(&store, retention_days, clock.now()).await }
+    })?;
+
+    // Процессу поиска без ключа снимать баланс и искать пары не с чего
+    if config.safety.mode != ExecutionMode::Scan {
+        let spec = jobs.balance_snapshot.clone()
+            .unwrap_or_else(|| every_sec(config.monitoring.balance_snapshot_interval_sec));
+        let (snapshot_config, snapshot_wallet, snapshot_store) = (config.clone(), wallet.clone(), store.clone());
+        scheduler.add("balance_snapshot", &spec, move || {
+            let (config, wallet, store) = (snapshot_config.clone(), snapshot_wallet.clone(), snapshot_store.clone());
+            async move {
+                let snapshot = record_snapshot(&config, &*wallet.current().await, &store).await?;
+                Ok(format!("{} SOL, токенов: {}", snapshot.sol, snapshot.tokens.len()))
+            }
+        })?;
+
+        let (cleanup_config, cleanup_wallet, cleanup_dexes) = (config.clone(), wallet.clone(), dex_manager.clone());
+        let (cleanup_fees, cleanup_store, cleanup_clock) = (monitor.fees().clone(), store.clone(), clock.clone());
+        scheduler.add("ata_cleanup", &jobs.ata_cleanup, move || {
+            let (config, wallet, dex_manager) = (cleanup_config.clone(), cleanup_wallet.clone(), cleanup_dexes.clone());
+            let (fees, store, clock) = (cleanup_fees.clone(), cleanup_store.clone(), cleanup_clock.clone());
+            async move { run_cleanup(&config, &wallet, &dex_manager, &fees, &store, clock.now()).await }
+        })?;
+
+        if config.pair_discovery.enabled {
+            let spec = jobs.pair_discovery.clone().unwrap_or_else(|| every_sec(config.pair_discovery.interval_sec));
+            let (discovery_config, discovery_wallet, discovery_dexes) = (config.clone(), wallet.clone(), dex_manager.clone());
+            scheduler.add("pair_discovery", &spec, move || {
+                let (config, wallet, dex_manager) = (discovery_config.clone(), discovery_wallet.clone(), discovery_dexes.clone());
+                async move {
+                    let pairs = refresh_discovered_pairs(&config, &wallet, &dex_manager).await?;
+                    Ok(format!("пар из токенов кошелька: {}", pairs))
+                }
+            })?;
+        }
+    } else if config.pair_discovery.enabled {
+        log::warn!("pair_discovery не работает в режиме scan: ключ кошелька не загружается");
+    }
+
+    let registry_dexes = dex_manager.clone();
+    scheduler.add("pool_registry_refresh", &jobs.pool_registry_refresh, move || {
+        let dex_manager = registry_dexes.clone();
+        async move { Ok(format!("пулов найдено: {}", dex_manager.refresh_pools().await)) }
+    })?;
+
+    // Шаблоны транзакций нужны только процессу, исполняющему сделки
+    if config.templates.enabled && config.safety.mode.executes() {
+        let (templates, templates_wallet) = (config.templates.clone(), wallet.clone());
+        scheduler.add("lookup_tables_refresh", &jobs.lookup_tables_refresh, move || {
+            let (templates, wallet, dex_manager) = (templates.clone(), templates_wallet.clone(), dex_manager.clone());
+            async move {
+                let payer = *wallet.current().await.pubkey();
+                Ok(format!("шаблонов транзакций: {}", dex_manager.prepare_templates(&templates, &payer).await))
+            }
+        })?;
+    }
+
+    scheduler.spawn(supervisor);
+    Ok(())
+}
+
+/// Сводка сделок за сутки до `now`
+///
+/// `balance_sol` — средний баланс SOL кошелька за сутки для оборачиваемости капитала.
+pub fn daily_report(trades: &[TradeRecord], balance_sol: Option<Decimal>, now: DateTime<Utc>) -> String {
+    let since = now - chrono::Duration::days(1);
+    let recent: Vec<&TradeRecord> = trades.iter()
+        .filter(|trade| trade.timestamp > since && trade.timestamp <= now)
+        .collect();
+    let count = |status: TradeStatus| recent.iter().filter(|trade| trade.status == status).count();
+    let successful = recent.iter().filter(|trade| trade.status == TradeStatus::Success);
+    let profit_sol: Decimal = successful.clone().filter_map(|trade| trade.profit_sol).sum();
+    let profit_usd: Decimal = successful.filter_map(|trade| trade.profit_usd).sum();
+    let capital = capital_usage(trades, balance_sol, now);
+    let mut report = format!(
+        "📊 Сутки до {}: сделок {} (успешных {}, неудачных {}, симуляций {}), прибыль {} SOL / {} USD, капитал {} SOL",
+        now.format("%Y-%m-%d %H:%M UTC"),
+        recent.len(),
+        count(TradeStatus::Success),
+        count(TradeStatus::Failed),
+        count(TradeStatus::Simulated),
+        profit_sol.round_dp(9).normalize(),
+        profit_usd.round_dp(2),
+        capital.deployed_day_sol.round_dp(9).normalize(),
+    );
+    if let Some(hold_ms) = capital.average_hold_ms {
+        report.push_str(&format!(", удержание между ногами {} мс", hold_ms));
+    }
+    if let Some(turnover) = capital.turnover {
+        report.push_str(&format!(", оборачиваемость {}", turnover.round_dp(2)));
+    }
+    report
+}
+
+/// Сжатие журналов хранилища
+///
+/// Журнал сделок сокращается до загружаемых при запуске сделок; при
+/// `retention_days` > 0 удаляются более старые снимки баланса и найденные возможности.
+pub async fn compact_history(store: &Store, retention_days: u32, now: DateTime<Utc>) -> Result<String> {
+    let (trades, annotations) = compact_trades(store).await?;
+    let mut summary = format!("сделок удалено: {}, заметок: {}", trades, annotations);
+    if retention_days > 0 {
+        let cutoff = now - chrono::Duration::days(retention_days.into());
+        let (before, after) = store.rewrite(BALANCE_COLLECTION, |mut snapshots: Vec<BalanceSnapshot>| {
+            snapshots.retain(|snapshot| snapshot.timestamp >= cutoff);
+            snapshots
+        }).await?;
+        summary.push_str(&format!(", снимков баланса: {}", before - after));
+        let (before, after) = store.rewrite(OPPORTUNITIES_COLLECTION, |mut signals: Vec<Signal>| {
+            signals.retain(|signal| signal.timestamp >= cutoff);
+            signals
+        }).await?;
+        summary.push_str(&format!(", возможностей: {}", before - after));
+    }
+    Ok(summary)
+}
@@ -1,6 +1,80 @@
+pub mod account_snapshot;
+pub mod ata_cleanup;
+pub mod balance_guard;
+pub mod balance_history;
+pub mod bus;
+#[cfg(feature = "chaos")]
+pub mod chaos;
 pub mod config;
+pub mod config_check;
+pub mod config_history;
+pub mod congestion;
+pub mod custom_amm;
 pub mod wallet;
+pub mod devnet;
+pub mod dry_run;
+pub mod failure;
+pub mod event_log;
+pub mod fees;
+pub mod fiat;
+pub mod flash_loan;
 pub mod dex;
+pub mod heatmap;
+pub mod history;
+pub mod jobs;
+pub mod lifecycle;
+pub mod log_filter;
+pub mod math;
+pub mod migrations;
+pub mod allocator;
+pub mod api_tokens;
 pub mod arbitrage;
+pub mod cli;
+pub mod clock;
+pub mod clock_skew;
 pub mod monitor;
+pub mod notify;
+pub mod ops_state;
+pub mod pair_discovery;
+pub mod openbook_v2;
+pub mod lifinity;
+pub mod saber;
+pub mod orca_api;
+pub mod pool_registry;
+pub mod price_check;
+pub mod price_snapshot;
+pub mod profile;
+pub mod presend;
+pub mod profit;
+pub mod proxy;
+pub mod pubsub;
+pub mod ranking;
+pub mod scan_budget;
+pub mod scan_stats;
+pub mod self_test;
+pub mod service;
+pub mod raydium_amm;
+pub mod raydium_api;
+pub mod receipt;
+pub mod reserve_guard;
+pub mod routing;
+pub mod rpc;
+pub mod rpc_cache;
+pub mod schedule;
+pub mod signal;
+pub mod store;
+pub mod supervisor;
+pub mod tokens;
+pub mod trace;
+pub mod trade_memo;
+pub mod trade_size;
+pub mod tx_error;
+pub mod tx_template;
+pub mod units;
+pub mod venue_limiter;
+pub mod venue_score;
+pub mod web;
+pub mod whirlpool;
+pub mod wsol;
+pub mod geyser;
 
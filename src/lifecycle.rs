@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// Состояние торговой задачи (цикл поиска и исполнения)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradingState {
+    /// Цикл работает
+    Running,
+    /// Цикл завершился с ошибкой и ждёт перезапуска через API
+    Failed,
+    /// Перезапуск запрошен, цикл ещё не запущен
+    Restarting,
+}
+
+/// Диагностика торговой задачи для API
+#[derive(Debug, Clone, Serialize)]
+pub struct TradingStatus {
+    pub state: TradingState,
+    /// Время последнего запуска цикла
+    pub started_at: Option<DateTime<Utc>>,
+    /// Время завершения последнего прогона цикла (поиск, ожидание очереди или пауза)
+    pub last_cycle_at: Option<DateTime<Utc>>,
+    /// Время последнего падения цикла
+    pub failed_at: Option<DateTime<Utc>>,
+    /// Ошибка, с которой завершился цикл
+    pub last_error: Option<String>,
+    /// Количество перезапусков через API
+    pub restarts: u32,
+}
+
+/// Управление жизненным циклом торговой задачи
+///
+/// Веб-сервер работает независимо от торгового цикла: если цикл завершился
+/// с ошибкой, API остаётся доступным для диагностики, а цикл перезапускается
+/// запросом `POST /api/control/restart`.
+#[derive(Debug, Clone)]
+pub struct TradingControl {
+    status: Arc<Mutex<TradingStatus>>,
+    restart: Arc<Notify>,
+}
+
+impl Default for TradingControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradingControl {
+    pub fn new() -> Self {
+        Self {
+            status: Arc::new(Mutex::new(TradingStatus {
+                state: TradingState::Restarting,
+                started_at: None,
+                last_cycle_at: None,
+                failed_at: None,
+                last_error: None,
+                restarts: 0,
+            })),
+            restart: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Текущее состояние
+    pub fn status(&self) -> TradingStatus {
+        self.lock().clone()
+    }
+
+    /// Цикл запущен
+    pub fn mark_running(&self, now: DateTime<Utc>) {
+        let mut status = self.lock();
+        status.state = TradingState::Running;
+        status.started_at = Some(now);
+    }
+
+    /// Прогон цикла завершён
+    pub fn mark_cycle(&self, now: DateTime<Utc>) {
+        self.lock().last_cycle_at = Some(now);
+    }
+
+    /// Завис ли работающий цикл: прогон не завершался дольше `timeout`
+    ///
+    /// Цикл, остановленный с ошибкой и ждущий перезапуска через API, не считается
+    /// зависшим — процесс работает, а автоматический перезапуск обошёл бы остановку.
+    pub fn stalled(&self, now: DateTime<Utc>, timeout: chrono::Duration) -> bool {
+        let status = self.lock();
+        if status.state != TradingState::Running {
+            return false;
+        }
+        match status.last_cycle_at.max(status.started_at) {
+            Some(last) => now - last > timeout,
+            None => false,
+        }
+    }
+
+    /// Цикл завершился с ошибкой
+    pub fn mark_failed(&self, error: &anyhow::Error, now: DateTime<Utc>) {
+        let mut status = self.lock();
+        status.state = TradingState::Failed;
+        status.failed_at = Some(now);
+        status.last_error = Some(format!("{:#}", error));
+    }
+
+    /// Запрос перезапуска; false, если цикл не в состоянии ошибки
+    pub fn request_restart(&self) -> bool {
+        let mut status = self.lock();
+        if status.state != TradingState::Failed {
+            return false;
+        }
+        status.state = TradingState::Restarting;
+        status.restarts += 1;
+        self.restart.notify_one();
+        true
+    }
+
+    /// Ожидание запроса перезапуска
+    pub async fn wait_for_restart(&self) {
+        self.restart.notified().await;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TradingStatus> {
+        self.status.lock().expect("мьютекс состояния торговой задачи отравлен")
+    }
+}
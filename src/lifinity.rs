@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address;
+use crate::math::Fee;
+
+/// Lifinity AMM v2 Program ID (только mainnet)
+pub const LIFINITY_V2_PROGRAM_ID: &str = "2wT8Yq49kHgDzXuPxZSaeLaH1qbmGXtEyPy64bL7aD3c";
+
+/// Размер аккаунта пула (discriminator Anchor + 903 байта)
+pub const AMM_ACCOUNT_SIZE: u64 = 911;
+/// Смещение mint токена A в аккаунте пула
+pub const AMM_TOKEN_A_MINT_OFFSET: usize = 254;
+/// Смещение mint токена B в аккаунте пула
+pub const AMM_TOKEN_B_MINT_OFFSET: usize = 286;
+
+/// Смещения полей аккаунта пула
+const FREEZE_TRADE_OFFSET: usize = 124;
+const TOKEN_A_ACCOUNT_OFFSET: usize = 158;
+const TOKEN_B_ACCOUNT_OFFSET: usize = 190;
+const POOL_MINT_OFFSET: usize = 222;
+const FEE_ACCOUNT_OFFSET: usize = 318;
+const ORACLE_MAIN_OFFSET: usize = 350;
+const ORACLE_SUB_OFFSET: usize = 382;
+const ORACLE_PC_OFFSET: usize = 414;
+const TRADE_FEE_NUMERATOR_OFFSET: usize = 446;
+const TRADE_FEE_DENOMINATOR_OFFSET: usize = 454;
+
+/// Magic аккаунтов Pyth (push oracle v2)
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+/// Тип аккаунта Pyth с ценой
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+/// Статус агрегированной цены Pyth: торгуется
+const PYTH_STATUS_TRADING: u32 = 1;
+
+/// Смещения полей ценового аккаунта Pyth
+const PYTH_ACCOUNT_TYPE_OFFSET: usize = 8;
+const PYTH_EXPONENT_OFFSET: usize = 20;
+const PYTH_TIMESTAMP_OFFSET: usize = 96;
+const PYTH_AGGREGATE_PRICE_OFFSET: usize = 208;
+const PYTH_AGGREGATE_STATUS_OFFSET: usize = 224;
+
+/// Размер ценового аккаунта Pyth до конца агрегированной цены
+const PYTH_PRICE_MIN_SIZE: usize = 240;
+
+/// Максимальный возраст цены оракула в секундах (по более старой цене курс пула неизвестен)
+pub const MAX_ORACLE_AGE_SEC: i64 = 60;
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .with_context(|| format!("Данные короче смещения {}", offset + N))?
+        .try_into()
+        .context("Некорректная длина поля")
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(data, offset)?))
+}
+
+/// Аккаунт пула Lifinity v2
+///
+/// Курс пула задаёт оракул (proactive market maker), резервы определяют
+/// только, сколько пул готов отдать.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amm {
+    /// Свопы остановлены администратором
+    pub freeze_trade: bool,
+    pub token_a_account: Pubkey,
+    pub token_b_account: Pubkey,
+    pub pool_mint: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub fee_account: Pubkey,
+    /// Цена токена A (Pyth)
+    pub oracle_main_account: Pubkey,
+    pub oracle_sub_account: Pubkey,
+    /// Цена токена B (Pyth); совпадает с oracle_main_account, если не используется
+    pub oracle_pc_account: Pubkey,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+}
+
+impl Amm {
+    /// Разбор данных аккаунта пула
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() as u64 != AMM_ACCOUNT_SIZE {
+            anyhow::bail!(
+                "Размер аккаунта {} байт не совпадает с пулом Lifinity v2 ({} байт)",
+                data.len(), AMM_ACCOUNT_SIZE
+            );
+        }
+        Ok(Self {
+            freeze_trade: data[FREEZE_TRADE_OFFSET] != 0,
+            token_a_account: read_pubkey(data, TOKEN_A_ACCOUNT_OFFSET)?,
+            token_b_account: read_pubkey(data, TOKEN_B_ACCOUNT_OFFSET)?,
+            pool_mint: read_pubkey(data, POOL_MINT_OFFSET)?,
+            token_a_mint: read_pubkey(data, AMM_TOKEN_A_MINT_OFFSET)?,
+            token_b_mint: read_pubkey(data, AMM_TOKEN_B_MINT_OFFSET)?,
+            fee_account: read_pubkey(data, FEE_ACCOUNT_OFFSET)?,
+            oracle_main_account: read_pubkey(data, ORACLE_MAIN_OFFSET)?,
+            oracle_sub_account: read_pubkey(data, ORACLE_SUB_OFFSET)?,
+            oracle_pc_account: read_pubkey(data, ORACLE_PC_OFFSET)?,
+            trade_fee_numerator: read_u64(data, TRADE_FEE_NUMERATOR_OFFSET)?,
+            trade_fee_denominator: read_u64(data, TRADE_FEE_DENOMINATOR_OFFSET)?,
+        })
+    }
+
+    /// Комиссия свопа
+    pub fn trade_fee(&self) -> Result<Fee> {
+        Fee::new(self.trade_fee_numerator, self.trade_fee_denominator)
+            .context("Некорректная комиссия пула Lifinity")
+    }
+
+    /// Используется ли отдельный оракул цены токена B
+    pub fn has_quote_oracle(&self) -> bool {
+        self.oracle_pc_account != self.oracle_main_account && self.oracle_pc_account != Pubkey::default()
+    }
+
+    /// Хранилище пула для токена и хранилище противоположного токена
+    pub fn vaults_for(&self, from_mint: &Pubkey) -> Result<(Pubkey, Pubkey)> {
+        if *from_mint == self.token_a_mint {
+            Ok((self.token_a_account, self.token_b_account))
+        } else if *from_mint == self.token_b_mint {
+            Ok((self.token_b_account, self.token_a_account))
+        } else {
+            anyhow::bail!("Токен {} не торгуется в пуле Lifinity", from_mint)
+        }
+    }
+}
+
+/// Цена из оракула Pyth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: Decimal,
+    /// Время публикации (unix-время)
+    pub published_at: i64,
+}
+
+impl OraclePrice {
+    /// Ошибка, если цена опубликована раньше чем MAX_ORACLE_AGE_SEC назад
+    pub fn ensure_fresh(&self, now: i64) -> Result<()> {
+        let age = now.saturating_sub(self.published_at);
+        if age > MAX_ORACLE_AGE_SEC {
+            anyhow::bail!("Цена оракула устарела: опубликована {} с назад", age);
+        }
+        Ok(())
+    }
+}
+
+/// Агрегированная цена из ценового аккаунта Pyth (push oracle v2)
+///
+/// Ошибка, если аккаунт не ценовой аккаунт Pyth или цена сейчас не торгуется.
+pub fn parse_pyth_price(data: &[u8]) -> Result<OraclePrice> {
+    if data.len() < PYTH_PRICE_MIN_SIZE || read_u32(data, 0)? != PYTH_MAGIC {
+        anyhow::bail!("Оракул не ценовой аккаунт Pyth");
+    }
+    if read_u32(data, PYTH_ACCOUNT_TYPE_OFFSET)? != PYTH_PRICE_ACCOUNT_TYPE {
+        anyhow::bail!("Аккаунт Pyth не содержит цену");
+    }
+    if read_u32(data, PYTH_AGGREGATE_STATUS_OFFSET)? != PYTH_STATUS_TRADING {
+        anyhow::bail!("Цена Pyth сейчас не публикуется (статус не trading)");
+    }
+    let exponent = i32::from_le_bytes(read_bytes(data, PYTH_EXPONENT_OFFSET)?);
+    let raw = i64::from_le_bytes(read_bytes(data, PYTH_AGGREGATE_PRICE_OFFSET)?);
+    if raw <= 0 {
+        anyhow::bail!("Некорректная цена Pyth: {}", raw);
+    }
+    let scale = u32::try_from(-exponent)
+        .ok()
+        .filter(|&scale| scale <= 28)
+        .with_context(|| format!("Неподдерживаемая экспонента цены Pyth: {}", exponent))?;
+    Ok(OraclePrice {
+        price: Decimal::new(raw, scale),
+        published_at: i64::from_le_bytes(read_bytes(data, PYTH_TIMESTAMP_OFFSET)?),
+    })
+}
+
+/// Цена токена A в токенах B по оракулам пула
+///
+/// `quote_oracle` — цена токена B (None, если пул котирует токен A сразу к токену B).
+pub fn pool_price(main_oracle: &OraclePrice, quote_oracle: Option<&OraclePrice>) -> Result<Decimal> {
+    match quote_oracle {
+        Some(quote) => main_oracle.price.checked_div(quote.price).context("Переполнение при расчёте цены"),
+        None => Ok(main_oracle.price),
+    }
+}
+
+/// Адрес authority пула (PDA от адреса пула)
+pub fn pool_authority(program_id: &Pubkey, amm_address: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[amm_address.as_ref()], program_id).0
+}
+
+/// Discriminator инструкции Anchor
+fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+/// Инструкция swap: amount_in токена `from_mint` на не меньше min_amount_out
+///
+/// Минимальный выход проверяет программа. Токены списываются с ATA кошелька
+/// и зачисляются на ATA кошелька.
+pub fn swap_instruction(
+    program_id: &Pubkey,
+    amm_address: &Pubkey,
+    amm: &Amm,
+    user_wallet: &Pubkey,
+    from_mint: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<Instruction> {
+    let (swap_source, swap_destination) = amm.vaults_for(from_mint)?;
+    let to_mint = if *from_mint == amm.token_a_mint { amm.token_b_mint } else { amm.token_a_mint };
+    let accounts = vec![
+        AccountMeta::new_readonly(pool_authority(program_id, amm_address), false),
+        AccountMeta::new(*amm_address, false),
+        AccountMeta::new_readonly(*user_wallet, true),
+        AccountMeta::new(get_associated_token_address(user_wallet, from_mint), false),
+        AccountMeta::new(get_associated_token_address(user_wallet, &to_mint), false),
+        AccountMeta::new(swap_source, false),
+        AccountMeta::new(swap_destination, false),
+        AccountMeta::new(amm.pool_mint, false),
+        AccountMeta::new(amm.fee_account, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(amm.oracle_main_account, false),
+        AccountMeta::new_readonly(amm.oracle_sub_account, false),
+        AccountMeta::new_readonly(amm.oracle_pc_account, false),
+    ];
+
+    let mut data = Vec::with_capacity(24);
+    data.extend_from_slice(&instruction_discriminator("swap"));
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
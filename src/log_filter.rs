@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{OnceLock, RwLock};
+
+/// Префикс целей логов модулей бота (`arb_bot::dex` задаётся как `dex`)
+const CRATE_PREFIX: &str = "arb_bot::";
+
+/// Уровень логирования по умолчанию, если RUST_LOG не задан (как у env_logger)
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Error;
+
+/// Фильтр логов: уровень по умолчанию и уровни по модулям
+///
+/// Формат как у RUST_LOG: `info,dex=debug,web=warn`. Модуль без уровня
+/// включает для него все логи, уровень без модуля задаёт уровень по умолчанию.
+/// Модули бота указываются без префикса крейта; для вложенного модуля
+/// действует самое длинное совпадение.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// Модули и уровни, от самого длинного модуля
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self { default: DEFAULT_LEVEL, modules: Vec::new() }
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut filter = LogFilter::default();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            if directive.contains('/') {
+                anyhow::bail!("Фильтр по тексту сообщения не поддерживается: {}", directive);
+            }
+            match directive.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    if module.is_empty() {
+                        anyhow::bail!("Не указан модуль в {}", directive);
+                    }
+                    let level = LevelFilter::from_str(level.trim())
+                        .with_context(|| format!("Неизвестный уровень логирования в {}", directive))?;
+                    filter.set_module(module, level);
+                }
+                // Только уровень — уровень по умолчанию, только модуль — все логи модуля
+                None => match LevelFilter::from_str(directive) {
+                    Ok(level) => filter.default = level,
+                    Err(_) => filter.set_module(directive, LevelFilter::Trace),
+                },
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.as_str().to_lowercase())?;
+        // В порядке от общего модуля к вложенному
+        let mut modules: Vec<_> = self.modules.iter().collect();
+        modules.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then_with(|| a.0.cmp(&b.0)));
+        for (module, level) in modules {
+            write!(f, ",{}={}", module, level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl LogFilter {
+    fn set_module(&mut self, module: &str, level: LevelFilter) {
+        let module = module.strip_prefix(CRATE_PREFIX).unwrap_or(module).to_string();
+        self.modules.retain(|(existing, _)| *existing != module);
+        self.modules.push((module, level));
+        self.modules.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+    }
+
+    /// Уровень для цели лога (`record.target()`)
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let target = target.strip_prefix(CRATE_PREFIX).unwrap_or(target);
+        self.modules.iter()
+            .find(|(module, _)| {
+                target == module
+                    || (target.starts_with(module.as_str()) && target[module.len()..].starts_with("::"))
+            })
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    /// Самый подробный уровень фильтра (для log::set_max_level)
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules.iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+fn filter() -> &'static RwLock<LogFilter> {
+    static FILTER: OnceLock<RwLock<LogFilter>> = OnceLock::new();
+    FILTER.get_or_init(|| RwLock::new(LogFilter::default()))
+}
+
+/// Текущий фильтр логов
+pub fn current() -> LogFilter {
+    filter().read().expect("блокировка фильтра логов отравлена").clone()
+}
+
+/// Замена фильтра логов без перезапуска (ошибка разбора оставляет прежний фильтр)
+pub fn set(spec: &str) -> Result<LogFilter> {
+    let parsed = LogFilter::from_str(spec)?;
+    *filter().write().expect("блокировка фильтра логов отравлена") = parsed.clone();
+    log::set_max_level(parsed.max_level());
+    Ok(parsed)
+}
+
+/// Логгер env_logger с фильтром, изменяемым во время работы
+struct RuntimeLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for RuntimeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= filter().read().expect("блокировка фильтра логов отравлена").level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Установка глобального логгера: формат из builder, фильтр из RUST_LOG
+///
+/// Фильтрацию выполняет `LogFilter`, поэтому собственный фильтр builder
+/// пропускает всё. Некорректный RUST_LOG заменяется уровнем по умолчанию.
+pub fn init(mut builder: env_logger::Builder) {
+    let spec = std::env::var("RUST_LOG").unwrap_or_default();
+    if let Err(e) = set(&spec) {
+        eprintln!("Некорректный RUST_LOG ({:#}), используется уровень {}", e, DEFAULT_LEVEL);
+    }
+    let inner = builder.filter_level(LevelFilter::Trace).build();
+    if log::set_boxed_logger(Box::new(RuntimeLogger { inner })).is_err() {
+        eprintln!("Логгер уже установлен");
+        return;
+    }
+    log::set_max_level(current().max_level());
+}
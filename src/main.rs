@@ -2,37 +2,166 @@ use anyhow::Result;
 use log::info;
 use std::process;
 
+mod account_snapshot;
+mod ata_cleanup;
+mod balance_guard;
+mod balance_history;
+mod bus;
+// Сбои включают только стресс-тесты через библиотеку
+#[cfg(feature = "chaos")]
+#[allow(dead_code)]
+mod chaos;
 mod config;
+mod config_check;
+mod config_history;
+mod congestion;
+mod custom_amm;
 mod wallet;
+mod devnet;
+mod dry_run;
+mod failure;
+mod event_log;
+mod fees;
+mod fiat;
+mod flash_loan;
 mod dex;
+mod heatmap;
+mod history;
+mod jobs;
+mod lifecycle;
+mod log_filter;
+mod migrations;
+mod math;
+mod allocator;
+mod api_tokens;
 mod arbitrage;
+mod cli;
+mod clock;
+mod clock_skew;
 mod monitor;
+mod notify;
+mod ops_state;
+mod pair_discovery;
+mod openbook_v2;
+mod lifinity;
+mod saber;
+mod orca_api;
+mod pool_registry;
+mod price_check;
+mod price_snapshot;
+mod profile;
+mod presend;
+mod profit;
+mod proxy;
+mod pubsub;
+mod ranking;
+mod scan_budget;
+mod scan_stats;
+mod self_test;
+mod service;
+mod raydium_amm;
+mod raydium_api;
+mod receipt;
+mod reserve_guard;
+mod routing;
+mod rpc;
+mod rpc_cache;
+mod schedule;
+mod signal;
+mod store;
+mod supervisor;
+mod tokens;
+mod trace;
+mod trade_memo;
+mod trade_size;
+mod tx_error;
+mod tx_template;
+mod units;
+mod venue_limiter;
+mod venue_score;
+mod geyser;
 mod web;
+mod whirlpool;
+mod wsol;
 
-use config::Config;
+use cli::Command;
+use config::{Config, ExecutionMode};
 use monitor::Monitor;
+use scan_stats::SkipReason;
 use std::sync::Arc;
 
 /// Точка входа в приложение
 #[tokio::main]
 async fn main() {
-    // Инициализация логирования
-    env_logger::Builder::from_default_env()
-        .format_timestamp_secs()
-        .format_level(true)
-        .init();
+    // Инициализация логирования (с идентификатором запроса/прогона, если он есть);
+    // уровни по модулям меняются без перезапуска через PUT /api/logging
+    let mut logger = env_logger::Builder::new();
+    logger
+        .format(|buf, record| {
+            use std::io::Write;
+            let trace_id = trace::current()
+                .map(|id| format!(" {}", id))
+                .unwrap_or_default();
+            writeln!(
+                buf,
+                "[{} {:<5} {}{}] {}",
+                buf.timestamp_seconds(),
+                record.level(),
+                record.target(),
+                trace_id,
+                record.args()
+            )
+        });
+    log_filter::init(logger);
+
+    // Разбор аргументов командной строки
+    let command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            eprintln!("Ошибка аргументов командной строки: {}", e);
+            process::exit(2);
+        }
+    };
+
+    // Проверка конфигурации для CI/деплоя выполняется без запуска бота
+    if command == Command::CheckConfig {
+        process::exit(run_check_config());
+    }
+    if command == Command::CheckMigrations {
+        process::exit(run_check_migrations().await);
+    }
+    if command == Command::Healthcheck {
+        process::exit(run_healthcheck().await);
+    }
 
     info!("=== Запуск арбитражного бота Solana ===");
 
     // Загрузка конфигурации
     let config = match Config::load() {
-        Ok(cfg) => {
+        Ok(mut cfg) => {
+            // Прогон --once транзакций не отправляет
+            if command == Command::RunOnce {
+                cfg.safety.simulation_mode = true;
+            }
             info!("Конфигурация загружена успешно");
-            if cfg.safety.simulation_mode {
+            if cfg.safety.mode == ExecutionMode::Signal {
+                info!("📡 РЕЖИМ СИГНАЛОВ - возможности публикуются, сделки не исполняются");
+            } else if cfg.safety.mode == ExecutionMode::Scan {
+                info!("📡 РЕЖИМ ПОИСКА - возможности передаются в очередь, ключ кошелька не загружается");
+            } else if cfg.safety.mode == ExecutionMode::ExecuteOnly && cfg.safety.simulation_mode {
+                info!("⚠️  РЕЖИМ ИСПОЛНЕНИЯ ИЗ ОЧЕРЕДИ (СИМУЛЯЦИЯ) - реальные транзакции не выполняются");
+            } else if cfg.safety.mode == ExecutionMode::ExecuteOnly {
+                info!("⚠️  РЕЖИМ ИСПОЛНЕНИЯ ИЗ ОЧЕРЕДИ - реальные транзакции будут выполняться");
+            } else if cfg.safety.simulation_mode {
                 info!("⚠️  РЕЖИМ СИМУЛЯЦИИ АКТИВЕН - реальные транзакции не выполняются");
             } else {
                 info!("⚠️  РЕЖИМ ПРОДАКШН - реальные транзакции будут выполняться");
             }
+            for pair in &cfg.dex.trading_pairs {
+                if pair.split('/').any(|token| !cfg.safety.token_allowed(token)) {
+                    log::warn!("Пара {} содержит токен вне safety.allowed_tokens: только отслеживание", pair);
+                }
+            }
             cfg
         }
         Err(e) => {
@@ -41,12 +170,40 @@ async fn main() {
         }
     };
 
+    // Единый источник времени для цикла и фоновых задач
+    let clock = clock::system_clock();
+
+    // Все фоновые задачи работают под надзором с автоматическим перезапуском
+    let supervisor = supervisor::Supervisor::new(&config.supervisor);
+
+    // Запуск отправки уведомлений (если включена)
+    let notifier = match notify::spawn_notifier(&supervisor, &config.notifications, &config.network.proxy, clock.clone()) {
+        Ok(n) => n,
+        Err(e) => {
+            eprintln!("Ошибка инициализации уведомлений: {}", e);
+            process::exit(1);
+        }
+    };
+
     // Инициализация монитора
-    let monitor = Monitor::new(&config);
+    let monitor = Monitor::new(&config)
+        .with_notifier(notifier)
+        .with_supervisor(supervisor.clone());
+
+    // Контроль расхождения локальных часов с временем кластера
+    clock_skew::spawn_clock_skew_monitor(&supervisor, &config, monitor.clock_skew().clone());
 
-    // Инициализация кошелька
-    let wallet = match wallet::Wallet::new(&config) {
-        Ok(w) => {
+    // Повышение порога прибыли во время перегрузки сети
+    congestion::spawn_congestion_monitor(&supervisor, &config.network, monitor.congestion().clone());
+
+    // Инициализация кошелька: процессу поиска ключ не нужен и не выдаётся
+    let load_key = config.safety.mode != ExecutionMode::Scan || matches!(command, Command::DevnetAirdrop { .. });
+    let wallet = match load_key.then(|| wallet::Wallet::new(&config)).transpose() {
+        Ok(None) => {
+            info!("Ключ кошелька не загружается (mode = \"scan\")");
+            Arc::new(wallet::Wallet::ephemeral())
+        }
+        Ok(Some(w)) => {
             info!("Кошелёк инициализирован");
             Arc::new(w)
         }
@@ -56,8 +213,65 @@ async fn main() {
         }
     };
 
+    // Вспомогательные команды devnet выполняются вместо основного цикла
+    if let Command::DevnetAirdrop { sol, mints } = command {
+        if let Err(e) = run_devnet_airdrop(&config, &wallet, sol, &mints).await {
+            eprintln!("Ошибка devnet airdrop: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    if let Command::DevnetSeedPool { amount } = command {
+        if let Err(e) = run_devnet_seed_pool(&config, &wallet, amount).await {
+            eprintln!("Ошибка создания тестового пула: {:#}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // Подписка Yellowstone gRPC: RPC клиенты читают аккаунты пулов из её кеша
+    if config.network.data_source == config::DataSource::Geyser {
+        geyser::spawn(config.network.clone());
+        info!("Подписка Yellowstone gRPC запущена");
+    }
+    // Подписка accountSubscribe через WebSocket наполняет тот же кеш
+    if config.network.data_source == config::DataSource::Websocket {
+        pubsub::spawn(config.network.clone());
+        info!("Подписка WebSocket запущена");
+    }
+
+    // Хранилище истории и запись снимков баланса
+    let store = match store::Store::open(&config.storage).await {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Ошибка открытия хранилища: {}", e);
+            process::exit(1);
+        }
+    };
+    match migrations::migrate(&store).await {
+        Ok(applied) if !applied.is_empty() => {
+            info!("Хранилище обновлено до версии схемы {}", migrations::latest_version());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Ошибка миграции хранилища: {:#}", e);
+            process::exit(1);
+        }
+    }
+
+    // Журнал сделок сохраняется в хранилище вместе с заметками оператора
+    let monitor = monitor.with_store(store.clone());
+    match monitor.restore_trade_history().await {
+        Ok(count) => info!("Загружено сделок из журнала: {}", count),
+        Err(e) => log::warn!("Не удалось загрузить журнал сделок: {}", e),
+    }
+    match monitor.fees().restore().await {
+        Ok(count) => info!("Загружено расходов на транзакции: {}", count),
+        Err(e) => log::warn!("Не удалось загрузить расходы на транзакции: {}", e),
+    }
+
     // Инициализация DEX менеджера
-    let dex_manager = match dex::DexManager::new(&config) {
+    let dex_manager = match dex::DexManager::new_with_clock_skew(&config, monitor.clock_skew().clone()) {
         Ok(dm) => {
             info!("DEX менеджер инициализирован");
             dm
@@ -68,13 +282,71 @@ async fn main() {
         }
     };
 
+    // Заготовки транзакций для приоритетных пар (сборка вне горячего пути)
+    if config.templates.enabled && config.safety.mode.executes() {
+        let prepared = dex_manager.prepare_templates(&config.templates, wallet.pubkey()).await;
+        info!("Шаблонов транзакций подготовлено: {}", prepared);
+    }
+
+    // Самопроверка адаптеров на известных пулах до начала торговли (devnet или simulation_mode)
+    if let Some(report) = self_test::run_at_startup(&config, &dex_manager, &wallet).await {
+        monitor.self_test().set(report);
+    }
+
     // Инициализация движка арбитража
     let arb_engine = arbitrage::ArbitrageEngine::new(
         config.clone(),
         wallet.clone(),
         dex_manager,
         monitor.clone(),
-    );
+    ).with_clock(clock.clone());
+
+    // Один прогон с отчётом вместо торгового цикла
+    if command == Command::RunOnce {
+        process::exit(run_once(&config, &arb_engine, monitor.self_test().report()).await);
+    }
+
+    // Расписание окон обслуживания (валидировано при загрузке конфигурации)
+    let schedule = match schedule::Schedule::from_config(&config.schedule) {
+        Ok(s) => schedule::ScheduleControl::new(s),
+        Err(e) => {
+            eprintln!("Ошибка расписания: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let venue_limiter = arb_engine.venue_limiter();
+    let dex_manager = arb_engine.dex_manager();
+    // Кошелёк с заменой ключа без перезапуска (общий для движка, веб-сервера и снимков баланса)
+    let hot_wallet = arb_engine.wallet();
+    // Регулярные задачи обслуживания ([jobs]): отчёты, журналы, снимки баланса, пары, пулы, ALT
+    if let Err(e) = jobs::spawn_maintenance(
+        &supervisor,
+        &config,
+        &monitor,
+        hot_wallet.clone(),
+        store.clone(),
+        dex_manager.clone(),
+        clock.clone(),
+    ) {
+        eprintln!("Ошибка запуска регулярных задач: {:#}", e);
+        process::exit(1);
+    }
+    // Проверка RPC и баланса для gauge состояния (/api/metrics/prometheus)
+    ops_state::spawn_state_probe(&supervisor, &config, hot_wallet.clone(), monitor.ops_state().clone());
+
+    // Очередь возможностей между процессами поиска и исполнения
+    let bus = if config.safety.mode.uses_bus() {
+        match bus::OpportunityBus::new(&config.bus) {
+            Ok(b) => Some(Arc::new(b)),
+            Err(e) => {
+                eprintln!("Ошибка инициализации очереди возможностей: {:#}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
 
     // Обёртка движка арбитража для совместного использования
     let arb_engine_shared = Arc::new(tokio::sync::Mutex::new(arb_engine));
@@ -84,25 +356,224 @@ async fn main() {
         let web_state = web::create_state(
             config.clone(),
             monitor.clone(),
-            wallet.clone(),
+            hot_wallet.clone(),
             arb_engine_shared.clone(),
+            schedule.clone(),
+            store.clone(),
+            venue_limiter.clone(),
+            dex_manager.clone(),
         );
         
         let web_config = config.clone();
-        tokio::spawn(async move {
-            if let Err(e) = web::start_server(web_state, &web_config).await {
-                log::error!("Ошибка веб-сервера: {}", e);
-            }
+        supervisor.spawn("web", move || {
+            let web_state = web_state.clone();
+            let web_config = web_config.clone();
+            async move { web::start_server(web_state, &web_config).await }
         });
         info!("Веб-сервер запущен на http://{}:{}", config.web.bind_address, config.web.port);
     }
 
-    // Запуск основного цикла
-    let arb_engine_for_loop = arb_engine_shared.clone();
-    if let Err(e) = run_arbitrage_loop(arb_engine_for_loop, config, monitor).await {
-        eprintln!("Критическая ошибка: {}", e);
-        process::exit(1);
+    // Профилирование этапов конвейера (--profile)
+    let profiler = if let Command::Run { profile: true } = command {
+        match profile::Profiler::start(&config.storage.data_dir.join("profiles")).await {
+            Ok(p) => Some(p),
+            Err(e) => {
+                eprintln!("Ошибка запуска профилирования: {}", e);
+                process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Пинги watchdog systemd, пока торговый цикл завершает прогоны
+    service::spawn_watchdog(&supervisor, &config, monitor.trading().clone(), clock.clone());
+
+    // Запуск основного цикла: при его остановке веб-сервер остаётся доступен
+    let trading_config = config.clone();
+    supervisor.spawn("trading", move || {
+        run_trading(
+            arb_engine_shared.clone(),
+            trading_config.clone(),
+            monitor.clone(),
+            schedule.clone(),
+            clock.clone(),
+            profiler.clone(),
+            bus.clone(),
+        )
+    });
+
+    // Предварительные проверки пройдены (конфигурация, кошелёк, хранилище, DEX)
+    service::notify_ready(&format!("Режим {:?}, DEX: {}", config.safety.mode, config.dex.enabled_dexes.join(", ")));
+
+    // Задачи работают под надзором; процесс завершается по сигналу
+    std::future::pending::<()>().await;
+}
+
+/// Торговая задача: цикл поиска и исполнения с перезапуском через API
+///
+/// Если движок остановил исполнение (критическая ошибка или лимит неудач),
+/// состояние и причина публикуются в /api/status, а новый запуск ждёт запроса
+/// POST /api/control/restart. Без веб-сервера ждать некому — процесс завершается.
+/// Паника цикла обрабатывается надзором: задача перезапускается с паузой.
+async fn run_trading(
+    engine: Arc<tokio::sync::Mutex<arbitrage::ArbitrageEngine>>,
+    config: Config,
+    monitor: Monitor,
+    schedule: schedule::ScheduleControl,
+    clock: clock::SharedClock,
+    profiler: Option<profile::Profiler>,
+    bus: Option<Arc<bus::OpportunityBus>>,
+) -> Result<()> {
+    let trading = monitor.trading().clone();
+    loop {
+        trading.mark_running(clock.now());
+        let error = match run_arbitrage_loop(
+            engine.clone(),
+            config.clone(),
+            monitor.clone(),
+            schedule.clone(),
+            clock.clone(),
+            profiler.clone(),
+            bus.clone(),
+        ).await {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        trading.mark_failed(&error, clock.now());
+        if !config.web.enabled {
+            eprintln!("Критическая ошибка: {:#}", error);
+            process::exit(1);
+        }
+        monitor.log_critical(&format!(
+            "Торговый цикл остановлен: {:#}. Веб-сервер работает, перезапуск — POST /api/control/restart",
+            error
+        ));
+
+        trading.wait_for_restart().await;
+        engine.lock().await.resume();
+        info!("Перезапуск торгового цикла по запросу API");
+    }
+}
+
+/// Проверка конфигурации: вывод всех замечаний и код выхода (0 — нет ошибок)
+fn run_check_config() -> i32 {
+    let config_path = Config::path();
+    let config = match Config::read() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("[ОШИБКА] {:#}", e);
+            return 1;
+        }
+    };
+
+    let report = config_check::check_config(&config);
+    for finding in &report.findings {
+        println!("{}", finding);
     }
+
+    let errors = report.by_severity(config_check::Severity::Error).count();
+    let warnings = report.by_severity(config_check::Severity::Warning).count();
+    println!("{:?}: ошибок — {}, предупреждений — {}", config_path, errors, warnings);
+
+    if report.has_errors() { 1 } else { 0 }
+}
+
+/// Прогон `run --once`: отчёт в stdout (JSON) и код выхода (0 — проверки и симуляции пройдены)
+async fn run_once(
+    config: &Config,
+    engine: &arbitrage::ArbitrageEngine,
+    self_test: Option<self_test::SelfTestReport>,
+) -> i32 {
+    let simulator = self_test::RpcSimulator::new(&config.network);
+    let report = dry_run::run(engine, config_check::check_config(config), self_test, &simulator).await;
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("[ОШИБКА] {:#}", e);
+            return 1;
+        }
+    }
+    if report.passed { 0 } else { 1 }
+}
+
+/// Проверка миграций хранилища: список недостающих и код выхода (0 — схема актуальна)
+async fn run_check_migrations() -> i32 {
+    let config = match Config::read() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("[ОШИБКА] {:#}", e);
+            return 1;
+        }
+    };
+    let status = match store::Store::open(&config.storage).await
+        .and_then(|store| migrations::status(&store))
+    {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("[ОШИБКА] {:#}", e);
+            return 1;
+        }
+    };
+
+    for migration in &status.pending {
+        println!("[ОЖИДАЕТ] {}: {}", migration.version, migration.description);
+    }
+    println!(
+        "{:?}: версия схемы {}, актуальная {}, миграций к применению — {}",
+        config.storage.data_dir, status.current, status.latest, status.pending.len()
+    );
+
+    if status.pending.is_empty() { 0 } else { 1 }
+}
+
+/// Проверка здоровья работающего бота: код выхода 0, если /health ответил 2xx
+async fn run_healthcheck() -> i32 {
+    let config = match Config::read() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("[ОШИБКА] {:#}", e);
+            return 1;
+        }
+    };
+    match service::healthcheck(&config.web).await {
+        Ok(()) => {
+            println!("healthy");
+            0
+        }
+        Err(e) => {
+            eprintln!("[ОШИБКА] {:#}", e);
+            1
+        }
+    }
+}
+
+/// Подготовка кошелька на devnet: пополнение SOL до `sol` и создание ATA
+async fn run_devnet_airdrop(
+    config: &Config,
+    wallet: &wallet::Wallet,
+    sol: rust_decimal::Decimal,
+    mints: &[String],
+) -> Result<()> {
+    let balance = devnet::ensure_funded(&config.network, wallet, sol).await?;
+    devnet::create_token_accounts(&config.network, wallet, mints).await?;
+
+    info!("Баланс кошелька {}: {} lamports", wallet.pubkey(), balance);
+    Ok(())
+}
+
+/// Создание тестового пула на devnet и вывод его адресов
+async fn run_devnet_seed_pool(config: &Config, wallet: &wallet::Wallet, amount: u64) -> Result<()> {
+    let amount = amount.checked_mul(10u64.pow(u32::from(devnet::TEST_TOKEN_DECIMALS)))
+        .ok_or_else(|| anyhow::anyhow!("Слишком большой резерв пула: {}", amount))?;
+    let pool = devnet::seed_test_pool(&config.network, wallet, amount).await?;
+
+    info!("Тестовый пул SPL Token Swap: {}", pool.swap);
+    info!("Токен A: {} (резерв {})", pool.mint_a, pool.token_a);
+    info!("Токен B: {} (резерв {})", pool.mint_b, pool.token_b);
+    info!("Mint LP: {}", pool.pool_mint);
+    Ok(())
 }
 
 /// Основной цикл поиска и выполнения арбитража
@@ -110,49 +581,179 @@ async fn run_arbitrage_loop(
     engine: Arc<tokio::sync::Mutex<arbitrage::ArbitrageEngine>>,
     config: Config,
     monitor: Monitor,
+    schedule: schedule::ScheduleControl,
+    clock: clock::SharedClock,
+    profiler: Option<profile::Profiler>,
+    bus: Option<Arc<bus::OpportunityBus>>,
 ) -> Result<()> {
     let check_interval = std::time::Duration::from_millis(config.monitoring.check_interval_ms);
+    let mut paused_reason: Option<String> = None;
+    let mode = config.safety.mode;
+
+    let trading = monitor.trading().clone();
+    // С подпиской WebSocket прогон начинается по изменению аккаунта пула
+    let mut account_changes = (config.network.data_source == config::DataSource::Websocket)
+        .then(|| geyser::cache().changes());
 
     loop {
-        let opportunities = {
-            let engine_guard = engine.lock().await;
-            engine_guard.find_opportunities().await
-        };
+        // Прошлый прогон завершён: отметка для /health и watchdog systemd
+        trading.mark_cycle(clock.now());
+
+        // Приостановка исполнения по расписанию или вручную через API
+        let pause = schedule.pause_reason(clock.now());
+        if pause != paused_reason {
+            match pause {
+                Some(ref reason) => log::warn!("⏸  Исполнение сделок приостановлено: {}", reason),
+                None => log::info!("▶  Исполнение сделок возобновлено"),
+            }
+            paused_reason = pause;
+        }
+        if paused_reason.is_some() {
+            clock.sleep(check_interval).await;
+            continue;
+        }
+
+        // Процесс исполнения ждёт возможностей из очереди вместо собственного поиска
+        if let (ExecutionMode::ExecuteOnly, Some(bus)) = (mode, bus.as_deref()) {
+            let queued = match bus.receive(check_interval).await {
+                Ok(queued) => bus.fresh(queued, clock.now()),
+                Err(e) => {
+                    log::error!("Ошибка очереди возможностей: {:#}", e);
+                    clock.sleep(check_interval).await;
+                    continue;
+                }
+            };
+            let Some(first) = queued.first() else { continue };
+            // Исполнение продолжает идентификатор прогона поиска, нашедшего возможности
+            let trace_id = first.trace_id.as_deref()
+                .and_then(trace::TraceId::from_external)
+                .unwrap_or_else(|| trace::TraceId::new("exec"));
+            let opportunities = queued.into_iter().map(|queued| queued.opportunity).collect();
+            let cycle = profile::scope(profiler.clone(), run_queued_cycle(&engine, &monitor, opportunities));
+            trace::scope(trace_id, cycle).await?;
+            continue;
+        }
+
+        // Каждый прогон поиска и исполнения получает свой идентификатор для логов и сделок
+        let cycle = profile::scope(profiler.clone(), run_scan_cycle(&engine, &monitor, mode, bus.as_deref()));
+        trace::scope(trace::TraceId::new("scan"), cycle).await?;
+
+        wait_next_scan(&clock, check_interval, account_changes.as_mut()).await;
+    }
+}
 
-        match opportunities {
-            Ok(opportunities) => {
-                if opportunities.is_empty() {
-                    log::debug!("Арбитражные возможности не найдены");
-                } else {
-                    log::info!("Найдено {} возможностей", opportunities.len());
-                    for opp in opportunities {
-                        log::info!("Возможность: {} -> {} (прибыль: {:.2}%, после комиссий: {:.2}%)", 
-                            opp.from_dex, opp.to_dex, opp.profit_percent, opp.profit_percent_after_fees);
-                        
-                        let result = {
-                            let mut engine_guard = engine.lock().await;
-                            engine_guard.execute_arbitrage(opp).await
-                        };
-                        
-                        match result {
-                            Ok(_) => {
-                                // Успешное выполнение - счётчик неудач уже сброшен в execute_arbitrage
-                            }
-                            Err(e) => {
-                                log::error!("Ошибка выполнения арбитража: {}", e);
-                                // Проверка лимита неудач (execute_arbitrage уже проверил, но на всякий случай)
-                                // Если достигнут лимит, execute_arbitrage вернёт ошибку, которую нужно обработать
-                            }
-                        }
+/// Пауза до следующего прогона поиска: check_interval или, с подпиской
+/// WebSocket, изменение аккаунта в кеше (в том числе во время прошлого прогона)
+async fn wait_next_scan(
+    clock: &clock::SharedClock,
+    check_interval: std::time::Duration,
+    account_changes: Option<&mut tokio::sync::watch::Receiver<u64>>,
+) {
+    let Some(changes) = account_changes else {
+        clock.sleep(check_interval).await;
+        return;
+    };
+    tokio::select! {
+        _ = clock.sleep(check_interval) => {}
+        _ = changes.changed() => {}
+    }
+}
+
+/// Один прогон конвейера: поиск возможностей, публикация и исполнение
+///
+/// В режиме сигналов возможности только публикуются, в режиме поиска —
+/// ещё и передаются в очередь процессу исполнения. Ошибка возвращается,
+/// только если движок остановил исполнение (критическая ошибка или лимит
+/// неудач) — тогда торговый цикл завершается.
+async fn run_scan_cycle(
+    engine: &Arc<tokio::sync::Mutex<arbitrage::ArbitrageEngine>>,
+    monitor: &Monitor,
+    mode: ExecutionMode,
+    bus: Option<&bus::OpportunityBus>,
+) -> Result<()> {
+    let opportunities = {
+        let engine_guard = engine.lock().await;
+        engine_guard.scan().await
+    };
+
+    match opportunities {
+        Ok(opportunities) => {
+            if opportunities.is_empty() {
+                log::debug!("Арбитражные возможности не найдены");
+            } else {
+                log::info!("Найдено {} возможностей", opportunities.len());
+                for opp in &opportunities {
+                    log::info!("Возможность: {} -> {} (прибыль: {:.2}%, после комиссий: {:.2}%)", 
+                        opp.from_dex, opp.to_dex, opp.profit_percent, opp.profit_percent_after_fees);
+                    monitor.publish_opportunity(opp);
+                }
+                if let Some(bus) = bus {
+                    // Сбой очереди не останавливает поиск: возможности этого прогона теряются
+                    if let Err(e) = bus.publish(&opportunities, chrono::Utc::now()).await {
+                        log::error!("Не удалось передать возможности в очередь: {:#}", e);
                     }
                 }
+                if !mode.executes() {
+                    monitor.scan_stats().record_skipped(SkipReason::NoExecution, opportunities.len());
+                    return Ok(());
+                }
+                execute_opportunities(engine, monitor, opportunities).await?;
             }
-            Err(e) => {
-                log::error!("Ошибка поиска возможностей: {}", e);
-            }
         }
+        Err(e) => {
+            log::error!("Ошибка поиска возможностей: {}", e);
+        }
+    }
+    Ok(())
+}
 
-        tokio::time::sleep(check_interval).await;
+/// Прогон процесса исполнения: возможности, полученные из очереди
+async fn run_queued_cycle(
+    engine: &Arc<tokio::sync::Mutex<arbitrage::ArbitrageEngine>>,
+    monitor: &Monitor,
+    opportunities: Vec<arbitrage::ArbitrageOpportunity>,
+) -> Result<()> {
+    log::info!("Получено из очереди {} возможностей", opportunities.len());
+    for opp in &opportunities {
+        monitor.publish_opportunity(opp);
+    }
+    execute_opportunities(engine, monitor, opportunities).await
+}
+
+/// Распределение капитала и исполнение возможностей прогона
+///
+/// Возможности без общих токенов и DEX исполняются одновременно
+/// (allocation.parallel_execution).
+async fn execute_opportunities(
+    engine: &Arc<tokio::sync::Mutex<arbitrage::ArbitrageEngine>>,
+    monitor: &Monitor,
+    opportunities: Vec<arbitrage::ArbitrageOpportunity>,
+) -> Result<()> {
+    let batches = {
+        let engine_guard = engine.lock().await;
+        let opportunities = engine_guard.allocate(engine_guard.executable(opportunities));
+        engine_guard.batches(opportunities)
+    };
+    let mut batches = batches.into_iter();
+    for batch in batches.by_ref() {
+        let results = {
+            let mut engine_guard = engine.lock().await;
+            engine_guard.execute_batch(batch).await
+        };
+
+        // Успешное выполнение - счётчик неудач уже сброшен в execute_batch
+        for e in results.into_iter().filter_map(Result::err) {
+            log::error!("Ошибка выполнения арбитража: {}", e);
+        }
+        // Критическая ошибка или лимит неудач: движок остановил исполнение
+        let engine_guard = engine.lock().await;
+        if let Some(reason) = engine_guard.halt_reason() {
+            // Оставшиеся пачки прогона не исполняются
+            let remaining = batches.map(|batch| batch.len()).sum();
+            monitor.scan_stats().record_skipped(SkipReason::Halted, remaining);
+            anyhow::bail!("Исполнение остановлено: {}", reason);
+        }
     }
+    Ok(())
 }
 
@@ -0,0 +1,372 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+
+/// Комиссия пула в виде дроби numerator / denominator (как хранят её AMM on-chain)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fee {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl Fee {
+    /// Без комиссии
+    pub const ZERO: Fee = Fee { numerator: 0, denominator: 1 };
+
+    /// Комиссия numerator / denominator (ошибка, если она не меньше 100%)
+    pub fn new(numerator: u64, denominator: u64) -> Result<Self> {
+        if denominator == 0 {
+            anyhow::bail!("Знаменатель комиссии равен нулю");
+        }
+        if numerator >= denominator {
+            anyhow::bail!("Комиссия {}/{} не меньше 100%", numerator, denominator);
+        }
+        Ok(Self { numerator, denominator })
+    }
+
+    /// Комиссия в процентах
+    pub fn percent(&self) -> Decimal {
+        Decimal::from(self.numerator) * Decimal::from(100) / Decimal::from(self.denominator)
+    }
+
+    /// Сумма после удержания комиссии (комиссия округляется вверх — в пользу пула)
+    pub fn deduct(&self, amount: u64) -> Result<u64> {
+        let fee = mul_div_ceil(amount, self.numerator, self.denominator)?;
+        amount.checked_sub(fee).context("Комиссия больше суммы")
+    }
+}
+
+/// a * b / denominator с округлением вниз, без промежуточного переполнения
+pub fn mul_div_floor(a: u64, b: u64, denominator: u64) -> Result<u64> {
+    if denominator == 0 {
+        anyhow::bail!("Деление на ноль");
+    }
+    let result = u128::from(a) * u128::from(b) / u128::from(denominator);
+    u64::try_from(result).context("Результат не помещается в u64")
+}
+
+/// a * b / denominator с округлением вверх, без промежуточного переполнения
+pub fn mul_div_ceil(a: u64, b: u64, denominator: u64) -> Result<u64> {
+    if denominator == 0 {
+        anyhow::bail!("Деление на ноль");
+    }
+    let result = (u128::from(a) * u128::from(b)).div_ceil(u128::from(denominator));
+    u64::try_from(result).context("Результат не помещается в u64")
+}
+
+/// Выход свопа в пуле x*y=k: сколько reserve_out токенов даст amount_in
+///
+/// Комиссия удерживается со входа. Результат округляется вниз (в пользу
+/// пула), как это делают программы AMM, поэтому k после свопа не уменьшается.
+pub fn constant_product_output(amount_in: u64, reserve_in: u64, reserve_out: u64, fee: Fee) -> Result<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        anyhow::bail!("Резерв равен нулю");
+    }
+    let amount_in = u128::from(fee.deduct(amount_in)?);
+    // amount_out = amount_in * reserve_out / (reserve_in + amount_in) < reserve_out,
+    // произведение двух u64 всегда помещается в u128
+    let amount_out = amount_in * u128::from(reserve_out) / (u128::from(reserve_in) + amount_in);
+    u64::try_from(amount_out).context("Результат не помещается в u64")
+}
+
+/// Вход свопа в пуле x*y=k: сколько нужно отдать, чтобы получить amount_out
+///
+/// Обратная к `constant_product_output`: результат округляется вверх (в пользу
+/// пула), так что своп на найденную сумму даёт не меньше amount_out.
+pub fn constant_product_input(amount_out: u64, reserve_in: u64, reserve_out: u64, fee: Fee) -> Result<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        anyhow::bail!("Резерв равен нулю");
+    }
+    if amount_out >= reserve_out {
+        anyhow::bail!("Запрошено {} из резерва {}: пул не может отдать весь резерв", amount_out, reserve_out);
+    }
+    // Вход без комиссии: reserve_in * amount_out / (reserve_out - amount_out), вверх
+    let net_in = mul_div_ceil(reserve_in, amount_out, reserve_out - amount_out)?;
+    // Вход с комиссией: net_in * denominator / (denominator - numerator), вверх
+    let keep = fee.denominator.checked_sub(fee.numerator)
+        .filter(|keep| *keep > 0)
+        .context("Комиссия не меньше 100%")?;
+    let gross_in = mul_div_ceil(net_in, fee.denominator, keep)?;
+    // Комиссия удерживается с округлением вверх — добираем недостающие единицы
+    let mut amount_in = gross_in;
+    while fee.deduct(amount_in)? < net_in {
+        amount_in = amount_in.checked_add(1).context("Переполнение: вход свопа не помещается в u64")?;
+    }
+    Ok(amount_in)
+}
+
+/// Спот цена пула x*y=k: сколько quote за 1 base (в минимальных единицах)
+pub fn spot_price(base_reserve: u64, quote_reserve: u64) -> Result<Decimal> {
+    if base_reserve == 0 {
+        anyhow::bail!("Резерв base токена равен нулю");
+    }
+    Decimal::from(quote_reserve)
+        .checked_div(Decimal::from(base_reserve))
+        .context("Переполнение при расчёте цены")
+}
+
+/// 2^64 — единица sqrt-цены в формате Q64.64 (Orca Whirlpools)
+pub const Q64: u128 = 1 << 64;
+
+/// Границы индекса тика Whirlpool
+pub const MIN_TICK_INDEX: i32 = -443_636;
+pub const MAX_TICK_INDEX: i32 = 443_636;
+
+/// 1/sqrt(1.0001)^(2^i) в формате Q128 для битов индекса тика
+const TICK_RATIO_FACTORS: [u128; 19] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x09aa508b5b7a84e1c677de54f3e99bc9,
+    0x005d6af8dedb81196699c329225ee604,
+    0x00002216e584f5fa1ea926041bedfe98,
+];
+
+/// Полное произведение двух u128: (старшие, младшие) 128 бит
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // Средние слагаемые с переносом в старшую часть
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (middle << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (hi, lo)
+}
+
+/// Деление 256-битного (hi, lo) на u128: (частное, остаток), частное должно помещаться в u128
+fn div_wide(hi: u128, lo: u128, denominator: u128) -> Result<(u128, u128)> {
+    if denominator == 0 {
+        anyhow::bail!("Деление на ноль");
+    }
+    if hi >= denominator {
+        anyhow::bail!("Результат не помещается в u128");
+    }
+    // Деление столбиком по битам младшей части
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Ok((quotient, remainder))
+}
+
+/// a * b / denominator для u128 с 256-битным промежуточным произведением
+pub fn mul_div_u128(a: u128, b: u128, denominator: u128, round_up: bool) -> Result<u128> {
+    let (hi, lo) = mul_wide(a, b);
+    let (quotient, remainder) = div_wide(hi, lo, denominator)?;
+    if round_up && remainder > 0 {
+        quotient.checked_add(1).context("Результат не помещается в u128")
+    } else {
+        Ok(quotient)
+    }
+}
+
+/// sqrt-цена тика в формате Q64.64: sqrt(1.0001^tick) * 2^64
+pub fn sqrt_price_from_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK_INDEX..=MAX_TICK_INDEX).contains(&tick) {
+        anyhow::bail!("Индекс тика {} вне диапазона [{}, {}]", tick, MIN_TICK_INDEX, MAX_TICK_INDEX);
+    }
+    // ratio = 1/sqrt(1.0001)^|tick| в формате Q128 (2^128 заменено на u128::MAX)
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = u128::MAX;
+    for (bit, factor) in TICK_RATIO_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = mul_wide(ratio, *factor).0;
+        }
+    }
+    if tick > 0 {
+        // 2^192 / ratio: обратное значение в Q64.64
+        mul_div_u128(1 << 96, 1 << 96, ratio, false)
+    } else {
+        Ok((ratio >> 64) + u128::from(ratio as u64 != 0))
+    }
+}
+
+/// Цена из sqrt-цены Q64.64: сколько токенов B за 1 токен A (в целых токенах)
+pub fn price_from_sqrt_price(sqrt_price: u128, decimals_a: u8, decimals_b: u8) -> Result<Decimal> {
+    let sqrt = Decimal::try_from_i128_with_scale(
+        i128::try_from(sqrt_price).context("sqrt-цена не помещается в Decimal")?,
+        0,
+    ).context("sqrt-цена не помещается в Decimal")?;
+    // 2^64 не помещается в u64
+    let q64 = Decimal::from(u64::MAX) + Decimal::ONE;
+    let ratio = sqrt.checked_div(q64).context("Переполнение при расчёте цены")?;
+    let mut price = ratio.checked_mul(ratio).context("Переполнение при расчёте цены")?;
+    // Цена в минимальных единицах -> в целых токенах: * 10^(decimals_a - decimals_b)
+    for _ in decimals_b..decimals_a {
+        price = price.checked_mul(Decimal::TEN).context("Переполнение при расчёте цены")?;
+    }
+    for _ in decimals_a..decimals_b {
+        price = price.checked_div(Decimal::TEN).context("Переполнение при расчёте цены")?;
+    }
+    Ok(price)
+}
+
+fn ordered(sqrt_price_0: u128, sqrt_price_1: u128) -> (u128, u128) {
+    if sqrt_price_0 <= sqrt_price_1 {
+        (sqrt_price_0, sqrt_price_1)
+    } else {
+        (sqrt_price_1, sqrt_price_0)
+    }
+}
+
+/// Количество токена A между двумя sqrt-ценами при ликвидности L:
+/// L * (upper - lower) / (upper * lower), в Q64.64
+pub fn amount_a_delta(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_0, sqrt_price_1);
+    if lower == 0 {
+        anyhow::bail!("sqrt-цена равна нулю");
+    }
+    let per_upper = mul_div_u128(liquidity, upper - lower, upper, round_up)?;
+    mul_div_u128(per_upper, Q64, lower, round_up)
+}
+
+/// Количество токена B между двумя sqrt-ценами при ликвидности L: L * (upper - lower)
+pub fn amount_b_delta(sqrt_price_0: u128, sqrt_price_1: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (lower, upper) = ordered(sqrt_price_0, sqrt_price_1);
+    mul_div_u128(liquidity, upper - lower, Q64, round_up)
+}
+
+/// sqrt-цена после добавления amount токена A (цена снижается)
+///
+/// Округляется вверх — в пользу пула, как в программе Whirlpools.
+pub fn next_sqrt_price_from_a_input(sqrt_price: u128, liquidity: u128, amount: u64) -> Result<u128> {
+    if sqrt_price == 0 || liquidity == 0 {
+        anyhow::bail!("Нулевая sqrt-цена или ликвидность");
+    }
+    // Виртуальный резерв A = L / sqrt_price
+    let reserve_a = mul_div_u128(liquidity, Q64, sqrt_price, false)?;
+    let denominator = reserve_a.checked_add(u128::from(amount)).context("Переполнение резерва A")?;
+    mul_div_u128(liquidity, Q64, denominator, true)
+}
+
+/// sqrt-цена после добавления amount токена B (цена растёт)
+///
+/// Округляется вниз — в пользу пула, как в программе Whirlpools.
+pub fn next_sqrt_price_from_b_input(sqrt_price: u128, liquidity: u128, amount: u64) -> Result<u128> {
+    if liquidity == 0 {
+        anyhow::bail!("Нулевая ликвидность");
+    }
+    let delta = mul_div_u128(u128::from(amount), Q64, liquidity, false)?;
+    sqrt_price.checked_add(delta).context("Переполнение sqrt-цены")
+}
+
+/// Число токенов в пуле stable swap (Saber)
+const STABLE_SWAP_COINS: u128 = 2;
+/// Предел итераций метода Ньютона в stable swap (обычно хватает нескольких)
+const STABLE_SWAP_MAX_ITERATIONS: usize = 256;
+
+/// Инвариант D пула stable swap на два токена
+///
+/// Ann·Σx + D = Ann·D + D³/(n²·Πx) при n = 2 и Ann = A·n (соглашение Saber);
+/// решается методом Ньютона до изменения не больше 1.
+pub fn stable_swap_d(amp: u64, amount_a: u64, amount_b: u64) -> Result<u128> {
+    let (amount_a, amount_b) = (u128::from(amount_a), u128::from(amount_b));
+    let sum = amount_a + amount_b;
+    if sum == 0 {
+        return Ok(0);
+    }
+    if amount_a == 0 || amount_b == 0 || amp == 0 {
+        anyhow::bail!("Нулевой резерв или коэффициент усиления пула stable swap");
+    }
+    let ann = u128::from(amp) * STABLE_SWAP_COINS;
+    let mut d = sum;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        // D³ / (n²·Πx) = D·D/(n·x_a)·D/(n·x_b)
+        let d_product = mul_div_u128(
+            mul_div_u128(d, d, amount_a * STABLE_SWAP_COINS, false)?,
+            d,
+            amount_b * STABLE_SWAP_COINS,
+            false,
+        )?;
+        let previous = d;
+        // D = (Ann·Σx + n·D_p)·D / ((Ann − 1)·D + (n + 1)·D_p)
+        let numerator = (ann * sum).checked_add(d_product * STABLE_SWAP_COINS)
+            .context("Переполнение инварианта stable swap")?;
+        let denominator = (ann - 1).checked_mul(d)
+            .and_then(|value| value.checked_add(d_product.checked_mul(STABLE_SWAP_COINS + 1)?))
+            .context("Переполнение инварианта stable swap")?;
+        d = mul_div_u128(numerator, d, denominator, false)?;
+        if d.abs_diff(previous) <= 1 {
+            return Ok(d);
+        }
+    }
+    anyhow::bail!("Инвариант stable swap не сошёлся")
+}
+
+/// Резерв второго токена при резерве `amount_x` первого и том же инварианте D
+///
+/// y² + (x + D/Ann − D)·y = D³/(n²·x·Ann), методом Ньютона до изменения не больше 1.
+pub fn stable_swap_y(amp: u64, amount_x: u64, d: u128) -> Result<u128> {
+    if amount_x == 0 || amp == 0 {
+        anyhow::bail!("Нулевой резерв или коэффициент усиления пула stable swap");
+    }
+    let amount_x = u128::from(amount_x);
+    let ann = u128::from(amp) * STABLE_SWAP_COINS;
+    // c = D·D/(n·x)·D/(n·Ann)
+    let c = mul_div_u128(
+        mul_div_u128(d, d, amount_x * STABLE_SWAP_COINS, false)?,
+        d,
+        ann * STABLE_SWAP_COINS,
+        false,
+    )?;
+    let b = amount_x + d / ann;
+    let mut y = d;
+    for _ in 0..STABLE_SWAP_MAX_ITERATIONS {
+        let previous = y;
+        // y = (y² + c) / (2y + b − D)
+        let numerator = y.checked_mul(y)
+            .and_then(|square| square.checked_add(c))
+            .context("Переполнение инварианта stable swap")?;
+        let denominator = (y * 2 + b).checked_sub(d)
+            .filter(|denominator| *denominator > 0)
+            .context("Некорректный инвариант stable swap")?;
+        y = numerator / denominator;
+        if y.abs_diff(previous) <= 1 {
+            return Ok(y);
+        }
+    }
+    anyhow::bail!("Резерв stable swap не сошёлся")
+}
+
+/// Выход свопа в пуле stable swap: сколько reserve_out токенов даст amount_in
+///
+/// В отличие от x*y=k, курс около равновесия близок к 1:1 и для крупных
+/// сделок. Комиссия удерживается с выхода с округлением вниз, как в Saber.
+pub fn stable_swap_output(amount_in: u64, reserve_in: u64, reserve_out: u64, amp: u64, fee: Fee) -> Result<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        anyhow::bail!("Резерв равен нулю");
+    }
+    let d = stable_swap_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = reserve_in.checked_add(amount_in).context("Переполнение резерва")?;
+    let new_reserve_out = stable_swap_y(amp, new_reserve_in, d)?;
+    let amount_out = u64::try_from(u128::from(reserve_out).saturating_sub(new_reserve_out))
+        .context("Результат не помещается в u64")?;
+    let fee_amount = mul_div_floor(amount_out, fee.numerator, fee.denominator)?;
+    Ok(amount_out - fee_amount)
+}
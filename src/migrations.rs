@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::balance_history::BALANCE_COLLECTION;
+use crate::fees::FEES_COLLECTION;
+use crate::history::{ANNOTATIONS_COLLECTION, TRADES_COLLECTION};
+use crate::store::Store;
+
+/// Файл с версией схемы в каталоге хранилища
+pub const SCHEMA_FILE: &str = "schema.json";
+
+/// Журналы бота (отчёты профилирования — отдельные файлы прогонов, не мигрируются)
+const JOURNALS: &[&str] = &[TRADES_COLLECTION, ANNOTATIONS_COLLECTION, BALANCE_COLLECTION, FEES_COLLECTION];
+
+/// Миграция хранилища на версию `version`
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    apply: fn(&Store) -> Result<()>,
+}
+
+/// Встроенные миграции по возрастанию версии
+///
+/// Новая миграция добавляется в конец со следующей версией; применённые
+/// миграции не меняются — по записанной версии хранилища они уже выполнены.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "исходная схема: журналы JSON Lines",
+        apply: |_| Ok(()),
+    },
+    Migration {
+        version: 2,
+        description: "удаление повреждённых строк из журналов (с резервной копией)",
+        apply: drop_corrupted_lines,
+    },
+];
+
+/// Версия схемы, записанная в хранилище
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaVersion {
+    version: u32,
+    updated_at: DateTime<Utc>,
+}
+
+/// Состояние миграций хранилища
+pub struct MigrationStatus {
+    /// Версия схемы хранилища (0 — хранилище без версии)
+    pub current: u32,
+    /// Версия схемы, которую ожидает бот
+    pub latest: u32,
+    /// Миграции, которые будут применены при запуске
+    pub pending: Vec<&'static Migration>,
+}
+
+/// Версия схемы, которую ожидает бот
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+fn read_version(store: &Store) -> Result<u32> {
+    let path = store.dir().join(SCHEMA_FILE);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("Не удалось прочитать {:?}", path)),
+    };
+    let schema: SchemaVersion = serde_json::from_str(&content)
+        .with_context(|| format!("Некорректный файл версии схемы {:?}", path))?;
+    Ok(schema.version)
+}
+
+fn write_version(store: &Store, version: u32) -> Result<()> {
+    let path = store.dir().join(SCHEMA_FILE);
+    let content = serde_json::to_string_pretty(&SchemaVersion { version, updated_at: Utc::now() })
+        .context("Не удалось сериализовать версию схемы")?;
+    write_atomic(&path, &content)
+}
+
+/// Запись файла через временный файл и переименование
+fn write_atomic(path: &std::path::Path, content: &str) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, content).with_context(|| format!("Не удалось записать {:?}", tmp))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("Не удалось заменить {:?}", path))
+}
+
+/// Состояние миграций (без изменения хранилища)
+///
+/// Ошибка, если хранилище создано более новой версией бота.
+pub fn status(store: &Store) -> Result<MigrationStatus> {
+    let current = read_version(store)?;
+    let latest = latest_version();
+    if current > latest {
+        anyhow::bail!(
+            "Версия схемы хранилища {} новее поддерживаемой ({}): хранилище создано более новой версией бота",
+            current, latest
+        );
+    }
+    Ok(MigrationStatus {
+        current,
+        latest,
+        pending: MIGRATIONS.iter().filter(|migration| migration.version > current).collect(),
+    })
+}
+
+/// Применение недостающих миграций, возвращает применённые версии
+///
+/// Версия записывается после каждой миграции: прерванный запуск продолжит
+/// со следующей. Работает с файлами синхронно — из async кода вызывается
+/// через `migrate`.
+pub fn run(store: &Store) -> Result<Vec<u32>> {
+    let pending = status(store)?.pending;
+    let mut applied = Vec::new();
+    for migration in pending {
+        log::info!("Миграция хранилища до версии {}: {}", migration.version, migration.description);
+        (migration.apply)(store)
+            .with_context(|| format!("Не удалось применить миграцию {}", migration.version))?;
+        write_version(store, migration.version)?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+/// Применение миграций при запуске (в отдельном потоке для блокирующего ввода-вывода)
+pub async fn migrate(store: &Store) -> Result<Vec<u32>> {
+    let store = store.clone();
+    tokio::task::spawn_blocking(move || run(&store))
+        .await
+        .context("Задача миграции хранилища завершилась аварийно")?
+}
+
+/// Миграция 2: строки, оборванные при записи, удаляются из журналов
+///
+/// Раньше такие строки пропускались с предупреждением при каждом чтении.
+/// Исходный журнал сохраняется рядом как `<коллекция>.jsonl.bak`.
+fn drop_corrupted_lines(store: &Store) -> Result<()> {
+    for collection in JOURNALS {
+        let path = store.collection_path(collection);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("Не удалось прочитать {:?}", path)),
+        };
+
+        let mut kept = String::with_capacity(content.len());
+        let mut dropped = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            if serde_json::from_str::<serde_json::Value>(line).is_ok() {
+                kept.push_str(line);
+                kept.push('\n');
+            } else {
+                dropped += 1;
+            }
+        }
+        if dropped == 0 {
+            continue;
+        }
+
+        let backup = path.with_extension("jsonl.bak");
+        std::fs::copy(&path, &backup)
+            .with_context(|| format!("Не удалось сохранить резервную копию {:?}", backup))?;
+        write_atomic(&path, &kept)?;
+        log::warn!("{:?}: удалено повреждённых строк: {} (копия в {:?})", path, dropped, backup);
+    }
+    Ok(())
+}
@@ -1,18 +1,252 @@
 use log;
-use crate::config::Config;
+use crate::clock_skew::ClockSkew;
+use crate::congestion::Congestion;
+use crate::fees::FeeLedger;
+use crate::fiat::FiatRates;
+use crate::lifecycle::TradingControl;
+use crate::supervisor::Supervisor;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::config::{Config, ExecutionMode};
+use crate::event_log::{BotEvent, EventLog, SafetyEvent};
+use crate::heatmap::OPPORTUNITIES_COLLECTION;
+use crate::history::{load_trades, push_trade, TradeAnnotation, TradeHistory, TradeRecord, ANNOTATIONS_COLLECTION, TRADES_COLLECTION};
+use crate::jobs::JobStates;
+use crate::notify::{Notifier, Severity};
+use crate::ops_state::OpsState;
+use crate::price_snapshot::PriceFeed;
+use crate::receipt::ReceiptFeed;
+use crate::scan_stats::ScanStats;
+use crate::self_test::SelfTestResults;
+use crate::signal::{Signal, SignalFeed};
+use crate::store::Store;
 use rust_decimal::Decimal;
 
 /// Система мониторинга и логирования
 #[derive(Clone)]
 pub struct Monitor {
     config: Config,
+    clock_skew: ClockSkew,
+    congestion: Congestion,
+    fiat: FiatRates,
+    trading: TradingControl,
+    supervisor: Supervisor,
+    notifier: Option<Notifier>,
+    trade_history: TradeHistory,
+    signals: SignalFeed,
+    receipts: ReceiptFeed,
+    events: EventLog,
+    fees: FeeLedger,
+    ops_state: OpsState,
+    scan_stats: ScanStats,
+    prices: PriceFeed,
+    jobs: JobStates,
+    self_test: SelfTestResults,
+    store: Option<Store>,
 }
 
 impl Monitor {
     /// Создание нового монитора
     pub fn new(config: &Config) -> Self {
+        let events = EventLog::new();
+        let clock_skew = ClockSkew::new();
         Self {
             config: config.clone(),
+            fiat: FiatRates::new(&config.fiat, &config.network.proxy).with_clock_skew(clock_skew.clone()),
+            clock_skew,
+            congestion: Congestion::new(&config.congestion),
+            trading: TradingControl::new(),
+            supervisor: Supervisor::new(&config.supervisor),
+            notifier: None,
+            trade_history: TradeHistory::default(),
+            signals: SignalFeed::new(),
+            receipts: ReceiptFeed::new().with_events(events.clone()),
+            events,
+            fees: FeeLedger::default(),
+            ops_state: OpsState::new(config),
+            scan_stats: ScanStats::new(),
+            prices: PriceFeed::new(),
+            jobs: JobStates::default(),
+            self_test: SelfTestResults::default(),
+            store: None,
+        }
+    }
+
+    /// Подключение общего надзора за фоновыми задачами
+    pub fn with_supervisor(mut self, supervisor: Supervisor) -> Self {
+        self.supervisor = supervisor;
+        self
+    }
+
+    /// Надзор за фоновыми задачами (здоровье задач для API)
+    pub fn supervisor(&self) -> &Supervisor {
+        &self.supervisor
+    }
+
+    /// Подключение отправки уведомлений
+    pub fn with_notifier(mut self, notifier: Option<Notifier>) -> Self {
+        self.notifier = notifier;
+        self
+    }
+
+    /// Расхождение локальных часов с кластером
+    pub fn clock_skew(&self) -> &ClockSkew {
+        &self.clock_skew
+    }
+
+    /// Состояние перегрузки сети
+    pub fn congestion(&self) -> &Congestion {
+        &self.congestion
+    }
+
+    /// Курсы токенов в USD
+    pub fn fiat(&self) -> &FiatRates {
+        &self.fiat
+    }
+
+    /// Результат самопроверки адаптеров при запуске
+    pub fn self_test(&self) -> &SelfTestResults {
+        &self.self_test
+    }
+
+    /// Состояние торговой задачи (общее с веб-сервером)
+    pub fn trading(&self) -> &TradingControl {
+        &self.trading
+    }
+
+    /// История сделок (общая с веб-сервером)
+    pub fn trade_history(&self) -> TradeHistory {
+        self.trade_history.clone()
+    }
+
+    /// Подключение хранилища: сделки, заметки к ним, расходы на транзакции
+    /// и найденные возможности сохраняются на диск
+    pub fn with_store(mut self, store: Store) -> Self {
+        self.fees = self.fees.with_store(store.clone());
+        self.store = Some(store);
+        self
+    }
+
+    /// Загрузка сохранённой истории сделок в память
+    pub async fn restore_trade_history(&self) -> anyhow::Result<usize> {
+        let Some(ref store) = self.store else { return Ok(0) };
+        let trades = load_trades(store).await?;
+        let count = trades.len();
+        *self.trade_history.lock().await = trades;
+        Ok(count)
+    }
+
+    /// Сохранение записи о сделке
+    pub async fn record_trade(&self, record: TradeRecord) {
+        if let Some(ref store) = self.store {
+            if let Err(e) = store.append(TRADES_COLLECTION, &record).await {
+                log::warn!("Не удалось сохранить сделку {}: {}", record.id, e);
+            }
+        }
+        self.events.publish(BotEvent::Trade(record.clone()));
+        push_trade(&self.trade_history, record).await;
+    }
+
+    /// Изменение заметки и тегов сделки
+    ///
+    /// Возвращает обновлённую запись или None, если сделка не найдена.
+    pub async fn annotate_trade(&self, annotation: TradeAnnotation) -> anyhow::Result<Option<TradeRecord>> {
+        let annotation = annotation.normalized()?;
+        let mut trades = self.trade_history.lock().await;
+        let Some(record) = trades.iter_mut().find(|t| t.id == annotation.trade_id) else {
+            return Ok(None);
+        };
+
+        if let Some(ref store) = self.store {
+            store.append(ANNOTATIONS_COLLECTION, &annotation).await?;
+        }
+        annotation.apply(record);
+        Ok(Some(record.clone()))
+    }
+
+    /// Лента найденных возможностей (общая с веб-сервером)
+    pub fn signals(&self) -> &SignalFeed {
+        &self.signals
+    }
+
+    /// Расходы кошелька на транзакции (общие с веб-сервером)
+    pub fn fees(&self) -> &FeeLedger {
+        &self.fees
+    }
+
+    /// Рабочее состояние для gauge Prometheus (общее с веб-сервером)
+    pub fn ops_state(&self) -> &OpsState {
+        &self.ops_state
+    }
+
+    /// Статистика прогонов поиска (общая с веб-сервером)
+    pub fn scan_stats(&self) -> &ScanStats {
+        &self.scan_stats
+    }
+
+    /// Снимки цен прогонов поиска (общие с веб-сервером)
+    pub fn prices(&self) -> &PriceFeed {
+        &self.prices
+    }
+
+    /// Состояние регулярных задач планировщика (общее с веб-сервером)
+    pub fn jobs(&self) -> &JobStates {
+        &self.jobs
+    }
+
+    /// Лента событий исполнения сделок (общая с веб-сервером)
+    pub fn receipts(&self) -> &ReceiptFeed {
+        &self.receipts
+    }
+
+    /// Журнал событий с номерами: возможности, исполнение и сделки (общий с веб-сервером)
+    pub fn events(&self) -> &EventLog {
+        &self.events
+    }
+
+    /// Публикация перехода режима работы или защитного механизма
+    pub fn publish_safety(&self, event: SafetyEvent) {
+        self.ops_state.apply(&event);
+        self.events.publish(BotEvent::Safety(event));
+    }
+
+    /// Публикация найденной возможности
+    ///
+    /// Возможность сохраняется в хранилище в фоне (для тепловой карты).
+    /// В режиме сигналов возможность дополнительно уходит в уведомления,
+    /// так как сделок (и уведомлений о них) не будет.
+    pub fn publish_opportunity(&self, opportunity: &ArbitrageOpportunity) {
+        let signal = Signal::from_opportunity(opportunity, chrono::Utc::now());
+        if let Some(ref store) = self.store {
+            let store = store.clone();
+            let record = signal.clone();
+            tokio::spawn(async move {
+                if let Err(e) = store.append(OPPORTUNITIES_COLLECTION, &record).await {
+                    log::warn!("Не удалось сохранить возможность {}/{}: {}", record.base_token, record.quote_token, e);
+                }
+            });
+        }
+        self.events.publish(BotEvent::Opportunity(signal.clone()));
+        self.signals.publish(signal);
+
+        if self.config.safety.mode == ExecutionMode::Signal {
+            log::info!(
+                "[SIGNAL] {} -> {} {}/{} | Прибыль после комиссий: {:.2}%",
+                opportunity.from_dex,
+                opportunity.to_dex,
+                opportunity.base_token,
+                opportunity.quote_token,
+                opportunity.profit_percent_after_fees
+            );
+            self.alert(Severity::Info, format!(
+                "📡 {}/{}: купить на {} по {}, продать на {} по {} (прибыль {:.2}%)",
+                opportunity.base_token,
+                opportunity.quote_token,
+                opportunity.from_dex,
+                opportunity.buy_price,
+                opportunity.to_dex,
+                opportunity.sell_price,
+                opportunity.profit_percent_after_fees
+            ));
         }
     }
 
@@ -39,16 +273,44 @@ impl Monitor {
                 profit_percent
             );
         }
+
+        if let Some(ref notifier) = self.notifier {
+            let mode = if simulation_mode { "симуляция" } else { "продакшн" };
+            notifier.trade(
+                format!("✅ {} -> {}: прибыль {:.2}% ({})", from_dex, to_dex, profit_percent, mode),
+                profit_percent,
+            );
+        }
+    }
+
+    /// Отчёт (например, ежедневная сводка): в лог и в уведомления
+    pub fn report(&self, message: &str) {
+        log::info!("[REPORT] {}", message);
+        self.alert(Severity::Info, message.to_string());
     }
 
     /// Логирование ошибки
     pub fn log_error(&self, error: &str) {
         log::error!("[ERROR] {}", error);
+        self.alert(Severity::Warning, format!("❌ {}", error));
     }
 
     /// Логирование предупреждения
     pub fn log_warning(&self, warning: &str) {
         log::warn!("[WARNING] {}", warning);
+        self.alert(Severity::Warning, format!("⚠️ {}", warning));
+    }
+
+    /// Критичный алерт: отправляется сразу, минуя сводку
+    pub fn log_critical(&self, message: &str) {
+        log::error!("[CRITICAL] {}", message);
+        self.alert(Severity::Critical, format!("🚨 {}", message));
+    }
+
+    fn alert(&self, severity: Severity, message: String) {
+        if let Some(ref notifier) = self.notifier {
+            notifier.alert(severity, message);
+        }
     }
 }
 
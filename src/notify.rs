@@ -0,0 +1,389 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use crate::clock::{to_chrono, SharedClock};
+use crate::supervisor::Supervisor;
+use crate::config::{NotificationMode, NotificationSinkConfig, NotificationSinkKind, NotificationsConfig, ProxyConfig};
+use crate::proxy::{self, ProxyEndpoint};
+
+/// Шаблон сводки по умолчанию
+///
+/// Доступные подстановки: {period_min}, {trades}, {profit_avg}, {profit_max},
+/// {warnings}, {events}.
+pub const DEFAULT_DIGEST_TEMPLATE: &str =
+    "📊 Сводка за {period_min} мин\nСделок: {trades}, средняя прибыль: {profit_avg}%, максимальная: {profit_max}%\nПредупреждений: {warnings}\n{events}";
+
+/// Максимальное количество событий, перечисляемых в одной сводке
+const MAX_DIGEST_EVENTS: usize = 20;
+/// Таймаут запросов к Telegram Bot API и вебхукам
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Период проверки готовности сводок
+const DIGEST_TICK: Duration = Duration::from_secs(1);
+
+/// Важность уведомления
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    /// Критичные алерты отправляются сразу, даже в режиме сводки
+    Critical,
+}
+
+/// Уведомление о событии бота
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub severity: Severity,
+    pub message: String,
+    /// Прибыль сделки в процентах (только для уведомлений о сделках)
+    pub profit_percent: Option<Decimal>,
+}
+
+/// Канал доставки уведомлений
+#[async_trait::async_trait]
+pub trait NotificationSink: Send + Sync {
+    /// Название канала для логов
+    fn name(&self) -> &str;
+
+    /// Отправка текста сообщения
+    async fn send(&self, text: &str) -> Result<()>;
+}
+
+/// Канал, пишущий уведомления в лог
+pub struct LogSink;
+
+#[async_trait::async_trait]
+impl NotificationSink for LogSink {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    async fn send(&self, text: &str) -> Result<()> {
+        log::info!("[NOTIFY] {}", text);
+        Ok(())
+    }
+}
+
+/// Канал Telegram Bot API
+pub struct TelegramSink {
+    client: reqwest::Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl TelegramSink {
+    /// Создание канала; токен и chat id берутся из переменных окружения (.env)
+    pub fn new(config: &NotificationSinkConfig, proxy: &ProxyConfig) -> Result<Self> {
+        let bot_token = std::env::var(&config.bot_token_env)
+            .with_context(|| format!("Переменная окружения {} не задана", config.bot_token_env))?;
+        let chat_id = std::env::var(&config.chat_id_env)
+            .with_context(|| format!("Переменная окружения {} не задана", config.chat_id_env))?;
+        let builder = reqwest::Client::builder().timeout(HTTP_TIMEOUT);
+        let client = proxy::configure(builder, proxy, ProxyEndpoint::Notifications)?
+            .build()
+            .context("Не удалось создать HTTP клиент для Telegram")?;
+
+        Ok(Self { client, bot_token, chat_id })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSink for TelegramSink {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    async fn send(&self, text: &str) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+        let body = serde_json::json!({
+            "chat_id": self.chat_id,
+            "text": text,
+            "disable_web_page_preview": true,
+        });
+
+        // URL содержит токен бота — убираем его из текста ошибок
+        let response = self.client.post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Ошибка запроса к Telegram: {}", e.without_url()))?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Telegram вернул статус {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Канал вебхука: JSON `{"text": ...}` методом POST
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl WebhookSink {
+    /// Создание канала; URL берётся из переменной окружения (может содержать токен)
+    pub fn new(config: &NotificationSinkConfig, proxy: &ProxyConfig) -> Result<Self> {
+        let url = std::env::var(&config.url_env)
+            .with_context(|| format!("Переменная окружения {} не задана", config.url_env))?;
+        let builder = reqwest::Client::builder().timeout(HTTP_TIMEOUT);
+        let client = proxy::configure(builder, proxy, ProxyEndpoint::Notifications)?
+            .build()
+            .context("Не удалось создать HTTP клиент для вебхука")?;
+
+        Ok(Self { client, url })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationSink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    async fn send(&self, text: &str) -> Result<()> {
+        let response = self.client.post(&self.url)
+            .json(&serde_json::json!({ "text": text }))
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Ошибка запроса к вебхуку: {}", e.without_url()))?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Вебхук вернул статус {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Накопитель событий для одного канала
+///
+/// В режиме immediate возвращает текст каждого события сразу,
+/// в режиме digest копит события до вызова `flush`.
+#[derive(Debug)]
+pub struct DigestBuffer {
+    mode: NotificationMode,
+    interval: Duration,
+    template: String,
+    period_start: DateTime<Utc>,
+    trades: u64,
+    profit_sum: Decimal,
+    profit_max: Option<Decimal>,
+    warnings: u64,
+    events: Vec<String>,
+    dropped_events: usize,
+}
+
+impl DigestBuffer {
+    /// Создание накопителя согласно настройкам канала; now — начало первого периода
+    pub fn new(config: &NotificationSinkConfig, now: DateTime<Utc>) -> Self {
+        Self {
+            mode: config.mode,
+            interval: Duration::from_secs(config.digest_interval_sec),
+            template: config.digest_template.clone()
+                .unwrap_or_else(|| DEFAULT_DIGEST_TEMPLATE.to_string()),
+            period_start: now,
+            trades: 0,
+            profit_sum: Decimal::ZERO,
+            profit_max: None,
+            warnings: 0,
+            events: Vec::new(),
+            dropped_events: 0,
+        }
+    }
+
+    /// Приём события; возвращает текст, если его нужно отправить немедленно
+    pub fn accept(&mut self, notification: &Notification) -> Option<String> {
+        if self.mode == NotificationMode::Immediate || notification.severity == Severity::Critical {
+            return Some(notification.message.clone());
+        }
+
+        if let Some(profit) = notification.profit_percent {
+            self.trades += 1;
+            self.profit_sum += profit;
+            self.profit_max = Some(self.profit_max.map_or(profit, |max| max.max(profit)));
+        }
+        if notification.severity == Severity::Warning {
+            self.warnings += 1;
+        }
+
+        if self.events.len() < MAX_DIGEST_EVENTS {
+            self.events.push(notification.message.clone());
+        } else {
+            self.dropped_events += 1;
+        }
+        None
+    }
+
+    /// Пора ли отправлять сводку
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.mode == NotificationMode::Digest && now - self.period_start >= to_chrono(self.interval)
+    }
+
+    /// Формирование сводки и сброс накопленных событий
+    ///
+    /// Возвращает None, если за период не было событий.
+    pub fn flush(&mut self, now: DateTime<Utc>) -> Option<String> {
+        let period_sec = (now - self.period_start).num_seconds().max(0) as u64;
+        let period_min = period_sec.div_ceil(60);
+        self.period_start = now;
+
+        if self.events.is_empty() {
+            return None;
+        }
+
+        let profit_avg = if self.trades > 0 {
+            self.profit_sum / Decimal::from(self.trades)
+        } else {
+            Decimal::ZERO
+        };
+        let mut events = self.events.join("\n");
+        if self.dropped_events > 0 {
+            events.push_str(&format!("\n… и ещё {} событий", self.dropped_events));
+        }
+
+        let text = render_template(&self.template, &[
+            ("period_min", period_min.to_string()),
+            ("trades", self.trades.to_string()),
+            ("profit_avg", format!("{:.2}", profit_avg)),
+            ("profit_max", format!("{:.2}", self.profit_max.unwrap_or(Decimal::ZERO))),
+            ("warnings", self.warnings.to_string()),
+            ("events", events),
+        ]);
+
+        self.trades = 0;
+        self.profit_sum = Decimal::ZERO;
+        self.profit_max = None;
+        self.warnings = 0;
+        self.events.clear();
+        self.dropped_events = 0;
+
+        Some(text)
+    }
+}
+
+/// Подстановка значений вида {name} в шаблон
+pub fn render_template(template: &str, values: &[(&str, String)]) -> String {
+    values.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// Отправитель уведомлений
+///
+/// Отправка не блокирует вызывающий код: события передаются в фоновую задачу,
+/// которая распределяет их по каналам.
+#[derive(Clone)]
+pub struct Notifier {
+    tx: mpsc::UnboundedSender<Notification>,
+}
+
+impl Notifier {
+    /// Отправка уведомления
+    pub fn notify(&self, notification: Notification) {
+        if self.tx.send(notification).is_err() {
+            log::debug!("Задача уведомлений остановлена, уведомление пропущено");
+        }
+    }
+
+    /// Уведомление о выполненной сделке
+    pub fn trade(&self, message: String, profit_percent: Decimal) {
+        self.notify(Notification {
+            severity: Severity::Info,
+            message,
+            profit_percent: Some(profit_percent),
+        });
+    }
+
+    /// Алерт без привязки к сделке
+    pub fn alert(&self, severity: Severity, message: String) {
+        self.notify(Notification {
+            severity,
+            message,
+            profit_percent: None,
+        });
+    }
+}
+
+/// Очередь и каналы уведомлений; переживают перезапуск задачи надзором
+struct NotifierState {
+    rx: mpsc::UnboundedReceiver<Notification>,
+    sinks: Vec<(Box<dyn NotificationSink>, DigestBuffer)>,
+}
+
+/// Запуск фоновой задачи уведомлений под надзором
+///
+/// Возвращает None, если уведомления отключены или каналы не настроены.
+pub fn spawn_notifier(
+    supervisor: &Supervisor,
+    config: &NotificationsConfig,
+    proxy: &ProxyConfig,
+    clock: SharedClock,
+) -> Result<Option<Notifier>> {
+    if !config.enabled || config.sinks.is_empty() {
+        return Ok(None);
+    }
+
+    let mut sinks: Vec<(Box<dyn NotificationSink>, DigestBuffer)> = Vec::with_capacity(config.sinks.len());
+    for sink_config in &config.sinks {
+        let sink: Box<dyn NotificationSink> = match sink_config.kind {
+            NotificationSinkKind::Log => Box::new(LogSink),
+            NotificationSinkKind::Telegram => Box::new(TelegramSink::new(sink_config, proxy)?),
+            NotificationSinkKind::Webhook => Box::new(WebhookSink::new(sink_config, proxy)?),
+        };
+        log::info!("Канал уведомлений {} ({:?})", sink.name(), sink_config.mode);
+        sinks.push((sink, DigestBuffer::new(sink_config, clock.now())));
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let state = Arc::new(tokio::sync::Mutex::new(NotifierState { rx, sinks }));
+    supervisor.spawn("notifier", move || run_notifier(state.clone(), clock.clone()));
+
+    Ok(Some(Notifier { tx }))
+}
+
+/// Цикл распределения уведомлений по каналам
+async fn run_notifier(state: Arc<tokio::sync::Mutex<NotifierState>>, clock: SharedClock) -> Result<()> {
+    let mut state = state.lock().await;
+    let NotifierState { rx, sinks } = &mut *state;
+    let mut timer = tokio::time::interval(DIGEST_TICK);
+    loop {
+        tokio::select! {
+            received = rx.recv() => {
+                let Some(notification) = received else { break };
+                for (sink, buffer) in sinks.iter_mut() {
+                    if let Some(text) = buffer.accept(&notification) {
+                        deliver(sink.as_ref(), &text).await;
+                    }
+                }
+            }
+            _ = timer.tick() => {
+                let now = clock.now();
+                for (sink, buffer) in sinks.iter_mut() {
+                    if buffer.is_due(now) {
+                        if let Some(text) = buffer.flush(now) {
+                            deliver(sink.as_ref(), &text).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Отправка накопленного при остановке
+    let now = clock.now();
+    for (sink, buffer) in sinks.iter_mut() {
+        if let Some(text) = buffer.flush(now) {
+            deliver(sink.as_ref(), &text).await;
+        }
+    }
+    Ok(())
+}
+
+/// Отправка с логированием ошибки (уведомления не должны останавливать бота)
+async fn deliver(sink: &dyn NotificationSink, text: &str) {
+    if let Err(e) = sink.send(text).await {
+        log::warn!("Не удалось отправить уведомление в {}: {}", sink.name(), e);
+    }
+}
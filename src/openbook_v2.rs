@@ -0,0 +1,417 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use crate::math::{self, Fee};
+
+/// OpenBook v2 Program ID (mainnet и devnet)
+pub const OPENBOOK_V2_PROGRAM_ID: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+
+/// Размер аккаунта Market (discriminator Anchor + 840 байт)
+pub const MARKET_ACCOUNT_SIZE: u64 = 848;
+/// Смещение mint base токена в аккаунте Market
+pub const MARKET_BASE_MINT_OFFSET: usize = 576;
+/// Смещение mint quote токена в аккаунте Market
+pub const MARKET_QUOTE_MINT_OFFSET: usize = 608;
+
+/// Смещения полей аккаунта Market
+const BASE_DECIMALS_OFFSET: usize = 9;
+const QUOTE_DECIMALS_OFFSET: usize = 10;
+const MARKET_AUTHORITY_OFFSET: usize = 16;
+const BIDS_OFFSET: usize = 200;
+const ASKS_OFFSET: usize = 232;
+const EVENT_HEAP_OFFSET: usize = 264;
+const ORACLE_A_OFFSET: usize = 296;
+const ORACLE_B_OFFSET: usize = 328;
+const QUOTE_LOT_SIZE_OFFSET: usize = 448;
+const BASE_LOT_SIZE_OFFSET: usize = 456;
+const TAKER_FEE_OFFSET: usize = 488;
+const BASE_VAULT_OFFSET: usize = 640;
+const QUOTE_VAULT_OFFSET: usize = 680;
+const QUOTE_DEPOSIT_TOTAL_OFFSET: usize = 712;
+
+/// Знаменатель комиссий рынка (миллионные доли)
+const FEES_SCALE_FACTOR: u64 = 1_000_000;
+
+/// Размер аккаунта BookSide
+pub const BOOK_SIDE_ACCOUNT_SIZE: usize = 90_952;
+/// Смещение корня дерева ордеров с фиксированной ценой
+const FIXED_ROOT_OFFSET: usize = 8;
+/// Смещение первого узла дерева
+const NODES_OFFSET: usize = 840;
+/// Размер узла дерева
+const NODE_SIZE: usize = 88;
+/// Узлов в аккаунте BookSide
+const MAX_NODES: usize = 1024;
+
+/// Теги узлов дерева ордеров
+const INNER_NODE_TAG: u8 = 1;
+const LEAF_NODE_TAG: u8 = 2;
+
+/// Смещения полей узлов
+const INNER_CHILDREN_OFFSET: usize = 24;
+const LEAF_TIME_IN_FORCE_OFFSET: usize = 2;
+const LEAF_KEY_OFFSET: usize = 8;
+const LEAF_QUANTITY_OFFSET: usize = 56;
+const LEAF_TIMESTAMP_OFFSET: usize = 64;
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .with_context(|| format!("Данные короче смещения {}", offset + N))?
+        .try_into()
+        .context("Некорректная длина поля")
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+fn read_i64(data: &[u8], offset: usize) -> Result<i64> {
+    Ok(i64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+/// Необязательный адрес (нулевой — не задан)
+fn read_optional_pubkey(data: &[u8], offset: usize) -> Result<Option<Pubkey>> {
+    let address = read_pubkey(data, offset)?;
+    Ok((address != Pubkey::default()).then_some(address))
+}
+
+/// Сторона ордера
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Покупка base за quote
+    Bid,
+    /// Продажа base за quote
+    Ask,
+}
+
+/// Аккаунт рынка OpenBook v2
+#[derive(Debug, Clone, PartialEq)]
+pub struct Market {
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub market_authority: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_heap: Pubkey,
+    pub oracle_a: Option<Pubkey>,
+    pub oracle_b: Option<Pubkey>,
+    /// Минимальные единицы quote в одном лоте
+    pub quote_lot_size: u64,
+    /// Минимальные единицы base в одном лоте
+    pub base_lot_size: u64,
+    /// Комиссия тейкера в миллионных долях
+    pub taker_fee: u64,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    /// Quote на счетах рынка (для выбора самого ликвидного рынка пары)
+    pub quote_deposit_total: u64,
+}
+
+impl Market {
+    /// Разбор данных аккаунта Market
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() as u64 != MARKET_ACCOUNT_SIZE {
+            anyhow::bail!(
+                "Размер аккаунта {} байт не совпадает с Market OpenBook v2 ({} байт)",
+                data.len(), MARKET_ACCOUNT_SIZE
+            );
+        }
+        let positive = |value: i64, name: &str| -> Result<u64> {
+            u64::try_from(value).ok()
+                .filter(|&value| value > 0)
+                .with_context(|| format!("Некорректный {} рынка: {}", name, value))
+        };
+        let taker_fee = read_i64(data, TAKER_FEE_OFFSET)?;
+        Ok(Self {
+            base_decimals: data[BASE_DECIMALS_OFFSET],
+            quote_decimals: data[QUOTE_DECIMALS_OFFSET],
+            market_authority: read_pubkey(data, MARKET_AUTHORITY_OFFSET)?,
+            bids: read_pubkey(data, BIDS_OFFSET)?,
+            asks: read_pubkey(data, ASKS_OFFSET)?,
+            event_heap: read_pubkey(data, EVENT_HEAP_OFFSET)?,
+            oracle_a: read_optional_pubkey(data, ORACLE_A_OFFSET)?,
+            oracle_b: read_optional_pubkey(data, ORACLE_B_OFFSET)?,
+            quote_lot_size: positive(read_i64(data, QUOTE_LOT_SIZE_OFFSET)?, "quote_lot_size")?,
+            base_lot_size: positive(read_i64(data, BASE_LOT_SIZE_OFFSET)?, "base_lot_size")?,
+            taker_fee: u64::try_from(taker_fee)
+                .with_context(|| format!("Отрицательная комиссия тейкера: {}", taker_fee))?,
+            base_mint: read_pubkey(data, MARKET_BASE_MINT_OFFSET)?,
+            quote_mint: read_pubkey(data, MARKET_QUOTE_MINT_OFFSET)?,
+            base_vault: read_pubkey(data, BASE_VAULT_OFFSET)?,
+            quote_vault: read_pubkey(data, QUOTE_VAULT_OFFSET)?,
+            quote_deposit_total: u64::from_le_bytes(read_bytes(data, QUOTE_DEPOSIT_TOTAL_OFFSET)?),
+        })
+    }
+
+    /// Комиссия тейкера
+    pub fn taker_fee(&self) -> Result<Fee> {
+        Fee::new(self.taker_fee, FEES_SCALE_FACTOR).context("Некорректная комиссия рынка OpenBook v2")
+    }
+
+    /// Цена в лотах (quote лотов за base лот) как quote за 1 base
+    pub fn price_from_lots(&self, price_lots: u64) -> Result<Decimal> {
+        let lots = Decimal::from(price_lots)
+            .checked_mul(Decimal::from(self.quote_lot_size))
+            .and_then(|native| native.checked_div(Decimal::from(self.base_lot_size)))
+            .context("Переполнение при расчёте цены")?;
+        let scale = |decimals: u8| -> Result<Decimal> {
+            10u64.checked_pow(u32::from(decimals)).map(Decimal::from)
+                .with_context(|| format!("Слишком большие decimals: {}", decimals))
+        };
+        lots.checked_mul(scale(self.base_decimals)?)
+            .and_then(|price| price.checked_div(scale(self.quote_decimals).ok()?))
+            .context("Переполнение при расчёте цены")
+    }
+}
+
+/// Ордер в книге
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Order {
+    /// Цена: quote лотов за base лот
+    pub price_lots: u64,
+    /// Количество в base лотах
+    pub quantity: u64,
+}
+
+/// Ордера стороны книги с фиксированной ценой, от лучшей цены
+///
+/// Ордера с ценой относительно оракула (oracle pegged) не учитываются,
+/// истёкшие по time_in_force на момент `now` (unix-время) пропускаются.
+pub fn parse_book_side(data: &[u8], side: Side, now: u64) -> Result<Vec<Order>> {
+    if data.len() != BOOK_SIDE_ACCOUNT_SIZE {
+        anyhow::bail!(
+            "Размер аккаунта {} байт не совпадает с BookSide OpenBook v2 ({} байт)",
+            data.len(), BOOK_SIDE_ACCOUNT_SIZE
+        );
+    }
+    let root = u32::from_le_bytes(read_bytes(data, FIXED_ROOT_OFFSET)?);
+    let leaf_count = u32::from_le_bytes(read_bytes(data, FIXED_ROOT_OFFSET + 4)?);
+
+    let mut orders = Vec::new();
+    let mut stack = if leaf_count > 0 { vec![root] } else { Vec::new() };
+    let mut visited = 0;
+    while let Some(handle) = stack.pop() {
+        visited += 1;
+        if handle as usize >= MAX_NODES || visited > MAX_NODES {
+            anyhow::bail!("Повреждённое дерево ордеров: узел {}", handle);
+        }
+        let node = NODES_OFFSET + handle as usize * NODE_SIZE;
+        match data[node] {
+            INNER_NODE_TAG => {
+                stack.push(u32::from_le_bytes(read_bytes(data, node + INNER_CHILDREN_OFFSET)?));
+                stack.push(u32::from_le_bytes(read_bytes(data, node + INNER_CHILDREN_OFFSET + 4)?));
+            }
+            LEAF_NODE_TAG => {
+                let time_in_force = u16::from_le_bytes(read_bytes(data, node + LEAF_TIME_IN_FORCE_OFFSET)?);
+                let timestamp = u64::from_le_bytes(read_bytes(data, node + LEAF_TIMESTAMP_OFFSET)?);
+                if time_in_force != 0 && now >= timestamp.saturating_add(u64::from(time_in_force)) {
+                    continue;
+                }
+                let key = u128::from_le_bytes(read_bytes(data, node + LEAF_KEY_OFFSET)?);
+                let quantity = read_i64(data, node + LEAF_QUANTITY_OFFSET)?;
+                if quantity > 0 {
+                    orders.push(Order { price_lots: (key >> 64) as u64, quantity: quantity as u64 });
+                }
+            }
+            tag => anyhow::bail!("Неожиданный узел дерева ордеров: тег {}", tag),
+        }
+    }
+
+    match side {
+        Side::Bid => orders.sort_by_key(|order| std::cmp::Reverse(order.price_lots)),
+        Side::Ask => orders.sort_by_key(|order| order.price_lots),
+    }
+    Ok(orders)
+}
+
+/// Котировка рыночного ордера
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TakeQuote {
+    /// Получено в минимальных единицах (base для Bid, quote для Ask) после комиссии
+    pub amount_out: u64,
+    /// Потрачено из amount_in (остаток не исполнен: не хватило книги или лота)
+    pub amount_in_used: u64,
+    /// Комиссия тейкера в quote
+    pub fee_amount: u64,
+}
+
+/// Котировка рыночного ордера на amount_in по ордерам встречной стороны
+///
+/// Bid: amount_in — quote с учётом комиссии, `book` — asks. Ask: amount_in —
+/// base, `book` — bids. Исполнение идёт целыми лотами, как в программе.
+pub fn quote_take(market: &Market, book: &[Order], side: Side, amount_in: u64) -> Result<TakeQuote> {
+    let (base_lot, quote_lot) = (market.base_lot_size, market.quote_lot_size);
+    match side {
+        Side::Bid => {
+            // Комиссия взимается сверх цены: для ордеров остаётся amount_in / (1 + fee)
+            let budget = math::mul_div_floor(amount_in, FEES_SCALE_FACTOR, FEES_SCALE_FACTOR + market.taker_fee)?;
+            let mut remaining = budget;
+            let mut base_out: u64 = 0;
+            for order in book {
+                let lot_cost = order.price_lots.checked_mul(quote_lot).context("Переполнение цены лота")?;
+                let lots = (remaining / lot_cost).min(order.quantity);
+                remaining -= lots * lot_cost;
+                base_out = base_out.checked_add(lots * base_lot).context("Переполнение объёма")?;
+                if lots < order.quantity {
+                    break;
+                }
+            }
+            let spent = budget - remaining;
+            let fee_amount = math::mul_div_ceil(spent, market.taker_fee, FEES_SCALE_FACTOR)?;
+            Ok(TakeQuote { amount_out: base_out, amount_in_used: spent + fee_amount, fee_amount })
+        }
+        Side::Ask => {
+            let mut remaining = amount_in / base_lot;
+            let mut quote_out: u64 = 0;
+            for order in book {
+                if remaining == 0 {
+                    break;
+                }
+                let lots = remaining.min(order.quantity);
+                remaining -= lots;
+                let quote = lots.checked_mul(order.price_lots)
+                    .and_then(|quote_lots| quote_lots.checked_mul(quote_lot))
+                    .context("Переполнение суммы ордера")?;
+                quote_out = quote_out.checked_add(quote).context("Переполнение суммы")?;
+            }
+            let amount_out = market.taker_fee()?.deduct(quote_out)?;
+            Ok(TakeQuote {
+                amount_out,
+                amount_in_used: (amount_in / base_lot - remaining) * base_lot,
+                fee_amount: quote_out - amount_out,
+            })
+        }
+    }
+}
+
+/// Объём стороны книги в минимальных единицах: base для asks, quote для bids
+pub fn book_depth(market: &Market, book: &[Order], side: Side) -> u64 {
+    let total: u128 = book.iter()
+        .map(|order| match side {
+            Side::Ask => u128::from(order.quantity) * u128::from(market.base_lot_size),
+            Side::Bid => u128::from(order.quantity) * u128::from(order.price_lots) * u128::from(market.quote_lot_size),
+        })
+        .sum();
+    u64::try_from(total).unwrap_or(u64::MAX)
+}
+
+/// Параметры place_take_order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TakeOrderArgs {
+    pub side: Side,
+    /// Худшая допустимая цена в лотах (максимум для Bid, минимум для Ask)
+    pub price_lots: i64,
+    pub max_base_lots: i64,
+    pub max_quote_lots_including_fees: i64,
+}
+
+impl TakeOrderArgs {
+    /// Рыночный ордер на amount_in с ценой не хуже той, что даёт min_amount_out
+    ///
+    /// Программа не проверяет минимальный выход, поэтому он задаётся
+    /// предельной ценой ордера (без учёта комиссии тейкера): ордер может
+    /// исполниться частично, но ни один лот не исполнится по худшему курсу.
+    pub fn exact_in(market: &Market, side: Side, amount_in: u64, min_amount_out: u64) -> Self {
+        let (base_lot, quote_lot) = (u128::from(market.base_lot_size), u128::from(market.quote_lot_size));
+        let clamp = |value: u128| i64::try_from(value).unwrap_or(i64::MAX);
+        match side {
+            Side::Bid => Self {
+                side,
+                // Максимальная цена: amount_in quote за min_amount_out base
+                price_lots: if min_amount_out == 0 {
+                    i64::MAX
+                } else {
+                    clamp(u128::from(amount_in) * base_lot / (quote_lot * u128::from(min_amount_out))).max(1)
+                },
+                max_base_lots: i64::MAX,
+                max_quote_lots_including_fees: clamp(u128::from(amount_in) / quote_lot),
+            },
+            Side::Ask => {
+                let base_lots = u128::from(amount_in) / base_lot;
+                let denominator = quote_lot * base_lots;
+                Self {
+                    side,
+                    // Минимальная цена: min_amount_out quote за проданные лоты (округление вверх)
+                    price_lots: if denominator == 0 {
+                        1
+                    } else {
+                        clamp(u128::from(min_amount_out).div_ceil(denominator)).max(1)
+                    },
+                    max_base_lots: clamp(base_lots),
+                    max_quote_lots_including_fees: i64::MAX,
+                }
+            }
+        }
+    }
+}
+
+/// Discriminator инструкции Anchor
+fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash.to_bytes()[..8]);
+    discriminator
+}
+
+/// Тип ордера: немедленное исполнение, остаток отменяется
+const ORDER_TYPE_IMMEDIATE_OR_CANCEL: u8 = 1;
+/// Максимум ордеров книги, с которыми сводится один тейк
+const TAKE_ORDER_MATCH_LIMIT: u8 = 50;
+
+/// Инструкция place_take_order: рыночный ордер без аккаунта OpenOrders
+///
+/// Токены списываются с `user_base_account`/`user_quote_account` и
+/// зачисляются на них же в той же инструкции.
+pub fn place_take_order_instruction(
+    program_id: &Pubkey,
+    market_address: &Pubkey,
+    market: &Market,
+    signer: &Pubkey,
+    user_base_account: &Pubkey,
+    user_quote_account: &Pubkey,
+    args: &TakeOrderArgs,
+) -> Instruction {
+    // Необязательные аккаунты Anchor передаются адресом программы
+    let optional = |address: Option<Pubkey>| match address {
+        Some(address) => AccountMeta::new_readonly(address, false),
+        None => AccountMeta::new_readonly(*program_id, false),
+    };
+    let accounts = vec![
+        AccountMeta::new(*signer, true),
+        AccountMeta::new(*signer, true), // penalty_payer
+        AccountMeta::new(*market_address, false),
+        AccountMeta::new_readonly(market.market_authority, false),
+        AccountMeta::new(market.bids, false),
+        AccountMeta::new(market.asks, false),
+        AccountMeta::new(market.base_vault, false),
+        AccountMeta::new(market.quote_vault, false),
+        AccountMeta::new(market.event_heap, false),
+        AccountMeta::new(*user_base_account, false),
+        AccountMeta::new(*user_quote_account, false),
+        optional(market.oracle_a),
+        optional(market.oracle_b),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        optional(None), // open_orders_admin
+    ];
+
+    let mut data = Vec::with_capacity(35);
+    data.extend_from_slice(&instruction_discriminator("place_take_order"));
+    data.push(match args.side {
+        Side::Bid => 0,
+        Side::Ask => 1,
+    });
+    data.extend_from_slice(&args.price_lots.to_le_bytes());
+    data.extend_from_slice(&args.max_base_lots.to_le_bytes());
+    data.extend_from_slice(&args.max_quote_lots_including_fees.to_le_bytes());
+    data.push(ORDER_TYPE_IMMEDIATE_OR_CANCEL);
+    data.push(TAKE_ORDER_MATCH_LIMIT);
+
+    Instruction { program_id: *program_id, accounts, data }
+}
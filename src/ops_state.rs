@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::config::{Config, ExecutionMode};
+use crate::event_log::SafetyEvent;
+use crate::rpc::create_rpc_client;
+use crate::supervisor::Supervisor;
+use crate::units::{sol_to_lamports, Rounding};
+use crate::wallet::HotWallet;
+
+/// Значение флага, ещё не проверенного фоновой проверкой
+const UNKNOWN: u8 = u8::MAX;
+
+/// Флаг, который выставляет фоновая проверка (до первой проверки — неизвестен)
+#[derive(Debug)]
+struct ProbeFlag(AtomicU8);
+
+impl Default for ProbeFlag {
+    fn default() -> Self {
+        Self(AtomicU8::new(UNKNOWN))
+    }
+}
+
+impl ProbeFlag {
+    fn set(&self, value: bool) {
+        self.0.store(u8::from(value), Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Option<bool> {
+        match self.0.load(Ordering::Relaxed) {
+            UNKNOWN => None,
+            value => Some(value == 1),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct OpsStateInner {
+    simulation_mode: AtomicBool,
+    kill_switch: AtomicBool,
+    circuit_open_until: Mutex<Option<DateTime<Utc>>>,
+    rpc_healthy: ProbeFlag,
+    balance_above_min: ProbeFlag,
+}
+
+/// Рабочее состояние бота для правил Alertmanager
+///
+/// Режим, остановка исполнения и автомат сбоев обновляются по событиям
+/// `SafetyEvent`, доступность RPC и баланс — фоновой проверкой.
+#[derive(Debug, Clone)]
+pub struct OpsState {
+    mode: ExecutionMode,
+    inner: Arc<OpsStateInner>,
+}
+
+impl OpsState {
+    pub fn new(config: &Config) -> Self {
+        let inner = OpsStateInner::default();
+        inner.simulation_mode.store(config.safety.simulation_mode, Ordering::Relaxed);
+        Self { mode: config.safety.mode, inner: Arc::new(inner) }
+    }
+
+    /// Учёт перехода режима работы или защитного механизма
+    pub fn apply(&self, event: &SafetyEvent) {
+        match event {
+            SafetyEvent::KillSwitch { engaged, .. } => {
+                self.inner.kill_switch.store(*engaged, Ordering::Relaxed);
+                // Снятие остановки сбрасывает и автомат сбоев
+                if !engaged {
+                    *self.inner.circuit_open_until.lock().expect("мьютекс состояния бота отравлен") = None;
+                }
+            }
+            SafetyEvent::CircuitBreakerTripped { until, .. } => {
+                *self.inner.circuit_open_until.lock().expect("мьютекс состояния бота отравлен") = Some(*until);
+            }
+            SafetyEvent::ModeChanged { simulation_mode, effective: true } => {
+                self.inner.simulation_mode.store(*simulation_mode, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+
+    /// Бот исполняет реальные сделки (не симуляция и режим с исполнением)
+    pub fn live_mode(&self) -> bool {
+        self.mode.executes() && !self.inner.simulation_mode.load(Ordering::Relaxed)
+    }
+
+    pub fn kill_switch_engaged(&self) -> bool {
+        self.inner.kill_switch.load(Ordering::Relaxed)
+    }
+
+    pub fn circuit_breaker_open(&self, now: DateTime<Utc>) -> bool {
+        self.inner.circuit_open_until.lock().expect("мьютекс состояния бота отравлен")
+            .is_some_and(|until| now < until)
+    }
+
+    /// Доступность RPC по последней проверке (None — проверок ещё не было)
+    pub fn rpc_healthy(&self) -> Option<bool> {
+        self.inner.rpc_healthy.get()
+    }
+
+    /// Баланс не ниже safety.min_balance_sol по последней проверке (None — проверок ещё не было)
+    pub fn balance_above_min(&self) -> Option<bool> {
+        self.inner.balance_above_min.get()
+    }
+
+    pub fn record_rpc_health(&self, healthy: bool) {
+        self.inner.rpc_healthy.set(healthy);
+    }
+
+    pub fn record_balance_check(&self, above_min: bool) {
+        self.inner.balance_above_min.set(above_min);
+    }
+
+    /// Gauge в текстовом формате Prometheus (значения 0/1)
+    ///
+    /// Непроверенные состояния не выводятся, чтобы правило не срабатывало
+    /// до первой проверки (для них подходит `absent()`).
+    pub fn render_prometheus(&self, now: DateTime<Utc>) -> String {
+        let gauges = [
+            ("arb_bot_live_mode", "Бот исполняет реальные сделки", Some(self.live_mode())),
+            ("arb_bot_kill_switch_engaged", "Исполнение остановлено до перезапуска торгового цикла", Some(self.kill_switch_engaged())),
+            ("arb_bot_circuit_breaker_open", "Автомат сбоев RPC разомкнут", Some(self.circuit_breaker_open(now))),
+            ("arb_bot_rpc_healthy", "RPC отвечает на getHealth", self.rpc_healthy()),
+            ("arb_bot_balance_above_min", "Баланс кошелька не ниже safety.min_balance_sol", self.balance_above_min()),
+        ];
+        let mut output = String::new();
+        for (name, help, value) in gauges {
+            let Some(value) = value else { continue };
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} gauge", name);
+            let _ = writeln!(output, "{} {}", name, u8::from(value));
+        }
+        output
+    }
+}
+
+/// Проверка доступности RPC (getHealth)
+async fn check_rpc_health(config: &Config) -> Result<()> {
+    let network = config.network.clone();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        let client = create_rpc_client(&network)?;
+        client.get_health().context("RPC сообщает о неготовности")
+    })
+    .await
+    .context("Задача проверки RPC завершилась с ошибкой")?
+}
+
+/// Запуск фоновой проверки RPC и баланса под надзором
+pub fn spawn_state_probe(supervisor: &Supervisor, config: &Config, wallet: HotWallet, state: OpsState) {
+    if config.monitoring.state_probe_interval_sec == 0 {
+        return;
+    }
+    let config = config.clone();
+    supervisor.spawn("state_probe", move || run_state_probe(config.clone(), wallet.clone(), state.clone()));
+}
+
+async fn run_state_probe(config: Config, wallet: HotWallet, state: OpsState) -> Result<()> {
+    let min_balance = sol_to_lamports(
+        Decimal::from_f64(config.safety.min_balance_sol).unwrap_or(Decimal::ZERO),
+        Rounding::Up,
+    )?;
+    let mut timer = tokio::time::interval(Duration::from_secs(config.monitoring.state_probe_interval_sec));
+    loop {
+        timer.tick().await;
+        match check_rpc_health(&config).await {
+            Ok(()) => state.record_rpc_health(true),
+            Err(e) => {
+                log::warn!("RPC недоступен: {:#}", e);
+                state.record_rpc_health(false);
+            }
+        }
+        // Процесс поиска (mode = "scan") работает без ключа кошелька
+        if config.safety.mode == ExecutionMode::Scan {
+            continue;
+        }
+        match wallet.current().await.get_balance(&config.network).await {
+            Ok(lamports) => state.record_balance_check(lamports >= min_balance),
+            Err(e) => log::warn!("Не удалось проверить баланс кошелька: {:#}", e),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::config::{OrcaApiConfig, ProxyConfig};
+use crate::fiat::{get_json, http_client, parse_price};
+
+/// Whirlpool из списка Orca API (`/v1/whirlpool/list`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrcaApiPool {
+    pub address: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub tick_spacing: u16,
+    /// Комиссия пула (fee tier) в процентах
+    pub fee_percent: Decimal,
+    /// Ликвидность пула в USD (None, если API её не вернул)
+    pub tvl_usd: Option<Decimal>,
+}
+
+fn pubkey(value: &Value, field: &str) -> Result<Pubkey> {
+    let text = value.as_str().with_context(|| format!("Нет поля {}", field))?;
+    Pubkey::from_str(text).with_context(|| format!("Некорректный адрес в поле {}: {}", field, text))
+}
+
+/// Разбор списка whirlpools
+///
+/// Записи, которые не удалось разобрать, пропускаются (в списке тысячи пулов,
+/// одна некорректная запись не должна ломать поиск остальных).
+pub fn parse_whirlpools(body: &Value) -> Result<Vec<OrcaApiPool>> {
+    let items = body["whirlpools"].as_array()
+        .context("Orca API: нет списка whirlpools в ответе")?;
+
+    let mut skipped = 0;
+    let pools: Vec<OrcaApiPool> = items.iter()
+        .filter_map(|item| {
+            let pool = (|| -> Result<OrcaApiPool> {
+                Ok(OrcaApiPool {
+                    address: pubkey(&item["address"], "address")?,
+                    mint_a: pubkey(&item["tokenA"]["mint"], "tokenA.mint")?,
+                    mint_b: pubkey(&item["tokenB"]["mint"], "tokenB.mint")?,
+                    tick_spacing: item["tickSpacing"].as_u64()
+                        .and_then(|spacing| u16::try_from(spacing).ok())
+                        .context("Поле tickSpacing")?,
+                    // lpFeeRate — доля (0.003 = 0.3%)
+                    fee_percent: parse_price(&item["lpFeeRate"]).context("Поле lpFeeRate")? * Decimal::from(100),
+                    tvl_usd: parse_price(&item["tvl"]).ok(),
+                })
+            })();
+            pool.map_err(|_| skipped += 1).ok()
+        })
+        .collect();
+    if skipped > 0 {
+        log::debug!("Orca API: пропущено нераспознанных whirlpools: {}", skipped);
+    }
+    Ok(pools)
+}
+
+/// Загруженный список whirlpools
+struct PoolList {
+    fetched_at: Instant,
+    pools: Arc<Vec<OrcaApiPool>>,
+}
+
+/// Клиент Orca API для поиска whirlpools
+///
+/// Список всех whirlpools загружается целиком и обновляется не чаще
+/// `refresh_interval_sec`. Если обновить список не удалось, используется
+/// прежний (если он есть), а ошибка возвращается только без списка.
+pub struct OrcaApi {
+    client: reqwest::Client,
+    base_url: String,
+    refresh_interval: Duration,
+    list: Mutex<Option<PoolList>>,
+}
+
+impl OrcaApi {
+    pub fn new(config: &OrcaApiConfig, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client("Orca API", proxy)?,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            refresh_interval: Duration::from_secs(config.refresh_interval_sec),
+            list: Mutex::new(None),
+        })
+    }
+
+    /// Все whirlpools (из кеша, если список свежий)
+    pub async fn whirlpools(&self) -> Result<Arc<Vec<OrcaApiPool>>> {
+        let mut list = self.list.lock().await;
+        if let Some(ref current) = *list {
+            if current.fetched_at.elapsed() < self.refresh_interval {
+                return Ok(current.pools.clone());
+            }
+        }
+
+        let url = format!("{}/v1/whirlpool/list", self.base_url);
+        let fetched = match get_json(&self.client, &url, "Orca API").await {
+            Ok(body) => parse_whirlpools(&body),
+            Err(e) => Err(e),
+        };
+        match (fetched, list.as_mut()) {
+            (Ok(pools), _) => {
+                log::debug!("Orca API: загружено whirlpools: {}", pools.len());
+                let pools = Arc::new(pools);
+                *list = Some(PoolList { fetched_at: Instant::now(), pools: pools.clone() });
+                Ok(pools)
+            }
+            (Err(e), Some(stale)) => {
+                log::warn!("Orca API: не удалось обновить список whirlpools, используем прежний: {:#}", e);
+                // Следующая попытка — через интервал обновления, а не на каждом запросе
+                stale.fetched_at = Instant::now();
+                Ok(stale.pools.clone())
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    /// Whirlpools пары (в любом порядке mint)
+    pub async fn pools_by_mints(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Vec<OrcaApiPool>> {
+        Ok(self.whirlpools().await?
+            .iter()
+            .filter(|pool| {
+                (pool.mint_a == *mint_a && pool.mint_b == *mint_b)
+                    || (pool.mint_a == *mint_b && pool.mint_b == *mint_a)
+            })
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use crate::config::{Config, PairDiscoveryConfig};
+use crate::dex::DexManager;
+use crate::tokens::TokenRegistry;
+use crate::wallet::HotWallet;
+
+/// Пары из балансов SPL токенов кошелька (mint → количество в целых токенах)
+///
+/// Base токен пары — токен реестра с балансом выше min_balance, quote — каждый
+/// из quote_tokens, кроме самого токена. Mint вне реестра (`[tokens]` и
+/// встроенные) пропускаются: адаптеры DEX работают с символами.
+pub fn discover_pairs(
+    holdings: &BTreeMap<String, Decimal>,
+    tokens: &TokenRegistry,
+    config: &PairDiscoveryConfig,
+) -> Vec<String> {
+    let min_balance = Decimal::from_f64(config.min_balance).unwrap_or(Decimal::ZERO);
+    let mut pairs = Vec::new();
+    for (mint, balance) in holdings {
+        if *balance <= min_balance {
+            continue;
+        }
+        let Some(base_token) = Pubkey::from_str(mint).ok().and_then(|mint| tokens.symbol(&mint)) else {
+            log::debug!("Токен {} кошелька не известен реестру — пара не добавлена", mint);
+            continue;
+        };
+        for quote_token in &config.quote_tokens {
+            if quote_token != base_token {
+                pairs.push(format!("{}/{}", base_token, quote_token));
+            }
+        }
+    }
+    pairs
+}
+
+/// Обновление пар из токенов кошелька (задача `pair_discovery` планировщика)
+///
+/// Если балансы прочитать не удалось, прежний список пар остаётся до
+/// следующего успешного запуска. Возвращает количество найденных пар.
+pub async fn refresh_discovered_pairs(config: &Config, wallet: &HotWallet, dex_manager: &DexManager) -> Result<usize> {
+    let current = wallet.current().await;
+    let holdings = current.get_token_balances(&config.network).await
+        .context("Не удалось получить балансы токенов")?;
+
+    let before = dex_manager.discovered_pairs();
+    dex_manager.set_discovered_pairs(discover_pairs(&holdings, &dex_manager.tokens(), &config.pair_discovery));
+    let after = dex_manager.discovered_pairs();
+    let added: Vec<&str> = after.iter().filter(|pair| !before.contains(pair)).map(String::as_str).collect();
+    let removed: Vec<&str> = before.iter().filter(|pair| !after.contains(pair)).map(String::as_str).collect();
+    if !added.is_empty() {
+        log::info!("Пары из токенов кошелька добавлены в поиск: {}", added.join(", "));
+    }
+    if !removed.is_empty() {
+        log::info!("Пары из токенов кошелька убраны из поиска: {}", removed.join(", "));
+    }
+    Ok(after.len())
+}
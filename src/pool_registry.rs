@@ -0,0 +1,833 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::config::{Config, NetworkConfig};
+use crate::custom_amm;
+use crate::lifinity;
+use crate::openbook_v2;
+use crate::orca_api::{OrcaApi, OrcaApiPool};
+use crate::raydium_api::{RaydiumApi, RaydiumApiPool, RaydiumPoolKeys};
+use crate::rpc_cache::{CachedKind, RpcCache};
+use crate::saber;
+use crate::rpc::create_rpc_client;
+
+/// Размер аккаунта пула Raydium AMM v4
+pub const AMM_V4_ACCOUNT_SIZE: u64 = 752;
+/// Смещение mint base токена (coin) в аккаунте пула AMM v4
+pub const AMM_V4_COIN_MINT_OFFSET: usize = 400;
+/// Смещение mint quote токена (pc) в аккаунте пула AMM v4
+pub const AMM_V4_PC_MINT_OFFSET: usize = 432;
+
+/// Размер аккаунта Orca Whirlpool
+pub const WHIRLPOOL_ACCOUNT_SIZE: u64 = 653;
+/// Смещение fee_rate (u16, сотые доли базисного пункта) в аккаунте Whirlpool
+pub const WHIRLPOOL_FEE_RATE_OFFSET: usize = 45;
+/// Смещение liquidity (u128) в аккаунте Whirlpool
+pub const WHIRLPOOL_LIQUIDITY_OFFSET: usize = 49;
+/// Смещение mint токена A в аккаунте Whirlpool
+pub const WHIRLPOOL_MINT_A_OFFSET: usize = 101;
+/// Смещение mint токена B в аккаунте Whirlpool
+pub const WHIRLPOOL_MINT_B_OFFSET: usize = 181;
+
+/// Откуда известен пул
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolSource {
+    /// HTTP API DEX (с комиссией и ликвидностью)
+    Api,
+    /// Поиск по аккаунтам программы
+    OnChain,
+}
+
+/// Пул пары в реестре
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolInfo {
+    pub address: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// Комиссия пула в процентах (None — неизвестна, используется типичная для DEX)
+    pub fee_percent: Option<Decimal>,
+    /// Ликвидность пула в USD (None — неизвестна)
+    pub liquidity_usd: Option<Decimal>,
+    pub source: PoolSource,
+}
+
+/// Самый ликвидный пул нужной программы из ответа Raydium API
+pub fn select_api_pool(pools: &[RaydiumApiPool], program_id: &Pubkey) -> Option<PoolInfo> {
+    pools.iter()
+        .filter(|pool| pool.program_id == *program_id)
+        .max_by_key(|pool| pool.tvl_usd.unwrap_or(Decimal::ZERO))
+        .map(|pool| PoolInfo {
+            address: pool.id,
+            mint_a: pool.mint_a,
+            mint_b: pool.mint_b,
+            fee_percent: Some(pool.fee_percent),
+            liquidity_usd: pool.tvl_usd,
+            source: PoolSource::Api,
+        })
+}
+
+/// Самый крупный whirlpool пары из списка Orca API
+///
+/// Пулы без TVL в ответе считаются наименьшими.
+pub fn select_whirlpool(pools: &[OrcaApiPool]) -> Option<PoolInfo> {
+    pools.iter()
+        .max_by_key(|pool| pool.tvl_usd.unwrap_or(Decimal::ZERO))
+        .map(|pool| PoolInfo {
+            address: pool.address,
+            mint_a: pool.mint_a,
+            mint_b: pool.mint_b,
+            fee_percent: Some(pool.fee_percent),
+            liquidity_usd: pool.tvl_usd,
+            source: PoolSource::Api,
+        })
+}
+
+/// Комиссия (в процентах) и ликвидность из данных аккаунта Whirlpool
+pub fn parse_whirlpool_account(data: &[u8]) -> Option<(Decimal, u128)> {
+    let fee_rate = data.get(WHIRLPOOL_FEE_RATE_OFFSET..WHIRLPOOL_FEE_RATE_OFFSET + 2)?;
+    let liquidity = data.get(WHIRLPOOL_LIQUIDITY_OFFSET..WHIRLPOOL_LIQUIDITY_OFFSET + 16)?;
+    let fee_rate = u16::from_le_bytes(fee_rate.try_into().ok()?);
+    // fee_rate в сотых долях базисного пункта: 3000 = 0.3%
+    Some((
+        Decimal::from(fee_rate) / Decimal::from(10_000),
+        u128::from_le_bytes(liquidity.try_into().ok()?),
+    ))
+}
+
+fn pair_key(mint_a: &Pubkey, mint_b: &Pubkey) -> (Pubkey, Pubkey) {
+    if mint_a <= mint_b { (*mint_a, *mint_b) } else { (*mint_b, *mint_a) }
+}
+
+/// Ключ пула пары в кеше RPC
+fn pool_cache_key(dex: &str, mint_a: &Pubkey, mint_b: &Pubkey) -> String {
+    let (first, second) = pair_key(mint_a, mint_b);
+    format!("{}:{}:{}", dex, first, second)
+}
+
+/// Ключ ключей пула Raydium в кеше RPC
+fn pool_keys_cache_key(pool: &Pubkey) -> String {
+    format!("raydium_keys:{}", pool)
+}
+
+/// Реестр пулов Raydium AMM v4
+///
+/// Пул пары ищется сначала через Raydium API (оттуда же берутся комиссия,
+/// ликвидность и ключи пула), а если API недоступен или не знает пула — по
+/// аккаунтам программы on-chain. Найденный пул сохраняется в кеш RPC на диске
+/// на `rpc_cache.pool_ttl_sec`, поэтому перезапуск не повторяет дорогой поиск.
+pub struct RaydiumPoolRegistry {
+    api: Option<RaydiumApi>,
+    program_id: Pubkey,
+    network: NetworkConfig,
+    rpc_cache: Arc<RpcCache>,
+    pools: Mutex<HashMap<(Pubkey, Pubkey), PoolInfo>>,
+}
+
+impl RaydiumPoolRegistry {
+    pub fn new(config: &Config, program_id: Pubkey, rpc_cache: Arc<RpcCache>) -> Self {
+        let api = if config.raydium_api.enabled {
+            RaydiumApi::new(&config.raydium_api, &config.network.proxy)
+                .map_err(|e| log::warn!("Raydium API недоступен, пулы ищутся только on-chain: {:#}", e))
+                .ok()
+        } else {
+            None
+        };
+        Self {
+            api,
+            program_id,
+            network: config.network.clone(),
+            rpc_cache,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Пул пары из реестра без запросов (None — ещё не найден)
+    pub fn cached(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<PoolInfo> {
+        let pool = self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .get(&pair_key(mint_a, mint_b))
+            .cloned();
+        pool.or_else(|| {
+            // Пул, найденный до перезапуска
+            let pool: PoolInfo = self.rpc_cache.get(CachedKind::Pool, &pool_cache_key("raydium", mint_a, mint_b))?;
+            self.pools.lock()
+                .expect("мьютекс реестра пулов отравлен")
+                .insert(pair_key(mint_a, mint_b), pool.clone());
+            Some(pool)
+        })
+    }
+
+    /// Ключи пула из Raydium API (None — пул найден не через API)
+    pub fn pool_keys(&self, pool: &Pubkey) -> Option<RaydiumPoolKeys> {
+        self.rpc_cache.get(CachedKind::Market, &pool_keys_cache_key(pool))
+    }
+
+    /// Пул пары: из реестра, через API или on-chain
+    pub async fn resolve(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        if let Some(pool) = self.cached(base_mint, quote_mint) {
+            return Ok(pool);
+        }
+
+        let from_api = match self.api {
+            Some(ref api) => match api.pools_by_mints(base_mint, quote_mint).await {
+                Ok(pools) => {
+                    let pool = select_api_pool(&pools, &self.program_id);
+                    match pool {
+                        Some(ref pool) => self.fetch_pool_keys(api, &pool.address).await,
+                        None => log::debug!("Raydium API: нет пула AMM v4 для {}/{}", base_mint, quote_mint),
+                    }
+                    pool
+                }
+                Err(e) => {
+                    log::warn!("Raydium API недоступен, поиск пула on-chain: {:#}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let pool = match from_api {
+            Some(pool) => pool,
+            None => self.discover_on_chain(base_mint, quote_mint).await?,
+        };
+
+        log::info!(
+            "Raydium: пул {}/{} — {} ({:?}, комиссия {}, ликвидность {} USD)",
+            base_mint, quote_mint, pool.address, pool.source,
+            pool.fee_percent.map(|fee| format!("{}%", fee)).unwrap_or_else(|| "неизвестна".to_string()),
+            pool.liquidity_usd.map(|tvl| tvl.round_dp(0).to_string()).unwrap_or_else(|| "?".to_string()),
+        );
+        self.rpc_cache.insert(CachedKind::Pool, &pool_cache_key("raydium", base_mint, quote_mint), &pool);
+        self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .insert(pair_key(base_mint, quote_mint), pool.clone());
+        Ok(pool)
+    }
+
+    /// Загрузка ключей пула из API в кеш (без них адреса читаются из аккаунта пула)
+    async fn fetch_pool_keys(&self, api: &RaydiumApi, pool: &Pubkey) {
+        if self.pool_keys(pool).is_some() {
+            return;
+        }
+        match api.pool_keys(pool).await {
+            Ok(Some(keys)) if keys.program_id == self.program_id => {
+                self.rpc_cache.insert(CachedKind::Market, &pool_keys_cache_key(pool), &keys);
+            }
+            Ok(_) => log::debug!("Raydium API: нет ключей AMM v4 для пула {}", pool),
+            Err(e) => log::debug!("Raydium API: ключи пула {} не получены: {:#}", pool, e),
+        }
+    }
+
+    /// Поиск пула по mint в аккаунтах программы (в обоих порядках токенов)
+    async fn discover_on_chain(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        let network = self.network.clone();
+        let program_id = self.program_id;
+        let (base_mint, quote_mint) = (*base_mint, *quote_mint);
+        tokio::task::spawn_blocking(move || -> Result<PoolInfo> {
+            let client = create_rpc_client(&network)?;
+            for (coin, pc) in [(base_mint, quote_mint), (quote_mint, base_mint)] {
+                let config = RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(AMM_V4_ACCOUNT_SIZE),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(AMM_V4_COIN_MINT_OFFSET, coin.as_ref())),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(AMM_V4_PC_MINT_OFFSET, pc.as_ref())),
+                    ]),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                let accounts = client.get_program_accounts_with_config(&program_id, config)
+                    .context("Не удалось найти пулы Raydium on-chain")?;
+                if let Some((address, _)) = accounts.into_iter().next() {
+                    return Ok(PoolInfo {
+                        address,
+                        mint_a: coin,
+                        mint_b: pc,
+                        fee_percent: None,
+                        liquidity_usd: None,
+                        source: PoolSource::OnChain,
+                    });
+                }
+            }
+            anyhow::bail!("Пул Raydium для {}/{} не найден", base_mint, quote_mint)
+        })
+        .await
+        .context("Задача поиска пула завершилась с ошибкой")?
+    }
+}
+
+/// Реестр whirlpools Orca
+///
+/// Пул пары (с fee tier) берётся из списка whirlpools Orca API — самый крупный
+/// по TVL, а если API недоступен или не знает пары — самый ликвидный из
+/// аккаунтов программы on-chain. Найденный пул перепроверяется раз в
+/// `orca_api.refresh_interval_sec`: ликвидность переходит между fee tier.
+pub struct WhirlpoolRegistry {
+    api: Option<OrcaApi>,
+    program_id: Pubkey,
+    network: NetworkConfig,
+    refresh_interval: Duration,
+    pools: Mutex<HashMap<(Pubkey, Pubkey), (Instant, PoolInfo)>>,
+}
+
+impl WhirlpoolRegistry {
+    pub fn new(config: &Config, program_id: Pubkey) -> Self {
+        let api = if config.orca_api.enabled {
+            OrcaApi::new(&config.orca_api, &config.network.proxy)
+                .map_err(|e| log::warn!("Orca API недоступен, whirlpools ищутся только on-chain: {:#}", e))
+                .ok()
+        } else {
+            None
+        };
+        Self {
+            api,
+            program_id,
+            network: config.network.clone(),
+            refresh_interval: Duration::from_secs(config.orca_api.refresh_interval_sec),
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Пул пары из реестра без запросов (None — ещё не найден или устарел)
+    pub fn cached(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<PoolInfo> {
+        self.pools.lock()
+            .expect("мьютекс реестра whirlpools отравлен")
+            .get(&pair_key(mint_a, mint_b))
+            .filter(|(found_at, _)| found_at.elapsed() < self.refresh_interval)
+            .map(|(_, pool)| pool.clone())
+    }
+
+    /// Пул пары: из реестра, через API или on-chain
+    ///
+    /// Если обновить устаревшую запись не удалось, используется прежний пул.
+    pub async fn resolve(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        if let Some(pool) = self.cached(base_mint, quote_mint) {
+            return Ok(pool);
+        }
+
+        let from_api = match self.api {
+            Some(ref api) => match api.pools_by_mints(base_mint, quote_mint).await {
+                Ok(pools) => {
+                    let pool = select_whirlpool(&pools);
+                    if pool.is_none() {
+                        log::debug!("Orca API: нет whirlpool для {}/{}", base_mint, quote_mint);
+                    }
+                    pool
+                }
+                Err(e) => {
+                    log::warn!("Orca API недоступен, поиск whirlpool on-chain: {:#}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let pool = match from_api {
+            Some(pool) => pool,
+            None => match self.discover_on_chain(base_mint, quote_mint).await {
+                Ok(pool) => pool,
+                Err(e) => {
+                    let stale = self.pools.lock()
+                        .expect("мьютекс реестра whirlpools отравлен")
+                        .get(&pair_key(base_mint, quote_mint))
+                        .map(|(_, pool)| pool.clone());
+                    match stale {
+                        Some(pool) => {
+                            log::warn!("Whirlpool {}/{} не обновлён, используем прежний: {:#}", base_mint, quote_mint, e);
+                            pool
+                        }
+                        None => return Err(e),
+                    }
+                }
+            },
+        };
+
+        log::info!(
+            "Orca: whirlpool {}/{} — {} ({:?}, комиссия {}, ликвидность {} USD)",
+            base_mint, quote_mint, pool.address, pool.source,
+            pool.fee_percent.map(|fee| format!("{}%", fee)).unwrap_or_else(|| "неизвестна".to_string()),
+            pool.liquidity_usd.map(|tvl| tvl.round_dp(0).to_string()).unwrap_or_else(|| "?".to_string()),
+        );
+        self.pools.lock()
+            .expect("мьютекс реестра whirlpools отравлен")
+            .insert(pair_key(base_mint, quote_mint), (Instant::now(), pool.clone()));
+        Ok(pool)
+    }
+
+    /// Поиск самого ликвидного whirlpool пары в аккаунтах программы
+    async fn discover_on_chain(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        let network = self.network.clone();
+        let program_id = self.program_id;
+        let (base_mint, quote_mint) = (*base_mint, *quote_mint);
+        tokio::task::spawn_blocking(move || -> Result<PoolInfo> {
+            let client = create_rpc_client(&network)?;
+            let mut best: Option<(u128, PoolInfo)> = None;
+            for (mint_a, mint_b) in [(base_mint, quote_mint), (quote_mint, base_mint)] {
+                let config = RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(WHIRLPOOL_ACCOUNT_SIZE),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(WHIRLPOOL_MINT_A_OFFSET, mint_a.as_ref())),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(WHIRLPOOL_MINT_B_OFFSET, mint_b.as_ref())),
+                    ]),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                let accounts = client.get_program_accounts_with_config(&program_id, config)
+                    .context("Не удалось найти whirlpools on-chain")?;
+                for (address, account) in accounts {
+                    let Some((fee_percent, liquidity)) = parse_whirlpool_account(&account.data) else { continue };
+                    if best.as_ref().is_none_or(|(best_liquidity, _)| liquidity > *best_liquidity) {
+                        best = Some((liquidity, PoolInfo {
+                            address,
+                            mint_a,
+                            mint_b,
+                            fee_percent: Some(fee_percent),
+                            liquidity_usd: None,
+                            source: PoolSource::OnChain,
+                        }));
+                    }
+                }
+            }
+            best.map(|(_, pool)| pool)
+                .with_context(|| format!("Whirlpool для {}/{} не найден", base_mint, quote_mint))
+        })
+        .await
+        .context("Задача поиска whirlpool завершилась с ошибкой")?
+    }
+}
+
+/// Реестр рынков OpenBook v2
+///
+/// Рынок пары ищется по mint в аккаунтах программы (в обоих порядках токенов),
+/// из нескольких рынков выбирается тот, у которого больше quote на счетах.
+/// Найденный рынок сохраняется в кеш RPC на диске, как пулы Raydium.
+pub struct OpenBookMarketRegistry {
+    program_id: Pubkey,
+    network: NetworkConfig,
+    rpc_cache: Arc<RpcCache>,
+    markets: Mutex<HashMap<(Pubkey, Pubkey), PoolInfo>>,
+}
+
+impl OpenBookMarketRegistry {
+    pub fn new(config: &Config, program_id: Pubkey, rpc_cache: Arc<RpcCache>) -> Self {
+        Self {
+            program_id,
+            network: config.network.clone(),
+            rpc_cache,
+            markets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Рынок пары из реестра без запросов (None — ещё не найден)
+    pub fn cached(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<PoolInfo> {
+        let market = self.markets.lock()
+            .expect("мьютекс реестра рынков отравлен")
+            .get(&pair_key(mint_a, mint_b))
+            .cloned();
+        market.or_else(|| {
+            // Рынок, найденный до перезапуска
+            let market: PoolInfo = self.rpc_cache.get(CachedKind::Pool, &pool_cache_key("openbook_v2", mint_a, mint_b))?;
+            self.markets.lock()
+                .expect("мьютекс реестра рынков отравлен")
+                .insert(pair_key(mint_a, mint_b), market.clone());
+            Some(market)
+        })
+    }
+
+    /// Рынок пары: из реестра или on-chain
+    pub async fn resolve(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        if let Some(market) = self.cached(base_mint, quote_mint) {
+            return Ok(market);
+        }
+
+        let market = self.discover_on_chain(base_mint, quote_mint).await?;
+        log::info!(
+            "OpenBook v2: рынок {}/{} — {} (комиссия тейкера {})",
+            market.mint_a, market.mint_b, market.address,
+            market.fee_percent.map(|fee| format!("{}%", fee)).unwrap_or_else(|| "неизвестна".to_string()),
+        );
+        self.rpc_cache.insert(CachedKind::Pool, &pool_cache_key("openbook_v2", base_mint, quote_mint), &market);
+        self.markets.lock()
+            .expect("мьютекс реестра рынков отравлен")
+            .insert(pair_key(base_mint, quote_mint), market.clone());
+        Ok(market)
+    }
+
+    /// Поиск рынка пары с наибольшим quote на счетах
+    async fn discover_on_chain(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        let network = self.network.clone();
+        let program_id = self.program_id;
+        let (base_mint, quote_mint) = (*base_mint, *quote_mint);
+        tokio::task::spawn_blocking(move || -> Result<PoolInfo> {
+            let client = create_rpc_client(&network)?;
+            let mut best: Option<(u64, PoolInfo)> = None;
+            for (base, quote) in [(base_mint, quote_mint), (quote_mint, base_mint)] {
+                let config = RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(openbook_v2::MARKET_ACCOUNT_SIZE),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(openbook_v2::MARKET_BASE_MINT_OFFSET, base.as_ref())),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(openbook_v2::MARKET_QUOTE_MINT_OFFSET, quote.as_ref())),
+                    ]),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                let accounts = client.get_program_accounts_with_config(&program_id, config)
+                    .context("Не удалось найти рынки OpenBook v2 on-chain")?;
+                for (address, account) in accounts {
+                    let Ok(market) = openbook_v2::Market::parse(&account.data) else { continue };
+                    if best.as_ref().is_none_or(|(deposits, _)| market.quote_deposit_total > *deposits) {
+                        best = Some((market.quote_deposit_total, PoolInfo {
+                            address,
+                            mint_a: base,
+                            mint_b: quote,
+                            fee_percent: market.taker_fee().ok().map(|fee| fee.percent()),
+                            liquidity_usd: None,
+                            source: PoolSource::OnChain,
+                        }));
+                    }
+                }
+            }
+            best.map(|(_, market)| market)
+                .with_context(|| format!("Рынок OpenBook v2 для {}/{} не найден", base_mint, quote_mint))
+        })
+        .await
+        .context("Задача поиска рынка завершилась с ошибкой")?
+    }
+}
+
+/// Смещение amount (u64) в аккаунте SPL Token
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// Пул с наибольшим quote в хранилище из найденных (хранилище quote, пул)
+fn largest_quote_pool(
+    client: &solana_client::rpc_client::RpcClient,
+    mut candidates: Vec<(Pubkey, PoolInfo)>,
+    dex: &str,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Result<PoolInfo> {
+    if candidates.len() <= 1 {
+        return candidates.pop().map(|(_, pool)| pool)
+            .with_context(|| format!("Пул {} для {}/{} не найден", dex, base_mint, quote_mint));
+    }
+
+    // Несколько пулов пары: выбирается пул с наибольшим quote в хранилище
+    let vaults: Vec<Pubkey> = candidates.iter().map(|(vault, _)| *vault).collect();
+    let balances = client.get_multiple_accounts(&vaults)
+        .with_context(|| format!("Не удалось получить хранилища пулов {}", dex))?;
+    let quote_amount = |account: &Option<solana_sdk::account::Account>| {
+        account.as_ref()
+            .and_then(|account| account.data.get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8))
+            .and_then(|amount| amount.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0)
+    };
+    candidates.into_iter()
+        .zip(balances.iter().map(quote_amount))
+        .max_by_key(|(_, amount)| *amount)
+        .map(|((_, pool), _)| pool)
+        .with_context(|| format!("Пул {} для {}/{} не найден", dex, base_mint, quote_mint))
+}
+
+/// Реестр пулов Lifinity v2
+///
+/// Пул пары ищется по mint в аккаунтах программы (в обоих порядках токенов),
+/// пулы с остановленными свопами пропускаются, из нескольких пулов выбирается
+/// тот, у которого больше quote в хранилище. Найденный пул сохраняется в кеш
+/// RPC на диске, как пулы Raydium.
+pub struct LifinityPoolRegistry {
+    program_id: Pubkey,
+    network: NetworkConfig,
+    rpc_cache: Arc<RpcCache>,
+    pools: Mutex<HashMap<(Pubkey, Pubkey), PoolInfo>>,
+}
+
+impl LifinityPoolRegistry {
+    pub fn new(config: &Config, program_id: Pubkey, rpc_cache: Arc<RpcCache>) -> Self {
+        Self {
+            program_id,
+            network: config.network.clone(),
+            rpc_cache,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Пул пары из реестра без запросов (None — ещё не найден)
+    pub fn cached(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<PoolInfo> {
+        let pool = self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .get(&pair_key(mint_a, mint_b))
+            .cloned();
+        pool.or_else(|| {
+            // Пул, найденный до перезапуска
+            let pool: PoolInfo = self.rpc_cache.get(CachedKind::Pool, &pool_cache_key("lifinity", mint_a, mint_b))?;
+            self.pools.lock()
+                .expect("мьютекс реестра пулов отравлен")
+                .insert(pair_key(mint_a, mint_b), pool.clone());
+            Some(pool)
+        })
+    }
+
+    /// Пул пары: из реестра или on-chain
+    pub async fn resolve(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        if let Some(pool) = self.cached(base_mint, quote_mint) {
+            return Ok(pool);
+        }
+
+        let pool = self.discover_on_chain(base_mint, quote_mint).await?;
+        log::info!(
+            "Lifinity: пул {}/{} — {} (комиссия {})",
+            pool.mint_a, pool.mint_b, pool.address,
+            pool.fee_percent.map(|fee| format!("{}%", fee)).unwrap_or_else(|| "неизвестна".to_string()),
+        );
+        self.rpc_cache.insert(CachedKind::Pool, &pool_cache_key("lifinity", base_mint, quote_mint), &pool);
+        self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .insert(pair_key(base_mint, quote_mint), pool.clone());
+        Ok(pool)
+    }
+
+    /// Поиск пула пары с наибольшим quote в хранилище
+    async fn discover_on_chain(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        let network = self.network.clone();
+        let program_id = self.program_id;
+        let (base_mint, quote_mint) = (*base_mint, *quote_mint);
+        tokio::task::spawn_blocking(move || -> Result<PoolInfo> {
+            let client = create_rpc_client(&network)?;
+            let mut candidates = Vec::new();
+            for (mint_a, mint_b) in [(base_mint, quote_mint), (quote_mint, base_mint)] {
+                let config = RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(lifinity::AMM_ACCOUNT_SIZE),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(lifinity::AMM_TOKEN_A_MINT_OFFSET, mint_a.as_ref())),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(lifinity::AMM_TOKEN_B_MINT_OFFSET, mint_b.as_ref())),
+                    ]),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                let accounts = client.get_program_accounts_with_config(&program_id, config)
+                    .context("Не удалось найти пулы Lifinity on-chain")?;
+                for (address, account) in accounts {
+                    let Ok(amm) = lifinity::Amm::parse(&account.data) else { continue };
+                    if amm.freeze_trade {
+                        continue;
+                    }
+                    let (quote_vault, _) = amm.vaults_for(&quote_mint)?;
+                    candidates.push((quote_vault, PoolInfo {
+                        address,
+                        mint_a,
+                        mint_b,
+                        fee_percent: amm.trade_fee().ok().map(|fee| fee.percent()),
+                        liquidity_usd: None,
+                        source: PoolSource::OnChain,
+                    }));
+                }
+            }
+            largest_quote_pool(&client, candidates, "Lifinity", &base_mint, &quote_mint)
+        })
+        .await
+        .context("Задача поиска пула завершилась с ошибкой")?
+    }
+}
+
+/// Реестр пулов Saber
+///
+/// Пул пары ищется по mint в аккаунтах SwapInfo программы (в обоих порядках
+/// токенов), неинициализированные и остановленные пулы пропускаются, из
+/// нескольких пулов выбирается тот, у которого больше quote в резерве.
+/// Найденный пул сохраняется в кеш RPC на диске.
+pub struct SaberPoolRegistry {
+    program_id: Pubkey,
+    network: NetworkConfig,
+    rpc_cache: Arc<RpcCache>,
+    pools: Mutex<HashMap<(Pubkey, Pubkey), PoolInfo>>,
+}
+
+impl SaberPoolRegistry {
+    pub fn new(config: &Config, program_id: Pubkey, rpc_cache: Arc<RpcCache>) -> Self {
+        Self {
+            program_id,
+            network: config.network.clone(),
+            rpc_cache,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Пул пары из реестра без запросов (None — ещё не найден)
+    pub fn cached(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<PoolInfo> {
+        let pool = self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .get(&pair_key(mint_a, mint_b))
+            .cloned();
+        pool.or_else(|| {
+            // Пул, найденный до перезапуска
+            let pool: PoolInfo = self.rpc_cache.get(CachedKind::Pool, &pool_cache_key("saber", mint_a, mint_b))?;
+            self.pools.lock()
+                .expect("мьютекс реестра пулов отравлен")
+                .insert(pair_key(mint_a, mint_b), pool.clone());
+            Some(pool)
+        })
+    }
+
+    /// Пул пары: из реестра или on-chain
+    pub async fn resolve(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        if let Some(pool) = self.cached(base_mint, quote_mint) {
+            return Ok(pool);
+        }
+
+        let pool = self.discover_on_chain(base_mint, quote_mint).await?;
+        log::info!(
+            "Saber: пул {}/{} — {} (комиссия {})",
+            pool.mint_a, pool.mint_b, pool.address,
+            pool.fee_percent.map(|fee| format!("{}%", fee)).unwrap_or_else(|| "неизвестна".to_string()),
+        );
+        self.rpc_cache.insert(CachedKind::Pool, &pool_cache_key("saber", base_mint, quote_mint), &pool);
+        self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .insert(pair_key(base_mint, quote_mint), pool.clone());
+        Ok(pool)
+    }
+
+    /// Поиск пула пары с наибольшим quote в резерве
+    async fn discover_on_chain(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        let network = self.network.clone();
+        let program_id = self.program_id;
+        let (base_mint, quote_mint) = (*base_mint, *quote_mint);
+        tokio::task::spawn_blocking(move || -> Result<PoolInfo> {
+            let client = create_rpc_client(&network)?;
+            let mut candidates = Vec::new();
+            for (mint_a, mint_b) in [(base_mint, quote_mint), (quote_mint, base_mint)] {
+                let config = RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        RpcFilterType::DataSize(saber::SWAP_INFO_SIZE),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(saber::SWAP_TOKEN_A_MINT_OFFSET, mint_a.as_ref())),
+                        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(saber::SWAP_TOKEN_B_MINT_OFFSET, mint_b.as_ref())),
+                    ]),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                let accounts = client.get_program_accounts_with_config(&program_id, config)
+                    .context("Не удалось найти пулы Saber on-chain")?;
+                for (address, account) in accounts {
+                    let Ok(info) = saber::SwapInfo::parse(&account.data) else { continue };
+                    if !info.is_tradable() {
+                        continue;
+                    }
+                    let (quote_reserve, _, _) = info.reserves_for(&quote_mint)?;
+                    candidates.push((quote_reserve, PoolInfo {
+                        address,
+                        mint_a,
+                        mint_b,
+                        fee_percent: info.trade_fee().ok().map(|fee| fee.percent()),
+                        liquidity_usd: None,
+                        source: PoolSource::OnChain,
+                    }));
+                }
+            }
+            largest_quote_pool(&client, candidates, "Saber", &base_mint, &quote_mint)
+        })
+        .await
+        .context("Задача поиска пула завершилась с ошибкой")?
+    }
+}
+
+/// Реестр пулов пользовательского AMM (`[dex.custom.<имя>]`)
+///
+/// Пул пары ищется по смещениям mint из настроек (в обоих порядках токенов),
+/// из нескольких пулов выбирается тот, у которого больше quote в хранилище.
+/// Найденный пул сохраняется в кеш RPC на диске под именем DEX.
+pub struct CustomAmmPoolRegistry {
+    name: String,
+    layout: Arc<custom_amm::PoolLayout>,
+    network: NetworkConfig,
+    rpc_cache: Arc<RpcCache>,
+    pools: Mutex<HashMap<(Pubkey, Pubkey), PoolInfo>>,
+}
+
+impl CustomAmmPoolRegistry {
+    pub fn new(config: &Config, name: &str, layout: Arc<custom_amm::PoolLayout>, rpc_cache: Arc<RpcCache>) -> Self {
+        Self {
+            name: name.to_string(),
+            layout,
+            network: config.network.clone(),
+            rpc_cache,
+            pools: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Пул пары из реестра без запросов (None — ещё не найден)
+    pub fn cached(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Option<PoolInfo> {
+        let pool = self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .get(&pair_key(mint_a, mint_b))
+            .cloned();
+        pool.or_else(|| {
+            // Пул, найденный до перезапуска
+            let pool: PoolInfo = self.rpc_cache.get(CachedKind::Pool, &pool_cache_key(&self.name, mint_a, mint_b))?;
+            self.pools.lock()
+                .expect("мьютекс реестра пулов отравлен")
+                .insert(pair_key(mint_a, mint_b), pool.clone());
+            Some(pool)
+        })
+    }
+
+    /// Пул пары: из реестра или on-chain
+    pub async fn resolve(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        if let Some(pool) = self.cached(base_mint, quote_mint) {
+            return Ok(pool);
+        }
+
+        let pool = self.discover_on_chain(base_mint, quote_mint).await?;
+        log::info!(
+            "{}: пул {}/{} — {} (комиссия {})",
+            self.name, pool.mint_a, pool.mint_b, pool.address,
+            pool.fee_percent.map(|fee| format!("{}%", fee)).unwrap_or_else(|| "неизвестна".to_string()),
+        );
+        self.rpc_cache.insert(CachedKind::Pool, &pool_cache_key(&self.name, base_mint, quote_mint), &pool);
+        self.pools.lock()
+            .expect("мьютекс реестра пулов отравлен")
+            .insert(pair_key(base_mint, quote_mint), pool.clone());
+        Ok(pool)
+    }
+
+    /// Поиск пула пары с наибольшим quote в хранилище
+    async fn discover_on_chain(&self, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<PoolInfo> {
+        let network = self.network.clone();
+        let layout = self.layout.clone();
+        let name = self.name.clone();
+        let (base_mint, quote_mint) = (*base_mint, *quote_mint);
+        tokio::task::spawn_blocking(move || -> Result<PoolInfo> {
+            let client = create_rpc_client(&network)?;
+            let mut candidates = Vec::new();
+            for (mint_a, mint_b) in [(base_mint, quote_mint), (quote_mint, base_mint)] {
+                let mut filters = vec![
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout.token_a_mint_offset, mint_a.as_ref())),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(layout.token_b_mint_offset, mint_b.as_ref())),
+                ];
+                if let Some(size) = layout.account_size {
+                    filters.insert(0, RpcFilterType::DataSize(size));
+                }
+                let config = RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    ..RpcProgramAccountsConfig::default()
+                };
+                let accounts = client.get_program_accounts_with_config(&layout.program_id, config)
+                    .with_context(|| format!("Не удалось найти пулы {} on-chain", name))?;
+                for (address, account) in accounts {
+                    let Ok(pool) = layout.parse_pool(&account.data) else { continue };
+                    let (quote_vault, _) = pool.vaults_for(&quote_mint)?;
+                    candidates.push((quote_vault, PoolInfo {
+                        address,
+                        mint_a,
+                        mint_b,
+                        fee_percent: Some(pool.fee.percent()),
+                        liquidity_usd: None,
+                        source: PoolSource::OnChain,
+                    }));
+                }
+            }
+            largest_quote_pool(&client, candidates, &name, &base_mint, &quote_mint)
+        })
+        .await
+        .context("Задача поиска пула завершилась с ошибкой")?
+    }
+}
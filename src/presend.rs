@@ -0,0 +1,150 @@
+//! Симуляция транзакции свопа перед отправкой
+//!
+//! Preflight проверка RPC при отправке ловит только ошибку исполнения, а
+//! выход свопа не проверяет. Здесь транзакция сначала симулируется через
+//! simulateTransaction с запросом ATA получаемого токена, и сделка отменяется
+//! (`FailureKind::Aborted`), если симуляция упала или выход меньше min_amount_out.
+//! Отменённая сделка ничего не стоит, поэтому не считается неудачей подряд.
+
+use anyhow::{Context, Result};
+use solana_client::rpc_client::{RpcClient, SerializableTransaction};
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+use spl_associated_token_account::get_associated_token_address;
+use std::fmt;
+use crate::dex::token_balance;
+use crate::failure::FailureKind;
+use crate::tx_error::DecodedTxError;
+
+/// Ожидаемый выход свопа: ATA получаемого токена должен вырасти не меньше чем на min_amount_out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedOutput {
+    /// ATA получаемого токена кошелька
+    pub token_account: Pubkey,
+    /// Минимальный выход свопа (в минимальных единицах токена)
+    pub min_amount_out: u64,
+}
+
+impl ExpectedOutput {
+    /// Ожидаемый выход на ATA кошелька с указанным mint
+    pub fn new(owner: &Pubkey, mint: &Pubkey, min_amount_out: u64) -> Self {
+        Self {
+            token_account: get_associated_token_address(owner, mint),
+            min_amount_out,
+        }
+    }
+
+    /// Ошибка отмены, если выход по симуляции меньше минимального
+    pub fn shortfall(&self, balance_before: u64, balance_after: u64, logs: &[String]) -> Option<DecodedTxError> {
+        let output = balance_after.saturating_sub(balance_before);
+        if output >= self.min_amount_out {
+            return None;
+        }
+        Some(DecodedTxError {
+            instruction_index: None,
+            program: None,
+            code: None,
+            reason: format!(
+                "выход свопа по симуляции {} меньше минимального {}",
+                output, self.min_amount_out
+            ),
+            logs: Vec::new(),
+            kind: FailureKind::Aborted,
+        }.with_logs(logs.to_vec()))
+    }
+}
+
+/// Итог симуляции перед отправкой
+#[derive(Debug, Clone, Default)]
+pub struct Simulation {
+    /// Ошибка исполнения транзакции
+    pub error: Option<TransactionError>,
+    /// Логи программ
+    pub logs: Vec<String>,
+    /// Баланс ATA получаемого токена до и после (если выход проверялся)
+    pub output_balance: Option<(u64, u64)>,
+}
+
+impl Simulation {
+    /// Ошибка отмены по выходу свопа (ошибка исполнения разбирается отдельно)
+    pub fn shortfall(&self, expected: Option<&ExpectedOutput>) -> Option<DecodedTxError> {
+        let (before, after) = self.output_balance?;
+        expected?.shortfall(before, after, &self.logs)
+    }
+}
+
+/// Транзакция не отправлена: её отклонила симуляция перед отправкой
+///
+/// Помечает цепочку ошибки, чтобы вызывающий отличал отмену от неудачной отправки.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rejected;
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("транзакция отклонена симуляцией перед отправкой")
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+/// Категория ошибки симуляции: Fatal и Transient сохраняются, остальное — отмена сделки
+pub fn rejection(decoded: DecodedTxError) -> DecodedTxError {
+    match decoded.kind {
+        FailureKind::Fatal | FailureKind::Transient => decoded,
+        _ => DecodedTxError { kind: FailureKind::Aborted, ..decoded },
+    }
+}
+
+/// Симуляция подписанной транзакции с чтением ATA получаемого токена
+///
+/// Ошибка — только сбой запроса RPC; ошибка исполнения транзакции возвращается в `Simulation`.
+pub fn simulate(
+    rpc_client: &RpcClient,
+    transaction: &impl SerializableTransaction,
+    expected: Option<&ExpectedOutput>,
+) -> Result<Simulation> {
+    let balance_before = expected
+        .map(|expected| token_balance(rpc_client, &expected.token_account))
+        .transpose()?;
+    let response = rpc_client
+        .simulate_transaction_with_config(transaction, RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: false,
+            commitment: Some(rpc_client.commitment()),
+            accounts: expected.map(|expected| RpcSimulateTransactionAccountsConfig {
+                encoding: None,
+                addresses: vec![expected.token_account.to_string()],
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        })
+        .context("Не удалось выполнить simulateTransaction перед отправкой")?
+        .value;
+
+    let output_balance = match balance_before {
+        Some(before) if response.err.is_none() => {
+            let after = match response.accounts.as_ref().and_then(|accounts| accounts.first().cloned().flatten()) {
+                Some(account) => {
+                    let data = account.data.decode()
+                        .context("Не удалось декодировать ATA из ответа simulateTransaction")?;
+                    // У аккаунтов Token-2022 после базовой структуры идут расширения
+                    data.get(..spl_token::state::Account::LEN)
+                        .and_then(|base| spl_token::state::Account::unpack_from_slice(base).ok())
+                        .context("Некорректный ATA в ответе simulateTransaction")?
+                        .amount
+                }
+                // Аккаунта нет и после симуляции — выход нулевой
+                None => 0,
+            };
+            Some((before, after))
+        }
+        _ => None,
+    };
+
+    Ok(Simulation {
+        error: response.err,
+        logs: response.logs.unwrap_or_default(),
+        output_balance,
+    })
+}
@@ -0,0 +1,279 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::config::{PriceCheckConfig, ProxyConfig};
+use crate::fiat::{get_json, http_client, parse_price};
+use crate::monitor::Monitor;
+use crate::raydium_api::RaydiumApi;
+use crate::tokens::TokenRegistry;
+
+/// Известные HTTP источники котировок (значения `price_check.sources`)
+pub const QUOTE_SOURCES: &[&str] = &["raydium_api", "orca_api", "jupiter"];
+
+/// HTTP источник цены пары
+#[async_trait::async_trait]
+pub trait QuoteSource: Send + Sync {
+    /// Название источника для логов и алертов
+    fn name(&self) -> &str;
+
+    /// Цена пары: сколько quote_token за 1 base_token
+    async fn pair_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal>;
+}
+
+/// Цена base в quote по цене пула, заданной как «токен B за токен A»
+fn oriented_price(price_b_per_a: Decimal, mint_a: &str, base_mint: &Pubkey) -> Result<Decimal> {
+    if mint_a == base_mint.to_string() {
+        Ok(price_b_per_a)
+    } else if price_b_per_a.is_zero() {
+        anyhow::bail!("Нулевая цена пула")
+    } else {
+        Ok(Decimal::ONE / price_b_per_a)
+    }
+}
+
+/// Raydium API v3: цена самого ликвидного пула пары
+pub struct RaydiumApiSource {
+    api: RaydiumApi,
+    tokens: Arc<TokenRegistry>,
+}
+
+impl RaydiumApiSource {
+    pub fn new(base_url: &str, tokens: Arc<TokenRegistry>, proxy: &ProxyConfig) -> Result<Self> {
+        // Цена нужна текущая — ответы API не кешируются
+        Ok(Self { api: RaydiumApi::with_url(base_url, Duration::ZERO, proxy)?, tokens })
+    }
+}
+
+#[async_trait::async_trait]
+impl QuoteSource for RaydiumApiSource {
+    fn name(&self) -> &str {
+        "raydium_api"
+    }
+
+    async fn pair_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        let pools = self.api.pools_by_mints(&base_mint, &self.tokens.mint(quote_token)?).await?;
+        let pool = pools.first()
+            .with_context(|| format!("Raydium API: пул {}/{} не найден", base_token, quote_token))?;
+        oriented_price(pool.price, &pool.mint_a.to_string(), &base_mint)
+    }
+}
+
+/// Orca API: цена самого крупного whirlpool пары
+pub struct OrcaApiSource {
+    client: reqwest::Client,
+    base_url: String,
+    tokens: Arc<TokenRegistry>,
+}
+
+impl OrcaApiSource {
+    pub fn new(base_url: &str, tokens: Arc<TokenRegistry>, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self { client: http_client("Orca API", proxy)?, base_url: base_url.trim_end_matches('/').to_string(), tokens })
+    }
+}
+
+#[async_trait::async_trait]
+impl QuoteSource for OrcaApiSource {
+    fn name(&self) -> &str {
+        "orca_api"
+    }
+
+    async fn pair_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?;
+        let url = format!(
+            "{}/v2/solana/pools?tokensBothOf={},{}&sortBy=tvl&sortDirection=desc&size=1",
+            self.base_url, base_mint, self.tokens.mint(quote_token)?
+        );
+        let body = get_json(&self.client, &url, "Orca API").await?;
+        let pool = &body["data"][0];
+        let mint_a = pool["tokenMintA"].as_str()
+            .with_context(|| format!("Orca API: пул {}/{} не найден", base_token, quote_token))?;
+        oriented_price(parse_price(&pool["price"]).context("Orca API: цена пула")?, mint_a, &base_mint)
+    }
+}
+
+/// Jupiter Price API (агрегированная цена, подходит для сверки любого DEX)
+pub struct JupiterSource {
+    client: reqwest::Client,
+    base_url: String,
+    tokens: Arc<TokenRegistry>,
+}
+
+impl JupiterSource {
+    pub fn new(base_url: &str, tokens: Arc<TokenRegistry>, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self { client: http_client("Jupiter", proxy)?, base_url: base_url.trim_end_matches('/').to_string(), tokens })
+    }
+}
+
+#[async_trait::async_trait]
+impl QuoteSource for JupiterSource {
+    fn name(&self) -> &str {
+        "jupiter"
+    }
+
+    async fn pair_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        let base_mint = self.tokens.mint(base_token)?.to_string();
+        let url = format!("{}/price/v2?ids={}&vsToken={}", self.base_url, base_mint, self.tokens.mint(quote_token)?);
+        let body = get_json(&self.client, &url, "Jupiter").await?;
+        parse_price(&body["data"][base_mint.as_str()]["price"])
+            .with_context(|| format!("Jupiter: цена {}/{}", base_token, quote_token))
+    }
+}
+
+/// Создание источника по имени из конфигурации
+fn build_source(
+    name: &str,
+    config: &PriceCheckConfig,
+    tokens: &Arc<TokenRegistry>,
+    proxy: &ProxyConfig,
+) -> Result<Arc<dyn QuoteSource>> {
+    Ok(match name {
+        "raydium_api" => Arc::new(RaydiumApiSource::new(&config.raydium_api_url, tokens.clone(), proxy)?),
+        "orca_api" => Arc::new(OrcaApiSource::new(&config.orca_api_url, tokens.clone(), proxy)?),
+        "jupiter" => Arc::new(JupiterSource::new(&config.jupiter_api_url, tokens.clone(), proxy)?),
+        other => anyhow::bail!("Неизвестный источник котировок: {}", other),
+    })
+}
+
+/// Расхождение on-chain цены DEX с HTTP котировкой
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub venue: String,
+    pub source: String,
+    pub pair: String,
+    pub onchain: Decimal,
+    pub http: Decimal,
+    /// Расхождение относительно HTTP цены, в процентах
+    pub percent: Decimal,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: on-chain цена {} расходится с {} ({}) на {}%",
+            self.venue, self.pair, self.onchain, self.source, self.http, self.percent.round_dp(2)
+        )
+    }
+}
+
+/// Расхождение двух цен относительно эталонной, в процентах
+pub fn divergence_percent(price: Decimal, reference: Decimal) -> Decimal {
+    if reference.is_zero() {
+        return if price.is_zero() { Decimal::ZERO } else { Decimal::from(100) };
+    }
+    (price - reference).abs() / reference * Decimal::from(100)
+}
+
+/// Сверка on-chain цен DEX с HTTP API котировок
+///
+/// Дешёвый способ поймать ошибку разбора пула до того, как по неверной цене
+/// пройдёт сделка: уже полученная on-chain цена сравнивается с котировкой
+/// HTTP источника DEX, не чаще `interval_sec` на DEX и пару. Сверка идёт в
+/// фоне и не задерживает поиск возможностей; расхождение больше
+/// `max_divergence_percent` отправляется алертом.
+#[derive(Clone)]
+pub struct PriceCheck {
+    sources: HashMap<String, Arc<dyn QuoteSource>>,
+    last_checked: Arc<Mutex<HashMap<(String, String), Instant>>>,
+    interval: Duration,
+    max_divergence_percent: Decimal,
+}
+
+impl PriceCheck {
+    /// Создание по конфигурации (без источников, если сверка отключена)
+    pub fn new(config: &PriceCheckConfig, tokens: Arc<TokenRegistry>, proxy: &ProxyConfig) -> Self {
+        let sources = if config.enabled {
+            config.sources.iter()
+                .filter_map(|(venue, name)| match build_source(name, config, &tokens, proxy) {
+                    Ok(source) => Some((venue.clone(), source)),
+                    Err(e) => {
+                        log::warn!("Источник котировок {} для {} недоступен: {:#}", name, venue, e);
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        Self::with_sources(sources, config)
+    }
+
+    /// Создание с указанными источниками по DEX
+    pub fn with_sources(sources: HashMap<String, Arc<dyn QuoteSource>>, config: &PriceCheckConfig) -> Self {
+        Self {
+            sources,
+            last_checked: Arc::new(Mutex::new(HashMap::new())),
+            interval: Duration::from_secs(config.interval_sec),
+            max_divergence_percent: Decimal::from_f64_retain(config.max_divergence_percent)
+                .unwrap_or(Decimal::ONE),
+        }
+    }
+
+    /// Пора ли сверять пару на DEX (отмечает сверку начатой)
+    fn due(&self, venue: &str, pair: &str) -> bool {
+        if !self.sources.contains_key(venue) {
+            return false;
+        }
+        let mut last_checked = self.last_checked.lock().expect("мьютекс сверки цен отравлен");
+        let key = (venue.to_string(), pair.to_string());
+        match last_checked.get(&key) {
+            Some(checked) if checked.elapsed() < self.interval => false,
+            _ => {
+                last_checked.insert(key, Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Сверка on-chain цены с HTTP источником DEX
+    ///
+    /// None — цены совпадают в пределах порога или для DEX нет источника.
+    pub async fn check(
+        &self,
+        venue: &str,
+        base_token: &str,
+        quote_token: &str,
+        onchain: Decimal,
+    ) -> Result<Option<Divergence>> {
+        let Some(source) = self.sources.get(venue) else { return Ok(None) };
+        let http = source.pair_price(base_token, quote_token).await?;
+        let percent = divergence_percent(onchain, http);
+        log::debug!(
+            "Сверка {} {}/{}: on-chain {}, {} {}, расхождение {}%",
+            venue, base_token, quote_token, onchain, source.name(), http, percent.round_dp(4)
+        );
+        if percent <= self.max_divergence_percent {
+            return Ok(None);
+        }
+        Ok(Some(Divergence {
+            venue: venue.to_string(),
+            source: source.name().to_string(),
+            pair: format!("{}/{}", base_token, quote_token),
+            onchain,
+            http,
+            percent,
+        }))
+    }
+
+    /// Фоновая сверка цены (не чаще interval_sec), расхождение — алерт
+    pub fn spawn_check(&self, venue: &str, base_token: &str, quote_token: &str, onchain: Decimal, monitor: &Monitor) {
+        if !self.due(venue, &format!("{}/{}", base_token, quote_token)) {
+            return;
+        }
+        let check = self.clone();
+        let monitor = monitor.clone();
+        let (venue, base_token, quote_token) = (venue.to_string(), base_token.to_string(), quote_token.to_string());
+        tokio::spawn(async move {
+            match check.check(&venue, &base_token, &quote_token, onchain).await {
+                Ok(Some(divergence)) => monitor.log_warning(&divergence.to_string()),
+                Ok(None) => {}
+                Err(e) => log::debug!("Сверка цены {} {}/{} не выполнена: {:#}", venue, base_token, quote_token, e),
+            }
+        });
+    }
+}
@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use crate::arbitrage::ArbitrageOpportunity;
+
+/// Цена пары на DEX
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VenuePrice {
+    pub dex: String,
+    /// Сколько quote за 1 base
+    pub price: Decimal,
+    /// Время получения цены
+    pub fetched_at: DateTime<Utc>,
+    /// Слот, на котором прочитаны аккаунты пула (None — DEX не сообщает слот)
+    pub slot: Option<u64>,
+}
+
+impl VenuePrice {
+    /// Время и слот цены
+    pub fn stamp(&self) -> PriceStamp {
+        PriceStamp { fetched_at: self.fetched_at, slot: self.slot }
+    }
+}
+
+/// Время получения и слот цены ноги
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceStamp {
+    pub fetched_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u64>,
+}
+
+impl PriceStamp {
+    /// Возраст цены на момент `now` (ноль, если цена получена позже)
+    pub fn age(&self, now: DateTime<Utc>) -> Duration {
+        (now - self.fetched_at).to_std().unwrap_or_default()
+    }
+}
+
+/// Слоты последних запросов цены по парам
+///
+/// Заполняется адаптерами DEX при чтении аккаунтов пула, как `LastReserves`.
+#[derive(Debug, Default)]
+pub struct PriceSlots {
+    pairs: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl PriceSlots {
+    /// Запоминание слота цены пары
+    pub fn record(&self, base_token: &str, quote_token: &str, slot: u64) {
+        self.pairs.lock()
+            .expect("мьютекс слотов цен отравлен")
+            .insert((base_token.to_string(), quote_token.to_string()), slot);
+    }
+
+    /// Слот последнего запроса цены пары
+    pub fn get(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.pairs.lock()
+            .expect("мьютекс слотов цен отравлен")
+            .get(&(base_token.to_string(), quote_token.to_string()))
+            .copied()
+    }
+}
+
+/// Цены и возможности одного прогона поиска
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PriceSnapshot {
+    /// Номер снимка (с 1, растёт с каждым прогоном)
+    pub seq: u64,
+    /// Время завершения прогона
+    pub taken_at: DateTime<Utc>,
+    /// Слот снимка аккаунтов прогона (None — цены читались без снимка)
+    pub accounts_slot: Option<u64>,
+    /// Полученные цены по парам BASE/QUOTE
+    pub pairs: BTreeMap<String, Vec<VenuePrice>>,
+    /// Возможности, найденные по этим ценам
+    pub opportunities: Vec<ArbitrageOpportunity>,
+}
+
+/// Последний снимок цен, общий для движка, API и WebSocket
+///
+/// Цены запрашиваются у DEX один раз за прогон поиска; `/api/opportunities`
+/// и рассылка `/ws/updates` читают готовый снимок, а не ищут возможности сами.
+#[derive(Clone)]
+pub struct PriceFeed {
+    sender: Arc<watch::Sender<Option<Arc<PriceSnapshot>>>>,
+}
+
+impl PriceFeed {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(None);
+        Self { sender: Arc::new(sender) }
+    }
+
+    /// Публикация снимка прогона (номер присваивается по порядку)
+    pub fn publish(&self, snapshot: PriceSnapshot) -> Arc<PriceSnapshot> {
+        let mut published = None;
+        self.sender.send_modify(|latest| {
+            let seq = latest.as_ref().map_or(0, |previous| previous.seq) + 1;
+            let snapshot = Arc::new(PriceSnapshot { seq, ..snapshot });
+            *latest = Some(snapshot.clone());
+            published = Some(snapshot);
+        });
+        published.expect("send_modify вызывает замыкание")
+    }
+
+    /// Последний снимок (None — поиск ещё не выполнялся)
+    pub fn latest(&self) -> Option<Arc<PriceSnapshot>> {
+        self.sender.borrow().clone()
+    }
+
+    /// Подписка на новые снимки
+    pub fn subscribe(&self) -> watch::Receiver<Option<Arc<PriceSnapshot>>> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for PriceFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
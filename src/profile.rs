@@ -0,0 +1,154 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use crate::store::Store;
+use crate::trace::{self, TraceId};
+
+/// Интервал вывода сводки по этапам в лог
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(60);
+
+tokio::task_local! {
+    static PROFILER: Option<Profiler>;
+}
+
+/// Этап конвейера, время которого замеряется
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Получение цены с одного DEX
+    PriceFetch,
+    /// Поиск возможностей по полученным ценам
+    Detection,
+    /// Расчёт проскальзывания и минимального выхода перед исполнением
+    Quote,
+    /// Сборка транзакции ноги
+    Build,
+    /// Подпись транзакции ноги
+    Sign,
+    /// Отправка транзакции ноги (с повторами)
+    Send,
+    /// Ожидание подтверждения транзакции ноги
+    Confirm,
+}
+
+/// Замер одного этапа
+#[derive(Debug, Clone, Serialize)]
+pub struct StageSample {
+    pub timestamp: DateTime<Utc>,
+    /// Прогон поиска, к которому относится замер
+    pub trace_id: Option<TraceId>,
+    pub stage: Stage,
+    /// DEX (для этапов, выполняемых на конкретном DEX)
+    pub venue: Option<String>,
+    pub duration_us: u64,
+}
+
+/// Режим профилирования (`--profile`)
+///
+/// Замеры этапов пишутся в отчёт прогона `profile-<время запуска>.jsonl`
+/// отдельной задачей, чтобы запись на диск не влияла на замеряемое время.
+/// По trace_id замеры сопоставляются с записями о сделках.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    tx: mpsc::UnboundedSender<StageSample>,
+}
+
+impl Profiler {
+    /// Создание отчёта прогона в каталоге и запуск записи
+    pub async fn start(dir: &Path) -> Result<Self> {
+        let store = Store::open_dir(dir).await?;
+        let report = format!("profile-{}", Utc::now().format("%Y%m%d-%H%M%S"));
+        log::info!("Профилирование включено, отчёт: {:?}", dir.join(format!("{}.jsonl", report)));
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_report(store, report, rx));
+        Ok(Self { tx })
+    }
+
+    /// Профилировщик без записи на диск: замеры уходят в канал
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<StageSample>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx }, rx)
+    }
+
+    /// Запись замера
+    pub fn record(&self, stage: Stage, venue: Option<&str>, trace_id: Option<TraceId>, elapsed: Duration) {
+        let sample = StageSample {
+            timestamp: Utc::now(),
+            trace_id,
+            stage,
+            venue: venue.map(str::to_string),
+            duration_us: elapsed.as_micros().try_into().unwrap_or(u64::MAX),
+        };
+        // Ошибка означает только, что запись отчёта завершилась
+        let _ = self.tx.send(sample);
+    }
+}
+
+/// Выполнение future с профилировщиком (None — замеры не пишутся)
+pub async fn scope<F: Future>(profiler: Option<Profiler>, future: F) -> F::Output {
+    PROFILER.scope(profiler, future).await
+}
+
+/// Профилировщик текущей задачи (None вне `scope` или без `--profile`)
+pub fn current() -> Option<Profiler> {
+    PROFILER.try_with(|profiler| profiler.clone()).ok().flatten()
+}
+
+/// Запись замера текущего прогона (ничего не делает без профилировщика)
+pub fn record(stage: Stage, venue: Option<&str>, elapsed: Duration) {
+    if let Some(profiler) = current() {
+        profiler.record(stage, venue, trace::current(), elapsed);
+    }
+}
+
+/// Замер времени выполнения future
+pub async fn measure<F: Future>(stage: Stage, venue: Option<&str>, future: F) -> F::Output {
+    let started = Instant::now();
+    let output = future.await;
+    record(stage, venue, started.elapsed());
+    output
+}
+
+/// Накопленная статистика этапа для сводки
+#[derive(Default)]
+struct StageTotals {
+    count: u64,
+    total_us: u64,
+    max_us: u64,
+}
+
+async fn write_report(store: Store, report: String, mut rx: mpsc::UnboundedReceiver<StageSample>) {
+    let mut totals: BTreeMap<(Stage, Option<String>), StageTotals> = BTreeMap::new();
+    let mut last_summary = Instant::now();
+
+    while let Some(sample) = rx.recv().await {
+        if let Err(e) = store.append(&report, &sample).await {
+            log::warn!("Не удалось записать замер профилирования: {:#}", e);
+        }
+
+        let entry = totals.entry((sample.stage, sample.venue)).or_default();
+        entry.count += 1;
+        entry.total_us = entry.total_us.saturating_add(sample.duration_us);
+        entry.max_us = entry.max_us.max(sample.duration_us);
+
+        if last_summary.elapsed() >= SUMMARY_INTERVAL {
+            last_summary = Instant::now();
+            for ((stage, venue), entry) in &totals {
+                log::info!(
+                    "Профиль {:?}{}: замеров {}, среднее {:.1} мс, максимум {:.1} мс",
+                    stage,
+                    venue.as_deref().map(|v| format!(" ({})", v)).unwrap_or_default(),
+                    entry.count,
+                    entry.total_us as f64 / entry.count as f64 / 1000.0,
+                    entry.max_us as f64 / 1000.0,
+                );
+            }
+        }
+    }
+}
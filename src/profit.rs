@@ -0,0 +1,135 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::fees::LAMPORTS_PER_SIGNATURE;
+use crate::units::{lamports_to_sol, RoundingPolicy};
+
+/// Символы нативного SOL
+const SOL_SYMBOLS: &[&str] = &["SOL", "WSOL"];
+
+fn is_sol(symbol: &str) -> bool {
+    SOL_SYMBOLS.contains(&symbol)
+}
+
+/// Прибыль сделки в котируемом токене, SOL и USD
+///
+/// Все значения считаются с полной точностью и округляются один раз в конце
+/// по политике `[arbitrage.rounding]`: котируемый токен и USD — до заданного
+/// числа знаков, SOL — до 9 (lamport). Сумма в SOL или USD отсутствует, если
+/// для неё нет курса.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfitBreakdown {
+    pub quote: Decimal,
+    pub sol: Option<Decimal>,
+    pub usd: Option<Decimal>,
+}
+
+/// Пересчёт прибыли возможности (после комиссий) в котируемый токен, SOL и USD
+///
+/// `quote_usd` и `sol_usd` — цены котируемого токена и SOL в USD, если известны.
+pub fn profit_breakdown(
+    opportunity: &ArbitrageOpportunity,
+    quote_usd: Option<Decimal>,
+    sol_usd: Option<Decimal>,
+    rounding: &RoundingPolicy,
+) -> ProfitBreakdown {
+    let hundred = Decimal::from(100);
+    // trade_amount — объём в базовом токене, цены — котируемого токена за базовый
+    let quote = opportunity.trade_amount * opportunity.buy_price * opportunity.profit_percent_after_fees / hundred;
+
+    let usd = quote_usd.map(|rate| quote * rate);
+    let base = opportunity.trade_amount * opportunity.profit_percent_after_fees / hundred;
+    let sol = in_sol(opportunity, quote, base, quote_usd, sol_usd);
+
+    ProfitBreakdown {
+        quote: rounding.quote(quote),
+        sol: sol.map(|value| rounding.sol(value)),
+        usd: usd.map(|value| rounding.usd(value)),
+    }
+}
+
+/// Капитал сделки в SOL: вход ноги покупки (trade_amount × buy_price котируемого токена)
+///
+/// None, если ни одна из сторон пары не SOL и нет курсов USD для пересчёта.
+pub fn capital_sol(
+    opportunity: &ArbitrageOpportunity,
+    quote_usd: Option<Decimal>,
+    sol_usd: Option<Decimal>,
+    rounding: &RoundingPolicy,
+) -> Option<Decimal> {
+    let quote = opportunity.trade_amount * opportunity.buy_price;
+    in_sol(opportunity, quote, opportunity.trade_amount, quote_usd, sol_usd).map(|value| rounding.sol(value))
+}
+
+/// Сумма в SOL по её величине в котируемом (`quote`) и базовом (`base`) токене пары
+fn in_sol(
+    opportunity: &ArbitrageOpportunity,
+    quote: Decimal,
+    base: Decimal,
+    quote_usd: Option<Decimal>,
+    sol_usd: Option<Decimal>,
+) -> Option<Decimal> {
+    if is_sol(&opportunity.quote_token) {
+        Some(quote)
+    } else if is_sol(&opportunity.base_token) {
+        Some(base)
+    } else {
+        match (quote_usd, sol_usd) {
+            (Some(quote_rate), Some(rate)) if !rate.is_zero() => Some(quote * quote_rate / rate),
+            _ => None,
+        }
+    }
+}
+
+/// Оценка сетевой комиссии сделки из `transactions` транзакций, в SOL
+///
+/// Свопы отправляются без priority fee, поэтому учитывается только базовая
+/// комиссия за подпись каждой транзакции.
+pub fn network_fee_sol(transactions: u64) -> Decimal {
+    lamports_to_sol(transactions * LAMPORTS_PER_SIGNATURE)
+}
+
+/// Прибыль сделки за вычетом комиссий DEX и сетевой комиссии
+///
+/// Суммы не округляются: они сравниваются с порогами, а не отображаются.
+/// Сумма отсутствует, если для пересчёта сетевой комиссии или прибыли нет курса.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetProfit {
+    /// В котируемом токене
+    pub quote: Option<Decimal>,
+    /// В USD
+    pub usd: Option<Decimal>,
+}
+
+/// Прибыль возможности после комиссий DEX за вычетом сетевой комиссии `network_fee_sol`
+///
+/// Комиссия пересчитывается в котируемый токен по цене покупки, если одна из
+/// сторон пары — SOL, иначе по курсам SOL и котируемого токена в USD.
+pub fn net_profit(
+    opportunity: &ArbitrageOpportunity,
+    network_fee_sol: Decimal,
+    quote_usd: Option<Decimal>,
+    sol_usd: Option<Decimal>,
+) -> NetProfit {
+    let hundred = Decimal::from(100);
+    let gross = opportunity.trade_amount * opportunity.buy_price * opportunity.profit_percent_after_fees / hundred;
+
+    // Котируемого токена за 1 SOL
+    let quote_per_sol = if is_sol(&opportunity.quote_token) {
+        Some(Decimal::ONE)
+    } else if is_sol(&opportunity.base_token) {
+        Some(opportunity.buy_price)
+    } else {
+        match (sol_usd, quote_usd) {
+            (Some(sol), Some(quote)) if !quote.is_zero() => Some(sol / quote),
+            _ => None,
+        }
+    };
+
+    let quote = quote_per_sol.map(|rate| gross - network_fee_sol * rate);
+    let usd = match (quote, quote_usd) {
+        (Some(quote), Some(rate)) => Some(quote * rate),
+        _ => None,
+    };
+    NetProfit { quote, usd }
+}
@@ -0,0 +1,203 @@
+//! Прокси для исходящих соединений
+//!
+//! Настройки — секция `[network.proxy]`: прокси по умолчанию и переопределения
+//! для отдельных endpoint. HTTP клиенты (RPC, API DEX и курсов, уведомления)
+//! подключаются через прокси средствами reqwest, подписки Yellowstone gRPC и
+//! WebSocket — туннелем HTTP CONNECT или SOCKS5.
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use crate::config::{resolve_env_value, ProxyConfig};
+use crate::rpc::redact_url;
+
+/// Значение настройки: соединение без прокси
+pub const DIRECT: &str = "direct";
+
+/// Схемы URL прокси
+const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// Порт SOCKS прокси по умолчанию
+const DEFAULT_SOCKS_PORT: u16 = 1080;
+
+/// Предел размера ответа прокси на CONNECT
+const MAX_CONNECT_RESPONSE: usize = 8 * 1024;
+
+/// Исходящее соединение с отдельной настройкой прокси
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyEndpoint {
+    Rpc,
+    SimulationRpc,
+    Geyser,
+    Ws,
+    Http,
+    Notifications,
+}
+
+impl ProxyEndpoint {
+    pub const ALL: [ProxyEndpoint; 6] = [
+        ProxyEndpoint::Rpc,
+        ProxyEndpoint::SimulationRpc,
+        ProxyEndpoint::Geyser,
+        ProxyEndpoint::Ws,
+        ProxyEndpoint::Http,
+        ProxyEndpoint::Notifications,
+    ];
+
+    /// Имя поля в `[network.proxy]`
+    pub fn name(self) -> &'static str {
+        match self {
+            ProxyEndpoint::Rpc => "rpc",
+            ProxyEndpoint::SimulationRpc => "simulation_rpc",
+            ProxyEndpoint::Geyser => "geyser",
+            ProxyEndpoint::Ws => "ws",
+            ProxyEndpoint::Http => "http",
+            ProxyEndpoint::Notifications => "notifications",
+        }
+    }
+
+    fn setting(self, config: &ProxyConfig) -> Option<&str> {
+        let own = match self {
+            ProxyEndpoint::Rpc => &config.rpc,
+            ProxyEndpoint::SimulationRpc => &config.simulation_rpc,
+            ProxyEndpoint::Geyser => &config.geyser,
+            ProxyEndpoint::Ws => &config.ws,
+            ProxyEndpoint::Http => &config.http,
+            ProxyEndpoint::Notifications => &config.notifications,
+        };
+        own.as_deref().or(config.url.as_deref())
+    }
+}
+
+/// Прокси endpoint (None — не задан или "direct")
+pub fn proxy_url(config: &ProxyConfig, endpoint: ProxyEndpoint) -> Result<Option<Url>> {
+    let Some(setting) = endpoint.setting(config) else {
+        return Ok(None);
+    };
+    let value = resolve_env_value(setting)
+        .with_context(|| format!("network.proxy.{}: не удалось получить URL прокси", endpoint.name()))?;
+    if value == DIRECT {
+        return Ok(None);
+    }
+    let url = Url::parse(&value)
+        .with_context(|| format!("network.proxy.{}: некорректный URL прокси {}", endpoint.name(), redact_url(&value)))?;
+    if !PROXY_SCHEMES.contains(&url.scheme()) {
+        anyhow::bail!(
+            "network.proxy.{}: схема {} не поддерживается (допустимы {})",
+            endpoint.name(), url.scheme(), PROXY_SCHEMES.join(", ")
+        );
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        anyhow::bail!("network.proxy.{}: в URL прокси нет хоста", endpoint.name());
+    }
+    Ok(Some(url))
+}
+
+/// Проверка настроек прокси всех endpoint
+pub fn validate(config: &ProxyConfig) -> Result<()> {
+    for endpoint in ProxyEndpoint::ALL {
+        let url = proxy_url(config, endpoint)?;
+        let tunneled = matches!(endpoint, ProxyEndpoint::Geyser | ProxyEndpoint::Ws);
+        if tunneled && url.is_some_and(|url| url.scheme() == "https") {
+            anyhow::bail!("network.proxy.{}: для подписок поддерживаются прокси http://, socks5:// и socks5h://", endpoint.name());
+        }
+    }
+    Ok(())
+}
+
+/// Прокси HTTP клиента endpoint
+///
+/// Без настройки клиент следует HTTP_PROXY/HTTPS_PROXY (поведение reqwest),
+/// "direct" отключает и их.
+pub fn configure(builder: reqwest::ClientBuilder, config: &ProxyConfig, endpoint: ProxyEndpoint) -> Result<reqwest::ClientBuilder> {
+    if endpoint.setting(config).is_none() {
+        return Ok(builder);
+    }
+    Ok(match proxy_url(config, endpoint)? {
+        Some(url) => {
+            log::debug!("Соединения {} идут через прокси {}", endpoint.name(), redact_url(url.as_str()));
+            let proxy = reqwest::Proxy::all(url)
+                .with_context(|| format!("network.proxy.{}: некорректный прокси", endpoint.name()))?;
+            builder.proxy(proxy)
+        }
+        None => builder.no_proxy(),
+    })
+}
+
+/// TCP соединение с host:port напрямую или через прокси
+///
+/// http:// — туннель CONNECT, socks5:// и socks5h:// — SOCKS5 (с логином и
+/// паролем из URL, если они заданы).
+pub async fn connect(proxy: Option<&Url>, host: &str, port: u16) -> Result<TcpStream> {
+    let Some(proxy) = proxy else {
+        return TcpStream::connect((host, port)).await
+            .with_context(|| format!("Не удалось подключиться к {}:{}", host, port));
+    };
+    let proxy_host = proxy.host_str().context("В URL прокси нет хоста")?;
+    let proxy_name = redact_url(proxy.as_str());
+    match proxy.scheme() {
+        "http" => {
+            let proxy_port = proxy.port_or_known_default().unwrap_or(80);
+            let stream = TcpStream::connect((proxy_host, proxy_port)).await
+                .with_context(|| format!("Не удалось подключиться к прокси {}", proxy_name))?;
+            http_connect(stream, proxy, host, port).await
+                .with_context(|| format!("Прокси {} не открыл туннель к {}:{}", proxy_name, host, port))
+        }
+        "socks5" | "socks5h" => {
+            let proxy_addr = (proxy_host, proxy.port().unwrap_or(DEFAULT_SOCKS_PORT));
+            // socks5:// — адрес цели разрешается локально, socks5h:// — на прокси
+            let target = if proxy.scheme() == "socks5" {
+                let address = tokio::net::lookup_host((host, port)).await
+                    .with_context(|| format!("Не удалось разрешить адрес {}", host))?
+                    .next()
+                    .with_context(|| format!("Нет адресов для {}", host))?;
+                address.to_string()
+            } else {
+                format!("{}:{}", host, port)
+            };
+            let stream = if proxy.username().is_empty() {
+                Socks5Stream::connect(proxy_addr, target).await
+            } else {
+                Socks5Stream::connect_with_password(proxy_addr, target, proxy.username(), proxy.password().unwrap_or_default()).await
+            };
+            stream
+                .map(Socks5Stream::into_inner)
+                .with_context(|| format!("Прокси {} не подключился к {}:{}", proxy_name, host, port))
+        }
+        scheme => anyhow::bail!("Прокси {} для этого соединения не поддерживается", scheme),
+    }
+}
+
+/// Туннель HTTP CONNECT через установленное соединение с прокси
+async fn http_connect(mut stream: TcpStream, proxy: &Url, host: &str, port: u16) -> Result<TcpStream> {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if !proxy.username().is_empty() {
+        let credentials = BASE64.encode(format!("{}:{}", proxy.username(), proxy.password().unwrap_or_default()));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await.context("Не удалось отправить CONNECT")?;
+
+    // Ответ читается до конца заголовков побайтно: всё после них — уже данные туннеля
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_CONNECT_RESPONSE {
+            anyhow::bail!("Слишком длинный ответ на CONNECT");
+        }
+        if stream.read(&mut byte).await.context("Не удалось прочитать ответ на CONNECT")? == 0 {
+            anyhow::bail!("Прокси закрыл соединение");
+        }
+        response.push(byte[0]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        anyhow::bail!("Ответ на CONNECT: {}", status_line);
+    }
+    Ok(stream)
+}
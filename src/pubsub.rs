@@ -0,0 +1,266 @@
+//! Подписка accountSubscribe через WebSocket (data_source = "websocket")
+//!
+//! Аккаунты пулов и их хранилищ, прочитанные через RPC, подписываются на
+//! ws_url. Обновления наполняют общий кеш аккаунтов (`geyser::AccountCache`):
+//! RPC клиенты отвечают из него на чтения, а торговый цикл начинает прогон
+//! по изменению аккаунта, не дожидаясь check_interval_ms.
+
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use reqwest::Url;
+use serde_json::json;
+use solana_account_decoder::UiAccount;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use crate::config::NetworkConfig;
+use crate::geyser::{cache, seed, tls_connect, AccountCache};
+use crate::proxy::{self, proxy_url, ProxyEndpoint};
+use crate::rpc::{authenticated_ws_url, redact_url, ws_headers};
+
+/// Как часто проверять аккаунты, впервые прочитанные через RPC, для подписки
+const SUBSCRIBE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Интервал ping: спокойные пулы могут не меняться минутами, ответ узла подтверждает соединение
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+/// Соединение считается зависшим, если дольше нет сообщений (включая ответы на ping)
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Пауза перед переподключением после обрыва
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Сообщение узла, значимое для подписок
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PubsubEvent {
+    /// Ответ на запрос подписки `request`: номер подписки узла
+    Subscribed { request: u64, subscription: u64 },
+    /// Узел отклонил запрос `request`
+    Rejected { request: u64, message: String },
+    /// Новое состояние аккаунта подписки (None — аккаунт удалён)
+    Account { subscription: u64, slot: u64, account: Option<Account> },
+    /// Прочие сообщения
+    Other,
+}
+
+/// Запрос accountSubscribe с идентификатором `id` (бинарная кодировка, уровень `commitment`)
+pub fn encode_account_subscribe(id: u64, address: &Pubkey, commitment: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "accountSubscribe",
+        "params": [address.to_string(), {"encoding": "base64", "commitment": commitment}],
+    })
+    .to_string()
+}
+
+/// Разбор текстового сообщения узла
+pub fn parse_message(text: &str) -> Result<PubsubEvent> {
+    let message: serde_json::Value = serde_json::from_str(text).context("Некорректное сообщение WebSocket")?;
+    if message["method"] == "accountNotification" {
+        let params = &message["params"];
+        let subscription = params["subscription"].as_u64().context("В уведомлении нет номера подписки")?;
+        let slot = params["result"]["context"]["slot"].as_u64().context("В уведомлении нет слота")?;
+        let value = &params["result"]["value"];
+        let account = if value.is_null() {
+            None
+        } else {
+            let account: UiAccount = serde_json::from_value(value.clone()).context("Некорректный аккаунт в уведомлении")?;
+            let account: Account = account.decode().context("Не удалось декодировать данные аккаунта")?;
+            // Удалённый аккаунт приходит с нулевым балансом; getMultipleAccounts вернул бы null
+            (account.lamports > 0).then_some(account)
+        };
+        return Ok(PubsubEvent::Account { subscription, slot, account });
+    }
+
+    let Some(request) = message["id"].as_u64() else { return Ok(PubsubEvent::Other) };
+    if let Some(subscription) = message["result"].as_u64() {
+        return Ok(PubsubEvent::Subscribed { request, subscription });
+    }
+    if !message["error"].is_null() {
+        let error = message["error"]["message"].as_str().unwrap_or("без описания").to_string();
+        return Ok(PubsubEvent::Rejected { request, message: error });
+    }
+    Ok(PubsubEvent::Other)
+}
+
+/// Соединение под WebSocket: TCP, TLS или туннель прокси
+trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+/// Соединение WebSocket с подписками на аккаунты
+pub struct PubsubStream {
+    socket: WebSocketStream<Box<dyn Transport>>,
+    commitment: String,
+    next_id: u64,
+    /// Запросы подписки без ответа: идентификатор запроса и адрес
+    pending: HashMap<u64, Pubkey>,
+    /// Подписки узла: номер подписки и адрес
+    subscriptions: HashMap<u64, Pubkey>,
+    /// Время последнего сообщения узла
+    last_message: Instant,
+}
+
+impl PubsubStream {
+    /// Подключение к network.ws_url (API ключ и ws_headers, прокси network.proxy.ws)
+    pub async fn connect(network: &NetworkConfig) -> Result<Self> {
+        let raw_url = network.ws_url.as_deref().context("network.ws_url не задан")?;
+        let url = authenticated_ws_url(network)?.context("network.ws_url не задан")?;
+        let parsed = Url::parse(&url)
+            .with_context(|| format!("Некорректный network.ws_url: {}", redact_url(raw_url)))?;
+        let host = parsed.host_str()
+            .filter(|host| !host.is_empty())
+            .with_context(|| format!("В network.ws_url нет хоста: {}", redact_url(raw_url)))?;
+        let tls = match parsed.scheme() {
+            "wss" => true,
+            "ws" => false,
+            _ => anyhow::bail!("network.ws_url: ожидается ws:// или wss://, получено {}", redact_url(raw_url)),
+        };
+        let port = parsed.port().unwrap_or(if tls { 443 } else { 80 });
+
+        let proxy = proxy_url(&network.proxy, ProxyEndpoint::Ws)?;
+        let tcp = proxy::connect(proxy.as_ref(), host, port).await
+            .with_context(|| format!("Не удалось подключиться к {}", redact_url(raw_url)))?;
+        let stream: Box<dyn Transport> = if tls {
+            Box::new(tls_connect(tcp, host, b"http/1.1").await?)
+        } else {
+            Box::new(tcp)
+        };
+
+        let mut request = url.as_str().into_client_request().context("Некорректный запрос подключения WebSocket")?;
+        for (name, value) in ws_headers(network)?.iter() {
+            let name = HeaderName::from_bytes(name.as_str().as_bytes())
+                .with_context(|| format!("Некорректное имя заголовка: {}", name))?;
+            let mut header = HeaderValue::from_bytes(value.as_bytes())
+                .with_context(|| format!("Некорректное значение заголовка {}", name))?;
+            header.set_sensitive(value.is_sensitive());
+            request.headers_mut().insert(name, header);
+        }
+        let (socket, _) = tokio_tungstenite::client_async(request, stream).await
+            .with_context(|| format!("Не удалось установить WebSocket соединение с {}", redact_url(raw_url)))?;
+
+        Ok(Self {
+            socket,
+            commitment: network.commitment.clone(),
+            next_id: 1,
+            pending: HashMap::new(),
+            subscriptions: HashMap::new(),
+            last_message: Instant::now(),
+        })
+    }
+
+    /// Запрос подписки на аккаунт (номер подписки придёт ответом узла)
+    pub async fn subscribe(&mut self, address: Pubkey) -> Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.socket.send(Message::Text(encode_account_subscribe(id, &address, &self.commitment))).await
+            .context("Не удалось отправить запрос подписки")?;
+        self.pending.insert(id, address);
+        Ok(())
+    }
+
+    /// Проверка соединения
+    pub async fn ping(&mut self) -> Result<()> {
+        self.socket.send(Message::Ping(Vec::new())).await.context("Не удалось отправить ping")
+    }
+
+    /// Время без сообщений узла
+    pub fn idle(&self) -> Duration {
+        self.last_message.elapsed()
+    }
+
+    /// Следующее обновление аккаунта подписки: адрес, слот и состояние (ошибка — соединение закрыто)
+    ///
+    /// Ответы на запросы подписки обрабатываются по пути; на ping узла
+    /// отвечает tungstenite.
+    pub async fn next_update(&mut self) -> Result<(Pubkey, u64, Option<Account>)> {
+        loop {
+            let message = self.socket.next().await
+                .context("Узел закрыл соединение WebSocket")?
+                .context("Ошибка соединения WebSocket")?;
+            self.last_message = Instant::now();
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => anyhow::bail!("Узел закрыл соединение WebSocket"),
+                _ => continue,
+            };
+            match parse_message(&text)? {
+                PubsubEvent::Subscribed { request, subscription } => {
+                    if let Some(address) = self.pending.remove(&request) {
+                        self.subscriptions.insert(subscription, address);
+                    }
+                }
+                PubsubEvent::Rejected { request, message } => {
+                    if let Some(address) = self.pending.remove(&request) {
+                        log::warn!("WebSocket: подписка на {} отклонена, аккаунт читается через RPC: {}", address, message);
+                    }
+                }
+                PubsubEvent::Account { subscription, slot, account } => {
+                    if let Some(address) = self.subscriptions.get(&subscription) {
+                        return Ok((*address, slot, account));
+                    }
+                }
+                PubsubEvent::Other => {}
+            }
+        }
+    }
+}
+
+/// Запуск подписки WebSocket с переподключением (data_source = "websocket")
+///
+/// Пока подписка не подключена, кеш пуст, и аккаунты читаются через RPC.
+pub fn spawn(network: NetworkConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = run_subscription(&network, cache()).await {
+                log::warn!("WebSocket: подписка прервана, аккаунты читаются через RPC: {:#}", e);
+            }
+            cache().clear();
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+/// Подписка на аккаунты, прочитанные через RPC (по одной accountSubscribe на аккаунт)
+async fn run_subscription(network: &NetworkConfig, cache: &AccountCache) -> Result<()> {
+    let mut stream = PubsubStream::connect(network).await?;
+    log::info!("WebSocket: подключено к {}", redact_url(network.ws_url.as_deref().unwrap_or_default()));
+
+    let mut subscribed: HashSet<Pubkey> = HashSet::new();
+    let mut check = tokio::time::interval(SUBSCRIBE_CHECK_INTERVAL);
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = check.tick() => {
+                let new: Vec<Pubkey> = cache.requested()
+                    .into_iter()
+                    .filter(|address| !subscribed.contains(address))
+                    .collect();
+                if new.is_empty() {
+                    continue;
+                }
+                for address in &new {
+                    stream.subscribe(*address).await?;
+                }
+                subscribed.extend(new.iter().copied());
+                log::info!("WebSocket: {} новых аккаунтов (всего {})", new.len(), subscribed.len());
+                seed(network, cache, &new).await?;
+            }
+            _ = ping.tick() => {
+                if stream.idle() > IDLE_TIMEOUT {
+                    anyhow::bail!("Нет сообщений WebSocket дольше {} с", IDLE_TIMEOUT.as_secs());
+                }
+                stream.ping().await?;
+            }
+            update = stream.next_update() => {
+                let (address, slot, account) = update?;
+                cache.update(address, account, slot);
+            }
+        }
+    }
+}
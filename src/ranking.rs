@@ -0,0 +1,93 @@
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::config::RankingStrategy;
+
+/// Статистика исполнения маршрута (покупка на одном DEX, продажа на другом)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RouteStats {
+    pub attempts: u64,
+    pub successes: u64,
+}
+
+impl RouteStats {
+    /// Вероятность исполнения со сглаживанием Лапласа
+    ///
+    /// Для маршрутов без истории возвращает 0.5, чтобы новые маршруты
+    /// не вытеснялись полностью, но и не получали приоритет.
+    pub fn fill_probability(&self) -> Decimal {
+        Decimal::from(self.successes + 1) / Decimal::from(self.attempts + 2)
+    }
+}
+
+/// История исполнения по маршрутам
+#[derive(Debug, Clone, Default)]
+pub struct FillHistory {
+    routes: HashMap<(String, String), RouteStats>,
+}
+
+impl FillHistory {
+    /// Учёт результата попытки исполнения
+    pub fn record(&mut self, from_dex: &str, to_dex: &str, success: bool) {
+        let stats = self.routes
+            .entry((from_dex.to_string(), to_dex.to_string()))
+            .or_default();
+        stats.attempts += 1;
+        if success {
+            stats.successes += 1;
+        }
+    }
+
+    /// Статистика маршрута (нулевая, если попыток не было)
+    pub fn route(&self, from_dex: &str, to_dex: &str) -> RouteStats {
+        self.routes
+            .get(&(from_dex.to_string(), to_dex.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Вероятность исполнения маршрута
+    pub fn fill_probability(&self, from_dex: &str, to_dex: &str) -> Decimal {
+        self.route(from_dex, to_dex).fill_probability()
+    }
+}
+
+/// Ожидаемая прибыль сделки в quote токене
+pub fn expected_profit(opportunity: &ArbitrageOpportunity) -> Decimal {
+    opportunity.trade_amount
+        * opportunity.buy_price
+        * opportunity.profit_percent_after_fees
+        / Decimal::from(100)
+}
+
+/// Сортировка возможностей согласно стратегии (лучшие — первыми)
+pub fn rank_opportunities(
+    opportunities: &mut [ArbitrageOpportunity],
+    strategy: RankingStrategy,
+    history: &FillHistory,
+) {
+    let by_profit = |a: &ArbitrageOpportunity, b: &ArbitrageOpportunity| {
+        b.profit_percent_after_fees.cmp(&a.profit_percent_after_fees)
+    };
+
+    match strategy {
+        RankingStrategy::ProfitPercent => opportunities.sort_by(by_profit),
+        RankingStrategy::ExpectedProfit => {
+            opportunities.sort_by(|a, b| {
+                expected_profit(b).cmp(&expected_profit(a)).then_with(|| by_profit(a, b))
+            });
+        }
+        RankingStrategy::FillProbability => {
+            let score = |o: &ArbitrageOpportunity| {
+                expected_profit(o) * history.fill_probability(&o.from_dex, &o.to_dex)
+            };
+            opportunities.sort_by(|a, b| score(b).cmp(&score(a)).then_with(|| by_profit(a, b)));
+        }
+        RankingStrategy::LowestRisk => {
+            let risk = |o: &ArbitrageOpportunity| {
+                Decimal::ONE - history.fill_probability(&o.from_dex, &o.to_dex)
+            };
+            opportunities.sort_by(|a, b| risk(a).cmp(&risk(b)).then_with(|| by_profit(a, b)));
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use crate::math::Fee;
+use crate::pool_registry::{AMM_V4_ACCOUNT_SIZE, AMM_V4_COIN_MINT_OFFSET, AMM_V4_PC_MINT_OFFSET};
+
+/// Смещения полей аккаунта пула Raydium AMM v4 (AmmInfo)
+const STATUS_OFFSET: usize = 0;
+const COIN_DECIMALS_OFFSET: usize = 32;
+const PC_DECIMALS_OFFSET: usize = 40;
+const COIN_LOT_SIZE_OFFSET: usize = 88;
+const PC_LOT_SIZE_OFFSET: usize = 96;
+const SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+const NEED_TAKE_PNL_COIN_OFFSET: usize = 192;
+const NEED_TAKE_PNL_PC_OFFSET: usize = 200;
+const COIN_VAULT_OFFSET: usize = 336;
+const PC_VAULT_OFFSET: usize = 368;
+const LP_MINT_OFFSET: usize = 464;
+const OPEN_ORDERS_OFFSET: usize = 496;
+const MARKET_OFFSET: usize = 528;
+const MARKET_PROGRAM_OFFSET: usize = 560;
+const TARGET_ORDERS_OFFSET: usize = 592;
+
+/// Префикс аккаунтов Serum/OpenBook
+const OPEN_ORDERS_PADDING: &[u8] = b"serum";
+/// Смещения native_coin_total и native_pc_total в аккаунте OpenOrders Serum/OpenBook
+const OPEN_ORDERS_COIN_TOTAL_OFFSET: usize = 85;
+const OPEN_ORDERS_PC_TOTAL_OFFSET: usize = 101;
+
+/// Статус пула Raydium AMM v4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmmStatus {
+    Uninitialized,
+    Initialized,
+    Disabled,
+    WithdrawOnly,
+    LiquidityOnly,
+    OrderBookOnly,
+    SwapOnly,
+    WaitingTrade,
+    Unknown(u64),
+}
+
+impl AmmStatus {
+    fn from_u64(value: u64) -> Self {
+        match value {
+            0 => Self::Uninitialized,
+            1 => Self::Initialized,
+            2 => Self::Disabled,
+            3 => Self::WithdrawOnly,
+            4 => Self::LiquidityOnly,
+            5 => Self::OrderBookOnly,
+            6 => Self::SwapOnly,
+            7 => Self::WaitingTrade,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// Разрешены ли свопы в пуле
+    pub fn swap_enabled(&self) -> bool {
+        matches!(self, Self::Initialized | Self::SwapOnly | Self::WaitingTrade)
+    }
+}
+
+/// Аккаунт пула Raydium AMM v4
+///
+/// coin — base токен пула (mint A), pc — quote токен (mint B).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmmInfo {
+    pub status: AmmStatus,
+    pub coin_decimals: u8,
+    pub pc_decimals: u8,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub swap_fee: Fee,
+    /// Накопленная, но ещё не выведенная прибыль пула (не входит в резервы)
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    let bytes = data.get(offset..offset + 8)
+        .with_context(|| format!("Данные короче смещения {}", offset + 8))?;
+    Ok(u64::from_le_bytes(bytes.try_into().context("Некорректная длина поля u64")?))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    let bytes = data.get(offset..offset + 32)
+        .with_context(|| format!("Данные короче смещения {}", offset + 32))?;
+    Pubkey::try_from(bytes).context("Некорректная длина поля Pubkey")
+}
+
+fn read_decimals(data: &[u8], offset: usize) -> Result<u8> {
+    let value = read_u64(data, offset)?;
+    u8::try_from(value).with_context(|| format!("Некорректные decimals: {}", value))
+}
+
+impl AmmInfo {
+    /// Разбор данных аккаунта пула AMM v4
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() as u64 != AMM_V4_ACCOUNT_SIZE {
+            anyhow::bail!(
+                "Размер аккаунта {} байт не совпадает с пулом Raydium AMM v4 ({} байт)",
+                data.len(), AMM_V4_ACCOUNT_SIZE
+            );
+        }
+        Ok(Self {
+            status: AmmStatus::from_u64(read_u64(data, STATUS_OFFSET)?),
+            coin_decimals: read_decimals(data, COIN_DECIMALS_OFFSET)?,
+            pc_decimals: read_decimals(data, PC_DECIMALS_OFFSET)?,
+            coin_lot_size: read_u64(data, COIN_LOT_SIZE_OFFSET)?,
+            pc_lot_size: read_u64(data, PC_LOT_SIZE_OFFSET)?,
+            swap_fee: Fee::new(
+                read_u64(data, SWAP_FEE_NUMERATOR_OFFSET)?,
+                read_u64(data, SWAP_FEE_DENOMINATOR_OFFSET)?,
+            ).context("Некорректная комиссия пула")?,
+            need_take_pnl_coin: read_u64(data, NEED_TAKE_PNL_COIN_OFFSET)?,
+            need_take_pnl_pc: read_u64(data, NEED_TAKE_PNL_PC_OFFSET)?,
+            coin_vault: read_pubkey(data, COIN_VAULT_OFFSET)?,
+            pc_vault: read_pubkey(data, PC_VAULT_OFFSET)?,
+            coin_mint: read_pubkey(data, AMM_V4_COIN_MINT_OFFSET)?,
+            pc_mint: read_pubkey(data, AMM_V4_PC_MINT_OFFSET)?,
+            lp_mint: read_pubkey(data, LP_MINT_OFFSET)?,
+            open_orders: read_pubkey(data, OPEN_ORDERS_OFFSET)?,
+            market: read_pubkey(data, MARKET_OFFSET)?,
+            market_program: read_pubkey(data, MARKET_PROGRAM_OFFSET)?,
+            target_orders: read_pubkey(data, TARGET_ORDERS_OFFSET)?,
+        })
+    }
+
+    /// Резервы пула (coin, pc), как их считает программа AMM
+    ///
+    /// Баланс vault плюс средства пула в ордерах OpenBook минус прибыль,
+    /// ожидающая вывода. `open_orders` — (coin, pc) из `open_orders_totals`,
+    /// нули, если пул не размещает ордера.
+    pub fn reserves(&self, coin_vault_amount: u64, pc_vault_amount: u64, open_orders: (u64, u64)) -> Result<(u64, u64)> {
+        let coin = coin_vault_amount.checked_add(open_orders.0)
+            .and_then(|total| total.checked_sub(self.need_take_pnl_coin))
+            .context("Некорректный резерв coin пула")?;
+        let pc = pc_vault_amount.checked_add(open_orders.1)
+            .and_then(|total| total.checked_sub(self.need_take_pnl_pc))
+            .context("Некорректный резерв pc пула")?;
+        Ok((coin, pc))
+    }
+}
+
+/// Баланс SPL токен аккаунта (vault пула)
+pub fn token_account_amount(data: &[u8]) -> Result<u64> {
+    let account = spl_token::state::Account::unpack(data)
+        .context("Аккаунт не является SPL токен аккаунтом")?;
+    Ok(account.amount)
+}
+
+/// Средства в ордерах OpenBook (native_coin_total, native_pc_total)
+pub fn open_orders_totals(data: &[u8]) -> Result<(u64, u64)> {
+    if !data.starts_with(OPEN_ORDERS_PADDING) {
+        anyhow::bail!("Аккаунт не является OpenOrders Serum/OpenBook");
+    }
+    Ok((
+        read_u64(data, OPEN_ORDERS_COIN_TOTAL_OFFSET).context("Некорректный аккаунт OpenOrders")?,
+        read_u64(data, OPEN_ORDERS_PC_TOTAL_OFFSET).context("Некорректный аккаунт OpenOrders")?,
+    ))
+}
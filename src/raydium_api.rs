@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::config::{ProxyConfig, RaydiumApiConfig};
+use crate::fiat::{get_json, http_client, parse_price};
+
+/// Максимум пулов пары в одном ответе API
+const PAGE_SIZE: usize = 20;
+
+/// Пул из Raydium API v3 (`/pools/info/mint`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaydiumApiPool {
+    /// Адрес аккаунта пула
+    pub id: Pubkey,
+    /// Программа пула (AMM v4, CPMM, CLMM)
+    pub program_id: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    /// Цена: сколько mint_b за 1 mint_a
+    pub price: Decimal,
+    /// Комиссия пула в процентах
+    pub fee_percent: Decimal,
+    /// Ликвидность пула в USD (None, если API её не вернул)
+    pub tvl_usd: Option<Decimal>,
+}
+
+fn pubkey(value: &Value, field: &str) -> Result<Pubkey> {
+    let text = value.as_str().with_context(|| format!("Нет поля {}", field))?;
+    Pubkey::from_str(text).with_context(|| format!("Некорректный адрес в поле {}: {}", field, text))
+}
+
+/// Разбор списка пулов из ответа `/pools/info/mint`
+///
+/// Записи, которые не удалось разобрать, пропускаются с предупреждением.
+pub fn parse_pools(body: &Value) -> Result<Vec<RaydiumApiPool>> {
+    if body["success"].as_bool() == Some(false) {
+        anyhow::bail!("Raydium API вернул ошибку: {}", body["msg"]);
+    }
+    let items = body["data"]["data"].as_array()
+        .context("Raydium API: нет списка пулов в ответе")?;
+
+    Ok(items.iter()
+        .filter_map(|item| {
+            let pool = (|| -> Result<RaydiumApiPool> {
+                Ok(RaydiumApiPool {
+                    id: pubkey(&item["id"], "id")?,
+                    program_id: pubkey(&item["programId"], "programId")?,
+                    mint_a: pubkey(&item["mintA"]["address"], "mintA.address")?,
+                    mint_b: pubkey(&item["mintB"]["address"], "mintB.address")?,
+                    price: parse_price(&item["price"]).context("Поле price")?,
+                    // feeRate — доля (0.0025 = 0.25%)
+                    fee_percent: parse_price(&item["feeRate"]).context("Поле feeRate")? * Decimal::from(100),
+                    tvl_usd: parse_price(&item["tvl"]).ok(),
+                })
+            })();
+            match pool {
+                Ok(pool) => Some(pool),
+                Err(e) => {
+                    log::warn!("Raydium API: пропущен пул {}: {:#}", item["id"], e);
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+/// Ключи пула AMM v4 из Raydium API v3 (`/pools/key/ids`)
+///
+/// Адреса аккаунтов, которые не меняются за время жизни пула: по ним
+/// читаются резервы без предварительного чтения аккаунта пула.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RaydiumPoolKeys {
+    pub id: Pubkey,
+    pub program_id: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub open_orders: Pubkey,
+    pub target_orders: Pubkey,
+    pub market_id: Pubkey,
+    pub market_program_id: Pubkey,
+}
+
+/// Разбор ключей пулов из ответа `/pools/key/ids`
+///
+/// Неизвестные API пулы приходят как null и пропускаются; пулы других типов
+/// (CPMM, CLMM) без OpenBook рынка пропускаются с предупреждением.
+pub fn parse_pool_keys(body: &Value) -> Result<Vec<RaydiumPoolKeys>> {
+    if body["success"].as_bool() == Some(false) {
+        anyhow::bail!("Raydium API вернул ошибку: {}", body["msg"]);
+    }
+    let items = body["data"].as_array()
+        .context("Raydium API: нет списка ключей пулов в ответе")?;
+
+    Ok(items.iter()
+        .filter(|item| !item.is_null())
+        .filter_map(|item| {
+            let keys = (|| -> Result<RaydiumPoolKeys> {
+                Ok(RaydiumPoolKeys {
+                    id: pubkey(&item["id"], "id")?,
+                    program_id: pubkey(&item["programId"], "programId")?,
+                    mint_a: pubkey(&item["mintA"]["address"], "mintA.address")?,
+                    mint_b: pubkey(&item["mintB"]["address"], "mintB.address")?,
+                    vault_a: pubkey(&item["vault"]["A"], "vault.A")?,
+                    vault_b: pubkey(&item["vault"]["B"], "vault.B")?,
+                    open_orders: pubkey(&item["openOrders"], "openOrders")?,
+                    target_orders: pubkey(&item["targetOrders"], "targetOrders")?,
+                    market_id: pubkey(&item["marketId"], "marketId")?,
+                    market_program_id: pubkey(&item["marketProgramId"], "marketProgramId")?,
+                })
+            })();
+            match keys {
+                Ok(keys) => Some(keys),
+                Err(e) => {
+                    log::warn!("Raydium API: пропущены ключи пула {}: {:#}", item["id"], e);
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+type PoolCache = HashMap<(Pubkey, Pubkey), (Instant, Vec<RaydiumApiPool>)>;
+
+/// Клиент Raydium API v3 для поиска пулов по mint
+///
+/// Ответы кешируются на `cache_ttl_sec`: список пулов пары меняется редко,
+/// а API ограничивает частоту запросов. Ошибка API не кешируется — вызывающий
+/// код переходит к поиску пулов on-chain.
+pub struct RaydiumApi {
+    client: reqwest::Client,
+    base_url: String,
+    ttl: Duration,
+    cache: Mutex<PoolCache>,
+}
+
+impl RaydiumApi {
+    pub fn new(config: &RaydiumApiConfig, proxy: &ProxyConfig) -> Result<Self> {
+        Self::with_url(&config.base_url, Duration::from_secs(config.cache_ttl_sec), proxy)
+    }
+
+    /// Клиент с указанным URL и временем жизни кеша (ноль — без кеша)
+    pub fn with_url(base_url: &str, ttl: Duration, proxy: &ProxyConfig) -> Result<Self> {
+        Ok(Self {
+            client: http_client("Raydium API", proxy)?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn cached(&self, key: &(Pubkey, Pubkey)) -> Option<Vec<RaydiumApiPool>> {
+        let cache = self.cache.lock().expect("мьютекс кеша Raydium API отравлен");
+        cache.get(key)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < self.ttl)
+            .map(|(_, pools)| pools.clone())
+    }
+
+    /// Пулы пары (в любом порядке mint), от самого ликвидного
+    pub async fn pools_by_mints(&self, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<Vec<RaydiumApiPool>> {
+        // Ключ не зависит от порядка mint
+        let key = if mint_a <= mint_b { (*mint_a, *mint_b) } else { (*mint_b, *mint_a) };
+        if let Some(pools) = self.cached(&key) {
+            return Ok(pools);
+        }
+
+        let url = format!(
+            "{}/pools/info/mint?mint1={}&mint2={}&poolType=all&poolSortField=liquidity&sortType=desc&pageSize={}&page=1",
+            self.base_url, mint_a, mint_b, PAGE_SIZE
+        );
+        let body = get_json(&self.client, &url, "Raydium API").await?;
+        let pools = parse_pools(&body)?;
+
+        if !self.ttl.is_zero() {
+            self.cache.lock()
+                .expect("мьютекс кеша Raydium API отравлен")
+                .insert(key, (Instant::now(), pools.clone()));
+        }
+        Ok(pools)
+    }
+
+    /// Ключи пула (None — API не знает пула или это не пул AMM v4)
+    ///
+    /// Не кешируются здесь: ключи неизменны, их сохраняет реестр пулов.
+    pub async fn pool_keys(&self, id: &Pubkey) -> Result<Option<RaydiumPoolKeys>> {
+        let url = format!("{}/pools/key/ids?ids={}", self.base_url, id);
+        let body = get_json(&self.client, &url, "Raydium API").await?;
+        Ok(parse_pool_keys(&body)?.into_iter().find(|keys| keys.id == *id))
+    }
+}
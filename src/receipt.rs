@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+use solana_sdk::commitment_config::CommitmentConfig;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::config::NetworkConfig;
+use crate::event_log::{BotEvent, EventLog};
+use crate::fees::FeeLedger;
+use crate::profile::Stage;
+use crate::rpc::create_rpc_client;
+
+/// Ёмкость канала подписчиков (медленные подписчики пропускают старые события)
+const RECEIPT_CHANNEL_CAPACITY: usize = 256;
+/// Строк логов одной симуляции перед отправкой, сохраняемых в записи о сделке
+const MAX_SIMULATION_LOG_LINES: usize = 50;
+/// Интервал опроса статуса отправленной транзакции
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+tokio::task_local! {
+    static EXECUTION: ExecutionTracker;
+    static LEG: TradeLeg;
+}
+
+/// Этап исполнения сделки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionStage {
+    /// Рассчитаны объём и минимальный выход, сделка принята к исполнению
+    Quoted,
+    /// Транзакция ноги собрана и подписана
+    Built,
+    /// Транзакция ноги принята RPC (прошла preflight)
+    Sent,
+    /// Транзакция ноги подтверждена кластером
+    Confirmed,
+    /// Нога или сделка не удалась
+    Failed,
+}
+
+/// Нога двухэтапной сделки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeLeg {
+    Buy,
+    Sell,
+}
+
+/// Событие исполнения сделки для ленты WebSocket
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionReceipt {
+    /// Идентификатор исполнения (общий для всех событий сделки)
+    pub execution_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub trace_id: Option<String>,
+    pub stage: ExecutionStage,
+    /// Нога сделки (None — событие всей сделки)
+    pub leg: Option<TradeLeg>,
+    pub from_dex: String,
+    pub to_dex: String,
+    pub base_token: String,
+    pub quote_token: String,
+    pub trade_amount: Decimal,
+    /// Подпись транзакции ноги
+    pub signature: Option<String>,
+    /// Причина неудачи
+    pub reason: Option<String>,
+    /// Режим симуляции (подписи не настоящие)
+    pub simulated: bool,
+}
+
+/// Лента событий исполнения
+#[derive(Clone)]
+pub struct ReceiptFeed {
+    tx: broadcast::Sender<ExecutionReceipt>,
+    events: Option<EventLog>,
+}
+
+impl ReceiptFeed {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(RECEIPT_CHANNEL_CAPACITY);
+        Self { tx, events: None }
+    }
+
+    /// Подключение журнала событий (нумерация и догрузка для WebSocket)
+    pub fn with_events(mut self, events: EventLog) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Публикация события
+    pub fn publish(&self, receipt: ExecutionReceipt) {
+        if let Some(ref events) = self.events {
+            events.publish(BotEvent::Execution(receipt.clone()));
+        }
+        // Ошибка означает только отсутствие подписчиков
+        let _ = self.tx.send(receipt);
+    }
+
+    /// Подписка на новые события
+    pub fn subscribe(&self) -> broadcast::Receiver<ExecutionReceipt> {
+        self.tx.subscribe()
+    }
+}
+
+impl Default for ReceiptFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Отслеживание одной сделки: события с общим execution_id
+#[derive(Clone)]
+pub struct ExecutionTracker {
+    feed: ReceiptFeed,
+    base: Arc<ExecutionReceipt>,
+    simulation_logs: Arc<Mutex<Vec<String>>>,
+}
+
+impl ExecutionTracker {
+    pub fn new(feed: &ReceiptFeed, opportunity: &ArbitrageOpportunity, simulated: bool) -> Self {
+        Self {
+            feed: feed.clone(),
+            base: Arc::new(ExecutionReceipt {
+                execution_id: Uuid::new_v4(),
+                timestamp: Utc::now(),
+                trace_id: crate::trace::current().map(|id| id.to_string()),
+                stage: ExecutionStage::Quoted,
+                leg: None,
+                from_dex: opportunity.from_dex.clone(),
+                to_dex: opportunity.to_dex.clone(),
+                base_token: opportunity.base_token.clone(),
+                quote_token: opportunity.quote_token.clone(),
+                trade_amount: opportunity.trade_amount,
+                signature: None,
+                reason: None,
+                simulated,
+            }),
+            simulation_logs: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn execution_id(&self) -> Uuid {
+        self.base.execution_id
+    }
+
+    /// DEX, на котором исполняется нога
+    pub fn venue(&self, leg: TradeLeg) -> &str {
+        match leg {
+            TradeLeg::Buy => &self.base.from_dex,
+            TradeLeg::Sell => &self.base.to_dex,
+        }
+    }
+
+    /// Логи симуляций перед отправкой (строки каждой ноги помечены ногой)
+    pub fn simulation_logs(&self) -> Vec<String> {
+        self.simulation_logs.lock().expect("блокировка логов симуляции отравлена").clone()
+    }
+
+    /// Сохранение последних строк логов симуляции ноги (None — атомарной транзакции)
+    pub fn record_simulation_logs(&self, leg: Option<TradeLeg>, logs: &[String]) {
+        let prefix = match leg {
+            Some(TradeLeg::Buy) => "[buy] ",
+            Some(TradeLeg::Sell) => "[sell] ",
+            None => "",
+        };
+        let skip = logs.len().saturating_sub(MAX_SIMULATION_LOG_LINES);
+        self.simulation_logs.lock().expect("блокировка логов симуляции отравлена")
+            .extend(logs.iter().skip(skip).map(|line| format!("{}{}", prefix, line)));
+    }
+
+    /// Публикация события этапа
+    pub fn emit(&self, stage: ExecutionStage, leg: Option<TradeLeg>, signature: Option<&str>, reason: Option<String>) {
+        let mut receipt = (*self.base).clone();
+        receipt.timestamp = Utc::now();
+        receipt.stage = stage;
+        receipt.leg = leg;
+        receipt.signature = signature.map(str::to_string);
+        receipt.reason = reason;
+        self.feed.publish(receipt);
+    }
+}
+
+/// Выполнение сделки с привязанным отслеживанием
+pub async fn scope<F: Future>(tracker: ExecutionTracker, future: F) -> F::Output {
+    EXECUTION.scope(tracker, future).await
+}
+
+/// Выполнение ноги сделки: события из слоя DEX помечаются этой ногой
+pub async fn leg_scope<F: Future>(leg: TradeLeg, future: F) -> F::Output {
+    LEG.scope(leg, future).await
+}
+
+/// Отслеживание текущей сделки (None вне `scope`)
+pub fn current() -> Option<ExecutionTracker> {
+    EXECUTION.try_with(|tracker| tracker.clone()).ok()
+}
+
+/// Текущая нога сделки (None вне `leg_scope` — атомарная транзакция обеих ног)
+pub fn current_leg() -> Option<TradeLeg> {
+    LEG.try_with(|leg| *leg).ok()
+}
+
+/// Событие текущей ноги (ничего не делает вне `scope`)
+pub fn emit(stage: ExecutionStage, signature: Option<&str>) {
+    if let Some(tracker) = current() {
+        tracker.emit(stage, current_leg(), signature, None);
+    }
+}
+
+/// Логи симуляции перед отправкой для записи о текущей сделке (ничего не делает вне `scope`)
+pub fn record_simulation_logs(logs: &[String]) {
+    if let Some(tracker) = current() {
+        tracker.record_simulation_logs(current_leg(), logs);
+    }
+}
+
+/// Фоновое ожидание подтверждения транзакции ноги (None — атомарной транзакции обеих ног)
+///
+/// Не задерживает исполнение: событие confirmed или failed приходит в ленту,
+/// когда кластер подтвердит транзакцию или истечёт `timeout`. Затем расходы
+/// кошелька на транзакцию учитываются в `fees`.
+pub fn spawn_confirmation_watch(
+    tracker: ExecutionTracker,
+    leg: Option<TradeLeg>,
+    signature: String,
+    network: &NetworkConfig,
+    timeout: Duration,
+    fees: FeeLedger,
+) {
+    let network = network.clone();
+    let profiler = crate::profile::current();
+    let trace_id = crate::trace::current();
+    tokio::spawn(async move {
+        let started = std::time::Instant::now();
+        let result = wait_for_confirmation(&network, &signature, None, timeout).await;
+        if let Some(profiler) = profiler {
+            profiler.record(Stage::Confirm, leg.map(|leg| tracker.venue(leg)), trace_id, started.elapsed());
+        }
+        let (stage, reason) = match result {
+            Ok(()) => (ExecutionStage::Confirmed, None),
+            Err(e) => (ExecutionStage::Failed, Some(format!("{:#}", e))),
+        };
+        tracker.emit(stage, leg, Some(&signature), reason);
+        // Комиссия списывается и за транзакцию, исполненную с ошибкой
+        fees.record_transaction(&network, &signature).await;
+    });
+}
+
+/// Ожидание подтверждения транзакции с указанным commitment (None — commitment RPC клиента)
+///
+/// Ошибка, если транзакция исполнилась с ошибкой или не подтверждена за `timeout`.
+pub async fn wait_for_confirmation(
+    network: &NetworkConfig,
+    signature: &str,
+    commitment: Option<CommitmentConfig>,
+    timeout: Duration,
+) -> Result<()> {
+    let signature: solana_sdk::signature::Signature = signature.parse()
+        .context("Некорректная подпись транзакции")?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let network = network.clone();
+        let status = tokio::task::spawn_blocking(move || -> Result<_> {
+            let client = create_rpc_client(&network)?;
+            let statuses = client.get_signature_statuses(&[signature])
+                .context("Не удалось получить статус транзакции")?;
+            Ok(statuses.value.into_iter().next().flatten()
+                .map(|status| (status.satisfies_commitment(commitment.unwrap_or(client.commitment())), status.err)))
+        })
+        .await
+        .context("Задача проверки статуса транзакции завершилась с ошибкой")??;
+
+        match status {
+            Some((_, Some(err))) => anyhow::bail!("Транзакция не исполнена: {}", err),
+            Some((true, None)) => return Ok(()),
+            _ => {}
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("Транзакция не подтверждена за {} с", timeout.as_secs());
+        }
+        tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Резервы пула пары: (base, quote) в минимальных единицах токенов
+pub type Reserves = (u64, u64);
+
+/// Резервы, прочитанные адаптером DEX при последнем запросе цены
+///
+/// Позволяет сравнивать резервы между прогонами без дополнительных запросов RPC.
+#[derive(Debug, Default)]
+pub struct LastReserves {
+    pairs: Mutex<HashMap<(String, String), Reserves>>,
+}
+
+impl LastReserves {
+    /// Запоминание резервов пары
+    pub fn record(&self, base_token: &str, quote_token: &str, reserves: Reserves) {
+        self.pairs.lock()
+            .expect("мьютекс резервов пулов отравлен")
+            .insert((base_token.to_string(), quote_token.to_string()), reserves);
+    }
+
+    /// Резервы пары из последнего запроса цены
+    pub fn get(&self, base_token: &str, quote_token: &str) -> Option<Reserves> {
+        self.pairs.lock()
+            .expect("мьютекс резервов пулов отравлен")
+            .get(&(base_token.to_string(), quote_token.to_string()))
+            .copied()
+    }
+}
+
+/// Наблюдение резервов пула
+#[derive(Debug, Clone, Copy)]
+struct Observation {
+    reserves: Reserves,
+    at: DateTime<Utc>,
+}
+
+/// Изменение резерва в процентах (None — прежний резерв нулевой)
+fn change_percent(previous: u64, current: u64) -> Option<Decimal> {
+    if previous == 0 {
+        return None;
+    }
+    let delta = Decimal::from(previous.abs_diff(current));
+    Some(delta * Decimal::ONE_HUNDRED / Decimal::from(previous))
+}
+
+/// Защита от котирования по пулу, резервы которого резко изменились
+///
+/// Резервы каждого пула сравниваются с наблюдением предыдущего прогона поиска.
+/// Если base или quote резерв изменился больше чем на `max_change_percent`
+/// (крупный своп или манипуляция в процессе), пара откладывается на прогон:
+/// следующий прогон сравнивает уже с новыми резервами. Наблюдения старше двух
+/// интервалов проверки (пауза, пропуск пары бюджетом) не сравниваются.
+pub struct ReserveGuard {
+    max_change_percent: Decimal,
+    max_age: chrono::Duration,
+    observations: Mutex<HashMap<(String, String), Observation>>,
+}
+
+impl ReserveGuard {
+    /// `max_change_percent` = 0 отключает защиту
+    pub fn new(max_change_percent: Decimal, check_interval: Duration) -> Self {
+        let max_age = chrono::Duration::from_std(check_interval * 2)
+            .unwrap_or(chrono::Duration::MAX);
+        Self {
+            max_change_percent,
+            max_age,
+            observations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Включена ли защита
+    pub fn enabled(&self) -> bool {
+        self.max_change_percent > Decimal::ZERO
+    }
+
+    /// Учёт резервов пула пары на DEX
+    ///
+    /// Возвращает изменение в процентах, если оно превышает порог.
+    /// Текущие резервы в любом случае становятся новым наблюдением.
+    pub fn observe(&self, dex: &str, pair: &str, reserves: Reserves, now: DateTime<Utc>) -> Option<Decimal> {
+        let mut observations = self.observations.lock().expect("мьютекс защиты резервов отравлен");
+        let previous = observations.insert((dex.to_string(), pair.to_string()), Observation { reserves, at: now })?;
+        if !self.enabled() || now - previous.at > self.max_age {
+            return None;
+        }
+        let change = [
+            change_percent(previous.reserves.0, reserves.0),
+            change_percent(previous.reserves.1, reserves.1),
+        ].into_iter().flatten().max()?;
+        (change > self.max_change_percent).then_some(change)
+    }
+}
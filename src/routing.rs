@@ -0,0 +1,208 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Наименьшая длина маршрута: обмены из двух шагов (покупка и продажа одной
+/// пары на разных DEX) находит основной поиск по парам
+pub const MIN_ROUTE_HOPS: usize = 3;
+/// Наибольшая длина маршрута: число путей в графе растёт экспоненциально
+pub const MAX_ROUTE_HOPS: usize = 5;
+
+/// Котировка пула: цена пары на DEX и комиссия пула
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolQuote {
+    pub dex: String,
+    pub base_token: String,
+    pub quote_token: String,
+    /// Сколько quote токена за 1 base токен
+    pub price: Decimal,
+    /// Комиссия пула в процентах
+    pub fee_percent: Decimal,
+}
+
+/// Шаг маршрута: обмен from_token на to_token в пуле DEX
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteHop {
+    pub dex: String,
+    pub from_token: String,
+    pub to_token: String,
+    /// Сколько to_token за 1 from_token до комиссии пула
+    pub rate: Decimal,
+    /// Комиссия пула в процентах
+    pub fee_percent: Decimal,
+}
+
+impl RouteHop {
+    /// Курс шага после комиссии пула
+    pub fn net_rate(&self) -> Decimal {
+        self.rate * (Decimal::ONE - self.fee_percent / Decimal::from(100))
+    }
+}
+
+impl std::fmt::Display for RouteHop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}->{}", self.dex, self.from_token, self.to_token)
+    }
+}
+
+/// Замкнутый маршрут: начинается и заканчивается в одном токене
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub hops: Vec<RouteHop>,
+}
+
+impl Route {
+    /// Токен, в котором маршрут начинается и заканчивается
+    pub fn start_token(&self) -> &str {
+        &self.hops[0].from_token
+    }
+
+    /// Произведение курсов шагов до комиссий
+    pub fn gross_rate(&self) -> Decimal {
+        self.hops.iter().fold(Decimal::ONE, |rate, hop| rate * hop.rate)
+    }
+
+    /// Произведение курсов шагов после комиссий
+    pub fn net_rate(&self) -> Decimal {
+        self.hops.iter().fold(Decimal::ONE, |rate, hop| rate * hop.net_rate())
+    }
+
+    /// Прибыль маршрута в процентах до комиссий
+    pub fn profit_percent(&self) -> Decimal {
+        (self.gross_rate() - Decimal::ONE) * Decimal::from(100)
+    }
+
+    /// Прибыль маршрута в процентах после комиссий всех пулов
+    pub fn profit_percent_after_fees(&self) -> Decimal {
+        (self.net_rate() - Decimal::ONE) * Decimal::from(100)
+    }
+
+    /// Сумма комиссий пулов маршрута в процентах
+    pub fn total_fee_percent(&self) -> Decimal {
+        self.hops.iter().map(|hop| hop.fee_percent).sum()
+    }
+
+    /// Ключ маршрута без учёта стартового шага: один и тот же цикл,
+    /// найденный из разных токенов, даёт один ключ
+    fn cycle_key(&self) -> Vec<(&str, &str, &str)> {
+        let hops: Vec<(&str, &str, &str)> = self.hops.iter()
+            .map(|hop| (hop.dex.as_str(), hop.from_token.as_str(), hop.to_token.as_str()))
+            .collect();
+        (0..hops.len())
+            .map(|shift| {
+                let mut rotated = hops.clone();
+                rotated.rotate_left(shift);
+                rotated
+            })
+            .min()
+            .unwrap_or_default()
+    }
+}
+
+/// Граф токенов: рёбра — обмены в пулах всех DEX в обе стороны
+#[derive(Debug, Clone, Default)]
+pub struct TokenGraph {
+    edges: HashMap<String, Vec<RouteHop>>,
+}
+
+impl TokenGraph {
+    /// Граф по котировкам пулов (котировки с неположительной ценой пропускаются)
+    pub fn new(quotes: &[PoolQuote]) -> Self {
+        let mut edges: HashMap<String, Vec<RouteHop>> = HashMap::new();
+        for quote in quotes {
+            if quote.price <= Decimal::ZERO || quote.base_token == quote.quote_token {
+                continue;
+            }
+            edges.entry(quote.base_token.clone()).or_default().push(RouteHop {
+                dex: quote.dex.clone(),
+                from_token: quote.base_token.clone(),
+                to_token: quote.quote_token.clone(),
+                rate: quote.price,
+                fee_percent: quote.fee_percent,
+            });
+            edges.entry(quote.quote_token.clone()).or_default().push(RouteHop {
+                dex: quote.dex.clone(),
+                from_token: quote.quote_token.clone(),
+                to_token: quote.base_token.clone(),
+                rate: Decimal::ONE / quote.price,
+                fee_percent: quote.fee_percent,
+            });
+        }
+        Self { edges }
+    }
+
+    /// Маршруты из start_token обратно в него длиной от 3 до max_hops шагов,
+    /// прибыль которых после комиссий не меньше min_profit_percent
+    ///
+    /// Токены внутри маршрута не повторяются, поэтому каждая пара встречается
+    /// в маршруте не больше одного раза.
+    pub fn find_cycles(&self, start_token: &str, max_hops: usize, min_profit_percent: Decimal) -> Vec<Route> {
+        let mut routes = Vec::new();
+        let mut path = Vec::new();
+        let mut visited = HashSet::from([start_token.to_string()]);
+        self.extend(start_token, start_token, max_hops, min_profit_percent, &mut path, &mut visited, &mut routes);
+        routes
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn extend(
+        &self,
+        start_token: &str,
+        token: &str,
+        max_hops: usize,
+        min_profit_percent: Decimal,
+        path: &mut Vec<RouteHop>,
+        visited: &mut HashSet<String>,
+        routes: &mut Vec<Route>,
+    ) {
+        let Some(edges) = self.edges.get(token) else { return };
+        for hop in edges {
+            if hop.to_token == start_token {
+                if path.len() + 1 >= MIN_ROUTE_HOPS {
+                    let mut hops = path.clone();
+                    hops.push(hop.clone());
+                    let route = Route { hops };
+                    if route.profit_percent_after_fees() >= min_profit_percent {
+                        routes.push(route);
+                    }
+                }
+                continue;
+            }
+            if path.len() + 1 >= max_hops || visited.contains(&hop.to_token) {
+                continue;
+            }
+            visited.insert(hop.to_token.clone());
+            path.push(hop.clone());
+            self.extend(start_token, &hop.to_token, max_hops, min_profit_percent, path, visited, routes);
+            path.pop();
+            visited.remove(&hop.to_token);
+        }
+    }
+}
+
+/// Прибыльные маршруты из стартовых токенов, лучшие первыми
+///
+/// Цикл, который проходит через несколько стартовых токенов, возвращается один
+/// раз — из токена, указанного раньше.
+pub fn find_routes(
+    quotes: &[PoolQuote],
+    start_tokens: &[&str],
+    max_hops: usize,
+    min_profit_percent: Decimal,
+) -> Vec<Route> {
+    let graph = TokenGraph::new(quotes);
+    let mut seen = HashSet::new();
+    let mut routes = Vec::new();
+    for start_token in start_tokens {
+        for route in graph.find_cycles(start_token, max_hops, min_profit_percent) {
+            let key: Vec<(String, String, String)> = route.cycle_key().into_iter()
+                .map(|(dex, from, to)| (dex.to_string(), from.to_string(), to.to_string()))
+                .collect();
+            if seen.insert(key) {
+                routes.push(route);
+            }
+        }
+    }
+    routes.sort_by_key(|route| std::cmp::Reverse(route.profit_percent_after_fees()));
+    routes
+}
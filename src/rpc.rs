@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Url;
+use serde_json::json;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_request::RpcRequest;
+use solana_rpc_client::http_sender::HttpSender;
+use solana_rpc_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::account_snapshot::AccountSnapshot;
+use crate::config::{resolve_env_value, DataSource, NetworkConfig, ProxyConfig};
+use crate::proxy::{self, ProxyEndpoint};
+
+/// Таймаут HTTP запросов к RPC
+const RPC_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Предел адресов в одном запросе getMultipleAccounts
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Создание RPC клиента с учётом авторизации провайдера
+///
+/// Заголовки и API ключ подставляются из конфигурации и .env,
+/// поэтому в конфиге и логах остаётся только URL без секретов.
+pub fn create_rpc_client(network: &NetworkConfig) -> Result<RpcClient> {
+    let mut headers = build_headers(&network.rpc_headers)?;
+    let url = authenticate(&network.rpc_url, network, &mut headers)?;
+
+    let mut builder = reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(RPC_REQUEST_TIMEOUT);
+    if network.keep_alive_sec > 0 {
+        let keep_alive = Duration::from_secs(network.keep_alive_sec);
+        builder = builder
+            .tcp_keepalive(keep_alive)
+            .pool_idle_timeout(keep_alive);
+    }
+    let builder = proxy::configure(builder, &network.proxy, ProxyEndpoint::Rpc)?;
+    let http_client = builder.build()
+        .context("Не удалось создать HTTP клиент для RPC")?;
+
+    log::debug!("Создан RPC клиент для {}", redact_url(&network.rpc_url));
+
+    let sender = RedactedSender { inner: HttpSender::new_with_client(url, http_client) };
+    #[cfg(feature = "chaos")]
+    let sender = crate::chaos::ChaosSender::for_url(sender, &network.rpc_url);
+    let sender = crate::account_snapshot::SnapshotSender::new(sender, AccountSnapshot::for_url(&network.rpc_url));
+    let config = RpcClientConfig::with_commitment(CommitmentConfig::confirmed());
+    Ok(match network.data_source {
+        DataSource::Geyser | DataSource::Websocket => RpcClient::new_sender(crate::geyser::CachedSender::new(sender), config),
+        DataSource::Rpc => RpcClient::new_sender(sender, config),
+    })
+}
+
+/// Настройки сети для симуляций, проверок перед запуском и аналитики
+///
+/// С `simulation_rpc_url` запросы идут на него с заголовками
+/// `simulation_rpc_headers`, прокси `network.proxy.simulation_rpc` и без API
+/// ключа основного провайдера; без него —
+/// на rpc_url. Симуляция перед отправкой сделки остаётся на rpc_url: она должна
+/// видеть то же состояние и blockhash, что и отправка.
+pub fn simulation_network(network: &NetworkConfig) -> NetworkConfig {
+    let Some(ref url) = network.simulation_rpc_url else {
+        return network.clone();
+    };
+    NetworkConfig {
+        rpc_url: url.clone(),
+        rpc_api_key_env: None,
+        rpc_api_key_header: None,
+        rpc_headers: network.simulation_rpc_headers.clone(),
+        data_source: DataSource::Rpc,
+        proxy: ProxyConfig {
+            rpc: network.proxy.simulation_rpc.clone(),
+            ..network.proxy.clone()
+        },
+        ..network.clone()
+    }
+}
+
+/// Транспорт RPC без URL в ошибках
+///
+/// Ошибки reqwest (таймауты, ответы 401/429/5xx) содержат URL запроса, а в нём
+/// может быть API ключ (query параметр) или токен в пути (QuickNode). Такие
+/// ошибки попадают в логи повторов отправки и фоновых задач.
+struct RedactedSender<S> {
+    inner: S,
+}
+
+#[async_trait::async_trait]
+impl<S: RpcSender + Send + Sync> RpcSender for RedactedSender<S> {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        self.inner.send(request, params).await.map_err(without_url)
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.inner.get_transport_stats()
+    }
+
+    fn url(&self) -> String {
+        self.inner.url()
+    }
+}
+
+fn without_url(error: ClientError) -> ClientError {
+    match error.kind {
+        ClientErrorKind::Reqwest(e) => ClientError { request: error.request, kind: ClientErrorKind::Reqwest(e.without_url()) },
+        kind => ClientError { request: error.request, kind },
+    }
+}
+
+/// Чтение аккаунтов (getAccountInfo или getMultipleAccounts), которое можно
+/// ответить из локального состояния вместо RPC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountRead {
+    pub addresses: Vec<Pubkey>,
+    /// getAccountInfo: в ответе один аккаунт, а не список
+    pub single: bool,
+    /// Бинарная кодировка (base64, base64+zstd): только такие ответы собираются локально
+    pub binary: bool,
+}
+
+impl AccountRead {
+    /// Разбор запроса (None — другой метод или некорректные адреса)
+    pub fn parse(request: RpcRequest, params: &serde_json::Value) -> Option<Self> {
+        let single = match request {
+            RpcRequest::GetAccountInfo => true,
+            RpcRequest::GetMultipleAccounts => false,
+            _ => return None,
+        };
+        let addresses = if single {
+            params[0].as_str().and_then(|address| Pubkey::from_str(address).ok()).map(|address| vec![address])
+        } else {
+            params[0].as_array().and_then(|addresses| {
+                addresses.iter()
+                    .map(|address| address.as_str().and_then(|address| Pubkey::from_str(address).ok()))
+                    .collect::<Option<Vec<_>>>()
+            })
+        }?;
+        let encoding = params[1]["encoding"].as_str().unwrap_or("base58");
+        Some(Self { addresses, single, binary: matches!(encoding, "base64" | "base64+zstd") })
+    }
+
+    /// Ответ RPC с аккаунтами запроса (в порядке адресов) на слоте `slot`
+    pub fn response(&self, accounts: &[Option<Account>], slot: u64) -> serde_json::Value {
+        let mut values: Vec<serde_json::Value> = accounts.iter()
+            .map(|account| account.as_ref().map_or(serde_json::Value::Null, ui_account))
+            .collect();
+        let value = if self.single { values.remove(0) } else { serde_json::Value::Array(values) };
+        json!({ "context": { "slot": slot }, "value": value })
+    }
+}
+
+/// Аккаунт в формате ответа RPC (encoding = base64)
+fn ui_account(account: &Account) -> serde_json::Value {
+    json!({
+        "lamports": account.lamports,
+        "data": [BASE64.encode(&account.data), "base64"],
+        "owner": account.owner.to_string(),
+        "executable": account.executable,
+        "rentEpoch": account.rent_epoch,
+        "space": account.data.len(),
+    })
+}
+
+/// URL WebSocket с добавленным API ключом (если ключ передаётся в URL)
+pub fn authenticated_ws_url(network: &NetworkConfig) -> Result<Option<String>> {
+    let Some(ref ws_url) = network.ws_url else { return Ok(None) };
+    let mut headers = HeaderMap::new();
+    authenticate(ws_url, network, &mut headers).map(Some)
+}
+
+/// Заголовки для авторизации WebSocket подключения (с API ключом, если он передаётся заголовком)
+pub fn ws_headers(network: &NetworkConfig) -> Result<HeaderMap> {
+    let mut headers = build_headers(&network.ws_headers)?;
+    if let Some(ref ws_url) = network.ws_url {
+        authenticate(ws_url, network, &mut headers)?;
+    }
+    Ok(headers)
+}
+
+/// API ключ из переменной `rpc_api_key_env`: в заголовок `rpc_api_key_header`, если он задан,
+/// иначе в query параметр `rpc_api_key_param`. Возвращает URL для запросов
+fn authenticate(url: &str, network: &NetworkConfig, headers: &mut HeaderMap) -> Result<String> {
+    let Some(ref key_env) = network.rpc_api_key_env else {
+        return Ok(url.to_string());
+    };
+
+    let api_key = std::env::var(key_env)
+        .with_context(|| format!("Переменная окружения {} не задана", key_env))?;
+
+    if let Some(ref header) = network.rpc_api_key_header {
+        let header_name = HeaderName::from_bytes(header.as_bytes())
+            .with_context(|| format!("Некорректное имя заголовка: {}", header))?;
+        let mut header_value = HeaderValue::from_str(&api_key)
+            .with_context(|| format!("Некорректное значение API ключа в {}", key_env))?;
+        header_value.set_sensitive(true);
+        headers.insert(header_name, header_value);
+        return Ok(url.to_string());
+    }
+
+    let mut parsed = Url::parse(url)
+        .with_context(|| format!("Некорректный URL: {}", redact_url(url)))?;
+    parsed.query_pairs_mut().append_pair(&network.rpc_api_key_param, &api_key);
+
+    Ok(parsed.to_string())
+}
+
+/// Построение HTTP заголовков из конфигурации
+fn build_headers(raw: &HashMap<String, String>) -> Result<HeaderMap> {
+    let mut headers = HttpSender::default_headers();
+    for (name, value) in raw {
+        let value = resolve_env_value(value)
+            .with_context(|| format!("Не удалось получить значение заголовка {}", name))?;
+        let header_name = HeaderName::from_bytes(name.as_bytes())
+            .with_context(|| format!("Некорректное имя заголовка: {}", name))?;
+        let mut header_value = HeaderValue::from_str(&value)
+            .with_context(|| format!("Некорректное значение заголовка {}", name))?;
+        // Значения заголовков могут содержать токены — скрываем их из Debug вывода
+        header_value.set_sensitive(true);
+        headers.insert(header_name, header_value);
+    }
+    Ok(headers)
+}
+
+/// URL без секретов для логов и API
+///
+/// Оставляет только схему, хост и порт: провайдеры передают токены
+/// как в query параметрах, так и в пути (QuickNode).
+pub fn redact_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(parsed) => {
+            let host = parsed.host_str().unwrap_or_default();
+            let port = parsed.port().map(|p| format!(":{}", p)).unwrap_or_default();
+            let has_secret_parts = parsed.path() != "/" || parsed.query().is_some();
+            let suffix = if has_secret_parts { "/***" } else { "" };
+            format!("{}://{}{}{}", parsed.scheme(), host, port, suffix)
+        }
+        Err(_) => "<некорректный URL>".to_string(),
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::clock::{system_clock, to_chrono, SharedClock};
+use crate::config::RpcCacheConfig;
+
+/// Имя файла кеша в каталоге данных
+const CACHE_FILE: &str = "rpc_cache.json";
+
+/// Вид неизменяемых данных (определяет время жизни записи)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CachedKind {
+    /// Параметры mint (decimals)
+    Mint,
+    /// Статические поля пула или рынка (mint, vault, order book адреса)
+    Market,
+    /// Список аккаунтов программы
+    ProgramAccounts,
+    /// Пул торговой пары из реестра пулов
+    Pool,
+}
+
+impl CachedKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CachedKind::Mint => "mint",
+            CachedKind::Market => "market",
+            CachedKind::ProgramAccounts => "program_accounts",
+            CachedKind::Pool => "pool",
+        }
+    }
+}
+
+/// Запись кеша
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    kind: CachedKind,
+    fetched_at: DateTime<Utc>,
+    value: serde_json::Value,
+}
+
+/// Статистика кеша
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RpcCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Кеш ответов RPC для неизменяемых данных
+///
+/// Адаптеры DEX перечитывают при каждом сканировании аккаунты, данные которых
+/// не меняются (decimals mint, адреса vault и order book рынка). Кеш хранит
+/// их с долгим временем жизни и сохраняет в `rpc_cache.json` в каталоге
+/// данных, чтобы после перезапуска не запрашивать их заново.
+pub struct RpcCache {
+    config: RpcCacheConfig,
+    path: Option<PathBuf>,
+    clock: SharedClock,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl RpcCache {
+    /// Кеш в каталоге данных (при `persist = false` — только в памяти)
+    ///
+    /// Повреждённый файл не мешает запуску: кеш начинается с нуля.
+    pub fn open(config: &RpcCacheConfig, data_dir: &Path) -> Self {
+        let mut cache = Self::in_memory(config);
+        if !config.enabled || !config.persist {
+            return cache;
+        }
+
+        let path = data_dir.join(CACHE_FILE);
+        match load_entries(&path) {
+            Ok(entries) => {
+                let now = cache.clock.now();
+                let entries: HashMap<_, _> = entries
+                    .into_iter()
+                    .filter(|(_, entry)| cache.is_fresh(entry, now))
+                    .collect();
+                if !entries.is_empty() {
+                    log::info!("Загружено записей кеша RPC: {}", entries.len());
+                }
+                cache.entries = Mutex::new(entries);
+            }
+            Err(e) => log::warn!("Кеш RPC не загружен, начинаем с пустого: {:#}", e),
+        }
+        cache.path = Some(path);
+        cache
+    }
+
+    /// Кеш без сохранения на диск
+    pub fn in_memory(config: &RpcCacheConfig) -> Self {
+        Self {
+            config: config.clone(),
+            path: None,
+            clock: system_clock(),
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Использование другого источника времени
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Время жизни записи
+    pub fn ttl(&self, kind: CachedKind) -> Duration {
+        Duration::from_secs(match kind {
+            CachedKind::Mint => self.config.mint_ttl_sec,
+            CachedKind::Market => self.config.market_ttl_sec,
+            CachedKind::ProgramAccounts => self.config.program_accounts_ttl_sec,
+            CachedKind::Pool => self.config.pool_ttl_sec,
+        })
+    }
+
+    /// Значение из кеша, если оно ещё не устарело
+    pub fn get<T: DeserializeOwned>(&self, kind: CachedKind, key: &str) -> Option<T> {
+        if !self.config.enabled {
+            return None;
+        }
+        let now = self.clock.now();
+        let value = self.lock()
+            .get(&entry_key(kind, key))
+            .filter(|entry| self.is_fresh(entry, now))
+            .map(|entry| entry.value.clone());
+        let parsed = value.and_then(|value| serde_json::from_value(value).ok());
+        match parsed {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        parsed
+    }
+
+    /// Сохранение значения
+    ///
+    /// Ошибка записи файла только логируется: кеш в памяти продолжает работать.
+    pub fn insert<T: Serialize>(&self, kind: CachedKind, key: &str, value: &T) {
+        if !self.config.enabled {
+            return;
+        }
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("Не удалось сериализовать запись кеша RPC {}: {}", key, e);
+                return;
+            }
+        };
+
+        let mut entries = self.lock();
+        entries.insert(entry_key(kind, key), CacheEntry { kind, fetched_at: self.clock.now(), value });
+        if let Some(ref path) = self.path {
+            if let Err(e) = save_entries(path, &entries) {
+                log::warn!("Не удалось сохранить кеш RPC: {:#}", e);
+            }
+        }
+    }
+
+    /// Значение из кеша или результат `fetch` (сохраняется при успехе)
+    pub fn get_or_fetch<T, F>(&self, kind: CachedKind, key: &str, fetch: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Result<T>,
+    {
+        if let Some(value) = self.get(kind, key) {
+            return Ok(value);
+        }
+        let value = fetch()?;
+        self.insert(kind, key, &value);
+        Ok(value)
+    }
+
+    /// Данные аккаунта пула или рынка для чтения статических полей
+    ///
+    /// Изменяемые поля (резервы, цена, order book) из этих данных брать нельзя.
+    pub fn static_account_data(&self, client: &RpcClient, address: &Pubkey) -> Result<Vec<u8>> {
+        let encoded: String = self.get_or_fetch(CachedKind::Market, &address.to_string(), || {
+            let data = client.get_account_data(address)
+                .with_context(|| format!("Не удалось получить данные аккаунта {}", address))?;
+            Ok(BASE64.encode(data))
+        })?;
+        BASE64.decode(encoded).context("Повреждённая запись кеша RPC")
+    }
+
+    /// Decimals токена по адресу mint
+    pub fn mint_decimals(&self, client: &RpcClient, mint: &Pubkey) -> Result<u8> {
+        self.get_or_fetch(CachedKind::Mint, &mint.to_string(), || {
+            let data = client.get_account_data(mint)
+                .with_context(|| format!("Не удалось получить аккаунт mint {}", mint))?;
+            let mint_state = spl_token::state::Mint::unpack(&data)
+                .with_context(|| format!("Аккаунт {} не является SPL mint", mint))?;
+            Ok(mint_state.decimals)
+        })
+    }
+
+    /// Аккаунты программы (адрес и данные)
+    pub fn program_accounts(&self, client: &RpcClient, program_id: &Pubkey) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let encoded: Vec<(String, String)> = self.get_or_fetch(CachedKind::ProgramAccounts, &program_id.to_string(), || {
+            let accounts = client.get_program_accounts(program_id)
+                .with_context(|| format!("Не удалось получить аккаунты программы {}", program_id))?;
+            Ok(accounts
+                .into_iter()
+                .map(|(address, account)| (address.to_string(), BASE64.encode(account.data)))
+                .collect())
+        })?;
+        encoded
+            .into_iter()
+            .map(|(address, data)| {
+                Ok((
+                    Pubkey::from_str(&address).context("Повреждённая запись кеша RPC")?,
+                    BASE64.decode(data).context("Повреждённая запись кеша RPC")?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Статистика попаданий
+    pub fn stats(&self) -> RpcCacheStats {
+        RpcCacheStats {
+            entries: self.lock().len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry, now: DateTime<Utc>) -> bool {
+        now - entry.fetched_at < to_chrono(self.ttl(entry.kind))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CacheEntry>> {
+        self.entries.lock().expect("мьютекс кеша RPC отравлен")
+    }
+}
+
+fn entry_key(kind: CachedKind, key: &str) -> String {
+    format!("{}:{}", kind.as_str(), key)
+}
+
+fn load_entries(path: &Path) -> Result<HashMap<String, CacheEntry>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("Не удалось прочитать {:?}", path)),
+    };
+    serde_json::from_str(&content).with_context(|| format!("Повреждённый файл кеша {:?}", path))
+}
+
+/// Запись через временный файл, чтобы обрыв не испортил кеш
+fn save_entries(path: &Path, entries: &HashMap<String, CacheEntry>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Не удалось создать каталог {:?}", dir))?;
+    }
+    let content = serde_json::to_string(entries).context("Не удалось сериализовать кеш RPC")?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, content)
+        .with_context(|| format!("Не удалось записать {:?}", tmp_path))?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Не удалось заменить {:?}", path))?;
+    Ok(())
+}
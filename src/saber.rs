@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address;
+use crate::math::{self, Fee};
+
+/// Saber StableSwap Program ID (mainnet)
+pub const SABER_PROGRAM_ID: &str = "SSwpkEEcbUqx4vtttyA6T1XNkpYEZqV8yK9p4EmJZ7A";
+
+/// Размер аккаунта SwapInfo (без discriminator — программа не на Anchor)
+pub const SWAP_INFO_SIZE: u64 = 395;
+/// Смещение mint токена A в аккаунте SwapInfo
+pub const SWAP_TOKEN_A_MINT_OFFSET: usize = 203;
+/// Смещение mint токена B в аккаунте SwapInfo
+pub const SWAP_TOKEN_B_MINT_OFFSET: usize = 235;
+
+/// Смещения полей аккаунта SwapInfo
+const IS_INITIALIZED_OFFSET: usize = 0;
+const IS_PAUSED_OFFSET: usize = 1;
+const NONCE_OFFSET: usize = 2;
+const INITIAL_AMP_FACTOR_OFFSET: usize = 3;
+const TARGET_AMP_FACTOR_OFFSET: usize = 11;
+const START_RAMP_TS_OFFSET: usize = 19;
+const STOP_RAMP_TS_OFFSET: usize = 27;
+const TOKEN_A_RESERVE_OFFSET: usize = 107;
+const TOKEN_B_RESERVE_OFFSET: usize = 139;
+const POOL_MINT_OFFSET: usize = 171;
+const ADMIN_FEE_A_OFFSET: usize = 267;
+const ADMIN_FEE_B_OFFSET: usize = 299;
+const TRADE_FEE_NUMERATOR_OFFSET: usize = 363;
+const TRADE_FEE_DENOMINATOR_OFFSET: usize = 371;
+
+/// Тег инструкции swap
+const SWAP_INSTRUCTION_TAG: u8 = 1;
+
+/// Доля резерва, которой оценивается спот цена пула (1/1_000_000)
+const PRICE_PROBE_DIVISOR: u64 = 1_000_000;
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .with_context(|| format!("Данные короче смещения {}", offset + N))?
+        .try_into()
+        .context("Некорректная длина поля")
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_i64(data: &[u8], offset: usize) -> Result<i64> {
+    Ok(i64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+/// Аккаунт пула Saber (SwapInfo)
+///
+/// Курс задаёт инвариант stable swap с коэффициентом усиления A, который
+/// администратор может плавно менять между start_ramp_ts и stop_ramp_ts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapInfo {
+    pub is_initialized: bool,
+    /// Свопы остановлены администратором
+    pub is_paused: bool,
+    /// Nonce для адреса authority пула
+    pub nonce: u8,
+    pub initial_amp_factor: u64,
+    pub target_amp_factor: u64,
+    pub start_ramp_ts: i64,
+    pub stop_ramp_ts: i64,
+    /// Хранилища (SPL токен-аккаунты) резервов пула
+    pub token_a_reserve: Pubkey,
+    pub token_b_reserve: Pubkey,
+    pub pool_mint: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    /// Аккаунты, куда зачисляется доля администратора в комиссии
+    pub admin_fee_a: Pubkey,
+    pub admin_fee_b: Pubkey,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+}
+
+impl SwapInfo {
+    /// Разбор данных аккаунта SwapInfo
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() as u64 != SWAP_INFO_SIZE {
+            anyhow::bail!(
+                "Размер аккаунта {} байт не совпадает с пулом Saber ({} байт)",
+                data.len(), SWAP_INFO_SIZE
+            );
+        }
+        Ok(Self {
+            is_initialized: data[IS_INITIALIZED_OFFSET] != 0,
+            is_paused: data[IS_PAUSED_OFFSET] != 0,
+            nonce: data[NONCE_OFFSET],
+            initial_amp_factor: read_u64(data, INITIAL_AMP_FACTOR_OFFSET)?,
+            target_amp_factor: read_u64(data, TARGET_AMP_FACTOR_OFFSET)?,
+            start_ramp_ts: read_i64(data, START_RAMP_TS_OFFSET)?,
+            stop_ramp_ts: read_i64(data, STOP_RAMP_TS_OFFSET)?,
+            token_a_reserve: read_pubkey(data, TOKEN_A_RESERVE_OFFSET)?,
+            token_b_reserve: read_pubkey(data, TOKEN_B_RESERVE_OFFSET)?,
+            pool_mint: read_pubkey(data, POOL_MINT_OFFSET)?,
+            token_a_mint: read_pubkey(data, SWAP_TOKEN_A_MINT_OFFSET)?,
+            token_b_mint: read_pubkey(data, SWAP_TOKEN_B_MINT_OFFSET)?,
+            admin_fee_a: read_pubkey(data, ADMIN_FEE_A_OFFSET)?,
+            admin_fee_b: read_pubkey(data, ADMIN_FEE_B_OFFSET)?,
+            trade_fee_numerator: read_u64(data, TRADE_FEE_NUMERATOR_OFFSET)?,
+            trade_fee_denominator: read_u64(data, TRADE_FEE_DENOMINATOR_OFFSET)?,
+        })
+    }
+
+    /// Пул принимает свопы
+    pub fn is_tradable(&self) -> bool {
+        self.is_initialized && !self.is_paused
+    }
+
+    /// Комиссия свопа
+    pub fn trade_fee(&self) -> Result<Fee> {
+        Fee::new(self.trade_fee_numerator, self.trade_fee_denominator)
+            .context("Некорректная комиссия пула Saber")
+    }
+
+    /// Коэффициент усиления A в момент `now` (unix-время)
+    ///
+    /// Во время изменения A линейно идёт от initial к target, как в программе.
+    pub fn amp_factor(&self, now: i64) -> u64 {
+        if now >= self.stop_ramp_ts || self.stop_ramp_ts <= self.start_ramp_ts {
+            return self.target_amp_factor;
+        }
+        if now <= self.start_ramp_ts {
+            return self.initial_amp_factor;
+        }
+        let range = (self.stop_ramp_ts - self.start_ramp_ts) as u128;
+        let elapsed = (now - self.start_ramp_ts) as u128;
+        let (initial, target) = (u128::from(self.initial_amp_factor), u128::from(self.target_amp_factor));
+        // |target − initial|·elapsed/range меньше |target − initial| и помещается в u64
+        if target >= initial {
+            (initial + (target - initial) * elapsed / range) as u64
+        } else {
+            (initial - (initial - target) * elapsed / range) as u64
+        }
+    }
+
+    /// Хранилище пула для токена, хранилище и аккаунт комиссии администратора противоположного токена
+    pub fn reserves_for(&self, from_mint: &Pubkey) -> Result<(Pubkey, Pubkey, Pubkey)> {
+        if *from_mint == self.token_a_mint {
+            Ok((self.token_a_reserve, self.token_b_reserve, self.admin_fee_b))
+        } else if *from_mint == self.token_b_mint {
+            Ok((self.token_b_reserve, self.token_a_reserve, self.admin_fee_a))
+        } else {
+            anyhow::bail!("Токен {} не торгуется в пуле Saber", from_mint)
+        }
+    }
+}
+
+/// Выход свопа amount_in при текущих резервах пула (с комиссией)
+pub fn quote_swap(info: &SwapInfo, now: i64, amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    math::stable_swap_output(amount_in, reserve_in, reserve_out, info.amp_factor(now), info.trade_fee()?)
+}
+
+/// Спот курс пула: сколько токенов reserve_out за 1 токен reserve_in (в минимальных единицах)
+///
+/// Считается своп без комиссии на малую долю резерва: у stable swap цена
+/// зависит от баланса резервов, а не от их отношения.
+pub fn spot_rate(info: &SwapInfo, now: i64, reserve_in: u64, reserve_out: u64) -> Result<Decimal> {
+    let probe = (reserve_in / PRICE_PROBE_DIVISOR).max(1);
+    let amount_out = math::stable_swap_output(probe, reserve_in, reserve_out, info.amp_factor(now), Fee::ZERO)?;
+    Decimal::from(amount_out)
+        .checked_div(Decimal::from(probe))
+        .context("Переполнение при расчёте цены")
+}
+
+/// Адрес authority пула (program address от адреса пула и nonce)
+pub fn swap_authority(program_id: &Pubkey, swap_address: &Pubkey, nonce: u8) -> Result<Pubkey> {
+    Pubkey::create_program_address(&[swap_address.as_ref(), &[nonce]], program_id)
+        .context("Некорректный nonce authority пула Saber")
+}
+
+/// Инструкция swap: amount_in токена `from_mint` на не меньше min_amount_out
+///
+/// Минимальный выход проверяет программа. Токены списываются с ATA кошелька
+/// и зачисляются на ATA кошелька.
+pub fn swap_instruction(
+    program_id: &Pubkey,
+    swap_address: &Pubkey,
+    info: &SwapInfo,
+    user_wallet: &Pubkey,
+    from_mint: &Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Result<Instruction> {
+    let (swap_source, swap_destination, admin_fee_destination) = info.reserves_for(from_mint)?;
+    let to_mint = if *from_mint == info.token_a_mint { info.token_b_mint } else { info.token_a_mint };
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_address, false),
+        AccountMeta::new_readonly(swap_authority(program_id, swap_address, info.nonce)?, false),
+        AccountMeta::new_readonly(*user_wallet, true),
+        AccountMeta::new(get_associated_token_address(user_wallet, from_mint), false),
+        AccountMeta::new(swap_source, false),
+        AccountMeta::new(swap_destination, false),
+        AccountMeta::new(get_associated_token_address(user_wallet, &to_mint), false),
+        AccountMeta::new(admin_fee_destination, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    let mut data = Vec::with_capacity(17);
+    data.push(SWAP_INSTRUCTION_TAG);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Ok(Instruction { program_id: *program_id, accounts, data })
+}
@@ -0,0 +1,116 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use crate::config::{DexConfig, ScanBudgetConfig};
+
+/// Сколько последних проверок пары учитывается в частоте возможностей
+const FREQUENCY_WINDOW: usize = 20;
+/// После стольких пропусков подряд пара проверяется вне очереди
+pub const STARVATION_SCANS: u32 = 10;
+
+/// Недавняя активность пары
+#[derive(Debug, Default)]
+struct PairActivity {
+    /// Находились ли возможности в последних проверках (старые первыми)
+    recent: VecDeque<bool>,
+    /// Сколько прогонов подряд пара пропущена из-за бюджета
+    skipped: u32,
+}
+
+impl PairActivity {
+    fn hits(&self) -> usize {
+        self.recent.iter().filter(|&&found| found).count()
+    }
+}
+
+/// План прогона поиска
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanPlan {
+    /// Пары, которые проверяются в этом прогоне (в порядке конфигурации)
+    pub pairs: Vec<String>,
+    /// Пары, не поместившиеся в бюджет
+    pub skipped: Vec<String>,
+    /// Запланированное количество запросов
+    pub requests: u32,
+}
+
+/// Бюджет запросов RPC на прогон поиска
+///
+/// Если все пары не помещаются в бюджет, пары проверяются по частоте
+/// возможностей в последних проверках; пара, пропущенная `STARVATION_SCANS`
+/// раз подряд, проверяется вне очереди, чтобы её статистика не устаревала.
+pub struct ScanBudget {
+    config: ScanBudgetConfig,
+    activity: Mutex<HashMap<String, PairActivity>>,
+}
+
+impl ScanBudget {
+    pub fn new(config: &ScanBudgetConfig) -> Self {
+        Self {
+            config: config.clone(),
+            activity: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Стоимость проверки пары: котировки на всех DEX, где она торгуется
+    pub fn pair_cost(&self, pair: &str, venues: &[&str], dex_config: &DexConfig) -> u32 {
+        venues.iter()
+            .filter(|venue| dex_config.lists_pair(venue, pair))
+            .map(|venue| self.config.request_cost(venue))
+            .sum()
+    }
+
+    /// Выбор пар для прогона в пределах бюджета
+    pub fn plan(&self, pairs: &[String], venues: &[&str], dex_config: &DexConfig) -> ScanPlan {
+        let costs: Vec<u32> = pairs.iter()
+            .map(|pair| self.pair_cost(pair, venues, dex_config))
+            .collect();
+        if self.config.max_requests == 0 {
+            return ScanPlan { pairs: pairs.to_vec(), skipped: Vec::new(), requests: costs.iter().sum() };
+        }
+
+        let mut activity = self.activity.lock().expect("мьютекс бюджета сканирования отравлен");
+        let priority = |pair: &String| {
+            let stats = activity.get(pair);
+            let skipped = stats.map_or(0, |stats| stats.skipped);
+            let starved = skipped >= STARVATION_SCANS;
+            (!starved, std::cmp::Reverse(if starved { skipped } else { 0 }), std::cmp::Reverse(stats.map_or(0, PairActivity::hits)))
+        };
+        let mut order: Vec<usize> = (0..pairs.len()).collect();
+        order.sort_by_key(|&i| priority(&pairs[i]));
+
+        let mut remaining = self.config.max_requests;
+        let mut selected = vec![false; pairs.len()];
+        for i in order {
+            if costs[i] <= remaining {
+                remaining -= costs[i];
+                selected[i] = true;
+            }
+        }
+
+        let mut plan = ScanPlan { pairs: Vec::new(), skipped: Vec::new(), requests: self.config.max_requests - remaining };
+        for (pair, selected) in pairs.iter().zip(selected) {
+            let stats = activity.entry(pair.clone()).or_default();
+            if selected {
+                stats.skipped = 0;
+                plan.pairs.push(pair.clone());
+            } else {
+                stats.skipped += 1;
+                plan.skipped.push(pair.clone());
+            }
+        }
+        plan
+    }
+
+    /// Учёт результата проверки пары
+    pub fn record(&self, pair: &str, found: bool) {
+        if self.config.max_requests == 0 {
+            return;
+        }
+        let mut activity = self.activity.lock().expect("мьютекс бюджета сканирования отравлен");
+        let stats = activity.entry(pair.to_string()).or_default();
+        if stats.recent.len() == FREQUENCY_WINDOW {
+            stats.recent.pop_front();
+        }
+        stats.recent.push_back(found);
+    }
+}
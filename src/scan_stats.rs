@@ -0,0 +1,179 @@
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Количество последних прогонов для перцентилей длительности
+const DURATION_WINDOW: usize = 1000;
+
+/// Причина, по которой найденная возможность не исполнялась
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Режим без исполнения (сигналы или только поиск)
+    NoExecution,
+    /// Токен пары не входит в safety.allowed_tokens
+    TokenNotAllowed,
+    /// Направление запрещено dex.directions
+    DirectionNotAllowed,
+    /// Распределение капитала не выделило объём
+    Allocation,
+    /// Исполнение остановлено (критическая ошибка или лимит неудач)
+    Halted,
+    /// Исполнение приостановлено circuit breaker
+    CircuitBreaker,
+    /// Маршрут через несколько пар (исполнение не поддерживается)
+    MultiHop,
+    /// Цена ноги старше arbitrage.max_price_age_ms
+    StalePrice,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::NoExecution => "no_execution",
+            SkipReason::TokenNotAllowed => "token_not_allowed",
+            SkipReason::DirectionNotAllowed => "direction_not_allowed",
+            SkipReason::Allocation => "allocation",
+            SkipReason::Halted => "halted",
+            SkipReason::CircuitBreaker => "circuit_breaker",
+            SkipReason::MultiHop => "multi_hop",
+            SkipReason::StalePrice => "stale_price",
+        }
+    }
+}
+
+/// Перцентили длительности прогона поиска, мс
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DurationPercentiles {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// Метрики прогонов поиска
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScanMetrics {
+    /// Всего прогонов поиска
+    pub cycles: u64,
+    /// Прогонов, завершившихся ошибкой
+    pub failed_cycles: u64,
+    /// Прогонов за последнюю минуту
+    pub cycles_per_minute: u64,
+    /// Среднее количество полученных цен за прогон
+    pub average_prices_per_cycle: f64,
+    /// Длительность прогона по последним 1000 прогонам, мс
+    pub duration_ms: DurationPercentiles,
+    /// Найдено возможностей
+    pub opportunities_found: u64,
+    /// Передано на исполнение
+    pub opportunities_executed: u64,
+    /// Не исполнено
+    pub opportunities_skipped: u64,
+    /// Не исполнено по причинам
+    pub skipped_by_reason: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+struct ScanStatsState {
+    cycles: u64,
+    failed_cycles: u64,
+    prices_fetched: u64,
+    /// Время завершения и длительность последних прогонов
+    recent: VecDeque<(DateTime<Utc>, Duration)>,
+    found: u64,
+    executed: u64,
+    skipped: BTreeMap<&'static str, u64>,
+}
+
+/// Статистика конвейера поиска: прогоны, полученные цены, судьба найденных возможностей
+///
+/// Метрики сделок описывают только исполнение; здесь видно, сколько
+/// возможностей находится и почему они не доходят до исполнения.
+#[derive(Debug, Clone, Default)]
+pub struct ScanStats {
+    state: Arc<Mutex<ScanStatsState>>,
+}
+
+impl ScanStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Учёт завершённого прогона поиска (`found` — None, если прогон завершился ошибкой)
+    pub fn record_cycle(&self, finished_at: DateTime<Utc>, duration: Duration, prices_fetched: usize, found: Option<usize>) {
+        let mut state = self.lock();
+        state.cycles += 1;
+        state.prices_fetched += prices_fetched as u64;
+        match found {
+            Some(found) => state.found += found as u64,
+            None => state.failed_cycles += 1,
+        }
+        if state.recent.len() == DURATION_WINDOW {
+            state.recent.pop_front();
+        }
+        state.recent.push_back((finished_at, duration));
+    }
+
+    /// Учёт возможностей, переданных на исполнение
+    pub fn record_executed(&self, count: usize) {
+        self.lock().executed += count as u64;
+    }
+
+    /// Учёт возможностей, не переданных на исполнение
+    pub fn record_skipped(&self, reason: SkipReason, count: usize) {
+        if count == 0 {
+            return;
+        }
+        *self.lock().skipped.entry(reason.as_str()).or_default() += count as u64;
+    }
+
+    /// Снимок метрик на момент `now`
+    pub fn snapshot(&self, now: DateTime<Utc>) -> ScanMetrics {
+        let state = self.lock();
+        let minute_ago = now - ChronoDuration::minutes(1);
+        let mut durations: Vec<f64> = state.recent.iter()
+            .map(|(_, duration)| duration.as_secs_f64() * 1000.0)
+            .collect();
+        durations.sort_by(f64::total_cmp);
+
+        ScanMetrics {
+            cycles: state.cycles,
+            failed_cycles: state.failed_cycles,
+            cycles_per_minute: state.recent.iter()
+                .filter(|(finished_at, _)| *finished_at > minute_ago && *finished_at <= now)
+                .count() as u64,
+            average_prices_per_cycle: if state.cycles == 0 {
+                0.0
+            } else {
+                state.prices_fetched as f64 / state.cycles as f64
+            },
+            duration_ms: DurationPercentiles {
+                p50: percentile(&durations, 50),
+                p95: percentile(&durations, 95),
+                p99: percentile(&durations, 99),
+                max: durations.last().copied().unwrap_or(0.0),
+            },
+            opportunities_found: state.found,
+            opportunities_executed: state.executed,
+            opportunities_skipped: state.skipped.values().sum(),
+            skipped_by_reason: state.skipped.iter()
+                .map(|(reason, count)| (reason.to_string(), *count))
+                .collect(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ScanStatsState> {
+        self.state.lock().expect("мьютекс статистики прогонов отравлен")
+    }
+}
+
+/// Перцентиль по методу nearest-rank (значения отсортированы по возрастанию)
+fn percentile(sorted: &[f64], percent: usize) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use crate::config::{MaintenanceWindowConfig, ScheduleConfig};
+
+/// Окно обслуживания, во время которого исполнение сделок приостанавливается
+#[derive(Debug, Clone)]
+pub struct MaintenanceWindow {
+    pub name: String,
+    kind: WindowKind,
+}
+
+#[derive(Debug, Clone)]
+enum WindowKind {
+    /// Еженедельное окно (время UTC); если end < start, окно переходит через полночь
+    Weekly {
+        days: Vec<Weekday>,
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+    /// Разовое окно (например, известное событие с высокой нагрузкой на сеть)
+    OneOff {
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    },
+}
+
+impl MaintenanceWindow {
+    /// Разбор окна из конфигурации
+    pub fn from_config(config: &MaintenanceWindowConfig) -> Result<Self> {
+        let kind = match (&config.from, &config.until, &config.start, &config.end) {
+            (Some(from), Some(until), None, None) => {
+                let from = parse_datetime(from)?;
+                let until = parse_datetime(until)?;
+                if until <= from {
+                    anyhow::bail!("Окно {}: until должен быть позже from", config.name);
+                }
+                WindowKind::OneOff { from, until }
+            }
+            (None, None, Some(start), Some(end)) => {
+                let days = config.days.iter()
+                    .map(|day| Weekday::from_str(day)
+                        .map_err(|_| anyhow::anyhow!("Окно {}: некорректный день недели: {}", config.name, day)))
+                    .collect::<Result<Vec<_>>>()?;
+                let start = parse_time(start)?;
+                let end = parse_time(end)?;
+                if start == end {
+                    anyhow::bail!("Окно {}: start и end совпадают", config.name);
+                }
+                WindowKind::Weekly { days, start, end }
+            }
+            _ => anyhow::bail!(
+                "Окно {}: укажите либо start/end (еженедельное), либо from/until (разовое)",
+                config.name
+            ),
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            kind,
+        })
+    }
+
+    /// Попадает ли момент времени в окно
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        match &self.kind {
+            WindowKind::OneOff { from, until } => now >= *from && now < *until,
+            WindowKind::Weekly { days, start, end } => {
+                let on_day = |day: Weekday| days.is_empty() || days.contains(&day);
+                let time = now.time();
+                if start < end {
+                    on_day(now.weekday()) && time >= *start && time < *end
+                } else {
+                    // Окно через полночь: день окна — день его начала
+                    (on_day(now.weekday()) && time >= *start)
+                        || (on_day(now.weekday().pred()) && time < *end)
+                }
+            }
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .with_context(|| format!("Некорректное время (ожидается HH:MM): {}", value))
+}
+
+fn parse_datetime(value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .with_context(|| format!("Некорректная дата (ожидается RFC3339): {}", value))
+}
+
+/// Расписание окон обслуживания
+#[derive(Debug, Clone, Default)]
+pub struct Schedule {
+    windows: Vec<MaintenanceWindow>,
+}
+
+impl Schedule {
+    /// Разбор расписания из конфигурации
+    pub fn from_config(config: &ScheduleConfig) -> Result<Self> {
+        let windows = config.maintenance_windows.iter()
+            .map(MaintenanceWindow::from_config)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { windows })
+    }
+
+    /// Все окна расписания
+    pub fn windows(&self) -> &[MaintenanceWindow] {
+        &self.windows
+    }
+
+    /// Активное в данный момент окно
+    pub fn active_window(&self, now: DateTime<Utc>) -> Option<&MaintenanceWindow> {
+        self.windows.iter().find(|w| w.contains(now))
+    }
+}
+
+/// Ручное переопределение расписания через API управления
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleOverride {
+    /// Следовать расписанию
+    Auto,
+    /// Приостановить исполнение независимо от расписания
+    Pause,
+    /// Исполнять сделки даже во время окна обслуживания
+    Resume,
+}
+
+impl ScheduleOverride {
+    fn to_u8(self) -> u8 {
+        match self {
+            ScheduleOverride::Auto => 0,
+            ScheduleOverride::Pause => 1,
+            ScheduleOverride::Resume => 2,
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ScheduleOverride::Pause,
+            2 => ScheduleOverride::Resume,
+            _ => ScheduleOverride::Auto,
+        }
+    }
+}
+
+/// Расписание с ручным переопределением, разделяемое основным циклом и веб-сервером
+#[derive(Debug, Clone)]
+pub struct ScheduleControl {
+    schedule: Arc<Schedule>,
+    override_mode: Arc<AtomicU8>,
+}
+
+impl ScheduleControl {
+    /// Создание контроля для расписания (без переопределения)
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule: Arc::new(schedule),
+            override_mode: Arc::new(AtomicU8::new(ScheduleOverride::Auto.to_u8())),
+        }
+    }
+
+    /// Расписание окон обслуживания
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    /// Текущее переопределение
+    pub fn override_mode(&self) -> ScheduleOverride {
+        ScheduleOverride::from_u8(self.override_mode.load(Ordering::Relaxed))
+    }
+
+    /// Установка переопределения
+    pub fn set_override(&self, mode: ScheduleOverride) {
+        self.override_mode.store(mode.to_u8(), Ordering::Relaxed);
+    }
+
+    /// Причина приостановки исполнения (None — исполнение разрешено)
+    pub fn pause_reason(&self, now: DateTime<Utc>) -> Option<String> {
+        match self.override_mode() {
+            ScheduleOverride::Pause => Some("приостановлено вручную".to_string()),
+            ScheduleOverride::Resume => None,
+            ScheduleOverride::Auto => self.schedule
+                .active_window(now)
+                .map(|w| format!("окно обслуживания \"{}\"", w.name)),
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::{instruction::Instruction, message::Message, pubkey::Pubkey, transaction::Transaction};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use crate::config::{Config, NetworkConfig};
+use crate::devnet::ensure_devnet;
+use crate::dex::{default_fee_percent, fetch_token_balance, venue_type, DexInterface, DexManager};
+use crate::rpc::{create_rpc_client, simulation_network};
+use crate::tx_error::decode_transaction_error;
+use crate::units::RoundingPolicy;
+use crate::wallet::Wallet;
+
+/// Этап самопроверки адаптера
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestStage {
+    /// Запрос цены пары
+    Price,
+    /// Ожидаемый выход свопа с учётом комиссии пула
+    Quote,
+    /// Сборка инструкций свопа
+    Build,
+    /// Симуляция транзакции через RPC
+    Simulate,
+}
+
+/// Итог этапа или адаптера
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Failed,
+    /// Этап не поддерживается адаптером или адаптеру нечего проверять
+    Skipped,
+}
+
+/// Результат одного этапа
+#[derive(Debug, Clone, Serialize)]
+pub struct StageResult {
+    pub stage: SelfTestStage,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub elapsed_ms: u64,
+}
+
+/// Результат самопроверки одного DEX
+///
+/// После неудачного этапа следующие не выполняются.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdapterResult {
+    pub dex: String,
+    pub pair: Option<String>,
+    pub status: CheckStatus,
+    pub stages: Vec<StageResult>,
+}
+
+/// Результат самопроверки при запуске
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Ни один адаптер не провалил проверку
+    pub passed: bool,
+    pub adapters: Vec<AdapterResult>,
+}
+
+impl SelfTestReport {
+    /// Итоги в лог: по строке на адаптер, проваленный этап — с причиной
+    pub fn log(&self) {
+        for adapter in &self.adapters {
+            let pair = adapter.pair.as_deref().unwrap_or("-");
+            match adapter.status {
+                CheckStatus::Passed => log::info!("🧪 Самопроверка {} ({}): пройдена", adapter.dex, pair),
+                CheckStatus::Skipped => log::warn!("🧪 Самопроверка {}: пропущена (нет пары для проверки)", adapter.dex),
+                CheckStatus::Failed => {
+                    let failed = adapter.stages.iter().find(|stage| stage.status == CheckStatus::Failed);
+                    match failed {
+                        Some(stage) => log::error!(
+                            "🧪 Самопроверка {} ({}): этап {:?} не пройден: {}",
+                            adapter.dex, pair, stage.stage, stage.detail
+                        ),
+                        None => log::error!("🧪 Самопроверка {} ({}): не пройдена", adapter.dex, pair),
+                    }
+                }
+            }
+        }
+        if self.passed {
+            log::info!("🧪 Самопроверка адаптеров пройдена ({} DEX)", self.adapters.len());
+        } else {
+            log::error!("🧪 Самопроверка адаптеров выявила ошибки — проверьте интеграции до начала торговли");
+        }
+    }
+}
+
+/// Последний результат самопроверки для /api/status
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestResults {
+    report: Arc<Mutex<Option<SelfTestReport>>>,
+}
+
+impl SelfTestResults {
+    pub fn set(&self, report: SelfTestReport) {
+        *self.report.lock().expect("блокировка самопроверки отравлена") = Some(report);
+    }
+
+    pub fn report(&self) -> Option<SelfTestReport> {
+        self.report.lock().expect("блокировка самопроверки отравлена").clone()
+    }
+}
+
+/// Симуляция собранных инструкций свопа
+#[async_trait::async_trait]
+pub trait Simulator: Send + Sync {
+    /// Ok с потраченными compute units или ошибка симуляции
+    async fn simulate(&self, instructions: &[Instruction], wallet: &Wallet) -> Result<Option<u64>>;
+
+    /// Баланс токен-аккаунта на том же узле (от него считается проверка баланса транзакции)
+    async fn token_balance(&self, token_account: Pubkey) -> Result<u64>;
+}
+
+/// Симуляция через simulateTransaction RPC (без подписи, с подстановкой blockhash узлом)
+///
+/// Запросы идут на `simulation_rpc_url`, если он задан.
+pub struct RpcSimulator {
+    network: NetworkConfig,
+}
+
+impl RpcSimulator {
+    pub fn new(network: &NetworkConfig) -> Self {
+        Self { network: simulation_network(network) }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simulator for RpcSimulator {
+    async fn simulate(&self, instructions: &[Instruction], wallet: &Wallet) -> Result<Option<u64>> {
+        let rpc_client = create_rpc_client(&self.network)?;
+        let transaction = Transaction::new_unsigned(Message::new(instructions, Some(wallet.pubkey())));
+        let response = rpc_client
+            .simulate_transaction_with_config(&transaction, RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                ..RpcSimulateTransactionConfig::default()
+            })
+            .context("Не удалось выполнить simulateTransaction")?;
+        if let Some(error) = response.value.err {
+            return Err(anyhow::Error::new(decode_transaction_error(&error, Some(&transaction))));
+        }
+        Ok(response.value.units_consumed)
+    }
+
+    async fn token_balance(&self, token_account: Pubkey) -> Result<u64> {
+        fetch_token_balance(Arc::new(create_rpc_client(&self.network)?), token_account).await
+    }
+}
+
+/// Самопроверка при запуске, если она включена и разрешена
+///
+/// Выполняется только в simulation_mode или на devnet (кластер определяется по
+/// genesis hash): симуляция не отправляет транзакций, но известные пулы
+/// devnet — единственное место, где проверку можно прогнать без риска.
+pub async fn run_at_startup(config: &Config, dex_manager: &DexManager, wallet: &Wallet) -> Option<SelfTestReport> {
+    if !config.self_test.enabled {
+        return None;
+    }
+    if !config.safety.simulation_mode {
+        if let Err(e) = ensure_devnet(&config.network).await {
+            log::warn!("🧪 Самопроверка пропущена: доступна только на devnet или в simulation_mode ({:#})", e);
+            return None;
+        }
+    }
+
+    log::info!("🧪 Самопроверка адаптеров DEX: цена, котировка, сборка и симуляция свопа");
+    let report = run(config, &dex_manager.active_dexes(), wallet, &RpcSimulator::new(&config.network)).await;
+    report.log();
+    Some(report)
+}
+
+/// Один полный прогон конвейера на каждом DEX: продажа base токена пары за quote
+pub async fn run(
+    config: &Config,
+    dexes: &[&dyn DexInterface],
+    wallet: &Wallet,
+    simulator: &dyn Simulator,
+) -> SelfTestReport {
+    let started_at = Utc::now();
+    let mut adapters = Vec::with_capacity(dexes.len());
+    for dex in dexes {
+        adapters.push(check_adapter(config, *dex, wallet, simulator).await);
+    }
+    SelfTestReport {
+        started_at,
+        finished_at: Utc::now(),
+        passed: adapters.iter().all(|adapter| adapter.status != CheckStatus::Failed),
+        adapters,
+    }
+}
+
+/// Пара для проверки DEX: из self_test.pair или первая из trading_pairs, торгуемая на DEX
+fn pair_for(config: &Config, dex: &str) -> Option<String> {
+    match config.self_test.pair {
+        Some(ref pair) => config.dex.lists_pair(dex, pair).then(|| pair.clone()),
+        None => config.dex.trading_pairs.iter()
+            .find(|pair| config.dex.lists_pair(dex, pair))
+            .cloned(),
+    }
+}
+
+async fn check_adapter(
+    config: &Config,
+    dex: &dyn DexInterface,
+    wallet: &Wallet,
+    simulator: &dyn Simulator,
+) -> AdapterResult {
+    let pair = pair_for(config, dex.name());
+    let mut result = AdapterResult {
+        dex: dex.name().to_string(),
+        pair: pair.clone(),
+        status: CheckStatus::Skipped,
+        stages: Vec::new(),
+    };
+    let Some((base_token, quote_token)) = pair.as_deref().and_then(|pair| pair.split_once('/')) else {
+        return result;
+    };
+    let amount = config.self_test.amount
+        .and_then(Decimal::from_f64)
+        .unwrap_or_else(|| config.dex.min_trade_amount(dex.name()));
+
+    // Цена
+    let started = Instant::now();
+    let price = dex.get_price(base_token, quote_token).await
+        .and_then(|price| if price > Decimal::ZERO { Ok(price) } else { anyhow::bail!("нулевая цена") });
+    let Some(price) = record(&mut result, SelfTestStage::Price, started, price, |price| format!("{} {}", price, quote_token)) else {
+        return result;
+    };
+
+    // Котировка: ожидаемый выход и минимальный выход с проскальзыванием ноги продажи
+    let started = Instant::now();
+    let fee_percent = dex.pool_fee_percent(base_token, quote_token).await
+        .unwrap_or_else(|| default_fee_percent(dex.name()));
+    let hundred = Decimal::from(100);
+    let expected = amount * price * (Decimal::ONE - fee_percent / hundred);
+    let slippage = config.arbitrage.sell_slippage(venue_type(dex.name()));
+    let min_output = expected * (Decimal::ONE - slippage / hundred);
+    let quote = if min_output > Decimal::ZERO {
+        Ok(min_output)
+    } else {
+        Err(anyhow::anyhow!("нулевой выход свопа {} {} (комиссия {}%)", amount, base_token, fee_percent))
+    };
+    let rounding = RoundingPolicy::new(&config.arbitrage.rounding);
+    let detail = |min_output: &Decimal| format!(
+        "{} {} -> {} {} (комиссия {}%, минимум {})",
+        amount, base_token, rounding.quote(expected), quote_token, fee_percent,
+        rounding.round_min_output(*min_output, rounding.quote_decimal_places)
+    );
+    let Some(min_output) = record(&mut result, SelfTestStage::Quote, started, quote, detail) else {
+        return result;
+    };
+
+    // Сборка и симуляция — только у DEX, собирающих инструкции свопа
+    if !dex.supports_atomic_swap() {
+        for stage in [SelfTestStage::Build, SelfTestStage::Simulate] {
+            result.stages.push(StageResult {
+                stage,
+                status: CheckStatus::Skipped,
+                detail: "DEX не собирает инструкции свопа отдельно от отправки".to_string(),
+                elapsed_ms: 0,
+            });
+        }
+        result.status = CheckStatus::Passed;
+        return result;
+    }
+
+    let started = Instant::now();
+    let instructions = dex.build_swap_instructions(base_token, quote_token, amount, min_output, wallet).await;
+    let Some(instructions) = record(&mut result, SelfTestStage::Build, started, instructions, |instructions| {
+        format!("{} инструкций", instructions.len())
+    }) else {
+        return result;
+    };
+
+    let started = Instant::now();
+    let simulated = simulator.simulate(&instructions, wallet).await;
+    let detail = |units: &Option<u64>| match units {
+        Some(units) => format!("{} compute units", units),
+        None => "без ошибок".to_string(),
+    };
+    if record(&mut result, SelfTestStage::Simulate, started, simulated, detail).is_some() {
+        result.status = CheckStatus::Passed;
+    }
+    result
+}
+
+/// Запись результата этапа; None (и провал адаптера), если этап не пройден
+fn record<T>(
+    result: &mut AdapterResult,
+    stage: SelfTestStage,
+    started: Instant,
+    outcome: Result<T>,
+    detail: impl FnOnce(&T) -> String,
+) -> Option<T> {
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    match outcome {
+        Ok(value) => {
+            result.stages.push(StageResult { stage, status: CheckStatus::Passed, detail: detail(&value), elapsed_ms });
+            Some(value)
+        }
+        Err(e) => {
+            result.stages.push(StageResult { stage, status: CheckStatus::Failed, detail: format!("{:#}", e), elapsed_ms });
+            result.status = CheckStatus::Failed;
+            None
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+use crate::clock::SharedClock;
+use crate::config::{Config, WebConfig};
+use crate::lifecycle::TradingControl;
+use crate::supervisor::Supervisor;
+
+/// Отправка уведомления менеджеру служб (протокол sd_notify)
+///
+/// Сообщение уходит датаграммой в сокет из `NOTIFY_SOCKET` (адрес с `@` —
+/// абстрактный сокет Linux). Возвращает false, если бот запущен не под
+/// systemd (`Type=notify`) и уведомлять некого.
+pub fn notify(state: &str) -> Result<bool> {
+    let Some(socket_path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return Ok(false);
+    };
+    let socket_path = socket_path.to_string_lossy();
+    send(&socket_path, state)
+        .with_context(|| format!("Не удалось отправить уведомление в {}", socket_path))?;
+    Ok(true)
+}
+
+#[cfg(target_os = "linux")]
+fn send(socket_path: &str, state: &str) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+    let socket = UnixDatagram::unbound()?;
+    match socket_path.strip_prefix('@') {
+        Some(name) => {
+            let addr = SocketAddr::from_abstract_name(name.as_bytes())?;
+            socket.send_to_addr(state.as_bytes(), &addr)?;
+        }
+        None => {
+            socket.send_to(state.as_bytes(), socket_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send(_socket_path: &str, _state: &str) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Сообщение о готовности: предварительные проверки пройдены, задачи запущены
+pub fn notify_ready(status: &str) {
+    match notify(&format!("READY=1\nSTATUS={}", status)) {
+        Ok(true) => log::info!("systemd уведомлён о готовности"),
+        Ok(false) => {}
+        Err(e) => log::warn!("{:#}", e),
+    }
+}
+
+/// Интервал watchdog из `WATCHDOG_USEC` (`WatchdogSec=` в unit-файле)
+///
+/// None — watchdog не включён или предназначен другому процессу (`WATCHDOG_PID`).
+pub fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.trim().parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+/// Запуск пингов watchdog systemd под надзором
+///
+/// Пинг отправляется дважды за интервал watchdog, пока торговый цикл не завис
+/// (прогон завершался не дольше `monitoring.scan_stall_timeout_sec` назад).
+/// Зависший цикл перестаёт пинговать, и systemd перезапускает процесс.
+pub fn spawn_watchdog(supervisor: &Supervisor, config: &Config, trading: TradingControl, clock: SharedClock) {
+    let Some(interval) = watchdog_interval() else {
+        return;
+    };
+    let stall_timeout = config.monitoring.scan_stall_timeout();
+    log::info!("Watchdog systemd: пинг каждые {:?}", interval / 2);
+    supervisor.spawn("watchdog", move || {
+        run_watchdog(interval / 2, stall_timeout, trading.clone(), clock.clone())
+    });
+}
+
+async fn run_watchdog(
+    ping_interval: Duration,
+    stall_timeout: chrono::Duration,
+    trading: TradingControl,
+    clock: SharedClock,
+) -> Result<()> {
+    let mut stalled = false;
+    loop {
+        if trading.stalled(clock.now(), stall_timeout) {
+            if !stalled {
+                log::error!(
+                    "Торговый цикл не завершал прогон дольше {} с: пинги watchdog остановлены",
+                    stall_timeout.num_seconds()
+                );
+            }
+            stalled = true;
+        } else {
+            stalled = false;
+            notify("WATCHDOG=1")?;
+        }
+        clock.sleep(ping_interval).await;
+    }
+}
+
+/// Время ожидания ответа /health в режиме `--healthcheck`
+const HEALTHCHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Адрес /health работающего бота (адрес привязки на все интерфейсы — через loopback)
+pub fn health_url(web: &WebConfig) -> String {
+    let host = match web.bind_address.as_str() {
+        "0.0.0.0" => "127.0.0.1".to_string(),
+        "::" => "[::1]".to_string(),
+        address if address.contains(':') => format!("[{}]", address),
+        address => address.to_string(),
+    };
+    format!("http://{}:{}/health", host, web.port)
+}
+
+/// Проверка здоровья работающего бота для `HEALTHCHECK` Docker и скриптов
+///
+/// Ошибка — веб-сервер не отвечает или /health вернул не 2xx (цикл завис).
+pub async fn healthcheck(web: &WebConfig) -> Result<()> {
+    if !web.enabled {
+        anyhow::bail!("Проверка здоровья требует web.enabled = true");
+    }
+    let url = health_url(web);
+    let response = reqwest::Client::builder()
+        .timeout(HEALTHCHECK_TIMEOUT)
+        .build()?
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("{} не отвечает", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("{}: {} {}", url, status, body);
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::routing::RouteHop;
+
+/// Сколько последних сигналов хранится для API
+const RECENT_SIGNALS: usize = 500;
+/// Ёмкость канала подписчиков (медленные подписчики пропускают старые сигналы)
+const SIGNAL_CHANNEL_CAPACITY: usize = 256;
+
+/// Опубликованная арбитражная возможность
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signal {
+    pub timestamp: DateTime<Utc>,
+    /// Идентификатор прогона, в котором найдена возможность
+    pub trace_id: Option<String>,
+    pub from_dex: String,
+    pub to_dex: String,
+    pub base_token: String,
+    pub quote_token: String,
+    pub buy_price: Decimal,
+    pub sell_price: Decimal,
+    pub profit_percent: Decimal,
+    pub profit_percent_after_fees: Decimal,
+    pub trade_amount: Decimal,
+    /// Шаги маршрута через несколько пар
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub route: Vec<RouteHop>,
+}
+
+impl Signal {
+    /// Сигнал по найденной возможности
+    pub fn from_opportunity(opportunity: &ArbitrageOpportunity, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            timestamp,
+            trace_id: crate::trace::current().map(|id| id.to_string()),
+            from_dex: opportunity.from_dex.clone(),
+            to_dex: opportunity.to_dex.clone(),
+            base_token: opportunity.base_token.clone(),
+            quote_token: opportunity.quote_token.clone(),
+            buy_price: opportunity.buy_price,
+            sell_price: opportunity.sell_price,
+            profit_percent: opportunity.profit_percent,
+            profit_percent_after_fees: opportunity.profit_percent_after_fees,
+            trade_amount: opportunity.trade_amount,
+            route: opportunity.route.clone(),
+        }
+    }
+}
+
+/// Лента сигналов: последние сигналы для API и рассылка подписчикам WebSocket
+#[derive(Clone)]
+pub struct SignalFeed {
+    tx: broadcast::Sender<Signal>,
+    recent: Arc<Mutex<VecDeque<Signal>>>,
+}
+
+impl SignalFeed {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(SIGNAL_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(RECENT_SIGNALS))),
+        }
+    }
+
+    /// Публикация сигнала
+    pub fn publish(&self, signal: Signal) {
+        {
+            let mut recent = self.recent.lock().expect("мьютекс ленты сигналов отравлен");
+            if recent.len() == RECENT_SIGNALS {
+                recent.pop_front();
+            }
+            recent.push_back(signal.clone());
+        }
+        // Ошибка означает только отсутствие подписчиков
+        let _ = self.tx.send(signal);
+    }
+
+    /// Подписка на новые сигналы
+    pub fn subscribe(&self) -> broadcast::Receiver<Signal> {
+        self.tx.subscribe()
+    }
+
+    /// Последние сигналы, новые первыми
+    pub fn recent(&self, limit: usize) -> Vec<Signal> {
+        let recent = self.recent.lock().expect("мьютекс ленты сигналов отравлен");
+        recent.iter().rev().take(limit).cloned().collect()
+    }
+}
+
+impl Default for SignalFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use crate::config::StorageConfig;
+
+/// Файловое хранилище истории бота
+///
+/// Каждая коллекция — отдельный JSON Lines файл в data_dir: записи только
+/// добавляются, поэтому обрыв посреди записи портит не больше одной строки.
+/// Перезапись коллекции (`rewrite`) не теряет записи, добавляемые параллельно.
+#[derive(Debug, Clone)]
+pub struct Store {
+    dir: PathBuf,
+    /// Блокировка записи: добавление ждёт окончания перезаписи
+    writes: Arc<Mutex<()>>,
+}
+
+impl Store {
+    /// Открытие хранилища (каталог создаётся при необходимости)
+    pub async fn open(config: &StorageConfig) -> Result<Self> {
+        Self::open_dir(&config.data_dir).await
+    }
+
+    /// Открытие хранилища в указанном каталоге
+    pub async fn open_dir(dir: &Path) -> Result<Self> {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("Не удалось создать каталог хранилища: {:?}", dir))?;
+        Ok(Self { dir: dir.to_path_buf(), writes: Arc::new(Mutex::new(())) })
+    }
+
+    /// Каталог хранилища
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Путь к файлу коллекции
+    pub fn collection_path(&self, collection: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", collection))
+    }
+
+    /// Добавление записи в коллекцию
+    pub async fn append<T: Serialize>(&self, collection: &str, record: &T) -> Result<()> {
+        let mut line = serde_json::to_string(record)
+            .with_context(|| format!("Не удалось сериализовать запись {}", collection))?;
+        line.push('\n');
+
+        let path = self.collection_path(collection);
+        let _writes = self.writes.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Не удалось открыть {:?}", path))?;
+        file.write_all(line.as_bytes())
+            .await
+            .with_context(|| format!("Не удалось записать в {:?}", path))?;
+        Ok(())
+    }
+
+    /// Чтение всех записей коллекции
+    ///
+    /// Повреждённые строки пропускаются с предупреждением.
+    pub async fn read_all<T: DeserializeOwned>(&self, collection: &str) -> Result<Vec<T>> {
+        let path = self.collection_path(collection);
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Не удалось прочитать {:?}", path)),
+        };
+
+        let mut records = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => log::warn!("{:?}:{}: повреждённая запись пропущена: {}", path, index + 1, e),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Перезапись коллекции: `update` получает все записи и возвращает оставляемые
+    ///
+    /// Файл заменяется через временный файл и переименование; добавление записей
+    /// на это время приостанавливается. Повреждённые строки не переносятся.
+    /// Возвращает количество записей до и после.
+    pub async fn rewrite<T, F>(&self, collection: &str, update: F) -> Result<(usize, usize)>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Vec<T>) -> Vec<T>,
+    {
+        let _writes = self.writes.lock().await;
+        let records: Vec<T> = self.read_all(collection).await?;
+        let before = records.len();
+        let records = update(records);
+        if records.len() == before {
+            return Ok((before, before));
+        }
+
+        let mut content = String::new();
+        for record in &records {
+            content.push_str(&serde_json::to_string(record)
+                .with_context(|| format!("Не удалось сериализовать запись {}", collection))?);
+            content.push('\n');
+        }
+        let path = self.collection_path(collection);
+        let tmp = path.with_extension("jsonl.tmp");
+        tokio::fs::write(&tmp, content)
+            .await
+            .with_context(|| format!("Не удалось записать {:?}", tmp))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .with_context(|| format!("Не удалось заменить {:?}", path))?;
+        Ok((before, records.len()))
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::clock::to_chrono;
+use crate::config::SupervisorConfig;
+
+/// Состояние фоновой задачи
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    /// Задача работает
+    Running,
+    /// Задача упала и будет перезапущена после паузы
+    Backoff,
+    /// Задача штатно завершилась (например, закрыт канал)
+    Finished,
+}
+
+/// Здоровье фоновой задачи для API
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHealth {
+    pub name: String,
+    pub state: TaskState,
+    /// Время последнего запуска
+    pub started_at: DateTime<Utc>,
+    /// Количество автоматических перезапусков
+    pub restarts: u32,
+    /// Ошибка или паника, с которой задача упала последний раз
+    pub last_error: Option<String>,
+    /// Время последнего падения
+    pub last_failure_at: Option<DateTime<Utc>>,
+    /// Время следующего запуска (в состоянии backoff)
+    pub next_restart_at: Option<DateTime<Utc>>,
+}
+
+/// Надзор за фоновыми задачами
+///
+/// Задача описывается фабрикой, создающей её future. Если future вернул
+/// ошибку или запаниковал, задача запускается заново через паузу, которая
+/// удваивается с каждым падением подряд (от `initial_backoff_ms` до
+/// `max_backoff_sec`). Задача, проработавшая дольше `max_backoff_sec`,
+/// считается восстановившейся — пауза сбрасывается.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    tasks: Arc<Mutex<BTreeMap<String, TaskHealth>>>,
+}
+
+impl Supervisor {
+    pub fn new(config: &SupervisorConfig) -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_secs(config.max_backoff_sec),
+            tasks: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Пауза перед перезапуском после `failures` падений подряд
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// Запуск задачи под надзором
+    pub fn spawn<F, Fut>(&self, name: &str, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let name = name.to_string();
+        tokio::spawn(async move {
+            let mut failures = 0u32;
+            loop {
+                let started_at = Utc::now();
+                supervisor.update(&name, |health| {
+                    health.state = TaskState::Running;
+                    health.started_at = started_at;
+                    health.next_restart_at = None;
+                });
+
+                // Отдельная задача, чтобы паника не уронила надзор
+                let error = match tokio::spawn(factory()).await {
+                    Ok(Ok(())) => {
+                        log::info!("Фоновая задача {} завершилась", name);
+                        supervisor.update(&name, |health| health.state = TaskState::Finished);
+                        return;
+                    }
+                    Ok(Err(e)) => format!("{:#}", e),
+                    Err(e) => format!("паника: {}", e),
+                };
+
+                let now = Utc::now();
+                if now - started_at > to_chrono(supervisor.max_backoff) {
+                    failures = 0;
+                }
+                failures += 1;
+                let delay = supervisor.backoff(failures);
+                log::error!(
+                    "Фоновая задача {} упала ({}), перезапуск через {} мс",
+                    name, error, delay.as_millis()
+                );
+                supervisor.update(&name, |health| {
+                    health.state = TaskState::Backoff;
+                    health.last_error = Some(error);
+                    health.last_failure_at = Some(now);
+                    health.next_restart_at = Some(now + to_chrono(delay));
+                });
+
+                tokio::time::sleep(delay).await;
+                supervisor.update(&name, |health| health.restarts += 1);
+            }
+        });
+    }
+
+    /// Здоровье всех задач (по имени)
+    pub fn health(&self) -> Vec<TaskHealth> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn update(&self, name: &str, apply: impl FnOnce(&mut TaskHealth)) {
+        let mut tasks = self.lock();
+        let health = tasks.entry(name.to_string()).or_insert_with(|| TaskHealth {
+            name: name.to_string(),
+            state: TaskState::Running,
+            started_at: Utc::now(),
+            restarts: 0,
+            last_error: None,
+            last_failure_at: None,
+            next_restart_at: None,
+        });
+        apply(health);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, TaskHealth>> {
+        self.tasks.lock().expect("мьютекс состояния задач отравлен")
+    }
+}
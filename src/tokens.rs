@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use crate::config::TokenConfig;
+use crate::rpc_cache::RpcCache;
+use crate::units::{self, Rounding, MAX_DECIMALS};
+
+/// Mint wrapped SOL (нативный SOL в свопах DEX)
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+/// Токены, известные без настройки (mainnet): символ, mint, decimals
+const BUILTIN_TOKENS: &[(&str, &str, u8)] = &[
+    ("SOL", WSOL_MINT, 9),
+    ("WSOL", WSOL_MINT, 9),
+    ("USDC", "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 6),
+    ("USDT", "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", 6),
+];
+
+/// Токен в реестре
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub mint: Pubkey,
+    /// Decimals mint (None — читаются из аккаунта mint при первом обращении)
+    pub decimals: Option<u8>,
+}
+
+/// Реестр токенов: символ из торговых пар → mint и decimals
+///
+/// Встроенные токены (SOL, WSOL, USDC, USDT на mainnet) дополняются и
+/// переопределяются секцией `[tokens]` — например, mint USDC на devnet.
+/// Decimals, не заданные в настройках, читаются из аккаунта mint через кеш RPC.
+#[derive(Clone)]
+pub struct TokenRegistry {
+    tokens: HashMap<String, TokenInfo>,
+    rpc_cache: Option<Arc<RpcCache>>,
+}
+
+impl TokenRegistry {
+    /// Реестр из встроенных токенов и секции `[tokens]`
+    pub fn new(config: &HashMap<String, TokenConfig>) -> Result<Self> {
+        let mut tokens: HashMap<String, TokenInfo> = BUILTIN_TOKENS.iter()
+            .map(|&(symbol, mint, decimals)| {
+                let mint = Pubkey::from_str(mint).expect("встроенный mint токена корректен");
+                (symbol.to_string(), TokenInfo { symbol: symbol.to_string(), mint, decimals: Some(decimals) })
+            })
+            .collect();
+
+        for (symbol, token) in config {
+            if symbol.is_empty() || symbol.contains(char::is_whitespace) || symbol.contains('/') {
+                anyhow::bail!("tokens: некорректный символ токена \"{}\"", symbol);
+            }
+            let mint = Pubkey::from_str(&token.mint)
+                .with_context(|| format!("tokens.{}: некорректный mint {}", symbol, token.mint))?;
+            if let Some(decimals) = token.decimals {
+                if decimals > MAX_DECIMALS {
+                    anyhow::bail!("tokens.{}: decimals {} больше {}", symbol, decimals, MAX_DECIMALS);
+                }
+            }
+            tokens.insert(symbol.clone(), TokenInfo { symbol: symbol.clone(), mint, decimals: token.decimals });
+        }
+
+        Ok(Self { tokens, rpc_cache: None })
+    }
+
+    /// Чтение неизвестных decimals из аккаунта mint через кеш RPC
+    pub fn with_rpc_cache(mut self, rpc_cache: Arc<RpcCache>) -> Self {
+        self.rpc_cache = Some(rpc_cache);
+        self
+    }
+
+    /// Токен по символу
+    pub fn get(&self, symbol: &str) -> Option<&TokenInfo> {
+        self.tokens.get(symbol)
+    }
+
+    /// Символ токена по mint (из нескольких символов одного mint, как SOL и WSOL, — первый по алфавиту)
+    pub fn symbol(&self, mint: &Pubkey) -> Option<&str> {
+        self.tokens.values()
+            .filter(|token| token.mint == *mint)
+            .map(|token| token.symbol.as_str())
+            .min()
+    }
+
+    /// Mint токена по символу
+    pub fn mint(&self, symbol: &str) -> Result<Pubkey> {
+        self.get(symbol)
+            .map(|token| token.mint)
+            .with_context(|| format!("Mint токена {} не известен (добавьте его в [tokens])", symbol))
+    }
+
+    /// Decimals токена по mint: из реестра или из аккаунта mint
+    pub fn decimals(&self, client: &RpcClient, mint: &Pubkey) -> Result<u8> {
+        let known = self.tokens.values()
+            .filter(|token| token.mint == *mint)
+            .find_map(|token| token.decimals);
+        match (known, &self.rpc_cache) {
+            (Some(decimals), _) => Ok(decimals),
+            (None, Some(rpc_cache)) => rpc_cache.mint_decimals(client, mint),
+            (None, None) => anyhow::bail!("Decimals токена {} не известны", mint),
+        }
+    }
+
+    /// Сумма в целых токенах → минимальные единицы mint
+    pub fn to_base_units(&self, client: &RpcClient, mint: &Pubkey, amount: Decimal, rounding: Rounding) -> Result<u64> {
+        units::to_base_units(amount, self.decimals(client, mint)?, rounding)
+    }
+
+    /// Минимальные единицы mint → сумма в целых токенах
+    #[allow(dead_code)]
+    pub fn base_units_to_amount(&self, client: &RpcClient, mint: &Pubkey, amount: u64) -> Result<Decimal> {
+        units::from_base_units(amount, self.decimals(client, mint)?)
+    }
+}
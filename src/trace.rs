@@ -0,0 +1,54 @@
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+
+/// Максимальная длина идентификатора, принимаемого извне (заголовок X-Request-Id)
+const MAX_EXTERNAL_ID_LEN: usize = 64;
+
+tokio::task_local! {
+    static TRACE_ID: TraceId;
+}
+
+/// Идентификатор запроса API или прогона конвейера поиска возможностей
+///
+/// Попадает в логи, события WebSocket, записи о сделках и ответы с ошибками,
+/// чтобы по сообщению оператора можно было найти связанные действия движка.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct TraceId(String);
+
+impl TraceId {
+    /// Новый идентификатор вида `<prefix>-<12 hex>`
+    pub fn new(prefix: &str) -> Self {
+        let random = uuid::Uuid::new_v4().simple().to_string();
+        Self(format!("{}-{}", prefix, &random[..12]))
+    }
+
+    /// Идентификатор, переданный клиентом; отбрасывается, если небезопасен для логов
+    pub fn from_external(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_EXTERNAL_ID_LEN
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Выполнение future с привязанным идентификатором
+pub async fn scope<F: Future>(id: TraceId, future: F) -> F::Output {
+    TRACE_ID.scope(id, future).await
+}
+
+/// Идентификатор текущей задачи (None вне `scope`)
+pub fn current() -> Option<TraceId> {
+    TRACE_ID.try_with(|id| id.clone()).ok()
+}
@@ -0,0 +1,55 @@
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use uuid::Uuid;
+use crate::receipt::{self, TradeLeg};
+
+/// Программа SPL Memo v2 (инструкция без аккаунтов не требует подписей)
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+/// Префикс memo транзакций бота
+const MEMO_PREFIX: &str = "arb-bot";
+
+/// Метка сделки в транзакции: memo с идентификатором, созданным на клиенте
+///
+/// Идентификатор — `execution_id` сделки, он известен до первой отправки и не
+/// меняется при переподписи ноги с новым blockhash. По memo транзакции в
+/// истории кошелька сопоставляются со сделками, а две транзакции с одним memo
+/// означают повторную отправку одной ноги. Длина memo фиксирована, поэтому
+/// шаблон транзакции резервирует под неё место заранее.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeMemo(String);
+
+impl TradeMemo {
+    pub fn new(execution_id: Uuid, leg: Option<TradeLeg>) -> Self {
+        let leg = match leg {
+            Some(TradeLeg::Buy) => 'b',
+            Some(TradeLeg::Sell) => 's',
+            None => 'a',
+        };
+        Self(format!("{}:{}:{}", MEMO_PREFIX, execution_id.simple(), leg))
+    }
+
+    /// Memo текущей сделки и ноги (вне `receipt::scope` — со случайным идентификатором)
+    pub fn current() -> Self {
+        let execution_id = receipt::current().map_or_else(Uuid::new_v4, |tracker| tracker.execution_id());
+        Self::new(execution_id, receipt::current_leg())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Инструкция SPL Memo с этой меткой
+    pub fn instruction(&self) -> Instruction {
+        Instruction {
+            program_id: program_id(),
+            accounts: Vec::new(),
+            data: self.0.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Адрес программы SPL Memo
+pub fn program_id() -> Pubkey {
+    Pubkey::from_str(MEMO_PROGRAM_ID).expect("некорректный адрес программы memo")
+}
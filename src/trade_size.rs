@@ -0,0 +1,139 @@
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+/// Итерации бисекции: отрезок сужается в 2^64 раз — меньше минимальной единицы любого токена
+const BISECTION_STEPS: usize = 64;
+
+/// Кривая обмена пула: сколько токена даст вход amount_in (в целых токенах)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwapCurve {
+    /// Пул x*y=k с резервами в целых токенах, комиссия удерживается со входа
+    ConstantProduct {
+        reserve_in: Decimal,
+        reserve_out: Decimal,
+        fee_percent: Decimal,
+    },
+    /// Постоянный курс без влияния на цену: резервы пула неизвестны
+    /// (книги ордеров, CLMM, оракульные пулы)
+    Fixed {
+        rate: Decimal,
+        fee_percent: Decimal,
+    },
+}
+
+impl SwapCurve {
+    /// Выход обмена amount_in
+    pub fn output(&self, amount_in: Decimal) -> Decimal {
+        if amount_in <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+        match *self {
+            SwapCurve::ConstantProduct { reserve_in, reserve_out, fee_percent } => {
+                let amount_in = amount_in * fee_multiplier(fee_percent);
+                reserve_out * (amount_in / (reserve_in + amount_in))
+            }
+            SwapCurve::Fixed { rate, fee_percent } => amount_in * rate * fee_multiplier(fee_percent),
+        }
+    }
+
+    /// Влияние обмена amount_in на цену в процентах (без комиссии пула)
+    ///
+    /// Для пула x*y=k — насколько средний курс обмена хуже текущего,
+    /// у постоянного курса влияния нет.
+    pub fn price_impact_percent(&self, amount_in: Decimal) -> Decimal {
+        match *self {
+            SwapCurve::ConstantProduct { reserve_in, fee_percent, .. } if amount_in > Decimal::ZERO => {
+                let amount_in = amount_in * fee_multiplier(fee_percent);
+                amount_in / (reserve_in + amount_in) * Decimal::ONE_HUNDRED
+            }
+            _ => Decimal::ZERO,
+        }
+    }
+}
+
+/// Доля входа, остающаяся после комиссии
+fn fee_multiplier(fee_percent: Decimal) -> Decimal {
+    Decimal::ONE - fee_percent / Decimal::from(100)
+}
+
+/// Квадратный корень методом Ньютона от приближения f64
+fn sqrt(value: Decimal) -> Decimal {
+    if value <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    let mut root = value.to_f64()
+        .and_then(|v| Decimal::from_f64(v.sqrt()))
+        .filter(|root| *root > Decimal::ZERO)
+        .unwrap_or(Decimal::ONE);
+    for _ in 0..4 {
+        root = (root + value / root) / Decimal::TWO;
+    }
+    root
+}
+
+/// Прибыль сделки: продажа купленного за input минус стоимость входа
+///
+/// cost_multiplier — сколько стоит единица входа (1 + комиссия флеш-займа).
+pub fn profit(buy: &SwapCurve, sell: &SwapCurve, input: Decimal, cost_multiplier: Decimal) -> Decimal {
+    sell.output(buy.output(input)) - input * cost_multiplier
+}
+
+/// Вход сделки (в quote токене) от 0 до max_input, при котором прибыль после
+/// комиссий пулов и влияния на цену наибольшая
+///
+/// Для двух пулов x*y=k выход сделки — дробно-линейная функция входа
+/// A·q / (B + C·q), и максимум находится в явном виде. В остальных случаях
+/// прибыль вогнута, и максимум ищется бисекцией по знаку предельной прибыли.
+/// 0 — сделка неприбыльна при любом объёме.
+pub fn optimal_input(buy: &SwapCurve, sell: &SwapCurve, max_input: Decimal, cost_multiplier: Decimal) -> Decimal {
+    if max_input <= Decimal::ZERO {
+        return Decimal::ZERO;
+    }
+    let input = match (*buy, *sell) {
+        (
+            SwapCurve::ConstantProduct { reserve_in: buy_in, reserve_out: buy_out, fee_percent: buy_fee },
+            SwapCurve::ConstantProduct { reserve_in: sell_in, reserve_out: sell_out, fee_percent: sell_fee },
+        ) => {
+            let buy_gamma = fee_multiplier(buy_fee);
+            let sell_gamma = fee_multiplier(sell_fee);
+            // Производная A·B / (B + C·q)^2 равна cost_multiplier в точке максимума:
+            // q = (√(A/k)·√B − B) / C. Корни берутся по множителям — произведения
+            // резервов крупных пулов не помещаются в Decimal
+            let sqrt_a = sqrt(buy_gamma * sell_gamma / cost_multiplier) * sqrt(buy_out) * sqrt(sell_out);
+            let sqrt_b = sqrt(buy_in) * sqrt(sell_in);
+            let c = buy_gamma * (sell_in + sell_gamma * buy_out);
+            if c <= Decimal::ZERO {
+                return Decimal::ZERO;
+            }
+            sqrt_b * (sqrt_a - sqrt_b) / c
+        }
+        (SwapCurve::Fixed { .. }, SwapCurve::Fixed { .. }) => {
+            // Прибыль линейна: либо весь объём, либо ничего
+            if profit(buy, sell, max_input, cost_multiplier) > Decimal::ZERO { max_input } else { Decimal::ZERO }
+        }
+        _ => bisect(buy, sell, max_input, cost_multiplier),
+    };
+    let input = input.max(Decimal::ZERO).min(max_input);
+    if profit(buy, sell, input, cost_multiplier) > Decimal::ZERO { input } else { Decimal::ZERO }
+}
+
+/// Поиск максимума вогнутой прибыли бисекцией по знаку предельной прибыли
+fn bisect(buy: &SwapCurve, sell: &SwapCurve, max_input: Decimal, cost_multiplier: Decimal) -> Decimal {
+    let step = max_input * Decimal::new(1, 9);
+    let marginal = |input: Decimal| {
+        profit(buy, sell, input + step, cost_multiplier) - profit(buy, sell, input, cost_multiplier)
+    };
+    if marginal(max_input - step) >= Decimal::ZERO {
+        return max_input;
+    }
+    let (mut low, mut high) = (Decimal::ZERO, max_input);
+    for _ in 0..BISECTION_STEPS {
+        let middle = (low + high) / Decimal::TWO;
+        if marginal(middle) > Decimal::ZERO {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
@@ -0,0 +1,304 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{Transaction, TransactionError, VersionedTransaction};
+use std::fmt;
+use std::str::FromStr;
+use crate::failure::FailureKind;
+use crate::lifinity::LIFINITY_V2_PROGRAM_ID;
+use crate::openbook_v2::OPENBOOK_V2_PROGRAM_ID;
+use crate::saber::SABER_PROGRAM_ID;
+
+/// Raydium AMM v4
+const RAYDIUM_AMM_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+/// Orca Whirlpools
+const WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+/// Количество последних строк логов симуляции, сохраняемых в ошибке
+const MAX_LOG_LINES: usize = 10;
+
+/// Разобранная ошибка симуляции/отправки транзакции
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedTxError {
+    /// Индекс инструкции, вызвавшей ошибку
+    pub instruction_index: Option<u8>,
+    /// Название программы (если известна)
+    pub program: Option<String>,
+    /// Код custom ошибки программы
+    pub code: Option<u32>,
+    /// Человекочитаемая причина
+    pub reason: String,
+    /// Последние строки логов симуляции
+    pub logs: Vec<String>,
+    /// Категория неудачи (для счётчика неудач и автомата)
+    pub kind: FailureKind,
+}
+
+impl fmt::Display for DecodedTxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref program) = self.program {
+            write!(f, "{}: ", program)?;
+        }
+        write!(f, "{}", self.reason)?;
+        if let Some(code) = self.code {
+            write!(f, " (код {})", code)?;
+        }
+        if let Some(index) = self.instruction_index {
+            write!(f, ", инструкция #{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DecodedTxError {}
+
+impl DecodedTxError {
+    /// Ошибка с последними строками логов симуляции
+    pub fn with_logs(mut self, logs: Vec<String>) -> Self {
+        let skip = logs.len().saturating_sub(MAX_LOG_LINES);
+        self.logs = logs.into_iter().skip(skip).collect();
+        self
+    }
+}
+
+/// Разбор ошибки RPC клиента (в т.ч. неудачной preflight симуляции)
+///
+/// Возвращает None, если ошибка не связана с исполнением транзакции
+/// (сетевые сбои, таймауты) — такие ошибки имеет смысл повторять.
+pub fn decode_client_error(error: &ClientError, transaction: &Transaction) -> Option<DecodedTxError> {
+    decode_client_error_with(error, |index| legacy_program_id(transaction, index))
+}
+
+/// Разбор ошибки RPC клиента для versioned транзакции (шаблоны с ALT)
+pub fn decode_versioned_client_error(error: &ClientError, transaction: &VersionedTransaction) -> Option<DecodedTxError> {
+    decode_client_error_with(error, |index| {
+        // Программы не могут загружаться из ALT, поэтому достаточно статических ключей
+        let instruction = transaction.message.instructions().get(index as usize)?;
+        transaction.message.static_account_keys().get(instruction.program_id_index as usize).copied()
+    })
+}
+
+fn decode_client_error_with(error: &ClientError, program_of: impl Fn(u8) -> Option<Pubkey>) -> Option<DecodedTxError> {
+    let (tx_error, logs) = match error.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+            ..
+        }) => (result.err.clone(), result.logs.clone().unwrap_or_default()),
+        ClientErrorKind::TransactionError(tx_error) => (Some(tx_error.clone()), Vec::new()),
+        _ => (None, Vec::new()),
+    };
+
+    Some(decode_with_program(&tx_error?, program_of).with_logs(logs))
+}
+
+/// Разбор ошибки транзакции; transaction нужна для определения программы по индексу инструкции
+pub fn decode_transaction_error(error: &TransactionError, transaction: Option<&Transaction>) -> DecodedTxError {
+    decode_with_program(error, |index| transaction.and_then(|tx| legacy_program_id(tx, index)))
+}
+
+fn legacy_program_id(transaction: &Transaction, index: u8) -> Option<Pubkey> {
+    let instruction = transaction.message.instructions.get(index as usize)?;
+    transaction.message.account_keys.get(instruction.program_id_index as usize).copied()
+}
+
+fn decode_with_program(error: &TransactionError, program_of: impl Fn(u8) -> Option<Pubkey>) -> DecodedTxError {
+    match error {
+        TransactionError::InstructionError(index, instruction_error) => {
+            let program_id = program_of(*index);
+            let program = program_id.map(|id| program_name(&id));
+
+            let kind = instruction_error_kind(program_id.as_ref(), instruction_error);
+            let (code, reason) = match instruction_error {
+                InstructionError::Custom(code) => {
+                    let reason = program_id
+                        .and_then(|id| program_error_reason(&id, *code))
+                        .map(str::to_string)
+                        .unwrap_or_else(|| "неизвестная ошибка программы".to_string());
+                    (Some(*code), reason)
+                }
+                other => (None, other.to_string()),
+            };
+
+            DecodedTxError {
+                instruction_index: Some(*index),
+                program,
+                code,
+                reason,
+                logs: Vec::new(),
+                kind,
+            }
+        }
+        TransactionError::InsufficientFundsForFee => simple("недостаточно SOL для оплаты комиссии", FailureKind::Fatal),
+        TransactionError::BlockhashNotFound => simple("blockhash устарел, транзакцию нужно пересобрать", FailureKind::Transient),
+        TransactionError::AlreadyProcessed => simple("транзакция уже обработана", FailureKind::Transient),
+        TransactionError::AccountNotFound => simple("аккаунт не найден (нет ATA или не инициализирован)", FailureKind::Fatal),
+        other => simple(&other.to_string(), FailureKind::Execution),
+    }
+}
+
+fn simple(reason: &str, kind: FailureKind) -> DecodedTxError {
+    DecodedTxError {
+        instruction_index: None,
+        program: None,
+        code: None,
+        reason: reason.to_string(),
+        logs: Vec::new(),
+        kind,
+    }
+}
+
+/// Категория ошибки инструкции
+///
+/// Нехватка средств и неверные аккаунты не исправятся повтором — это Fatal,
+/// остальное (проскальзывание, пустой пул и т.п.) — неудача исполнения.
+/// Срабатывание `BalanceGuard` тоже выглядит как нехватка средств SPL Token,
+/// его нужно переопределять по индексу инструкции.
+fn instruction_error_kind(program_id: Option<&Pubkey>, error: &InstructionError) -> FailureKind {
+    match error {
+        InstructionError::InsufficientFunds
+        | InstructionError::IncorrectProgramId
+        | InstructionError::InvalidAccountOwner
+        | InstructionError::UninitializedAccount
+        | InstructionError::NotEnoughAccountKeys => FailureKind::Fatal,
+        InstructionError::Custom(code) => {
+            let fatal = match program_id.map(|id| id.to_string()).as_deref() {
+                Some(RAYDIUM_AMM_PROGRAM_ID) => matches!(code, 1 | 18 | 24 | 38 | 40),
+                _ if program_id == Some(&spl_token::id()) => matches!(code, 1 | 2 | 3 | 4 | 17),
+                _ => matches!(code, 3001 | 3005 | 3007 | 3012),
+            };
+            if fatal { FailureKind::Fatal } else { FailureKind::Execution }
+        }
+        _ => FailureKind::Execution,
+    }
+}
+
+/// Название известной программы или её адрес
+fn program_name(program_id: &Pubkey) -> String {
+    let known = [
+        (RAYDIUM_AMM_PROGRAM_ID, "Raydium AMM"),
+        (WHIRLPOOL_PROGRAM_ID, "Orca Whirlpool"),
+        (OPENBOOK_V2_PROGRAM_ID, "OpenBook v2"),
+        (LIFINITY_V2_PROGRAM_ID, "Lifinity v2"),
+        (SABER_PROGRAM_ID, "Saber"),
+    ];
+    known.iter()
+        .find(|(id, _)| Pubkey::from_str(id).ok().as_ref() == Some(program_id))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| {
+            if *program_id == spl_token::id() {
+                "SPL Token".to_string()
+            } else {
+                program_id.to_string()
+            }
+        })
+}
+
+/// Расшифровка custom кода ошибки известной программы
+pub fn program_error_reason(program_id: &Pubkey, code: u32) -> Option<&'static str> {
+    let id = program_id.to_string();
+    match id.as_str() {
+        RAYDIUM_AMM_PROGRAM_ID => raydium_error(code),
+        WHIRLPOOL_PROGRAM_ID => whirlpool_error(code).or_else(|| anchor_error(code)),
+        OPENBOOK_V2_PROGRAM_ID => openbook_v2_error(code).or_else(|| anchor_error(code)),
+        _ if *program_id == spl_token::id() => spl_token_error(code),
+        _ => anchor_error(code),
+    }
+}
+
+/// Ошибки Raydium AMM v4 (AmmError)
+fn raydium_error(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0 => "аккаунт уже используется",
+        1 => "некорректный адрес программы",
+        14 => "аккаунт должен быть доступен на запись",
+        18 => "некорректный владелец аккаунта",
+        21 => "отсутствует подпись",
+        22 => "пул в неподходящем статусе (свопы отключены)",
+        23 => "некорректная инструкция",
+        24 => "неверное количество аккаунтов",
+        29 => "некорректные входные параметры",
+        30 => "превышено проскальзывание (выход меньше min_amount_out)",
+        31 => "ошибка расчёта курса",
+        36 => "пустой пул",
+        38 => "некорректная программа SPL Token",
+        40 => "недостаточно средств",
+        _ => return None,
+    })
+}
+
+/// Ошибки Orca Whirlpool (Anchor, коды от 6000)
+fn whirlpool_error(code: u32) -> Option<&'static str> {
+    Some(match code {
+        6006 => "деление на ноль",
+        6011 => "sqrt price вне допустимого диапазона",
+        6012 => "нулевая ликвидность",
+        6017 => "превышен максимум токенов (token max exceeded)",
+        6018 => "выход меньше минимума (token min subceeded)",
+        6023 => "некорректная последовательность tick array",
+        6034 => "некорректное направление sqrt_price_limit",
+        6035 => "нулевой объём для обмена",
+        6036 => "выход меньше минимума (amount out below minimum)",
+        6037 => "вход больше максимума (amount in above maximum)",
+        6038 => "некорректный индекс в последовательности tick array",
+        _ => return None,
+    })
+}
+
+/// Ошибки OpenBook v2 (Anchor, коды от 6000)
+fn openbook_v2_error(code: u32) -> Option<&'static str> {
+    Some(match code {
+        6002 => "рынок истёк",
+        6004 => "некорректное количество лотов",
+        6008 => "некорректный тип ордера",
+        6009 => "некорректная цена в лотах",
+        _ => return None,
+    })
+}
+
+/// Общие ошибки фреймворка Anchor
+fn anchor_error(code: u32) -> Option<&'static str> {
+    Some(match code {
+        100 => "неизвестный discriminator инструкции",
+        101 => "отсутствует discriminator инструкции",
+        102 => "не удалось десериализовать инструкцию",
+        2000 => "нарушено ограничение mut",
+        2001 => "нарушено ограничение has_one",
+        2003 => "нарушено ограничение constraint",
+        2006 => "нарушено ограничение seeds",
+        2012 => "нарушено ограничение address",
+        2014 => "нарушено ограничение token mint",
+        2015 => "нарушено ограничение token owner",
+        3001 => "не найден discriminator аккаунта",
+        3002 => "discriminator аккаунта не совпадает",
+        3003 => "не удалось десериализовать аккаунт",
+        3005 => "недостаточно аккаунтов",
+        3007 => "аккаунт принадлежит другой программе",
+        3012 => "аккаунт не инициализирован",
+        _ => return None,
+    })
+}
+
+/// Ошибки программы SPL Token
+fn spl_token_error(code: u32) -> Option<&'static str> {
+    Some(match code {
+        0 => "аккаунт не освобождён от ренты",
+        1 => "недостаточно токенов",
+        2 => "некорректный mint",
+        3 => "mint аккаунта не совпадает",
+        4 => "владелец не совпадает",
+        17 => "аккаунт заморожен",
+        _ => return None,
+    })
+}
+
+/// Человекочитаемая причина ошибки из цепочки anyhow (для записи о сделке)
+pub fn failure_reason(error: &anyhow::Error) -> String {
+    error.chain()
+        .find_map(|cause| cause.downcast_ref::<DecodedTxError>())
+        .map(|decoded| match decoded.kind {
+            FailureKind::Aborted => format!("отменено до отправки: {}", decoded),
+            _ => decoded.to_string(),
+        })
+        .unwrap_or_else(|| format!("{:#}", error))
+}
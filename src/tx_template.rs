@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+use crate::config::TemplatesConfig;
+use crate::trade_memo::TradeMemo;
+use crate::wallet::Wallet;
+
+/// Смещение amount_in в данных инструкции swap (после 8 байт discriminator)
+pub const SWAP_AMOUNT_IN_OFFSET: usize = 8;
+/// Смещение min_amount_out в данных инструкции swap
+pub const SWAP_MIN_AMOUNT_OUT_OFFSET: usize = 16;
+
+/// Поле u64 (little endian) в данных инструкции, заполняемое при исполнении
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmountSlot {
+    /// Индекс инструкции в списке, переданном в `TxTemplate::build`
+    pub instruction: usize,
+    /// Смещение поля в данных инструкции
+    pub offset: usize,
+}
+
+/// Заготовка транзакции: аккаунты, ALT и compute budget собраны заранее
+///
+/// На горячем пути остаётся подставить суммы, memo сделки и blockhash и
+/// подписать — компиляция сообщения (сортировка ключей, поиск в ALT) уже выполнена.
+#[derive(Debug, Clone)]
+pub struct TxTemplate {
+    payer: Pubkey,
+    message: v0::Message,
+    /// Поля сумм с индексами инструкций уже скомпилированного сообщения
+    slots: Vec<AmountSlot>,
+    /// Индекс инструкции memo (последняя в сообщении)
+    memo_instruction: usize,
+}
+
+impl TxTemplate {
+    /// Сборка шаблона; суммы в инструкциях могут быть любыми (перезаписываются)
+    ///
+    /// В конец добавляется инструкция memo с заглушкой той же длины, что и memo сделки.
+    pub fn build(
+        payer: &Pubkey,
+        config: &TemplatesConfig,
+        instructions: &[Instruction],
+        slots: &[AmountSlot],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<Self> {
+        let mut all_instructions = compute_budget_instructions(config);
+        let shift = all_instructions.len();
+        all_instructions.extend_from_slice(instructions);
+        all_instructions.push(TradeMemo::new(Uuid::nil(), None).instruction());
+        let memo_instruction = all_instructions.len() - 1;
+
+        let slots: Vec<AmountSlot> = slots.iter()
+            .map(|slot| AmountSlot { instruction: slot.instruction + shift, offset: slot.offset })
+            .collect();
+
+        let message = v0::Message::try_compile(payer, &all_instructions, lookup_tables, Hash::default())
+            .context("Не удалось скомпилировать сообщение шаблона")?;
+
+        // Проверка слотов один раз при сборке, а не при каждой сделке
+        for slot in &slots {
+            let data_len = message.instructions
+                .get(slot.instruction)
+                .map(|instruction| instruction.data.len())
+                .with_context(|| format!("Слот ссылается на несуществующую инструкцию #{}", slot.instruction))?;
+            if slot.offset + 8 > data_len {
+                anyhow::bail!(
+                    "Слот #{}+{} выходит за данные инструкции ({} байт)",
+                    slot.instruction, slot.offset, data_len
+                );
+            }
+        }
+
+        Ok(Self { payer: *payer, message, slots, memo_instruction })
+    }
+
+    /// Плательщик, для которого собран шаблон
+    pub fn payer(&self) -> &Pubkey {
+        &self.payer
+    }
+
+    /// Подстановка сумм (по порядку слотов), memo и blockhash, подпись ключом кошелька
+    pub fn instantiate(
+        &self,
+        amounts: &[u64],
+        memo: &TradeMemo,
+        recent_blockhash: Hash,
+        wallet: &Wallet,
+    ) -> Result<VersionedTransaction> {
+        if amounts.len() != self.slots.len() {
+            anyhow::bail!("Шаблону нужно {} сумм, передано {}", self.slots.len(), amounts.len());
+        }
+        if *wallet.pubkey() != self.payer {
+            anyhow::bail!("Шаблон собран для другого ключа (ключ кошелька был заменён)");
+        }
+
+        let mut message = self.message.clone();
+        for (slot, amount) in self.slots.iter().zip(amounts) {
+            message.instructions[slot.instruction].data[slot.offset..slot.offset + 8]
+                .copy_from_slice(&amount.to_le_bytes());
+        }
+        message.instructions[self.memo_instruction].data = memo.as_str().as_bytes().to_vec();
+        message.recent_blockhash = recent_blockhash;
+
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[wallet.keypair()])
+            .context("Не удалось подписать транзакцию из шаблона")
+    }
+}
+
+/// Инструкции compute budget из настроек шаблонов (0 — не добавлять)
+pub fn compute_budget_instructions(config: &TemplatesConfig) -> Vec<Instruction> {
+    let mut instructions = Vec::with_capacity(2);
+    if config.compute_unit_limit > 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(config.compute_unit_limit));
+    }
+    if config.compute_unit_price_micro_lamports > 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(config.compute_unit_price_micro_lamports));
+    }
+    instructions
+}
+
+type TemplateMap = HashMap<(String, String), Arc<TxTemplate>>;
+
+/// Шаблоны свопов DEX по направлению (from_token, to_token)
+#[derive(Clone, Default)]
+pub struct TemplateCache {
+    templates: Arc<RwLock<TemplateMap>>,
+}
+
+impl TemplateCache {
+    pub fn insert(&self, from_token: &str, to_token: &str, template: TxTemplate) {
+        self.templates
+            .write()
+            .expect("мьютекс шаблонов транзакций отравлен")
+            .insert((from_token.to_string(), to_token.to_string()), Arc::new(template));
+    }
+
+    pub fn get(&self, from_token: &str, to_token: &str) -> Option<Arc<TxTemplate>> {
+        self.templates
+            .read()
+            .expect("мьютекс шаблонов транзакций отравлен")
+            .get(&(from_token.to_string(), to_token.to_string()))
+            .cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.read().expect("мьютекс шаблонов транзакций отравлен").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
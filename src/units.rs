@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use crate::config::{OutputRounding, RoundingConfig};
+
+/// Decimals нативного SOL (1 SOL = 10^9 lamports)
+pub const SOL_DECIMALS: u8 = 9;
+
+/// Максимум decimals, который помещается в масштаб Decimal
+pub const MAX_DECIMALS: u8 = 28;
+
+/// Округление при переводе суммы в минимальные единицы токена
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Вниз (отбросить дробную часть минимальной единицы)
+    Down,
+    /// Вверх (до следующей целой минимальной единицы)
+    Up,
+    /// Без округления: дробная часть минимальной единицы — ошибка
+    Exact,
+}
+
+impl Rounding {
+    /// Вход свопа: вниз — своп не тратит больше запланированного
+    pub const INPUT: Rounding = Rounding::Down;
+    /// Минимальный выход свопа: всегда вниз — сделка, давшая ровно расчётный
+    /// выход, не откатывается из-за доли минимальной единицы
+    pub const MIN_OUTPUT: Rounding = Rounding::Down;
+}
+
+/// Политика округления расчётных сумм (`[arbitrage.rounding]`)
+///
+/// Суммы считаются с полной точностью Decimal и округляются один раз — при
+/// записи результата. Знаков для SOL всегда 9 (lamport).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundingPolicy {
+    pub outputs: OutputRounding,
+    pub quote_decimal_places: u32,
+    pub usd_decimal_places: u32,
+}
+
+impl Default for RoundingPolicy {
+    fn default() -> Self {
+        Self::new(&RoundingConfig::default())
+    }
+}
+
+impl RoundingPolicy {
+    pub fn new(config: &RoundingConfig) -> Self {
+        Self {
+            outputs: config.outputs,
+            quote_decimal_places: config.quote_decimal_places,
+            usd_decimal_places: config.usd_decimal_places,
+        }
+    }
+
+    /// Ожидаемый выход или прибыль, округлённые по правилу `outputs`
+    pub fn round_output(&self, value: Decimal, decimal_places: u32) -> Decimal {
+        let strategy = match self.outputs {
+            OutputRounding::Bankers => RoundingStrategy::MidpointNearestEven,
+            OutputRounding::Floor => RoundingStrategy::ToNegativeInfinity,
+        };
+        value.round_dp_with_strategy(decimal_places, strategy)
+    }
+
+    /// Минимальный выход: вниз при любом правиле `outputs`
+    pub fn round_min_output(&self, value: Decimal, decimal_places: u32) -> Decimal {
+        value.round_dp_with_strategy(decimal_places, RoundingStrategy::ToNegativeInfinity)
+    }
+
+    /// Сумма в котируемом токене
+    pub fn quote(&self, value: Decimal) -> Decimal {
+        self.round_output(value, self.quote_decimal_places)
+    }
+
+    /// Сумма в SOL (до lamport)
+    pub fn sol(&self, value: Decimal) -> Decimal {
+        self.round_output(value, u32::from(SOL_DECIMALS))
+    }
+
+    /// Сумма в USD
+    pub fn usd(&self, value: Decimal) -> Decimal {
+        self.round_output(value, self.usd_decimal_places)
+    }
+}
+
+fn check_decimals(decimals: u8) -> Result<()> {
+    if decimals > MAX_DECIMALS {
+        anyhow::bail!("Слишком много decimals: {} (максимум {})", decimals, MAX_DECIMALS);
+    }
+    Ok(())
+}
+
+/// Перевод суммы в минимальные единицы токена (lamports для SOL)
+///
+/// Ошибка, если сумма отрицательная, не помещается в u64 или (для
+/// `Rounding::Exact`) не делится на минимальную единицу без остатка.
+pub fn to_base_units(amount: Decimal, decimals: u8, rounding: Rounding) -> Result<u64> {
+    check_decimals(decimals)?;
+    if amount.is_sign_negative() && !amount.is_zero() {
+        anyhow::bail!("Отрицательная сумма: {}", amount);
+    }
+
+    let mut scaled = amount;
+    for _ in 0..decimals {
+        scaled = scaled.checked_mul(Decimal::TEN)
+            .with_context(|| format!("Сумма {} с {} decimals не помещается в u64", amount, decimals))?;
+    }
+    let rounded = match rounding {
+        Rounding::Down => scaled.floor(),
+        Rounding::Up => scaled.ceil(),
+        Rounding::Exact => {
+            if !scaled.fract().is_zero() {
+                anyhow::bail!("Сумма {} точнее минимальной единицы токена ({} decimals)", amount, decimals);
+            }
+            scaled
+        }
+    };
+    rounded.to_u64()
+        .with_context(|| format!("Сумма {} с {} decimals не помещается в u64", amount, decimals))
+}
+
+/// Перевод минимальных единиц токена в сумму (без потери точности)
+pub fn from_base_units(units: u64, decimals: u8) -> Result<Decimal> {
+    check_decimals(decimals)?;
+    Ok(Decimal::try_from_i128_with_scale(i128::from(units), u32::from(decimals))
+        .context("Сумма не помещается в Decimal")?
+        .normalize())
+}
+
+/// SOL в lamports
+pub fn sol_to_lamports(sol: Decimal, rounding: Rounding) -> Result<u64> {
+    to_base_units(sol, SOL_DECIMALS, rounding)
+}
+
+/// Lamports в SOL
+pub fn lamports_to_sol(lamports: u64) -> Decimal {
+    // u64 всегда помещается в мантиссу Decimal (96 бит), масштаб 9 допустим
+    Decimal::from_i128_with_scale(i128::from(lamports), u32::from(SOL_DECIMALS)).normalize()
+}
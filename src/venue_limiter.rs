@@ -0,0 +1,115 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use crate::config::DexConfig;
+
+/// Статистика очереди исполнения DEX
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VenueQueueStats {
+    /// Лимит одновременных транзакций
+    pub limit: usize,
+    /// Транзакций в работе сейчас
+    pub in_flight: usize,
+    /// Всего выданных разрешений
+    pub acquired: u64,
+    /// Сколько раз пришлось ждать освобождения слота
+    pub queued: u64,
+    /// Суммарное время ожидания в очереди, мс
+    pub total_wait_ms: u64,
+    /// Максимальное время ожидания в очереди, мс
+    pub max_wait_ms: u64,
+}
+
+struct Venue {
+    semaphore: Arc<Semaphore>,
+    limit: usize,
+    stats: VenueQueueStats,
+}
+
+/// Ограничение количества одновременных транзакций на каждый DEX
+///
+/// Некоторые DEX плохо переносят серию транзакций с одного ключа подряд,
+/// поэтому исполнение ждёт свободного слота венью перед отправкой свопа.
+pub struct VenueLimiter {
+    default_limit: usize,
+    limits: HashMap<String, usize>,
+    venues: Mutex<HashMap<String, Venue>>,
+}
+
+/// Разрешение на отправку транзакции (слот освобождается при drop)
+pub struct VenuePermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl VenueLimiter {
+    /// Создание ограничителя из конфигурации DEX
+    pub fn new(config: &DexConfig) -> Self {
+        Self {
+            default_limit: config.max_in_flight_per_dex.max(1),
+            limits: config.max_in_flight.clone(),
+            venues: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Ожидание свободного слота для DEX
+    pub async fn acquire(&self, venue: &str) -> Result<VenuePermit> {
+        let semaphore = self.semaphore(venue);
+        let started = Instant::now();
+
+        // Быстрый путь без ожидания
+        let (permit, waited) = match semaphore.clone().try_acquire_owned() {
+            Ok(permit) => (permit, false),
+            Err(_) => {
+                log::debug!("{}: все слоты исполнения заняты, ожидание в очереди", venue);
+                let permit = semaphore.acquire_owned()
+                    .await
+                    .map_err(|_| anyhow::anyhow!("Очередь исполнения {} закрыта", venue))?;
+                (permit, true)
+            }
+        };
+
+        let wait_ms = started.elapsed().as_millis() as u64;
+        let mut venues = self.venues.lock().expect("мьютекс очереди DEX отравлен");
+        if let Some(entry) = venues.get_mut(venue) {
+            entry.stats.acquired += 1;
+            if waited {
+                entry.stats.queued += 1;
+                entry.stats.total_wait_ms += wait_ms;
+                entry.stats.max_wait_ms = entry.stats.max_wait_ms.max(wait_ms);
+            }
+        }
+
+        Ok(VenuePermit { _permit: permit })
+    }
+
+    /// Статистика очередей по DEX
+    pub fn stats(&self) -> HashMap<String, VenueQueueStats> {
+        let venues = self.venues.lock().expect("мьютекс очереди DEX отравлен");
+        venues.iter()
+            .map(|(name, venue)| {
+                let mut stats = venue.stats.clone();
+                stats.limit = venue.limit;
+                stats.in_flight = venue.limit.saturating_sub(venue.semaphore.available_permits());
+                (name.clone(), stats)
+            })
+            .collect()
+    }
+
+    fn semaphore(&self, venue: &str) -> Arc<Semaphore> {
+        let mut venues = self.venues.lock().expect("мьютекс очереди DEX отравлен");
+        venues.entry(venue.to_string())
+            .or_insert_with(|| {
+                let limit = self.limits.get(venue).copied().unwrap_or(self.default_limit).max(1);
+                Venue {
+                    semaphore: Arc::new(Semaphore::new(limit)),
+                    limit,
+                    stats: VenueQueueStats::default(),
+                }
+            })
+            .semaphore
+            .clone()
+    }
+}
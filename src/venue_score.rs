@@ -0,0 +1,157 @@
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::arbitrage::ArbitrageOpportunity;
+use crate::config::VenueScoringConfig;
+
+/// Статистика исполнения ног сделок на DEX
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VenueStats {
+    /// Ноги, отправленные на DEX
+    pub attempts: u64,
+    /// Исполненные ноги
+    pub landed: u64,
+    slippage_samples: u64,
+    slippage_percent_sum: Decimal,
+    latency_samples: u64,
+    latency_ms_sum: u64,
+}
+
+impl VenueStats {
+    /// Доля исполненных ног со сглаживанием Лапласа (0.5 для DEX без истории)
+    pub fn landing_rate(&self) -> Decimal {
+        Decimal::from(self.landed + 1) / Decimal::from(self.attempts + 2)
+    }
+
+    /// Среднее фактическое проскальзывание в процентах (0 без замеров)
+    pub fn avg_slippage_percent(&self) -> Decimal {
+        if self.slippage_samples == 0 {
+            return Decimal::ZERO;
+        }
+        self.slippage_percent_sum / Decimal::from(self.slippage_samples)
+    }
+
+    /// Среднее время подтверждения ноги в миллисекундах (0 без замеров)
+    pub fn avg_latency_ms(&self) -> u64 {
+        self.latency_ms_sum.checked_div(self.latency_samples).unwrap_or(0)
+    }
+}
+
+/// История исполнения по DEX (общая для задач исполнения)
+#[derive(Debug, Clone, Default)]
+pub struct VenueHistory {
+    venues: Arc<Mutex<HashMap<String, VenueStats>>>,
+}
+
+impl VenueHistory {
+    fn update(&self, venue: &str, apply: impl FnOnce(&mut VenueStats)) {
+        let mut venues = self.venues.lock().expect("мьютекс истории DEX отравлен");
+        apply(venues.entry(venue.to_string()).or_default());
+    }
+
+    /// Учёт отправленной ноги и её исполнения
+    pub fn record_leg(&self, venue: &str, landed: bool) {
+        self.update(venue, |stats| {
+            stats.attempts += 1;
+            if landed {
+                stats.landed += 1;
+            }
+        });
+    }
+
+    /// Учёт времени подтверждения ноги
+    pub fn record_latency(&self, venue: &str, latency: Duration) {
+        self.update(venue, |stats| {
+            stats.latency_samples += 1;
+            stats.latency_ms_sum = stats.latency_ms_sum
+                .saturating_add(latency.as_millis().try_into().unwrap_or(u64::MAX));
+        });
+    }
+
+    /// Учёт фактического проскальзывания ноги в процентах
+    #[allow(dead_code)]
+    pub fn record_slippage(&self, venue: &str, slippage_percent: Decimal) {
+        self.update(venue, |stats| {
+            stats.slippage_samples += 1;
+            stats.slippage_percent_sum += slippage_percent.abs();
+        });
+    }
+
+    /// Статистика DEX (нулевая, если ног не было)
+    pub fn venue(&self, venue: &str) -> VenueStats {
+        self.venues.lock().expect("мьютекс истории DEX отравлен")
+            .get(venue)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Модель оценки DEX по истории исполнения и комиссии (веса из `[venue_scoring]`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VenueScorer {
+    landing_weight: Decimal,
+    slippage_weight: Decimal,
+    fee_weight: Decimal,
+    latency_weight: Decimal,
+}
+
+impl VenueScorer {
+    pub fn new(config: &VenueScoringConfig) -> Self {
+        Self {
+            landing_weight: VenueScoringConfig::weight(config.landing_weight),
+            slippage_weight: VenueScoringConfig::weight(config.slippage_weight),
+            fee_weight: VenueScoringConfig::weight(config.fee_weight),
+            latency_weight: VenueScoringConfig::weight(config.latency_weight),
+        }
+    }
+
+    /// Оценка DEX: чем больше, тем предпочтительнее
+    pub fn score(&self, stats: &VenueStats, fee_percent: Decimal) -> Decimal {
+        let latency_sec = Decimal::from(stats.avg_latency_ms()) / Decimal::from(1000);
+        self.landing_weight * stats.landing_rate()
+            - self.slippage_weight * stats.avg_slippage_percent()
+            - self.fee_weight * fee_percent
+            - self.latency_weight * latency_sec
+    }
+}
+
+/// Упорядочивание возможностей каждой пары по оценке DEX обеих ног
+///
+/// Возможности одной пары занимают прежние места в списке, но среди них
+/// первой идёт связка DEX с наибольшей суммарной оценкой (при равенстве
+/// сохраняется порядок ранжирования). Порядок пар между собой не меняется.
+/// `fee_percent` — комиссия пула пары на DEX.
+pub fn prefer_venues<F>(
+    opportunities: &mut [ArbitrageOpportunity],
+    scorer: &VenueScorer,
+    history: &VenueHistory,
+    fee_percent: F,
+) where
+    F: Fn(&str, &ArbitrageOpportunity) -> Decimal,
+{
+    let score = |opportunity: &ArbitrageOpportunity| {
+        [&opportunity.from_dex, &opportunity.to_dex].into_iter()
+            .map(|venue| scorer.score(&history.venue(venue), fee_percent(venue, opportunity)))
+            .sum::<Decimal>()
+    };
+
+    let mut pairs: HashMap<(String, String), Vec<usize>> = HashMap::new();
+    for (index, opportunity) in opportunities.iter().enumerate() {
+        if opportunity.is_route() {
+            continue;
+        }
+        pairs.entry((opportunity.base_token.clone(), opportunity.quote_token.clone()))
+            .or_default()
+            .push(index);
+    }
+    for positions in pairs.into_values().filter(|positions| positions.len() > 1) {
+        let mut group: Vec<(Decimal, ArbitrageOpportunity)> = positions.iter()
+            .map(|&index| (score(&opportunities[index]), opportunities[index].clone()))
+            .collect();
+        group.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        for (&index, (_, opportunity)) in positions.iter().zip(group) {
+            opportunities[index] = opportunity;
+        }
+    }
+}
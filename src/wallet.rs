@@ -1,22 +1,66 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
 use solana_sdk::{
+    instruction::Instruction,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
 };
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use crate::config::Config;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedRwLockReadGuard, RwLock};
+use crate::config::{Config, NetworkConfig};
+use crate::rpc::create_rpc_client;
+use crate::units::{from_base_units, lamports_to_sol};
+
+/// Токен-аккаунт кошелька с балансом
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenAccountBalance {
+    /// Адрес токен-аккаунта
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    /// Баланс в минимальных единицах токена
+    pub amount: u64,
+    pub decimals: u8,
+    /// Ассоциированный токен-аккаунт (ATA) кошелька для этого mint
+    pub associated: bool,
+    /// Баланс SOL аккаунта (рента), lamports
+    pub lamports: u64,
+}
+
+impl TokenAccountBalance {
+    /// Баланс в единицах токена
+    pub fn ui_amount(&self) -> Result<Decimal> {
+        from_base_units(self.amount, self.decimals)
+            .with_context(|| format!("Некорректный баланс токена {}", self.mint))
+    }
+}
 
 /// Управление кошельком Solana
 pub struct Wallet {
     keypair: Keypair,
     pubkey: Pubkey,
+    /// ATA, существование которых уже подтверждено RPC (не проверяются перед каждым свопом)
+    known_token_accounts: Mutex<HashSet<Pubkey>>,
 }
 
 impl Wallet {
     /// Создание нового экземпляра кошелька из конфигурации
     pub fn new(config: &Config) -> Result<Self> {
-        let key_path = &config.wallet.keypair_path;
+        Self::from_file(&config.wallet.keypair_path)
+    }
+
+    /// Загрузка кошелька из файла ключа
+    pub fn from_file(key_path: &Path) -> Result<Self> {
 
         // Проверка существования файла
         if !key_path.exists() {
@@ -64,7 +108,24 @@ impl Wallet {
 
         log::info!("Кошелёк загружен: {}", pubkey);
 
-        Ok(Self { keypair, pubkey })
+        Ok(Self::with_keypair(keypair))
+    }
+
+    /// Кошелёк со случайным ключом, существующим только в памяти процесса
+    ///
+    /// Для процесса поиска (`mode = "scan"`), которому ключ не выдаётся:
+    /// движок в этом режиме ничего не подписывает.
+    pub fn ephemeral() -> Self {
+        Self::with_keypair(Keypair::new())
+    }
+
+    fn with_keypair(keypair: Keypair) -> Self {
+        let pubkey = keypair.pubkey();
+        Self {
+            keypair,
+            pubkey,
+            known_token_accounts: Mutex::new(HashSet::new()),
+        }
     }
 
     /// Получение публичного ключа
@@ -78,19 +139,240 @@ impl Wallet {
     }
 
     /// Получение баланса кошелька
-    pub async fn get_balance(&self, rpc_url: &str) -> Result<u64> {
-        use solana_client::rpc_client::RpcClient;
-        use solana_sdk::commitment_config::CommitmentConfig;
-
-        let client = RpcClient::new_with_commitment(
-            rpc_url.to_string(),
-            CommitmentConfig::confirmed(),
-        );
+    pub async fn get_balance(&self, network: &NetworkConfig) -> Result<u64> {
+        let client = create_rpc_client(network)?;
 
         let balance = client.get_balance(&self.pubkey)
             .context("Не удалось получить баланс")?;
 
         Ok(balance)
     }
+
+    /// Баланс токена по mint в единицах токена (для SOL — нативный баланс)
+    pub async fn token_balance(&self, network: &NetworkConfig, mint: &Pubkey) -> Result<Decimal> {
+        if *mint == spl_token::native_mint::id() {
+            let lamports = self.get_balance(network).await?;
+            return Ok(lamports_to_sol(lamports));
+        }
+        let mut balances = self.get_token_balances(network).await?;
+        Ok(balances.remove(&mint.to_string()).unwrap_or(Decimal::ZERO))
+    }
+
+    /// Получение балансов SPL токенов кошелька (mint -> количество в единицах токена)
+    pub async fn get_token_balances(&self, network: &NetworkConfig) -> Result<BTreeMap<String, Decimal>> {
+        let mut balances = BTreeMap::new();
+        for account in self.get_token_accounts(network).await? {
+            *balances.entry(account.mint.to_string()).or_insert(Decimal::ZERO) += account.ui_amount()?;
+        }
+        Ok(balances)
+    }
+
+    /// Токен-аккаунты SPL Token кошелька с балансами (ATA и прочие)
+    pub async fn get_token_accounts(&self, network: &NetworkConfig) -> Result<Vec<TokenAccountBalance>> {
+        let client = create_rpc_client(network)?;
+        let owner = self.pubkey;
+
+        let accounts = tokio::task::spawn_blocking(move || {
+            client.get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(spl_token::id()))
+                .context("Не удалось получить токен-аккаунты")
+        })
+        .await
+        .context("Задача получения токен-аккаунтов завершилась с ошибкой")??;
+
+        let mut balances = Vec::with_capacity(accounts.len());
+        for keyed in accounts {
+            // Ответ в формате jsonParsed: parsed.info.{mint, tokenAmount.{amount, decimals}}
+            let data = serde_json::to_value(&keyed.account.data)
+                .context("Не удалось разобрать данные токен-аккаунта")?;
+            let info = &data["parsed"]["info"];
+            let (Some(mint), Some(amount), Some(decimals)) = (
+                info["mint"].as_str(),
+                info["tokenAmount"]["amount"].as_str(),
+                info["tokenAmount"]["decimals"].as_u64(),
+            ) else {
+                log::debug!("Токен-аккаунт {} в неожиданном формате, пропускаем", keyed.pubkey);
+                continue;
+            };
+            let address = Pubkey::from_str(&keyed.pubkey)
+                .with_context(|| format!("Некорректный адрес токен-аккаунта: {}", keyed.pubkey))?;
+            let mint = Pubkey::from_str(mint)
+                .with_context(|| format!("Некорректный mint токен-аккаунта {}: {}", address, mint))?;
+            let amount = amount.parse()
+                .with_context(|| format!("Некорректный баланс токена {}: {}", mint, amount))?;
+            let decimals = u8::try_from(decimals)
+                .with_context(|| format!("Некорректные decimals токена {}: {}", mint, decimals))?;
+            balances.push(TokenAccountBalance {
+                address,
+                mint,
+                amount,
+                decimals,
+                associated: address == self.associated_token_address(&mint),
+                lamports: keyed.account.lamports,
+            });
+        }
+
+        Ok(balances)
+    }
+
+    /// Адрес ассоциированного токен-аккаунта (ATA) кошелька для mint
+    pub fn associated_token_address(&self, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(&self.pubkey, mint)
+    }
+
+    /// Инструкция создания ATA кошелька для mint (идемпотентная: существующий аккаунт не ошибка)
+    pub fn create_token_account_instruction(&self, mint: &Pubkey) -> Instruction {
+        create_associated_token_account_idempotent(&self.pubkey, &self.pubkey, mint, &spl_token::id())
+    }
+
+    /// Забыть проверенный токен-аккаунт (например, закрываемый после свопа)
+    pub fn forget_token_account(&self, address: &Pubkey) {
+        self.known_token_accounts.lock().expect("мьютекс известных ATA отравлен").remove(address);
+    }
+
+    /// Инструкции создания недостающих ATA для mint свопа
+    ///
+    /// Существующие ATA запоминаются и больше не запрашиваются. Для недостающих
+    /// возвращаются идемпотентные инструкции создания: их ставят в транзакцию
+    /// перед свопом, и повтор транзакции не падает на уже созданном аккаунте.
+    pub fn missing_token_account_instructions(&self, rpc_client: &RpcClient, mints: &[Pubkey]) -> Result<Vec<Instruction>> {
+        let mut unknown: Vec<(Pubkey, Pubkey)> = Vec::new();
+        {
+            let known = self.known_token_accounts.lock().expect("мьютекс известных ATA отравлен");
+            for mint in mints {
+                let address = self.associated_token_address(mint);
+                if !known.contains(&address) && !unknown.iter().any(|(_, known)| *known == address) {
+                    unknown.push((*mint, address));
+                }
+            }
+        }
+        if unknown.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let addresses: Vec<Pubkey> = unknown.iter().map(|(_, address)| *address).collect();
+        let accounts = rpc_client.get_multiple_accounts(&addresses)
+            .context("Не удалось проверить токен-аккаунты кошелька")?;
+
+        let mut instructions = Vec::new();
+        let mut known = self.known_token_accounts.lock().expect("мьютекс известных ATA отравлен");
+        for ((mint, address), account) in unknown.into_iter().zip(accounts) {
+            if account.is_some() {
+                known.insert(address);
+            } else {
+                log::info!("ATA {} для mint {} не найден, будет создан перед свопом", address, mint);
+                instructions.push(self.create_token_account_instruction(&mint));
+            }
+        }
+        Ok(instructions)
+    }
+}
+
+/// Текущий ключ кошелька с возможностью замены
+struct WalletSlot {
+    wallet: Arc<Wallet>,
+    keypair_path: PathBuf,
+    rotated_at: Option<DateTime<Utc>>,
+}
+
+/// Результат замены ключа (только публичные адреса)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRotation {
+    pub old_pubkey: Pubkey,
+    pub new_pubkey: Pubkey,
+    pub rotated_at: DateTime<Utc>,
+}
+
+impl KeyRotation {
+    /// Изменился ли ключ (файл мог содержать тот же ключ)
+    pub fn changed(&self) -> bool {
+        self.old_pubkey != self.new_pubkey
+    }
+}
+
+/// Право подписывать транзакции текущим ключом
+///
+/// Пока аренда жива, ключ не будет заменён: замена дожидается завершения
+/// всех транзакций, подписанных старым ключом.
+pub struct WalletLease(OwnedRwLockReadGuard<WalletSlot>);
+
+impl Deref for WalletLease {
+    type Target = Wallet;
+
+    fn deref(&self) -> &Wallet {
+        &self.0.wallet
+    }
+}
+
+/// Кошелёк с заменой ключа без перезапуска (плановая ротация ключей)
+///
+/// Исполнение берёт `lease()` на всё время сделки. `rotate()` загружает новый
+/// ключ, ждёт завершения сделок со старым ключом и переключает подпись разом;
+/// новые сделки на время ожидания становятся в очередь за заменой.
+#[derive(Clone)]
+pub struct HotWallet {
+    slot: Arc<RwLock<WalletSlot>>,
 }
 
+impl HotWallet {
+    pub fn new(wallet: Arc<Wallet>, keypair_path: PathBuf) -> Self {
+        Self {
+            slot: Arc::new(RwLock::new(WalletSlot {
+                wallet,
+                keypair_path,
+                rotated_at: None,
+            })),
+        }
+    }
+
+    /// Текущий кошелёк (для чтения балансов; подписывать транзакции — через lease)
+    pub async fn current(&self) -> Arc<Wallet> {
+        self.slot.read().await.wallet.clone()
+    }
+
+    /// Время последней замены ключа
+    pub async fn rotated_at(&self) -> Option<DateTime<Utc>> {
+        self.slot.read().await.rotated_at
+    }
+
+    /// Аренда текущего ключа для подписи и отправки транзакций
+    pub async fn lease(&self) -> WalletLease {
+        WalletLease(self.slot.clone().read_owned().await)
+    }
+
+    /// Замена ключа
+    ///
+    /// Ключ читается из keypair_path (или из текущего пути, если он не задан)
+    /// до ожидания, поэтому ошибка чтения не задерживает исполнение. Если сделки
+    /// со старым ключом не завершились за drain_timeout, ключ не меняется.
+    pub async fn rotate(&self, keypair_path: Option<PathBuf>, drain_timeout: Duration) -> Result<KeyRotation> {
+        let keypair_path = match keypair_path {
+            Some(path) => path,
+            None => self.slot.read().await.keypair_path.clone(),
+        };
+        let load_path = keypair_path.clone();
+        let new_wallet = tokio::task::spawn_blocking(move || Wallet::from_file(&load_path))
+            .await
+            .context("Задача загрузки ключа завершилась с ошибкой")??;
+
+        let mut slot = tokio::time::timeout(drain_timeout, self.slot.write())
+            .await
+            .context("Транзакции, подписанные текущим ключом, не завершились вовремя — ключ не заменён")?;
+
+        let rotation = KeyRotation {
+            old_pubkey: *slot.wallet.pubkey(),
+            new_pubkey: *new_wallet.pubkey(),
+            rotated_at: Utc::now(),
+        };
+        slot.wallet = Arc::new(new_wallet);
+        slot.keypair_path = keypair_path;
+        slot.rotated_at = Some(rotation.rotated_at);
+        drop(slot);
+
+        if rotation.changed() {
+            log::warn!("🔑 Ключ кошелька заменён: {} -> {}", rotation.old_pubkey, rotation.new_pubkey);
+        } else {
+            log::info!("🔑 Ключ кошелька перечитан, адрес не изменился: {}", rotation.new_pubkey);
+        }
+        Ok(rotation)
+    }
+}
@@ -1,24 +1,62 @@
 use axum::{
-    extract::Request,
-    http::{header::AUTHORIZATION, StatusCode},
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
     middleware::Next,
     response::Response,
 };
 use base64::{engine::general_purpose, Engine as _};
 use std::env;
 
-/// Проверка Basic Authentication
-pub async fn auth_middleware(mut request: Request, next: Next) -> Result<Response, StatusCode> {
+use crate::api_tokens::Scope;
+use crate::web::error::ApiError;
+use crate::web::state::WebState;
+
+/// Автор запроса для журналов: имя пользователя панели или `token:<название>`
+///
+/// Добавляется в расширения запроса после успешной аутентификации.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor(pub String);
+
+/// Проверка аутентификации: Basic (администратор) или Bearer (токен API)
+///
+/// Токен API пропускает только запросы, на которые у него есть право
+/// (`Scope::required`); остальные отклоняются с 403.
+pub async fn auth_middleware(State(state): State<WebState>, mut request: Request, next: Next) -> Result<Response, ApiError> {
+    let invalid = || ApiError::unauthorized("Неверные учётные данные");
+
     // Получение заголовка Authorization
     let auth_header = request
         .headers()
         .get(AUTHORIZATION)
         .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .ok_or_else(|| ApiError::unauthorized("Требуется заголовок Authorization (Basic или Bearer)"))?;
+
+    if let Some(secret) = auth_header.strip_prefix("Bearer ") {
+        let token = state.api_tokens.authenticate(secret.trim(), chrono::Utc::now())
+            .await
+            .map_err(|e| {
+                log::error!("Ошибка проверки токена API: {:#}", e);
+                ApiError::unavailable("Не удалось проверить токен API")
+            })?
+            .ok_or_else(|| ApiError::unauthorized("Токен API неизвестен, истёк или отозван"))?;
+
+        let (method, path) = (request.method().as_str(), request.uri().path());
+        return match Scope::required(method, path) {
+            Some(scope) if token.allows(scope) => {
+                log::debug!("Запрос {} {} по токену API {}", method, path, token.name);
+                request.extensions_mut().insert(Actor(format!("token:{}", token.name)));
+                Ok(next.run(request).await)
+            }
+            _ => Err(ApiError::forbidden(format!(
+                "Токену API {} не выдано право на {} {}",
+                token.name, method, path
+            ))),
+        };
+    }
 
     // Проверка формата "Basic <credentials>"
     if !auth_header.starts_with("Basic ") {
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(ApiError::unauthorized("Поддерживается Basic аутентификация или токен API (Bearer)"));
     }
 
     let credentials = auth_header.trim_start_matches("Basic ");
@@ -26,15 +64,15 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Result<Respons
     // Декодирование base64
     let decoded = general_purpose::STANDARD
         .decode(credentials)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(|_| invalid())?;
     
     let credentials_str = String::from_utf8(decoded)
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(|_| invalid())?;
 
     // Разделение username:password
     let parts: Vec<&str> = credentials_str.splitn(2, ':').collect();
     if parts.len() != 2 {
-        return Err(StatusCode::UNAUTHORIZED);
+        return Err(invalid());
     }
 
     let username = parts[0];
@@ -45,13 +83,14 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Result<Respons
         .unwrap_or_else(|_| "admin".to_string());
     let expected_password = env::var("WEB_PASSWORD")
         .ok()
-        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+        .ok_or_else(|| ApiError::internal("WEB_PASSWORD не задан"))?;
 
     // Проверка учётных данных
     if username == expected_username && password == expected_password {
+        request.extensions_mut().insert(Actor(username.to_string()));
         Ok(next.run(request).await)
     } else {
-        Err(StatusCode::UNAUTHORIZED)
+        Err(invalid())
     }
 }
 
@@ -0,0 +1,148 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+/// Тип содержимого ответа с ошибкой (RFC 9457)
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Через сколько секунд повторять запрос, если ошибка временная (заголовок Retry-After)
+pub const RETRY_AFTER_SEC: u64 = 1;
+
+/// Код ошибки API: стабильная причина, по которой клиент выбирает реакцию
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Некорректные параметры или тело запроса
+    InvalidRequest,
+    /// Нет учётных данных или они неверны
+    Unauthorized,
+    /// Токену API не выдано право на этот запрос
+    Forbidden,
+    /// Маршрут или объект не найден
+    NotFound,
+    /// Метод не поддерживается маршрутом
+    MethodNotAllowed,
+    /// Действие невозможно в текущем состоянии бота
+    Conflict,
+    /// Запрос корректен, но выполнить его не удалось (например, оценить сделку)
+    Unprocessable,
+    /// Движок арбитража занят торговым циклом дольше web.engine_busy_timeout_ms
+    EngineBusy,
+    /// Временно недоступен внешний источник (RPC, хранилище)
+    Unavailable,
+    /// Внутренняя ошибка сервера
+    Internal,
+}
+
+impl ErrorCode {
+    /// HTTP статус ответа
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::InvalidRequest => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            ErrorCode::Conflict => StatusCode::CONFLICT,
+            ErrorCode::Unprocessable => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCode::EngineBusy | ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Повтор того же запроса может пройти
+    pub fn retryable(self) -> bool {
+        matches!(self, ErrorCode::EngineBusy | ErrorCode::Unavailable)
+    }
+
+    /// Код для ответа с ошибкой, сформированного без ApiError (axum, tower-http)
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+            StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+            StatusCode::NOT_FOUND => ErrorCode::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => ErrorCode::MethodNotAllowed,
+            StatusCode::CONFLICT => ErrorCode::Conflict,
+            StatusCode::UNPROCESSABLE_ENTITY => ErrorCode::Unprocessable,
+            StatusCode::SERVICE_UNAVAILABLE => ErrorCode::Unavailable,
+            status if status.is_client_error() => ErrorCode::InvalidRequest,
+            _ => ErrorCode::Internal,
+        }
+    }
+}
+
+/// Тело ответа с ошибкой (application/problem+json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Problem {
+    /// Название HTTP статуса
+    pub title: String,
+    pub status: u16,
+    pub code: ErrorCode,
+    /// Причина для человека
+    pub message: String,
+    pub request_id: String,
+    /// Повтор того же запроса может пройти
+    pub retryable: bool,
+}
+
+/// Ошибка обработчика API
+///
+/// Ответ получает только статус; тело problem+json с request_id дописывает
+/// `request_id_middleware`.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unauthorized, message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unprocessable, message)
+    }
+
+    pub fn engine_busy() -> Self {
+        Self::new(ErrorCode::EngineBusy, "Движок арбитража занят торговым циклом, повторите запрос")
+    }
+
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unavailable, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let mut response = self.code.status().into_response();
+        response.extensions_mut().insert(self);
+        response
+    }
+}
@@ -1,38 +1,122 @@
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::Json,
+    extract::{Extension, Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json},
 };
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
+use uuid::Uuid;
 
+use crate::api_tokens::{ApiToken, TokenRequest};
+use crate::arbitrage::{LegStamps, SandboxTrade};
+use crate::config::{Config, ExecutionMode, LegGate, MinProfitAbsolute, RoundingConfig};
+use crate::config_history::{ChangeSource, ConfigChange, FieldChange};
+use crate::event_log::{SafetyEvent, EVENT_LOG_CAPACITY};
+use crate::fees::{net_profit_sol, FeeTotals};
+use crate::fiat::UsdRate;
+use crate::history::{capital_usage, trade_activity, CapitalUsage, TradeAnnotation};
+use crate::jobs::JobStatus;
+use crate::lifecycle::{TradingState, TradingStatus};
+use crate::log_filter;
+use crate::scan_stats::ScanMetrics;
+use crate::schedule::ScheduleOverride;
+use crate::self_test::SelfTestReport;
+use crate::signal::Signal;
+use crate::supervisor::TaskHealth;
+use crate::units::{lamports_to_sol, RoundingPolicy};
+use crate::venue_limiter::VenueQueueStats;
+use crate::web::auth::Actor;
+use crate::web::error::ApiError;
 use crate::web::state::{BotStatus, Metrics, TradeRecord, WebState};
+use crate::web::websocket::WsEnvelope;
 
 /// Ответ статуса бота
 #[derive(Serialize)]
 pub struct StatusResponse {
     pub status: String,
+    pub mode: ExecutionMode,
     pub simulation_mode: bool,
     pub uptime_seconds: u64,
     pub version: String,
+    pub clock_skew_ms: i64,
+    /// Перегрузка сети (порог прибыли повышен)
+    pub congested: bool,
+    /// Состояние торгового цикла и последняя ошибка
+    pub trading: TradingStatus,
+    /// Здоровье фоновых задач (перезапуски, последняя ошибка)
+    pub tasks: Vec<TaskHealth>,
+    /// Регулярные задачи планировщика ([jobs]): расписание, последний и следующий запуск
+    pub jobs: Vec<JobStatus>,
+    /// Самопроверка адаптеров DEX при запуске (null — не выполнялась)
+    pub self_test: Option<SelfTestReport>,
+}
+
+/// Публичная сводка статуса (без балансов, кошелька и деталей задач)
+#[derive(Serialize)]
+pub struct PublicStatusResponse {
+    pub status: String,
+    pub simulation_mode: bool,
+    pub uptime_seconds: u64,
+    /// Сделок с начала суток (UTC)
+    pub trades_today: usize,
+    /// Время последней сделки (RFC 3339)
+    pub last_trade_at: Option<String>,
 }
 
 /// Ответ баланса кошелька
 #[derive(Serialize)]
 pub struct BalanceResponse {
     pub sol_balance: String,
-    pub usd_equivalent: String,
+    /// Баланс в USD (null, если курс SOL неизвестен)
+    pub usd_equivalent: Option<String>,
+    /// Курс SOL/USD, по которому посчитан usd_equivalent
+    pub usd_rate: Option<UsdRateItem>,
     pub min_balance_sol: String,
 }
 
+/// Курс токена в USD для API
+#[derive(Serialize)]
+pub struct UsdRateItem {
+    pub symbol: String,
+    pub price: String,
+    /// Источник курса ("pyth", "coingecko", "binance" или "peg")
+    pub source: String,
+    /// Источники, подтвердившие цену
+    pub confirmed_by: Vec<String>,
+    pub published_at: String,
+    /// Возраст цены в секундах
+    pub age_sec: i64,
+}
+
+/// Ответ курсов токенов в USD
+#[derive(Serialize)]
+pub struct FiatRatesResponse {
+    pub rates: Vec<UsdRateItem>,
+}
+
+fn usd_rate_item(symbol: &str, rate: UsdRate, now: chrono::DateTime<chrono::Utc>) -> UsdRateItem {
+    UsdRateItem {
+        symbol: symbol.to_string(),
+        price: rate.price.to_string(),
+        age_sec: rate.age_sec(now),
+        source: rate.source,
+        confirmed_by: rate.confirmed_by,
+        published_at: rate.published_at.to_rfc3339(),
+    }
+}
+
 /// Ответ арбитражных возможностей
 #[derive(Serialize)]
 pub struct OpportunitiesResponse {
     pub opportunities: Vec<OpportunityItem>,
     pub count: usize,
     pub timestamp: String,
+    /// Номер снимка цен, по которому найдены возможности
+    pub snapshot_seq: u64,
+    /// Время снимка цен
+    pub prices_at: String,
 }
 
 /// Арбитражная возможность
@@ -48,6 +132,76 @@ pub struct OpportunityItem {
     pub profit_percent_after_fees: String,
     pub trade_amount: String,
     pub estimated_fees: String,
+    /// Влияние объёма на цену покупки и продажи, %
+    pub buy_price_impact_percent: String,
+    pub sell_price_impact_percent: String,
+    /// Время получения и слот цен ног
+    pub price_stamps: LegStamps,
+    /// Шаги маршрута через несколько пар (нет у покупки и продажи одной пары)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub route: Vec<RouteHopItem>,
+}
+
+/// Шаг маршрута через несколько пар
+#[derive(Serialize)]
+pub struct RouteHopItem {
+    pub dex: String,
+    pub from_token: String,
+    pub to_token: String,
+    pub rate: String,
+    pub fee_percent: String,
+}
+
+/// Запрос оценки сделки в песочнице
+#[derive(Deserialize)]
+pub struct SandboxTradeRequest {
+    /// Пара в формате "BASE/QUOTE"
+    pub pair: String,
+    pub buy_dex: String,
+    pub sell_dex: String,
+    /// Объём в базовом токене
+    pub amount: String,
+}
+
+/// Проверка перед исполнением
+#[derive(Serialize)]
+pub struct RiskCheckItem {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Ответ оценки сделки в песочнице
+#[derive(Serialize)]
+pub struct SandboxTradeResponse {
+    pub pair: String,
+    pub buy_dex: String,
+    pub sell_dex: String,
+    pub amount: String,
+    pub buy_price: String,
+    pub sell_price: String,
+    pub buy_fee_percent: String,
+    pub sell_fee_percent: String,
+    pub profit_percent: String,
+    pub profit_percent_after_fees: String,
+    pub min_profit_percent: String,
+    pub estimated_fees: String,
+    pub buy_price_impact_percent: String,
+    pub sell_price_impact_percent: String,
+    pub buy_slippage_percent: String,
+    pub sell_slippage_percent: String,
+    /// Ожидаемый выход ног по состоянию пулов (null — котировку получить не удалось)
+    pub buy_expected_output: Option<String>,
+    pub sell_expected_output: Option<String>,
+    pub buy_min_output: String,
+    pub sell_min_output: String,
+    pub profit_quote: String,
+    pub profit_sol: Option<String>,
+    pub profit_usd: Option<String>,
+    pub checks: Vec<RiskCheckItem>,
+    /// Прошла бы сделка все проверки
+    pub would_execute: bool,
+    pub simulation_mode: bool,
 }
 
 /// Параметры запроса для opportunities
@@ -73,6 +227,8 @@ pub struct HistoryQuery {
     pub offset: Option<usize>,
     pub from_dex: Option<String>,
     pub status: Option<String>,
+    /// Только сделки с указанным тегом
+    pub tag: Option<String>,
 }
 
 /// Запись о сделке для API
@@ -86,9 +242,35 @@ pub struct TradeItem {
     pub quote_token: String,
     pub amount: String,
     pub profit_percent: String,
-    pub profit_sol: String,
+    /// Прибыль в котируемом токене
+    pub profit_quote: String,
+    /// Прибыль в SOL (null, если нет курса)
+    pub profit_sol: Option<String>,
+    /// Прибыль в USD (null, если курс неизвестен)
+    pub profit_usd: Option<String>,
     pub status: String,
     pub tx_signature: Option<String>,
+    /// Причина неудачи (для статуса failed)
+    pub failure_reason: Option<String>,
+    /// Идентификатор прогона движка
+    pub trace_id: Option<String>,
+    /// Заметка оператора
+    pub notes: Option<String>,
+    /// Теги оператора
+    pub tags: Vec<String>,
+    /// Условие перехода к продаже (null для атомарных сделок)
+    pub leg_gate: Option<LegGate>,
+    /// Логи симуляции транзакций перед отправкой
+    pub simulation_logs: Vec<String>,
+}
+
+/// Запрос изменения заметки и тегов сделки (отсутствующие поля не меняются)
+#[derive(Deserialize)]
+pub struct TradeAnnotationRequest {
+    /// Заметка; пустая строка удаляет заметку
+    pub notes: Option<String>,
+    /// Теги (заменяют прежние)
+    pub tags: Option<Vec<String>>,
 }
 
 /// Ответ метрик
@@ -104,6 +286,42 @@ pub struct MetricsResponse {
     pub rpc_errors_count: u64,
     pub average_response_time_ms: f64,
     pub last_trade_timestamp: Option<String>,
+    /// Очереди исполнения по DEX (лимит, в работе, время ожидания)
+    pub venue_queues: HashMap<String, VenueQueueStats>,
+    /// Расходы кошелька на транзакции, в lamports
+    pub fees: FeeTotals,
+    /// Все расходы на транзакции, в SOL
+    pub total_fees_sol: String,
+    /// Прибыль успешных сделок в SOL за вычетом расходов на транзакции
+    pub net_profit_sol: String,
+    /// Прогоны поиска: частота, длительность, судьба найденных возможностей
+    pub scan: ScanMetrics,
+    /// Использование капитала: объём сделок, удержание между ногами, оборачиваемость
+    pub capital: CapitalUsageResponse,
+}
+
+/// Использование капитала успешными сделками
+#[derive(Serialize)]
+pub struct CapitalUsageResponse {
+    /// Капитал сделок за последний час, SOL
+    pub deployed_last_hour_sol: String,
+    /// Капитал сделок за последние сутки, SOL
+    pub deployed_last_day_sol: String,
+    /// Среднее время между ногами двухэтапных сделок за сутки, мс
+    pub average_hold_ms: Option<u64>,
+    /// Капитал сделок за сутки к среднему балансу SOL кошелька
+    pub turnover_ratio: Option<String>,
+}
+
+impl From<CapitalUsage> for CapitalUsageResponse {
+    fn from(usage: CapitalUsage) -> Self {
+        Self {
+            deployed_last_hour_sol: usage.deployed_hour_sol.to_string(),
+            deployed_last_day_sol: usage.deployed_day_sol.to_string(),
+            average_hold_ms: usage.average_hold_ms,
+            turnover_ratio: usage.turnover.map(|turnover| turnover.round_dp(4).to_string()),
+        }
+    }
 }
 
 /// Ответ конфигурации (без секретов)
@@ -119,12 +337,16 @@ pub struct ConfigResponse {
 #[derive(Serialize)]
 pub struct NetworkConfigResponse {
     pub rpc_url: String,
+    /// Endpoint симуляций и аналитики (None — используется rpc_url)
+    pub simulation_rpc_url: Option<String>,
     pub commitment: String,
 }
 
 #[derive(Serialize)]
 pub struct ArbitrageConfigResponse {
     pub min_profit_percent: f64,
+    pub min_profit_absolute: MinProfitAbsolute,
+    pub rounding: RoundingConfig,
     pub max_trade_amount_sol: f64,
     pub slippage_tolerance: f64,
 }
@@ -133,6 +355,10 @@ pub struct ArbitrageConfigResponse {
 pub struct DexConfigResponse {
     pub enabled_dexes: Vec<String>,
     pub trading_pairs: Vec<String>,
+    /// Пары из токенов кошелька (pair_discovery), сканируемые сверх trading_pairs
+    pub discovered_pairs: Vec<String>,
+    /// Разрешённые направления по парам (пары без записи торгуются в обе стороны)
+    pub directions: HashMap<String, Vec<String>>,
 }
 
 #[derive(Serialize)]
@@ -155,64 +381,313 @@ pub struct ControlResponse {
     pub message: String,
 }
 
+/// Параметры запроса цен
+#[derive(Deserialize)]
+pub struct PricesQuery {
+    /// Пара BASE/QUOTE (по умолчанию — все пары из настроек)
+    pub pair: Option<String>,
+}
+
+/// Цена пары на одном DEX
+#[derive(Serialize)]
+pub struct DexPrice {
+    pub dex: String,
+    /// Сколько quote за 1 base; null, если цену получить не удалось
+    pub price: Option<String>,
+}
+
+/// Цены пары на включённых DEX
+#[derive(Serialize)]
+pub struct PairPrices {
+    pub pair: String,
+    pub prices: Vec<DexPrice>,
+}
+
+/// Ответ цен
+#[derive(Serialize)]
+pub struct PricesResponse {
+    pub pairs: Vec<PairPrices>,
+    pub timestamp: String,
+}
+
+/// Состояние DEX
+#[derive(Serialize)]
+pub struct DexItem {
+    pub name: String,
+    pub enabled: bool,
+    pub venue_type: &'static str,
+}
+
+/// Ответ списка DEX
+#[derive(Serialize)]
+pub struct DexesResponse {
+    pub dexes: Vec<DexItem>,
+}
+
+/// Параметры запроса для wallet/history
+#[derive(Deserialize)]
+pub struct WalletHistoryQuery {
+    /// Начало периода (RFC3339)
+    pub from: Option<String>,
+    /// Конец периода (RFC3339)
+    pub to: Option<String>,
+    /// Максимальное количество точек ряда
+    pub points: Option<usize>,
+}
+
+/// Ответ истории баланса кошелька
+#[derive(Serialize)]
+pub struct WalletHistoryResponse {
+    pub points: Vec<WalletHistoryPoint>,
+    pub total_snapshots: usize,
+}
+
+/// Точка ряда баланса
+#[derive(Serialize)]
+pub struct WalletHistoryPoint {
+    pub timestamp: String,
+    pub sol: String,
+    pub tokens: HashMap<String, String>,
+}
+
+/// Параметры запроса для history/cleanups
+#[derive(Deserialize)]
+pub struct CleanupHistoryQuery {
+    /// Начало периода (RFC3339)
+    pub from: Option<String>,
+    /// Конец периода (RFC3339)
+    pub to: Option<String>,
+}
+
+/// Ответ истории очисток токен-аккаунтов
+#[derive(Serialize)]
+pub struct CleanupHistoryResponse {
+    /// Очистки, новые первыми
+    pub cleanups: Vec<CleanupItem>,
+    /// Возвращённая рента за период, SOL
+    pub rent_reclaimed_sol: String,
+    /// Комиссии транзакций очистки за период, SOL
+    pub fees_sol: String,
+}
+
+/// Выполненная очистка: транзакция и закрытые в ней аккаунты
+#[derive(Serialize)]
+pub struct CleanupItem {
+    pub timestamp: String,
+    pub signature: String,
+    pub accounts: Vec<crate::ata_cleanup::ClosedAccount>,
+    pub rent_reclaimed_sol: String,
+    pub fee_sol: String,
+}
+
+/// Параметры запроса для analytics/heatmap
+#[derive(Deserialize)]
+pub struct HeatmapQuery {
+    /// Начало периода (RFC3339)
+    pub from: Option<String>,
+    /// Конец периода (RFC3339)
+    pub to: Option<String>,
+}
+
+/// Ответ тепловой карты возможностей
+#[derive(Serialize)]
+pub struct HeatmapResponse {
+    pub cells: Vec<HeatmapCellItem>,
+    /// Сохранённые возможности за период (включая маршруты, не попавшие в карту)
+    pub total_opportunities: usize,
+}
+
+/// Ячейка тепловой карты: час суток (UTC) × пара × связка DEX
+#[derive(Serialize)]
+pub struct HeatmapCellItem {
+    pub hour: u32,
+    pub pair: String,
+    pub from_dex: String,
+    pub to_dex: String,
+    pub count: u64,
+    pub avg_profit_percent_after_fees: String,
+    /// Средняя ожидаемая чистая прибыль, в quote токене
+    pub avg_net_profit: String,
+    pub max_profit_percent_after_fees: String,
+}
+
+/// Ответ состояния расписания
+#[derive(Serialize)]
+pub struct ScheduleResponse {
+    pub paused: bool,
+    pub pause_reason: Option<String>,
+    pub active_window: Option<String>,
+    #[serde(rename = "override")]
+    pub override_mode: ScheduleOverride,
+    pub windows: Vec<String>,
+}
+
+/// Параметры запроса сигналов
+#[derive(Deserialize)]
+pub struct SignalsQuery {
+    pub limit: Option<usize>,
+}
+
+/// Ответ с последними опубликованными возможностями
+#[derive(Serialize)]
+pub struct SignalsResponse {
+    pub mode: ExecutionMode,
+    pub count: usize,
+    pub signals: Vec<Signal>,
+}
+
+/// Параметры догрузки событий WebSocket
+#[derive(Deserialize)]
+pub struct EventsReplayQuery {
+    /// Номер последнего полученного события
+    pub since_seq: u64,
+    pub limit: Option<usize>,
+}
+
+/// Ответ догрузки событий
+#[derive(Serialize)]
+pub struct EventsReplayResponse {
+    /// Номер последнего опубликованного события
+    pub last_seq: u64,
+    /// Часть пропущенных событий уже вытеснена из журнала
+    pub gap: bool,
+    /// Есть ещё события: повторить запрос с since_seq последнего из events
+    pub has_more: bool,
+    /// События в формате сообщений /ws/updates, старые первыми
+    pub events: Vec<WsEnvelope>,
+}
+
+/// Запрос переопределения расписания
+#[derive(Deserialize)]
+pub struct ScheduleOverrideRequest {
+    pub mode: ScheduleOverride,
+}
+
+/// Фильтр логов (запрос и ответ /api/logging)
+#[derive(Serialize, Deserialize)]
+pub struct LoggingFilter {
+    /// Уровни в формате RUST_LOG: `info,dex=debug,web=warn`
+    pub filter: String,
+}
+
 /// GET /api/status
-pub async fn get_status(State(state): State<WebState>) -> Result<Json<StatusResponse>, StatusCode> {
-    let status = *state.bot_status.lock().await;
-    let status_str = match status {
+pub async fn get_status(State(state): State<WebState>) -> Result<Json<StatusResponse>, ApiError> {
+    let trading = state.monitor.trading().status();
+    let status_str = status_name(&state, &trading).await;
+
+    Ok(Json(StatusResponse {
+        status: status_str.to_string(),
+        mode: state.config.safety.mode,
+        simulation_mode: state.config.safety.simulation_mode,
+        uptime_seconds: state.uptime_seconds(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        clock_skew_ms: state.monitor.clock_skew().skew_ms(),
+        congested: state.monitor.congestion().is_congested(),
+        trading,
+        tasks: state.monitor.supervisor().health(),
+        jobs: state.monitor.jobs().list(),
+        self_test: state.monitor.self_test().report(),
+    }))
+}
+
+/// Статус бота для ответа: упавший торговый цикл важнее ручного статуса
+async fn status_name(state: &WebState, trading: &TradingStatus) -> &'static str {
+    let status = if trading.state == TradingState::Failed {
+        BotStatus::Error
+    } else {
+        *state.bot_status.lock().await
+    };
+    match status {
         BotStatus::Running => "running",
         BotStatus::Stopped => "stopped",
         BotStatus::Error => "error",
-    };
+    }
+}
 
-    Ok(Json(StatusResponse {
+/// Движок арбитража для запроса; engine_busy, если торговый цикл держит его дольше web.engine_busy_timeout_ms
+async fn lock_engine(state: &WebState) -> Result<tokio::sync::MutexGuard<'_, crate::arbitrage::ArbitrageEngine>, ApiError> {
+    let timeout = std::time::Duration::from_millis(state.config.web.engine_busy_timeout_ms);
+    tokio::time::timeout(timeout, state.arbitrage_engine.lock())
+        .await
+        .map_err(|_| ApiError::engine_busy())
+}
+
+/// GET /public/status — без авторизации, включается web.public_status
+pub async fn get_public_status(State(state): State<WebState>) -> Result<Json<PublicStatusResponse>, ApiError> {
+    let trading = state.monitor.trading().status();
+    let status_str = status_name(&state, &trading).await;
+    let activity = trade_activity(&state.trade_history.lock().await, chrono::Utc::now());
+
+    Ok(Json(PublicStatusResponse {
         status: status_str.to_string(),
         simulation_mode: state.config.safety.simulation_mode,
         uptime_seconds: state.uptime_seconds(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
+        trades_today: activity.trades_today,
+        last_trade_at: activity.last_trade_at.map(|at| at.to_rfc3339()),
     }))
 }
 
 /// GET /api/balance
 pub async fn get_balance(
     State(state): State<WebState>,
-) -> Result<Json<BalanceResponse>, StatusCode> {
+) -> Result<Json<BalanceResponse>, ApiError> {
     let balance_lamports = state
         .wallet
-        .get_balance(&state.config.network.rpc_url)
+        .current()
+        .await
+        .get_balance(&state.config.network)
         .await
         .map_err(|e| {
             log::error!("Ошибка получения баланса: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            ApiError::unavailable("Не удалось получить баланс кошелька")
         })?;
 
-    let balance_sol = balance_lamports as f64 / 1_000_000_000.0;
-    
-    // Примерный курс SOL/USD (можно получать из API)
-    let sol_price_usd = 100.0; // TODO: получать реальный курс
-    let usd_equivalent = balance_sol * sol_price_usd;
+    let balance_sol = Decimal::from(balance_lamports) / Decimal::from(1_000_000_000u64);
+    let usd_rate = state.monitor.fiat().usd_rate("SOL").await;
+    let usd_equivalent = usd_rate.as_ref()
+        .map(|rate| RoundingPolicy::new(&state.config.arbitrage.rounding).usd(balance_sol * rate.price).to_string());
 
     Ok(Json(BalanceResponse {
         sol_balance: format!("{:.9}", balance_sol),
-        usd_equivalent: format!("{:.2}", usd_equivalent),
+        usd_equivalent,
+        usd_rate: usd_rate.map(|rate| usd_rate_item("SOL", rate, state.monitor.clock_skew().cluster_now())),
         min_balance_sol: format!("{:.9}", state.config.safety.min_balance_sol),
     }))
 }
 
+/// GET /api/fiat/rates — курсы в USD с источником и возрастом
+pub async fn get_fiat_rates(
+    State(state): State<WebState>,
+) -> Json<FiatRatesResponse> {
+    let now = state.monitor.clock_skew().cluster_now();
+    let rates = state.monitor.fiat().cached_rates().await
+        .into_iter()
+        .map(|(symbol, rate)| usd_rate_item(&symbol, rate, now))
+        .collect();
+    Json(FiatRatesResponse { rates })
+}
+
 /// GET /api/opportunities
+///
+/// Возможности из последнего снимка цен торгового цикла; запросов к DEX нет.
+/// Поиск по запросу выполняется, только если снимка ещё нет.
 pub async fn get_opportunities(
     State(state): State<WebState>,
     Query(params): Query<OpportunitiesQuery>,
-) -> Result<Json<OpportunitiesResponse>, StatusCode> {
-    let engine_guard = state.arbitrage_engine.lock().await;
-    let engine = &*engine_guard;
-
-    let mut opportunities = engine
-        .find_opportunities()
-        .await
-        .map_err(|e| {
-            log::error!("Ошибка поиска возможностей: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
+) -> Result<Json<OpportunitiesResponse>, ApiError> {
+    let snapshot = match state.monitor.prices().latest() {
+        Some(snapshot) => snapshot,
+        None => {
+            let engine_guard = lock_engine(&state).await?;
+            engine_guard.find_opportunities().await.map_err(|e| {
+                log::error!("Ошибка поиска возможностей: {}", e);
+                ApiError::unavailable("Не удалось получить цены для поиска возможностей")
+            })?;
+            state.monitor.prices().latest()
+                .ok_or_else(|| ApiError::unavailable("Снимок цен не получен"))?
+        }
+    };
+    let mut opportunities = snapshot.opportunities.clone();
 
     // Фильтрация по минимальной прибыли
     if let Some(min_profit) = params.min_profit {
@@ -238,6 +713,18 @@ pub async fn get_opportunities(
             profit_percent_after_fees: opp.profit_percent_after_fees.to_string(),
             trade_amount: opp.trade_amount.to_string(),
             estimated_fees: opp.estimated_fees.to_string(),
+            buy_price_impact_percent: opp.price_impact.buy_percent.round_dp(4).to_string(),
+            sell_price_impact_percent: opp.price_impact.sell_percent.round_dp(4).to_string(),
+            price_stamps: opp.price_stamps,
+            route: opp.route.into_iter()
+                .map(|hop| RouteHopItem {
+                    dex: hop.dex,
+                    from_token: hop.from_token,
+                    to_token: hop.to_token,
+                    rate: hop.rate.to_string(),
+                    fee_percent: hop.fee_percent.to_string(),
+                })
+                .collect(),
         })
         .collect();
 
@@ -245,14 +732,177 @@ pub async fn get_opportunities(
         count: items.len(),
         opportunities: items,
         timestamp: chrono::Utc::now().to_rfc3339(),
+        snapshot_seq: snapshot.seq,
+        prices_at: snapshot.taken_at.to_rfc3339(),
+    }))
+}
+
+/// POST /api/sandbox/trade — оценка сделки без исполнения
+pub async fn sandbox_trade(
+    State(state): State<WebState>,
+    Json(request): Json<SandboxTradeRequest>,
+) -> Result<Json<SandboxTradeResponse>, ApiError> {
+    let Some((base_token, quote_token)) = request.pair.split_once('/') else {
+        return Err(ApiError::invalid_request("pair должна быть в формате BASE/QUOTE"));
+    };
+    let amount = Decimal::from_str(request.amount.trim())
+        .map_err(|_| ApiError::invalid_request(format!("Некорректный amount: {:?}", request.amount)))?;
+    if base_token.is_empty() || quote_token.is_empty() {
+        return Err(ApiError::invalid_request("pair должна быть в формате BASE/QUOTE"));
+    }
+    if amount <= Decimal::ZERO {
+        return Err(ApiError::invalid_request("amount должен быть больше 0"));
+    }
+    if request.buy_dex == request.sell_dex {
+        return Err(ApiError::invalid_request("buy_dex и sell_dex должны различаться"));
+    }
+    let trade = SandboxTrade {
+        base_token: base_token.to_string(),
+        quote_token: quote_token.to_string(),
+        buy_dex: request.buy_dex,
+        sell_dex: request.sell_dex,
+        amount,
+    };
+
+    let evaluation = {
+        let engine = lock_engine(&state).await?;
+        engine.evaluate_trade(&trade).await
+    }.map_err(|e| {
+        log::warn!("Песочница: не удалось оценить сделку {}: {:#}", request.pair, e);
+        ApiError::unprocessable(format!("Не удалось оценить сделку: {:#}", e))
+    })?;
+
+    let would_execute = evaluation.would_execute();
+    let opportunity = evaluation.opportunity;
+    Ok(Json(SandboxTradeResponse {
+        pair: request.pair,
+        buy_dex: opportunity.from_dex,
+        sell_dex: opportunity.to_dex,
+        amount: opportunity.trade_amount.to_string(),
+        buy_price: opportunity.buy_price.to_string(),
+        sell_price: opportunity.sell_price.to_string(),
+        buy_fee_percent: evaluation.buy_fee_percent.to_string(),
+        sell_fee_percent: evaluation.sell_fee_percent.to_string(),
+        profit_percent: opportunity.profit_percent.to_string(),
+        profit_percent_after_fees: opportunity.profit_percent_after_fees.to_string(),
+        min_profit_percent: evaluation.min_profit_percent.to_string(),
+        estimated_fees: opportunity.estimated_fees.to_string(),
+        buy_price_impact_percent: opportunity.price_impact.buy_percent.round_dp(4).to_string(),
+        sell_price_impact_percent: opportunity.price_impact.sell_percent.round_dp(4).to_string(),
+        buy_slippage_percent: evaluation.slippage.buy.to_string(),
+        sell_slippage_percent: evaluation.slippage.sell.to_string(),
+        buy_expected_output: evaluation.quote.map(|quote| quote.buy.to_string()),
+        sell_expected_output: evaluation.quote.map(|quote| quote.sell.to_string()),
+        buy_min_output: evaluation.min_output.buy.to_string(),
+        sell_min_output: evaluation.min_output.sell.to_string(),
+        profit_quote: evaluation.profit.quote.to_string(),
+        profit_sol: evaluation.profit.sol.map(|value| value.to_string()),
+        profit_usd: evaluation.profit.usd.map(|value| value.to_string()),
+        checks: evaluation.checks.into_iter()
+            .map(|check| RiskCheckItem { name: check.name.to_string(), passed: check.passed, detail: check.detail })
+            .collect(),
+        would_execute,
+        simulation_mode: state.config.safety.simulation_mode,
     }))
 }
 
+/// GET /api/prices — цены пар на включённых DEX без блокировки движка
+pub async fn get_prices(
+    State(state): State<WebState>,
+    Query(params): Query<PricesQuery>,
+) -> Result<Json<PricesResponse>, ApiError> {
+    let pairs = match params.pair {
+        Some(pair) => vec![pair],
+        None => state.dex_manager.trading_pairs(),
+    };
+
+    let mut result = Vec::with_capacity(pairs.len());
+    for pair in pairs {
+        let Some((base_token, quote_token)) = pair.split_once('/')
+            .filter(|(base, quote)| !base.is_empty() && !quote.is_empty())
+        else {
+            return Err(ApiError::invalid_request("pair должна быть в формате BASE/QUOTE"));
+        };
+        let mut prices = Vec::new();
+        for dex in state.dex_manager.active_dexes() {
+            if !state.config.dex.lists_pair(dex.name(), &pair) {
+                continue;
+            }
+            let price = match dex.get_price(base_token, quote_token).await {
+                Ok(price) => Some(price.to_string()),
+                Err(e) => {
+                    log::debug!("Цена {} на {} недоступна: {:#}", pair, dex.name(), e);
+                    None
+                }
+            };
+            prices.push(DexPrice { dex: dex.name().to_string(), price });
+        }
+        result.push(PairPrices { pair, prices });
+    }
+
+    Ok(Json(PricesResponse {
+        pairs: result,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }))
+}
+
+/// GET /api/dexes — зарегистрированные DEX и включены ли они
+pub async fn get_dexes(State(state): State<WebState>) -> Json<DexesResponse> {
+    Json(dexes_response(&state))
+}
+
+/// POST /api/dexes/:name/enable
+pub async fn enable_dex(
+    State(state): State<WebState>,
+    Extension(actor): Extension<Actor>,
+    Path(name): Path<String>,
+) -> Result<Json<DexesResponse>, ApiError> {
+    set_dex_enabled(&state, &actor, &name, true).await
+}
+
+/// POST /api/dexes/:name/disable — DEX перестаёт участвовать в поиске возможностей
+pub async fn disable_dex(
+    State(state): State<WebState>,
+    Extension(actor): Extension<Actor>,
+    Path(name): Path<String>,
+) -> Result<Json<DexesResponse>, ApiError> {
+    set_dex_enabled(&state, &actor, &name, false).await
+}
+
+async fn set_dex_enabled(state: &WebState, actor: &Actor, name: &str, enabled: bool) -> Result<Json<DexesResponse>, ApiError> {
+    let was_enabled = state.dex_manager.is_enabled(name);
+    state.dex_manager.set_enabled(name, enabled)
+        .map_err(|e| ApiError::not_found(format!("{:#}", e)))?;
+    log::warn!("DEX {} {} через API", name, if enabled { "включён" } else { "отключён" });
+    if was_enabled != enabled {
+        state.monitor.publish_safety(SafetyEvent::VenueBenched {
+            venue: name.to_string(),
+            benched: !enabled,
+            reason: "оператор через API".to_string(),
+        });
+        let change = FieldChange::new(format!("dex.{}.enabled", name), was_enabled, enabled);
+        record_config_change(state, actor, ChangeSource::Dex, change).await;
+    }
+    Ok(Json(dexes_response(state)))
+}
+
+fn dexes_response(state: &WebState) -> DexesResponse {
+    DexesResponse {
+        dexes: state.dex_manager.get_dexes().iter()
+            .map(|dex| DexItem {
+                name: dex.name().to_string(),
+                enabled: state.dex_manager.is_enabled(dex.name()),
+                venue_type: crate::dex::venue_type(dex.name()).as_str(),
+            })
+            .collect(),
+    }
+}
+
 /// GET /api/history
 pub async fn get_history(
     State(state): State<WebState>,
     Query(params): Query<HistoryQuery>,
-) -> Result<Json<HistoryResponse>, StatusCode> {
+) -> Result<Json<HistoryResponse>, ApiError> {
     let history_guard = state.trade_history.lock().await;
     let mut trades: Vec<TradeItem> = history_guard
         .iter()
@@ -264,6 +914,12 @@ pub async fn get_history(
                     return false;
                 }
             }
+            // Фильтр по тегу
+            if let Some(ref tag) = params.tag {
+                if !trade.tags.contains(tag) {
+                    return false;
+                }
+            }
             // Фильтр по статусу
             if let Some(ref status) = params.status {
                 let trade_status = match trade.status {
@@ -277,23 +933,7 @@ pub async fn get_history(
             }
             true
         })
-        .map(|trade| TradeItem {
-            id: trade.id.to_string(),
-            timestamp: trade.timestamp.to_rfc3339(),
-            from_dex: trade.from_dex,
-            to_dex: trade.to_dex,
-            base_token: trade.base_token,
-            quote_token: trade.quote_token,
-            amount: trade.amount.to_string(),
-            profit_percent: trade.profit_percent.to_string(),
-            profit_sol: trade.profit_sol.to_string(),
-            status: match trade.status {
-                crate::web::state::TradeStatus::Success => "success".to_string(),
-                crate::web::state::TradeStatus::Failed => "failed".to_string(),
-                crate::web::state::TradeStatus::Simulated => "simulated".to_string(),
-            },
-            tx_signature: trade.tx_signature,
-        })
+        .map(trade_item)
         .collect();
 
     // Сортировка по времени (новые первыми)
@@ -318,12 +958,86 @@ pub async fn get_history(
     }))
 }
 
+/// PATCH /api/history/:id — заметка и теги сделки
+pub async fn annotate_trade(
+    State(state): State<WebState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<TradeAnnotationRequest>,
+) -> Result<Json<TradeItem>, ApiError> {
+    let annotation = TradeAnnotation {
+        trade_id: id,
+        timestamp: chrono::Utc::now(),
+        notes: request.notes,
+        tags: request.tags,
+    };
+    match state.monitor.annotate_trade(annotation).await {
+        Ok(Some(record)) => {
+            log::info!("Обновлены заметка/теги сделки {}", id);
+            Ok(Json(trade_item(record)))
+        }
+        Ok(None) => Err(ApiError::not_found(format!("Сделка {} не найдена", id))),
+        Err(e) => {
+            log::warn!("Отклонено изменение сделки {}: {:#}", id, e);
+            Err(ApiError::invalid_request(format!("{:#}", e)))
+        }
+    }
+}
+
+/// Преобразование записи о сделке в ответ API
+fn trade_item(trade: TradeRecord) -> TradeItem {
+    TradeItem {
+        id: trade.id.to_string(),
+        timestamp: trade.timestamp.to_rfc3339(),
+        from_dex: trade.from_dex,
+        to_dex: trade.to_dex,
+        base_token: trade.base_token,
+        quote_token: trade.quote_token,
+        amount: trade.amount.to_string(),
+        profit_percent: trade.profit_percent.to_string(),
+        profit_quote: trade.profit_quote.to_string(),
+        profit_sol: trade.profit_sol.map(|value| value.to_string()),
+        profit_usd: trade.profit_usd.map(|value| value.to_string()),
+        status: match trade.status {
+            crate::web::state::TradeStatus::Success => "success".to_string(),
+            crate::web::state::TradeStatus::Failed => "failed".to_string(),
+            crate::web::state::TradeStatus::Simulated => "simulated".to_string(),
+        },
+        tx_signature: trade.tx_signature,
+        failure_reason: trade.failure_reason,
+        trace_id: trade.trace_id,
+        notes: trade.notes,
+        tags: trade.tags,
+        leg_gate: trade.leg_gate,
+        simulation_logs: trade.simulation_logs,
+    }
+}
+
+/// GET /api/metrics/prometheus
+///
+/// Gauge рабочего состояния в текстовом формате Prometheus для правил Alertmanager.
+pub async fn get_prometheus_metrics(State(state): State<WebState>) -> impl IntoResponse {
+    let body = state.monitor.ops_state().render_prometheus(chrono::Utc::now());
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")], body)
+}
+
 /// GET /api/metrics
 pub async fn get_metrics(
     State(state): State<WebState>,
-) -> Result<Json<MetricsResponse>, StatusCode> {
-    let metrics_guard = state.metrics.lock().await;
-    let metrics = metrics_guard.clone();
+) -> Result<Json<MetricsResponse>, ApiError> {
+    let metrics = state.metrics.lock().await.clone();
+    let fees = state.monitor.fees().totals();
+    let now = chrono::Utc::now();
+    let balances = crate::balance_history::load_history(&state.store, Some(now - chrono::Duration::days(1)), Some(now))
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Ошибка чтения истории баланса для оборачиваемости: {}", e);
+            Vec::new()
+        });
+    let (net_profit, capital) = {
+        let trades = state.trade_history.lock().await;
+        let balance = crate::balance_history::average_sol(&balances);
+        (net_profit_sol(&trades, &fees), capital_usage(&trades, balance, now))
+    };
 
     Ok(Json(MetricsResponse {
         total_trades: metrics.total_trades,
@@ -336,26 +1050,37 @@ pub async fn get_metrics(
         rpc_errors_count: metrics.rpc_errors_count,
         average_response_time_ms: metrics.average_response_time_ms,
         last_trade_timestamp: metrics.last_trade_timestamp.map(|dt| dt.to_rfc3339()),
+        venue_queues: state.venue_limiter.stats(),
+        fees,
+        total_fees_sol: fees.total_sol().to_string(),
+        net_profit_sol: net_profit.to_string(),
+        scan: state.monitor.scan_stats().snapshot(now),
+        capital: capital.into(),
     }))
 }
 
 /// GET /api/config
 pub async fn get_config(
     State(state): State<WebState>,
-) -> Result<Json<ConfigResponse>, StatusCode> {
+) -> Result<Json<ConfigResponse>, ApiError> {
     Ok(Json(ConfigResponse {
         network: NetworkConfigResponse {
-            rpc_url: state.config.network.rpc_url.clone(),
+            rpc_url: crate::rpc::redact_url(&state.config.network.rpc_url),
+            simulation_rpc_url: state.config.network.simulation_rpc_url.as_deref().map(crate::rpc::redact_url),
             commitment: state.config.network.commitment.clone(),
         },
         arbitrage: ArbitrageConfigResponse {
             min_profit_percent: state.config.arbitrage.min_profit_percent,
+            min_profit_absolute: state.config.arbitrage.min_profit_absolute.clone(),
+            rounding: state.config.arbitrage.rounding.clone(),
             max_trade_amount_sol: state.config.arbitrage.max_trade_amount_sol,
             slippage_tolerance: state.config.arbitrage.slippage_tolerance,
         },
         dex: DexConfigResponse {
             enabled_dexes: state.config.dex.enabled_dexes.clone(),
             trading_pairs: state.config.dex.trading_pairs.clone(),
+            discovered_pairs: state.dex_manager.discovered_pairs(),
+            directions: state.config.dex.directions.clone(),
         },
         monitoring: MonitoringConfigResponse {
             check_interval_ms: state.config.monitoring.check_interval_ms,
@@ -372,7 +1097,7 @@ pub async fn get_config(
 /// POST /api/control/start
 pub async fn control_start(
     State(state): State<WebState>,
-) -> Result<Json<ControlResponse>, StatusCode> {
+) -> Result<Json<ControlResponse>, ApiError> {
     let mut status = state.bot_status.lock().await;
     *status = BotStatus::Running;
     
@@ -385,7 +1110,7 @@ pub async fn control_start(
 /// POST /api/control/stop
 pub async fn control_stop(
     State(state): State<WebState>,
-) -> Result<Json<ControlResponse>, StatusCode> {
+) -> Result<Json<ControlResponse>, ApiError> {
     let mut status = state.bot_status.lock().await;
     *status = BotStatus::Stopped;
     
@@ -395,25 +1120,447 @@ pub async fn control_stop(
     }))
 }
 
+/// POST /api/control/restart
+///
+/// Перезапуск торгового цикла после ошибки (снимает остановку исполнения).
+/// 409, если цикл работает.
+pub async fn control_restart(
+    State(state): State<WebState>,
+) -> Result<Json<ControlResponse>, ApiError> {
+    if !state.monitor.trading().request_restart() {
+        return Err(ApiError::conflict("Торговый цикл работает, перезапуск не нужен"));
+    }
+    log::warn!("Запрошен перезапуск торгового цикла через API");
+
+    Ok(Json(ControlResponse {
+        status: "restarting".to_string(),
+        message: "Торговый цикл перезапускается".to_string(),
+    }))
+}
+
 /// POST /api/config/reload
+///
+/// Перечитывает конфигурацию с диска. Сейчас применяется только смена
+/// wallet.keypair_path (ротация ключа); остальные настройки — после перезапуска.
 pub async fn config_reload(
     State(state): State<WebState>,
-) -> Result<Json<ControlResponse>, StatusCode> {
-    // Перезагрузка конфигурации (только перезагрузка из файлов, не изменение)
-    // В будущем можно реализовать перезагрузку конфига
-    log::info!("Запрос на перезагрузку конфигурации (пока не реализовано)");
-    
+    Extension(actor): Extension<Actor>,
+) -> Result<Json<ControlResponse>, ApiError> {
+    let config = tokio::task::spawn_blocking(Config::read)
+        .await
+        .map_err(|_| ApiError::internal("Задача чтения конфигурации завершилась с ошибкой"))?
+        .map_err(|e| {
+            log::warn!("Не удалось перечитать конфигурацию: {:#}", e);
+            ApiError::invalid_request(format!("Некорректная конфигурация: {:#}", e))
+        })?;
+
+    let changed = state.config.changed_sections(&config);
+    if let Err(e) = state.config_history.record_reload(&config, &actor.0, chrono::Utc::now()).await {
+        log::error!("Не удалось записать изменения конфигурации в журнал: {:#}", e);
+    }
+    if config.wallet.keypair_path == state.config.wallet.keypair_path {
+        log::info!("Конфигурация перечитана, путь к ключу не изменился");
+        publish_reload(&state, &config, changed, Vec::new());
+        return Ok(Json(ControlResponse {
+            status: "reloaded".to_string(),
+            message: "Конфигурация перечитана; изменения, кроме wallet.keypair_path, применяются после перезапуска".to_string(),
+        }));
+    }
+
+    let rotation = rotate_wallet(&state, Some(config.wallet.keypair_path.clone())).await?;
+    publish_reload(&state, &config, changed, vec!["wallet".to_string()]);
     Ok(Json(ControlResponse {
         status: "reloaded".to_string(),
-        message: "Конфигурация перезагружена".to_string(),
+        message: format!("Кошелёк переключён на {}", rotation.new_pubkey),
+    }))
+}
+
+/// События перечитанной конфигурации для `/ws/updates`
+///
+/// Смена simulation_mode применяется только после перезапуска бота, но панели
+/// узнают о ней сразу (`effective: false`).
+fn publish_reload(state: &WebState, config: &Config, changed: Vec<String>, applied: Vec<String>) {
+    state.monitor.publish_safety(SafetyEvent::ConfigReloaded { changed, applied });
+    if config.safety.simulation_mode != state.config.safety.simulation_mode {
+        state.monitor.publish_safety(SafetyEvent::ModeChanged {
+            simulation_mode: config.safety.simulation_mode,
+            effective: false,
+        });
+    }
+}
+
+/// Ответ на замену ключа (только публичные адреса)
+#[derive(Serialize)]
+pub struct WalletRotateResponse {
+    pub old_pubkey: String,
+    pub new_pubkey: String,
+    pub changed: bool,
+    pub rotated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// POST /api/wallet/rotate
+///
+/// Перечитывает ключ из файла wallet.keypair_path и переключает подпись после
+/// завершения текущих сделок. Ключ через API не передаётся и не возвращается.
+pub async fn wallet_rotate(
+    State(state): State<WebState>,
+) -> Result<Json<WalletRotateResponse>, ApiError> {
+    let rotation = rotate_wallet(&state, None).await?;
+    Ok(Json(WalletRotateResponse {
+        old_pubkey: rotation.old_pubkey.to_string(),
+        new_pubkey: rotation.new_pubkey.to_string(),
+        changed: rotation.changed(),
+        rotated_at: rotation.rotated_at,
+    }))
+}
+
+/// Замена ключа с ожиданием сделок не дольше двух таймаутов транзакции (две ноги)
+async fn rotate_wallet(
+    state: &WebState,
+    keypair_path: Option<std::path::PathBuf>,
+) -> Result<crate::wallet::KeyRotation, ApiError> {
+    let drain_timeout = std::time::Duration::from_secs(state.config.arbitrage.transaction_timeout_sec * 2);
+    state.wallet.rotate(keypair_path, drain_timeout).await.map_err(|e| {
+        log::error!("Не удалось заменить ключ кошелька: {:#}", e);
+        ApiError::conflict(format!("Не удалось заменить ключ кошелька: {:#}", e))
+    })
+}
+
+/// Запись изменения настроек в журнал (ошибка записи не отменяет изменение)
+async fn record_config_change(state: &WebState, actor: &Actor, source: ChangeSource, change: FieldChange) {
+    if let Err(e) = state.config_history.record(source, &actor.0, vec![change], chrono::Utc::now()).await {
+        log::error!("Не удалось записать изменение настроек в журнал: {:#}", e);
+    }
+}
+
+/// Параметры запроса журнала изменений конфигурации
+#[derive(Deserialize)]
+pub struct ConfigHistoryQuery {
+    /// Начало периода (RFC 3339)
+    pub from: Option<String>,
+    /// Конец периода (RFC 3339)
+    pub to: Option<String>,
+    /// Сколько последних записей вернуть (по умолчанию 100, не больше 1000)
+    pub limit: Option<usize>,
+}
+
+/// Ответ журнала изменений конфигурации
+#[derive(Serialize)]
+pub struct ConfigHistoryResponse {
+    /// Изменения от новых к старым
+    pub changes: Vec<ConfigChange>,
+    /// Изменений за период (до ограничения limit)
+    pub total: usize,
+}
+
+/// GET /api/config/history
+///
+/// Изменения конфигурации и настроек во время работы: кто, когда и какие поля
+/// изменил (значения до и после).
+pub async fn get_config_history(
+    State(state): State<WebState>,
+    Query(params): Query<ConfigHistoryQuery>,
+) -> Result<Json<ConfigHistoryResponse>, ApiError> {
+    let from = parse_time(params.from)?;
+    let to = parse_time(params.to)?;
+    let limit = params.limit.unwrap_or(100).min(1000);
+
+    let mut changes = state.config_history.load(from, to)
+        .await
+        .map_err(|e| {
+            log::error!("Ошибка чтения журнала конфигурации: {}", e);
+            ApiError::unavailable("Не удалось прочитать журнал конфигурации")
+        })?;
+    let total = changes.len();
+    changes.reverse();
+    changes.truncate(limit);
+
+    Ok(Json(ConfigHistoryResponse { changes, total }))
+}
+
+/// Разбор границы периода из параметра запроса (RFC 3339)
+fn parse_time(value: Option<String>) -> Result<Option<chrono::DateTime<chrono::Utc>>, ApiError> {
+    value
+        .map(|v| chrono::DateTime::parse_from_rfc3339(&v)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .map_err(|_| ApiError::invalid_request(format!("Некорректное время (нужен RFC 3339): {}", v))))
+        .transpose()
+}
+
+/// GET /api/wallet/history
+pub async fn get_wallet_history(
+    State(state): State<WebState>,
+    Query(params): Query<WalletHistoryQuery>,
+) -> Result<Json<WalletHistoryResponse>, ApiError> {
+    let from = parse_time(params.from)?;
+    let to = parse_time(params.to)?;
+    let max_points = params.points.unwrap_or(500).min(5000);
+
+    let snapshots = crate::balance_history::load_history(&state.store, from, to)
+        .await
+        .map_err(|e| {
+            log::error!("Ошибка чтения истории баланса: {}", e);
+            ApiError::unavailable("Не удалось прочитать историю баланса")
+        })?;
+
+    let points = crate::balance_history::downsample(&snapshots, max_points)
+        .into_iter()
+        .map(|s| WalletHistoryPoint {
+            timestamp: s.timestamp.to_rfc3339(),
+            sol: s.sol.to_string(),
+            tokens: s.tokens.into_iter().map(|(mint, amount)| (mint, amount.to_string())).collect(),
+        })
+        .collect();
+
+    Ok(Json(WalletHistoryResponse {
+        points,
+        total_snapshots: snapshots.len(),
+    }))
+}
+
+/// GET /api/history/cleanups
+pub async fn get_cleanup_history(
+    State(state): State<WebState>,
+    Query(params): Query<CleanupHistoryQuery>,
+) -> Result<Json<CleanupHistoryResponse>, ApiError> {
+    let from = parse_time(params.from)?;
+    let to = parse_time(params.to)?;
+
+    let mut cleanups = crate::ata_cleanup::load_cleanups(&state.store, from, to)
+        .await
+        .map_err(|e| {
+            log::error!("Ошибка чтения истории очисток токен-аккаунтов: {}", e);
+            ApiError::unavailable("Не удалось прочитать историю очисток токен-аккаунтов")
+        })?;
+    cleanups.sort_by_key(|cleanup| std::cmp::Reverse(cleanup.timestamp));
+
+    let rent_reclaimed: u64 = cleanups.iter().map(|cleanup| cleanup.rent_reclaimed).sum();
+    let fees: u64 = cleanups.iter().map(|cleanup| cleanup.fee).sum();
+    Ok(Json(CleanupHistoryResponse {
+        cleanups: cleanups.into_iter()
+            .map(|cleanup| CleanupItem {
+                timestamp: cleanup.timestamp.to_rfc3339(),
+                signature: cleanup.signature,
+                accounts: cleanup.accounts,
+                rent_reclaimed_sol: lamports_to_sol(cleanup.rent_reclaimed).to_string(),
+                fee_sol: lamports_to_sol(cleanup.fee).to_string(),
+            })
+            .collect(),
+        rent_reclaimed_sol: lamports_to_sol(rent_reclaimed).to_string(),
+        fees_sol: lamports_to_sol(fees).to_string(),
     }))
 }
 
+/// GET /api/analytics/heatmap
+///
+/// Сохранённые возможности по часам суток (UTC), парам и связкам DEX.
+pub async fn get_heatmap(
+    State(state): State<WebState>,
+    Query(params): Query<HeatmapQuery>,
+) -> Result<Json<HeatmapResponse>, ApiError> {
+    let from = parse_time(params.from)?;
+    let to = parse_time(params.to)?;
+
+    let signals = crate::heatmap::load_opportunities(&state.store, from, to)
+        .await
+        .map_err(|e| {
+            log::error!("Ошибка чтения истории возможностей: {}", e);
+            ApiError::unavailable("Не удалось прочитать историю возможностей")
+        })?;
+
+    let cells = crate::heatmap::build_heatmap(&signals)
+        .into_iter()
+        .map(|cell| HeatmapCellItem {
+            hour: cell.hour,
+            pair: format!("{}/{}", cell.base_token, cell.quote_token),
+            from_dex: cell.from_dex,
+            to_dex: cell.to_dex,
+            count: cell.count,
+            avg_profit_percent_after_fees: cell.avg_profit_percent_after_fees.round_dp(4).to_string(),
+            avg_net_profit: cell.avg_net_profit.round_dp(9).normalize().to_string(),
+            max_profit_percent_after_fees: cell.max_profit_percent_after_fees.round_dp(4).to_string(),
+        })
+        .collect();
+
+    Ok(Json(HeatmapResponse {
+        cells,
+        total_opportunities: signals.len(),
+    }))
+}
+
+/// GET /api/schedule
+pub async fn get_schedule(
+    State(state): State<WebState>,
+) -> Result<Json<ScheduleResponse>, ApiError> {
+    Ok(Json(schedule_response(&state)))
+}
+
+/// POST /api/schedule/override
+pub async fn schedule_override(
+    State(state): State<WebState>,
+    Extension(actor): Extension<Actor>,
+    Json(request): Json<ScheduleOverrideRequest>,
+) -> Result<Json<ScheduleResponse>, ApiError> {
+    let previous = state.schedule.override_mode();
+    state.schedule.set_override(request.mode);
+    log::info!("Переопределение расписания через API: {:?}", request.mode);
+    if previous != request.mode {
+        let change = FieldChange::new("schedule.override", previous, request.mode);
+        record_config_change(&state, &actor, ChangeSource::Schedule, change).await;
+    }
+    Ok(Json(schedule_response(&state)))
+}
+
+fn schedule_response(state: &WebState) -> ScheduleResponse {
+    let now = chrono::Utc::now();
+    let schedule = state.schedule.schedule();
+    let pause_reason = state.schedule.pause_reason(now);
+    ScheduleResponse {
+        paused: pause_reason.is_some(),
+        pause_reason,
+        active_window: schedule.active_window(now).map(|w| w.name.clone()),
+        override_mode: state.schedule.override_mode(),
+        windows: schedule.windows().iter().map(|w| w.name.clone()).collect(),
+    }
+}
+
+/// GET /api/logging
+pub async fn get_logging() -> Json<LoggingFilter> {
+    Json(LoggingFilter { filter: log_filter::current().to_string() })
+}
+
+/// PUT /api/logging
+pub async fn set_logging(
+    State(state): State<WebState>,
+    Extension(actor): Extension<Actor>,
+    Json(request): Json<LoggingFilter>,
+) -> Result<Json<LoggingFilter>, ApiError> {
+    let previous = log_filter::current().to_string();
+    match log_filter::set(&request.filter) {
+        Ok(filter) => {
+            log::warn!("Фильтр логов изменён через API: {}", filter);
+            if previous != filter.to_string() {
+                let change = FieldChange::new("logging.filter", previous, filter.to_string());
+                record_config_change(&state, &actor, ChangeSource::Logging, change).await;
+            }
+            Ok(Json(LoggingFilter { filter: filter.to_string() }))
+        }
+        Err(e) => {
+            log::warn!("Некорректный фильтр логов {:?}: {:#}", request.filter, e);
+            Err(ApiError::invalid_request(format!("Некорректный фильтр логов: {:#}", e)))
+        }
+    }
+}
+
+/// GET /api/signals
+pub async fn get_signals(
+    State(state): State<WebState>,
+    Query(params): Query<SignalsQuery>,
+) -> Result<Json<SignalsResponse>, ApiError> {
+    let limit = params.limit.unwrap_or(50).min(500);
+    let signals = state.monitor.signals().recent(limit);
+    Ok(Json(SignalsResponse {
+        mode: state.config.safety.mode,
+        count: signals.len(),
+        signals,
+    }))
+}
+
+/// GET /api/events/replay
+pub async fn replay_events(
+    State(state): State<WebState>,
+    Query(params): Query<EventsReplayQuery>,
+) -> Result<Json<EventsReplayResponse>, ApiError> {
+    let limit = params.limit.unwrap_or(EVENT_LOG_CAPACITY).min(EVENT_LOG_CAPACITY);
+    let replay = state.monitor.events().since(params.since_seq, limit);
+    Ok(Json(EventsReplayResponse {
+        last_seq: replay.last_seq,
+        gap: replay.gap,
+        has_more: replay.has_more,
+        events: replay.events.into_iter().map(WsEnvelope::from).collect(),
+    }))
+}
+
+/// Выпущенный токен API: секрет возвращается только в этом ответе
+#[derive(Serialize)]
+pub struct CreatedApiTokenResponse {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    /// Секрет для заголовка `Authorization: Bearer <token>`
+    #[serde(rename = "token")]
+    pub secret: String,
+}
+
+/// Список токенов API (без секретов)
+#[derive(Serialize)]
+pub struct ApiTokensResponse {
+    pub tokens: Vec<ApiToken>,
+}
+
+/// POST /api/auth/tokens — выпуск токена с ограниченными правами (только администратор)
+pub async fn create_api_token(
+    State(state): State<WebState>,
+    Json(request): Json<TokenRequest>,
+) -> Result<Json<CreatedApiTokenResponse>, ApiError> {
+    let request = request.normalized()
+        .map_err(|e| ApiError::invalid_request(format!("{:#}", e)))?;
+    let (token, secret) = state.api_tokens.issue(request, chrono::Utc::now())
+        .await
+        .map_err(|e| {
+            log::error!("Ошибка выпуска токена API: {:#}", e);
+            ApiError::unavailable("Не удалось сохранить токен API")
+        })?;
+    log::info!("Выпущен токен API {} ({}), права: {:?}, до {}", token.name, token.id, token.scopes, token.expires_at);
+    Ok(Json(CreatedApiTokenResponse { token, secret }))
+}
+
+/// GET /api/auth/tokens
+pub async fn list_api_tokens(State(state): State<WebState>) -> Result<Json<ApiTokensResponse>, ApiError> {
+    let tokens = state.api_tokens.list()
+        .await
+        .map_err(|e| {
+            log::error!("Ошибка чтения токенов API: {:#}", e);
+            ApiError::unavailable("Не удалось прочитать токены API")
+        })?;
+    Ok(Json(ApiTokensResponse { tokens }))
+}
+
+/// DELETE /api/auth/tokens/:id — отзыв токена
+pub async fn revoke_api_token(
+    State(state): State<WebState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiTokensResponse>, ApiError> {
+    match state.api_tokens.revoke(id, chrono::Utc::now()).await {
+        Ok(true) => {
+            log::warn!("Токен API {} отозван", id);
+            list_api_tokens(State(state)).await
+        }
+        Ok(false) => Err(ApiError::not_found(format!("Токен API {} не найден", id))),
+        Err(e) => {
+            log::error!("Ошибка отзыва токена API {}: {:#}", id, e);
+            Err(ApiError::unavailable("Не удалось сохранить отзыв токена API"))
+        }
+    }
+}
+
 /// GET /health
-pub async fn health_check() -> Json<HashMap<&'static str, String>> {
+///
+/// 503, если торговый цикл завис (прогон не завершался дольше
+/// monitoring.scan_stall_timeout_sec) — для проверок Docker и `--healthcheck`.
+/// Цикл, остановленный с ошибкой и ждущий перезапуска через API, здоров.
+pub async fn health_check(State(state): State<WebState>) -> Result<Json<HashMap<&'static str, String>>, ApiError> {
+    let now = chrono::Utc::now();
+    let stall_timeout = state.config.monitoring.scan_stall_timeout();
+    if state.monitor.trading().stalled(now, stall_timeout) {
+        return Err(ApiError::unavailable(format!(
+            "Торговый цикл не завершал прогон дольше {} с",
+            stall_timeout.num_seconds()
+        )));
+    }
+
     let mut response = HashMap::new();
     response.insert("status", "healthy".to_string());
-    response.insert("timestamp", chrono::Utc::now().to_rfc3339());
-    Json(response)
+    response.insert("timestamp", now.to_rfc3339());
+    Ok(Json(response))
 }
 
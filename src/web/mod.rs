@@ -2,22 +2,33 @@ pub mod state;
 pub mod handlers;
 pub mod websocket;
 pub mod auth;
+pub mod error;
+pub mod request_id;
 pub mod server;
 
 use crate::config::Config;
+use crate::dex::DexManager;
 use crate::monitor::Monitor;
 use crate::arbitrage::ArbitrageEngine;
-use crate::wallet::Wallet;
+use crate::schedule::ScheduleControl;
+use crate::store::Store;
+use crate::venue_limiter::VenueLimiter;
+use crate::wallet::HotWallet;
 use std::sync::Arc;
 
 /// Создание состояния веб-сервера
+#[allow(clippy::too_many_arguments)]
 pub fn create_state(
     config: Config,
     monitor: Monitor,
-    wallet: Arc<Wallet>,
+    wallet: HotWallet,
     arbitrage_engine: Arc<tokio::sync::Mutex<ArbitrageEngine>>,
+    schedule: ScheduleControl,
+    store: Store,
+    venue_limiter: Arc<VenueLimiter>,
+    dex_manager: Arc<DexManager>,
 ) -> state::WebState {
-    state::WebState::new(config, monitor, wallet, arbitrage_engine)
+    state::WebState::new(config, monitor, wallet, arbitrage_engine, schedule, store, venue_limiter, dex_manager)
 }
 
 /// Запуск веб-сервера
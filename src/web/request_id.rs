@@ -0,0 +1,84 @@
+use axum::{
+    body::to_bytes,
+    extract::Request,
+    http::{header::{CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER}, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::trace::{self, TraceId};
+use crate::web::error::{ApiError, ErrorCode, Problem, PROBLEM_CONTENT_TYPE, RETRY_AFTER_SEC};
+
+/// Заголовок с идентификатором запроса (принимается от клиента и возвращается в ответе)
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Сколько байт тела ответа без ApiError берётся в message (текст отказа axum)
+const MAX_ERROR_BODY_BYTES: usize = 4096;
+
+/// Присвоение идентификатора каждому запросу
+///
+/// Обработчик выполняется внутри `trace::scope`, поэтому все его логи помечены идентификатором.
+/// Все ответы с ошибкой приводятся к problem+json с кодом, причиной и request_id.
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    let request_id = request.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(TraceId::from_external)
+        .unwrap_or_else(|| TraceId::new("req"));
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    let mut response = trace::scope(request_id.clone(), async move {
+        let response = next.run(request).await;
+        log::debug!("{} {} -> {}", method, path, response.status().as_u16());
+        response
+    }).await;
+
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        response = problem_response(response, &request_id).await;
+    }
+
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Ответ с ошибкой в виде problem+json
+///
+/// Код и причина берутся из ApiError обработчика; ответы axum и tower-http
+/// (отказ в разборе тела, 404, 405) получают код по статусу и свой текст как причину.
+async fn problem_response(response: Response, request_id: &TraceId) -> Response {
+    let status = response.status();
+    let (mut parts, body) = response.into_parts();
+    let error = match parts.extensions.remove::<ApiError>() {
+        Some(error) => error,
+        None => {
+            let text = to_bytes(body, MAX_ERROR_BODY_BYTES).await
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+                .map(|text| text.trim().to_string())
+                .filter(|text| !text.is_empty());
+            let message = text.unwrap_or_else(|| status.canonical_reason().unwrap_or("error").to_string());
+            ApiError::new(ErrorCode::from_status(status), message)
+        }
+    };
+
+    let problem = Problem {
+        title: status.canonical_reason().unwrap_or("error").to_string(),
+        status: status.as_u16(),
+        code: error.code,
+        message: error.message,
+        request_id: request_id.to_string(),
+        retryable: error.code.retryable(),
+    };
+    parts.headers.insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_CONTENT_TYPE));
+    parts.headers.remove(CONTENT_LENGTH);
+    if problem.retryable {
+        parts.headers.insert(RETRY_AFTER, HeaderValue::from(RETRY_AFTER_SEC));
+    }
+    Response::from_parts(parts, Json(problem).into_response().into_body())
+}
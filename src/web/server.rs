@@ -3,7 +3,7 @@ use axum::{
     http::StatusCode,
     middleware,
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::{delete, get, patch, post},
     Router,
 };
 use tower::ServiceBuilder;
@@ -18,6 +18,7 @@ use crate::web::state::WebState;
 use crate::web::handlers;
 use crate::web::websocket;
 use crate::web::auth;
+use crate::web::request_id;
 use crate::config::Config;
 
 /// Запуск веб-сервера
@@ -47,29 +48,53 @@ pub async fn start_server(
     Ok(())
 }
 
-/// Создание роутера с маршрутами
-fn create_router(state: WebState) -> Router {
+/// Создание роутера с маршрутами (без привязки к адресу — для запуска на своём listener)
+pub fn create_router(state: WebState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
     // Публичные маршруты
-    let public_routes = Router::new()
+    let mut public_routes = Router::new()
         .route("/health", get(handlers::health_check));
+    if state.config.web.public_status {
+        public_routes = public_routes.route("/public/status", get(handlers::get_public_status));
+    }
 
     // Защищённые маршруты
     let protected_routes = Router::new()
         .route("/api/status", get(handlers::get_status))
         .route("/api/balance", get(handlers::get_balance))
+        .route("/api/fiat/rates", get(handlers::get_fiat_rates))
+        .route("/api/wallet/history", get(handlers::get_wallet_history))
+        .route("/api/analytics/heatmap", get(handlers::get_heatmap))
+        .route("/api/wallet/rotate", post(handlers::wallet_rotate))
         .route("/api/opportunities", get(handlers::get_opportunities))
+        .route("/api/prices", get(handlers::get_prices))
+        .route("/api/dexes", get(handlers::get_dexes))
+        .route("/api/dexes/:name/enable", post(handlers::enable_dex))
+        .route("/api/dexes/:name/disable", post(handlers::disable_dex))
+        .route("/api/sandbox/trade", post(handlers::sandbox_trade))
+        .route("/api/signals", get(handlers::get_signals))
+        .route("/api/events/replay", get(handlers::replay_events))
         .route("/api/history", get(handlers::get_history))
+        .route("/api/history/cleanups", get(handlers::get_cleanup_history))
+        .route("/api/history/:id", patch(handlers::annotate_trade))
         .route("/api/metrics", get(handlers::get_metrics))
+        .route("/api/metrics/prometheus", get(handlers::get_prometheus_metrics))
         .route("/api/config", get(handlers::get_config))
+        .route("/api/config/history", get(handlers::get_config_history))
         .route("/api/control/start", post(handlers::control_start))
         .route("/api/control/stop", post(handlers::control_stop))
+        .route("/api/control/restart", post(handlers::control_restart))
         .route("/api/config/reload", post(handlers::config_reload))
-        .layer(middleware::from_fn(auth::auth_middleware));
+        .route("/api/schedule", get(handlers::get_schedule))
+        .route("/api/schedule/override", post(handlers::schedule_override))
+        .route("/api/logging", get(handlers::get_logging).put(handlers::set_logging))
+        .route("/api/auth/tokens", get(handlers::list_api_tokens).post(handlers::create_api_token))
+        .route("/api/auth/tokens/:id", delete(handlers::revoke_api_token))
+        .layer(middleware::from_fn_with_state(state.clone(), auth::auth_middleware));
 
     // WebSocket маршруты (аутентификация внутри handlers)
     let ws_routes = Router::new()
@@ -91,6 +116,7 @@ fn create_router(state: WebState) -> Router {
         .layer(
             ServiceBuilder::new()
                 .layer(TraceLayer::new_for_http())
+                .layer(middleware::from_fn(request_id::request_id_middleware))
                 .layer(cors)
         )
         .with_state(state)
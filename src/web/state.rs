@@ -1,25 +1,41 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Serialize, Deserialize};
 
+use crate::api_tokens::ApiTokens;
 use crate::config::Config;
+use crate::config_history::ConfigHistory;
+use crate::dex::DexManager;
+pub use crate::history::{TradeRecord, TradeStatus};
 use crate::monitor::Monitor;
 use crate::arbitrage::{ArbitrageEngine, ArbitrageOpportunity};
-use crate::wallet::Wallet;
+use crate::schedule::ScheduleControl;
+use crate::store::Store;
+use crate::venue_limiter::VenueLimiter;
+use crate::wallet::HotWallet;
 
 /// Состояние веб-сервера для доступа к данным бота
+#[derive(Clone)]
 pub struct WebState {
     pub config: Arc<Config>,
     pub monitor: Arc<Monitor>,
     pub arbitrage_engine: Arc<tokio::sync::Mutex<ArbitrageEngine>>,
-    pub wallet: Arc<Wallet>,
+    pub wallet: HotWallet,
     pub metrics: Arc<Mutex<Metrics>>,
     pub trade_history: Arc<Mutex<Vec<TradeRecord>>>,
     pub start_time: DateTime<Utc>,
     pub bot_status: Arc<Mutex<BotStatus>>,
+    pub schedule: ScheduleControl,
+    pub store: Store,
+    pub venue_limiter: Arc<VenueLimiter>,
+    /// Менеджер DEX, общий с движком (цены и отключение DEX без блокировки движка)
+    pub dex_manager: Arc<DexManager>,
+    /// Токены API с ограниченными правами (хранятся в store)
+    pub api_tokens: ApiTokens,
+    /// Журнал изменений конфигурации (хранится в store)
+    pub config_history: ConfigHistory,
 }
 
 /// Метрики производительности
@@ -46,47 +62,36 @@ pub enum BotStatus {
     Error,
 }
 
-/// Запись о сделке
-#[derive(Debug, Clone, Serialize)]
-pub struct TradeRecord {
-    pub id: Uuid,
-    pub timestamp: DateTime<Utc>,
-    pub from_dex: String,
-    pub to_dex: String,
-    pub base_token: String,
-    pub quote_token: String,
-    pub amount: Decimal,
-    pub profit_percent: Decimal,
-    pub profit_sol: Decimal,
-    pub status: TradeStatus,
-    pub tx_signature: Option<String>,
-}
-
-/// Статус сделки
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
-pub enum TradeStatus {
-    Success,
-    Failed,
-    Simulated,
-}
-
 impl WebState {
     /// Создание нового состояния веб-сервера
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: Config,
         monitor: Monitor,
-        wallet: Arc<Wallet>,
+        wallet: HotWallet,
         arbitrage_engine: Arc<tokio::sync::Mutex<ArbitrageEngine>>,
+        schedule: ScheduleControl,
+        store: Store,
+        venue_limiter: Arc<VenueLimiter>,
+        dex_manager: Arc<DexManager>,
     ) -> Self {
+        let trade_history = monitor.trade_history();
+        let config_history = ConfigHistory::new(store.clone(), &config);
         Self {
             config: Arc::new(config),
             monitor: Arc::new(monitor),
             arbitrage_engine,
             wallet,
             metrics: Arc::new(Mutex::new(Metrics::default())),
-            trade_history: Arc::new(Mutex::new(Vec::new())),
+            trade_history,
             start_time: Utc::now(),
             bot_status: Arc::new(Mutex::new(BotStatus::Running)),
+            schedule,
+            api_tokens: ApiTokens::new(store.clone()),
+            config_history,
+            store,
+            venue_limiter,
+            dex_manager,
         }
     }
 
@@ -1,15 +1,20 @@
 use axum::{
-    extract::{ws::WebSocket, State, Query},
+    extract::{ws::{WebSocket, WebSocketUpgrade}, State, Query},
     response::Response,
 };
 use futures_util::{SinkExt, StreamExt};
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use tokio::sync::broadcast;
 use tokio::time::{interval, Duration};
 
-use crate::web::state::WebState;
+use chrono::{DateTime, Utc};
+
+use crate::event_log::{BotEvent, SafetyEvent, SequencedEvent};
+use crate::price_snapshot::PriceSnapshot;
+use crate::receipt::ExecutionReceipt;
+use crate::web::state::{TradeStatus, WebState};
 
 /// Тип WebSocket сообщения
 #[derive(Debug, Serialize, Clone)]
@@ -27,6 +32,11 @@ pub enum WsMessage {
         quote_token: String,
         profit_percent: String,
         profit_percent_after_fees: String,
+        /// Шаги маршрута через несколько пар в виде "dex:FROM->TO"
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        route: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trace_id: Option<String>,
     },
     Trade {
         id: String,
@@ -35,6 +45,23 @@ pub enum WsMessage {
         to_dex: String,
         profit_percent: String,
         status: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trace_id: Option<String>,
+    },
+    /// Этап исполнения сделки (quoted → built → sent → confirmed/failed)
+    Execution(ExecutionReceipt),
+    /// Смена конфигурации, режима работы или состояния защитных механизмов
+    Safety(SafetyEvent),
+    /// Снимок цен прогона поиска
+    Prices {
+        snapshot_seq: u64,
+        taken_at: String,
+        /// Слот снимка аккаунтов, по которому считались цены
+        accounts_slot: Option<u64>,
+        /// Цены по парам BASE/QUOTE
+        pairs: BTreeMap<String, Vec<WsVenuePrice>>,
+        /// Количество возможностей по этим ценам
+        opportunities: usize,
     },
     Metrics {
         total_trades: u64,
@@ -44,9 +71,104 @@ pub enum WsMessage {
     },
     Error {
         message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trace_id: Option<String>,
     },
 }
 
+/// Цена пары на DEX в снимке
+#[derive(Debug, Serialize, Clone)]
+pub struct WsVenuePrice {
+    pub dex: String,
+    pub price: String,
+    pub fetched_at: String,
+    /// Слот, на котором прочитаны аккаунты пула (null — DEX не сообщает слот)
+    pub slot: Option<u64>,
+}
+
+impl From<&PriceSnapshot> for WsMessage {
+    fn from(snapshot: &PriceSnapshot) -> Self {
+        WsMessage::Prices {
+            snapshot_seq: snapshot.seq,
+            taken_at: snapshot.taken_at.to_rfc3339(),
+            accounts_slot: snapshot.accounts_slot,
+            pairs: snapshot.pairs.iter()
+                .map(|(pair, prices)| {
+                    let prices = prices.iter()
+                        .map(|price| WsVenuePrice {
+                            dex: price.dex.clone(),
+                            price: price.price.to_string(),
+                            fetched_at: price.fetched_at.to_rfc3339(),
+                            slot: price.slot,
+                        })
+                        .collect();
+                    (pair.clone(), prices)
+                })
+                .collect(),
+            opportunities: snapshot.opportunities.len(),
+        }
+    }
+}
+
+/// Сообщение WebSocket с номером и временем сервера
+///
+/// События (возможности, исполнение, сделки, переходы режима) нумеруются сквозным `seq`;
+/// периодические `Status` и `Metrics` и снимки `Prices` несут номер последнего события,
+/// по которому клиент замечает пропуск и догружает его через
+/// `/api/events/replay?since_seq=`.
+#[derive(Debug, Serialize)]
+pub struct WsEnvelope {
+    pub seq: u64,
+    pub server_time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub message: WsMessage,
+}
+
+impl WsEnvelope {
+    /// Периодическое сообщение с номером последнего события
+    fn heartbeat(seq: u64, message: WsMessage) -> Self {
+        Self { seq, server_time: Utc::now(), message }
+    }
+}
+
+impl From<SequencedEvent> for WsEnvelope {
+    fn from(event: SequencedEvent) -> Self {
+        Self { seq: event.seq, server_time: event.server_time, message: event.event.into() }
+    }
+}
+
+impl From<BotEvent> for WsMessage {
+    fn from(event: BotEvent) -> Self {
+        match event {
+            BotEvent::Opportunity(signal) => WsMessage::Opportunity {
+                from_dex: signal.from_dex,
+                to_dex: signal.to_dex,
+                base_token: signal.base_token,
+                quote_token: signal.quote_token,
+                profit_percent: signal.profit_percent.to_string(),
+                profit_percent_after_fees: signal.profit_percent_after_fees.to_string(),
+                route: signal.route.iter().map(|hop| hop.to_string()).collect(),
+                trace_id: signal.trace_id,
+            },
+            BotEvent::Execution(receipt) => WsMessage::Execution(receipt),
+            BotEvent::Safety(event) => WsMessage::Safety(event),
+            BotEvent::Trade(trade) => WsMessage::Trade {
+                id: trade.id.to_string(),
+                timestamp: trade.timestamp.to_rfc3339(),
+                from_dex: trade.from_dex,
+                to_dex: trade.to_dex,
+                profit_percent: trade.profit_percent.to_string(),
+                status: match trade.status {
+                    TradeStatus::Success => "success",
+                    TradeStatus::Failed => "failed",
+                    TradeStatus::Simulated => "simulated",
+                }.to_string(),
+                trace_id: trade.trace_id,
+            },
+        }
+    }
+}
+
 /// Параметры запроса для WebSocket
 #[derive(Deserialize)]
 pub struct WsQuery {
@@ -55,7 +177,7 @@ pub struct WsQuery {
 
 /// Обработчик WebSocket для обновлений
 pub async fn ws_updates_handler(
-    ws: WebSocket,
+    ws: WebSocketUpgrade,
     State(state): State<WebState>,
     Query(params): Query<WsQuery>,
 ) -> Response {
@@ -64,14 +186,14 @@ pub async fn ws_updates_handler(
         if !crate::web::auth::verify_ws_token(&token) {
             return axum::response::Response::builder()
                 .status(axum::http::StatusCode::UNAUTHORIZED)
-                .body("Unauthorized".into())
+                .body(axum::body::Body::from("Unauthorized"))
                 .unwrap()
                 .into_response();
         }
     } else {
         return axum::response::Response::builder()
             .status(axum::http::StatusCode::UNAUTHORIZED)
-            .body("Token required".into())
+            .body(axum::body::Body::from("Token required"))
             .unwrap()
             .into_response();
     }
@@ -83,17 +205,44 @@ pub async fn ws_updates_handler(
 async fn handle_updates_socket(socket: WebSocket, state: WebState) {
     let (mut sender, mut receiver) = socket.split();
     let mut interval_timer = interval(Duration::from_secs(5));
+    let mut events = state.monitor.events().subscribe();
+    let mut prices = state.monitor.prices().subscribe();
 
     loop {
         tokio::select! {
+            // Пересылка событий: возможности, исполнение сделок, сделки
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        // Клиент увидит пропуск по seq и догрузит события через replay
+                        log::debug!("WebSocket клиент отстал, пропущено событий: {}", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let json = serde_json::to_string(&WsEnvelope::from(event)).unwrap_or_default();
+                if sender.send(axum::extract::ws::Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            // Снимок цен каждого прогона поиска (цены у DEX не запрашиваются)
+            Ok(()) = prices.changed() => {
+                let Some(snapshot) = prices.borrow_and_update().clone() else { continue };
+                let seq = state.monitor.events().last_seq();
+                let json = serde_json::to_string(&WsEnvelope::heartbeat(seq, WsMessage::from(&*snapshot))).unwrap_or_default();
+                if sender.send(axum::extract::ws::Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
             // Отправка периодических обновлений
             _ = interval_timer.tick() => {
                 // Отправка статуса
                 let status = *state.bot_status.lock().await;
                 let status_str = match status {
                     crate::web::state::BotStatus::Running => "running",
-                    crate::web::state::Stopped => "stopped",
-                    crate::web::state::Error => "error",
+                    crate::web::state::BotStatus::Stopped => "stopped",
+                    crate::web::state::BotStatus::Error => "error",
                 };
 
                 let msg = WsMessage::Status {
@@ -102,7 +251,8 @@ async fn handle_updates_socket(socket: WebSocket, state: WebState) {
                     uptime_seconds: state.uptime_seconds(),
                 };
 
-                let json = serde_json::to_string(&msg).unwrap_or_default();
+                let seq = state.monitor.events().last_seq();
+                let json = serde_json::to_string(&WsEnvelope::heartbeat(seq, msg)).unwrap_or_default();
                 if sender.send(axum::extract::ws::Message::Text(json)).await.is_err() {
                     break;
                 }
@@ -116,7 +266,7 @@ async fn handle_updates_socket(socket: WebSocket, state: WebState) {
                     total_profit_sol: metrics.total_profit_sol.to_string(),
                 };
 
-                let metrics_json = serde_json::to_string(&metrics_msg).unwrap_or_default();
+                let metrics_json = serde_json::to_string(&WsEnvelope::heartbeat(seq, metrics_msg)).unwrap_or_default();
                 if sender.send(axum::extract::ws::Message::Text(metrics_json)).await.is_err() {
                     break;
                 }
@@ -139,7 +289,7 @@ async fn handle_updates_socket(socket: WebSocket, state: WebState) {
 
 /// Обработчик WebSocket для логов
 pub async fn ws_logs_handler(
-    ws: WebSocket,
+    ws: WebSocketUpgrade,
     State(_state): State<WebState>,
     Query(params): Query<WsQuery>,
 ) -> Response {
@@ -148,14 +298,14 @@ pub async fn ws_logs_handler(
         if !crate::web::auth::verify_ws_token(&token) {
             return axum::response::Response::builder()
                 .status(axum::http::StatusCode::UNAUTHORIZED)
-                .body("Unauthorized".into())
+                .body(axum::body::Body::from("Unauthorized"))
                 .unwrap()
                 .into_response();
         }
     } else {
         return axum::response::Response::builder()
             .status(axum::http::StatusCode::UNAUTHORIZED)
-            .body("Token required".into())
+            .body(axum::body::Body::from("Token required"))
             .unwrap()
             .into_response();
     }
@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+use crate::math::{self, Fee, MAX_TICK_INDEX, MIN_TICK_INDEX};
+use crate::pool_registry::{
+    WHIRLPOOL_ACCOUNT_SIZE, WHIRLPOOL_FEE_RATE_OFFSET, WHIRLPOOL_LIQUIDITY_OFFSET, WHIRLPOOL_MINT_A_OFFSET,
+    WHIRLPOOL_MINT_B_OFFSET,
+};
+
+/// Смещения полей аккаунта Whirlpool
+const TICK_SPACING_OFFSET: usize = 41;
+const SQRT_PRICE_OFFSET: usize = 65;
+const TICK_CURRENT_INDEX_OFFSET: usize = 81;
+const TOKEN_VAULT_A_OFFSET: usize = 133;
+const TOKEN_VAULT_B_OFFSET: usize = 213;
+
+/// Знаменатель fee_rate: сотые доли базисного пункта (3000 = 0.3%)
+const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Тиков в одном аккаунте TickArray
+pub const TICK_ARRAY_SIZE: i32 = 88;
+/// Размер аккаунта TickArray
+pub const TICK_ARRAY_ACCOUNT_SIZE: usize = 9988;
+/// Смещение start_tick_index (i32) в аккаунте TickArray
+const TICK_ARRAY_START_OFFSET: usize = 8;
+/// Смещение первого тика в аккаунте TickArray
+const TICK_ARRAY_TICKS_OFFSET: usize = 12;
+/// Размер тика: initialized, liquidity_net, liquidity_gross, fee_growth и reward_growths
+const TICK_SIZE: usize = 113;
+
+/// Сколько TickArray загружается в направлении свопа (как в программе Whirlpools)
+pub const TICK_ARRAYS_PER_SWAP: usize = 3;
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    data.get(offset..offset + N)
+        .with_context(|| format!("Данные короче смещения {}", offset + N))?
+        .try_into()
+        .context("Некорректная длина поля")
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+/// Аккаунт пула Orca Whirlpool
+#[derive(Debug, Clone, PartialEq)]
+pub struct Whirlpool {
+    pub tick_spacing: u16,
+    /// Комиссия в сотых долях базисного пункта
+    pub fee_rate: u16,
+    /// Активная ликвидность в текущем диапазоне тиков
+    pub liquidity: u128,
+    /// sqrt-цена (B за A в минимальных единицах) в формате Q64.64
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+}
+
+impl Whirlpool {
+    /// Разбор данных аккаунта Whirlpool
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() as u64 != WHIRLPOOL_ACCOUNT_SIZE {
+            anyhow::bail!(
+                "Размер аккаунта {} байт не совпадает с Whirlpool ({} байт)",
+                data.len(), WHIRLPOOL_ACCOUNT_SIZE
+            );
+        }
+        let pool = Self {
+            tick_spacing: u16::from_le_bytes(read_bytes(data, TICK_SPACING_OFFSET)?),
+            fee_rate: u16::from_le_bytes(read_bytes(data, WHIRLPOOL_FEE_RATE_OFFSET)?),
+            liquidity: u128::from_le_bytes(read_bytes(data, WHIRLPOOL_LIQUIDITY_OFFSET)?),
+            sqrt_price: u128::from_le_bytes(read_bytes(data, SQRT_PRICE_OFFSET)?),
+            tick_current_index: i32::from_le_bytes(read_bytes(data, TICK_CURRENT_INDEX_OFFSET)?),
+            token_mint_a: read_pubkey(data, WHIRLPOOL_MINT_A_OFFSET)?,
+            token_vault_a: read_pubkey(data, TOKEN_VAULT_A_OFFSET)?,
+            token_mint_b: read_pubkey(data, WHIRLPOOL_MINT_B_OFFSET)?,
+            token_vault_b: read_pubkey(data, TOKEN_VAULT_B_OFFSET)?,
+        };
+        if pool.tick_spacing == 0 {
+            anyhow::bail!("Нулевой tick_spacing в аккаунте Whirlpool");
+        }
+        Ok(pool)
+    }
+
+    /// Комиссия пула
+    pub fn fee(&self) -> Result<Fee> {
+        Fee::new(u64::from(self.fee_rate), FEE_RATE_DENOMINATOR).context("Некорректная комиссия Whirlpool")
+    }
+
+    /// Тиков в одном TickArray пула
+    fn ticks_per_array(&self) -> i32 {
+        TICK_ARRAY_SIZE * i32::from(self.tick_spacing)
+    }
+
+    /// start_tick_index TickArray, нужных для свопа в направлении a_to_b
+    ///
+    /// Первый — массив с текущим тиком, следующие — по направлению движения цены.
+    pub fn tick_array_starts(&self, a_to_b: bool) -> Vec<i32> {
+        let span = self.ticks_per_array();
+        let current = self.tick_current_index.div_euclid(span) * span;
+        let step = if a_to_b { -span } else { span };
+        (0..TICK_ARRAYS_PER_SWAP as i32)
+            .map(|i| current + step * i)
+            .filter(|start| *start + span > MIN_TICK_INDEX && *start <= MAX_TICK_INDEX)
+            .collect()
+    }
+}
+
+/// Адрес (PDA) аккаунта TickArray
+pub fn tick_array_address(program_id: &Pubkey, whirlpool: &Pubkey, start_tick_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"tick_array", whirlpool.as_ref(), start_tick_index.to_string().as_bytes()],
+        program_id,
+    ).0
+}
+
+/// Инициализированный тик: изменение ликвидности при пересечении слева направо
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    pub index: i32,
+    pub liquidity_net: i128,
+}
+
+/// Аккаунт TickArray: инициализированные тики одного диапазона
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickArray {
+    pub start_tick_index: i32,
+    /// Инициализированные тики по возрастанию индекса
+    pub ticks: Vec<Tick>,
+}
+
+impl TickArray {
+    /// Разбор данных аккаунта TickArray пула с шагом tick_spacing
+    pub fn parse(data: &[u8], tick_spacing: u16) -> Result<Self> {
+        if data.len() != TICK_ARRAY_ACCOUNT_SIZE {
+            anyhow::bail!(
+                "Размер аккаунта {} байт не совпадает с TickArray ({} байт)",
+                data.len(), TICK_ARRAY_ACCOUNT_SIZE
+            );
+        }
+        let start_tick_index = i32::from_le_bytes(read_bytes(data, TICK_ARRAY_START_OFFSET)?);
+        let mut ticks = Vec::new();
+        for i in 0..TICK_ARRAY_SIZE {
+            let offset = TICK_ARRAY_TICKS_OFFSET + i as usize * TICK_SIZE;
+            if data[offset] == 0 {
+                continue;
+            }
+            ticks.push(Tick {
+                index: start_tick_index + i * i32::from(tick_spacing),
+                liquidity_net: i128::from_le_bytes(read_bytes(data, offset + 1)?),
+            });
+        }
+        Ok(Self { start_tick_index, ticks })
+    }
+}
+
+/// Результат котировки свопа в Whirlpool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    /// sqrt-цена после свопа
+    pub sqrt_price: u128,
+}
+
+/// Котировка свопа amount_in с учётом концентрированной ликвидности
+///
+/// Своп идёт по инициализированным тикам из `tick_arrays` (загруженных
+/// по `Whirlpool::tick_array_starts`), ликвидность меняется на каждом
+/// пересечённом тике. Комиссия удерживается с входа каждого шага. Если вход
+/// не исчерпан к концу загруженных массивов — ошибка, а не заниженная цена.
+pub fn quote_exact_in(pool: &Whirlpool, tick_arrays: &[TickArray], amount_in: u64, a_to_b: bool) -> Result<SwapQuote> {
+    let fee = pool.fee()?;
+    let span = pool.ticks_per_array();
+
+    // Тики в направлении свопа: A->B — не выше текущего по убыванию, B->A — выше по возрастанию
+    let mut ticks: Vec<Tick> = tick_arrays.iter()
+        .flat_map(|array| array.ticks.iter().copied())
+        .filter(|tick| if a_to_b { tick.index <= pool.tick_current_index } else { tick.index > pool.tick_current_index })
+        .collect();
+    if a_to_b {
+        ticks.sort_by_key(|tick| std::cmp::Reverse(tick.index));
+    } else {
+        ticks.sort_by_key(|tick| tick.index);
+    }
+    // Граница загруженного диапазона: дальше ликвидность неизвестна
+    let boundary = tick_arrays.iter()
+        .map(|array| if a_to_b { array.start_tick_index } else { array.start_tick_index + span })
+        .reduce(|a, b| if a_to_b { a.min(b) } else { a.max(b) })
+        .context("Не загружено ни одного TickArray")?
+        .clamp(MIN_TICK_INDEX, MAX_TICK_INDEX);
+
+    let mut remaining = amount_in;
+    let mut amount_out: u128 = 0;
+    let mut fee_amount: u64 = 0;
+    let mut sqrt_price = pool.sqrt_price;
+    let mut liquidity = pool.liquidity;
+
+    let targets = ticks.iter()
+        .take_while(|tick| if a_to_b { tick.index >= boundary } else { tick.index <= boundary })
+        .map(|tick| (tick.index, Some(tick.liquidity_net)))
+        .chain(std::iter::once((boundary, None)));
+    for (target_tick, liquidity_net) in targets {
+        if remaining == 0 {
+            break;
+        }
+        let target_sqrt_price = math::sqrt_price_from_tick(target_tick)?;
+        let remaining_less_fee = fee.deduct(remaining)?;
+        let max_in = if a_to_b {
+            math::amount_a_delta(target_sqrt_price, sqrt_price, liquidity, true)?
+        } else {
+            math::amount_b_delta(sqrt_price, target_sqrt_price, liquidity, true)?
+        };
+
+        if u128::from(remaining_less_fee) >= max_in {
+            // Шаг доходит до тика: вход max_in плюс комиссия на него
+            let step_in = u64::try_from(max_in).context("Вход шага не помещается в u64")?;
+            let step_fee = math::mul_div_ceil(step_in, fee.numerator, fee.denominator - fee.numerator)?;
+            amount_out += if a_to_b {
+                math::amount_b_delta(target_sqrt_price, sqrt_price, liquidity, false)?
+            } else {
+                math::amount_a_delta(sqrt_price, target_sqrt_price, liquidity, false)?
+            };
+            remaining = remaining.saturating_sub(step_in.saturating_add(step_fee));
+            fee_amount = fee_amount.saturating_add(step_fee);
+            sqrt_price = target_sqrt_price;
+
+            match liquidity_net {
+                Some(net) => {
+                    let net = if a_to_b { -net } else { net };
+                    liquidity = liquidity.checked_add_signed(net)
+                        .context("Некорректная ликвидность при пересечении тика")?;
+                }
+                None if remaining > 0 => {
+                    anyhow::bail!("Недостаточно ликвидности в загруженных TickArray для свопа {}", amount_in);
+                }
+                None => {}
+            }
+        } else {
+            // Вход исчерпан внутри диапазона
+            let next_sqrt_price = if a_to_b {
+                math::next_sqrt_price_from_a_input(sqrt_price, liquidity, remaining_less_fee)?
+            } else {
+                math::next_sqrt_price_from_b_input(sqrt_price, liquidity, remaining_less_fee)?
+            };
+            amount_out += if a_to_b {
+                math::amount_b_delta(next_sqrt_price, sqrt_price, liquidity, false)?
+            } else {
+                math::amount_a_delta(sqrt_price, next_sqrt_price, liquidity, false)?
+            };
+            fee_amount = fee_amount.saturating_add(remaining - remaining_less_fee);
+            remaining = 0;
+            sqrt_price = next_sqrt_price;
+        }
+    }
+
+    Ok(SwapQuote {
+        amount_out: u64::try_from(amount_out).context("Выход свопа не помещается в u64")?,
+        fee_amount,
+        sqrt_price,
+    })
+}
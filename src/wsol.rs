@@ -0,0 +1,56 @@
+use anyhow::Result;
+use solana_sdk::{instruction::Instruction, system_instruction};
+use crate::wallet::Wallet;
+
+/// Символ нативного SOL: своп с ним оборачивает SOL в WSOL и разворачивает обратно
+///
+/// Символ `WSOL` означает уже обёрнутые токены на ATA кошелька и обрабатывается
+/// как обычный SPL токен.
+pub const NATIVE_SOL_SYMBOL: &str = "SOL";
+
+/// Оборачивается ли токен свопа из нативного SOL
+pub fn is_native_sol(symbol: &str) -> bool {
+    symbol == NATIVE_SOL_SYMBOL
+}
+
+/// Обёртка SOL вокруг свопа через временный ATA WSOL
+///
+/// Перед свопом ATA WSOL создаётся (идемпотентно), при продаже SOL на него
+/// переводится сумма свопа и выполняется `sync_native`. После свопа аккаунт
+/// закрывается: остаток WSOL и рента возвращаются на кошелёк нативным SOL.
+/// Инструкции идут в одной транзакции со свопом, поэтому при неудаче
+/// транзакции SOL не остаётся обёрнутым.
+#[derive(Debug, Default)]
+pub struct WsolWrap {
+    /// Инструкции перед свопом
+    pub wrap: Vec<Instruction>,
+    /// Инструкции после свопа
+    pub unwrap: Vec<Instruction>,
+}
+
+impl WsolWrap {
+    /// Обёртка для свопа `from_token -> to_token` на `amount_in` минимальных единиц
+    ///
+    /// Пустая, если ни одна сторона свопа не нативный SOL.
+    pub fn for_swap(wallet: &Wallet, from_token: &str, to_token: &str, amount_in: u64) -> Result<Self> {
+        if !is_native_sol(from_token) && !is_native_sol(to_token) {
+            return Ok(Self::default());
+        }
+
+        let owner = wallet.pubkey();
+        let native_mint = spl_token::native_mint::id();
+        let account = wallet.associated_token_address(&native_mint);
+
+        let mut wrap = vec![wallet.create_token_account_instruction(&native_mint)];
+        if is_native_sol(from_token) {
+            wrap.push(system_instruction::transfer(owner, &account, amount_in));
+            wrap.push(spl_token::instruction::sync_native(&spl_token::id(), &account)?);
+        }
+        let unwrap = vec![spl_token::instruction::close_account(&spl_token::id(), &account, owner, owner, &[])?];
+
+        // Аккаунт закрывается транзакцией: проверка ATA перед следующим свопом нужна заново
+        wallet.forget_token_account(&account);
+        log::debug!("Своп {} -> {}: SOL оборачивается через временный ATA WSOL {}", from_token, to_token, account);
+        Ok(Self { wrap, unwrap })
+    }
+}
@@ -122,7 +122,9 @@ class ArbBotDashboard {
         try {
             const data = await this.apiCall('/balance');
             document.getElementById('solBalance').textContent = parseFloat(data.sol_balance).toFixed(4) + ' SOL';
-            document.getElementById('usdEquivalent').textContent = '$' + parseFloat(data.usd_equivalent).toFixed(2);
+            document.getElementById('usdEquivalent').textContent = data.usd_equivalent === null
+                ? '—'
+                : '$' + parseFloat(data.usd_equivalent).toFixed(2);
         } catch (error) {
             console.error('Ошибка обновления баланса:', error);
         }
@@ -117,7 +117,7 @@ async fn test_devnet_wallet_initialization() -> Result<()> {
     log::info!("✅ Кошелёк инициализирован: {}", wallet.pubkey());
 
     // Получение баланса
-    let balance = wallet.get_balance(&config.network.rpc_url).await
+    let balance = wallet.get_balance(&config.network).await
         .context("Не удалось получить баланс")?;
 
     log::info!("✅ Баланс кошелька: {} lamports ({} SOL)", 
@@ -128,12 +128,60 @@ async fn test_devnet_wallet_initialization() -> Result<()> {
     // Если баланс нулевой, можно запросить airdrop (только для devnet)
     if balance == 0 {
         log::warn!("⚠️  Баланс равен нулю. Для тестирования можно запросить airdrop:");
-        log::warn!("   solana airdrop 1 {} --url devnet", wallet.pubkey());
+        log::warn!("   arb-bot devnet airdrop --sol 1");
     }
 
     Ok(())
 }
 
+/// Тест подготовки кошелька через devnet faucet
+#[tokio::test]
+#[ignore]
+async fn test_devnet_faucet_setup() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let config = create_devnet_config(&temp_dir)?;
+    let wallet = Wallet::new(&config)?;
+
+    // Пополнение кошелька до 1 SOL
+    let balance = arb_bot::devnet::ensure_funded(&config.network, &wallet, Decimal::from(1)).await
+        .context("Не удалось пополнить кошелёк через faucet")?;
+    assert!(balance >= 1_000_000_000, "Баланс должен быть не меньше 1 SOL");
+
+    // Создание ATA для devnet USDC (идемпотентно, повторный вызов не должен падать)
+    let usdc_devnet = vec!["4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU".to_string()];
+    let first = arb_bot::devnet::create_token_accounts(&config.network, &wallet, &usdc_devnet).await?;
+    let second = arb_bot::devnet::create_token_accounts(&config.network, &wallet, &usdc_devnet).await?;
+    assert_eq!(first, second, "Адрес ATA должен быть детерминированным");
+
+    log::info!("✅ Кошелёк подготовлен: {} lamports, ATA: {:?}", balance, first);
+
+    Ok(())
+}
+
+/// Тест создания тестового пула SPL Token Swap на devnet
+#[tokio::test]
+#[ignore]
+async fn test_devnet_seed_pool() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let config = create_devnet_config(&temp_dir)?;
+    let wallet = Wallet::new(&config)?;
+
+    arb_bot::devnet::ensure_funded(&config.network, &wallet, Decimal::from(1)).await
+        .context("Не удалось пополнить кошелёк через faucet")?;
+    let pool = arb_bot::devnet::seed_test_pool(&config.network, &wallet, 1_000_000_000).await?;
+
+    // Резервы пула на аккаунтах PDA пула
+    let client = RpcClient::new_with_commitment(config.network.rpc_url.clone(), CommitmentConfig::confirmed());
+    for reserve in [pool.token_a, pool.token_b] {
+        let balance = client.get_token_account_balance(&reserve)?;
+        assert_eq!(balance.amount, "1000000000");
+    }
+
+    log::info!("✅ Тестовый пул создан: {:?}", pool);
+
+    Ok(())
+}
+
 /// Тест инициализации DexManager на devnet
 #[tokio::test]
 #[ignore]
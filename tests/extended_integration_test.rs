@@ -9,6 +9,8 @@ use arb_bot::wallet::Wallet;
 use arb_bot::dex::DexManager;
 use arb_bot::monitor::Monitor;
 use arb_bot::arbitrage::ArbitrageEngine;
+use arb_bot::event_log::{BotEvent, SafetyEvent};
+use arb_bot::history::TradeStatus;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use tempfile::TempDir;
@@ -16,7 +18,7 @@ use tempfile::TempDir;
 #[path = "mocks.rs"]
 mod mocks;
 
-use mocks::{MockRpcClient, create_test_keypair};
+use mocks::{MockDex, MockRpcClient, SwapCall, create_test_keypair};
 
 /// Создание тестовой конфигурации
 fn create_test_config(temp_dir: &TempDir) -> Result<Config> {
@@ -72,7 +74,7 @@ min_balance_sol = 0.1
 async fn test_find_opportunities_with_mocks() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -96,7 +98,7 @@ async fn test_find_opportunities_with_mocks() -> Result<()> {
 async fn test_error_handling_price_fetch() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -138,7 +140,7 @@ async fn test_component_initialization() -> Result<()> {
     let config = create_test_config(&temp_dir)?;
 
     // Инициализация кошелька
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     assert!(!wallet.pubkey().to_string().is_empty());
 
     // Инициализация DexManager
@@ -186,7 +188,7 @@ async fn test_profit_calculation() -> Result<()> {
 async fn test_edge_cases() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -288,7 +290,7 @@ async fn test_min_profit_threshold() -> Result<()> {
     let mut config = create_test_config(&temp_dir)?;
     config.arbitrage.min_profit_percent = 1.0; // 1% минимальная прибыль
 
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -313,3 +315,1200 @@ async fn test_min_profit_threshold() -> Result<()> {
     Ok(())
 }
 
+
+/// Сквозной тест: расхождение цен -> возможность -> исполнение -> история -> счётчик неудач
+#[tokio::test]
+async fn test_trade_flow_with_mock_dexes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.arbitrage.buy_slippage_tolerance = Some(1.0);
+    config.arbitrage.sell_slippage_tolerance = Some(2.0);
+    config.safety.max_consecutive_failures = 3;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(100));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let history = monitor.trade_history();
+    let events = monitor.events().clone();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor);
+
+    // Одинаковые цены: возможностей нет
+    assert!(engine.find_opportunities().await?.is_empty());
+
+    // Цены разошлись на 3% (комиссии 0.25% + 0.3%): покупка на Raydium, продажа на Orca
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    let opportunities = engine.find_opportunities().await?;
+    assert_eq!(opportunities.len(), 1);
+    let opportunity = opportunities[0].clone();
+    assert_eq!((opportunity.from_dex.as_str(), opportunity.to_dex.as_str()), ("raydium", "orca"));
+    assert_eq!(opportunity.trade_amount, Decimal::ONE);
+    assert_eq!(opportunity.profit_percent_after_fees, Decimal::from_str("2.45")?);
+
+    // Покупка: 100 USDC -> SOL, котировка 1 SOL − 0.25% комиссии, минимум на 1% меньше;
+    // продажа — гарантированный выход покупки: котировка 103 − 0.3% комиссии, минимум на 2% меньше
+    // за 1 SOL, уменьшенный в той же пропорции
+    let executable = engine.executable(engine.allocate(opportunities));
+    for batch in engine.batches(executable) {
+        for result in engine.execute_batch(batch).await {
+            result?;
+        }
+    }
+    assert_eq!(raydium.swap_calls(), vec![SwapCall {
+        simulation_mode: true,
+        from_token: "USDC".to_string(),
+        to_token: "SOL".to_string(),
+        amount: Decimal::from(100),
+        min_output: Decimal::from_str("0.987525")?,
+    }]);
+    assert_eq!(orca.swap_calls(), vec![SwapCall {
+        simulation_mode: true,
+        from_token: "SOL".to_string(),
+        to_token: "USDC".to_string(),
+        amount: Decimal::from_str("0.987525")?,
+        min_output: Decimal::from_str("99.3817311795")?,
+    }]);
+
+    {
+        let trades = history.lock().await;
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].status, TradeStatus::Simulated);
+        assert_eq!(trades[0].amount, Decimal::ONE);
+        assert_eq!(trades[0].tx_signature.as_deref(), Some("mock_signature_orca_1"));
+        assert_eq!(trades[0].profit_quote, Decimal::from_str("2.45")?);
+        // Капитал — вход покупки в SOL; ноги разными транзакциями, время удержания записано
+        assert_eq!(trades[0].capital_sol, Some(Decimal::ONE));
+        assert!(trades[0].hold_ms.is_some());
+    }
+    let published = events.since(0, usize::MAX).events;
+    assert_eq!(published.iter().filter(|event| matches!(event.event, BotEvent::Trade(_))).count(), 1);
+    assert!(published.iter().any(|event| matches!(event.event, BotEvent::Execution(_))));
+
+    // Ошибки продажи: неудачи копятся, успех сбрасывает счётчик
+    orca.set_should_fail_swap(true);
+    for _ in 0..2 {
+        assert!(engine.execute_arbitrage(opportunity.clone()).await.is_err());
+    }
+    assert!(engine.halt_reason().is_none());
+    orca.set_should_fail_swap(false);
+    engine.execute_arbitrage(opportunity.clone()).await?;
+
+    // После сброса остановка наступает только на третьей неудаче подряд
+    orca.set_should_fail_swap(true);
+    for _ in 0..2 {
+        assert!(engine.execute_arbitrage(opportunity.clone()).await.is_err());
+        assert!(engine.halt_reason().is_none());
+    }
+    assert!(engine.execute_arbitrage(opportunity.clone()).await.is_err());
+    assert!(engine.halt_reason().is_some());
+
+    // Остановленный движок не вызывает DEX
+    let calls = raydium.get_swap_call_count();
+    assert!(engine.execute_arbitrage(opportunity.clone()).await.is_err());
+    assert_eq!(raydium.get_swap_call_count(), calls);
+
+    let trades = history.lock().await;
+    let statuses: Vec<TradeStatus> = trades.iter().map(|trade| trade.status).collect();
+    assert_eq!(statuses, vec![
+        TradeStatus::Simulated,
+        TradeStatus::Failed,
+        TradeStatus::Failed,
+        TradeStatus::Simulated,
+        TradeStatus::Failed,
+        TradeStatus::Failed,
+        TradeStatus::Failed,
+    ]);
+    assert!(trades[1].failure_reason.as_deref().is_some_and(|reason| reason.contains("Симуляция ошибки выполнения свопа")));
+
+    Ok(())
+}
+
+/// Отмена сделки симуляцией перед отправкой: не неудача подряд, логи симуляции в записи о сделке
+#[tokio::test]
+async fn test_presend_rejection_does_not_count_as_failure() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.safety.max_consecutive_failures = 2;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let history = monitor.trade_history();
+    let events = monitor.events().clone();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor);
+    let opportunity = engine.find_opportunities().await?.remove(0);
+
+    // Симуляция продажи упала: сделки отменяются, сколько бы их ни было подряд
+    let logs = vec!["Program log: Error: exceeds desired slippage limit".to_string()];
+    orca.set_presend_rejection(Some(logs));
+    for _ in 0..3 {
+        assert!(engine.execute_arbitrage(opportunity.clone()).await.is_err());
+    }
+    assert!(engine.halt_reason().is_none());
+
+    {
+        let trades = history.lock().await;
+        assert_eq!(trades.len(), 3);
+        assert!(trades.iter().all(|trade| trade.status == TradeStatus::Failed));
+        assert_eq!(trades[0].simulation_logs, vec!["[sell] Program log: Error: exceeds desired slippage limit".to_string()]);
+        assert!(trades[0].failure_reason.as_deref().is_some_and(|reason| reason.starts_with("отменено до отправки: ")));
+    }
+
+    // Настоящие неудачи по-прежнему останавливают исполнение
+    orca.set_presend_rejection(None);
+    orca.set_should_fail_swap(true);
+    assert!(engine.execute_arbitrage(opportunity.clone()).await.is_err());
+    assert!(engine.halt_reason().is_none());
+    assert!(engine.execute_arbitrage(opportunity).await.is_err());
+    assert!(engine.halt_reason().is_some());
+
+    let trades = history.lock().await;
+    assert!(trades[3].simulation_logs.is_empty());
+
+    // Остановка и её снятие публикуются для панелей
+    engine.resume();
+    let kill_switch: Vec<bool> = events.since(0, usize::MAX).events.into_iter()
+        .filter_map(|event| match event.event {
+            BotEvent::Safety(SafetyEvent::KillSwitch { engaged, .. }) => Some(engaged),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(kill_switch, vec![true, false]);
+
+    Ok(())
+}
+
+/// Атомарное исполнение: обе ноги одной транзакцией, если оба DEX это поддерживают
+#[tokio::test]
+async fn test_atomic_execution_with_mock_dexes() -> Result<()> {
+    use arb_bot::config::LegGate;
+
+    let temp_dir = TempDir::new()?;
+    let config = create_test_config(&temp_dir)?;
+    assert!(config.arbitrage.atomic_execution);
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    raydium.set_atomic(true);
+    orca.set_atomic(true);
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let history = monitor.trade_history();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor);
+
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    engine.execute_arbitrage(opportunity.clone()).await?;
+    // Отдельные свопы не вызываются, у сделки нет условия перехода между ногами
+    assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (0, 0));
+
+    // DEX без сборки инструкций: сделка в два этапа
+    orca.set_atomic(false);
+    engine.execute_arbitrage(opportunity).await?;
+    assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (1, 1));
+
+    let trades = history.lock().await;
+    assert_eq!(trades[0].tx_signature.as_deref(), Some("simulated_signature_atomic"));
+    assert_eq!(trades[0].leg_gate, None);
+    assert_eq!(trades[1].tx_signature.as_deref(), Some("mock_signature_orca_1"));
+    assert_eq!(trades[1].leg_gate, Some(LegGate::Confirmed));
+    Ok(())
+}
+
+/// Продажа в два этапа без проверки полученного — на гарантированный выход покупки
+#[tokio::test]
+async fn test_two_step_sell_leg_sizing_with_mock_dexes() -> Result<()> {
+    use arb_bot::config::LegGate;
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    // Вне режима симуляции: продажа идёт ветвью оптимистичного перехода
+    config.safety.simulation_mode = false;
+    config.arbitrage.leg_gate = LegGate::Optimistic;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let history = monitor.trade_history();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor);
+
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    engine.execute_arbitrage(opportunity.clone()).await?;
+    assert_eq!(history.lock().await[0].leg_gate, Some(LegGate::Optimistic));
+
+    // Продаётся минимальный выход покупки, а не объём возможности; минимум продажи — в той же пропорции
+    let (buy, sell) = (raydium.swap_calls().remove(0), orca.swap_calls().remove(0));
+    assert_eq!((buy.from_token.as_str(), sell.from_token.as_str()), ("USDC", "SOL"));
+    assert!(!buy.simulation_mode && !sell.simulation_mode);
+    assert_eq!(sell.amount, buy.min_output);
+    assert!(sell.amount < opportunity.trade_amount);
+    let planned_min_sell = sell.min_output * opportunity.trade_amount / sell.amount;
+    assert!(planned_min_sell > sell.min_output);
+    Ok(())
+}
+
+/// Менеджер DEX общий для движка и веб-сервера: цены без блокировки движка, отключение DEX во время работы
+#[tokio::test]
+async fn test_shared_dex_manager_prices_and_toggling() -> Result<()> {
+    use arb_bot::schedule::{Schedule, ScheduleControl};
+    use arb_bot::store::Store;
+    use arb_bot::web::{create_state, server::create_router};
+    use base64::{engine::general_purpose, Engine as _};
+    use std::sync::Arc;
+
+    std::env::set_var("WEB_PASSWORD", "dex-test");
+    let credentials = format!("Basic {}", general_purpose::STANDARD.encode("admin:dex-test"));
+
+    let temp_dir = TempDir::new()?;
+    let config = create_test_config(&temp_dir)?;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor.clone());
+    let dex_manager = engine.dex_manager();
+    assert_eq!(engine.find_opportunities().await?.len(), 1);
+
+    // Отключённый DEX не участвует в поиске, незарегистрированный — ошибка
+    dex_manager.set_enabled("orca", false)?;
+    assert!(engine.find_opportunities().await?.is_empty());
+    assert!(dex_manager.set_enabled("jupiter", false).is_err());
+
+    let state = create_state(
+        config.clone(),
+        monitor,
+        engine.wallet(),
+        Arc::new(tokio::sync::Mutex::new(engine)),
+        ScheduleControl::new(Schedule::from_config(&config.schedule)?),
+        Store::open_dir(&temp_dir.path().join("data")).await?,
+        Arc::new(arb_bot::venue_limiter::VenueLimiter::new(&config.dex)),
+        dex_manager.clone(),
+    );
+    let engine = state.arbitrage_engine.clone();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, create_router(state)).await });
+    let client = reqwest::Client::new();
+    let request = |method: reqwest::Method, path: &str| {
+        client.request(method, format!("http://{}{}", addr, path)).header("authorization", credentials.as_str())
+    };
+
+    let dexes: serde_json::Value = request(reqwest::Method::POST, "/api/dexes/orca/enable").send().await?.json().await?;
+    assert_eq!(dexes["dexes"][1], serde_json::json!({"name": "orca", "enabled": true, "venue_type": "clmm"}));
+    assert!(dex_manager.is_enabled("orca"));
+    // Возврат DEX в работу виден панелям событием; повторное включение события не даёт
+    request(reqwest::Method::POST, "/api/dexes/orca/enable").send().await?;
+    let replay: serde_json::Value = request(reqwest::Method::GET, "/api/events/replay?since_seq=0").send().await?.json().await?;
+    assert_eq!(replay["events"].as_array().map(Vec::len), Some(1));
+    let event = &replay["events"][0];
+    assert_eq!((&event["type"], &event["kind"]), (&serde_json::json!("Safety"), &serde_json::json!("venue_benched")));
+    assert_eq!((&event["venue"], &event["benched"]), (&serde_json::json!("orca"), &serde_json::json!(false)));
+    let response = request(reqwest::Method::POST, "/api/dexes/jupiter/disable").send().await?;
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    // Цены отвечают, пока торговый цикл держит движок
+    let _guard = engine.lock().await;
+    let prices: serde_json::Value = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        async { request(reqwest::Method::GET, "/api/prices?pair=SOL/USDC").send().await?.json().await },
+    ).await??;
+    assert_eq!(prices["pairs"][0]["pair"], "SOL/USDC");
+    assert_eq!(prices["pairs"][0]["prices"], serde_json::json!([
+        {"dex": "raydium", "price": "100"},
+        {"dex": "orca", "price": "103"},
+    ]));
+
+    dex_manager.set_enabled("raydium", false)?;
+    let prices: serde_json::Value = request(reqwest::Method::GET, "/api/prices").send().await?.json().await?;
+    assert_eq!(prices["pairs"][0]["prices"], serde_json::json!([{"dex": "orca", "price": "103"}]));
+    Ok(())
+}
+
+/// Снимок цен прогона общий для движка, /api/opportunities и /ws/updates: API не запрашивает цены у DEX
+#[tokio::test]
+async fn test_price_snapshot_shared_with_api_and_websocket() -> Result<()> {
+    use arb_bot::schedule::{Schedule, ScheduleControl};
+    use arb_bot::store::Store;
+    use arb_bot::web::{create_state, server::create_router};
+    use base64::{engine::general_purpose, Engine as _};
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::Message;
+
+    std::env::set_var("WEB_PASSWORD", "snapshot-test");
+    let encoded = general_purpose::STANDARD.encode("admin:snapshot-test");
+    let credentials = format!("Basic {}", encoded);
+
+    let temp_dir = TempDir::new()?;
+    let config = create_test_config(&temp_dir)?;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor.clone());
+    let dex_manager = engine.dex_manager();
+    assert!(monitor.prices().latest().is_none());
+
+    assert_eq!(engine.find_opportunities().await?.len(), 1);
+    let snapshot = monitor.prices().latest().expect("снимок после прогона");
+    assert_eq!((snapshot.seq, snapshot.opportunities.len()), (1, 1));
+    let prices: Vec<(&str, Decimal)> = snapshot.pairs["SOL/USDC"].iter().map(|price| (price.dex.as_str(), price.price)).collect();
+    assert_eq!(prices, vec![("raydium", Decimal::from(100)), ("orca", Decimal::from(103))]);
+
+    let state = create_state(
+        config.clone(),
+        monitor.clone(),
+        engine.wallet(),
+        Arc::new(tokio::sync::Mutex::new(engine)),
+        ScheduleControl::new(Schedule::from_config(&config.schedule)?),
+        Store::open_dir(&temp_dir.path().join("data")).await?,
+        Arc::new(arb_bot::venue_limiter::VenueLimiter::new(&config.dex)),
+        dex_manager,
+    );
+    let engine = state.arbitrage_engine.clone();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, create_router(state)).await });
+    let client = reqwest::Client::new();
+    let get_opportunities = || async {
+        client.get(format!("http://{}/api/opportunities", addr))
+            .header("authorization", credentials.as_str())
+            .send().await?
+            .json::<serde_json::Value>().await
+    };
+
+    // Ответ из снимка: DEX недоступны, движок занят торговым циклом
+    raydium.set_should_fail_get_price(true);
+    orca.set_should_fail_get_price(true);
+    let guard = engine.lock().await;
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), get_opportunities()).await??;
+    assert_eq!((&response["count"], &response["snapshot_seq"]), (&serde_json::json!(1), &serde_json::json!(1)));
+    assert_eq!(response["prices_at"], snapshot.taken_at.to_rfc3339());
+
+    // Новый прогон рассылается подписчикам WebSocket и сменяет снимок API
+    let token = encoded.replace('+', "%2B").replace('/', "%2F").replace('=', "%3D");
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/updates?token={}", addr, token)).await?;
+    async fn next_of_type<S>(socket: &mut S, kind: &str) -> Result<serde_json::Value>
+    where
+        S: futures::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        while let Some(message) = socket.next().await {
+            if let Message::Text(text) = message? {
+                let message: serde_json::Value = serde_json::from_str(&text)?;
+                if message["type"] == kind {
+                    return Ok(message);
+                }
+            }
+        }
+        anyhow::bail!("WebSocket закрыт сервером")
+    }
+    // Первый статус — соединение подписано на снимки
+    tokio::time::timeout(std::time::Duration::from_secs(5), next_of_type(&mut socket, "Status")).await??;
+    assert!(guard.find_opportunities().await?.is_empty());
+    drop(guard);
+    let message = tokio::time::timeout(std::time::Duration::from_secs(5), next_of_type(&mut socket, "Prices")).await??;
+    assert_eq!((&message["snapshot_seq"], &message["opportunities"]), (&serde_json::json!(2), &serde_json::json!(0)));
+    assert_eq!(message["pairs"]["SOL/USDC"], serde_json::json!([]));
+
+    let response = get_opportunities().await?;
+    assert_eq!((&response["count"], &response["snapshot_seq"]), (&serde_json::json!(0), &serde_json::json!(2)));
+    Ok(())
+}
+
+/// Журнал изменений настроек: DEX и расписание через API с автором и значениями до и после
+#[tokio::test]
+async fn test_config_history_api() -> Result<()> {
+    use arb_bot::schedule::{Schedule, ScheduleControl};
+    use arb_bot::store::Store;
+    use arb_bot::web::{create_state, server::create_router};
+    use base64::{engine::general_purpose, Engine as _};
+    use std::sync::Arc;
+
+    std::env::set_var("WEB_PASSWORD", "dex-test");
+    let credentials = format!("Basic {}", general_purpose::STANDARD.encode("admin:dex-test"));
+
+    let temp_dir = TempDir::new()?;
+    let config = create_test_config(&temp_dir)?;
+    let wallet = Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(MockDex::new("raydium")), Box::new(MockDex::new("orca"))]);
+    let monitor = Monitor::new(&config);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor.clone());
+    let dex_manager = engine.dex_manager();
+    let state = create_state(
+        config.clone(),
+        monitor,
+        engine.wallet(),
+        Arc::new(tokio::sync::Mutex::new(engine)),
+        ScheduleControl::new(Schedule::from_config(&config.schedule)?),
+        Store::open_dir(&temp_dir.path().join("data")).await?,
+        Arc::new(arb_bot::venue_limiter::VenueLimiter::new(&config.dex)),
+        dex_manager,
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move { axum::serve(listener, create_router(state)).await });
+    let client = reqwest::Client::new();
+    let request = |method: reqwest::Method, path: &str| {
+        client.request(method, format!("http://{}{}", addr, path)).header("authorization", credentials.as_str())
+    };
+
+    request(reqwest::Method::POST, "/api/dexes/orca/disable").send().await?.error_for_status()?;
+    // Повторное отключение ничего не меняет и в журнал не попадает
+    request(reqwest::Method::POST, "/api/dexes/orca/disable").send().await?.error_for_status()?;
+    request(reqwest::Method::POST, "/api/schedule/override")
+        .json(&serde_json::json!({"mode": "pause"}))
+        .send().await?.error_for_status()?;
+
+    let history: serde_json::Value = request(reqwest::Method::GET, "/api/config/history").send().await?.json().await?;
+    assert_eq!(history["total"], 2);
+    let changes = &history["changes"];
+    assert_eq!((&changes[0]["source"], &changes[0]["actor"]), (&serde_json::json!("schedule"), &serde_json::json!("admin")));
+    assert_eq!(changes[0]["changes"], serde_json::json!([{"path": "schedule.override", "old": "auto", "new": "pause"}]));
+    assert_eq!(changes[1]["source"], "dex");
+    assert_eq!(changes[1]["changes"], serde_json::json!([{"path": "dex.orca.enabled", "old": true, "new": false}]));
+
+    let limited: serde_json::Value = request(reqwest::Method::GET, "/api/config/history?limit=1").send().await?.json().await?;
+    assert_eq!((limited["total"].as_u64(), limited["changes"].as_array().map(Vec::len)), (Some(2), Some(1)));
+    let empty: serde_json::Value = request(reqwest::Method::GET, "/api/config/history?from=2099-01-01T00:00:00Z").send().await?.json().await?;
+    assert_eq!(empty["total"], 0);
+    Ok(())
+}
+
+/// Пара с резко изменившимися резервами пула откладывается на один прогон
+#[tokio::test]
+async fn test_reserve_jump_defers_pair() -> Result<()> {
+    use arb_bot::clock::ManualClock;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.safety.max_reserve_change_percent = 5.0;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    raydium.set_reserves("SOL", "USDC", (1_000_000_000_000, 100_000_000_000));
+
+    let clock = ManualClock::new(chrono::Utc::now());
+    let wallet = Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config))
+        .with_clock(Arc::new(clock.clone()));
+    let interval = Duration::from_millis(config.monitoring.check_interval_ms);
+
+    // Первое наблюдение и изменение в пределах порога
+    assert_eq!(engine.find_opportunities().await?.len(), 1);
+    clock.advance(interval);
+    raydium.set_reserves("SOL", "USDC", (1_040_000_000_000, 96_200_000_000));
+    assert_eq!(engine.find_opportunities().await?.len(), 1);
+
+    // Крупный своп между прогонами: пара пропускает прогон
+    clock.advance(interval);
+    raydium.set_reserves("SOL", "USDC", (1_300_000_000_000, 77_000_000_000));
+    assert!(engine.find_opportunities().await?.is_empty());
+
+    // Следующий прогон сравнивает с новыми резервами
+    clock.advance(interval);
+    assert_eq!(engine.find_opportunities().await?.len(), 1);
+
+    // Наблюдение старше двух интервалов не сравнивается
+    clock.advance(interval * 3);
+    raydium.set_reserves("SOL", "USDC", (2_000_000_000_000, 50_000_000_000));
+    assert_eq!(engine.find_opportunities().await?.len(), 1);
+    Ok(())
+}
+
+/// Объём сделки по резервам пулов: мелкий пул продажи ограничивает объём
+#[tokio::test]
+async fn test_trade_size_follows_pool_reserves() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.arbitrage.max_trade_amount_sol = 100.0;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config));
+
+    // Резервы неизвестны: влияния на цену нет, объём — максимальный
+    assert_eq!(engine.find_opportunities().await?[0].trade_amount, Decimal::from(100));
+
+    // 1000 SOL / 100 000 USDC на Raydium и 10 SOL / 1030 USDC на Orca
+    raydium.set_reserves("SOL", "USDC", (1_000_000_000_000, 100_000_000_000));
+    orca.set_reserves("SOL", "USDC", (10_000_000_000, 1_030_000_000));
+    let amount = engine.find_opportunities().await?[0].trade_amount;
+    assert!(amount > Decimal::ZERO && amount < Decimal::ONE, "объём {}", amount);
+    Ok(())
+}
+
+/// Объём на DEX без резервов пула ограничен ликвидностью, которую сообщает адаптер
+#[tokio::test]
+async fn test_trade_size_limited_by_dex_liquidity() -> Result<()> {
+    use arb_bot::arbitrage::SandboxTrade;
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.arbitrage.max_trade_amount_sol = 100.0;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config));
+
+    // Ликвидность неизвестна: объём ограничен только max_trade_amount_sol, влияния на цену нет
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    assert_eq!(opportunity.trade_amount, Decimal::from(100));
+    assert_eq!(opportunity.price_impact.total(), Decimal::ZERO);
+
+    // Покупка на Raydium: в asks 40 SOL — объём съедает лишь часть глубины,
+    // влияние на цену покупки меньше спреда
+    raydium.set_liquidity("SOL", "USDC", (40_000_000_000, 1_000_000_000_000));
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    assert!(opportunity.trade_amount > Decimal::ZERO && opportunity.trade_amount < Decimal::from(40));
+    assert!(opportunity.price_impact.buy_percent > Decimal::ZERO);
+    assert_eq!(opportunity.price_impact.sell_percent, Decimal::ZERO);
+    assert!(opportunity.price_impact.total() < opportunity.profit_percent);
+    let buy_only = opportunity.trade_amount;
+
+    // Продажа на Orca: bids на 2060 USDC (20 SOL по цене 103) — объём ещё меньше
+    orca.set_liquidity("SOL", "USDC", (1_000_000_000_000, 2_060_000_000));
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    assert!(opportunity.trade_amount < buy_only);
+    assert!(opportunity.price_impact.sell_percent > Decimal::ZERO);
+
+    // Объём на всю глубину: влияние на цену съедает спред 3%
+    let trade = SandboxTrade {
+        base_token: "SOL".to_string(),
+        quote_token: "USDC".to_string(),
+        buy_dex: "raydium".to_string(),
+        sell_dex: "orca".to_string(),
+        amount: Decimal::from(20),
+    };
+    let evaluation = engine.evaluate_trade(&trade).await?;
+    assert!(evaluation.opportunity.price_impact.total() >= evaluation.opportunity.profit_percent);
+    let check = evaluation.checks.iter().find(|check| check.name == "price_impact").unwrap();
+    assert!(!check.passed, "{}", check.detail);
+    assert!(!evaluation.would_execute());
+    // Минимальный выход отсчитывается от котировки с влиянием на цену, а не от цены DEX
+    let quote = evaluation.quote.expect("котировка ног");
+    assert!(quote.buy < Decimal::from(15), "{:?}", quote);
+    assert!(evaluation.min_output.buy < quote.buy && evaluation.min_output.sell < quote.sell);
+    Ok(())
+}
+
+/// Направления пар: запрещённое направление не находится и не исполняется
+#[tokio::test]
+async fn test_pair_directions_with_mock_dexes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.dex.directions.insert("SOL/USDC".to_string(), vec!["raydium->orca".to_string()]);
+    config.validate()?;
+    assert!(config.dex.direction_allowed("SOL/USDC", "raydium", "orca"));
+    assert!(!config.dex.direction_allowed("SOL/USDC", "orca", "raydium"));
+    assert!(config.dex.direction_allowed("SOL/USDT", "orca", "raydium"));
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config));
+
+    let mut opportunity = engine.find_opportunities().await?.remove(0);
+    assert_eq!((opportunity.from_dex.as_str(), opportunity.to_dex.as_str()), ("raydium", "orca"));
+
+    // Обратный спред не рассматривается
+    raydium.set_price("SOL", "USDC", Decimal::from(106));
+    assert!(engine.find_opportunities().await?.is_empty());
+
+    // Возможность в обход поиска (шина, API) не исполняется
+    std::mem::swap(&mut opportunity.from_dex, &mut opportunity.to_dex);
+    assert!(engine.executable(vec![opportunity.clone()]).is_empty());
+    let err = engine.execute_arbitrage(opportunity).await.unwrap_err();
+    assert!(err.to_string().contains("dex.directions"), "{:#}", err);
+    assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (0, 0));
+
+    // Маршрут с DEX вне enabled_dexes отклоняется при загрузке
+    config.dex.directions.insert("SOL/USDC".to_string(), vec!["orca->serum".to_string()]);
+    assert!(config.validate().is_err());
+    config.dex.directions.insert("SOL/USDC".to_string(), vec!["orca".to_string()]);
+    assert!(config.validate().is_err());
+    Ok(())
+}
+
+/// Статистика прогонов поиска: прогоны, цены, найденные, исполненные и пропущенные возможности
+#[tokio::test]
+async fn test_scan_stats_with_mock_dexes() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.safety.allowed_tokens = vec!["SOL".to_string(), "USDC".to_string()];
+    config.dex.trading_pairs.push("SOL/BONK".to_string());
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    raydium.set_price("SOL", "BONK", Decimal::from(100));
+    orca.set_price("SOL", "BONK", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor.clone());
+
+    // Поиск через API не считается прогоном торгового цикла
+    engine.find_opportunities().await?;
+    assert_eq!(monitor.scan_stats().snapshot(chrono::Utc::now()).cycles, 0);
+
+    let opportunities = engine.scan().await?;
+    assert_eq!(opportunities.len(), 2);
+    let batches = engine.batches(engine.allocate(engine.executable(opportunities)));
+    for batch in batches {
+        for result in engine.execute_batch(batch).await {
+            result?;
+        }
+    }
+
+    let metrics = monitor.scan_stats().snapshot(chrono::Utc::now());
+    assert_eq!((metrics.cycles, metrics.failed_cycles, metrics.cycles_per_minute), (1, 0, 1));
+    assert_eq!(metrics.average_prices_per_cycle, 4.0);
+    assert!(metrics.duration_ms.p50 > 0.0 && metrics.duration_ms.p99 <= metrics.duration_ms.max);
+    assert_eq!(
+        (metrics.opportunities_found, metrics.opportunities_executed, metrics.opportunities_skipped),
+        (2, 1, 1)
+    );
+    assert_eq!(metrics.skipped_by_reason.get("token_not_allowed"), Some(&1));
+
+    // Остановленное исполнение: возможности прогона пропускаются с причиной
+    let opportunity = engine.scan().await?.remove(0);
+    orca.set_should_fail_swap(true);
+    for _ in 0..config.safety.max_consecutive_failures {
+        let _ = engine.execute_batch(vec![opportunity.clone()]).await;
+    }
+    assert!(engine.halt_reason().is_some());
+    let _ = engine.execute_batch(vec![opportunity]).await;
+    let metrics = monitor.scan_stats().snapshot(chrono::Utc::now());
+    assert_eq!(metrics.cycles, 2);
+    assert_eq!(metrics.skipped_by_reason.get("halted"), Some(&1));
+    Ok(())
+}
+
+/// Абсолютный порог прибыли: прибыль после комиссий DEX и сетевой комиссии
+#[tokio::test]
+async fn test_min_profit_absolute_with_mock_dexes() -> Result<()> {
+    use arb_bot::arbitrage::ArbitrageOpportunity;
+    use arb_bot::profit::{net_profit, network_fee_sol};
+
+    // SOL/USDC: 1 SOL по 100 с прибылью 0.5% = 0.5 USDC, две подписи — 0.00001 SOL = 0.001 USDC
+    let opportunity = ArbitrageOpportunity {
+        from_dex: "raydium".to_string(),
+        to_dex: "orca".to_string(),
+        base_token: "SOL".to_string(),
+        quote_token: "USDC".to_string(),
+        buy_price: Decimal::from(100),
+        sell_price: Decimal::from(100),
+        profit_percent: Decimal::new(5, 1),
+        profit_percent_after_fees: Decimal::new(5, 1),
+        trade_amount: Decimal::ONE,
+        estimated_fees: Decimal::ZERO,
+        route: Vec::new(),
+        price_impact: Default::default(),
+        price_stamps: Default::default(),
+    };
+    assert_eq!(network_fee_sol(2), Decimal::new(1, 5));
+    let net = net_profit(&opportunity, network_fee_sol(2), Some(Decimal::ONE), None);
+    assert_eq!((net.quote, net.usd), (Some(Decimal::new(499, 3)), Some(Decimal::new(499, 3))));
+    // RAY/USDT без курса SOL: комиссию не пересчитать
+    let ray = ArbitrageOpportunity { base_token: "RAY".to_string(), quote_token: "USDT".to_string(), ..opportunity };
+    assert_eq!(net_profit(&ray, network_fee_sol(1), Some(Decimal::ONE), None).quote, None);
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let engine = |config: &Config| -> Result<ArbitrageEngine> {
+        let wallet = std::sync::Arc::new(Wallet::new(config)?);
+        let dex_manager = DexManager::from_dexes(config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+        Ok(ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(config)))
+    };
+    let found = engine(&config)?.find_opportunities().await?.remove(0);
+    let net = net_profit(&found, network_fee_sol(2), Some(Decimal::ONE), None).quote.expect("прибыль в USDC");
+    let net = rust_decimal::prelude::ToPrimitive::to_f64(&net).expect("f64");
+
+    // Порог выше прибыли после сетевой комиссии — возможность отсекается
+    config.arbitrage.min_profit_absolute.quote = Some(net + 0.001);
+    config.validate()?;
+    assert!(engine(&config)?.find_opportunities().await?.is_empty());
+    config.arbitrage.min_profit_absolute.quote = Some(net - 0.001);
+    assert_eq!(engine(&config)?.find_opportunities().await?.len(), 1);
+
+    // Порог в USD: USDC по паритету
+    config.arbitrage.min_profit_absolute.usd = Some(net + 0.01);
+    assert!(engine(&config)?.find_opportunities().await?.is_empty());
+
+    config.arbitrage.min_profit_absolute.usd = Some(0.0);
+    assert!(config.validate().is_err());
+    Ok(())
+}
+
+/// Прогон `run --once`: возможности, собранная атомарная транзакция и её симуляция в отчёте
+#[tokio::test]
+async fn test_dry_run_report_with_mock_dexes() -> Result<()> {
+    use arb_bot::config_check;
+    use arb_bot::dry_run;
+    use arb_bot::self_test::{CheckStatus, Simulator};
+    use arb_bot::trade_memo;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::pubkey::Pubkey;
+    use std::sync::Mutex;
+
+    /// Симуляция, принимающая только транзакцию исполнения: покупка, продажа, memo и проверка баланса
+    #[derive(Default)]
+    struct FakeSimulator {
+        swaps: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Simulator for FakeSimulator {
+        async fn simulate(&self, instructions: &[Instruction], _wallet: &Wallet) -> Result<Option<u64>> {
+            anyhow::ensure!(instructions.len() == 4, "ожидались две ноги, memo и проверка: {:?}", instructions);
+            let swaps: Vec<String> = instructions[..2].iter()
+                .map(|instruction| String::from_utf8(instruction.data.clone()))
+                .collect::<std::result::Result<_, _>>()?;
+            anyhow::ensure!(swaps[0].contains("USDC -> SOL") && swaps[1].contains("SOL -> USDC"), "{:?}", swaps);
+            anyhow::ensure!(instructions[2].program_id == trade_memo::program_id(), "нет memo сделки");
+            anyhow::ensure!(instructions[3].program_id == spl_token::id(), "нет проверки баланса");
+            *self.swaps.lock().unwrap() = swaps;
+            Ok(Some(85_000))
+        }
+
+        async fn token_balance(&self, _token_account: Pubkey) -> Result<u64> {
+            Ok(1_000_000)
+        }
+    }
+    let simulator = FakeSimulator::default();
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.safety.simulation_mode = true;
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config));
+
+    // DEX не собирают инструкции: симуляция пропущена, отчёт пройден
+    let report = dry_run::run(&engine, config_check::check_config(&config), None, &simulator).await;
+    assert!(report.passed, "{:?}", report);
+    assert_eq!(report.opportunities.len(), 1);
+    assert_eq!(report.opportunities[0].simulation.status, CheckStatus::Skipped);
+
+    // Атомарная транзакция собрана из двух ног и симулирована
+    raydium.set_atomic(true);
+    orca.set_atomic(true);
+    let report = dry_run::run(&engine, config_check::check_config(&config), None, &simulator).await;
+    assert!(report.passed, "{:?}", report);
+    let opportunity = &report.opportunities[0];
+    assert_eq!(opportunity.simulation.detail, "85000 compute units");
+    assert_eq!(opportunity.legs.len(), 2);
+    assert_eq!((opportunity.legs[0].dex.as_str(), opportunity.legs[1].dex.as_str()), ("raydium", "orca"));
+    assert!(opportunity.legs.iter().all(|leg| leg.instructions == Some(1)));
+    // Продаётся гарантированный выход покупки, а не объём возможности
+    let (buy, sell) = (&opportunity.legs[0], &opportunity.legs[1]);
+    assert_eq!(sell.amount_in, buy.min_output);
+    assert!(sell.amount_in < opportunity.opportunity.trade_amount);
+    let simulated = simulator.swaps.lock().unwrap().clone();
+    assert!(simulated[1].starts_with(&format!("{} SOL -> USDC min {}", buy.min_output, sell.min_output)), "{:?}", simulated);
+    // Ничего не отправлено
+    assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (0, 0));
+
+    // Без цен возможностей нет
+    raydium.set_should_fail_get_price(true);
+    orca.set_should_fail_get_price(true);
+    let report = dry_run::run(&engine, config_check::check_config(&config), None, &simulator).await;
+    assert!(report.opportunities.is_empty());
+
+    // Отчёт в JSON для CI
+    let json = serde_json::to_value(&report)?;
+    assert!(json["preflight"]["config"].is_array());
+    assert!(json["opportunities"].as_array().is_some_and(Vec::is_empty));
+    Ok(())
+}
+
+/// Самопроверка адаптеров: цена, котировка, сборка и симуляция по каждому DEX
+#[tokio::test]
+async fn test_startup_self_test_with_mock_dexes() -> Result<()> {
+    use arb_bot::dex::DexInterface;
+    use arb_bot::self_test::{self, CheckStatus, SelfTestStage, Simulator};
+    use solana_sdk::instruction::Instruction;
+
+    /// Симуляция, отклоняющая свопы с заданным DEX в данных инструкции
+    struct FakeSimulator;
+
+    #[async_trait::async_trait]
+    impl Simulator for FakeSimulator {
+        async fn simulate(&self, instructions: &[Instruction], _wallet: &Wallet) -> Result<Option<u64>> {
+            assert_eq!(instructions.len(), 1);
+            let data = String::from_utf8(instructions[0].data.clone())?;
+            anyhow::ensure!(data.starts_with("0.5 SOL -> USDC"), "неожиданный своп: {}", data);
+            Ok(Some(12_000))
+        }
+
+        // Самопроверка симулирует своп без проверки баланса
+        async fn token_balance(&self, _token_account: solana_sdk::pubkey::Pubkey) -> Result<u64> {
+            anyhow::bail!("баланс не запрашивается")
+        }
+    }
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.dex.enabled_dexes.push("serum".to_string());
+    config.self_test.enabled = true;
+    config.self_test.amount = Some(0.5);
+    config.validate()?;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    let serum = MockDex::new("serum");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    raydium.set_atomic(true);
+    orca.set_should_fail_get_price(true);
+    serum.set_price("SOL", "USDC", Decimal::from(101));
+    let wallet = Wallet::new(&config)?;
+    let dexes: [&dyn DexInterface; 3] = [&raydium, &orca, &serum];
+
+    let report = self_test::run(&config, &dexes, &wallet, &FakeSimulator).await;
+    assert!(!report.passed);
+    let statuses: Vec<(&str, CheckStatus)> = report.adapters.iter()
+        .map(|adapter| (adapter.dex.as_str(), adapter.status))
+        .collect();
+    assert_eq!(statuses, vec![
+        ("raydium", CheckStatus::Passed),
+        ("orca", CheckStatus::Failed),
+        ("serum", CheckStatus::Passed),
+    ]);
+
+    // Полный прогон: 0.5 SOL по 100 за вычетом 0.25% комиссии
+    let raydium_stages = &report.adapters[0].stages;
+    assert_eq!(raydium_stages.len(), 4);
+    assert!(raydium_stages[1].detail.contains("49.875"), "{}", raydium_stages[1].detail);
+    assert_eq!(raydium_stages[3].detail, "12000 compute units");
+    // После неудачной цены следующие этапы не выполняются
+    assert_eq!(report.adapters[1].stages.len(), 1);
+    assert_eq!(report.adapters[1].stages[0].stage, SelfTestStage::Price);
+    // Без сборки инструкций сборка и симуляция пропускаются
+    assert!(report.adapters[2].stages[2..].iter().all(|stage| stage.status == CheckStatus::Skipped));
+
+    // Результат попадает в /api/status в виде JSON
+    let json = serde_json::to_value(&report)?;
+    assert_eq!(json["adapters"][1]["stages"][0]["status"], "failed");
+    assert_eq!(json["adapters"][0]["stages"][3]["stage"], "simulate");
+
+    // Пара для проверки должна сканироваться
+    config.self_test.pair = Some("RAY/USDC".to_string());
+    assert!(config.validate().is_err());
+    Ok(())
+}
+
+/// Маршруты через несколько пар находятся поиском и публикуются, но не исполняются
+#[tokio::test]
+async fn test_multi_hop_routes_are_detected_but_not_executed() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.dex.trading_pairs = vec!["SOL/USDC".to_string(), "RAY/USDC".to_string(), "RAY/SOL".to_string()];
+    config.routing.enabled = true;
+    config.validate()?;
+
+    // Цены пар совпадают на обоих DEX; RAY дешевле в SOL на Orca
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    for dex in [&raydium, &orca] {
+        dex.set_price("SOL", "USDC", Decimal::from(100));
+        dex.set_price("RAY", "USDC", Decimal::from(2));
+    }
+    raydium.set_price("RAY", "SOL", Decimal::from_str("0.02")?);
+    orca.set_price("RAY", "SOL", Decimal::from_str("0.0196")?);
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let scan_stats = monitor.scan_stats().clone();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor);
+
+    // USDC -> SOL -> RAY (Orca) -> USDC: по одному маршруту на каждый выбор DEX для SOL/USDC и RAY/USDC
+    let opportunities = engine.find_opportunities().await?;
+    let routes: Vec<_> = opportunities.iter().filter(|o| o.is_route()).collect();
+    assert_eq!(routes.len(), 4);
+    let best = routes.iter()
+        .max_by_key(|o| o.profit_percent_after_fees)
+        .unwrap();
+    let hops: Vec<String> = best.route.iter().map(|hop| hop.to_string()).collect();
+    assert_eq!(hops, vec!["raydium:USDC->SOL", "orca:SOL->RAY", "raydium:RAY->USDC"]);
+    assert_eq!((best.base_token.as_str(), best.quote_token.as_str()), ("SOL", "USDC"));
+    assert_eq!((best.from_dex.as_str(), best.to_dex.as_str()), ("raydium", "raydium"));
+    assert_eq!(best.buy_price, Decimal::from(100));
+    assert!(best.sell_price > best.buy_price);
+    assert_eq!(best.profit_percent.round_dp(4), Decimal::from_str("2.0408")?);
+
+    // Сделка по одной паре RAY/SOL по-прежнему находится
+    assert!(opportunities.iter().any(|o| !o.is_route() && o.base_token == "RAY" && o.quote_token == "SOL"));
+
+    // Маршруты отсеиваются перед исполнением и учитываются как пропущенные
+    let executable = engine.executable(opportunities.clone());
+    assert!(executable.iter().all(|o| !o.is_route()));
+    let metrics = scan_stats.snapshot(chrono::Utc::now());
+    assert_eq!(metrics.skipped_by_reason.get("multi_hop"), Some(&4));
+    let route = opportunities.into_iter().find(|o| o.is_route()).unwrap();
+    assert!(engine.execute_arbitrage(route).await.is_err());
+    assert!(raydium.swap_calls().is_empty() && orca.swap_calls().is_empty());
+
+    // Без routing маршруты не ищутся
+    config.routing.enabled = false;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config));
+    assert!(engine.find_opportunities().await?.iter().all(|o| !o.is_route()));
+
+    config.routing.enabled = true;
+    config.routing.max_hops = 6;
+    assert!(config.validate().is_err());
+    Ok(())
+}
+
+/// Возможность по устаревшим ценам не исполняется (arbitrage.max_price_age_ms)
+#[tokio::test]
+async fn test_stale_prices_are_not_executed() -> Result<()> {
+    use arb_bot::arbitrage::LegStamps;
+    use arb_bot::clock::{Clock, ManualClock};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.arbitrage.max_price_age_ms = 2000;
+    config.validate()?;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+
+    let clock = ManualClock::new(chrono::Utc::now());
+    let wallet = Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let scan_stats = monitor.scan_stats().clone();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor.clone())
+        .with_clock(Arc::new(clock.clone()));
+
+    // Цены ног помечены временем получения; моки не сообщают слот
+    let opportunity = engine.scan().await?.remove(0);
+    let buy = opportunity.price_stamps.buy.expect("время цены покупки");
+    assert_eq!(buy.fetched_at, clock.now());
+    assert_eq!(buy.slot, None);
+    assert!(opportunity.price_stamps.sell.is_some());
+    let snapshot = monitor.prices().latest().unwrap();
+    assert!(snapshot.pairs["SOL/USDC"].iter().all(|price| price.fetched_at == clock.now()));
+
+    // В пределах max_price_age_ms возможность исполняется
+    clock.advance(Duration::from_millis(2000));
+    assert_eq!(engine.executable(vec![opportunity.clone()]).len(), 1);
+
+    // Цены старше порога: отсеивается перед исполнением и учитывается как пропущенная
+    clock.advance(Duration::from_millis(1));
+    assert!(engine.executable(vec![opportunity.clone()]).is_empty());
+    let metrics = scan_stats.snapshot(clock.now());
+    assert_eq!(metrics.skipped_by_reason.get("stale_price"), Some(&1));
+    let error = engine.execute_arbitrage(opportunity.clone()).await.unwrap_err();
+    assert!(error.to_string().contains("устарела"), "{}", error);
+    assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (0, 0));
+
+    // Возможность без времени цен (формат шины до появления поля) не проверяется
+    let mut json = serde_json::to_value(&opportunity)?;
+    json.as_object_mut().unwrap().remove("price_stamps");
+    let legacy: arb_bot::arbitrage::ArbitrageOpportunity = serde_json::from_value(json)?;
+    assert_eq!(legacy.price_stamps, LegStamps::default());
+    assert_eq!(engine.executable(vec![legacy]).len(), 1);
+
+    // 0 — проверка отключена
+    config.arbitrage.max_price_age_ms = 0;
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), Arc::new(Wallet::new(&config)?), dex_manager, Monitor::new(&config))
+        .with_clock(Arc::new(clock.clone()));
+    assert_eq!(engine.executable(vec![opportunity]).len(), 1);
+    Ok(())
+}
+
+/// Тест: цены ног с большой разницей слотов не сравниваются, разница слотов
+/// попадает в возможность и в проверки песочницы
+#[tokio::test]
+async fn test_slot_skew_limits_price_comparison() -> Result<()> {
+    use arb_bot::arbitrage::SandboxTrade;
+    use std::sync::Arc;
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.arbitrage.max_slot_skew = 5;
+    config.validate()?;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    raydium.set_price_slot("SOL", "USDC", 1000);
+    orca.set_price_slot("SOL", "USDC", 1005);
+    let engine_with = |config: &arb_bot::config::Config| -> Result<ArbitrageEngine> {
+        let dex_manager = DexManager::from_dexes(config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+        Ok(ArbitrageEngine::new(config.clone(), Arc::new(Wallet::new(config)?), dex_manager, Monitor::new(config)))
+    };
+
+    // Разница в пределах порога: возможность найдена, разница слотов в ответе
+    let engine = engine_with(&config)?;
+    let opportunity = engine.scan().await?.remove(0);
+    assert_eq!(opportunity.price_stamps.slot_skew, Some(5));
+    let json = serde_json::to_value(&opportunity)?;
+    assert_eq!(json["price_stamps"]["slot_skew"], 5);
+    assert_eq!(json["price_stamps"]["sell"]["slot"], 1005);
+
+    // Цена продажи на слишком позднем слоте: пара не сравнивается
+    orca.set_price_slot("SOL", "USDC", 1006);
+    assert!(engine.scan().await?.is_empty());
+    let trade = SandboxTrade {
+        base_token: "SOL".to_string(),
+        quote_token: "USDC".to_string(),
+        buy_dex: "raydium".to_string(),
+        sell_dex: "orca".to_string(),
+        amount: Decimal::ONE,
+    };
+    let evaluation = engine.evaluate_trade(&trade).await?;
+    let check = evaluation.checks.iter().find(|check| check.name == "slot_skew").expect("проверка слотов");
+    assert!(!check.passed, "{}", check.detail);
+    assert_eq!(evaluation.opportunity.price_stamps.slot_skew, Some(6));
+
+    // 0 — проверка отключена
+    config.arbitrage.max_slot_skew = 0;
+    assert_eq!(engine_with(&config)?.scan().await?.len(), 1);
+    Ok(())
+}
+
+/// Пара из токенов кошелька сканируется наравне с настроенными
+#[tokio::test]
+async fn test_discovered_pair_is_scanned() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let config = create_test_config(&temp_dir)?;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    for dex in [&raydium, &orca] {
+        dex.set_price("SOL", "USDC", Decimal::from(100));
+    }
+    raydium.set_price("RAY", "USDC", Decimal::from(2));
+    orca.set_price("RAY", "USDC", Decimal::from_str("2.06")?);
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, Monitor::new(&config));
+
+    // Без найденных пар RAY/USDC не сканируется
+    assert!(engine.find_opportunities().await?.is_empty());
+
+    engine.dex_manager().set_discovered_pairs(vec!["RAY/USDC".to_string()]);
+    let opportunities = engine.find_opportunities().await?;
+    assert_eq!(opportunities.len(), 1);
+    assert_eq!((opportunities[0].base_token.as_str(), opportunities[0].quote_token.as_str()), ("RAY", "USDC"));
+    assert_eq!((opportunities[0].from_dex.as_str(), opportunities[0].to_dex.as_str()), ("raydium", "orca"));
+    Ok(())
+}
+
+/// Флеш-займ: объём сверх max_trade_amount_sol, комиссия займа в прибыли, исполнение одной транзакцией
+#[tokio::test]
+async fn test_flash_loan_execution_with_mock_dexes() -> Result<()> {
+    use arb_bot::config::FlashLoanReserveConfig;
+    use solana_sdk::pubkey::Pubkey;
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.flash_loan.enabled = true;
+    config.flash_loan.reserves.insert("USDC".to_string(), FlashLoanReserveConfig {
+        lending_market: Pubkey::new_unique().to_string(),
+        reserve: Pubkey::new_unique().to_string(),
+        liquidity_supply: Pubkey::new_unique().to_string(),
+        fee_receiver: Pubkey::new_unique().to_string(),
+        fee_percent: 0.05,
+        max_borrow: 1000.0,
+    });
+    config.validate()?;
+
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    raydium.set_atomic(true);
+    orca.set_atomic(true);
+
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let history = monitor.trade_history();
+    let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor);
+
+    // Заём до 1000 USDC: 10 SOL по 100 вместо max_trade_amount_sol = 1; комиссия займа 0.05%
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    assert_eq!(opportunity.trade_amount, Decimal::from(10));
+    assert_eq!(opportunity.profit_percent_after_fees, Decimal::from_str("2.40")?);
+    engine.execute_arbitrage(opportunity).await?;
+    assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (0, 0));
+    assert_eq!(history.lock().await[0].tx_signature.as_deref(), Some("simulated_signature_flash_loan"));
+
+    // DEX без сборки инструкций: займа нет, объём ограничен балансом
+    orca.set_atomic(false);
+    let opportunity = engine.find_opportunities().await?.remove(0);
+    assert_eq!(opportunity.trade_amount, Decimal::ONE);
+    assert_eq!(opportunity.profit_percent_after_fees, Decimal::from_str("2.45")?);
+
+    // Резерв для нативного SOL не допускается
+    let usdc = config.flash_loan.reserves["USDC"].clone();
+    config.flash_loan.reserves.insert("SOL".to_string(), usdc);
+    assert!(config.validate().is_err());
+    Ok(())
+}
@@ -3,13 +3,21 @@
 /// Этот модуль предоставляет моки для тестирования без реальных сетевых запросов
 
 use anyhow::Result;
+use arb_bot::dex::DexInterface;
+use arb_bot::failure::FailureKind;
+use arb_bot::presend::Rejected;
+use arb_bot::reserve_guard::Reserves;
+use arb_bot::tx_error::DecodedTxError;
+use arb_bot::wallet::Wallet;
 use rust_decimal::Decimal;
 use solana_sdk::{
+    instruction::Instruction,
     pubkey::Pubkey,
     signature::Keypair,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Мок RPC клиента для тестирования
 pub struct MockRpcClient {
@@ -94,13 +102,32 @@ impl MockRpcClient {
     }
 }
 
+/// Вызов свопа, записанный моком DEX
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapCall {
+    pub simulation_mode: bool,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount: Decimal,
+    pub min_output: Decimal,
+}
+
 /// Мок DEX для тестирования
+///
+/// Клоны разделяют состояние: тест сохраняет клон после передачи мока в DexManager.
+#[derive(Clone)]
 pub struct MockDex {
     name: String,
     prices: Arc<Mutex<HashMap<(String, String), Decimal>>>,
+    reserves: Arc<Mutex<HashMap<(String, String), Reserves>>>,
+    liquidity: Arc<Mutex<HashMap<(String, String), Reserves>>>,
+    slots: Arc<Mutex<HashMap<(String, String), u64>>>,
     should_fail_get_price: Arc<Mutex<bool>>,
     should_fail_swap: Arc<Mutex<bool>>,
-    swap_call_count: Arc<Mutex<u32>>,
+    presend_rejection: Arc<Mutex<Option<Vec<String>>>>,
+    swap_calls: Arc<Mutex<Vec<SwapCall>>>,
+    price_delay: Arc<Mutex<Duration>>,
+    atomic: Arc<Mutex<bool>>,
 }
 
 impl MockDex {
@@ -109,9 +136,15 @@ impl MockDex {
         Self {
             name: name.to_string(),
             prices: Arc::new(Mutex::new(HashMap::new())),
+            reserves: Arc::new(Mutex::new(HashMap::new())),
+            liquidity: Arc::new(Mutex::new(HashMap::new())),
+            slots: Arc::new(Mutex::new(HashMap::new())),
             should_fail_get_price: Arc::new(Mutex::new(false)),
             should_fail_swap: Arc::new(Mutex::new(false)),
-            swap_call_count: Arc::new(Mutex::new(0)),
+            presend_rejection: Arc::new(Mutex::new(None)),
+            swap_calls: Arc::new(Mutex::new(Vec::new())),
+            price_delay: Arc::new(Mutex::new(Duration::ZERO)),
+            atomic: Arc::new(Mutex::new(false)),
         }
     }
 
@@ -121,6 +154,21 @@ impl MockDex {
         prices.insert((base_token.to_string(), quote_token.to_string()), price);
     }
 
+    /// Установка резервов пула торговой пары (base, quote)
+    pub fn set_reserves(&self, base_token: &str, quote_token: &str, reserves: Reserves) {
+        self.reserves.lock().unwrap().insert((base_token.to_string(), quote_token.to_string()), reserves);
+    }
+
+    /// Установка доступной ликвидности торговой пары (base, quote) без резервов пула
+    pub fn set_liquidity(&self, base_token: &str, quote_token: &str, liquidity: Reserves) {
+        self.liquidity.lock().unwrap().insert((base_token.to_string(), quote_token.to_string()), liquidity);
+    }
+
+    /// Установка слота, на котором получена цена торговой пары
+    pub fn set_price_slot(&self, base_token: &str, quote_token: &str, slot: u64) {
+        self.slots.lock().unwrap().insert((base_token.to_string(), quote_token.to_string()), slot);
+    }
+
     /// Включение режима ошибок для get_price
     pub fn set_should_fail_get_price(&self, should_fail: bool) {
         let mut flag = self.should_fail_get_price.lock().unwrap();
@@ -133,13 +181,45 @@ impl MockDex {
         *flag = should_fail;
     }
 
+    /// Отмена swap симуляцией перед отправкой с указанными логами (None — без отмены)
+    pub fn set_presend_rejection(&self, logs: Option<Vec<String>>) {
+        *self.presend_rejection.lock().unwrap() = logs;
+    }
+
+    /// Задержка ответа get_price (имитация задержки RPC)
+    pub fn set_price_delay(&self, delay: Duration) {
+        *self.price_delay.lock().unwrap() = delay;
+    }
+
+    /// Поддержка атомарных транзакций (по умолчанию нет — сделки в два этапа)
+    pub fn set_atomic(&self, atomic: bool) {
+        *self.atomic.lock().unwrap() = atomic;
+    }
+
     /// Получение счётчика вызовов swap
     pub fn get_swap_call_count(&self) -> u32 {
-        *self.swap_call_count.lock().unwrap()
+        self.swap_calls.lock().unwrap().len() as u32
+    }
+
+    /// Все вызовы swap по порядку
+    pub fn swap_calls(&self) -> Vec<SwapCall> {
+        self.swap_calls.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl DexInterface for MockDex {
+    fn name(&self) -> &str {
+        &self.name
     }
 
     /// Получение цены (имитация DEX API)
-    pub async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+    async fn get_price(&self, base_token: &str, quote_token: &str) -> Result<Decimal> {
+        let delay = *self.price_delay.lock().unwrap();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+
         let should_fail = *self.should_fail_get_price.lock().unwrap();
         if should_fail {
             anyhow::bail!("Симуляция ошибки получения цены");
@@ -151,24 +231,78 @@ impl MockDex {
             .ok_or_else(|| anyhow::anyhow!("Цена не найдена для пары {}/{}", base_token, quote_token))
     }
 
+    fn price_slot(&self, base_token: &str, quote_token: &str) -> Option<u64> {
+        self.slots.lock().unwrap().get(&(base_token.to_string(), quote_token.to_string())).copied()
+    }
+
+    fn pool_reserves(&self, base_token: &str, quote_token: &str) -> Option<Reserves> {
+        self.reserves.lock().unwrap().get(&(base_token.to_string(), quote_token.to_string())).copied()
+    }
+
+    async fn get_liquidity(&self, base_token: &str, quote_token: &str) -> Result<Reserves> {
+        self.liquidity.lock().unwrap().get(&(base_token.to_string(), quote_token.to_string()))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Ликвидность не найдена для пары {}/{}", base_token, quote_token))
+    }
+
+    fn supports_atomic_swap(&self) -> bool {
+        *self.atomic.lock().unwrap()
+    }
+
+    /// Сборка инструкций свопа (заглушка с параметрами свопа в данных инструкции)
+    async fn build_swap_instructions(
+        &self,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        _wallet: &Wallet,
+    ) -> Result<Vec<Instruction>> {
+        if !self.supports_atomic_swap() {
+            anyhow::bail!("{}: сборка инструкций не поддерживается", self.name);
+        }
+        let data = format!("{} {} -> {} min {}", amount, from_token, to_token, min_output);
+        Ok(vec![Instruction::new_with_bytes(Pubkey::new_from_array([7; 32]), data.as_bytes(), Vec::new())])
+    }
+
     /// Выполнение свопа (имитация DEX API)
-    pub async fn execute_swap(
+    async fn execute_swap(
         &self,
-        _simulation_mode: bool,
-        _from_token: &str,
-        _to_token: &str,
-        _amount: Decimal,
-        _min_output: Decimal,
+        simulation_mode: bool,
+        from_token: &str,
+        to_token: &str,
+        amount: Decimal,
+        min_output: Decimal,
+        _wallet: &Wallet,
     ) -> Result<String> {
-        let mut count = self.swap_call_count.lock().unwrap();
-        *count += 1;
+        let mut calls = self.swap_calls.lock().unwrap();
+        calls.push(SwapCall {
+            simulation_mode,
+            from_token: from_token.to_string(),
+            to_token: to_token.to_string(),
+            amount,
+            min_output,
+        });
 
         let should_fail = *self.should_fail_swap.lock().unwrap();
         if should_fail {
             anyhow::bail!("Симуляция ошибки выполнения свопа");
         }
 
-        Ok(format!("mock_signature_{}_{}", self.name, count))
+        if let Some(logs) = self.presend_rejection.lock().unwrap().clone() {
+            arb_bot::receipt::record_simulation_logs(&logs);
+            let decoded = DecodedTxError {
+                instruction_index: Some(0),
+                program: None,
+                code: Some(30),
+                reason: "превышено проскальзывание".to_string(),
+                logs,
+                kind: FailureKind::Aborted,
+            };
+            return Err(anyhow::Error::new(decoded).context(Rejected));
+        }
+
+        Ok(format!("mock_signature_{}_{}", self.name, calls.len()))
     }
 }
 
@@ -246,6 +380,7 @@ mod tests {
             "USDC",
             Decimal::from(1),
             Decimal::from(100),
+            &Wallet::ephemeral(),
         ).await.unwrap();
         assert!(signature.contains("mock_signature"));
         assert_eq!(mock_dex.get_swap_call_count(), 1);
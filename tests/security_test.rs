@@ -136,7 +136,7 @@ min_balance_sol = 0.1
 #[tokio::test]
 async fn test_config_security_validation() -> Result<()> {
     // Тест с невалидной конфигурацией (отрицательная прибыль)
-    let _invalid_config = r#"
+    let invalid_config = r#"
 [network]
 rpc_url = "https://api.devnet.solana.com"
 commitment = "confirmed"
@@ -319,3 +319,198 @@ async fn test_trading_pair_validation() -> Result<()> {
     Ok(())
 }
 
+
+/// Тест скрытия секретов RPC провайдера в URL
+#[tokio::test]
+async fn test_rpc_url_redaction() -> Result<()> {
+    use arb_bot::config::resolve_env_value;
+    use arb_bot::rpc::redact_url;
+
+    // Публичный URL не изменяется
+    assert_eq!(redact_url("https://api.devnet.solana.com"), "https://api.devnet.solana.com");
+
+    // API ключ в query параметре скрывается
+    let redacted = redact_url("https://mainnet.helius-rpc.com/?api-key=secret123");
+    assert!(!redacted.contains("secret123"), "API ключ не должен попадать в логи");
+
+    // Токен в пути (QuickNode) скрывается
+    let redacted = redact_url("https://example.quiknode.pro/token456/");
+    assert!(!redacted.contains("token456"), "Токен в пути не должен попадать в логи");
+
+    // Ссылки на переменные окружения разрешаются, обычные значения — нет
+    std::env::set_var("ARB_BOT_TEST_RPC_TOKEN", "value");
+    assert_eq!(resolve_env_value("env:ARB_BOT_TEST_RPC_TOKEN")?, "value");
+    assert_eq!(resolve_env_value("plain")?, "plain");
+    assert!(resolve_env_value("env:ARB_BOT_TEST_MISSING_VAR").is_err());
+
+    Ok(())
+}
+
+/// Тест: API ключ не попадает в текст ошибок RPC (ответ 401) ни в URL, ни в заголовке
+#[tokio::test]
+async fn test_rpc_api_key_not_in_errors() -> Result<()> {
+    use arb_bot::config::NetworkConfig;
+    use arb_bot::rpc::{create_rpc_client, ws_headers};
+    use solana_client::rpc_request::RpcRequest;
+    use std::io::{Read, Write};
+
+    // Фиктивный RPC: отклоняет каждый запрос с 401 и возвращает начало запроса
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let rpc_addr = listener.local_addr()?;
+    let server = std::thread::spawn(move || -> Vec<String> {
+        let mut requests = Vec::new();
+        for _ in 0..2 {
+            let (mut stream, _) = listener.accept().expect("подключение к RPC");
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).expect("чтение запроса");
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream.write_all(b"HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .expect("ответ RPC");
+            requests.push(String::from_utf8_lossy(&request).to_lowercase());
+        }
+        requests
+    });
+
+    std::env::set_var("ARB_BOT_TEST_LEAK_KEY", "leak-secret");
+    let mut network: NetworkConfig = toml::from_str(&format!(r#"
+rpc_url = "http://{}/"
+ws_url = "ws://{}/"
+commitment = "confirmed"
+rpc_api_key_env = "ARB_BOT_TEST_LEAK_KEY"
+"#, rpc_addr, rpc_addr))?;
+
+    // Ключ в query параметре (Helius): ошибка reqwest выводится без URL
+    let client = create_rpc_client(&network)?;
+    let error = tokio::task::spawn_blocking(move || client.send::<u64>(RpcRequest::GetSlot, serde_json::Value::Null))
+        .await?
+        .expect_err("RPC отвечает 401");
+    let text = format!("{} {:?}", error, error);
+    assert!(text.contains("401") && !text.contains("leak-secret"), "{}", text);
+
+    // Ключ в заголовке: URL запроса без ключа, ошибка тоже
+    network.rpc_api_key_header = Some("x-token".to_string());
+    let client = create_rpc_client(&network)?;
+    assert!(!client.url().contains("leak-secret"));
+    let error = tokio::task::spawn_blocking(move || client.send::<u64>(RpcRequest::GetSlot, serde_json::Value::Null))
+        .await?
+        .expect_err("RPC отвечает 401");
+    assert!(!format!("{} {:?}", error, error).contains("leak-secret"));
+    assert_eq!(ws_headers(&network)?.get("x-token").map(|value| value.to_str().ok()), Some(Some("leak-secret")));
+
+    let requests = server.join().expect("поток RPC");
+    assert!(requests[0].starts_with("post /?api-key=leak-secret "), "{}", requests[0]);
+    assert!(requests[1].starts_with("post / ") && requests[1].contains("x-token: leak-secret"), "{}", requests[1]);
+    Ok(())
+}
+
+/// Тест: отдельный endpoint симуляций получает свои заголовки, но не API ключ основного провайдера
+#[tokio::test]
+async fn test_simulation_rpc_endpoint_without_api_key() -> Result<()> {
+    use arb_bot::config::NetworkConfig;
+    use arb_bot::rpc::{create_rpc_client, simulation_network};
+    use solana_client::rpc_request::RpcRequest;
+    use std::io::{Read, Write};
+
+    // Фиктивный RPC симуляций: отклоняет запрос с 401 и возвращает его начало
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let simulation_addr = listener.local_addr()?;
+    let server = std::thread::spawn(move || -> String {
+        let (mut stream, _) = listener.accept().expect("подключение к RPC");
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        while !request.ends_with(b"}") {
+            let n = stream.read(&mut buf).expect("чтение запроса");
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        stream.write_all(b"HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+            .expect("ответ RPC");
+        String::from_utf8_lossy(&request).to_lowercase()
+    });
+
+    std::env::set_var("ARB_BOT_TEST_PREMIUM_KEY", "premium-secret");
+    std::env::set_var("ARB_BOT_TEST_SIMULATION_KEY", "simulation-secret");
+    let mut network: NetworkConfig = toml::from_str(r#"
+rpc_url = "https://premium.example.com/"
+commitment = "confirmed"
+rpc_api_key_env = "ARB_BOT_TEST_PREMIUM_KEY"
+rpc_headers = { "x-premium" = "env:ARB_BOT_TEST_PREMIUM_KEY" }
+"#)?;
+
+    // Без simulation_rpc_url симуляции идут через основной endpoint
+    assert_eq!(simulation_network(&network).rpc_url, network.rpc_url);
+
+    network.simulation_rpc_url = Some(format!("http://{}/", simulation_addr));
+    network.simulation_rpc_headers.insert("x-api-key".to_string(), "env:ARB_BOT_TEST_SIMULATION_KEY".to_string());
+    assert!(create_rpc_client(&network)?.url().starts_with("https://premium.example.com/"));
+    let client = create_rpc_client(&simulation_network(&network))?;
+    assert_eq!(client.url(), format!("http://{}/", simulation_addr));
+    let error = tokio::task::spawn_blocking(move || {
+        client.send::<u64>(RpcRequest::GetSlot, serde_json::Value::Null)
+            .map_err(|error| format!("{} {:?}", error, error))
+    })
+    .await?
+    .expect_err("RPC отвечает 401");
+    assert!(!error.contains("simulation-secret"), "{}", error);
+
+    let request = server.join().expect("поток RPC");
+    assert!(request.starts_with("post / "), "{}", request);
+    assert!(request.contains("x-api-key: simulation-secret"), "{}", request);
+    assert!(!request.contains("premium-secret") && !request.contains("x-premium"), "{}", request);
+    Ok(())
+}
+
+/// Тест: операции devnet определяют кластер по genesis hash, а не по адресу RPC
+#[tokio::test]
+async fn test_devnet_guard_checks_genesis_hash() -> Result<()> {
+    use arb_bot::config::NetworkConfig;
+    use arb_bot::devnet::{ensure_devnet, DEVNET_GENESIS_HASH};
+    use std::io::{Read, Write};
+
+    const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+
+    // Фиктивный RPC: на getGenesisHash отвечает по очереди hash mainnet и devnet
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let rpc_addr = listener.local_addr()?;
+    let server = std::thread::spawn(move || {
+        for hash in [MAINNET_GENESIS_HASH, DEVNET_GENESIS_HASH] {
+            let (mut stream, _) = listener.accept().expect("подключение к RPC");
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).expect("чтение запроса");
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            assert!(String::from_utf8_lossy(&request).contains("getGenesisHash"));
+            let body = format!(r#"{{"jsonrpc":"2.0","result":"{}","id":1}}"#, hash);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(), body
+            );
+            stream.write_all(response.as_bytes()).expect("ответ RPC");
+        }
+    });
+
+    // Адрес с «devnet» в пути не делает кластер devnet
+    let network: NetworkConfig = toml::from_str(&format!(r#"
+rpc_url = "http://{}/devnet"
+commitment = "confirmed"
+"#, rpc_addr))?;
+    let error = ensure_devnet(&network).await.expect_err("кластер mainnet");
+    assert!(format!("{:#}", error).contains(MAINNET_GENESIS_HASH));
+    ensure_devnet(&network).await?;
+
+    server.join().expect("поток RPC");
+    Ok(())
+}
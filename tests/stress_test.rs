@@ -15,6 +15,11 @@ use tempfile::TempDir;
 use solana_sdk::signature::Keypair;
 use futures::future;
 
+#[path = "mocks.rs"]
+mod mocks;
+
+use mocks::MockDex;
+
 /// Создание тестовой конфигурации
 fn create_test_config(temp_dir: &TempDir) -> Result<Config> {
     let keypair_path = temp_dir.path().join("test_wallet.json");
@@ -70,7 +75,7 @@ min_balance_sol = 0.1
 async fn test_performance_find_opportunities() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -100,7 +105,7 @@ async fn test_performance_find_opportunities() -> Result<()> {
 async fn test_multiple_find_opportunities() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -138,7 +143,7 @@ async fn test_multiple_find_opportunities() -> Result<()> {
 async fn test_parallel_find_opportunities() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -210,7 +215,7 @@ async fn test_many_trading_pairs() -> Result<()> {
         "USDC/USDT".to_string(),
     ];
 
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -240,7 +245,7 @@ async fn test_many_trading_pairs() -> Result<()> {
 async fn test_long_running_stability() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -285,7 +290,7 @@ async fn test_long_running_stability() -> Result<()> {
 async fn test_error_handling_under_load() -> Result<()> {
     let temp_dir = TempDir::new()?;
     let config = create_test_config(&temp_dir)?;
-    let wallet = Wallet::new(&config)?;
+    let wallet = std::sync::Arc::new(Wallet::new(&config)?);
     let dex_manager = DexManager::new(&config)?;
     let monitor = Monitor::new(&config);
 
@@ -321,3 +326,420 @@ async fn test_error_handling_under_load() -> Result<()> {
     Ok(())
 }
 
+
+/// 95-й перцентиль задержек
+fn p95(mut latencies: Vec<Duration>) -> Duration {
+    latencies.sort();
+    latencies[(latencies.len() * 95 / 100).min(latencies.len() - 1)]
+}
+
+/// Нагрузочный тест веб-сервера: REST и WebSocket параллельно с торговым циклом
+///
+/// Торговый цикл держит мьютекс движка весь прогон; /api/opportunities отдаёт
+/// последний снимок цен, /api/history и WebSocket от движка не зависят —
+/// никто из них не должен ждать движок.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_web_server_under_load() -> Result<()> {
+    use arb_bot::schedule::{Schedule, ScheduleControl};
+    use arb_bot::store::Store;
+    use arb_bot::web::{create_state, server::create_router};
+    use base64::{engine::general_purpose, Engine as _};
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio_tungstenite::tungstenite::Message;
+
+    const OPPORTUNITY_CLIENTS: usize = 16;
+    const HISTORY_CLIENTS: usize = 16;
+    const WS_CLIENTS: usize = 8;
+    const REQUESTS_PER_CLIENT: usize = 5;
+
+    std::env::set_var("WEB_PASSWORD", "load-test");
+    let credentials = general_purpose::STANDARD.encode("admin:load-test");
+
+    let temp_dir = TempDir::new()?;
+    let config = create_test_config(&temp_dir)?;
+
+    // Котировки с задержкой RPC: движок держит мьютекс на время запросов к DEX
+    let raydium = MockDex::new("raydium");
+    let orca = MockDex::new("orca");
+    raydium.set_price("SOL", "USDC", Decimal::from(100));
+    orca.set_price("SOL", "USDC", Decimal::from(103));
+    for dex in [&raydium, &orca] {
+        dex.set_price_delay(Duration::from_millis(5));
+    }
+
+    let wallet = Arc::new(Wallet::new(&config)?);
+    let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+    let monitor = Monitor::new(&config);
+    let engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor.clone());
+    let hot_wallet = engine.wallet();
+    let venue_limiter = engine.venue_limiter();
+    let dex_manager = engine.dex_manager();
+    let engine = Arc::new(tokio::sync::Mutex::new(engine));
+
+    let state = create_state(
+        config.clone(),
+        monitor,
+        hot_wallet,
+        engine.clone(),
+        ScheduleControl::new(Schedule::from_config(&config.schedule)?),
+        Store::open_dir(&temp_dir.path().join("data")).await?,
+        venue_limiter,
+        dex_manager,
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let server = tokio::spawn(async move { axum::serve(listener, create_router(state)).await });
+
+    // Торговый цикл: поиск и исполнение под мьютексом движка, как в основном цикле
+    let stop = Arc::new(AtomicBool::new(false));
+    let trading = {
+        let (engine, stop) = (engine.clone(), stop.clone());
+        tokio::spawn(async move {
+            let mut cycles = 0u32;
+            while !stop.load(Ordering::Relaxed) {
+                {
+                    let mut engine = engine.lock().await;
+                    let opportunities = engine.find_opportunities().await?;
+                    let executable = engine.executable(engine.allocate(opportunities));
+                    for batch in engine.batches(executable) {
+                        for result in engine.execute_batch(batch).await {
+                            result?;
+                        }
+                    }
+                }
+                cycles += 1;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            anyhow::Ok(cycles)
+        })
+    };
+
+    let client = reqwest::Client::new();
+    let get = |path: &'static str| {
+        let (client, credentials) = (client.clone(), credentials.clone());
+        async move {
+            let mut latencies = Vec::with_capacity(REQUESTS_PER_CLIENT);
+            for _ in 0..REQUESTS_PER_CLIENT {
+                let started = Instant::now();
+                let response = client.get(format!("http://{}{}", addr, path))
+                    .header("Authorization", format!("Basic {}", credentials))
+                    .send()
+                    .await?;
+                anyhow::ensure!(response.status().is_success(), "{}: статус {}", path, response.status());
+                let body: serde_json::Value = response.json().await?;
+                latencies.push(started.elapsed());
+                if path == "/api/opportunities" {
+                    anyhow::ensure!(body["count"] == 1, "{}: ожидалась одна возможность: {}", path, body);
+                }
+            }
+            anyhow::Ok(latencies)
+        }
+    };
+
+    // Клиент WebSocket ждёт статус (первое периодическое сообщение) и сделку из ленты событий
+    let token = credentials.replace('+', "%2B").replace('/', "%2F").replace('=', "%3D");
+    let ws_client = || {
+        let url = format!("ws://{}/ws/updates?token={}", addr, token);
+        async move {
+            let started = Instant::now();
+            let (mut socket, _) = tokio_tungstenite::connect_async(url).await?;
+            let (mut status, mut trade) = (false, false);
+            while !(status && trade) {
+                let Some(message) = socket.next().await else { anyhow::bail!("WebSocket закрыт сервером") };
+                if let Message::Text(text) = message? {
+                    let message: serde_json::Value = serde_json::from_str(&text)?;
+                    status |= message["type"] == "Status";
+                    trade |= message["type"] == "Trade";
+                }
+            }
+            anyhow::Ok(started.elapsed())
+        }
+    };
+
+    let load = async {
+        let opportunities = future::try_join_all((0..OPPORTUNITY_CLIENTS).map(|_| get("/api/opportunities")));
+        let history = future::try_join_all((0..HISTORY_CLIENTS).map(|_| get("/api/history")));
+        let ws = future::try_join_all((0..WS_CLIENTS).map(|_| ws_client()));
+        tokio::try_join!(opportunities, history, ws)
+    };
+    // Зависание любого клиента — взаимная блокировка
+    let (opportunities, history, ws) = tokio::time::timeout(Duration::from_secs(30), load)
+        .await
+        .context("Нагрузка не завершилась за 30 с: взаимная блокировка")??;
+
+    stop.store(true, Ordering::Relaxed);
+    let cycles = tokio::time::timeout(Duration::from_secs(5), trading)
+        .await
+        .context("Торговый цикл не остановился")???;
+    server.abort();
+
+    let opportunities_p95 = p95(opportunities.concat());
+    let history_p95 = p95(history.concat());
+    log::info!(
+        "p95: /api/opportunities {:?}, /api/history {:?}; торговых циклов: {}",
+        opportunities_p95, history_p95, cycles
+    );
+    assert!(cycles > 0, "Торговый цикл должен работать во время нагрузки");
+    assert!(opportunities_p95 < Duration::from_secs(3), "p95 /api/opportunities: {:?}", opportunities_p95);
+    assert!(history_p95 < Duration::from_millis(500), "p95 /api/history: {:?}", history_p95);
+    assert!(ws.iter().all(|elapsed| *elapsed < Duration::from_secs(10)));
+    assert_eq!(raydium.get_swap_call_count(), orca.get_swap_call_count());
+
+    Ok(())
+}
+
+/// Фиктивный узел Solana для стресс-теста со сбоями RPC
+///
+/// Ведёт учёт доставленных транзакций: исполняется транзакция с действующим
+/// blockhash (одним из последних выданных), повтор тех же байтов отбрасывается.
+#[cfg(feature = "chaos")]
+#[derive(Default)]
+struct FakeNode {
+    blockhashes: Vec<solana_sdk::hash::Hash>,
+    /// Сколько раз доставлена каждая подпись
+    deliveries: std::collections::HashMap<solana_sdk::signature::Signature, u32>,
+    /// Исполненные подписи и memo их сделок
+    landed: std::collections::HashMap<solana_sdk::signature::Signature, String>,
+    /// Сумма перевода-проверки баланса в каждой доставленной транзакции
+    guard_amounts: Vec<u64>,
+}
+
+#[cfg(feature = "chaos")]
+impl FakeNode {
+    /// Баланс quote токена кошелька: не меняется, сделки только проверяются
+    const QUOTE_BALANCE: u64 = 1_000_000_000;
+    /// Сколько последних blockhash считаются действующими
+    const VALID_BLOCKHASHES: usize = 4;
+
+    fn blockhash_valid(&self, hash: &solana_sdk::hash::Hash) -> bool {
+        self.blockhashes.iter().rev().take(Self::VALID_BLOCKHASHES).any(|valid| valid == hash)
+    }
+
+    fn deliver(&mut self, transaction: solana_sdk::transaction::Transaction) -> solana_sdk::signature::Signature {
+        let signature = transaction.signatures[0];
+        *self.deliveries.entry(signature).or_default() += 1;
+        let message = &transaction.message;
+        let program = |index: u8| message.account_keys[index as usize];
+
+        let guard = message.instructions.last().expect("транзакция без инструкций");
+        assert_eq!(program(guard.program_id_index), spl_token::id(), "последняя инструкция — проверка баланса");
+        match spl_token::instruction::TokenInstruction::unpack(&guard.data) {
+            Ok(spl_token::instruction::TokenInstruction::Transfer { amount }) => self.guard_amounts.push(amount),
+            other => panic!("проверка баланса не перевод SPL токена: {:?}", other),
+        }
+
+        let memo = message.instructions.iter()
+            .find(|instruction| program(instruction.program_id_index) == arb_bot::trade_memo::program_id())
+            .map(|instruction| String::from_utf8_lossy(&instruction.data).to_string())
+            .expect("транзакция без memo сделки");
+        if self.blockhash_valid(&message.recent_blockhash) {
+            self.landed.entry(signature).or_insert(memo);
+        }
+        signature
+    }
+
+    fn handle(&mut self, method: &str, params: &serde_json::Value) -> Result<serde_json::Value, String> {
+        use base64::Engine;
+        use serde_json::json;
+
+        let context = json!({"slot": 1});
+        Ok(match method {
+            "getVersion" => json!({"solana-core": "1.18.26", "feature-set": 0}),
+            "getLatestBlockhash" => {
+                let hash = solana_sdk::hash::Hash::new_unique();
+                self.blockhashes.push(hash);
+                json!({"context": context, "value": {"blockhash": hash.to_string(), "lastValidBlockHeight": 1000}})
+            }
+            "isBlockhashValid" => {
+                let hash = params[0].as_str().and_then(|hash| hash.parse().ok()).ok_or("некорректный blockhash")?;
+                json!({"context": context, "value": self.blockhash_valid(&hash)})
+            }
+            "sendTransaction" => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(params[0].as_str().unwrap_or_default())
+                    .map_err(|e| e.to_string())?;
+                let transaction = bincode::deserialize(&bytes).map_err(|e| e.to_string())?;
+                json!(self.deliver(transaction).to_string())
+            }
+            // Симуляция перед отправкой: сделки на узле всегда проходят проверку баланса
+            "simulateTransaction" => json!({"context": context, "value": {
+                "err": null, "logs": ["Program log: Instruction: Transfer"], "accounts": null, "unitsConsumed": 0
+            }}),
+            "getSignatureStatuses" => {
+                let statuses: Vec<serde_json::Value> = params[0].as_array().cloned().unwrap_or_default().iter()
+                    .map(|signature| {
+                        let landed = signature.as_str()
+                            .and_then(|signature| signature.parse().ok())
+                            .is_some_and(|signature| self.landed.contains_key(&signature));
+                        match landed {
+                            true => json!({
+                                "slot": 1, "confirmations": null, "err": null,
+                                "status": {"Ok": null}, "confirmationStatus": "confirmed"
+                            }),
+                            false => serde_json::Value::Null,
+                        }
+                    })
+                    .collect();
+                json!({"context": context, "value": statuses})
+            }
+            "getTokenAccountBalance" => json!({"context": context, "value": {
+                "amount": Self::QUOTE_BALANCE.to_string(), "decimals": 6,
+                "uiAmount": 1000.0, "uiAmountString": "1000"
+            }}),
+            // ATA quote токена существует: нулевой баланс по ошибке транспорта недопустим
+            "getAccountInfo" => json!({"context": context, "value": {
+                "lamports": 2_039_280, "owner": spl_token::id().to_string(), "data": ["", "base64"],
+                "executable": false, "rentEpoch": 0, "space": 0
+            }}),
+            "getTransaction" => serde_json::Value::Null,
+            _ => return Err(format!("метод {} не поддерживается", method)),
+        })
+    }
+}
+
+/// Тест: при задержках, потерянных отправках и двойной доставке транзакций
+/// подсистемы безопасности сохраняют согласованное состояние
+#[cfg(feature = "chaos")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_safety_state_under_rpc_faults() -> Result<()> {
+    use arb_bot::chaos::{self, ChaosConfig};
+    use arb_bot::clock::ManualClock;
+    use arb_bot::failure::{classify_failure, FailureKind};
+    use arb_bot::history::TradeStatus;
+    use axum::{extract::State, routing::post, Json, Router};
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+
+    const ENGINES: usize = 4;
+    const TRADES_PER_ENGINE: usize = 25;
+
+    let node = Arc::new(Mutex::new(FakeNode::default()));
+    let router = Router::new()
+        .route("/", post(|State(node): State<Arc<Mutex<FakeNode>>>, Json(request): Json<serde_json::Value>| async move {
+            let method = request["method"].as_str().unwrap_or_default().to_string();
+            let result = node.lock().expect("узел").handle(&method, &request["params"]);
+            Json(match result {
+                Ok(result) => serde_json::json!({"jsonrpc": "2.0", "result": result, "id": request["id"]}),
+                Err(message) => serde_json::json!({
+                    "jsonrpc": "2.0", "error": {"code": -32601, "message": message}, "id": request["id"]
+                }),
+            })
+        }))
+        .with_state(node.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let rpc_url = format!("http://{}/", listener.local_addr()?);
+    let server = tokio::spawn(async move { axum::serve(listener, router).await });
+
+    let temp_dir = TempDir::new()?;
+    let mut config = create_test_config(&temp_dir)?;
+    config.network.rpc_url = rpc_url.clone();
+    config.safety.simulation_mode = false;
+    config.fiat.enabled = false;
+    config.arbitrage.transaction_timeout_sec = 5;
+    // Одна возможность исполняется повторно с продвижением часов: возраст цены не проверяется
+    config.arbitrage.max_price_age_ms = 0;
+    let cooldown = Duration::from_secs(config.safety.circuit_breaker_cooldown_sec + 1);
+
+    let chaos = chaos::install(&rpc_url, ChaosConfig {
+        max_latency: Duration::from_millis(20),
+        drop_send: 0.15,
+        lost_ack: 0.2,
+        duplicate_send: 0.2,
+        rpc_error: 0.05,
+        seed: 1021,
+    });
+
+    // Несколько движков торгуют одновременно через один узел
+    let runs = (0..ENGINES).map(|_| {
+        let config = config.clone();
+        async move {
+            let raydium = MockDex::new("raydium");
+            let orca = MockDex::new("orca");
+            raydium.set_price("SOL", "USDC", Decimal::from(100));
+            orca.set_price("SOL", "USDC", Decimal::from(103));
+            raydium.set_atomic(true);
+            orca.set_atomic(true);
+
+            let wallet = std::sync::Arc::new(Wallet::new(&config)?);
+            let dex_manager = DexManager::from_dexes(&config, vec![Box::new(raydium.clone()), Box::new(orca.clone())]);
+            let monitor = Monitor::new(&config);
+            let history = monitor.trade_history();
+            let clock = ManualClock::new(chrono::Utc::now());
+            let mut engine = ArbitrageEngine::new(config.clone(), wallet, dex_manager, monitor)
+                .with_clock(Arc::new(clock.clone()));
+            let opportunity = engine.find_opportunities().await?.remove(0);
+
+            let mut results = Vec::with_capacity(TRADES_PER_ENGINE);
+            for _ in 0..TRADES_PER_ENGINE {
+                // Пауза после сбоев RPC истекает: каждая попытка доходит до отправки
+                clock.advance(cooldown);
+                results.push(engine.execute_arbitrage(opportunity.clone()).await);
+            }
+
+            assert_eq!(engine.halt_reason(), None, "временные сбои не останавливают исполнение");
+            let stats = engine.venue_limiter().stats();
+            assert!(stats.values().all(|venue| venue.in_flight == 0), "очереди DEX освобождены: {:?}", stats);
+            // Отдельные свопы не вызываются: повтор отправки не исполняет ноги по отдельности
+            assert_eq!((raydium.get_swap_call_count(), orca.get_swap_call_count()), (0, 0));
+            let trades = history.lock().await.clone();
+            anyhow::Ok((results, trades))
+        }
+    });
+    let runs = tokio::time::timeout(Duration::from_secs(120), future::try_join_all(runs))
+        .await
+        .context("Сделки со сбоями RPC не завершились за 120 с")??;
+    chaos::uninstall(&rpc_url);
+    server.abort();
+
+    let node = node.lock().expect("узел");
+    let chaos = chaos.stats();
+    let dropped: HashSet<&String> = chaos.dropped_sends.iter().collect();
+    let (mut succeeded, mut failed) = (0, 0);
+    for (results, trades) in &runs {
+        // Каждая попытка записана в журнал ровно один раз, статус совпадает с результатом
+        assert_eq!(trades.len(), results.len());
+        for (result, trade) in results.iter().zip(trades) {
+            match result {
+                Ok(()) => {
+                    succeeded += 1;
+                    assert_eq!(trade.status, TradeStatus::Success);
+                    let signature = trade.tx_signature.clone().expect("подпись успешной сделки");
+                    let parsed = signature.parse()?;
+                    assert!(
+                        node.deliveries.contains_key(&parsed) || dropped.contains(&signature),
+                        "подпись {} не доставлена узлу и не потеряна сбоем", signature
+                    );
+                }
+                Err(e) => {
+                    failed += 1;
+                    assert_eq!(trade.status, TradeStatus::Failed);
+                    assert_eq!(classify_failure(e), FailureKind::Transient, "{:#}", e);
+                }
+            }
+        }
+    }
+
+    // Повторы и двойная доставка не исполняют сделку дважды
+    let mut landed_per_trade: HashMap<&String, usize> = HashMap::new();
+    for memo in node.landed.values() {
+        *landed_per_trade.entry(memo).or_default() += 1;
+    }
+    assert!(landed_per_trade.values().all(|count| *count == 1), "сделка исполнена дважды: {:?}", landed_per_trade);
+    // Проверка баланса строится от фактического баланса, а не от нуля при сбое RPC
+    assert!(
+        node.guard_amounts.iter().all(|amount| *amount >= FakeNode::QUOTE_BALANCE),
+        "проверка баланса ослаблена: {:?}", node.guard_amounts
+    );
+
+    log::info!(
+        "Сделок: {} успешно, {} с ошибкой; сбоев: {:?}",
+        succeeded, failed, (chaos.dropped_sends.len(), chaos.lost_acks.len(), chaos.duplicated_sends.len(), chaos.rpc_errors)
+    );
+    assert_eq!(succeeded + failed, ENGINES * TRADES_PER_ENGINE);
+    assert!(succeeded > 0 && !node.landed.is_empty());
+    assert!(!chaos.dropped_sends.is_empty() && !chaos.lost_acks.is_empty() && !chaos.duplicated_sends.is_empty());
+    assert!(node.deliveries.values().any(|count| *count > 1), "двойная доставка должна дойти до узла");
+    Ok(())
+}